@@ -22,9 +22,7 @@ impl Breakpoint {
     /// Returns the file-span of the breakpoint expression.
     pub fn span(self, db: &dyn crate::Db) -> FileSpan {
         let tree = self.code.syntax_tree(db);
-        let expr_span = tree.spans(db)[self.expr];
-        let filename = self.code.filename(db);
-        expr_span.in_file(filename)
+        tree.file_span(db, tree.spans(db)[self.expr])
     }
 }
 
@@ -63,6 +61,10 @@ pub fn find_item(db: &dyn crate::Db, filename: Filename, offset: Offset) -> Opti
 ///
 /// Returns None if the cursor does not lie in the syntax tree at all.
 fn find_syntax_expr(db: &dyn crate::Db, syntax_tree: syntax::Tree, offset: Offset) -> syntax::Expr {
+    // The spans are relative to the anchor of the code, which may come
+    // after the start of the item (as `fn` does in `async fn`).
+    let anchor = syntax_tree.origin(db).anchor(db).offset(db);
+    let offset = Offset::from(u32::from(offset).saturating_sub(u32::from(anchor)));
     let spans = syntax_tree.spans(db);
     let data = syntax_tree.data(db);
     let traversal = TreeTraversal {
@@ -4,8 +4,10 @@ use dada_ir::{
         bir::{self, BirData},
         validated::{self, ExprOrigin},
     },
+    function::Function,
     storage::Atomic,
 };
+use dada_validate::prelude::*;
 use salsa::DebugWithDb;
 
 use crate::{
@@ -13,12 +15,20 @@ use crate::{
     cursor::Cursor,
 };
 
+/// Brews the BIR for `function` from its validated tree. Like validation,
+/// this is done per function, so that it is only done again when that
+/// function's validated tree changed.
 #[salsa::memoized(in crate::Jar)]
-pub fn brew(db: &dyn crate::Db, validated_tree: validated::Tree) -> bir::Bir {
-    let function = validated_tree.origin(db);
+pub fn brew(db: &dyn crate::Db, function: Function) -> bir::Bir {
+    let validated_tree = function.validated_tree(db);
     let code = function.code(db);
+    let _span = dada_ir::phase_span!("brew", db, code.filename(db), code.name).entered();
     let breakpoints = dada_breakpoint::locations::breakpoints_in_code(db, code);
+    // Each validated expression is brewed into about one statement.
     let mut tables = bir::Tables::default();
+    tables.reserve::<bir::Statement>(
+        validated::Expr::max_key(&validated_tree.data(db).tables).into(),
+    );
     let mut origins = bir::Origins::default();
     let brewery = &mut Brewery::new(
         db,
@@ -43,6 +53,9 @@ pub fn brew(db: &dyn crate::Db, validated_tree: validated::Tree) -> bir::Bir {
         );
     }
     let start_basic_block = cursor.complete();
+    if breakpoints.is_empty() {
+        crate::peephole::remove_temporaries(&mut tables);
+    }
 
     let bir = bir::Bir::new(
         db,
@@ -377,7 +390,7 @@ impl Cursor {
                     if values.is_empty() {
                         self.push_assignment(brewery, target, bir::ExprData::Unit, origin);
                     } else {
-                        assert_ne!(values.len(), 1);
+                        let values = brewery.add_list(values);
                         self.push_assignment(brewery, target, bir::ExprData::Tuple(values), origin);
                     }
                     self.push_breakpoint_end(brewery, Some(target), origin);
@@ -438,6 +451,8 @@ impl Cursor {
                         }
                     }
                     if places.len() == args.len() {
+                        let places = brewery.add_list(places);
+                        let names = brewery.add_list(names);
                         self.terminate_and_continue(
                             brewery,
                             |next_block| {
@@ -514,10 +529,18 @@ impl Cursor {
                 let place = brewery.add(bir::PlaceData::Function(*function), origin);
                 (place, vec![origin])
             }
+            validated::PlaceData::ExternFunction(function) => {
+                let place = brewery.add(bir::PlaceData::ExternFunction(*function), origin);
+                (place, vec![origin])
+            }
             validated::PlaceData::Intrinsic(intrinsic) => {
                 let place = brewery.add(bir::PlaceData::Intrinsic(*intrinsic), origin);
                 (place, vec![origin])
             }
+            validated::PlaceData::HostFunction(name) => {
+                let place = brewery.add(bir::PlaceData::HostFunction(*name), origin);
+                (place, vec![origin])
+            }
             validated::PlaceData::Class(class) => {
                 let place = brewery.add(bir::PlaceData::Class(*class), origin);
                 (place, vec![origin])
@@ -154,6 +154,15 @@ impl<'me> Brewery<'me> {
         add(self.tables, self.origins, data, origin)
     }
 
+    /// Adds a list (like the arguments of a call); lists have no origin
+    /// of their own, their elements do.
+    pub fn add_list<K>(&mut self, elements: impl IntoIterator<Item = K::Element>) -> K
+    where
+        K: dada_id::InternListKey<Table = bir::Tables>,
+    {
+        self.tables.add_list(elements)
+    }
+
     /// Converts a target-place into a place.
     pub fn place_from_target_place(&mut self, place: bir::TargetPlace) -> bir::Place {
         match self.tables[place] {
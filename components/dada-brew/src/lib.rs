@@ -1,11 +1,12 @@
 //! "Brews" the bir (i.e., compiles)
 
+#![feature(let_else)]
 #![feature(trait_upcasting)]
 #![feature(try_blocks)]
 #![allow(incomplete_features)]
 
 #[salsa::jar(Db)]
-pub struct Jar(brew::brew);
+pub struct Jar(brew::brew, loaded::loaded_bir);
 
 pub trait Db:
     salsa::DbWithJar<Jar> + dada_breakpoint::Db + dada_ir::Db + dada_parse::Db + dada_validate::Db
@@ -24,4 +25,6 @@ impl<T> Db for T where
 mod brew;
 mod brewery;
 mod cursor;
+pub mod loaded;
+pub mod peephole;
 pub mod prelude;
@@ -0,0 +1,10 @@
+use dada_ir::{code::bir, function::Function};
+
+/// Salsa input: the BIR for a function that was loaded from compiled
+/// bytecode (see the `dada-bytecode` crate).
+///
+/// Defaults to `None`, in which case the BIR is brewed from the source.
+#[salsa::memoized(in crate::Jar)]
+pub fn loaded_bir(_db: &dyn crate::Db, _function: Function) -> Option<bir::Bir> {
+    None
+}
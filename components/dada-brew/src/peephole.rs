@@ -0,0 +1,227 @@
+//! A peephole pass over brewed BIR that removes temporaries holding
+//! literals, which brewing creates for many expressions:
+//!
+//! * `temp = 22; clear(temp)`, where the value of an expression is never
+//!   used (most often `()`), is removed.
+//! * `temp = 22; x = temp.give` is turned into `x = 22`, when that is the
+//!   only use of `temp`, and then `clear(temp)` is removed.
+//!
+//! The heap graph at a breakpoint shows what temporaries hold, so functions
+//! with breakpoints are left as brewed.
+
+use dada_collections::{Map, Set};
+use dada_id::prelude::*;
+use dada_ir::code::bir;
+
+/// Removes the temporaries described above from the statements of the
+/// basic blocks in `tables`. The statements removed are left in the table
+/// of statements, but no block refers to them.
+pub fn remove_temporaries(tables: &mut bir::Tables) {
+    let uses = Uses::count(tables);
+
+    let mut removed = Set::default();
+    for basic_block in bir::BasicBlock::max_key(tables).iter() {
+        let statements = std::mem::take(&mut tables[basic_block].statements);
+        let mut kept = Vec::with_capacity(statements.len());
+        let mut index = 0;
+        while index < statements.len() {
+            let statement = statements[index];
+            let next = statements.get(index + 1).copied();
+            index += 1;
+
+            let Some((temporary, literal)) = literal_assignment(tables, statement) else {
+                kept.push(statement);
+                continue;
+            };
+            if uses.writes(temporary) != 1 {
+                kept.push(statement);
+                continue;
+            }
+
+            match next.map(|next| (next, &tables[next])) {
+                Some((_, bir::StatementData::Clear(cleared)))
+                    if *cleared == temporary && uses.reads(temporary) == 0 =>
+                {
+                    index += 1;
+                }
+                Some((next, &bir::StatementData::AssignExpr(target, expr)))
+                    if uses.reads(temporary) == 1 && gives(tables, expr) == Some(temporary) =>
+                {
+                    tables[next] = bir::StatementData::AssignExpr(target, literal);
+                    removed.insert(temporary);
+                }
+                _ => kept.push(statement),
+            }
+        }
+        tables[basic_block].statements = kept;
+    }
+
+    // The temporaries that were removed are never assigned, so clearing
+    // them does nothing.
+    for basic_block in bir::BasicBlock::max_key(tables).iter() {
+        let mut statements = std::mem::take(&mut tables[basic_block].statements);
+        statements.retain(|&statement| {
+            !matches!(&tables[statement], bir::StatementData::Clear(v) if removed.contains(v))
+        });
+        tables[basic_block].statements = statements;
+    }
+}
+
+/// If `statement` assigns a literal to a temporary, the temporary and the
+/// literal.
+fn literal_assignment(
+    tables: &bir::Tables,
+    statement: bir::Statement,
+) -> Option<(bir::LocalVariable, bir::Expr)> {
+    let bir::StatementData::AssignExpr(target, expr) = tables[statement] else {
+        return None;
+    };
+    let bir::TargetPlaceData::LocalVariable(variable) = tables[target] else {
+        return None;
+    };
+    if tables[variable].name.is_some() {
+        return None;
+    }
+    match tables[expr] {
+        bir::ExprData::BooleanLiteral(_)
+        | bir::ExprData::SignedIntegerLiteral(_)
+        | bir::ExprData::UnsignedIntegerLiteral(_)
+        | bir::ExprData::IntegerLiteral(_)
+        | bir::ExprData::FloatLiteral(_)
+        | bir::ExprData::StringLiteral(_)
+        | bir::ExprData::Unit => Some((variable, expr)),
+        _ => None,
+    }
+}
+
+/// If `expr` is `v.give` for a local variable `v`, then `v`.
+fn gives(tables: &bir::Tables, expr: bir::Expr) -> Option<bir::LocalVariable> {
+    let bir::ExprData::Give(place) = tables[expr] else {
+        return None;
+    };
+    match tables[place] {
+        bir::PlaceData::LocalVariable(variable) => Some(variable),
+        _ => None,
+    }
+}
+
+/// How many times the code in each basic block reads and writes each
+/// local variable (clearing it is neither).
+#[derive(Default)]
+struct Uses {
+    reads: Map<bir::LocalVariable, usize>,
+    writes: Map<bir::LocalVariable, usize>,
+}
+
+impl Uses {
+    fn count(tables: &bir::Tables) -> Self {
+        let mut uses = Uses::default();
+        for basic_block in bir::BasicBlock::max_key(tables).iter() {
+            let data = &tables[basic_block];
+            for &statement in &data.statements {
+                match &tables[statement] {
+                    bir::StatementData::AssignExpr(target, expr) => {
+                        uses.target_place(tables, *target);
+                        uses.expr(tables, *expr);
+                    }
+                    bir::StatementData::AssignPlace(target, place) => {
+                        uses.target_place(tables, *target);
+                        uses.place(tables, *place);
+                    }
+                    bir::StatementData::BreakpointEnd(_, _, _, place) => {
+                        if let Some(place) = place {
+                            uses.place(tables, *place);
+                        }
+                    }
+                    bir::StatementData::Clear(_) | bir::StatementData::BreakpointStart(..) => {}
+                }
+            }
+            match &tables[data.terminator] {
+                bir::TerminatorData::If(place, _, _) | bir::TerminatorData::Return(place) => {
+                    uses.place(tables, *place)
+                }
+                bir::TerminatorData::Assign(target, expr, _) => {
+                    uses.target_place(tables, *target);
+                    match expr {
+                        bir::TerminatorExpr::Await(place) => uses.place(tables, *place),
+                        bir::TerminatorExpr::Call {
+                            function,
+                            arguments,
+                            labels: _,
+                        } => {
+                            uses.place(tables, *function);
+                            for &argument in &tables[*arguments] {
+                                uses.place(tables, argument);
+                            }
+                        }
+                    }
+                }
+                bir::TerminatorData::Goto(_)
+                | bir::TerminatorData::StartAtomic(_)
+                | bir::TerminatorData::EndAtomic(_)
+                | bir::TerminatorData::Error
+                | bir::TerminatorData::Panic => {}
+            }
+        }
+        uses
+    }
+
+    fn reads(&self, variable: bir::LocalVariable) -> usize {
+        self.reads.get(&variable).copied().unwrap_or(0)
+    }
+
+    fn writes(&self, variable: bir::LocalVariable) -> usize {
+        self.writes.get(&variable).copied().unwrap_or(0)
+    }
+
+    fn target_place(&mut self, tables: &bir::Tables, target: bir::TargetPlace) {
+        match tables[target] {
+            bir::TargetPlaceData::LocalVariable(variable) => {
+                *self.writes.entry(variable).or_default() += 1
+            }
+            bir::TargetPlaceData::Dot(owner, _) => self.place(tables, owner),
+        }
+    }
+
+    fn place(&mut self, tables: &bir::Tables, place: bir::Place) {
+        match tables[place] {
+            bir::PlaceData::LocalVariable(variable) => {
+                *self.reads.entry(variable).or_default() += 1
+            }
+            bir::PlaceData::Dot(owner, _) => self.place(tables, owner),
+            bir::PlaceData::Function(_)
+            | bir::PlaceData::Class(_)
+            | bir::PlaceData::Intrinsic(_)
+            | bir::PlaceData::HostFunction(_)
+            | bir::PlaceData::ExternFunction(_) => {}
+        }
+    }
+
+    fn expr(&mut self, tables: &bir::Tables, expr: bir::Expr) {
+        match &tables[expr] {
+            bir::ExprData::Reserve(place)
+            | bir::ExprData::Share(place)
+            | bir::ExprData::Lease(place)
+            | bir::ExprData::Shlease(place)
+            | bir::ExprData::Give(place)
+            | bir::ExprData::Unary(_, place) => self.place(tables, *place),
+            bir::ExprData::Tuple(places) => {
+                for &place in &tables[*places] {
+                    self.place(tables, place);
+                }
+            }
+            bir::ExprData::Op(lhs, _, rhs) => {
+                self.place(tables, *lhs);
+                self.place(tables, *rhs);
+            }
+            bir::ExprData::BooleanLiteral(_)
+            | bir::ExprData::SignedIntegerLiteral(_)
+            | bir::ExprData::UnsignedIntegerLiteral(_)
+            | bir::ExprData::IntegerLiteral(_)
+            | bir::ExprData::FloatLiteral(_)
+            | bir::ExprData::StringLiteral(_)
+            | bir::ExprData::Unit
+            | bir::ExprData::Error => {}
+        }
+    }
+}
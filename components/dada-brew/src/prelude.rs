@@ -1,14 +1,18 @@
 use dada_ir::{code::bir, function::Function, item::Item};
-use dada_validate::prelude::*;
 
 pub trait BrewExt {
+    /// The BIR for this function. It is brewed the first time it is asked
+    /// for, which for `dada run` is when the function is first called, so
+    /// a program only pays for brewing the functions it runs.
     fn brew(self, db: &dyn crate::Db) -> bir::Bir;
 }
 
 impl BrewExt for Function {
     fn brew(self, db: &dyn crate::Db) -> bir::Bir {
-        let tree = self.validated_tree(db);
-        crate::brew::brew(db, tree)
+        if let Some(bir) = crate::loaded::loaded_bir(db, self) {
+            return bir;
+        }
+        crate::brew::brew(db, self)
     }
 }
 
@@ -18,7 +22,9 @@ pub trait MaybeBrewExt {
 
 impl MaybeBrewExt for Item {
     fn maybe_brew(self, db: &dyn crate::Db) -> Option<bir::Bir> {
-        self.validated_tree(db)
-            .map(|tree| crate::brew::brew(db, tree))
+        match self {
+            Item::Function(function) => Some(function.brew(db)),
+            Item::Class(_) | Item::ExternFunction(_) => None,
+        }
     }
 }
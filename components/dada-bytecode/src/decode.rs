@@ -0,0 +1,718 @@
+use dada_ir::{
+    class::Class,
+    code::{bir, syntax, validated, Code},
+    effect::Effect,
+    extern_function::{ExternFunction, ExternParameter, ExternSignature, FfiTy},
+    filename::Filename,
+    function::Function,
+    intrinsic::IntrinsicData,
+    item::Item,
+    parameter::Parameter,
+    return_type::{ReturnType, ReturnTypeKind},
+    span::{Anchor, AnchoredSpan, FileSpan, Offset, Span},
+    storage::{Atomic, SpannedSpecifier, Specifier},
+    token_tree::TokenTree,
+    word::{SpannedOptionalWord, SpannedWord, Word},
+};
+
+use crate::{
+    format::{Header, Reader},
+    DecodeError,
+};
+
+/// Salsa input: the contents of the `.dadac` file for `filename`.
+#[salsa::memoized(in crate::Jar ref)]
+#[allow(clippy::needless_lifetimes)]
+pub fn bytecode(_db: &dyn crate::Db, _filename: Filename) -> Vec<u8> {
+    panic!("input")
+}
+
+/// Decodes the `.dadac` file for `filename`, creating its items (just
+/// as parsing the source would).
+#[salsa::memoized(in crate::Jar ref)]
+#[allow(clippy::needless_lifetimes)]
+pub fn decode_module(db: &dyn crate::Db, filename: Filename) -> Result<Module, DecodeError> {
+    Decoder::new(db, filename, bytecode(db, filename)).decode()
+}
+
+/// The contents of a `.dadac` file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Module {
+    pub source_text: String,
+
+    /// The items in the file, in order.
+    pub items: Vec<Item>,
+
+    pub functions: Vec<LoadedFunction>,
+
+    pub classes: Vec<LoadedClass>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadedFunction {
+    pub function: Function,
+    pub parameters: Vec<Parameter>,
+
+    /// A syntax tree whose expressions are all `Error`, but whose spans
+    /// are those of the original tree.
+    pub syntax_tree: syntax::Tree,
+
+    pub bir: bir::Bir,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadedClass {
+    pub class: Class,
+    pub fields: Vec<Parameter>,
+}
+
+struct Decoder<'db> {
+    db: &'db dyn crate::Db,
+    filename: Filename,
+
+    /// The start of the file, which the spans that are written as they
+    /// are in the file are anchored at.
+    file_anchor: Anchor,
+
+    reader: Reader<'db>,
+    words: Vec<Word>,
+    items: Vec<Item>,
+}
+
+impl<'db> Decoder<'db> {
+    fn new(db: &'db dyn crate::Db, filename: Filename, bytes: &'db [u8]) -> Self {
+        Self {
+            db,
+            filename,
+            file_anchor: Anchor::new(db, filename, Offset::from(0_u32)),
+            reader: Reader::new(bytes),
+            words: vec![],
+            items: vec![],
+        }
+    }
+
+    fn decode(mut self) -> Result<Module, DecodeError> {
+        let db = self.db;
+        Header::read_from(&mut self.reader)?;
+        let source_text = self.reader.str()?.to_string();
+
+        let num_words = self.reader.usize()?;
+        for _ in 0..num_words {
+            let word = Word::from(db, self.reader.str()?);
+            self.words.push(word);
+        }
+
+        let num_items = self.reader.usize()?;
+        let mut functions = vec![];
+        let mut classes = vec![];
+        for _ in 0..num_items {
+            match self.reader.u8()? {
+                0 => {
+                    let (function, parameters) = self.function()?;
+                    self.items.push(Item::Function(function));
+                    functions.push((function, parameters));
+                }
+                1 => {
+                    let class = self.class()?;
+                    self.items.push(Item::Class(class.class));
+                    classes.push(class);
+                }
+                2 => {
+                    let function = self.extern_function()?;
+                    self.items.push(Item::ExternFunction(function));
+                }
+                _ => return Err(DecodeError::Corrupt),
+            }
+        }
+
+        let functions = functions
+            .into_iter()
+            .map(|(function, parameters)| {
+                let syntax_tree = self.syntax_tree(function.code(db))?;
+                let bir = self.bir(function, syntax_tree)?;
+                Ok(LoadedFunction {
+                    function,
+                    parameters,
+                    syntax_tree,
+                    bir,
+                })
+            })
+            .collect::<Result<_, DecodeError>>()?;
+
+        Ok(Module {
+            source_text,
+            items: self.items,
+            functions,
+            classes,
+        })
+    }
+
+    fn word(&mut self) -> Result<Word, DecodeError> {
+        let index = self.reader.usize()?;
+        self.words.get(index).copied().ok_or(DecodeError::Corrupt)
+    }
+
+    fn optional_word(&mut self) -> Result<Option<Word>, DecodeError> {
+        if self.reader.bool()? {
+            Ok(Some(self.word()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn span(&mut self) -> Result<Span, DecodeError> {
+        let start = self.reader.u32()?;
+        let end = self.reader.u32()?;
+        if start > end {
+            return Err(DecodeError::Corrupt);
+        }
+        Ok(Span::from(start, end))
+    }
+
+    fn file_span(&mut self) -> Result<FileSpan, DecodeError> {
+        Ok(self.span()?.in_file(self.filename))
+    }
+
+    /// Reads a span as it is in the file.
+    fn anchored_span(&mut self) -> Result<AnchoredSpan, DecodeError> {
+        Ok(AnchoredSpan {
+            anchor: self.file_anchor,
+            span: self.span()?,
+        })
+    }
+
+    fn anchor(&mut self) -> Result<Anchor, DecodeError> {
+        let offset = self.reader.u32()?;
+        Ok(Anchor::new(self.db, self.filename, Offset::from(offset)))
+    }
+
+    /// The tokens of loaded items are never looked at (the `loaded`
+    /// inputs take the place of parsing them), so they are left empty.
+    fn token_tree(&mut self, anchor: Anchor) -> Result<TokenTree, DecodeError> {
+        let span = self.span()?;
+        Ok(TokenTree::new(self.db, anchor, span, vec![]))
+    }
+
+    fn class(&mut self) -> Result<LoadedClass, DecodeError> {
+        let db = self.db;
+        let name = self.word()?;
+        let name_span = self.anchored_span()?;
+        let span = self.file_span()?;
+        let anchor = self.anchor()?;
+        let field_tokens = self.token_tree(anchor)?;
+        let fields = self.parameters()?;
+        let name = SpannedWord::new(db, name, name_span);
+        let class = Class::new(db, name, field_tokens, span);
+        Ok(LoadedClass { class, fields })
+    }
+
+    fn function(&mut self) -> Result<(Function, Vec<Parameter>), DecodeError> {
+        let db = self.db;
+        let name = self.word()?;
+        let name_span = self.anchored_span()?;
+        let span = self.file_span()?;
+        let effect_span = self.file_span()?;
+        let effect = match self.reader.u8()? {
+            0 => Effect::Atomic,
+            1 => Effect::Default,
+            2 => Effect::Async,
+            _ => return Err(DecodeError::Corrupt),
+        };
+        let return_type_kind = match self.reader.u8()? {
+            0 => ReturnTypeKind::Value,
+            1 => ReturnTypeKind::Unit,
+            _ => return Err(DecodeError::Corrupt),
+        };
+        let return_type_span = self.file_span()?;
+        let anchor = self.anchor()?;
+        let (parameter_tokens, parameters) = if self.reader.bool()? {
+            (Some(self.token_tree(anchor)?), self.parameters()?)
+        } else {
+            (None, vec![])
+        };
+        let body_tokens = self.token_tree(anchor)?;
+
+        let return_type = ReturnType::new(db, return_type_kind, return_type_span);
+        let code = Code::new(name, effect, parameter_tokens, return_type, body_tokens);
+        let name = SpannedWord::new(db, name, name_span);
+        let function = Function::new(db, name, code, span, effect_span);
+        Ok((function, parameters))
+    }
+
+    fn extern_function(&mut self) -> Result<ExternFunction, DecodeError> {
+        let db = self.db;
+        let name = self.word()?;
+        let name_span = self.anchored_span()?;
+        let span = self.file_span()?;
+        let library = self.word()?;
+
+        let num_parameters = self.reader.usize()?;
+        let parameters = (0..num_parameters)
+            .map(|_| {
+                Ok(ExternParameter {
+                    name: self.word()?,
+                    ty: self.ffi_ty()?,
+                })
+            })
+            .collect::<Result<_, DecodeError>>()?;
+        let return_ty = if self.reader.bool()? {
+            match self.ffi_ty()? {
+                // The parser rejects this, so no module it compiled has it.
+                FfiTy::Bytes => return Err(DecodeError::Corrupt),
+                ty => Some(ty),
+            }
+        } else {
+            None
+        };
+
+        let name = SpannedWord::new(db, name, name_span);
+        let signature = ExternSignature {
+            parameters,
+            return_ty,
+        };
+        Ok(ExternFunction::new(db, name, library, signature, span))
+    }
+
+    fn ffi_ty(&mut self) -> Result<FfiTy, DecodeError> {
+        let index = self.reader.usize()?;
+        FfiTy::ALL.get(index).copied().ok_or(DecodeError::Corrupt)
+    }
+
+    fn parameters(&mut self) -> Result<Vec<Parameter>, DecodeError> {
+        let num_parameters = self.reader.usize()?;
+        (0..num_parameters)
+            .map(|_| {
+                let name = self.word()?;
+                let specifier = self.spanned_specifier()?;
+                let atomic = self.atomic()?;
+                let atomic_span = self.span()?;
+                let name_span = self.span()?;
+                let decl = syntax::LocalVariableDeclData {
+                    specifier,
+                    atomic,
+                    name,
+                    ty: None,
+                };
+                let decl_span = syntax::LocalVariableDeclSpan {
+                    atomic_span,
+                    name_span,
+                };
+                Ok(Parameter::new(self.db, name, decl, decl_span))
+            })
+            .collect()
+    }
+
+    fn spanned_specifier(&mut self) -> Result<SpannedSpecifier, DecodeError> {
+        let specifier = match self.reader.u8()? {
+            0 => Specifier::My,
+            1 => Specifier::Our,
+            2 => Specifier::Leased,
+            3 => Specifier::Shleased,
+            4 => Specifier::Any,
+            _ => return Err(DecodeError::Corrupt),
+        };
+        let defaulted = self.reader.bool()?;
+        let span = self.anchored_span()?;
+        Ok(SpannedSpecifier::new(self.db, specifier, defaulted, span))
+    }
+
+    fn atomic(&mut self) -> Result<Atomic, DecodeError> {
+        Ok(if self.reader.bool()? {
+            Atomic::Yes
+        } else {
+            Atomic::No
+        })
+    }
+
+    fn syntax_tree(&mut self, code: Code) -> Result<syntax::Tree, DecodeError> {
+        let mut tables = syntax::Tables::default();
+        let mut spans = syntax::Spans::default();
+        let num_exprs = self.reader.usize()?;
+        for _ in 0..num_exprs {
+            let expr = tables.add(syntax::ExprData::Error);
+            spans.push(expr, self.span()?);
+        }
+        let root_expr = self.id::<syntax::Expr>(num_exprs)?;
+
+        // Operator spans are written in order of their expressions.
+        let mut operator_spans = syntax::OperatorSpans::default();
+        let mut last_expr = None;
+        for _ in 0..self.reader.usize()? {
+            let expr = self.id::<syntax::Expr>(num_exprs)?;
+            if last_expr >= Some(expr) {
+                return Err(DecodeError::Corrupt);
+            }
+            last_expr = Some(expr);
+            operator_spans.push(expr, self.span()?);
+        }
+
+        let data = syntax::TreeData {
+            tables,
+            parameter_decls: vec![],
+            root_expr,
+        };
+        Ok(syntax::Tree::new(self.db, code, data, spans, operator_spans))
+    }
+
+    /// Reads an id, checking that it is less than `max`.
+    fn id<I: From<usize>>(&mut self, max: usize) -> Result<I, DecodeError> {
+        let index = self.reader.usize()?;
+        if index >= max {
+            return Err(DecodeError::Corrupt);
+        }
+        Ok(I::from(index))
+    }
+
+    /// Reads an id whose table may not have been read yet. (Ids that are
+    /// out of range are caught by [`check_bir`].)
+    fn forward_id<I: From<usize>>(&mut self) -> Result<I, DecodeError> {
+        Ok(I::from(self.reader.usize()?))
+    }
+
+    fn bir(
+        &mut self,
+        function: Function,
+        syntax_tree: syntax::Tree,
+    ) -> Result<bir::Bir, DecodeError> {
+        let mut tables = bir::Tables::default();
+        let mut origins = bir::Origins::default();
+        let num_parameters = self.reader.usize()?;
+        let start_basic_block = self.forward_id()?;
+
+        let num_local_variables = self.reader.usize()?;
+        for _ in 0..num_local_variables {
+            let name = self.optional_word()?;
+            let specifier = if self.reader.bool()? {
+                Some(self.spanned_specifier()?)
+            } else {
+                None
+            };
+            let atomic = self.atomic()?;
+            let local_variable = tables.add(bir::LocalVariableData {
+                name,
+                specifier,
+                atomic,
+            });
+            let origin = match self.reader.u8()? {
+                0 => validated::LocalVariableOrigin::Temporary(self.forward_id()?),
+                1 => validated::LocalVariableOrigin::LocalVariable(self.forward_id()?),
+                2 => validated::LocalVariableOrigin::Parameter(self.forward_id()?),
+                _ => return Err(DecodeError::Corrupt),
+            };
+            origins.push(local_variable, origin);
+        }
+
+        let num_basic_blocks = self.reader.usize()?;
+        for _ in 0..num_basic_blocks {
+            let num_statements = self.reader.usize()?;
+            let statements = (0..num_statements)
+                .map(|_| self.forward_id())
+                .collect::<Result<_, _>>()?;
+            let terminator = self.forward_id()?;
+            let basic_block = tables.add(bir::BasicBlockData {
+                statements,
+                terminator,
+            });
+            origins.push(basic_block, self.forward_id()?);
+        }
+
+        let num_statements = self.reader.usize()?;
+        for _ in 0..num_statements {
+            let data = self.statement()?;
+            let statement = tables.add(data);
+            origins.push(statement, self.forward_id()?);
+        }
+
+        let num_terminators = self.reader.usize()?;
+        for _ in 0..num_terminators {
+            let data = self.terminator(&mut tables)?;
+            let terminator = tables.add(data);
+            origins.push(terminator, self.forward_id()?);
+        }
+
+        let num_exprs = self.reader.usize()?;
+        for _ in 0..num_exprs {
+            let data = self.expr(&mut tables)?;
+            let expr = tables.add(data);
+            origins.push(expr, self.forward_id()?);
+        }
+
+        let num_places = self.reader.usize()?;
+        for _ in 0..num_places {
+            let data = self.place()?;
+            let place = tables.add(data);
+            origins.push(place, self.forward_id()?);
+        }
+
+        let num_target_places = self.reader.usize()?;
+        for _ in 0..num_target_places {
+            let data = match self.reader.u8()? {
+                0 => bir::TargetPlaceData::LocalVariable(self.forward_id()?),
+                1 => bir::TargetPlaceData::Dot(self.forward_id()?, self.word()?),
+                _ => return Err(DecodeError::Corrupt),
+            };
+            let target_place = tables.add(data);
+            origins.push(target_place, self.forward_id()?);
+        }
+
+        check_bir(
+            &tables,
+            num_parameters,
+            start_basic_block,
+            &origins,
+            syntax_tree.data(self.db),
+        )?;
+        let data = bir::BirData::new(tables, num_parameters, start_basic_block);
+        Ok(bir::Bir::new(self.db, function, data, origins))
+    }
+
+    fn statement(&mut self) -> Result<bir::StatementData, DecodeError> {
+        Ok(match self.reader.u8()? {
+            0 => bir::StatementData::AssignExpr(self.forward_id()?, self.forward_id()?),
+            1 => bir::StatementData::AssignPlace(self.forward_id()?, self.forward_id()?),
+            2 => bir::StatementData::Clear(self.forward_id()?),
+            3 => bir::StatementData::BreakpointStart(self.filename, self.reader.usize()?),
+            4 => {
+                let index = self.reader.usize()?;
+                let expr = self.forward_id()?;
+                let place = if self.reader.bool()? {
+                    Some(self.forward_id()?)
+                } else {
+                    None
+                };
+                bir::StatementData::BreakpointEnd(self.filename, index, expr, place)
+            }
+            _ => return Err(DecodeError::Corrupt),
+        })
+    }
+
+    fn terminator(&mut self, tables: &mut bir::Tables) -> Result<bir::TerminatorData, DecodeError> {
+        Ok(match self.reader.u8()? {
+            0 => bir::TerminatorData::Goto(self.forward_id()?),
+            1 => {
+                bir::TerminatorData::If(self.forward_id()?, self.forward_id()?, self.forward_id()?)
+            }
+            2 => bir::TerminatorData::StartAtomic(self.forward_id()?),
+            3 => bir::TerminatorData::EndAtomic(self.forward_id()?),
+            4 => bir::TerminatorData::Return(self.forward_id()?),
+            5 => bir::TerminatorData::Assign(
+                self.forward_id()?,
+                bir::TerminatorExpr::Await(self.forward_id()?),
+                self.forward_id()?,
+            ),
+            6 => {
+                let target = self.forward_id()?;
+                let function = self.forward_id()?;
+                let num_arguments = self.reader.usize()?;
+                let arguments = (0..num_arguments)
+                    .map(|_| self.forward_id())
+                    .collect::<Result<Vec<_>, _>>()?;
+                let labels = (0..num_arguments)
+                    .map(|_| {
+                        let word = self.optional_word()?;
+                        let span = self.anchored_span()?;
+                        Ok(SpannedOptionalWord::new(self.db, word, span))
+                    })
+                    .collect::<Result<Vec<_>, DecodeError>>()?;
+                let arguments = tables.add_list(arguments);
+                let labels = tables.add_list(labels);
+                let next = self.forward_id()?;
+                bir::TerminatorData::Assign(
+                    target,
+                    bir::TerminatorExpr::Call {
+                        function,
+                        arguments,
+                        labels,
+                    },
+                    next,
+                )
+            }
+            7 => bir::TerminatorData::Error,
+            8 => bir::TerminatorData::Panic,
+            _ => return Err(DecodeError::Corrupt),
+        })
+    }
+
+    fn expr(&mut self, tables: &mut bir::Tables) -> Result<bir::ExprData, DecodeError> {
+        Ok(match self.reader.u8()? {
+            0 => bir::ExprData::BooleanLiteral(self.reader.bool()?),
+            1 => bir::ExprData::SignedIntegerLiteral(self.reader.i64()?),
+            2 => bir::ExprData::UnsignedIntegerLiteral(self.reader.u64()?),
+            3 => bir::ExprData::IntegerLiteral(self.reader.u64()?),
+            4 => bir::ExprData::FloatLiteral(eq_float::F64(self.reader.f64()?)),
+            5 => bir::ExprData::StringLiteral(self.word()?),
+            6 => bir::ExprData::Reserve(self.forward_id()?),
+            7 => bir::ExprData::Share(self.forward_id()?),
+            8 => bir::ExprData::Lease(self.forward_id()?),
+            9 => bir::ExprData::Shlease(self.forward_id()?),
+            10 => bir::ExprData::Give(self.forward_id()?),
+            11 => bir::ExprData::Unit,
+            12 => {
+                let num_places = self.reader.usize()?;
+                let places = (0..num_places)
+                    .map(|_| self.forward_id())
+                    .collect::<Result<Vec<_>, _>>()?;
+                bir::ExprData::Tuple(tables.add_list(places))
+            }
+            13 => bir::ExprData::Op(self.forward_id()?, self.op()?, self.forward_id()?),
+            14 => bir::ExprData::Unary(self.op()?, self.forward_id()?),
+            15 => bir::ExprData::Error,
+            _ => return Err(DecodeError::Corrupt),
+        })
+    }
+
+    fn op(&mut self) -> Result<validated::op::Op, DecodeError> {
+        let index = self.reader.usize()?;
+        validated::op::Op::all()
+            .nth(index)
+            .ok_or(DecodeError::Corrupt)
+    }
+
+    fn place(&mut self) -> Result<bir::PlaceData, DecodeError> {
+        Ok(match self.reader.u8()? {
+            0 => bir::PlaceData::LocalVariable(self.forward_id()?),
+            1 => match self.item()? {
+                Item::Function(function) => bir::PlaceData::Function(function),
+                Item::Class(_) | Item::ExternFunction(_) => return Err(DecodeError::Corrupt),
+            },
+            2 => match self.item()? {
+                Item::Class(class) => bir::PlaceData::Class(class),
+                Item::Function(_) | Item::ExternFunction(_) => return Err(DecodeError::Corrupt),
+            },
+            3 => {
+                let name = self.word()?;
+                bir::PlaceData::Intrinsic(IntrinsicData { name }.intern(self.db))
+            }
+            4 => bir::PlaceData::Dot(self.forward_id()?, self.word()?),
+            5 => bir::PlaceData::HostFunction(self.word()?),
+            6 => match self.item()? {
+                Item::ExternFunction(function) => bir::PlaceData::ExternFunction(function),
+                Item::Function(_) | Item::Class(_) => return Err(DecodeError::Corrupt),
+            },
+            _ => return Err(DecodeError::Corrupt),
+        })
+    }
+
+    fn item(&mut self) -> Result<Item, DecodeError> {
+        let index = self.reader.usize()?;
+        self.items.get(index).copied().ok_or(DecodeError::Corrupt)
+    }
+}
+
+/// Checks that every id in `tables` (and every syntax expression in
+/// `origins`) is in range, so that a corrupt file is reported when it
+/// is loaded rather than causing a panic when it is built or run.
+fn check_bir(
+    tables: &bir::Tables,
+    num_parameters: usize,
+    start_basic_block: bir::BasicBlock,
+    origins: &bir::Origins,
+    syntax: &syntax::TreeData,
+) -> Result<(), DecodeError> {
+    use dada_id::prelude::*;
+
+    let max_syntax_expr = syntax::Expr::max_key(&syntax.tables);
+    let local_variable = |v: bir::LocalVariable| v < bir::LocalVariable::max_key(tables);
+    let basic_block = |b: bir::BasicBlock| b < bir::BasicBlock::max_key(tables);
+    let statement = |s: bir::Statement| s < bir::Statement::max_key(tables);
+    let terminator = |t: bir::Terminator| t < bir::Terminator::max_key(tables);
+    let expr = |e: bir::Expr| e < bir::Expr::max_key(tables);
+    let place = |p: bir::Place| p < bir::Place::max_key(tables);
+    let target_place = |p: bir::TargetPlace| p < bir::TargetPlace::max_key(tables);
+    let syntax_expr = |e: syntax::Expr| e < max_syntax_expr;
+
+    let mut ok = num_parameters <= usize::from(bir::LocalVariable::max_key(tables))
+        && basic_block(start_basic_block);
+
+    for b in bir::BasicBlock::max_key(tables).iter() {
+        let block = &tables[b];
+        ok &= block.statements.iter().all(|&s| statement(s)) && terminator(block.terminator);
+        ok &= syntax_expr(origins[b]);
+    }
+
+    for s in bir::Statement::max_key(tables).iter() {
+        ok &= syntax_expr(origins[s]);
+        ok &= match &tables[s] {
+            bir::StatementData::AssignExpr(t, e) => target_place(*t) && expr(*e),
+            bir::StatementData::AssignPlace(t, p) => target_place(*t) && place(*p),
+            bir::StatementData::Clear(v) => local_variable(*v),
+            bir::StatementData::BreakpointStart(..) => true,
+            bir::StatementData::BreakpointEnd(_, _, e, p) => {
+                syntax_expr(*e) && p.iter().all(|&p| place(p))
+            }
+        };
+    }
+
+    for t in bir::Terminator::max_key(tables).iter() {
+        ok &= syntax_expr(origins[t]);
+        ok &= match &tables[t] {
+            bir::TerminatorData::Goto(b)
+            | bir::TerminatorData::StartAtomic(b)
+            | bir::TerminatorData::EndAtomic(b) => basic_block(*b),
+            bir::TerminatorData::If(p, b1, b2) => place(*p) && basic_block(*b1) && basic_block(*b2),
+            bir::TerminatorData::Return(p) => place(*p),
+            bir::TerminatorData::Assign(t, bir::TerminatorExpr::Await(p), b) => {
+                target_place(*t) && place(*p) && basic_block(*b)
+            }
+            bir::TerminatorData::Assign(
+                t,
+                bir::TerminatorExpr::Call {
+                    function,
+                    arguments,
+                    ..
+                },
+                b,
+            ) => {
+                target_place(*t)
+                    && place(*function)
+                    && tables[*arguments].iter().all(|&p| place(p))
+                    && basic_block(*b)
+            }
+            bir::TerminatorData::Error | bir::TerminatorData::Panic => true,
+        };
+    }
+
+    for e in bir::Expr::max_key(tables).iter() {
+        ok &= syntax_expr(origins[e]);
+        ok &= match &tables[e] {
+            bir::ExprData::Reserve(p)
+            | bir::ExprData::Share(p)
+            | bir::ExprData::Lease(p)
+            | bir::ExprData::Shlease(p)
+            | bir::ExprData::Give(p)
+            | bir::ExprData::Unary(_, p) => place(*p),
+            bir::ExprData::Tuple(places) => tables[*places].iter().all(|&p| place(p)),
+            bir::ExprData::Op(lhs, _, rhs) => place(*lhs) && place(*rhs),
+            _ => true,
+        };
+    }
+
+    for p in bir::Place::max_key(tables).iter() {
+        ok &= syntax_expr(origins[p]);
+        ok &= match &tables[p] {
+            bir::PlaceData::LocalVariable(v) => local_variable(*v),
+            bir::PlaceData::Dot(owner, _) => place(*owner),
+            _ => true,
+        };
+    }
+
+    for p in bir::TargetPlace::max_key(tables).iter() {
+        ok &= syntax_expr(origins[p]);
+        ok &= match &tables[p] {
+            bir::TargetPlaceData::LocalVariable(v) => local_variable(*v),
+            bir::TargetPlaceData::Dot(owner, _) => place(*owner),
+        };
+    }
+
+    for v in bir::LocalVariable::max_key(tables).iter() {
+        if let validated::LocalVariableOrigin::Temporary(e) = origins[v] {
+            ok &= syntax_expr(e);
+        }
+    }
+
+    if ok {
+        Ok(())
+    } else {
+        Err(DecodeError::Corrupt)
+    }
+}
@@ -0,0 +1,176 @@
+//! Storing the diagnostics of a file, for the on-disk cache of `dada
+//! check`. The file starts with the same header as a `.dadac` file, whose
+//! `source_hash` is whatever key the caller uses to tell whether the
+//! diagnostics are out of date. Spans record their filename, since a
+//! diagnostic can point into other files.
+
+use dada_ir::{
+    diagnostic::{Applicability, Diagnostic, Severity},
+    error_code::ErrorCode,
+    filename::Filename,
+    span::{FileSpan, Span},
+};
+
+use crate::{
+    format::{Header, Reader, Writer},
+    DecodeError,
+};
+
+/// Encodes `diagnostics`, reported for `header.filename`.
+pub fn encode_diagnostics(
+    db: &dyn crate::Db,
+    header: &Header,
+    diagnostics: &[Diagnostic],
+) -> Vec<u8> {
+    let mut writer = Writer::default();
+    header.write_to(&mut writer);
+    writer.usize(diagnostics.len());
+    for diagnostic in diagnostics {
+        diagnostic_to(db, &mut writer, diagnostic);
+    }
+    writer.into_bytes()
+}
+
+/// Decodes diagnostics written by [`encode_diagnostics`], returning them
+/// with the header they were written with.
+pub fn decode_diagnostics(
+    db: &dyn crate::Db,
+    bytes: &[u8],
+) -> Result<(Header, Vec<Diagnostic>), DecodeError> {
+    let reader = &mut Reader::new(bytes);
+    let header = Header::read_from(reader)?;
+    let diagnostics = (0..reader.usize()?)
+        .map(|_| diagnostic_from(db, reader))
+        .collect::<Result<_, _>>()?;
+    Ok((header, diagnostics))
+}
+
+fn diagnostic_to(db: &dyn crate::Db, writer: &mut Writer, diagnostic: &Diagnostic) {
+    writer.u8(match diagnostic.severity {
+        Severity::Help => 0,
+        Severity::Note => 1,
+        Severity::Warning => 2,
+        Severity::Error => 3,
+    });
+    match diagnostic.code {
+        Some(code) => {
+            writer.bool(true);
+            writer.str(code.as_str());
+        }
+        None => writer.bool(false),
+    }
+    file_span_to(db, writer, diagnostic.span);
+    writer.str(&diagnostic.message);
+    match &diagnostic.message_template {
+        Some(template) => {
+            writer.bool(true);
+            writer.str(template.template);
+            writer.usize(template.args.len());
+            for arg in &template.args {
+                writer.str(arg);
+            }
+        }
+        None => writer.bool(false),
+    }
+    writer.usize(diagnostic.labels.len());
+    for label in &diagnostic.labels {
+        file_span_to(db, writer, label.span);
+        writer.str(&label.message);
+    }
+    writer.usize(diagnostic.children.len());
+    for child in &diagnostic.children {
+        diagnostic_to(db, writer, child);
+    }
+    writer.usize(diagnostic.fixes.len());
+    for fix in &diagnostic.fixes {
+        writer.str(&fix.message);
+        file_span_to(db, writer, fix.span);
+        writer.str(&fix.replacement);
+        writer.u8(match fix.applicability {
+            Applicability::MachineApplicable => 0,
+            Applicability::MaybeIncorrect => 1,
+            Applicability::HasPlaceholders => 2,
+        });
+    }
+}
+
+/// Rebuilds a diagnostic with [`Diagnostic::builder`]; its labels are all
+/// written out, so the builder adds no primary label of its own.
+fn diagnostic_from(db: &dyn crate::Db, reader: &mut Reader<'_>) -> Result<Diagnostic, DecodeError> {
+    let severity = match reader.u8()? {
+        0 => Severity::Help,
+        1 => Severity::Note,
+        2 => Severity::Warning,
+        3 => Severity::Error,
+        _ => return Err(DecodeError::Corrupt),
+    };
+    let code = if reader.bool()? {
+        Some(ErrorCode::from_name(reader.str()?).ok_or(DecodeError::Corrupt)?)
+    } else {
+        None
+    };
+    let span = file_span_from(db, reader)?;
+    let message = reader.str()?.to_string();
+    let template = if reader.bool()? {
+        let template = reader.str()?;
+        let args = (0..reader.usize()?)
+            .map(|_| Ok(reader.str()?.to_string()))
+            .collect::<Result<Vec<_>, DecodeError>>()?;
+        Some((template, args))
+    } else {
+        None
+    };
+
+    // Only the templates that some message pack translates matter, and
+    // those are the only ones we can recover.
+    let known_template = match (code, &template) {
+        (Some(code), Some((template, _))) => dada_ir::messages::known_template(code, template),
+        _ => None,
+    };
+    let mut builder = match (known_template, template) {
+        (Some(known_template), Some((_, args))) => {
+            Diagnostic::builder_from_template(severity, span, known_template, args)
+        }
+        _ => Diagnostic::builder(severity, span, message),
+    }
+    .skip_primary_label();
+    if let Some(code) = code {
+        builder = builder.code(code);
+    }
+    for _ in 0..reader.usize()? {
+        let span = file_span_from(db, reader)?;
+        builder = builder.secondary_label(span, reader.str()?);
+    }
+    for _ in 0..reader.usize()? {
+        builder = builder.child(diagnostic_from(db, reader)?);
+    }
+    for _ in 0..reader.usize()? {
+        let message = reader.str()?;
+        let span = file_span_from(db, reader)?;
+        let replacement = reader.str()?;
+        let applicability = match reader.u8()? {
+            0 => Applicability::MachineApplicable,
+            1 => Applicability::MaybeIncorrect,
+            2 => Applicability::HasPlaceholders,
+            _ => return Err(DecodeError::Corrupt),
+        };
+        builder = builder.suggestion(span, replacement, message, applicability);
+    }
+    Ok(builder.finish())
+}
+
+fn file_span_to(db: &dyn crate::Db, writer: &mut Writer, span: FileSpan) {
+    writer.str(span.filename.as_str(db));
+    writer.u32(span.start.into());
+    writer.u32(span.end.into());
+}
+
+fn file_span_from(db: &dyn crate::Db, reader: &mut Reader<'_>) -> Result<FileSpan, DecodeError> {
+    let filename = Filename::from(db, reader.str()?);
+    let start = reader.u32()?;
+    let end = reader.u32()?;
+    if start > end {
+        return Err(DecodeError::Corrupt);
+    }
+    Ok(Span::from(start, end).in_file(filename))
+}
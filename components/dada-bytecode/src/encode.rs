@@ -0,0 +1,573 @@
+use dada_brew::prelude::*;
+use dada_collections::Map;
+use dada_id::prelude::*;
+use dada_ir::{
+    class::Class,
+    code::{bir, syntax, validated},
+    effect::Effect,
+    extern_function::{ExternFunction, FfiTy},
+    filename::Filename,
+    function::Function,
+    item::Item,
+    parameter::Parameter,
+    return_type::ReturnTypeKind,
+    span::{Anchor, FileSpan, Span},
+    storage::{Atomic, SpannedSpecifier, Specifier},
+    word::Word,
+};
+use dada_parse::prelude::*;
+
+use crate::format::{source_hash, Header, Writer};
+
+/// Encodes the items in `filename`, and the BIR for its functions, as a
+/// `.dadac` file.
+pub fn encode(db: &dyn crate::Db, filename: Filename) -> Vec<u8> {
+    Encoder::new(db, filename).encode()
+}
+
+struct Encoder<'db> {
+    db: &'db dyn crate::Db,
+    filename: Filename,
+
+    /// The items in the file; items are referenced by their index.
+    items: Vec<Item>,
+    item_indices: Map<Item, usize>,
+
+    /// Words are written once, in a table at the start of the file,
+    /// and referenced by their index.
+    words: Vec<Word>,
+    word_indices: Map<Word, usize>,
+}
+
+impl<'db> Encoder<'db> {
+    fn new(db: &'db dyn crate::Db, filename: Filename) -> Self {
+        let items = filename.items(db).clone();
+        let item_indices = items.iter().zip(0..).map(|(&i, n)| (i, n)).collect();
+        Self {
+            db,
+            filename,
+            items,
+            item_indices,
+            words: vec![],
+            word_indices: Map::default(),
+        }
+    }
+
+    fn encode(mut self) -> Vec<u8> {
+        let db = self.db;
+        let source_text = dada_ir::manifest::source_text(db, self.filename);
+
+        // Items come first, so that the BIR of any function can refer to
+        // any item.
+        let mut body = Writer::default();
+        body.usize(self.items.len());
+        for item in self.items.clone() {
+            match item {
+                Item::Function(function) => {
+                    body.u8(0);
+                    self.function(&mut body, function);
+                }
+                Item::Class(class) => {
+                    body.u8(1);
+                    self.class(&mut body, class);
+                }
+                Item::ExternFunction(function) => {
+                    body.u8(2);
+                    self.extern_function(&mut body, function);
+                }
+            }
+        }
+        for item in self.items.clone() {
+            if let Item::Function(function) = item {
+                self.syntax_spans(&mut body, function.syntax_tree(db));
+                self.bir(&mut body, function.brew(db));
+            }
+        }
+
+        let mut writer = Writer::default();
+        Header {
+            filename: self.filename.as_str(db).to_string(),
+            source_hash: source_hash(source_text),
+        }
+        .write_to(&mut writer);
+        writer.str(source_text);
+        writer.usize(self.words.len());
+        for word in &self.words {
+            writer.str(word.as_str(db));
+        }
+        writer.append(body);
+        writer.into_bytes()
+    }
+
+    fn word(&mut self, writer: &mut Writer, word: Word) {
+        let words = &mut self.words;
+        let index = *self.word_indices.entry(word).or_insert_with(|| {
+            words.push(word);
+            words.len() - 1
+        });
+        writer.usize(index);
+    }
+
+    fn optional_word(&mut self, writer: &mut Writer, word: Option<Word>) {
+        match word {
+            Some(word) => {
+                writer.bool(true);
+                self.word(writer, word);
+            }
+            None => writer.bool(false),
+        }
+    }
+
+    /// Spans are written without their filename, since everything in a
+    /// `.dadac` file comes from the same source file.
+    fn span(&self, writer: &mut Writer, span: Span) {
+        writer.u32(span.start.into());
+        writer.u32(span.end.into());
+    }
+
+    fn file_span(&self, writer: &mut Writer, span: FileSpan) {
+        assert_eq!(span.filename, self.filename);
+        self.span(writer, span.into());
+    }
+
+    /// The spans of an item's token trees, parameters, and syntax tree are
+    /// written relative to its anchor, which is written first.
+    fn anchor(&self, writer: &mut Writer, anchor: Anchor) {
+        assert_eq!(anchor.filename(self.db), self.filename);
+        writer.u32(anchor.offset(self.db).into());
+    }
+
+    fn class(&mut self, writer: &mut Writer, class: Class) {
+        let db = self.db;
+        self.word(writer, class.name(db).word(db));
+        self.file_span(writer, class.name(db).span(db));
+        self.file_span(writer, class.span(db));
+        self.anchor(writer, class.field_tokens(db).anchor(db));
+        self.span(writer, class.field_tokens(db).span(db));
+        self.parameters(writer, class.fields(db));
+    }
+
+    fn function(&mut self, writer: &mut Writer, function: Function) {
+        let db = self.db;
+        self.word(writer, function.name(db).word(db));
+        self.file_span(writer, function.name(db).span(db));
+        self.file_span(writer, function.span(db));
+        self.file_span(writer, function.effect_span(db));
+
+        let code = function.code(db);
+        writer.u8(match code.effect {
+            Effect::Atomic => 0,
+            Effect::Default => 1,
+            Effect::Async => 2,
+        });
+        writer.u8(match code.return_type.kind(db) {
+            ReturnTypeKind::Value => 0,
+            ReturnTypeKind::Unit => 1,
+        });
+        self.file_span(writer, code.return_type.span(db));
+        self.anchor(writer, code.anchor(db));
+        match code.parameter_tokens {
+            Some(tokens) => {
+                writer.bool(true);
+                self.span(writer, tokens.span(db));
+                self.parameters(writer, function.parameters(db));
+            }
+            None => writer.bool(false),
+        }
+        self.span(writer, code.body_tokens.span(db));
+    }
+
+    fn extern_function(&mut self, writer: &mut Writer, function: ExternFunction) {
+        let db = self.db;
+        self.word(writer, function.name(db).word(db));
+        self.file_span(writer, function.name(db).span(db));
+        self.file_span(writer, function.span(db));
+        self.word(writer, function.library(db));
+
+        let signature = function.signature(db);
+        writer.usize(signature.parameters.len());
+        for parameter in &signature.parameters {
+            self.word(writer, parameter.name);
+            self.ffi_ty(writer, parameter.ty);
+        }
+        match signature.return_ty {
+            Some(ty) => {
+                writer.bool(true);
+                self.ffi_ty(writer, ty);
+            }
+            None => writer.bool(false),
+        }
+    }
+
+    fn ffi_ty(&self, writer: &mut Writer, ty: FfiTy) {
+        let index = FfiTy::ALL.iter().position(|t| *t == ty).unwrap();
+        writer.usize(index);
+    }
+
+    fn parameters(&mut self, writer: &mut Writer, parameters: &[Parameter]) {
+        let db = self.db;
+        writer.usize(parameters.len());
+        for &parameter in parameters {
+            let decl = parameter.decl(db);
+            let decl_span = parameter.decl_span(db);
+            self.word(writer, parameter.name(db));
+            self.spanned_specifier(writer, decl.specifier);
+            self.atomic(writer, decl.atomic);
+            self.span(writer, decl_span.atomic_span);
+            self.span(writer, decl_span.name_span);
+        }
+    }
+
+    fn spanned_specifier(&mut self, writer: &mut Writer, specifier: SpannedSpecifier) {
+        let db = self.db;
+        writer.u8(match specifier.specifier(db) {
+            Specifier::My => 0,
+            Specifier::Our => 1,
+            Specifier::Leased => 2,
+            Specifier::Shleased => 3,
+            Specifier::Any => 4,
+        });
+        writer.bool(specifier.defaulted(db));
+        self.file_span(writer, specifier.span(db));
+    }
+
+    fn atomic(&self, writer: &mut Writer, atomic: Atomic) {
+        writer.bool(atomic == Atomic::Yes);
+    }
+
+    /// Writes the spans of the expressions in `tree`, and of their
+    /// operators, which is all the interpreter needs from the syntax tree.
+    fn syntax_spans(&mut self, writer: &mut Writer, tree: syntax::Tree) {
+        let db = self.db;
+        let data = tree.data(db);
+        let spans = tree.spans(db);
+        let max_expr = syntax::Expr::max_key(&data.tables);
+        writer.usize(max_expr.into());
+        for expr in max_expr.iter() {
+            self.span(writer, spans[expr]);
+        }
+        writer.usize(data.root_expr.into());
+
+        let operator_spans = tree.operator_spans(db);
+        writer.usize(operator_spans.iter().count());
+        for (expr, span) in operator_spans.iter() {
+            writer.usize(expr.into());
+            self.span(writer, span);
+        }
+    }
+
+    fn bir(&mut self, writer: &mut Writer, bir: bir::Bir) {
+        let db = self.db;
+        let data = bir.data(db);
+        let tables = &data.tables;
+        let origins = bir.origins(db);
+        writer.usize(data.num_parameters);
+        writer.usize(data.start_basic_block.into());
+
+        let max_local_variable = bir::LocalVariable::max_key(tables);
+        writer.usize(max_local_variable.into());
+        for local_variable in max_local_variable.iter() {
+            let local_variable_data = &tables[local_variable];
+            self.optional_word(writer, local_variable_data.name);
+            match local_variable_data.specifier {
+                Some(specifier) => {
+                    writer.bool(true);
+                    self.spanned_specifier(writer, specifier);
+                }
+                None => writer.bool(false),
+            }
+            self.atomic(writer, local_variable_data.atomic);
+            match origins[local_variable] {
+                validated::LocalVariableOrigin::Temporary(expr) => {
+                    writer.u8(0);
+                    writer.usize(expr.into());
+                }
+                validated::LocalVariableOrigin::LocalVariable(decl) => {
+                    writer.u8(1);
+                    writer.usize(decl.into());
+                }
+                validated::LocalVariableOrigin::Parameter(decl) => {
+                    writer.u8(2);
+                    writer.usize(decl.into());
+                }
+            }
+        }
+
+        let max_basic_block = bir::BasicBlock::max_key(tables);
+        writer.usize(max_basic_block.into());
+        for basic_block in max_basic_block.iter() {
+            let basic_block_data = &tables[basic_block];
+            writer.usize(basic_block_data.statements.len());
+            for &statement in &basic_block_data.statements {
+                writer.usize(statement.into());
+            }
+            writer.usize(basic_block_data.terminator.into());
+            writer.usize(origins[basic_block].into());
+        }
+
+        let max_statement = bir::Statement::max_key(tables);
+        writer.usize(max_statement.into());
+        for statement in max_statement.iter() {
+            self.statement(writer, &tables[statement]);
+            writer.usize(origins[statement].into());
+        }
+
+        let max_terminator = bir::Terminator::max_key(tables);
+        writer.usize(max_terminator.into());
+        for terminator in max_terminator.iter() {
+            self.terminator(writer, tables, &tables[terminator]);
+            writer.usize(origins[terminator].into());
+        }
+
+        let max_expr = bir::Expr::max_key(tables);
+        writer.usize(max_expr.into());
+        for expr in max_expr.iter() {
+            self.expr(writer, tables, &tables[expr]);
+            writer.usize(origins[expr].into());
+        }
+
+        let max_place = bir::Place::max_key(tables);
+        writer.usize(max_place.into());
+        for place in max_place.iter() {
+            self.place(writer, &tables[place]);
+            writer.usize(origins[place].into());
+        }
+
+        let max_target_place = bir::TargetPlace::max_key(tables);
+        writer.usize(max_target_place.into());
+        for target_place in max_target_place.iter() {
+            match &tables[target_place] {
+                bir::TargetPlaceData::LocalVariable(local_variable) => {
+                    writer.u8(0);
+                    writer.usize((*local_variable).into());
+                }
+                bir::TargetPlaceData::Dot(owner, field) => {
+                    writer.u8(1);
+                    writer.usize((*owner).into());
+                    self.word(writer, *field);
+                }
+            }
+            writer.usize(origins[target_place].into());
+        }
+    }
+
+    fn statement(&mut self, writer: &mut Writer, data: &bir::StatementData) {
+        match data {
+            bir::StatementData::AssignExpr(target, expr) => {
+                writer.u8(0);
+                writer.usize((*target).into());
+                writer.usize((*expr).into());
+            }
+            bir::StatementData::AssignPlace(target, source) => {
+                writer.u8(1);
+                writer.usize((*target).into());
+                writer.usize((*source).into());
+            }
+            bir::StatementData::Clear(local_variable) => {
+                writer.u8(2);
+                writer.usize((*local_variable).into());
+            }
+            bir::StatementData::BreakpointStart(filename, index) => {
+                assert_eq!(*filename, self.filename);
+                writer.u8(3);
+                writer.usize(*index);
+            }
+            bir::StatementData::BreakpointEnd(filename, index, expr, place) => {
+                assert_eq!(*filename, self.filename);
+                writer.u8(4);
+                writer.usize(*index);
+                writer.usize((*expr).into());
+                match place {
+                    Some(place) => {
+                        writer.bool(true);
+                        writer.usize((*place).into());
+                    }
+                    None => writer.bool(false),
+                }
+            }
+        }
+    }
+
+    fn terminator(
+        &mut self,
+        writer: &mut Writer,
+        tables: &bir::Tables,
+        data: &bir::TerminatorData,
+    ) {
+        let db = self.db;
+        match data {
+            bir::TerminatorData::Goto(target) => {
+                writer.u8(0);
+                writer.usize((*target).into());
+            }
+            bir::TerminatorData::If(condition, if_true, if_false) => {
+                writer.u8(1);
+                writer.usize((*condition).into());
+                writer.usize((*if_true).into());
+                writer.usize((*if_false).into());
+            }
+            bir::TerminatorData::StartAtomic(target) => {
+                writer.u8(2);
+                writer.usize((*target).into());
+            }
+            bir::TerminatorData::EndAtomic(target) => {
+                writer.u8(3);
+                writer.usize((*target).into());
+            }
+            bir::TerminatorData::Return(place) => {
+                writer.u8(4);
+                writer.usize((*place).into());
+            }
+            bir::TerminatorData::Assign(target, bir::TerminatorExpr::Await(place), next) => {
+                writer.u8(5);
+                writer.usize((*target).into());
+                writer.usize((*place).into());
+                writer.usize((*next).into());
+            }
+            bir::TerminatorData::Assign(
+                target,
+                bir::TerminatorExpr::Call {
+                    function,
+                    arguments,
+                    labels,
+                },
+                next,
+            ) => {
+                writer.u8(6);
+                writer.usize((*target).into());
+                writer.usize((*function).into());
+                let arguments = &tables[*arguments];
+                writer.usize(arguments.len());
+                for &argument in arguments {
+                    writer.usize(argument.into());
+                }
+                for &label in &tables[*labels] {
+                    self.optional_word(writer, label.word(db));
+                    self.file_span(writer, label.span(db));
+                }
+                writer.usize((*next).into());
+            }
+            bir::TerminatorData::Error => writer.u8(7),
+            bir::TerminatorData::Panic => writer.u8(8),
+        }
+    }
+
+    fn expr(&mut self, writer: &mut Writer, tables: &bir::Tables, data: &bir::ExprData) {
+        match data {
+            bir::ExprData::BooleanLiteral(value) => {
+                writer.u8(0);
+                writer.bool(*value);
+            }
+            bir::ExprData::SignedIntegerLiteral(value) => {
+                writer.u8(1);
+                writer.i64(*value);
+            }
+            bir::ExprData::UnsignedIntegerLiteral(value) => {
+                writer.u8(2);
+                writer.u64(*value);
+            }
+            bir::ExprData::IntegerLiteral(value) => {
+                writer.u8(3);
+                writer.u64(*value);
+            }
+            bir::ExprData::FloatLiteral(value) => {
+                writer.u8(4);
+                writer.f64(value.0);
+            }
+            bir::ExprData::StringLiteral(word) => {
+                writer.u8(5);
+                self.word(writer, *word);
+            }
+            bir::ExprData::Reserve(place) => {
+                writer.u8(6);
+                writer.usize((*place).into());
+            }
+            bir::ExprData::Share(place) => {
+                writer.u8(7);
+                writer.usize((*place).into());
+            }
+            bir::ExprData::Lease(place) => {
+                writer.u8(8);
+                writer.usize((*place).into());
+            }
+            bir::ExprData::Shlease(place) => {
+                writer.u8(9);
+                writer.usize((*place).into());
+            }
+            bir::ExprData::Give(place) => {
+                writer.u8(10);
+                writer.usize((*place).into());
+            }
+            bir::ExprData::Unit => writer.u8(11),
+            bir::ExprData::Tuple(places) => {
+                writer.u8(12);
+                let places = &tables[*places];
+                writer.usize(places.len());
+                for &place in places {
+                    writer.usize(place.into());
+                }
+            }
+            bir::ExprData::Op(lhs, op, rhs) => {
+                writer.u8(13);
+                writer.usize((*lhs).into());
+                writer.usize(op_index(*op));
+                writer.usize((*rhs).into());
+            }
+            bir::ExprData::Unary(op, rhs) => {
+                writer.u8(14);
+                writer.usize(op_index(*op));
+                writer.usize((*rhs).into());
+            }
+            bir::ExprData::Error => writer.u8(15),
+        }
+    }
+
+    fn place(&mut self, writer: &mut Writer, data: &bir::PlaceData) {
+        match data {
+            bir::PlaceData::LocalVariable(local_variable) => {
+                writer.u8(0);
+                writer.usize((*local_variable).into());
+            }
+            bir::PlaceData::Function(function) => {
+                writer.u8(1);
+                writer.usize(self.item_index(Item::Function(*function)));
+            }
+            bir::PlaceData::Class(class) => {
+                writer.u8(2);
+                writer.usize(self.item_index(Item::Class(*class)));
+            }
+            bir::PlaceData::Intrinsic(intrinsic) => {
+                writer.u8(3);
+                self.word(writer, intrinsic.name(self.db));
+            }
+            bir::PlaceData::Dot(owner, field) => {
+                writer.u8(4);
+                writer.usize((*owner).into());
+                self.word(writer, *field);
+            }
+            bir::PlaceData::HostFunction(name) => {
+                writer.u8(5);
+                self.word(writer, *name);
+            }
+            bir::PlaceData::ExternFunction(function) => {
+                writer.u8(6);
+                writer.usize(self.item_index(Item::ExternFunction(*function)));
+            }
+        }
+    }
+
+    fn item_index(&self, item: Item) -> usize {
+        match self.item_indices.get(&item) {
+            Some(&index) => index,
+            None => panic!(
+                "`{}` is not defined in `{}`",
+                item.name(self.db).as_str(self.db),
+                self.filename.as_str(self.db)
+            ),
+        }
+    }
+}
+
+fn op_index(op: validated::op::Op) -> usize {
+    validated::op::Op::all().position(|o| o == op).unwrap()
+}
@@ -0,0 +1,221 @@
+//! The low-level pieces of the `.dadac` format: the header and the
+//! primitive encodings that everything else is built from. The
+//! [`Writer`] and [`Reader`] are also used to encode machine snapshots
+//! (see `dada_execute::snapshot`).
+//!
+//! Integers are written as LEB128 varints (signed integers are zigzag
+//! encoded first), floats as their 8 little-endian bytes, and strings as
+//! a length followed by UTF-8 bytes.
+
+use crate::DecodeError;
+
+/// Every `.dadac` file starts with these bytes.
+const MAGIC: &[u8] = b"DADAC";
+
+/// Bumped whenever the encoding changes.
+const FORMAT_VERSION: u32 = 3;
+
+/// The version of dada that wrote a file; files written by any other
+/// version are rejected, since the BIR they contain may have changed.
+const COMPILER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The header of a `.dadac` file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// The path of the source file the program was compiled from.
+    pub filename: String,
+
+    /// Hash of the source text (see [`source_hash`]), used to tell whether
+    /// the compiled file is out of date.
+    pub source_hash: u64,
+}
+
+impl Header {
+    /// Reads the header of `bytes`, checking that it is a `.dadac` file
+    /// written by this version of dada.
+    pub fn read(bytes: &[u8]) -> Result<Header, DecodeError> {
+        Self::read_from(&mut Reader::new(bytes))
+    }
+
+    pub(crate) fn read_from(reader: &mut Reader<'_>) -> Result<Header, DecodeError> {
+        if !reader.remaining().starts_with(MAGIC) {
+            return Err(DecodeError::NotBytecode);
+        }
+        reader.skip(MAGIC.len())?;
+        let format_version = reader.u32()?;
+        let compiler_version = reader.str()?;
+        if format_version != FORMAT_VERSION || compiler_version != COMPILER_VERSION {
+            return Err(DecodeError::WrongVersion {
+                compiler_version: compiler_version.to_string(),
+            });
+        }
+        let source_hash = reader.u64()?;
+        let filename = reader.str()?.to_string();
+        Ok(Header {
+            filename,
+            source_hash,
+        })
+    }
+
+    pub(crate) fn write_to(&self, writer: &mut Writer) {
+        writer.bytes.extend_from_slice(MAGIC);
+        writer.u32(FORMAT_VERSION);
+        writer.str(COMPILER_VERSION);
+        writer.u64(self.source_hash);
+        writer.str(&self.filename);
+    }
+}
+
+/// A hash of `source_text` that is stable across runs and platforms
+/// (64-bit FNV-1a).
+pub fn source_hash(source_text: &str) -> u64 {
+    source_text
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+}
+
+/// True if `bytes` is a `.dadac` file written by this version of dada
+/// from exactly `source_text`.
+pub fn is_up_to_date(bytes: &[u8], source_text: &str) -> bool {
+    match Header::read(bytes) {
+        Ok(header) => header.source_hash == source_hash(source_text),
+        Err(_) => false,
+    }
+}
+
+#[derive(Default)]
+pub struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn append(&mut self, other: Writer) {
+        self.bytes.extend(other.bytes);
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(u8::from(value));
+    }
+
+    pub fn u64(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.u8(byte);
+                return;
+            }
+            self.u8(byte | 0x80);
+        }
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.u64(u64::from(value));
+    }
+
+    pub fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    pub fn i64(&mut self, value: i64) {
+        self.u64(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    pub fn f64(&mut self, value: f64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn str(&mut self, value: &str) {
+        self.usize(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+}
+
+pub struct Reader<'b> {
+    bytes: &'b [u8],
+}
+
+impl<'b> Reader<'b> {
+    pub fn new(bytes: &'b [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn remaining(&self) -> &'b [u8] {
+        self.bytes
+    }
+
+    /// True if everything has been read.
+    pub fn is_at_end(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'b [u8], DecodeError> {
+        if self.bytes.len() < len {
+            return Err(DecodeError::Corrupt);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), DecodeError> {
+        self.take(len).map(|_| ())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, DecodeError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::Corrupt),
+        }
+    }
+
+    pub fn u64(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0_u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::Corrupt)
+    }
+
+    pub fn u32(&mut self) -> Result<u32, DecodeError> {
+        u32::try_from(self.u64()?).map_err(|_| DecodeError::Corrupt)
+    }
+
+    pub fn usize(&mut self) -> Result<usize, DecodeError> {
+        usize::try_from(self.u64()?).map_err(|_| DecodeError::Corrupt)
+    }
+
+    pub fn i64(&mut self) -> Result<i64, DecodeError> {
+        let value = self.u64()?;
+        Ok(((value >> 1) as i64) ^ -((value & 1) as i64))
+    }
+
+    pub fn f64(&mut self) -> Result<f64, DecodeError> {
+        let bytes = self.take(8)?;
+        Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn str(&mut self) -> Result<&'b str, DecodeError> {
+        let len = self.usize()?;
+        std::str::from_utf8(self.take(len)?).map_err(|_| DecodeError::Corrupt)
+    }
+}
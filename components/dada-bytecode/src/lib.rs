@@ -0,0 +1,75 @@
+//! A compact binary encoding ("bytecode") of brewed dada programs, stored
+//! in `.dadac` files by `dada build --target=bytecode`.
+//!
+//! A `.dadac` file holds the items of one source file (their names,
+//! spans, parameters, and fields), the BIR for each function, and the
+//! spans of each function's syntax expressions, which are what the
+//! interpreter needs to report errors. It also holds the source text, so
+//! that those errors can be printed. Loading a file (see
+//! `dada_db::Db::load_bytecode`) recreates the items and sets the
+//! `loaded` inputs of `dada-parse` and `dada-brew`, so that the program
+//! runs without being lexed, parsed, validated, or brewed.
+//!
+//! The header records the format and compiler versions (files written by
+//! another version are rejected) and a hash of the source text, so that
+//! callers can tell whether a file is out of date (see [`is_up_to_date`]).
+//!
+//! The same format also stores the diagnostics of a file (see
+//! [`encode_diagnostics`]), for the on-disk cache of `dada check`.
+
+#![feature(let_else)]
+#![feature(trait_upcasting)]
+#![allow(incomplete_features)]
+
+mod decode;
+mod diagnostics;
+mod encode;
+mod format;
+
+pub use decode::{bytecode, decode_module, LoadedClass, LoadedFunction, Module};
+pub use diagnostics::{decode_diagnostics, encode_diagnostics};
+pub use encode::encode;
+pub use format::{is_up_to_date, source_hash, Header, Reader, Writer};
+
+#[salsa::jar(Db)]
+pub struct Jar(decode::bytecode, decode::decode_module);
+
+pub trait Db: salsa::DbWithJar<Jar> + dada_brew::Db + dada_ir::Db + dada_parse::Db {}
+
+impl<T> Db for T where T: salsa::DbWithJar<Jar> + dada_brew::Db + dada_ir::Db + dada_parse::Db {}
+
+/// Errors from reading a `.dadac` file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The file does not start with the `.dadac` magic bytes.
+    NotBytecode,
+
+    /// The file was written by a different version of dada.
+    WrongVersion { compiler_version: String },
+
+    /// The file is truncated or contains invalid data.
+    Corrupt,
+
+    /// The source of `filename` has changed since the data was written
+    /// (only checked for snapshots, see `dada_execute::snapshot`).
+    SourceChanged { filename: String },
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::NotBytecode => write!(f, "not a compiled dada program"),
+            DecodeError::WrongVersion { compiler_version } => write!(
+                f,
+                "compiled by a different version of dada ({}); rebuild it with `dada build --target=bytecode`",
+                compiler_version
+            ),
+            DecodeError::Corrupt => write!(f, "the compiled program is corrupt"),
+            DecodeError::SourceChanged { filename } => {
+                write!(f, "`{}` has changed since the snapshot was taken", filename)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
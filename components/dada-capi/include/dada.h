@@ -0,0 +1,83 @@
+/*
+ * C API for embedding the Dada interpreter; see `src/lib.rs` in the
+ * `dada-capi` crate for details.
+ *
+ *     DadaRuntime *runtime = dada_runtime_new();
+ *     dada_runtime_set_output_callback(runtime, on_output, NULL);
+ *     dada_runtime_set_diagnostic_callback(runtime, on_diagnostic, NULL);
+ *     if (dada_runtime_load(runtime, "hello.dada", source) == DADA_OK) {
+ *         dada_runtime_run_main(runtime);
+ *     }
+ *     dada_runtime_free(runtime);
+ *
+ * Strings passed to callbacks are only valid during the callback.
+ */
+
+#ifndef DADA_H
+#define DADA_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum DadaStatus {
+    DADA_OK = 0,
+    DADA_INVALID_ARGUMENT = 1,
+    DADA_COMPILE_ERROR = 2,
+    DADA_NO_MAIN = 3,
+    DADA_RUNTIME_ERROR = 4,
+    DADA_INTERNAL_ERROR = 5,
+} DadaStatus;
+
+typedef enum DadaSeverity {
+    DADA_SEVERITY_HELP = 0,
+    DADA_SEVERITY_NOTE = 1,
+    DADA_SEVERITY_WARNING = 2,
+    DADA_SEVERITY_ERROR = 3,
+} DadaSeverity;
+
+typedef struct DadaDiagnostic {
+    DadaSeverity severity;
+    const char *message;
+    /* Empty if the diagnostic doesn't refer to a file. */
+    const char *filename;
+    /* 1-based; 0 if the diagnostic doesn't refer to a file. */
+    uint32_t line;
+    uint32_t column;
+    /* Formatted as `dada run` would print it, without colors. */
+    const char *rendered;
+} DadaDiagnostic;
+
+typedef struct DadaRuntime DadaRuntime;
+
+/* Called with each line the program prints, as it is printed; `text` is
+ * UTF-8 and is *not* NUL-terminated. */
+typedef void (*DadaOutputCallback)(void *user_data, const char *text, size_t len);
+
+typedef void (*DadaDiagnosticCallback)(void *user_data, const DadaDiagnostic *diagnostic);
+
+/* Returns NULL if the runtime could not be created. */
+DadaRuntime *dada_runtime_new(void);
+
+void dada_runtime_free(DadaRuntime *runtime);
+
+DadaStatus dada_runtime_set_output_callback(
+    DadaRuntime *runtime, DadaOutputCallback callback, void *user_data);
+
+DadaStatus dada_runtime_set_diagnostic_callback(
+    DadaRuntime *runtime, DadaDiagnosticCallback callback, void *user_data);
+
+/* Loads (or replaces) a source file; returns DADA_COMPILE_ERROR if the
+ * compiler reported errors. */
+DadaStatus dada_runtime_load(DadaRuntime *runtime, const char *name, const char *source);
+
+DadaStatus dada_runtime_run_main(DadaRuntime *runtime);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* DADA_H */
@@ -0,0 +1,323 @@
+//! A C API for embedding the Dada interpreter, so that programs written
+//! in languages other than Rust can load and run Dada code. The matching
+//! declarations are in `include/dada.h`.
+//!
+//! A host creates a runtime with [`dada_runtime_new`], registers callbacks
+//! to receive diagnostics and printed output, loads one or more source
+//! files with [`dada_runtime_load`], and then runs the program with
+//! [`dada_runtime_run_main`]. Strings passed to the callbacks are only
+//! valid for the duration of the callback; copy them to keep them.
+//!
+//! A runtime must only be used from one thread at a time.
+
+use std::{
+    ffi::{c_void, CStr, CString},
+    os::raw::c_char,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use dada::{Diagnostic, Error, Severity};
+
+/// Called with each line that the program prints (including its newline),
+/// as it is printed, as UTF-8 that is *not* NUL-terminated.
+pub type DadaOutputCallback =
+    extern "C" fn(user_data: *mut c_void, text: *const c_char, len: usize);
+
+/// Called once for each diagnostic reported while loading or running.
+pub type DadaDiagnosticCallback =
+    extern "C" fn(user_data: *mut c_void, diagnostic: *const DadaDiagnostic);
+
+/// The result of a call to the C API.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DadaStatus {
+    Ok = 0,
+
+    /// A pointer was null, or a string was not valid UTF-8.
+    InvalidArgument = 1,
+
+    /// The source file had errors; they were passed to the diagnostic
+    /// callback.
+    CompileError = 2,
+
+    /// No loaded file defines a `main` function.
+    NoMain = 3,
+
+    /// The program reported an error while running; it was passed to the
+    /// diagnostic callback.
+    RuntimeError = 4,
+
+    /// Something went wrong inside the interpreter.
+    InternalError = 5,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DadaSeverity {
+    Help = 0,
+    Note = 1,
+    Warning = 2,
+    Error = 3,
+}
+
+/// A diagnostic, such as a type error. The strings are NUL-terminated.
+#[repr(C)]
+pub struct DadaDiagnostic {
+    pub severity: DadaSeverity,
+    pub message: *const c_char,
+
+    /// Name of the file the diagnostic refers to (empty if none).
+    pub filename: *const c_char,
+
+    /// 1-based line and column where the diagnostic starts (0 if the
+    /// diagnostic doesn't refer to a file).
+    pub line: u32,
+    pub column: u32,
+
+    /// The diagnostic formatted as `dada run` would print it (without
+    /// colors), including labels and source snippets.
+    pub rendered: *const c_char,
+}
+
+/// An embedded interpreter, created with [`dada_runtime_new`] and freed
+/// with [`dada_runtime_free`].
+pub struct DadaRuntime {
+    runtime: dada::Runtime,
+
+    /// Runs the futures returned by [`dada::Runtime::call`].
+    executor: tokio::runtime::Runtime,
+
+    diagnostic_callback: Option<(DadaDiagnosticCallback, *mut c_void)>,
+}
+
+impl DadaRuntime {
+    fn run_main(&mut self) -> DadaStatus {
+        let result = self.executor.block_on(self.runtime.call("main", vec![]));
+        match result {
+            Ok(_) => DadaStatus::Ok,
+            Err(Error::UnknownFunction(_)) => DadaStatus::NoMain,
+            Err(Error::Runtime(diagnostic)) => {
+                self.report_diagnostic(&diagnostic);
+                DadaStatus::RuntimeError
+            }
+            Err(error) => {
+                let message = error.to_string();
+                self.report(DadaSeverity::Error, &message, "", 0, 0, &message);
+                DadaStatus::InternalError
+            }
+        }
+    }
+
+    fn report_diagnostic(&self, diagnostic: &Diagnostic) {
+        let severity = match diagnostic.severity {
+            Severity::Help => DadaSeverity::Help,
+            Severity::Note => DadaSeverity::Note,
+            Severity::Warning => DadaSeverity::Warning,
+            Severity::Error => DadaSeverity::Error,
+        };
+        self.report(
+            severity,
+            &diagnostic.message,
+            &diagnostic.filename,
+            diagnostic.line,
+            diagnostic.column,
+            &diagnostic.rendered,
+        );
+    }
+
+    fn report(
+        &self,
+        severity: DadaSeverity,
+        message: &str,
+        filename: &str,
+        line: u32,
+        column: u32,
+        rendered: &str,
+    ) {
+        let Some((callback, user_data)) = self.diagnostic_callback else {
+            return;
+        };
+        let message = c_string(message);
+        let filename = c_string(filename);
+        let rendered = c_string(rendered);
+        let diagnostic = DadaDiagnostic {
+            severity,
+            message: message.as_ptr(),
+            filename: filename.as_ptr(),
+            line,
+            column,
+            rendered: rendered.as_ptr(),
+        };
+        callback(user_data, &diagnostic);
+    }
+}
+
+/// The `user_data` pointer given with the output callback, which the
+/// runtime keeps to pass back to it.
+#[derive(Copy, Clone)]
+struct UserData(*mut c_void);
+
+// SAFETY: a runtime is only used from one thread at a time, and only calls
+// the output callback while the host is calling into it.
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+impl UserData {
+    fn as_ptr(self) -> *mut c_void {
+        self.0
+    }
+}
+
+/// Converts `s` for C, dropping any NUL characters (which C would take to
+/// be the end of the string).
+fn c_string(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).unwrap()
+}
+
+/// Reads a NUL-terminated UTF-8 string, returning `None` if `ptr` is null
+/// or the string is not valid UTF-8.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string.
+unsafe fn str_from<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    CStr::from_ptr(ptr).to_str().ok()
+}
+
+/// Runs `op`, turning a panic into [`DadaStatus::InternalError`] rather
+/// than unwinding into C.
+fn guard(op: impl FnOnce() -> DadaStatus) -> DadaStatus {
+    catch_unwind(AssertUnwindSafe(op)).unwrap_or(DadaStatus::InternalError)
+}
+
+/// Creates a runtime with no files loaded. Returns null if the runtime
+/// could not be created.
+#[no_mangle]
+pub extern "C" fn dada_runtime_new() -> *mut DadaRuntime {
+    let executor = match tokio::runtime::Builder::new_current_thread().build() {
+        Ok(executor) => executor,
+        Err(_) => return std::ptr::null_mut(),
+    };
+    let mut runtime = dada::Runtime::new();
+    // Until there is an output callback, discard the output.
+    runtime.on_output(|_| {});
+    Box::into_raw(Box::new(DadaRuntime {
+        runtime,
+        executor,
+        diagnostic_callback: None,
+    }))
+}
+
+/// Frees a runtime created by [`dada_runtime_new`]. Does nothing if
+/// `runtime` is null.
+///
+/// # Safety
+///
+/// `runtime` must be null or a pointer returned by [`dada_runtime_new`]
+/// that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn dada_runtime_free(runtime: *mut DadaRuntime) {
+    if !runtime.is_null() {
+        drop(Box::from_raw(runtime));
+    }
+}
+
+/// Sets the function that receives each line the program prints, as it is
+/// printed, replacing any earlier one; `user_data` is passed back to it
+/// unchanged. Pass a null `callback` to discard the output.
+///
+/// # Safety
+///
+/// `runtime` must be a live pointer returned by [`dada_runtime_new`].
+#[no_mangle]
+pub unsafe extern "C" fn dada_runtime_set_output_callback(
+    runtime: *mut DadaRuntime,
+    callback: Option<DadaOutputCallback>,
+    user_data: *mut c_void,
+) -> DadaStatus {
+    let Some(runtime) = runtime.as_mut() else {
+        return DadaStatus::InvalidArgument;
+    };
+    let user_data = UserData(user_data);
+    runtime.runtime.on_output(move |text| {
+        if let Some(callback) = callback {
+            callback(
+                user_data.as_ptr(),
+                text.as_ptr() as *const c_char,
+                text.len(),
+            );
+        }
+    });
+    DadaStatus::Ok
+}
+
+/// Sets the function that receives diagnostics, replacing any earlier
+/// one; `user_data` is passed back to it unchanged. Pass a null
+/// `callback` to discard diagnostics.
+///
+/// # Safety
+///
+/// `runtime` must be a live pointer returned by [`dada_runtime_new`].
+#[no_mangle]
+pub unsafe extern "C" fn dada_runtime_set_diagnostic_callback(
+    runtime: *mut DadaRuntime,
+    callback: Option<DadaDiagnosticCallback>,
+    user_data: *mut c_void,
+) -> DadaStatus {
+    let Some(runtime) = runtime.as_mut() else {
+        return DadaStatus::InvalidArgument;
+    };
+    runtime.diagnostic_callback = callback.map(|callback| (callback, user_data));
+    DadaStatus::Ok
+}
+
+/// Loads (or, if `name` was loaded before, replaces) a source file,
+/// passing each diagnostic the compiler reports to the diagnostic
+/// callback. Returns [`DadaStatus::CompileError`] if any of them is an
+/// error.
+///
+/// # Safety
+///
+/// `runtime` must be a live pointer returned by [`dada_runtime_new`];
+/// `name` and `source` must be NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn dada_runtime_load(
+    runtime: *mut DadaRuntime,
+    name: *const c_char,
+    source: *const c_char,
+) -> DadaStatus {
+    let (Some(runtime), Some(name), Some(source)) =
+        (runtime.as_mut(), str_from(name), str_from(source))
+    else {
+        return DadaStatus::InvalidArgument;
+    };
+    guard(|| {
+        let diagnostics = runtime.runtime.load(name, source);
+        for diagnostic in &diagnostics {
+            runtime.report_diagnostic(diagnostic);
+        }
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            DadaStatus::CompileError
+        } else {
+            DadaStatus::Ok
+        }
+    })
+}
+
+/// Runs the `main` function of the loaded files, passing each line it
+/// prints to the output callback as it is printed, and any error to the
+/// diagnostic callback.
+///
+/// # Safety
+///
+/// `runtime` must be a live pointer returned by [`dada_runtime_new`].
+#[no_mangle]
+pub unsafe extern "C" fn dada_runtime_run_main(runtime: *mut DadaRuntime) -> DadaStatus {
+    let Some(runtime) = runtime.as_mut() else {
+        return DadaStatus::InvalidArgument;
+    };
+    guard(|| runtime.run_main())
+}
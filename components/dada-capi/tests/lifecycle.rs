@@ -0,0 +1,193 @@
+//! Drives a runtime through the C API as a C host would: creating it,
+//! registering callbacks, loading and running programs, and freeing it.
+
+use std::{
+    ffi::{c_void, CStr},
+    os::raw::c_char,
+    ptr,
+};
+
+use dada_capi::*;
+
+const PRINT_TWICE: &[u8] = b"\
+async fn main() {
+    print(\"hi\").await
+    print(\"there\").await
+}
+\0";
+
+const UNKNOWN_NAME: &[u8] = b"\
+async fn main() {
+    print(missing).await
+}
+\0";
+
+const DIVIDE_BY_ZERO: &[u8] = b"\
+async fn main() {
+    print(\"start\").await
+    x = 0
+    print(1 / x).await
+}
+\0";
+
+/// `bytes`, which end with a NUL, as a C string.
+fn cstr(bytes: &[u8]) -> &CStr {
+    CStr::from_bytes_with_nul(bytes).unwrap()
+}
+
+/// What the callbacks have received.
+#[derive(Default)]
+struct Received {
+    /// The text of each call to the output callback.
+    output: Vec<String>,
+
+    /// The severity, message, line, and column of each diagnostic.
+    diagnostics: Vec<(DadaSeverity, String, u32, u32)>,
+}
+
+extern "C" fn on_output(user_data: *mut c_void, text: *const c_char, len: usize) {
+    let received = unsafe { &mut *(user_data as *mut Received) };
+    let text = unsafe { std::slice::from_raw_parts(text as *const u8, len) };
+    received
+        .output
+        .push(String::from_utf8(text.to_vec()).unwrap());
+}
+
+extern "C" fn on_diagnostic(user_data: *mut c_void, diagnostic: *const DadaDiagnostic) {
+    let received = unsafe { &mut *(user_data as *mut Received) };
+    let diagnostic = unsafe { &*diagnostic };
+    let message = unsafe { CStr::from_ptr(diagnostic.message) };
+    received.diagnostics.push((
+        diagnostic.severity,
+        message.to_str().unwrap().to_string(),
+        diagnostic.line,
+        diagnostic.column,
+    ));
+}
+
+/// Loads `source` as `test.dada` into a fresh runtime with both callbacks
+/// registered, runs `main` if it loaded, and frees the runtime. Returns
+/// the status of loading, the status of running (if it ran), and what the
+/// callbacks received.
+fn run(source: &CStr) -> (DadaStatus, Option<DadaStatus>, Received) {
+    let mut received = Received::default();
+    let user_data = &mut received as *mut Received as *mut c_void;
+    unsafe {
+        let runtime = dada_runtime_new();
+        assert!(!runtime.is_null());
+        assert_eq!(
+            dada_runtime_set_output_callback(runtime, Some(on_output), user_data),
+            DadaStatus::Ok
+        );
+        assert_eq!(
+            dada_runtime_set_diagnostic_callback(runtime, Some(on_diagnostic), user_data),
+            DadaStatus::Ok
+        );
+        let name = cstr(b"test.dada\0");
+        let loaded = dada_runtime_load(runtime, name.as_ptr(), source.as_ptr());
+        let ran = (loaded == DadaStatus::Ok).then(|| dada_runtime_run_main(runtime));
+        dada_runtime_free(runtime);
+        (loaded, ran, received)
+    }
+}
+
+#[test]
+fn run_main() {
+    let (loaded, ran, received) = run(cstr(PRINT_TWICE));
+    assert_eq!(loaded, DadaStatus::Ok);
+    assert_eq!(ran, Some(DadaStatus::Ok));
+    // Each line is passed on as it is printed, not all at once at the end.
+    assert_eq!(received.output, ["hi\n", "there\n"]);
+    assert!(received.diagnostics.is_empty());
+}
+
+#[test]
+fn compile_error() {
+    let (loaded, ran, received) = run(cstr(UNKNOWN_NAME));
+    assert_eq!(loaded, DadaStatus::CompileError);
+    assert_eq!(ran, None);
+    assert_eq!(
+        received.diagnostics,
+        [(
+            DadaSeverity::Error,
+            "can't find anything named `missing`".to_string(),
+            2,
+            11
+        )]
+    );
+}
+
+#[test]
+fn runtime_error() {
+    let (loaded, ran, received) = run(cstr(DIVIDE_BY_ZERO));
+    assert_eq!(loaded, DadaStatus::Ok);
+    assert_eq!(ran, Some(DadaStatus::RuntimeError));
+    assert_eq!(received.output, ["start\n"]);
+    assert_eq!(
+        received.diagnostics,
+        [(DadaSeverity::Error, "divide by zero".to_string(), 4, 13)]
+    );
+}
+
+#[test]
+fn no_main() {
+    let (loaded, ran, _) = run(cstr(b"fn helper() {}\n\0"));
+    assert_eq!(loaded, DadaStatus::Ok);
+    assert_eq!(ran, Some(DadaStatus::NoMain));
+}
+
+#[test]
+fn null_pointers() {
+    unsafe {
+        let runtime = ptr::null_mut();
+        assert_eq!(
+            dada_runtime_set_output_callback(runtime, Some(on_output), ptr::null_mut()),
+            DadaStatus::InvalidArgument
+        );
+        assert_eq!(
+            dada_runtime_set_diagnostic_callback(runtime, Some(on_diagnostic), ptr::null_mut()),
+            DadaStatus::InvalidArgument
+        );
+        assert_eq!(
+            dada_runtime_load(runtime, cstr(b"test.dada\0").as_ptr(), cstr(b"\0").as_ptr()),
+            DadaStatus::InvalidArgument
+        );
+        assert_eq!(dada_runtime_run_main(runtime), DadaStatus::InvalidArgument);
+        dada_runtime_free(runtime);
+
+        let runtime = dada_runtime_new();
+        assert_eq!(
+            dada_runtime_load(runtime, ptr::null(), cstr(b"\0").as_ptr()),
+            DadaStatus::InvalidArgument
+        );
+        assert_eq!(
+            dada_runtime_load(runtime, cstr(b"test.dada\0").as_ptr(), ptr::null()),
+            DadaStatus::InvalidArgument
+        );
+        dada_runtime_free(runtime);
+    }
+}
+
+#[test]
+fn invalid_utf8() {
+    unsafe {
+        let runtime = dada_runtime_new();
+        assert_eq!(
+            dada_runtime_load(
+                runtime,
+                cstr(b"test\xff.dada\0").as_ptr(),
+                cstr(b"\0").as_ptr()
+            ),
+            DadaStatus::InvalidArgument
+        );
+        assert_eq!(
+            dada_runtime_load(
+                runtime,
+                cstr(b"test.dada\0").as_ptr(),
+                cstr(b"fn main() {\xff}\0").as_ptr()
+            ),
+            DadaStatus::InvalidArgument
+        );
+        dada_runtime_free(runtime);
+    }
+}
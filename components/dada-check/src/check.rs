@@ -18,6 +18,10 @@ pub fn check_filename(db: &dyn crate::Db, filename: Filename) {
             Item::Class(class) => {
                 class.fields(db);
             }
+            Item::ExternFunction(_) => {
+                // Fully checked by the parser; the library is not loaded
+                // until the function is called.
+            }
         }
     }
 }
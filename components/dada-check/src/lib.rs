@@ -2,9 +2,14 @@
 #![feature(trait_upcasting)]
 
 mod check;
+mod lint;
 
 #[salsa::jar(Db)]
-pub struct Jar(check::check_filename);
+pub struct Jar(
+    check::check_filename,
+    lint::lint_filename,
+    lint::lint_function,
+);
 
 pub trait Db:
     salsa::DbWithJar<Jar>
@@ -27,3 +32,4 @@ impl<T> Db for T where
 }
 
 pub use check::check_filename;
+pub use lint::lint_filename;
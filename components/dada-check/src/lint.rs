@@ -0,0 +1,392 @@
+//! The lints run by `dada lint` (see [`dada_ir::lint`]). Like the
+//! errors found by [`check_filename`](crate::check_filename), what they
+//! find is pushed onto the diagnostics accumulator, at the level set for
+//! each lint.
+
+use std::collections::{HashMap, HashSet};
+
+use dada_id::prelude::*;
+use dada_ir::{
+    code::{syntax, validated},
+    diagnostic::{Applicability, Diagnostic, DiagnosticBuilder, Severity},
+    filename::Filename,
+    function::Function,
+    item::Item,
+    lint::{Lint, LintLevel},
+    span::FileSpan,
+    storage::Specifier,
+};
+use dada_parse::prelude::*;
+use dada_validate::{prelude::*, symbols::Symbol};
+
+#[salsa::memoized(in crate::Jar)]
+pub fn lint_filename(db: &dyn crate::Db, filename: Filename) {
+    for &item in filename.items(db) {
+        if let Item::Function(function) = item {
+            lint_function(db, function);
+        }
+    }
+}
+
+#[salsa::memoized(in crate::Jar)]
+pub fn lint_function(db: &dyn crate::Db, function: Function) {
+    let linter = Linter {
+        db,
+        function,
+        tree: function.validated_tree(db),
+        spans: function.syntax_tree(db).spans(db),
+    };
+    linter.unused_variables();
+    linter.shadowing();
+    linter.unreachable_code();
+    linter.share_fresh();
+    linter.share_into_unique();
+}
+
+struct Linter<'me> {
+    db: &'me dyn crate::Db,
+    function: Function,
+    tree: validated::Tree,
+    spans: &'me syntax::Spans,
+}
+
+impl Linter<'_> {
+    fn tables(&self) -> &validated::Tables {
+        &self.tree.data(self.db).tables
+    }
+
+    fn origins(&self) -> &validated::Origins {
+        self.tree.origins(self.db)
+    }
+
+    fn span(&self, expr: validated::Expr) -> FileSpan {
+        let syntax_expr = self.origins()[expr].syntax_expr;
+        let syntax_tree = self.function.syntax_tree(self.db);
+        syntax_tree.file_span(self.db, self.spans[syntax_expr])
+    }
+
+    /// The span of the name of `local_variable`, if it was declared by the
+    /// user (rather than being a temporary).
+    fn declaration_span(&self, local_variable: validated::LocalVariable) -> Option<FileSpan> {
+        Symbol::LocalVariable(self.tree, local_variable).definition_span(self.db)
+    }
+
+    /// Starts reporting something that `lint` found at `span`, at the
+    /// lint's level for the file; `None` if the lint is allowed. The name
+    /// of the lint is added to `message`, so that the user knows what to
+    /// allow.
+    fn report(&self, lint: Lint, span: FileSpan, message: String) -> Option<DiagnosticBuilder> {
+        let severity = match dada_ir::lint::lint_level(self.db, span.filename, lint) {
+            LintLevel::Allow => return None,
+            LintLevel::Warn => Severity::Warning,
+            LintLevel::Deny => Severity::Error,
+        };
+        let message = format!("{} [{}]", message, lint.name());
+        Some(Diagnostic::builder(severity, span, message))
+    }
+
+    /// Variables (and parameters) that are never read. Those whose names
+    /// start with `_` are expected to be unused. Functions with errors are
+    /// skipped.
+    fn unused_variables(&self) {
+        let tables = self.tables();
+
+        // The reads in code with errors are lost, so the variables they
+        // read would look unused.
+        if validated::Expr::max_key(tables)
+            .iter()
+            .any(|expr| matches!(expr.data(tables), validated::ExprData::Error))
+        {
+            return;
+        }
+
+        let mut read = HashSet::new();
+        for place in validated::Place::max_key(tables).iter() {
+            if let validated::PlaceData::LocalVariable(local_variable) = place.data(tables) {
+                read.insert(*local_variable);
+            }
+        }
+
+        for local_variable in self.tree.data(self.db).max_local_variable().iter() {
+            let Some(name) = local_variable.data(tables).name else {
+                continue;
+            };
+            let name = name.as_str(self.db);
+            if read.contains(&local_variable) || name.starts_with('_') {
+                continue;
+            }
+            let Some(span) = self.declaration_span(local_variable) else {
+                continue;
+            };
+            if let Some(diagnostic) = self.report(
+                Lint::UnusedVariable,
+                span,
+                format!("unused variable `{}`", name),
+            ) {
+                diagnostic
+                    .primary_label("this variable is never read")
+                    .fix(span, format!("_{}", name), "prefix the name with `_`")
+                    .emit(self.db);
+            }
+        }
+    }
+
+    /// Local variables declared where another variable with the same name
+    /// is in scope: a parameter, or a local variable declared earlier in
+    /// the same block or an enclosing one.
+    fn shadowing(&self) {
+        let tables = self.tables();
+
+        // The span of the block that declares each local variable.
+        let mut blocks = vec![];
+        for expr in validated::Expr::max_key(tables).iter() {
+            if let validated::ExprData::Declare(vars, _) = expr.data(tables) {
+                let block_span = self.span(expr);
+                blocks.extend(vars.iter().map(|&var| (var, block_span)));
+            }
+        }
+        let block_of = |local_variable| {
+            blocks
+                .iter()
+                .find(|&&(var, _)| var == local_variable)
+                .map(|&(_, span)| span)
+        };
+
+        let variables = self.tree.data(self.db).max_local_variable();
+        for local_variable in variables.iter() {
+            if !matches!(
+                self.origins()[local_variable],
+                validated::LocalVariableOrigin::LocalVariable(_)
+            ) {
+                continue;
+            }
+            let Some(name) = local_variable.data(tables).name else {
+                continue;
+            };
+            let Some(span) = self.declaration_span(local_variable) else {
+                continue;
+            };
+
+            let shadowed = variables
+                .iter()
+                .filter(|&other| other != local_variable)
+                .filter(|&other| other.data(tables).name == Some(name))
+                .filter_map(|other| {
+                    let other_span = self.declaration_span(other)?;
+                    match self.origins()[other] {
+                        validated::LocalVariableOrigin::Parameter(_) => {
+                            Some((other_span, "parameter"))
+                        }
+                        validated::LocalVariableOrigin::LocalVariable(_) => {
+                            let block = block_of(other)?;
+                            let in_scope = other_span.end <= span.start
+                                && block.start <= span.start
+                                && span.end <= block.end;
+                            in_scope.then_some((other_span, "variable"))
+                        }
+                        validated::LocalVariableOrigin::Temporary(_) => None,
+                    }
+                })
+                .max_by_key(|(other_span, _)| other_span.start);
+
+            if let Some((shadowed, kind)) = shadowed {
+                if let Some(diagnostic) = self.report(
+                    Lint::Shadowing,
+                    span,
+                    format!(
+                        "`{}` shadows a variable with the same name",
+                        name.as_str(self.db)
+                    ),
+                ) {
+                    diagnostic
+                        .primary_label("declared again here")
+                        .secondary_label(shadowed, format!("the shadowed {kind} is declared here"))
+                        .emit(self.db);
+                }
+            }
+        }
+    }
+
+    /// Expressions in a block after one that always returns, breaks, or
+    /// continues. Only the first unreachable expression in each block is
+    /// reported.
+    fn unreachable_code(&self) {
+        let tables = self.tables();
+        for expr in validated::Expr::max_key(tables).iter() {
+            let validated::ExprData::Seq(exprs) = expr.data(tables) else {
+                continue;
+            };
+            let Some(index) = exprs.iter().position(|&e| self.diverges(e)) else {
+                continue;
+            };
+            let Some(&unreachable) = exprs[index + 1..]
+                .iter()
+                .find(|&&e| !self.origins()[e].synthesized)
+            else {
+                continue;
+            };
+            if let Some(diagnostic) = self.report(
+                Lint::UnreachableCode,
+                self.span(unreachable),
+                "unreachable code".to_string(),
+            ) {
+                diagnostic
+                    .primary_label("this is never executed")
+                    .secondary_label(
+                        self.span(exprs[index]),
+                        "because control never gets past this",
+                    )
+                    .emit(self.db);
+            }
+        }
+    }
+
+    /// True if control never continues after `expr`.
+    fn diverges(&self, expr: validated::Expr) -> bool {
+        match expr.data(self.tables()) {
+            validated::ExprData::Return(_)
+            | validated::ExprData::Break { .. }
+            | validated::ExprData::Continue(_) => true,
+            validated::ExprData::Seq(exprs) => exprs.iter().any(|&e| self.diverges(e)),
+            validated::ExprData::Declare(_, body) => self.diverges(*body),
+            validated::ExprData::If(condition, if_true, if_false) => {
+                self.diverges(*condition) || (self.diverges(*if_true) && self.diverges(*if_false))
+            }
+            _ => false,
+        }
+    }
+
+    /// Shared leases of a value that is not stored anywhere, like
+    /// `Pair(22, 44).lease.share` or `Pair(22, 44).shlease`: the leased
+    /// value only lives in a temporary.
+    fn share_fresh(&self) {
+        let tables = self.tables();
+        for expr in validated::Expr::max_key(tables).iter() {
+            if self.origins()[expr].synthesized {
+                continue;
+            }
+            let leased = match expr.data(tables) {
+                validated::ExprData::Shlease(place) => *place,
+                validated::ExprData::Share(operand) => match operand.data(tables) {
+                    validated::ExprData::Lease(place) => *place,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            let validated::PlaceData::LocalVariable(local_variable) = leased.data(tables) else {
+                continue;
+            };
+            if local_variable.data(tables).name.is_some() {
+                continue;
+            }
+            if let Some(diagnostic) = self.report(
+                Lint::ShareFresh,
+                self.span(expr),
+                "sharing a lease of a value that is not stored anywhere".to_string(),
+            ) {
+                diagnostic
+                    .primary_label("the leased value is only held by a temporary")
+                    .emit(self.db);
+            }
+        }
+    }
+
+    /// New values, like `Point(1, 2).share`, that are shared and then
+    /// stored in a `my` or `leased` variable. Those need a unique value, so
+    /// storing the shared one fails when the program runs.
+    fn share_into_unique(&self) {
+        let tables = self.tables();
+
+        // The user's `Specifier x = <rvalue>` is validated to `temp = <rvalue>;
+        // x = temp`, so find what each temporary was initialized with.
+        let mut initializers = HashMap::new();
+        for expr in validated::Expr::max_key(tables).iter() {
+            if let validated::ExprData::AssignTemporary(temporary, initializer) = expr.data(tables)
+            {
+                initializers.insert(*temporary, *initializer);
+            }
+        }
+
+        for expr in validated::Expr::max_key(tables).iter() {
+            let validated::ExprData::AssignFromPlace(target, place) = expr.data(tables) else {
+                continue;
+            };
+            let validated::TargetPlaceData::LocalVariable(local_variable) = target.data(tables)
+            else {
+                continue;
+            };
+            let Some(specifier) = local_variable.data(tables).specifier else {
+                continue;
+            };
+            if specifier.defaulted(self.db) || !specifier.specifier(self.db).must_be_unique() {
+                continue;
+            }
+            let validated::PlaceData::LocalVariable(temporary) = place.data(tables) else {
+                continue;
+            };
+            let Some(&share_expr) = initializers.get(temporary) else {
+                continue;
+            };
+            let validated::ExprData::Share(shared_expr) = share_expr.data(tables) else {
+                continue;
+            };
+            if !self.is_new_value(*shared_expr) {
+                continue;
+            }
+
+            let share_span = self.span(share_expr);
+            let keyword_span = FileSpan {
+                start: self.span(*shared_expr).end,
+                ..share_span
+            };
+            let specifier_span = specifier.span(self.db);
+            if let Some(diagnostic) = self.report(
+                Lint::ShareFresh,
+                share_span,
+                format!(
+                    "sharing a new value to store it in a `{}` variable",
+                    specifier.specifier(self.db)
+                ),
+            ) {
+                diagnostic
+                    .primary_label("the shared value is not unique, so it can't be stored here")
+                    .secondary_label(
+                        specifier_span,
+                        format!(
+                            "the variable is declared `{}` here",
+                            specifier.specifier(self.db)
+                        ),
+                    )
+                    .suggestion(
+                        keyword_span,
+                        "",
+                        "remove the `.share`",
+                        Applicability::MaybeIncorrect,
+                    )
+                    .suggestion(
+                        specifier_span,
+                        Specifier::Our.to_string(),
+                        "declare the variable `our` to store the shared value",
+                        Applicability::MaybeIncorrect,
+                    )
+                    .emit(self.db);
+            }
+        }
+    }
+
+    /// True if `expr` creates a new value, rather than giving or leasing
+    /// one that is stored somewhere.
+    fn is_new_value(&self, expr: validated::Expr) -> bool {
+        matches!(
+            expr.data(self.tables()),
+            validated::ExprData::BooleanLiteral(_)
+                | validated::ExprData::SignedIntegerLiteral(_)
+                | validated::ExprData::UnsignedIntegerLiteral(_)
+                | validated::ExprData::IntegerLiteral(_)
+                | validated::ExprData::FloatLiteral(_)
+                | validated::ExprData::StringLiteral(_)
+                | validated::ExprData::Call(..)
+                | validated::ExprData::Tuple(_)
+        )
+    }
+}
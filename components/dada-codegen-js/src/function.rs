@@ -0,0 +1,529 @@
+use std::fmt::Write;
+
+use dada_brew::prelude::*;
+use dada_collections::Map;
+use dada_id::prelude::*;
+use dada_ir::{
+    code::{
+        bir::{self, ExprData, PlaceData, StatementData, TargetPlaceData, TerminatorData},
+        syntax,
+        validated::op::Op,
+    },
+    error,
+    function::Function,
+    origin_table::HasOriginIn,
+    word::{SpannedOptionalWord, Word},
+};
+use dada_parse::prelude::*;
+
+use crate::module::{class_name, function_name, identifier, ModuleBuilder};
+
+/// Compiles the BIR for one function into a JS function.
+///
+/// A basic block that is the target of exactly one jump is emitted inline
+/// at that jump. The remaining blocks become the cases of a `switch` on
+/// `$bb` inside a loop, and jumping to one of them sets `$bb` and
+/// continues the loop. (If the start block is the only such block and
+/// nothing jumps to it, the loop is omitted.)
+pub(crate) struct FunctionCompiler<'m, 'db> {
+    module: &'m mut ModuleBuilder<'db>,
+    function: Function,
+    bir: bir::Bir,
+
+    /// JS name of each local variable.
+    names: Map<bir::LocalVariable, String>,
+
+    /// Number of jumps to each basic block.
+    predecessors: Map<bir::BasicBlock, usize>,
+
+    /// Temporaries that hold a reservation of a place (the function being
+    /// called, or the owner of a field being assigned), and that place.
+    /// Rather than storing the reservation, the compiled code reads the
+    /// reserved place where the temporary is used.
+    reserved: Map<bir::LocalVariable, bir::Place>,
+
+    output: String,
+}
+
+impl<'m, 'db> FunctionCompiler<'m, 'db> {
+    pub(crate) fn new(module: &'m mut ModuleBuilder<'db>, index: usize) -> Self {
+        let function = module.functions[index];
+        let bir = function.brew(module.db);
+        Self {
+            module,
+            function,
+            bir,
+            names: Map::default(),
+            predecessors: Map::default(),
+            reserved: Map::default(),
+            output: String::new(),
+        }
+    }
+
+    pub(crate) fn compile(mut self) -> String {
+        let db = self.module.db;
+        let bir_data = self.bir.data(db);
+        let tables = &bir_data.tables;
+
+        self.name_local_variables(bir_data);
+        for basic_block in bir_data.all_basic_blocks() {
+            for successor in successors(tables, basic_block) {
+                *self.predecessors.entry(successor).or_default() += 1;
+            }
+            for &statement in &basic_block.data(tables).statements {
+                if let StatementData::AssignExpr(target, expr) = statement.data(tables) {
+                    if let (TargetPlaceData::LocalVariable(temporary), ExprData::Reserve(place)) =
+                        (target.data(tables), expr.data(tables))
+                    {
+                        self.reserved.insert(*temporary, *place);
+                    }
+                }
+            }
+        }
+
+        let keyword = if self.function.code(db).effect.permits_await() {
+            "async function"
+        } else {
+            "function"
+        };
+        let parameters: Vec<&str> = bir_data
+            .parameters()
+            .map(|parameter| self.names[&parameter].as_str())
+            .collect();
+        let header = format!(
+            "{} {}({}) {{",
+            keyword,
+            function_name(db, self.function),
+            parameters.join(", ")
+        );
+        self.emit(1, &header);
+
+        let locals: Vec<String> = bir_data
+            .max_local_variable()
+            .iter()
+            .skip(bir_data.num_parameters())
+            .filter(|local_variable| !self.reserved.contains_key(local_variable))
+            .map(|local_variable| self.names[&local_variable].clone())
+            .collect();
+        if !locals.is_empty() {
+            self.emit(2, &format!("let {};", locals.join(", ")));
+        }
+
+        let start = bir_data.start_basic_block;
+        let cases: Vec<bir::BasicBlock> = bir_data
+            .all_basic_blocks()
+            .filter(|&bb| bb == start || self.predecessor_count(bb) > 1)
+            .collect();
+        if cases.len() == 1 && self.predecessor_count(start) == 0 {
+            self.compile_basic_block(tables, start, 2);
+        } else {
+            self.emit(2, &format!("let $bb = {};", u32::from(start)));
+            self.emit(2, "for (;;) {");
+            self.emit(3, "switch ($bb) {");
+            for basic_block in cases {
+                self.emit(4, &format!("case {}:", u32::from(basic_block)));
+                self.compile_basic_block(tables, basic_block, 5);
+            }
+            self.emit(3, "}");
+            self.emit(2, "}");
+        }
+
+        self.emit(1, "}");
+        self.output
+    }
+
+    /// Named variables keep their names (with a `$N` suffix if the name
+    /// is used more than once); temporaries are named `$tN`.
+    fn name_local_variables(&mut self, bir_data: &bir::BirData) {
+        let db = self.module.db;
+        let mut uses: Map<Word, usize> = Map::default();
+        for local_variable in bir_data.max_local_variable().iter() {
+            let name = match local_variable.data(&bir_data.tables).name {
+                Some(word) => {
+                    let count = uses.entry(word).or_default();
+                    *count += 1;
+                    if *count == 1 {
+                        identifier(word.as_str(db))
+                    } else {
+                        format!("{}${}", word.as_str(db), count)
+                    }
+                }
+                None => format!("$t{}", u32::from(local_variable)),
+            };
+            self.names.insert(local_variable, name);
+        }
+    }
+
+    fn predecessor_count(&self, basic_block: bir::BasicBlock) -> usize {
+        self.predecessors.get(&basic_block).copied().unwrap_or(0)
+    }
+
+    fn emit(&mut self, indent: usize, line: &str) {
+        writeln!(self.output, "{:width$}{}", "", line, width = indent * 2).unwrap();
+    }
+
+    fn compile_basic_block(
+        &mut self,
+        tables: &bir::Tables,
+        basic_block: bir::BasicBlock,
+        indent: usize,
+    ) {
+        let basic_block_data = basic_block.data(tables);
+        for &statement in &basic_block_data.statements {
+            self.compile_statement(tables, statement, indent);
+        }
+        self.compile_terminator(tables, basic_block_data.terminator, indent);
+    }
+
+    fn compile_statement(
+        &mut self,
+        tables: &bir::Tables,
+        statement: bir::Statement,
+        indent: usize,
+    ) {
+        match statement.data(tables) {
+            StatementData::AssignExpr(_, expr)
+                if matches!(expr.data(tables), ExprData::Reserve(_)) => {}
+            StatementData::AssignExpr(target, expr) => {
+                let target = self.target(tables, *target);
+                let expr = self.expr(tables, *expr);
+                self.emit(indent, &format!("{target} = {expr};"));
+            }
+            StatementData::AssignPlace(target, place) => {
+                let target = self.target(tables, *target);
+                let place = self.place(tables, *place);
+                self.emit(indent, &format!("{target} = {place};"));
+            }
+            StatementData::Clear(_)
+            | StatementData::BreakpointStart(..)
+            | StatementData::BreakpointEnd(..) => {}
+        }
+    }
+
+    fn compile_terminator(
+        &mut self,
+        tables: &bir::Tables,
+        terminator: bir::Terminator,
+        indent: usize,
+    ) {
+        match terminator.data(tables) {
+            TerminatorData::Goto(basic_block)
+            | TerminatorData::StartAtomic(basic_block)
+            | TerminatorData::EndAtomic(basic_block) => self.goto(tables, *basic_block, indent),
+            TerminatorData::If(condition, if_true, if_false) => {
+                let condition = self.place(tables, *condition);
+                self.emit(indent, &format!("if ($dada.bool({condition})) {{"));
+                self.goto(tables, *if_true, indent + 1);
+                self.emit(indent, "} else {");
+                self.goto(tables, *if_false, indent + 1);
+                self.emit(indent, "}");
+            }
+            TerminatorData::Return(place) => {
+                let place = self.place(tables, *place);
+                self.emit(indent, &format!("return {place};"));
+            }
+            TerminatorData::Assign(target, bir::TerminatorExpr::Await(place), next) => {
+                let target = self.target(tables, *target);
+                let place = self.place(tables, *place);
+                self.emit(indent, &format!("{target} = await $dada.await({place});"));
+                self.goto(tables, *next, indent);
+            }
+            TerminatorData::Assign(
+                target,
+                bir::TerminatorExpr::Call {
+                    function,
+                    arguments,
+                    labels,
+                },
+                next,
+            ) => {
+                let target = self.target(tables, *target);
+                let call = self.call(
+                    tables,
+                    terminator,
+                    *function,
+                    &tables[*arguments],
+                    &tables[*labels],
+                );
+                self.emit(indent, &format!("{target} = {call};"));
+                self.goto(tables, *next, indent);
+            }
+            TerminatorData::Error => self.emit(indent, "$dada.compilationError();"),
+            TerminatorData::Panic => self.emit(indent, "$dada.panic();"),
+        }
+    }
+
+    fn goto(&mut self, tables: &bir::Tables, basic_block: bir::BasicBlock, indent: usize) {
+        let start = self.bir.data(self.module.db).start_basic_block;
+        if basic_block != start && self.predecessor_count(basic_block) == 1 {
+            self.compile_basic_block(tables, basic_block, indent);
+        } else {
+            self.emit(indent, &format!("$bb = {};", u32::from(basic_block)));
+            self.emit(indent, "continue;");
+        }
+    }
+
+    fn call(
+        &mut self,
+        tables: &bir::Tables,
+        terminator: bir::Terminator,
+        callee: bir::Place,
+        arguments: &[bir::Place],
+        labels: &[SpannedOptionalWord],
+    ) -> String {
+        let db = self.module.db;
+        let arguments: Vec<String> = arguments
+            .iter()
+            .map(|argument| self.place(tables, *argument))
+            .collect();
+        let arguments = arguments.join(", ");
+        let callee = self.reserved_place(tables, callee);
+        match callee.data(tables) {
+            PlaceData::Function(function) => {
+                if !self.module.functions.contains(function) {
+                    return self.unsupported(callee, "calling functions from other files");
+                }
+                let names: Vec<Word> = function.parameters(db).iter().map(|p| p.name(db)).collect();
+                self.check_labels(terminator, labels, &names);
+                let name = function_name(db, *function);
+                if function.code(db).effect.permits_await() {
+                    // Calling an `async fn` only creates a thunk; the
+                    // function runs when the thunk is awaited.
+                    format!(
+                        "new $dada.Thunk({:?}, {}, [{}])",
+                        function.name(db).as_str(db),
+                        name,
+                        arguments
+                    )
+                } else {
+                    format!("{name}({arguments})")
+                }
+            }
+            PlaceData::Class(class) => {
+                if !self.module.classes.contains(class) {
+                    return self.unsupported(callee, "classes from other files");
+                }
+                let names: Vec<Word> = class.fields(db).iter().map(|p| p.name(db)).collect();
+                self.check_labels(terminator, labels, &names);
+                format!("new {}({})", class_name(db, *class), arguments)
+            }
+            PlaceData::Intrinsic(intrinsic) if matches!(intrinsic.as_str(db), "print" | "big") => {
+                let name = match intrinsic.as_str(db) {
+                    "print" => "message",
+                    _ => "value",
+                };
+                self.check_labels(terminator, labels, &[Word::from(db, name)]);
+                format!("$dada.{}({})", intrinsic.as_str(db), arguments)
+            }
+            PlaceData::Intrinsic(intrinsic) => {
+                let what = format!("the `{}` intrinsic", intrinsic.as_str(db));
+                self.unsupported(callee, &what)
+            }
+            PlaceData::HostFunction(name) => {
+                let what = format!("the host function `{}`", name.as_str(db));
+                self.unsupported(callee, &what)
+            }
+            PlaceData::ExternFunction(function) => {
+                let what = format!("the extern function `{}`", function.name(db).as_str(db));
+                self.unsupported(callee, &what)
+            }
+            PlaceData::LocalVariable(_) | PlaceData::Dot(..) => {
+                self.unsupported(callee, "calling a function stored in a variable")
+            }
+        }
+    }
+
+    /// Reports the same errors the interpreter would for arguments
+    /// that do not match the parameters being called.
+    fn check_labels(
+        &mut self,
+        terminator: bir::Terminator,
+        labels: &[SpannedOptionalWord],
+        expected_names: &[Word],
+    ) {
+        let db = self.module.db;
+        for (label, expected_name) in labels.iter().zip(expected_names) {
+            if let Some(actual_word) = label.word(db) {
+                if *expected_name != actual_word {
+                    let diagnostic = error!(
+                        ArgumentNameMismatch,
+                        label.span(db),
+                        "expected to find an argument named `{}`, but found the name `{}`",
+                        expected_name.as_str(db),
+                        actual_word.as_str(db),
+                    )
+                    .finish();
+                    self.module.diagnostics.push(diagnostic);
+                }
+            }
+        }
+
+        if labels.len() != expected_names.len() {
+            let diagnostic = error!(
+                ArgumentCountMismatch,
+                self.span(terminator),
+                "expected to find {} arguments, but found {}",
+                expected_names.len(),
+                labels.len(),
+            )
+            .finish();
+            self.module.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn expr(&mut self, tables: &bir::Tables, expr: bir::Expr) -> String {
+        match expr.data(tables) {
+            ExprData::BooleanLiteral(value) => value.to_string(),
+            ExprData::IntegerLiteral(value) => format!("{value}n"),
+            ExprData::UnsignedIntegerLiteral(value) => format!("new $dada.UnsignedInt({value}n)"),
+            ExprData::SignedIntegerLiteral(value) => format!("new $dada.SignedInt({value}n)"),
+            ExprData::FloatLiteral(value) => format!("{:?}", value.0),
+            ExprData::StringLiteral(word) => string_literal(word.as_str(self.module.db)),
+            ExprData::Unit => "$dada.unit".to_string(),
+
+            // Objects are shared by reference, so giving, sharing, and
+            // leasing a place all just read it.
+            ExprData::Reserve(place)
+            | ExprData::Share(place)
+            | ExprData::Lease(place)
+            | ExprData::Shlease(place)
+            | ExprData::Give(place) => self.place(tables, *place),
+
+            ExprData::Op(lhs, op, rhs) => {
+                let lhs = self.place(tables, *lhs);
+                let rhs = self.place(tables, *rhs);
+                format!("$dada.{}({}, {})", op_function(*op), lhs, rhs)
+            }
+            ExprData::Unary(Op::Minus, rhs) => {
+                let rhs = self.place(tables, *rhs);
+                format!("$dada.neg({rhs})")
+            }
+            ExprData::Unary(..) => self.unsupported(expr, "this operator"),
+            ExprData::Tuple(places) => {
+                let fields: Vec<String> = tables[*places]
+                    .iter()
+                    .map(|place| self.place(tables, *place))
+                    .collect();
+                format!("new $dada.Tuple([{}])", fields.join(", "))
+            }
+            ExprData::Error => "$dada.compilationError()".to_string(),
+        }
+    }
+
+    /// Returns the JS expression for the value stored in `place`.
+    fn place(&mut self, tables: &bir::Tables, place: bir::Place) -> String {
+        let db = self.module.db;
+        match place.data(tables) {
+            PlaceData::LocalVariable(local_variable) => match self.reserved.get(local_variable) {
+                Some(&reserved) => self.place(tables, reserved),
+                None => self.names[local_variable].clone(),
+            },
+            PlaceData::Dot(owner, field) => {
+                let owner = self.place(tables, *owner);
+                format!("{}.{}", owner, field.as_str(db))
+            }
+            PlaceData::Class(_) => self.unsupported(place, "classes as values"),
+            PlaceData::Function(_)
+            | PlaceData::ExternFunction(_)
+            | PlaceData::Intrinsic(_)
+            | PlaceData::HostFunction(_) => self.unsupported(place, "functions as values"),
+        }
+    }
+
+    /// If `place` is a temporary that holds a reservation, the reserved
+    /// place; otherwise `place`.
+    fn reserved_place(&self, tables: &bir::Tables, place: bir::Place) -> bir::Place {
+        match place.data(tables) {
+            PlaceData::LocalVariable(local_variable) => self
+                .reserved
+                .get(local_variable)
+                .map_or(place, |&reserved| self.reserved_place(tables, reserved)),
+            _ => place,
+        }
+    }
+
+    /// Returns the JS expression to assign to for `target`.
+    fn target(&mut self, tables: &bir::Tables, target: bir::TargetPlace) -> String {
+        let db = self.module.db;
+        match target.data(tables) {
+            TargetPlaceData::LocalVariable(local_variable) => self.names[local_variable].clone(),
+            TargetPlaceData::Dot(owner, field) => {
+                let owner = self.place(tables, *owner);
+                format!("{}.{}", owner, field.as_str(db))
+            }
+        }
+    }
+
+    /// Reports that `what` is not supported by this backend and returns
+    /// an expression to use in its place.
+    fn unsupported(
+        &mut self,
+        origin: impl HasOriginIn<bir::Origins, Origin = syntax::Expr>,
+        what: &str,
+    ) -> String {
+        let diagnostic = error!(
+            UnsupportedByBackend,
+            self.span(origin),
+            "the JavaScript backend does not yet support {}",
+            what
+        )
+        .finish();
+        self.module.diagnostics.push(diagnostic);
+        "undefined".to_string()
+    }
+
+    fn span(
+        &self,
+        origin: impl HasOriginIn<bir::Origins, Origin = syntax::Expr>,
+    ) -> dada_ir::span::FileSpan {
+        let db = self.module.db;
+        let syntax_expr = self.bir.origins(db)[origin];
+        let syntax_tree = self.function.syntax_tree(db);
+        syntax_tree.file_span(db, syntax_tree.spans(db)[syntax_expr])
+    }
+}
+
+/// The basic blocks that `basic_block` may jump to.
+fn successors(tables: &bir::Tables, basic_block: bir::BasicBlock) -> Vec<bir::BasicBlock> {
+    match basic_block.data(tables).terminator.data(tables) {
+        TerminatorData::Goto(next)
+        | TerminatorData::StartAtomic(next)
+        | TerminatorData::EndAtomic(next)
+        | TerminatorData::Assign(_, _, next) => vec![*next],
+        TerminatorData::If(_, if_true, if_false) => vec![*if_true, *if_false],
+        TerminatorData::Return(_) | TerminatorData::Error | TerminatorData::Panic => vec![],
+    }
+}
+
+/// The runtime function that implements `op`.
+fn op_function(op: Op) -> &'static str {
+    match op {
+        Op::EqualEqual => "eq",
+        Op::GreaterEqual => "ge",
+        Op::LessEqual => "le",
+        Op::Plus => "add",
+        Op::Minus => "sub",
+        Op::Times => "mul",
+        Op::DividedBy => "div",
+        Op::LessThan => "lt",
+        Op::GreaterThan => "gt",
+    }
+}
+
+/// Quotes `s` as a JS string literal.
+fn string_literal(s: &str) -> String {
+    let mut output = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => write!(output, "\\u{:04x}", c as u32).unwrap(),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
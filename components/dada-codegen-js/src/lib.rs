@@ -0,0 +1,44 @@
+//! Transpiles a dada program into JavaScript, so that it can run directly
+//! in a browser (or node) without shipping the interpreter.
+//!
+//! Each dada class becomes a JS class, and each function becomes a JS
+//! function (an `async function` for `async fn`s). Function bodies are
+//! compiled from BIR: basic blocks that can only be reached from one place
+//! are emitted inline (so `if`/`else` comes out as `if`/`else`), and the
+//! rest become the cases of a `switch` inside a loop. The generated code
+//! calls into a small runtime (`runtime.js`, included in the output) that
+//! implements dada's operators with the same overflow checks and errors
+//! as the interpreter. Once loaded, the script runs `main`; output goes to
+//! `console.log`, or to `globalThis.dadaPrint` if the host defines it.
+//!
+//! Values are not tracked by permission: objects are shared by reference,
+//! as in JS. This does not change the behavior of programs that run
+//! without errors in the interpreter, but programs that the interpreter
+//! would stop with a permission error (e.g., using a value after giving
+//! it away) keep running. For the same reason, printed values do not
+//! include their permission. Programs that use the `weak` and `upgrade`
+//! intrinsics or call functions stored in variables are rejected with a
+//! diagnostic.
+
+#![feature(let_else)]
+#![feature(trait_upcasting)]
+#![allow(incomplete_features)]
+
+use dada_ir::{diagnostic::Diagnostic, filename::Filename};
+
+mod function;
+mod module;
+
+pub trait Db: dada_brew::Db + dada_ir::Db + dada_parse::Db {}
+
+impl<T> Db for T where T: dada_brew::Db + dada_ir::Db + dada_parse::Db {}
+
+/// The runtime that each script starts with.
+pub const RUNTIME: &str = include_str!("runtime.js");
+
+/// Compiles the classes and functions in `filename` into a JavaScript
+/// script. Returns the diagnostics for any unsupported constructs
+/// if the program cannot be compiled.
+pub fn compile_module(db: &dyn Db, filename: Filename) -> Result<String, Vec<Diagnostic>> {
+    module::ModuleBuilder::new(db, filename).build()
+}
@@ -0,0 +1,174 @@
+use std::fmt::Write;
+
+use dada_ir::{
+    class::Class, diagnostic::Diagnostic, filename::Filename, function::Function, item::Item,
+};
+use dada_parse::prelude::*;
+
+use crate::{function::FunctionCompiler, RUNTIME};
+
+pub(crate) struct ModuleBuilder<'db> {
+    pub(crate) db: &'db dyn crate::Db,
+    filename: Filename,
+
+    /// The classes in the file.
+    pub(crate) classes: Vec<Class>,
+
+    /// The functions in the file.
+    pub(crate) functions: Vec<Function>,
+
+    /// Errors for constructs the backend does not support.
+    pub(crate) diagnostics: Vec<Diagnostic>,
+}
+
+impl<'db> ModuleBuilder<'db> {
+    pub(crate) fn new(db: &'db dyn crate::Db, filename: Filename) -> Self {
+        let mut classes = vec![];
+        let mut functions = vec![];
+        for item in filename.items(db) {
+            match item {
+                Item::Function(function) => functions.push(*function),
+                Item::Class(class) => classes.push(*class),
+                // Calls to these are reported as unsupported.
+                Item::ExternFunction(_) => {}
+            }
+        }
+        Self {
+            db,
+            filename,
+            classes,
+            functions,
+            diagnostics: vec![],
+        }
+    }
+
+    pub(crate) fn build(mut self) -> Result<String, Vec<Diagnostic>> {
+        let db = self.db;
+
+        let mut output = String::new();
+        writeln!(output, "// compiled from `{}`", self.filename.as_str(db)).unwrap();
+        writeln!(output, "\"use strict\";").unwrap();
+        writeln!(output).unwrap();
+        output.push_str(RUNTIME);
+        writeln!(output).unwrap();
+
+        // The program goes in its own block, so that its names cannot
+        // shadow the globals that the runtime uses.
+        writeln!(output, "{{").unwrap();
+        for &class in &self.classes {
+            output.push_str(&self.compile_class(class));
+            writeln!(output).unwrap();
+        }
+        for index in 0..self.functions.len() {
+            output.push_str(&FunctionCompiler::new(&mut self, index).compile());
+            writeln!(output).unwrap();
+        }
+        let main = self
+            .functions
+            .iter()
+            .find(|f| f.name(db).as_str(db) == "main");
+        if let Some(&main) = main {
+            writeln!(output, "  $dada.main({});", function_name(db, main)).unwrap();
+        }
+        writeln!(output, "}}").unwrap();
+
+        if !self.diagnostics.is_empty() {
+            return Err(self.diagnostics);
+        }
+        Ok(output)
+    }
+
+    /// A class becomes a JS class whose constructor takes the fields
+    /// in order.
+    fn compile_class(&self, class: Class) -> String {
+        let db = self.db;
+        let fields: Vec<&str> = class
+            .fields(db)
+            .iter()
+            .map(|field| field.name(db).as_str(db))
+            .collect();
+        let parameters: Vec<String> = fields.iter().map(|field| identifier(field)).collect();
+
+        let mut output = String::new();
+        writeln!(output, "  class {} {{", class_name(db, class)).unwrap();
+        writeln!(output, "    constructor({}) {{", parameters.join(", ")).unwrap();
+        for (field, parameter) in fields.iter().zip(&parameters) {
+            writeln!(output, "      this.{} = {};", field, parameter).unwrap();
+        }
+        writeln!(output, "    }}").unwrap();
+        writeln!(output, "  }}").unwrap();
+        output
+    }
+}
+
+/// The JS name for `function`.
+pub(crate) fn function_name(db: &dyn crate::Db, function: Function) -> String {
+    identifier(function.name(db).as_str(db))
+}
+
+/// The JS name for `class`.
+pub(crate) fn class_name(db: &dyn crate::Db, class: Class) -> String {
+    identifier(class.name(db).as_str(db))
+}
+
+/// Converts a dada identifier into a JS identifier, appending `_` to
+/// names that are reserved in JS. (Dada identifiers never contain `$`,
+/// so names starting with `$` are free for the compiler and runtime.)
+pub(crate) fn identifier(name: &str) -> String {
+    const RESERVED: &[&str] = &[
+        "arguments",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "debugger",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "enum",
+        "eval",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "function",
+        "if",
+        "implements",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "null",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typeof",
+        "undefined",
+        "var",
+        "void",
+        "while",
+        "with",
+        "yield",
+    ];
+    if RESERVED.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_string()
+    }
+}
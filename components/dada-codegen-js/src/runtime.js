@@ -0,0 +1,272 @@
+// The runtime that compiled dada programs call into.
+//
+// Values are represented as follows:
+// * integers are BigInts; unsigned (`_u`) and signed (`_i`) integers and
+//   big integers (from `big`) are wrapped in `UnsignedInt`, `SignedInt`,
+//   and `BigInteger` respectively;
+// * floats, booleans, and strings are JS numbers, booleans, and strings;
+// * `()` is `$dada.unit`, and tuples are `Tuple` objects;
+// * instances of dada classes are instances of the corresponding JS class;
+// * calling an async fn (or `print`) produces a `Thunk`, which does
+//   nothing until it is awaited.
+//
+// Dynamic errors throw a `DadaError` with the interpreter's message.
+const $dada = (() => {
+  "use strict";
+
+  const U64_MAX = (1n << 64n) - 1n;
+  const I64_MIN = -(1n << 63n);
+  const I64_MAX = (1n << 63n) - 1n;
+
+  class DadaError extends Error {}
+
+  class UnsignedInt {
+    constructor(value) {
+      this.value = value;
+    }
+  }
+
+  class SignedInt {
+    constructor(value) {
+      this.value = value;
+    }
+  }
+
+  class BigInteger {
+    constructor(value) {
+      this.value = value;
+    }
+  }
+
+  class Tuple {
+    constructor(fields) {
+      this.fields = fields;
+    }
+  }
+
+  class Thunk {
+    constructor(name, fn, args) {
+      this.name = name;
+      this.fn = fn;
+      this.args = args;
+    }
+  }
+
+  const unit = Object.freeze({});
+
+  function kindStr(value) {
+    switch (typeof value) {
+      case "bigint":
+        return "an integer";
+      case "number":
+        return "a float";
+      case "boolean":
+        return "a boolean";
+      case "string":
+        return "a string";
+    }
+    if (value === unit) return "nothing";
+    if (value instanceof UnsignedInt) return "an unsigned integer";
+    if (value instanceof SignedInt) return "a signed integer";
+    if (value instanceof BigInteger) return "a big integer";
+    if (value instanceof Tuple) return "a tuple";
+    if (value instanceof Thunk) return `a suspended call to \`${value.name}\``;
+    return `an instance of \`${value.constructor.name}\``;
+  }
+
+  // Formats a value the way the interpreter prints it (except that,
+  // since permissions are not tracked, they are not included).
+  function stringify(value) {
+    switch (typeof value) {
+      case "bigint":
+      case "number":
+      case "boolean":
+        return String(value);
+      case "string":
+        return value;
+    }
+    if (value === unit) return "()";
+    if (value instanceof UnsignedInt) return `${value.value}_u`;
+    if (value instanceof SignedInt) return `${value.value}_i`;
+    if (value instanceof BigInteger) return String(value.value);
+    if (value instanceof Tuple) return `(${value.fields.map(stringify).join(", ")})`;
+    if (value instanceof Thunk) {
+      return `${value.name}(${value.args.map(stringify).join(", ")})`;
+    }
+    return `${value.constructor.name}(${Object.values(value).map(stringify).join(", ")})`;
+  }
+
+  // Classifies an integer operand: returns its kind and BigInt value,
+  // or `undefined` if `value` is not an integer.
+  function integer(value) {
+    if (typeof value === "bigint") return { kind: "int", value };
+    if (value instanceof UnsignedInt) return { kind: "unsigned", value: value.value };
+    if (value instanceof SignedInt) return { kind: "signed", value: value.value };
+    if (value instanceof BigInteger) return { kind: "big", value: value.value };
+    return undefined;
+  }
+
+  function opError(op, lhs, rhs) {
+    return new DadaError(`cannot apply operator ${op} to ${kindStr(lhs)} and ${kindStr(rhs)}`);
+  }
+
+  // Applies an arithmetic operator, following the interpreter's rules
+  // for combining different kinds of integers.
+  function arithmetic(op, lhs, rhs, apply) {
+    if (typeof lhs === "number" && typeof rhs === "number") return apply(lhs, rhs);
+
+    const l = integer(lhs);
+    const r = integer(rhs);
+    if (l === undefined || r === undefined) throw opError(op, lhs, rhs);
+
+    if (l.kind === "big" || r.kind === "big") {
+      if (op === "/" && r.value === 0n) throw new DadaError("divide by zero");
+      return new BigInteger(apply(l.value, r.value));
+    }
+
+    let kind;
+    if (l.kind === r.kind || r.kind === "int") {
+      kind = l.kind;
+    } else if (l.kind === "int") {
+      kind = r.kind;
+    } else {
+      throw opError(op, lhs, rhs);
+    }
+
+    if (kind === "signed") {
+      for (const operand of [l, r]) {
+        if (operand.value > I64_MAX) throw new DadaError("overflow");
+      }
+    }
+    if (op === "/" && r.value === 0n) throw new DadaError("divide by zero");
+
+    const result = apply(l.value, r.value);
+    const [min, max] = kind === "signed" ? [I64_MIN, I64_MAX] : [0n, U64_MAX];
+    if (result < min) throw new DadaError("underflow");
+    if (result > max) throw new DadaError("overflow");
+
+    switch (kind) {
+      case "int":
+        return result;
+      case "unsigned":
+        return new UnsignedInt(result);
+      case "signed":
+        return new SignedInt(result);
+    }
+  }
+
+  // Applies a comparison operator. Booleans, strings, and `()` only
+  // support `==`, `>=`, and `<=`.
+  function compare(op, lhs, rhs, apply) {
+    const equality = op === "==" || op === ">=" || op === "<=";
+    if (lhs === unit && rhs === unit && equality) return true;
+    for (const type of ["boolean", "string"]) {
+      if (typeof lhs === type && typeof rhs === type && equality) return apply(lhs, rhs);
+    }
+    if (typeof lhs === "number" && typeof rhs === "number") return apply(lhs, rhs);
+
+    const l = integer(lhs);
+    const r = integer(rhs);
+    if (l === undefined || r === undefined) throw opError(op, lhs, rhs);
+    const compatible =
+      l.kind === r.kind || l.kind === "int" || r.kind === "int" || l.kind === "big" || r.kind === "big";
+    if (!compatible) throw opError(op, lhs, rhs);
+    if (l.kind !== "big" && r.kind !== "big" && (l.kind === "signed" || r.kind === "signed")) {
+      for (const operand of [l, r]) {
+        if (operand.value > I64_MAX) throw new DadaError("overflow");
+      }
+    }
+    return apply(l.value, r.value);
+  }
+
+  // Writes a line of output. Hosts can redirect the output by defining
+  // `globalThis.dadaPrint` before the program is loaded.
+  function output(text) {
+    (globalThis.dadaPrint ?? console.log)(text);
+  }
+
+  return {
+    DadaError,
+    UnsignedInt,
+    SignedInt,
+    BigInteger,
+    Tuple,
+    Thunk,
+    unit,
+    stringify,
+
+    add: (lhs, rhs) => arithmetic("+", lhs, rhs, (a, b) => a + b),
+    sub: (lhs, rhs) => arithmetic("-", lhs, rhs, (a, b) => a - b),
+    mul: (lhs, rhs) => arithmetic("*", lhs, rhs, (a, b) => a * b),
+    div: (lhs, rhs) => arithmetic("/", lhs, rhs, (a, b) => a / b),
+    eq: (lhs, rhs) => compare("==", lhs, rhs, (a, b) => a === b),
+    ge: (lhs, rhs) => compare(">=", lhs, rhs, (a, b) => a >= b),
+    le: (lhs, rhs) => compare("<=", lhs, rhs, (a, b) => a <= b),
+    lt: (lhs, rhs) => compare("<", lhs, rhs, (a, b) => a < b),
+    gt: (lhs, rhs) => compare(">", lhs, rhs, (a, b) => a > b),
+
+    neg(value) {
+      if (typeof value === "number") return -value;
+      const v = integer(value);
+      if (v === undefined || v.kind === "unsigned") {
+        throw new DadaError(`cannot apply operator - to ${kindStr(value)}`);
+      }
+      if (v.kind === "big") return new BigInteger(-v.value);
+      if (v.value > I64_MAX || -v.value > I64_MAX) throw new DadaError("overflow");
+      return new SignedInt(-v.value);
+    },
+
+    // The condition of an `if` or `while` must be a boolean.
+    bool(value) {
+      if (typeof value !== "boolean") {
+        throw new DadaError(`expected a boolean, found ${kindStr(value)}`);
+      }
+      return value;
+    },
+
+    async await(thunk) {
+      if (!(thunk instanceof Thunk)) {
+        throw new DadaError(`expected a thunk, found ${kindStr(thunk)}`);
+      }
+      return await thunk.fn(...thunk.args);
+    },
+
+    print(message) {
+      return new Thunk("print", (message) => {
+        output(stringify(message));
+        return unit;
+      }, [message]);
+    },
+
+    big(value) {
+      const v = integer(value);
+      if (v !== undefined) return new BigInteger(v.value);
+      if (typeof value === "string" && /^-?[0-9]+$/.test(value)) {
+        return new BigInteger(BigInt(value));
+      }
+      const found = typeof value === "string" ? `the string \`${value}\`` : kindStr(value);
+      throw new DadaError(`expected an integer or a string of digits, found ${found}`);
+    },
+
+    panic() {
+      throw new DadaError("panic! omg! 😱");
+    },
+
+    compilationError() {
+      throw new DadaError("compilation error encountered 😢");
+    },
+
+    // Runs the program's `main` function, printing its result (unless
+    // it is `()`) like the interpreter does. A `main` that takes a
+    // parameter gets the program's arguments, of which there are none.
+    async main(main) {
+      try {
+        const result = await (main.length > 0 ? main(new Tuple([])) : main());
+        if (result !== unit) output(stringify(result));
+      } catch (error) {
+        if (!(error instanceof DadaError)) throw error;
+        console.error(`error: ${error.message}`);
+      }
+    },
+  };
+})();
@@ -0,0 +1,608 @@
+use std::fmt::Write;
+
+use dada_brew::prelude::*;
+use dada_collections::Map;
+use dada_id::prelude::*;
+use dada_ir::{
+    code::{
+        bir::{self, ExprData, PlaceData, StatementData, TargetPlaceData, TerminatorData},
+        syntax,
+        validated::op::Op,
+    },
+    error,
+    function::Function,
+    origin_table::HasOriginIn,
+    storage::Specifier,
+    word::{SpannedOptionalWord, Word},
+};
+use dada_parse::prelude::*;
+
+use crate::{
+    module::{function_id, specifier_code, ModuleBuilder},
+    tag,
+};
+
+/// Compiles the BIR for one function into the body of a wasm function
+/// with the signature `$dada_fn`: it takes the address of a block holding
+/// its arguments (16 bytes each: the tag at offset 0, the payload at
+/// offset 8) and returns a (tag, payload) pair.
+///
+/// Each BIR local variable becomes a pair of wasm locals, `$tN` and `$pN`.
+/// Basic blocks are compiled into a `loop` that dispatches on `$bb`.
+pub(crate) struct FunctionCompiler<'m, 'db> {
+    module: &'m mut ModuleBuilder<'db>,
+    function: Function,
+    bir: bir::Bir,
+    output: String,
+
+    /// Temporaries that hold a reservation of a place (the function being
+    /// called, or the owner of a field being assigned), and that place. Rather than creating the
+    /// reservation, the compiled code traverses the reserved place again
+    /// where the interpreter would confirm the reservation.
+    reserved: Map<bir::LocalVariable, bir::Place>,
+}
+
+impl<'m, 'db> FunctionCompiler<'m, 'db> {
+    pub(crate) fn new(module: &'m mut ModuleBuilder<'db>, index: usize) -> Self {
+        let function = module.functions[index];
+        let bir = function.brew(module.db);
+        Self {
+            module,
+            function,
+            bir,
+            output: String::new(),
+            reserved: Map::default(),
+        }
+    }
+
+    pub(crate) fn compile(mut self) -> String {
+        let db = self.module.db;
+        let bir_data = self.bir.data(db);
+        let tables = &bir_data.tables;
+
+        writeln!(
+            self.output,
+            "  (func {} (type $dada_fn) (param $args i32) (result i32 i64)",
+            function_id(db, self.function)
+        )
+        .unwrap();
+        self.emit("(local $bb i32)");
+        self.emit("(local $ptr i32)");
+        self.emit("(local $thunk i32)");
+        self.emit("(local $specifier i32)");
+        self.emit("(local $vt i32) (local $vp i64)");
+        for local_variable in bir_data.max_local_variable().iter() {
+            let n = u32::from(local_variable);
+            self.emit(&format!("(local $t{n} i32) (local $p{n} i64)"));
+        }
+
+        // Load the parameters from the argument block; other locals start
+        // out as zero, which is `()`.
+        for (parameter, index) in bir_data.parameters().zip(0..) {
+            let n = u32::from(parameter);
+            self.emit("local.get $args");
+            self.emit(&format!("i32.load offset={}", index * 16));
+            self.emit(&format!("local.set $t{n}"));
+            self.emit("local.get $args");
+            self.emit(&format!("i64.load offset={}", index * 16 + 8));
+            self.emit(&format!("local.set $p{n}"));
+        }
+
+        for basic_block in bir_data.all_basic_blocks() {
+            for &statement in &basic_block.data(tables).statements {
+                if let StatementData::AssignExpr(target, expr) = statement.data(tables) {
+                    if let (TargetPlaceData::LocalVariable(temporary), ExprData::Reserve(place)) =
+                        (target.data(tables), expr.data(tables))
+                    {
+                        self.reserved.insert(*temporary, *place);
+                    }
+                }
+            }
+        }
+
+        self.emit(&format!(
+            "i32.const {}",
+            u32::from(bir_data.start_basic_block)
+        ));
+        self.emit("local.set $bb");
+
+        // Dispatch loop: block `$bN` is closed just before the code
+        // for basic block N, so `br_table` jumps straight to it.
+        let basic_blocks: Vec<bir::BasicBlock> = bir_data.all_basic_blocks().collect();
+        self.emit("loop $dispatch");
+        for basic_block in basic_blocks.iter().rev() {
+            self.emit(&format!("block $b{}", u32::from(*basic_block)));
+        }
+        self.emit("local.get $bb");
+        let labels: Vec<String> = basic_blocks
+            .iter()
+            .map(|bb| format!("$b{}", u32::from(*bb)))
+            .collect();
+        self.emit(&format!("br_table {}", labels.join(" ")));
+        for &basic_block in &basic_blocks {
+            self.emit("end");
+            self.emit(&format!(";; {:?}", basic_block));
+            let basic_block_data = basic_block.data(tables);
+            for &statement in &basic_block_data.statements {
+                self.compile_statement(tables, statement);
+            }
+            self.compile_terminator(tables, basic_block_data.terminator);
+        }
+        self.emit("end");
+        self.emit("unreachable)");
+        self.output.push('\n');
+        self.output
+    }
+
+    fn emit(&mut self, instruction: &str) {
+        writeln!(self.output, "    {}", instruction).unwrap();
+    }
+
+    fn compile_statement(&mut self, tables: &bir::Tables, statement: bir::Statement) {
+        match statement.data(tables) {
+            StatementData::AssignExpr(_, expr)
+                if matches!(expr.data(tables), ExprData::Reserve(_)) =>
+            {
+                // The reserved place is traversed again where the temporary
+                // is used, but traverse it now too, so that it traps if the
+                // place is not valid. (A reserved function or class is only
+                // ever called, which is compiled where the call is.)
+                let ExprData::Reserve(place) = expr.data(tables) else {
+                    unreachable!()
+                };
+                if matches!(place.data(tables), PlaceData::LocalVariable(_) | PlaceData::Dot(..)) {
+                    self.push_place(tables, *place);
+                    self.emit("drop");
+                    self.emit("drop");
+                }
+            }
+            StatementData::AssignExpr(target, expr) => {
+                self.compile_expr(tables, *expr);
+                self.store_target(tables, *target);
+            }
+            StatementData::AssignPlace(target, place) => match target.data(tables) {
+                TargetPlaceData::LocalVariable(local_variable) => {
+                    let specifier = tables[*local_variable]
+                        .specifier
+                        .map(|specifier| specifier.specifier(self.module.db));
+                    self.push_place(tables, *place);
+                    self.emit(&format!("i32.const {}", specifier_code(specifier)));
+                    self.emit("call $prepare");
+                    self.store_target(tables, *target);
+                }
+                TargetPlaceData::Dot(owner, name) => {
+                    // As in the interpreter, the value is prepared according
+                    // to the specifier of the field being assigned, and the
+                    // owner's tenants are only revoked when it is stored.
+                    let name = self.module.field_name(*name);
+                    self.push_place(tables, *owner);
+                    self.emit(&format!("i32.const {name}"));
+                    self.emit("call $assign_field");
+                    self.emit("local.set $specifier");
+                    self.emit("local.set $ptr");
+                    self.push_place(tables, *place);
+                    self.emit("local.get $specifier");
+                    self.emit("call $prepare");
+                    self.emit("local.set $vp");
+                    self.emit("local.set $vt");
+                    self.push_place(tables, *owner);
+                    self.emit("drop");
+                    self.emit("drop");
+                    self.emit("call $write_place");
+                    self.emit("local.get $ptr");
+                    self.emit("local.get $vt");
+                    self.emit("local.get $vp");
+                    self.emit("call $store");
+                }
+            },
+            StatementData::Clear(local_variable) => {
+                let n = u32::from(*local_variable);
+                self.drop_local(n);
+                self.emit(&format!("i32.const {}", tag::UNIT));
+                self.emit(&format!("local.set $t{n}"));
+                self.emit("i64.const 0");
+                self.emit(&format!("local.set $p{n}"));
+            }
+            StatementData::BreakpointStart(..) | StatementData::BreakpointEnd(..) => {}
+        }
+    }
+
+    fn compile_terminator(&mut self, tables: &bir::Tables, terminator: bir::Terminator) {
+        match terminator.data(tables) {
+            TerminatorData::Goto(basic_block)
+            | TerminatorData::StartAtomic(basic_block)
+            | TerminatorData::EndAtomic(basic_block) => self.goto(*basic_block),
+            TerminatorData::If(condition, if_true, if_false) => {
+                self.push_place(tables, *condition);
+                self.emit("call $truthy");
+                self.emit("if");
+                self.goto(*if_true);
+                self.emit("else");
+                self.goto(*if_false);
+                self.emit("end");
+            }
+            TerminatorData::Return(place) => {
+                self.push_place(tables, *place);
+                self.emit("call $give");
+                // The interpreter collects garbage when a function returns;
+                // drop the values in its local variables instead.
+                for local_variable in self.bir.data(self.module.db).max_local_variable().iter() {
+                    self.drop_local(u32::from(local_variable));
+                }
+                self.emit("return");
+            }
+            TerminatorData::Assign(target, bir::TerminatorExpr::Await(place), next) => {
+                self.push_place(tables, *place);
+                self.emit("call $give");
+                self.emit("call $await");
+                self.store_target(tables, *target);
+                self.goto(*next);
+            }
+            TerminatorData::Assign(
+                target,
+                bir::TerminatorExpr::Call {
+                    function,
+                    arguments,
+                    labels,
+                },
+                next,
+            ) => {
+                self.compile_call(
+                    tables,
+                    terminator,
+                    *function,
+                    &tables[*arguments],
+                    &tables[*labels],
+                );
+                self.store_target(tables, *target);
+                self.goto(*next);
+            }
+            TerminatorData::Error | TerminatorData::Panic => self.emit("unreachable"),
+        }
+    }
+
+    fn compile_call(
+        &mut self,
+        tables: &bir::Tables,
+        terminator: bir::Terminator,
+        callee: bir::Place,
+        arguments: &[bir::Place],
+        labels: &[SpannedOptionalWord],
+    ) {
+        let db = self.module.db;
+        let callee = self.reserved_place(tables, callee);
+        match callee.data(tables) {
+            PlaceData::Function(function) => {
+                let Some(index) = self.module.functions.iter().position(|f| f == function) else {
+                    self.unsupported(callee, "calling functions from other files");
+                    return;
+                };
+                let parameters = function.parameters(db);
+                let names: Vec<Word> = parameters.iter().map(|p| p.name(db)).collect();
+                self.check_labels(terminator, labels, &names);
+                let specifiers: Vec<Specifier> = parameters
+                    .iter()
+                    .map(|p| p.decl(db).specifier.specifier(db))
+                    .collect();
+                self.store_arguments(tables, arguments, &specifiers);
+
+                if function.code(db).effect.permits_await() {
+                    // Calling an `async fn` only creates a thunk, which
+                    // records the function and its arguments.
+                    self.emit("i32.const 8");
+                    self.emit("call $alloc");
+                    self.emit("local.set $thunk");
+                    self.emit("local.get $thunk");
+                    self.emit(&format!("i32.const {index}"));
+                    self.emit("i32.store");
+                    self.emit("local.get $thunk");
+                    self.emit("local.get $ptr");
+                    self.emit("i32.store offset=4");
+                    self.emit(&format!("i32.const {}", tag::FUNCTION_THUNK));
+                    self.emit("local.get $thunk");
+                    self.emit("i64.extend_i32_u");
+                } else {
+                    self.emit("local.get $ptr");
+                    self.emit(&format!("call {}", function_id(db, *function)));
+                }
+            }
+            PlaceData::Intrinsic(intrinsic) if intrinsic.as_str(db) == "print" => {
+                self.check_labels(terminator, labels, &[Word::from(db, "message")]);
+                self.store_arguments(tables, arguments, &[Specifier::Any]);
+                self.emit(&format!("i32.const {}", tag::PRINT_THUNK));
+                self.emit("local.get $ptr");
+                self.emit("i64.extend_i32_u");
+            }
+            PlaceData::Intrinsic(intrinsic) => {
+                let what = format!("the `{}` intrinsic", intrinsic.as_str(db));
+                self.unsupported(callee, &what);
+            }
+            PlaceData::Class(class) => {
+                let Some(index) = self.module.classes.iter().position(|c| c == class) else {
+                    self.unsupported(callee, "classes from other files");
+                    return;
+                };
+                let fields = class.fields(db);
+                let names: Vec<Word> = fields.iter().map(|f| f.name(db)).collect();
+                self.check_labels(terminator, labels, &names);
+                let specifiers: Vec<Specifier> = fields
+                    .iter()
+                    .map(|f| f.decl(db).specifier.specifier(db))
+                    .collect();
+                self.store_arguments(tables, arguments, &specifiers);
+                self.emit(&format!("i32.const {index}"));
+                self.emit("local.get $ptr");
+                self.emit(&format!("i32.const {}", arguments.len()));
+                self.emit("call $new_object");
+            }
+            PlaceData::HostFunction(name) => {
+                let what = format!("the host function `{}`", name.as_str(db));
+                self.unsupported(callee, &what)
+            }
+            PlaceData::ExternFunction(function) => {
+                let what = format!("the extern function `{}`", function.name(db).as_str(db));
+                self.unsupported(callee, &what)
+            }
+            PlaceData::LocalVariable(_) | PlaceData::Dot(..) => {
+                self.unsupported(callee, "calling a function stored in a variable")
+            }
+        }
+    }
+
+    /// If `place` is a temporary that holds a reservation, the reserved
+    /// place; otherwise `place`.
+    fn reserved_place(&self, tables: &bir::Tables, place: bir::Place) -> bir::Place {
+        match place.data(tables) {
+            PlaceData::LocalVariable(local_variable) => self
+                .reserved
+                .get(local_variable)
+                .map_or(place, |&reserved| self.reserved_place(tables, reserved)),
+            _ => place,
+        }
+    }
+
+    /// Reports the same errors the interpreter would for arguments
+    /// that do not match the parameters being called.
+    fn check_labels(
+        &mut self,
+        terminator: bir::Terminator,
+        labels: &[SpannedOptionalWord],
+        expected_names: &[Word],
+    ) {
+        let db = self.module.db;
+        for (label, expected_name) in labels.iter().zip(expected_names) {
+            if let Some(actual_word) = label.word(db) {
+                if *expected_name != actual_word {
+                    let diagnostic = error!(
+                        ArgumentNameMismatch,
+                        label.span(db),
+                        "expected to find an argument named `{}`, but found the name `{}`",
+                        expected_name.as_str(db),
+                        actual_word.as_str(db),
+                    )
+                    .finish();
+                    self.module.diagnostics.push(diagnostic);
+                }
+            }
+        }
+
+        if labels.len() != expected_names.len() {
+            let diagnostic = error!(
+                ArgumentCountMismatch,
+                self.span(terminator),
+                "expected to find {} arguments, but found {}",
+                expected_names.len(),
+                labels.len(),
+            )
+            .finish();
+            self.module.diagnostics.push(diagnostic);
+        }
+    }
+
+    /// Allocates an argument block, stores `arguments` into it (each
+    /// prepared for the corresponding specifier, as the interpreter
+    /// does), and leaves its address in `$ptr`.
+    fn store_arguments(
+        &mut self,
+        tables: &bir::Tables,
+        arguments: &[bir::Place],
+        specifiers: &[Specifier],
+    ) {
+        self.emit(&format!("i32.const {}", arguments.len() * 16));
+        self.emit("call $alloc");
+        self.emit("local.set $ptr");
+        for ((&argument, &specifier), index) in arguments.iter().zip(specifiers).zip(0..) {
+            self.push_place(tables, argument);
+            self.emit(&format!("i32.const {}", specifier_code(Some(specifier))));
+            self.emit("call $prepare");
+            self.emit("local.set $vp");
+            self.emit("local.set $vt");
+            self.emit("local.get $ptr");
+            self.emit("local.get $vt");
+            self.emit(&format!("i32.store offset={}", index * 16));
+            self.emit("local.get $ptr");
+            self.emit("local.get $vp");
+            self.emit(&format!("i64.store offset={}", index * 16 + 8));
+        }
+    }
+
+    /// Pushes the (tag, payload) pair for the value of `expr`.
+    fn compile_expr(&mut self, tables: &bir::Tables, expr: bir::Expr) {
+        match expr.data(tables) {
+            ExprData::BooleanLiteral(value) => self.push_constant(tag::BOOL, *value as i64),
+            ExprData::IntegerLiteral(value) => self.push_constant(tag::INT, *value as i64),
+            ExprData::UnsignedIntegerLiteral(value) => {
+                self.push_constant(tag::UNSIGNED_INT, *value as i64)
+            }
+            ExprData::SignedIntegerLiteral(value) => self.push_constant(tag::SIGNED_INT, *value),
+            ExprData::StringLiteral(word) => {
+                let (address, len) = self.module.intern_string(*word);
+                self.push_constant(tag::STRING, ((address as i64) << 32) | len as i64);
+            }
+            ExprData::Unit => self.push_constant(tag::UNIT, 0),
+
+            ExprData::Give(place) => {
+                self.push_place(tables, *place);
+                self.emit("call $give");
+            }
+            ExprData::Share(place) => {
+                self.push_place(tables, *place);
+                self.emit("call $share");
+            }
+            ExprData::Lease(place) => {
+                self.push_place(tables, *place);
+                self.emit("call $lease");
+            }
+            ExprData::Shlease(place) => {
+                self.push_place(tables, *place);
+                self.emit("call $shlease");
+            }
+            ExprData::Reserve(_) => self.unsupported(expr, "reserving a place"),
+
+            ExprData::Op(lhs, op, rhs) => {
+                self.emit(&format!("i32.const {}", op_code(*op)));
+                self.push_place(tables, *lhs);
+                self.push_place(tables, *rhs);
+                self.emit("call $binop");
+            }
+            ExprData::Unary(Op::Minus, rhs) => {
+                self.push_place(tables, *rhs);
+                self.emit("call $neg");
+            }
+            ExprData::Unary(..) => self.unsupported(expr, "this operator"),
+            ExprData::FloatLiteral(value) => {
+                self.push_constant(tag::FLOAT, value.0.to_bits() as i64)
+            }
+            ExprData::Tuple(places) => {
+                let places = &tables[*places];
+                self.store_arguments(tables, places, &vec![Specifier::Any; places.len()]);
+                self.emit("i32.const -1");
+                self.emit("local.get $ptr");
+                self.emit(&format!("i32.const {}", places.len()));
+                self.emit("call $new_object");
+            }
+            ExprData::Error => self.emit("unreachable"),
+        }
+    }
+
+    fn push_constant(&mut self, tag: i32, payload: i64) {
+        self.emit(&format!("i32.const {tag}"));
+        self.emit(&format!("i64.const {payload}"));
+    }
+
+    /// Traverses to the value stored in `place` (see `$traverse` in the
+    /// runtime), pushing its (tag, payload) pair.
+    fn push_place(&mut self, tables: &bir::Tables, place: bir::Place) {
+        self.emit("call $start_traversal");
+        self.traverse(tables, place);
+    }
+
+    fn traverse(&mut self, tables: &bir::Tables, place: bir::Place) {
+        match place.data(tables) {
+            PlaceData::LocalVariable(local_variable) => {
+                if let Some(&reserved) = self.reserved.get(local_variable) {
+                    return self.traverse(tables, reserved);
+                }
+                let n = u32::from(*local_variable);
+                self.emit(&format!("local.get $t{n}"));
+                self.emit(&format!("local.get $p{n}"));
+                self.emit("call $traverse");
+            }
+            PlaceData::Dot(owner, name) => {
+                self.traverse(tables, *owner);
+                let name = self.module.field_name(*name);
+                self.emit(&format!("i32.const {name}"));
+                self.emit("call $field");
+                self.emit("call $traverse");
+            }
+            PlaceData::Class(_) => self.unsupported(place, "classes as values"),
+            PlaceData::Function(_)
+            | PlaceData::ExternFunction(_)
+            | PlaceData::Intrinsic(_)
+            | PlaceData::HostFunction(_) => self.unsupported(place, "functions as values"),
+        }
+    }
+
+    /// Pops a (tag, payload) pair into `target`, dropping the value
+    /// it replaces.
+    fn store_target(&mut self, tables: &bir::Tables, target: bir::TargetPlace) {
+        match target.data(tables) {
+            TargetPlaceData::LocalVariable(local_variable) => {
+                let n = u32::from(*local_variable);
+                self.drop_local(n);
+                self.emit(&format!("local.set $p{n}"));
+                self.emit(&format!("local.set $t{n}"));
+            }
+            TargetPlaceData::Dot(owner, name) => {
+                let name = self.module.field_name(*name);
+                self.emit("local.set $vp");
+                self.emit("local.set $vt");
+                self.push_place(tables, *owner);
+                self.emit("call $write_place");
+                self.emit(&format!("i32.const {name}"));
+                self.emit("call $assign_field");
+                self.emit("drop");
+                self.emit("local.get $vt");
+                self.emit("local.get $vp");
+                self.emit("call $store");
+            }
+        }
+    }
+
+    /// Drops the value in the local variable numbered `n` (see `$drop`
+    /// in the runtime).
+    fn drop_local(&mut self, n: u32) {
+        self.emit(&format!("local.get $t{n}"));
+        self.emit(&format!("local.get $p{n}"));
+        self.emit("call $drop");
+    }
+
+    fn goto(&mut self, basic_block: bir::BasicBlock) {
+        self.emit(&format!("i32.const {}", u32::from(basic_block)));
+        self.emit("local.set $bb");
+        self.emit("br $dispatch");
+    }
+
+    /// Reports that `what` is not supported by this backend and emits
+    /// a trap in its place (keeping the generated code well-typed).
+    fn unsupported(
+        &mut self,
+        origin: impl HasOriginIn<bir::Origins, Origin = syntax::Expr>,
+        what: &str,
+    ) {
+        let diagnostic = error!(
+            UnsupportedByBackend,
+            self.span(origin),
+            "the wasm backend does not yet support {}",
+            what
+        )
+        .finish();
+        self.module.diagnostics.push(diagnostic);
+        self.emit("unreachable");
+    }
+
+    fn span(
+        &self,
+        origin: impl HasOriginIn<bir::Origins, Origin = syntax::Expr>,
+    ) -> dada_ir::span::FileSpan {
+        let db = self.module.db;
+        let syntax_expr = self.bir.origins(db)[origin];
+        let syntax_tree = self.function.syntax_tree(db);
+        syntax_tree.file_span(db, syntax_tree.spans(db)[syntax_expr])
+    }
+}
+
+/// The number `$binop` in the runtime uses for each operator.
+fn op_code(op: Op) -> u32 {
+    match op {
+        Op::EqualEqual => 0,
+        Op::GreaterEqual => 1,
+        Op::LessEqual => 2,
+        Op::Plus => 3,
+        Op::Minus => 4,
+        Op::Times => 5,
+        Op::DividedBy => 6,
+        Op::LessThan => 7,
+        Op::GreaterThan => 8,
+    }
+}
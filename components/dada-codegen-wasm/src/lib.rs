@@ -0,0 +1,56 @@
+//! Compiles a dada program ahead of time into a standalone WebAssembly
+//! module (in the text format), for hosts that want to run dada code
+//! without shipping the interpreter.
+//!
+//! The module exports its `memory` and a `main` function that runs the
+//! program's `main`. It imports a single function, `dada.print`, which is
+//! given the address and length of the UTF-8 text of each value to print
+//! (formatted the same way the interpreter would); the host writes it,
+//! followed by a newline.
+//!
+//! Modules can also be compiled for WASI (see [`compile_wasi_module`]),
+//! so that they run on their own under a runtime like wasmtime. These
+//! write printed values to stdout with `fd_write`, and the program runs
+//! when the runtime calls the exported `_start`.
+//!
+//! Supported are integers, floats, booleans, strings, `()`, tuples, and
+//! instances of the classes in the same file; arithmetic and comparisons;
+//! control flow; calls to functions in the same file; `print`; and
+//! `await`. Tuples and instances are tracked by permission, as in the
+//! interpreter, except that memory is never freed: rather than being
+//! collected, a `my` object is dropped (revoking any leases of it) when
+//! the place that holds it is overwritten, cleared, or goes out of scope.
+//! Programs that use other intrinsics, functions in other files, or
+//! functions as values are rejected with a diagnostic. Dynamic errors
+//! (overflow, applying an operator to the wrong kind of value, using an
+//! expired permission, and so on) trap rather than producing a dada
+//! error message, as does printing a float whose digits do not fit in a
+//! `u64`.
+
+#![feature(let_else)]
+#![feature(trait_upcasting)]
+#![allow(incomplete_features)]
+
+use dada_ir::{diagnostic::Diagnostic, filename::Filename};
+
+mod function;
+mod module;
+pub mod tag;
+
+pub trait Db: dada_brew::Db + dada_ir::Db + dada_parse::Db {}
+
+impl<T> Db for T where T: dada_brew::Db + dada_ir::Db + dada_parse::Db {}
+
+/// Compiles the functions in `filename` into a wasm module (in the text
+/// format). Returns the diagnostics for any unsupported constructs
+/// if the program cannot be compiled.
+pub fn compile_module(db: &dyn Db, filename: Filename) -> Result<String, Vec<Diagnostic>> {
+    module::ModuleBuilder::new(db, filename, module::Host::Embedder).build()
+}
+
+/// Like [`compile_module`], but the module imports nothing except the
+/// WASI `fd_write` function, so it can run under any WASI runtime
+/// (e.g., `wasmtime run program.wat`).
+pub fn compile_wasi_module(db: &dyn Db, filename: Filename) -> Result<String, Vec<Diagnostic>> {
+    module::ModuleBuilder::new(db, filename, module::Host::Wasi).build()
+}
@@ -0,0 +1,314 @@
+use std::fmt::Write;
+
+use dada_collections::Map;
+use dada_ir::{
+    class::Class, diagnostic::Diagnostic, filename::Filename, function::Function, item::Item,
+    storage::Specifier, word::Word,
+};
+use dada_parse::prelude::*;
+
+use crate::function::FunctionCompiler;
+
+/// String literals are placed in memory starting at this address,
+/// so that no string is ever stored at address 0.
+const DATA_START: u32 = 16;
+
+/// The most permissions a traversal can pass through (see `$traverse`
+/// in the runtime); they are recorded in a buffer after the data segment.
+const MAX_PATH_LEN: u32 = 64;
+
+const RUNTIME: &str = include_str!("runtime.wat");
+
+const WASI_RUNTIME: &str = include_str!("wasi.wat");
+
+/// The kind of host that the module will run in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Host {
+    /// A host that provides the `dada.print` import and calls `main`.
+    Embedder,
+
+    /// A WASI runtime, which calls `_start`.
+    Wasi,
+}
+
+pub(crate) struct ModuleBuilder<'db> {
+    pub(crate) db: &'db dyn crate::Db,
+    filename: Filename,
+    host: Host,
+
+    /// The functions in the file; a function's index in this list
+    /// is also its index in the module's function table.
+    pub(crate) functions: Vec<Function>,
+
+    /// The classes in the file; a class's index in this list is how
+    /// its instances record their class.
+    pub(crate) classes: Vec<Class>,
+
+    /// The number that stands for each field name (see `$field_index`
+    /// in the runtime).
+    field_names: Map<Word, u32>,
+
+    /// Contents of the data segment, which holds string literals.
+    data: Vec<u8>,
+
+    /// Address and length of each string literal in the data segment.
+    strings: Map<Word, (u32, u32)>,
+
+    /// Errors for constructs the backend does not support.
+    pub(crate) diagnostics: Vec<Diagnostic>,
+}
+
+impl<'db> ModuleBuilder<'db> {
+    pub(crate) fn new(db: &'db dyn crate::Db, filename: Filename, host: Host) -> Self {
+        let functions = filename
+            .items(db)
+            .iter()
+            .filter_map(|item| match item {
+                Item::Function(function) => Some(*function),
+                Item::Class(_) | Item::ExternFunction(_) => None,
+            })
+            .collect();
+        let classes = filename
+            .items(db)
+            .iter()
+            .filter_map(|item| match item {
+                Item::Class(class) => Some(*class),
+                Item::Function(_) | Item::ExternFunction(_) => None,
+            })
+            .collect();
+        Self {
+            db,
+            filename,
+            host,
+            functions,
+            classes,
+            field_names: Map::default(),
+            data: vec![],
+            strings: Map::default(),
+            diagnostics: vec![],
+        }
+    }
+
+    pub(crate) fn build(mut self) -> Result<String, Vec<Diagnostic>> {
+        let db = self.db;
+
+        let mut bodies = String::new();
+        for index in 0..self.functions.len() {
+            bodies.push_str(&FunctionCompiler::new(&mut self, index).compile());
+        }
+
+        if !self.diagnostics.is_empty() {
+            return Err(self.diagnostics);
+        }
+
+        let classes = self.compile_classes();
+
+        let path_start = align8(DATA_START + self.data.len() as u32);
+        let heap_start = path_start + MAX_PATH_LEN * 4;
+        let pages = (heap_start + 0xFFFF) / 0x10000;
+
+        let mut output = String::new();
+        writeln!(output, ";; compiled from `{}`", self.filename.as_str(db)).unwrap();
+        writeln!(output, "(module").unwrap();
+        writeln!(
+            output,
+            "  (type $dada_fn (func (param i32) (result i32 i64)))"
+        )
+        .unwrap();
+        match self.host {
+            Host::Embedder => writeln!(
+                output,
+                "  (import \"dada\" \"print\" (func $host_print (param i32 i32)))"
+            )
+            .unwrap(),
+            Host::Wasi => writeln!(
+                output,
+                "  (import \"wasi_snapshot_preview1\" \"fd_write\" \
+                 (func $fd_write (param i32 i32 i32 i32) (result i32)))"
+            )
+            .unwrap(),
+        }
+        writeln!(output, "  (memory (export \"memory\") {})", pages.max(1)).unwrap();
+        writeln!(output, "  (global $path i32 (i32.const {}))", path_start).unwrap();
+        writeln!(
+            output,
+            "  (global $heap_ptr (mut i32) (i32.const {}))",
+            heap_start
+        )
+        .unwrap();
+        writeln!(output, "  (table {} funcref)", self.functions.len()).unwrap();
+        if !self.functions.is_empty() {
+            let names: Vec<String> = self.functions.iter().map(|&f| function_id(db, f)).collect();
+            writeln!(output, "  (elem (i32.const 0) {})", names.join(" ")).unwrap();
+        }
+        if !self.data.is_empty() {
+            let mut escaped = String::new();
+            for byte in &self.data {
+                write!(escaped, "\\{:02x}", byte).unwrap();
+            }
+            writeln!(
+                output,
+                "  (data (i32.const {}) \"{}\")",
+                DATA_START, escaped
+            )
+            .unwrap();
+        }
+        writeln!(output).unwrap();
+        output.push_str(RUNTIME);
+        writeln!(output).unwrap();
+        if self.host == Host::Wasi {
+            output.push_str(WASI_RUNTIME);
+            writeln!(output).unwrap();
+        }
+        output.push_str(&classes);
+        output.push_str(&bodies);
+
+        let main = self
+            .functions
+            .iter()
+            .find(|f| f.name(db).as_str(db) == "main");
+        if let Some(&main) = main {
+            let export = match self.host {
+                Host::Embedder => "main",
+                Host::Wasi => "_start",
+            };
+            writeln!(output, "  (func (export \"{}\")", export).unwrap();
+            writeln!(output, "    (local $t i32)").unwrap();
+            writeln!(output, "    (local $p i64)").unwrap();
+            writeln!(output, "    i32.const 0").unwrap();
+            writeln!(output, "    call $alloc").unwrap();
+            writeln!(output, "    call {}", function_id(db, main)).unwrap();
+            writeln!(output, "    local.set $p").unwrap();
+            writeln!(output, "    local.set $t").unwrap();
+            writeln!(
+                output,
+                "    ;; like the interpreter, print the result unless it is `()`"
+            )
+            .unwrap();
+            writeln!(output, "    local.get $t").unwrap();
+            writeln!(output, "    i32.const {}", crate::tag::UNIT).unwrap();
+            writeln!(output, "    i32.ne").unwrap();
+            writeln!(output, "    if").unwrap();
+            writeln!(output, "      local.get $t").unwrap();
+            writeln!(output, "      local.get $p").unwrap();
+            writeln!(output, "      call $print").unwrap();
+            writeln!(output, "    end)").unwrap();
+        }
+
+        writeln!(output, ")").unwrap();
+        Ok(output)
+    }
+
+    /// Generates the functions the runtime uses to look up the fields and
+    /// names of classes: `$field_index` (from a class index and the number
+    /// of a field name to the index of that field, trapping if there is
+    /// no such field), `$field_specifier` (from a class and field index
+    /// to the field's specifier, numbered as for `$prepare`), and
+    /// `$class_name` (the address and length of the class's name).
+    fn compile_classes(&mut self) -> String {
+        let db = self.db;
+        let mut field_index = String::new();
+        let mut field_specifier = String::new();
+        let mut class_name = String::new();
+        for (class, class_index) in self.classes.clone().into_iter().zip(0..) {
+            for (field, index) in class.fields(db).iter().zip(0..) {
+                let name = self.field_name(field.name(db));
+                writeln!(
+                    field_index,
+                    "    (if (i32.and (i32.eq (local.get $class) (i32.const {class_index})) \
+                     (i32.eq (local.get $name) (i32.const {name})))"
+                )
+                .unwrap();
+                writeln!(field_index, "      (then (return (i32.const {index}))))").unwrap();
+                let specifier = specifier_code(Some(field.decl(db).specifier.specifier(db)));
+                writeln!(
+                    field_specifier,
+                    "    (if (i32.and (i32.eq (local.get $class) (i32.const {class_index})) \
+                     (i32.eq (local.get $index) (i32.const {index})))"
+                )
+                .unwrap();
+                writeln!(
+                    field_specifier,
+                    "      (then (return (i32.const {specifier}))))"
+                )
+                .unwrap();
+            }
+            let (address, len) = self.intern_string(class.name(db).word(db));
+            writeln!(
+                class_name,
+                "    (if (i32.eq (local.get $class) (i32.const {class_index}))"
+            )
+            .unwrap();
+            writeln!(
+                class_name,
+                "      (then (return (i32.const {address}) (i32.const {len}))))"
+            )
+            .unwrap();
+        }
+
+        let mut output = String::new();
+        writeln!(
+            output,
+            "  (func $field_index (param $class i32) (param $name i32) (result i32)"
+        )
+        .unwrap();
+        output.push_str(&field_index);
+        writeln!(output, "    unreachable)").unwrap();
+        writeln!(
+            output,
+            "  (func $field_specifier (param $class i32) (param $index i32) (result i32)"
+        )
+        .unwrap();
+        output.push_str(&field_specifier);
+        writeln!(output, "    unreachable)").unwrap();
+        writeln!(
+            output,
+            "  (func $class_name (param $class i32) (result i32 i32)"
+        )
+        .unwrap();
+        output.push_str(&class_name);
+        writeln!(output, "    unreachable)").unwrap();
+        writeln!(output).unwrap();
+        output
+    }
+
+    /// Returns the number that stands for the field name `word`.
+    pub(crate) fn field_name(&mut self, word: Word) -> u32 {
+        let next = self.field_names.len() as u32;
+        *self.field_names.entry(word).or_insert(next)
+    }
+
+    /// Returns the address and length of the string literal `word`,
+    /// adding it to the data segment if needed.
+    pub(crate) fn intern_string(&mut self, word: Word) -> (u32, u32) {
+        let db = self.db;
+        let data = &mut self.data;
+        *self.strings.entry(word).or_insert_with(|| {
+            let s = word.as_str(db);
+            let address = DATA_START + data.len() as u32;
+            data.extend_from_slice(s.as_bytes());
+            (address, s.len() as u32)
+        })
+    }
+}
+
+/// The wasm identifier for the compiled body of `function`.
+pub(crate) fn function_id(db: &dyn crate::Db, function: Function) -> String {
+    format!("$fn.{}", function.name(db).as_str(db))
+}
+
+/// The number `$prepare` in the runtime uses for each specifier
+/// (`None` being a compiler temporary, which is treated as `any`).
+pub(crate) fn specifier_code(specifier: Option<Specifier>) -> u32 {
+    match specifier {
+        None | Some(Specifier::Any) => 0,
+        Some(Specifier::My) => 1,
+        Some(Specifier::Our) => 2,
+        Some(Specifier::Leased) => 3,
+        Some(Specifier::Shleased) => 4,
+    }
+}
+
+fn align8(n: u32) -> u32 {
+    (n + 7) & !7
+}
@@ -0,0 +1,880 @@
+  ;; The runtime that compiled dada functions call into. Values are
+  ;; (tag, payload) pairs; see `tag.rs` for the meaning of each tag.
+  ;; Any dynamic error (e.g., an overflow, applying an operator to the
+  ;; wrong kind of value, or using a value whose permission has expired)
+  ;; traps with `unreachable`.
+
+  ;; Grows the memory, if needed, so that addresses below `$end` are valid.
+  (func $ensure (param $end i32)
+    (block $done
+      (loop $grow
+        (br_if $done
+          (i32.le_u (local.get $end) (i32.mul (memory.size) (i32.const 65536))))
+        (if (i32.eq (memory.grow (i32.const 1)) (i32.const -1))
+          (then unreachable))
+        (br $grow))))
+
+  ;; Allocates `$size` bytes (8-byte aligned) from the heap, growing the
+  ;; memory as needed. Memory is never freed.
+  (func $alloc (param $size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap_ptr))
+    (global.set $heap_ptr
+      (i32.and
+        (i32.add (i32.add (local.get $ptr) (local.get $size)) (i32.const 7))
+        (i32.const -8)))
+    (call $ensure (global.get $heap_ptr))
+    (local.get $ptr))
+
+  ;; Applies the binary operator `$op` to two values. Operators are
+  ;; numbered 0-8: `==`, `>=`, `<=`, `+`, `-`, `*`, `/`, `<`, `>`.
+  (func $binop (param $op i32) (param $lt i32) (param $lp i64) (param $rt i32) (param $rp i64)
+    (result i32 i64)
+    (local $kind i32)
+    ;; Floats and strings can only be combined with their own kind.
+    (if (i32.or
+          (i32.eq (local.get $lt) (i32.const 8))
+          (i32.eq (local.get $rt) (i32.const 8)))
+      (then
+        (if (i32.ne (local.get $lt) (local.get $rt))
+          (then unreachable))
+        (return
+          (call $binop_f64
+            (local.get $op)
+            (f64.reinterpret_i64 (local.get $lp))
+            (f64.reinterpret_i64 (local.get $rp))))))
+    (if (i32.or
+          (i32.eq (local.get $lt) (i32.const 5))
+          (i32.eq (local.get $rt) (i32.const 5)))
+      (then
+        (if (i32.or
+              (i32.ne (local.get $lt) (local.get $rt))
+              (i32.gt_u (local.get $op) (i32.const 2)))
+          (then unreachable))
+        (return
+          (i32.const 1)
+          (call $compare_s
+            (local.get $op)
+            (i64.extend_i32_s (call $string_order (local.get $lp) (local.get $rp)))
+            (i64.const 0)))))
+
+    ;; Unsuffixed integers adopt the kind of the other operand.
+    (if (i32.eq (local.get $lt) (local.get $rt))
+      (then (local.set $kind (local.get $lt)))
+      (else
+        (if (i32.eq (local.get $lt) (i32.const 2))
+          (then (local.set $kind (local.get $rt)))
+          (else
+            (if (i32.eq (local.get $rt) (i32.const 2))
+              (then (local.set $kind (local.get $lt)))
+              (else unreachable))))))
+    ;; ...but only other kinds of integer.
+    (if (i32.ne (local.get $lt) (local.get $rt))
+      (then
+        (if (i32.and
+              (i32.ne (local.get $kind) (i32.const 3))
+              (i32.ne (local.get $kind) (i32.const 4)))
+          (then unreachable))))
+
+    ;; `()` and booleans only support comparisons.
+    (if (i32.le_u (local.get $kind) (i32.const 1))
+      (then
+        (if (i32.gt_u (local.get $op) (i32.const 2))
+          (then unreachable))
+        (if (i32.eqz (local.get $kind))
+          (then (return (i32.const 1) (i64.const 1))))
+        (return
+          (i32.const 1)
+          (call $compare_u (local.get $op) (local.get $lp) (local.get $rp)))))
+
+    (if (i32.eq (local.get $kind) (i32.const 4))
+      (then
+        ;; An unsuffixed integer must fit in a signed integer.
+        (if (i32.and
+              (i32.eq (local.get $lt) (i32.const 2))
+              (i64.lt_s (local.get $lp) (i64.const 0)))
+          (then unreachable))
+        (if (i32.and
+              (i32.eq (local.get $rt) (i32.const 2))
+              (i64.lt_s (local.get $rp) (i64.const 0)))
+          (then unreachable))
+        (if (i32.lt_u (i32.sub (local.get $op) (i32.const 3)) (i32.const 4))
+          (then
+            (return
+              (i32.const 4)
+              (call $arith_s (local.get $op) (local.get $lp) (local.get $rp)))))
+        (return
+          (i32.const 1)
+          (call $compare_s (local.get $op) (local.get $lp) (local.get $rp)))))
+
+    (if (i32.or
+          (i32.eq (local.get $kind) (i32.const 2))
+          (i32.eq (local.get $kind) (i32.const 3)))
+      (then
+        (if (i32.lt_u (i32.sub (local.get $op) (i32.const 3)) (i32.const 4))
+          (then
+            (return
+              (local.get $kind)
+              (call $arith_u (local.get $op) (local.get $lp) (local.get $rp)))))
+        (return
+          (i32.const 1)
+          (call $compare_u (local.get $op) (local.get $lp) (local.get $rp)))))
+
+    unreachable)
+
+  (func $compare_u (param $op i32) (param $a i64) (param $b i64) (result i64)
+    (if (i32.eq (local.get $op) (i32.const 0))
+      (then (return (i64.extend_i32_u (i64.eq (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 1))
+      (then (return (i64.extend_i32_u (i64.ge_u (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 2))
+      (then (return (i64.extend_i32_u (i64.le_u (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 7))
+      (then (return (i64.extend_i32_u (i64.lt_u (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 8))
+      (then (return (i64.extend_i32_u (i64.gt_u (local.get $a) (local.get $b))))))
+    unreachable)
+
+  (func $compare_s (param $op i32) (param $a i64) (param $b i64) (result i64)
+    (if (i32.eq (local.get $op) (i32.const 0))
+      (then (return (i64.extend_i32_u (i64.eq (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 1))
+      (then (return (i64.extend_i32_u (i64.ge_s (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 2))
+      (then (return (i64.extend_i32_u (i64.le_s (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 7))
+      (then (return (i64.extend_i32_u (i64.lt_s (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 8))
+      (then (return (i64.extend_i32_u (i64.gt_s (local.get $a) (local.get $b))))))
+    unreachable)
+
+  ;; Checked unsigned arithmetic: traps on overflow, underflow,
+  ;; and division by zero.
+  (func $arith_u (param $op i32) (param $a i64) (param $b i64) (result i64)
+    (local $r i64)
+    (if (i32.eq (local.get $op) (i32.const 3))
+      (then
+        (local.set $r (i64.add (local.get $a) (local.get $b)))
+        (if (i64.lt_u (local.get $r) (local.get $a))
+          (then unreachable))
+        (return (local.get $r))))
+    (if (i32.eq (local.get $op) (i32.const 4))
+      (then
+        (if (i64.lt_u (local.get $a) (local.get $b))
+          (then unreachable))
+        (return (i64.sub (local.get $a) (local.get $b)))))
+    (if (i32.eq (local.get $op) (i32.const 5))
+      (then
+        (local.set $r (i64.mul (local.get $a) (local.get $b)))
+        (if (i64.ne (local.get $a) (i64.const 0))
+          (then
+            (if (i64.ne (i64.div_u (local.get $r) (local.get $a)) (local.get $b))
+              (then unreachable))))
+        (return (local.get $r))))
+    (if (i32.eq (local.get $op) (i32.const 6))
+      (then (return (i64.div_u (local.get $a) (local.get $b)))))
+    unreachable)
+
+  ;; Checked signed arithmetic: traps on overflow, underflow,
+  ;; and division by zero.
+  (func $arith_s (param $op i32) (param $a i64) (param $b i64) (result i64)
+    (local $r i64)
+    (if (i32.eq (local.get $op) (i32.const 3))
+      (then
+        (local.set $r (i64.add (local.get $a) (local.get $b)))
+        (if (i64.lt_s
+              (i64.and
+                (i64.xor (local.get $a) (local.get $r))
+                (i64.xor (local.get $b) (local.get $r)))
+              (i64.const 0))
+          (then unreachable))
+        (return (local.get $r))))
+    (if (i32.eq (local.get $op) (i32.const 4))
+      (then
+        (local.set $r (i64.sub (local.get $a) (local.get $b)))
+        (if (i64.lt_s
+              (i64.and
+                (i64.xor (local.get $a) (local.get $b))
+                (i64.xor (local.get $a) (local.get $r)))
+              (i64.const 0))
+          (then unreachable))
+        (return (local.get $r))))
+    (if (i32.eq (local.get $op) (i32.const 5))
+      (then
+        (local.set $r (i64.mul (local.get $a) (local.get $b)))
+        (if (i64.ne (local.get $a) (i64.const 0))
+          (then
+            ;; `div_s` itself traps for `i64::MIN / -1`, which is also an overflow.
+            (if (i64.ne (i64.div_s (local.get $r) (local.get $a)) (local.get $b))
+              (then unreachable))))
+        (return (local.get $r))))
+    (if (i32.eq (local.get $op) (i32.const 6))
+      (then (return (i64.div_s (local.get $a) (local.get $b)))))
+    unreachable)
+
+  ;; Unary minus: produces a signed integer.
+  (func $neg (param $t i32) (param $p i64) (result i32 i64)
+    (if (i32.eq (local.get $t) (i32.const 4))
+      (then
+        (if (i64.eq (local.get $p) (i64.const -9223372036854775808))
+          (then unreachable))
+        (return (i32.const 4) (i64.sub (i64.const 0) (local.get $p)))))
+    (if (i32.eq (local.get $t) (i32.const 2))
+      (then
+        (if (i64.lt_s (local.get $p) (i64.const 0))
+          (then unreachable))
+        (return (i32.const 4) (i64.sub (i64.const 0) (local.get $p)))))
+    unreachable)
+
+  ;; Converts a boolean to an `i32` suitable for `if`.
+  (func $truthy (param $t i32) (param $p i64) (result i32)
+    (if (i32.ne (local.get $t) (i32.const 1))
+      (then unreachable))
+    (i32.wrap_i64 (local.get $p)))
+
+  ;; Float arithmetic and comparisons, with the same numbering of
+  ;; operators as `$binop`.
+  (func $binop_f64 (param $op i32) (param $a f64) (param $b f64) (result i32 i64)
+    (if (i32.eq (local.get $op) (i32.const 0))
+      (then (return (i32.const 1) (i64.extend_i32_u (f64.eq (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 1))
+      (then (return (i32.const 1) (i64.extend_i32_u (f64.ge (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 2))
+      (then (return (i32.const 1) (i64.extend_i32_u (f64.le (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 3))
+      (then (return (i32.const 8) (i64.reinterpret_f64 (f64.add (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 4))
+      (then (return (i32.const 8) (i64.reinterpret_f64 (f64.sub (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 5))
+      (then (return (i32.const 8) (i64.reinterpret_f64 (f64.mul (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 6))
+      (then (return (i32.const 8) (i64.reinterpret_f64 (f64.div (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 7))
+      (then (return (i32.const 1) (i64.extend_i32_u (f64.lt (local.get $a) (local.get $b))))))
+    (if (i32.eq (local.get $op) (i32.const 8))
+      (then (return (i32.const 1) (i64.extend_i32_u (f64.gt (local.get $a) (local.get $b))))))
+    unreachable)
+
+  ;; Compares the bytes of two strings: returns -1, 0, or 1.
+  (func $string_order (param $a i64) (param $b i64) (result i32)
+    (local $ap i32) (local $al i32) (local $bp i32) (local $bl i32)
+    (local $i i32) (local $x i32) (local $y i32)
+    (local.set $ap (i32.wrap_i64 (i64.shr_u (local.get $a) (i64.const 32))))
+    (local.set $al (i32.wrap_i64 (local.get $a)))
+    (local.set $bp (i32.wrap_i64 (i64.shr_u (local.get $b) (i64.const 32))))
+    (local.set $bl (i32.wrap_i64 (local.get $b)))
+    (block $done
+      (loop $next
+        (br_if $done
+          (i32.or
+            (i32.eq (local.get $i) (local.get $al))
+            (i32.eq (local.get $i) (local.get $bl))))
+        (local.set $x (i32.load8_u (i32.add (local.get $ap) (local.get $i))))
+        (local.set $y (i32.load8_u (i32.add (local.get $bp) (local.get $i))))
+        (if (i32.ne (local.get $x) (local.get $y))
+          (then
+            (return (select (i32.const -1) (i32.const 1) (i32.lt_u (local.get $x) (local.get $y))))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i32.sub (i32.gt_u (local.get $al) (local.get $bl)) (i32.lt_u (local.get $al) (local.get $bl))))
+
+  ;; ## Objects and permissions
+  ;;
+  ;; Tuples and class instances live in the heap. Their payload holds the
+  ;; address of the object in its lower 32 bits and the address of the
+  ;; permission through which it is reached in its upper 32 bits. An
+  ;; object is the class index (-1 for a tuple) and number of fields,
+  ;; followed by the fields (16 bytes each: the tag at offset 0, the
+  ;; payload at offset 8).
+  ;;
+  ;; A permission is its flags (1 = valid, 2 = joint, 4 = leased), the
+  ;; address of its first tenant, and the address of the next tenant of
+  ;; its lessor. The operations below follow the interpreter's (see the
+  ;; `step` module of `dada-execute`); other values behave as if they
+  ;; were always `our`. There is no garbage collector: instead, a `my`
+  ;; value is dropped (revoking its leases) when the place holding it is
+  ;; overwritten, cleared, or goes out of scope.
+
+  (func $is_object (param $t i32) (result i32)
+    (i32.or (i32.eq (local.get $t) (i32.const 9)) (i32.eq (local.get $t) (i32.const 10))))
+
+  (func $object (param $p i64) (result i32)
+    (i32.wrap_i64 (local.get $p)))
+
+  (func $permission (param $p i64) (result i32)
+    (i32.wrap_i64 (i64.shr_u (local.get $p) (i64.const 32))))
+
+  (func $payload (param $object i32) (param $permission i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $permission)) (i64.const 32))
+      (i64.extend_i32_u (local.get $object))))
+
+  ;; Creates an object of class `$class` (-1 for a tuple) whose fields
+  ;; are copied from the `$len` values at `$args`, with a `my` permission.
+  (func $new_object (param $class i32) (param $args i32) (param $len i32) (result i32 i64)
+    (local $object i32)
+    (local.set $object
+      (call $alloc (i32.add (i32.const 8) (i32.shl (local.get $len) (i32.const 4)))))
+    (i32.store (local.get $object) (local.get $class))
+    (i32.store offset=4 (local.get $object) (local.get $len))
+    (memory.copy
+      (i32.add (local.get $object) (i32.const 8))
+      (local.get $args)
+      (i32.shl (local.get $len) (i32.const 4)))
+    (select (i32.const 9) (i32.const 10) (i32.lt_s (local.get $class) (i32.const 0)))
+    (call $payload (local.get $object) (call $new_permission (i32.const 1))))
+
+  (func $new_permission (param $flags i32) (result i32)
+    (local $permission i32)
+    (local.set $permission (call $alloc (i32.const 12)))
+    (i32.store (local.get $permission) (local.get $flags))
+    (i32.store offset=4 (local.get $permission) (i32.const 0))
+    (i32.store offset=8 (local.get $permission) (i32.const 0))
+    (local.get $permission))
+
+  ;; Creates a leased permission (joint if `$joint` is 2) that is a
+  ;; tenant of `$lessor`.
+  (func $new_tenant (param $joint i32) (param $lessor i32) (result i32)
+    (local $tenant i32)
+    (local.set $tenant (call $new_permission (i32.or (local.get $joint) (i32.const 5))))
+    (i32.store offset=8 (local.get $tenant) (i32.load offset=4 (local.get $lessor)))
+    (i32.store offset=4 (local.get $lessor) (local.get $tenant))
+    (local.get $tenant))
+
+  (func $revoke (param $permission i32)
+    (i32.store (local.get $permission) (i32.const 0))
+    (call $revoke_tenants (local.get $permission)))
+
+  (func $revoke_tenants (param $permission i32)
+    (local $tenant i32)
+    (local.set $tenant (i32.load offset=4 (local.get $permission)))
+    (i32.store offset=4 (local.get $permission) (i32.const 0))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $tenant)))
+        (call $revoke (local.get $tenant))
+        (local.set $tenant (i32.load offset=8 (local.get $tenant)))
+        (br $next))))
+
+  ;; Revokes the tenants of `$permission` that are not valid and joint.
+  (func $revoke_exclusive_tenants (param $permission i32)
+    (local $link i32)
+    (local $tenant i32)
+    ;; `$link` is the address that holds the address of `$tenant`.
+    (local.set $link (i32.add (local.get $permission) (i32.const 4)))
+    (block $done
+      (loop $next
+        (local.set $tenant (i32.load (local.get $link)))
+        (br_if $done (i32.eqz (local.get $tenant)))
+        (if (i32.eq (i32.and (i32.load (local.get $tenant)) (i32.const 3)) (i32.const 3))
+          (then (local.set $link (i32.add (local.get $tenant) (i32.const 8))))
+          (else
+            (i32.store (local.get $link) (i32.load offset=8 (local.get $tenant)))
+            (call $revoke (local.get $tenant))))
+        (br $next))))
+
+  ;; Applies `$revoke_exclusive_tenants` (`$op` 0, a read) or
+  ;; `$revoke_tenants` (`$op` 1, a write) to `$permission`.
+  (func $apply (param $op i32) (param $permission i32)
+    (if (local.get $op)
+      (then (call $revoke_tenants (local.get $permission)))
+      (else (call $revoke_exclusive_tenants (local.get $permission)))))
+
+  ;; ## Traversals
+  ;;
+  ;; Compiled code reaches the value in a place by calling
+  ;; `$start_traversal`, and then `$traverse` on the value in the local
+  ;; variable and on each field along the way (found with `$field`).
+  ;; This records the permissions that were traversed at `$path` and
+  ;; whether any of them were joint (2) or leased (4) in `$traversed`.
+
+  (global $path_len (mut i32) (i32.const 0))
+  (global $traversed (mut i32) (i32.const 0))
+
+  (func $start_traversal
+    (global.set $path_len (i32.const 0))
+    (global.set $traversed (i32.const 0)))
+
+  (func $traverse (param $t i32) (param $p i64) (result i32 i64)
+    (local $permission i32)
+    (local $flags i32)
+    (if (i32.eqz (call $is_object (local.get $t)))
+      (then
+        (global.set $traversed (i32.or (global.get $traversed) (i32.const 2)))
+        (return (local.get $t) (local.get $p))))
+    (local.set $permission (call $permission (local.get $p)))
+    (local.set $flags (i32.load (local.get $permission)))
+    (if (i32.eqz (i32.and (local.get $flags) (i32.const 1)))
+      (then unreachable))
+    (global.set $traversed
+      (i32.or (global.get $traversed) (i32.and (local.get $flags) (i32.const 6))))
+    (if (i32.eq (global.get $path_len) (i32.const 64))
+      (then unreachable))
+    (i32.store
+      (i32.add (global.get $path) (i32.shl (global.get $path_len) (i32.const 2)))
+      (local.get $permission))
+    (global.set $path_len (i32.add (global.get $path_len) (i32.const 1)))
+    (local.get $t)
+    (local.get $p))
+
+  ;; The last permission traversed, which is the one to the value itself.
+  (func $last_permission (result i32)
+    (i32.load
+      (i32.sub
+        (i32.add (global.get $path) (i32.shl (global.get $path_len) (i32.const 2)))
+        (i32.const 4))))
+
+  ;; The address of the field named `$name` (see `$field_index`) of
+  ;; the instance `$p`.
+  (func $field_address (param $t i32) (param $p i64) (param $name i32) (result i32)
+    (local $object i32)
+    (if (i32.ne (local.get $t) (i32.const 10))
+      (then unreachable))
+    (local.set $object (call $object (local.get $p)))
+    (i32.add
+      (i32.add (local.get $object) (i32.const 8))
+      (i32.shl
+        (call $field_index (i32.load (local.get $object)) (local.get $name))
+        (i32.const 4))))
+
+  (func $field (param $t i32) (param $p i64) (param $name i32) (result i32 i64)
+    (local $address i32)
+    (local.set $address (call $field_address (local.get $t) (local.get $p) (local.get $name)))
+    (i32.load (local.get $address))
+    (i64.load offset=8 (local.get $address)))
+
+  ;; Applies `$op` (see `$apply`) to each permission traversed and to
+  ;; each permission exclusively reachable from the value.
+  (func $access (param $op i32) (param $t i32) (param $p i64)
+    (local $i i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $i) (global.get $path_len)))
+        (call $apply
+          (local.get $op)
+          (i32.load (i32.add (global.get $path) (i32.shl (local.get $i) (i32.const 2)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (call $access_fields (local.get $op) (local.get $t) (local.get $p)))
+
+  (func $access_fields (param $op i32) (param $t i32) (param $p i64)
+    (local $field i32)
+    (local $end i32)
+    (local $permission i32)
+    (if (i32.eqz (call $is_object (local.get $t)))
+      (then (return)))
+    (local.set $field (i32.add (call $object (local.get $p)) (i32.const 8)))
+    (local.set $end
+      (i32.add
+        (local.get $field)
+        (i32.shl (i32.load offset=4 (call $object (local.get $p))) (i32.const 4))))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $field) (local.get $end)))
+        (if (call $is_object (i32.load (local.get $field)))
+          (then
+            (local.set $permission (call $permission (i64.load offset=8 (local.get $field))))
+            (if (i32.eq (i32.and (i32.load (local.get $permission)) (i32.const 3)) (i32.const 1))
+              (then
+                (call $apply (local.get $op) (local.get $permission))
+                (call $access_fields
+                  (local.get $op)
+                  (i32.load (local.get $field))
+                  (i64.load offset=8 (local.get $field)))))))
+        (local.set $field (i32.add (local.get $field) (i32.const 16)))
+        (br $next))))
+
+  ;; Checks that the place just traversed can be written, and revokes
+  ;; the tenants of the permissions along the way.
+  (func $write_place
+    (local $i i32)
+    (if (i32.and (global.get $traversed) (i32.const 2))
+      (then unreachable))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $i) (global.get $path_len)))
+        (call $revoke_tenants
+          (i32.load (i32.add (global.get $path) (i32.shl (local.get $i) (i32.const 2)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next))))
+
+  ;; Returns the address of the field named `$name` of the instance just
+  ;; traversed, and its specifier (see `$prepare`). Compiled code calls
+  ;; `$write_place` before storing into it.
+  (func $assign_field (param $t i32) (param $p i64) (param $name i32) (result i32 i32)
+    (local $object i32)
+    (local $index i32)
+    (if (i32.ne (local.get $t) (i32.const 10))
+      (then unreachable))
+    (local.set $object (call $object (local.get $p)))
+    (local.set $index (call $field_index (i32.load (local.get $object)) (local.get $name)))
+    (i32.add (i32.add (local.get $object) (i32.const 8)) (i32.shl (local.get $index) (i32.const 4)))
+    (call $field_specifier (i32.load (local.get $object)) (local.get $index)))
+
+  ;; Stores a value into a field, dropping the value it replaces.
+  (func $store (param $address i32) (param $t i32) (param $p i64)
+    (call $drop (i32.load (local.get $address)) (i64.load offset=8 (local.get $address)))
+    (i32.store (local.get $address) (local.get $t))
+    (i64.store offset=8 (local.get $address) (local.get $p)))
+
+  ;; Drops a value: if it is `my`, its permission (and so its tenants)
+  ;; is revoked, and so are those of the `my` values in its fields.
+  (func $drop (param $t i32) (param $p i64)
+    (local $permission i32)
+    (local $field i32)
+    (local $end i32)
+    (if (i32.eqz (call $is_object (local.get $t)))
+      (then (return)))
+    (local.set $permission (call $permission (local.get $p)))
+    (if (i32.ne (i32.load (local.get $permission)) (i32.const 1))
+      (then (return)))
+    (call $revoke (local.get $permission))
+    (local.set $field (i32.add (call $object (local.get $p)) (i32.const 8)))
+    (local.set $end
+      (i32.add
+        (local.get $field)
+        (i32.shl (i32.load offset=4 (call $object (local.get $p))) (i32.const 4))))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $field) (local.get $end)))
+        (call $drop (i32.load (local.get $field)) (i64.load offset=8 (local.get $field)))
+        (local.set $field (i32.add (local.get $field) (i32.const 16)))
+        (br $next))))
+
+  ;; ## Permission operations
+  ;;
+  ;; Each of these takes the value that was just traversed.
+
+  ;; Revokes the permission to the (`my`) value, which is being moved.
+  (func $take (param $t i32) (param $p i64)
+    (call $access (i32.const 1) (local.get $t) (local.get $p))
+    (call $revoke (call $last_permission)))
+
+  (func $give (param $t i32) (param $p i64) (result i32 i64)
+    (if (i32.and (global.get $traversed) (i32.const 2))
+      (then (return (call $share (local.get $t) (local.get $p)))))
+    (if (i32.and (global.get $traversed) (i32.const 4))
+      (then (return (call $lease (local.get $t) (local.get $p)))))
+    (call $take (local.get $t) (local.get $p))
+    (local.get $t)
+    (call $payload (call $object (local.get $p)) (call $new_permission (i32.const 1))))
+
+  (func $share (param $t i32) (param $p i64) (result i32 i64)
+    (local $last i32)
+    (call $access (i32.const 0) (local.get $t) (local.get $p))
+    (if (i32.eqz (call $is_object (local.get $t)))
+      (then (return (local.get $t) (local.get $p))))
+    (local.set $last (call $last_permission))
+    (if (i32.and (i32.load (local.get $last)) (i32.const 2))
+      (then (return (local.get $t) (local.get $p))))
+    (if (i32.and (global.get $traversed) (i32.const 4))
+      (then
+        (return
+          (local.get $t)
+          (call $payload
+            (call $object (local.get $p))
+            (call $new_tenant (i32.const 2) (local.get $last))))))
+    ;; Sharing a `my` value revokes the old permission.
+    (if (i32.eqz (i32.and (global.get $traversed) (i32.const 2)))
+      (then (call $take (local.get $t) (local.get $p))))
+    (local.get $t)
+    (call $payload (call $object (local.get $p)) (call $new_permission (i32.const 3))))
+
+  (func $lease (param $t i32) (param $p i64) (result i32 i64)
+    (local $last i32)
+    (call $access
+      (i32.eqz (i32.and (global.get $traversed) (i32.const 2)))
+      (local.get $t)
+      (local.get $p))
+    (if (i32.eqz (call $is_object (local.get $t)))
+      (then (return (local.get $t) (local.get $p))))
+    (local.set $last (call $last_permission))
+    (if (i32.and (i32.load (local.get $last)) (i32.const 2))
+      (then (return (local.get $t) (local.get $p))))
+    (if (i32.eq (i32.and (global.get $traversed) (i32.const 6)) (i32.const 2))
+      (then
+        (return
+          (local.get $t)
+          (call $payload (call $object (local.get $p)) (call $new_permission (i32.const 3))))))
+    (local.get $t)
+    (call $payload
+      (call $object (local.get $p))
+      (call $new_tenant (i32.and (global.get $traversed) (i32.const 2)) (local.get $last))))
+
+  (func $shlease (param $t i32) (param $p i64) (result i32 i64)
+    (local $last i32)
+    (call $access (i32.const 0) (local.get $t) (local.get $p))
+    (if (i32.eqz (call $is_object (local.get $t)))
+      (then (return (local.get $t) (local.get $p))))
+    (local.set $last (call $last_permission))
+    (if (i32.and (i32.load (local.get $last)) (i32.const 2))
+      (then (return (local.get $t) (local.get $p))))
+    (if (i32.eq (i32.and (global.get $traversed) (i32.const 6)) (i32.const 2))
+      (then
+        (return
+          (local.get $t)
+          (call $payload (call $object (local.get $p)) (call $new_permission (i32.const 3))))))
+    (local.get $t)
+    (call $payload
+      (call $object (local.get $p))
+      (call $new_tenant (i32.const 2) (local.get $last))))
+
+  ;; Prepares the value just traversed to be stored in a place with a
+  ;; specifier: 0 for none (or `any`), 1 for `my`, 2 for `our`, 3 for
+  ;; `leased`, and 4 for `shleased`. Traps if the result has fewer
+  ;; permissions than the specifier requires.
+  (func $prepare (param $t i32) (param $p i64) (param $specifier i32) (result i32 i64)
+    (local $flags i32)
+    (if (i32.le_u (local.get $specifier) (i32.const 1))
+      (then (call $give (local.get $t) (local.get $p)) (local.set $p) (local.set $t)))
+    (if (i32.eq (local.get $specifier) (i32.const 2))
+      (then (call $share (local.get $t) (local.get $p)) (local.set $p) (local.set $t)))
+    (if (i32.eq (local.get $specifier) (i32.const 3))
+      (then (call $lease (local.get $t) (local.get $p)) (local.set $p) (local.set $t)))
+    (if (i32.eq (local.get $specifier) (i32.const 4))
+      (then (call $shlease (local.get $t) (local.get $p)) (local.set $p) (local.set $t)))
+    (local.set $flags (i32.const 3))
+    (if (call $is_object (local.get $t))
+      (then (local.set $flags (i32.load (call $permission (local.get $p))))))
+    ;; `my` and `our` must be owned.
+    (if (i32.and
+          (i32.and (local.get $flags) (i32.const 4))
+          (i32.or
+            (i32.eq (local.get $specifier) (i32.const 1))
+            (i32.eq (local.get $specifier) (i32.const 2))))
+      (then unreachable))
+    ;; `my` and `leased` must be unique.
+    (if (i32.and
+          (i32.and (local.get $flags) (i32.const 2))
+          (i32.or
+            (i32.eq (local.get $specifier) (i32.const 1))
+            (i32.eq (local.get $specifier) (i32.const 3))))
+      (then unreachable))
+    (local.get $t)
+    (local.get $p))
+
+  ;; ## Printing
+  ;;
+  ;; Values are formatted the same way the interpreter formats them,
+  ;; into the free memory after the heap (`$out_len` bytes long), and
+  ;; then passed to the host.
+
+  (global $out_len (mut i32) (i32.const 0))
+
+  (func $print (param $t i32) (param $p i64)
+    (global.set $out_len (i32.const 0))
+    (call $format (local.get $t) (local.get $p))
+    (call $host_print (global.get $heap_ptr) (global.get $out_len)))
+
+  (func $put (param $byte i32)
+    (local $address i32)
+    (local.set $address (i32.add (global.get $heap_ptr) (global.get $out_len)))
+    (call $ensure (i32.add (local.get $address) (i32.const 1)))
+    (i32.store8 (local.get $address) (local.get $byte))
+    (global.set $out_len (i32.add (global.get $out_len) (i32.const 1))))
+
+  (func $put_bytes (param $ptr i32) (param $len i32)
+    (local $end i32)
+    (local.set $end (i32.add (local.get $ptr) (local.get $len)))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $ptr) (local.get $end)))
+        (call $put (i32.load8_u (local.get $ptr)))
+        (local.set $ptr (i32.add (local.get $ptr) (i32.const 1)))
+        (br $next))))
+
+  ;; Puts the first `$len` characters of `$chars`, lowest byte first
+  ;; (so `0x6968` is "hi").
+  (func $put_chars (param $chars i64) (param $len i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $len)))
+        (call $put (i32.wrap_i64 (i64.and (local.get $chars) (i64.const 255))))
+        (local.set $chars (i64.shr_u (local.get $chars) (i64.const 8)))
+        (local.set $len (i32.sub (local.get $len) (i32.const 1)))
+        (br $next))))
+
+  (func $put_u64 (param $n i64)
+    (if (i64.ge_u (local.get $n) (i64.const 10))
+      (then (call $put_u64 (i64.div_u (local.get $n) (i64.const 10)))))
+    (call $put (i32.add (i32.const 48) (i32.wrap_i64 (i64.rem_u (local.get $n) (i64.const 10))))))
+
+  (func $pow10 (param $k i32) (result i64)
+    (local $n i64)
+    (local.set $n (i64.const 1))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $k)))
+        (local.set $n (i64.mul (local.get $n) (i64.const 10)))
+        (local.set $k (i32.sub (local.get $k) (i32.const 1)))
+        (br $next)))
+    (local.get $n))
+
+  ;; Puts the shortest decimal form of `$x` that parses back to `$x`,
+  ;; like Rust's `Display` for `f64`: that is, the first (fewest digits
+  ;; after the point, or most zeros before it) `n * 10^k` that
+  ;; round-trips. Values too large or small for a `u64` of digits trap.
+  (func $put_f64 (param $x f64)
+    (local $k i32)
+    (local $scale f64)
+    (local $n f64)
+    (if (f64.ne (local.get $x) (local.get $x))
+      (then
+        (call $put_chars (i64.const 0x4e614e) (i32.const 3))
+        (return)))
+    (if (i64.lt_s (i64.reinterpret_f64 (local.get $x)) (i64.const 0))
+      (then
+        (call $put (i32.const 45))
+        (local.set $x (f64.neg (local.get $x)))))
+    (if (f64.eq (local.get $x) (f64.const inf))
+      (then
+        (call $put_chars (i64.const 0x666e69) (i32.const 3))
+        (return)))
+    (if (f64.eq (local.get $x) (f64.const 0))
+      (then
+        (call $put (i32.const 48))
+        (return)))
+    (if (f64.eq (local.get $x) (f64.floor (local.get $x)))
+      (then
+        (if (f64.ge (local.get $x) (f64.const 18446744073709551616))
+          (then unreachable))
+        (local.set $k (i32.const 19))
+        (loop $fewer_zeros
+          (local.set $scale (f64.convert_i64_u (call $pow10 (local.get $k))))
+          (local.set $n (f64.nearest (f64.div (local.get $x) (local.get $scale))))
+          (if (f64.ne (f64.mul (local.get $n) (local.get $scale)) (local.get $x))
+            (then
+              (local.set $k (i32.sub (local.get $k) (i32.const 1)))
+              (br $fewer_zeros))))
+        (call $put_u64 (i64.trunc_f64_u (local.get $n)))
+        (block $done
+          (loop $zero
+            (br_if $done (i32.eqz (local.get $k)))
+            (call $put (i32.const 48))
+            (local.set $k (i32.sub (local.get $k) (i32.const 1)))
+            (br $zero)))
+        (return)))
+    (local.set $k (i32.const 1))
+    (loop $more_digits
+      (if (i32.gt_u (local.get $k) (i32.const 19))
+        (then unreachable))
+      (local.set $scale (f64.convert_i64_u (call $pow10 (local.get $k))))
+      (local.set $n (f64.nearest (f64.mul (local.get $x) (local.get $scale))))
+      (if (f64.ge (local.get $n) (f64.const 18446744073709551616))
+        (then unreachable))
+      (if (f64.ne (f64.div (local.get $n) (local.get $scale)) (local.get $x))
+        (then
+          (local.set $k (i32.add (local.get $k) (i32.const 1)))
+          (br $more_digits))))
+    (call $put_u64 (i64.div_u (i64.trunc_f64_u (local.get $n)) (call $pow10 (local.get $k))))
+    (call $put (i32.const 46))
+    (loop $digit
+      (local.set $k (i32.sub (local.get $k) (i32.const 1)))
+      (call $put
+        (i32.add
+          (i32.const 48)
+          (i32.wrap_i64
+            (i64.rem_u
+              (i64.div_u (i64.trunc_f64_u (local.get $n)) (call $pow10 (local.get $k)))
+              (i64.const 10)))))
+      (br_if $digit (local.get $k))))
+
+  (func $format (param $t i32) (param $p i64)
+    (local $flags i32)
+    (local $field i32)
+    (local $end i32)
+    (if (i32.eqz (local.get $t))
+      (then
+        (call $put_chars (i64.const 0x2928) (i32.const 2))
+        (return)))
+    (if (i32.eq (local.get $t) (i32.const 1))
+      (then
+        (if (i64.eqz (local.get $p))
+          (then (call $put_chars (i64.const 0x65736c6166) (i32.const 5)))
+          (else (call $put_chars (i64.const 0x65757274) (i32.const 4))))
+        (return)))
+    (if (i32.eq (local.get $t) (i32.const 2))
+      (then
+        (call $put_u64 (local.get $p))
+        (return)))
+    (if (i32.eq (local.get $t) (i32.const 3))
+      (then
+        (call $put_u64 (local.get $p))
+        (call $put_chars (i64.const 0x755f) (i32.const 2))
+        (return)))
+    (if (i32.eq (local.get $t) (i32.const 4))
+      (then
+        (if (i64.lt_s (local.get $p) (i64.const 0))
+          (then
+            (call $put (i32.const 45))
+            ;; For `i64::MIN`, this wraps back to `i64::MIN`, whose
+            ;; unsigned value is the right magnitude.
+            (local.set $p (i64.sub (i64.const 0) (local.get $p)))))
+        (call $put_u64 (local.get $p))
+        (call $put_chars (i64.const 0x695f) (i32.const 2))
+        (return)))
+    (if (i32.eq (local.get $t) (i32.const 5))
+      (then
+        (call $put_bytes
+          (i32.wrap_i64 (i64.shr_u (local.get $p) (i64.const 32)))
+          (i32.wrap_i64 (local.get $p)))
+        (return)))
+    (if (i32.eq (local.get $t) (i32.const 8))
+      (then
+        (call $put_f64 (f64.reinterpret_i64 (local.get $p)))
+        (return)))
+    ;; Thunks cannot be printed.
+    (if (i32.eqz (call $is_object (local.get $t)))
+      (then unreachable))
+
+    (local.set $flags (i32.load (call $permission (local.get $p))))
+    (if (i32.eqz (i32.and (local.get $flags) (i32.const 1)))
+      (then
+        (call $put_chars (i64.const 0x6465726970786528) (i32.const 8))
+        (call $put (i32.const 41))
+        (return)))
+    (local.set $flags (i32.and (local.get $flags) (i32.const 6)))
+    (if (i32.eqz (local.get $flags))
+      (then (call $put_chars (i64.const 0x796d) (i32.const 2))))
+    (if (i32.eq (local.get $flags) (i32.const 2))
+      (then (call $put_chars (i64.const 0x72756f) (i32.const 3))))
+    (if (i32.eq (local.get $flags) (i32.const 4))
+      (then (call $put_chars (i64.const 0x64657361656c) (i32.const 6))))
+    (if (i32.eq (local.get $flags) (i32.const 6))
+      (then (call $put_chars (i64.const 0x64657361656c6873) (i32.const 8))))
+    (if (i32.eq (local.get $t) (i32.const 10))
+      (then
+        (call $put (i32.const 32))
+        (call $put_bytes (call $class_name (i32.load (call $object (local.get $p)))))))
+    (call $put (i32.const 40))
+    (local.set $field (i32.add (call $object (local.get $p)) (i32.const 8)))
+    (local.set $end
+      (i32.add
+        (local.get $field)
+        (i32.shl (i32.load offset=4 (call $object (local.get $p))) (i32.const 4))))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $field) (local.get $end)))
+        (if (i32.ne (local.get $field) (i32.add (call $object (local.get $p)) (i32.const 8)))
+          (then (call $put_chars (i64.const 0x202c) (i32.const 2))))
+        (call $format (i32.load (local.get $field)) (i64.load offset=8 (local.get $field)))
+        (local.set $field (i32.add (local.get $field) (i32.const 16)))
+        (br $next)))
+    (call $put (i32.const 41)))
+
+  ;; Awaits a thunk: either prints the value captured by a call to
+  ;; `print` or runs the suspended call to an `async fn`.
+  (func $await (param $t i32) (param $p i64) (result i32 i64)
+    (local $ptr i32)
+    (local.set $ptr (i32.wrap_i64 (local.get $p)))
+    (if (i32.eq (local.get $t) (i32.const 7))
+      (then
+        (call $print (i32.load (local.get $ptr)) (i64.load offset=8 (local.get $ptr)))
+        (return (i32.const 0) (i64.const 0))))
+    (if (i32.eq (local.get $t) (i32.const 6))
+      (then
+        (return
+          (call_indirect (type $dada_fn)
+            (i32.load offset=4 (local.get $ptr))
+            (i32.load (local.get $ptr))))))
+    unreachable)
@@ -0,0 +1,41 @@
+//! Every dada value is represented in wasm as a pair of a tag (an `i32`)
+//! and a payload (an `i64`). The tag says what kind of value it is and
+//! how to interpret the payload.
+
+/// `()`; the payload is unused.
+pub const UNIT: i32 = 0;
+
+/// A boolean; the payload is 0 or 1.
+pub const BOOL: i32 = 1;
+
+/// An unsuffixed integer; the payload is a `u64`.
+pub const INT: i32 = 2;
+
+/// An unsigned integer (e.g., `22_u`); the payload is a `u64`.
+pub const UNSIGNED_INT: i32 = 3;
+
+/// A signed integer (e.g., `22_i`); the payload is an `i64`.
+pub const SIGNED_INT: i32 = 4;
+
+/// A string; the upper 32 bits of the payload are the address of the
+/// UTF-8 data in the module's memory and the lower 32 bits its length.
+pub const STRING: i32 = 5;
+
+/// The result of calling an `async fn`; the payload is the address of
+/// the suspended call (function table index and argument block).
+pub const FUNCTION_THUNK: i32 = 6;
+
+/// The result of calling `print`; the payload is the address of the
+/// value to print.
+pub const PRINT_THUNK: i32 = 7;
+
+/// A float; the payload holds the bits of an `f64`.
+pub const FLOAT: i32 = 8;
+
+/// A tuple; the lower 32 bits of the payload are the address of the
+/// object and the upper 32 bits the address of its permission (see
+/// `runtime.wat` for their layout).
+pub const TUPLE: i32 = 9;
+
+/// An instance of a class; the payload is as for [`TUPLE`].
+pub const INSTANCE: i32 = 10;
@@ -0,0 +1,26 @@
+  ;; The part of the runtime used when compiling for WASI. WASI hosts have
+  ;; no `dada.print` import, so printed text is written to stdout with
+  ;; `fd_write`, followed by a newline. The first 16 bytes of memory are
+  ;; scratch space for this: an iovec at offset 0, the count of bytes
+  ;; written at offset 8, and the newline at offset 12.
+
+  (func $host_print (param $ptr i32) (param $len i32)
+    (call $write (local.get $ptr) (local.get $len))
+    (i32.store8 (i32.const 12) (i32.const 10))
+    (call $write (i32.const 12) (i32.const 1)))
+
+  ;; Writes `$len` bytes at `$ptr` to stdout, trapping if that fails.
+  (func $write (param $ptr i32) (param $len i32)
+    (local $remaining i32)
+    (local.set $remaining (local.get $len))
+    (block $done
+      (loop $more
+        (br_if $done (i32.eqz (local.get $remaining)))
+        (i32.store (i32.const 0) (local.get $ptr))
+        (i32.store (i32.const 4) (local.get $remaining))
+        (if (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))
+          (then unreachable))
+        ;; `fd_write` may write only part of the buffer.
+        (local.set $ptr (i32.add (local.get $ptr) (i32.load (i32.const 8))))
+        (local.set $remaining (i32.sub (local.get $remaining) (i32.load (i32.const 8))))
+        (br $more))))
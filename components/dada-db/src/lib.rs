@@ -1,19 +1,28 @@
-use dada_brew::prelude::MaybeBrewExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use dada_brew::prelude::{BrewExt, MaybeBrewExt};
+use dada_bytecode::DecodeError;
+use dada_collections::Set;
 use dada_ir::{
     diagnostic::Diagnostic,
     filename::Filename,
     function::Function,
+    intrinsic::Intrinsic,
     item::Item,
+    lint::{Lint, LintLevel},
+    manifest::Dependency,
+    source_text::SourceText,
     span::{FileSpan, LineColumn, Offset},
     word::Word,
 };
 use dada_parse::prelude::*;
 use dada_validate::prelude::*;
-use salsa::DebugWithDb;
+use salsa::{DebugWithDb, ParallelDatabase};
 
 #[salsa::db(
     dada_breakpoint::Jar,
     dada_brew::Jar,
+    dada_bytecode::Jar,
     dada_check::Jar,
     dada_error_format::Jar,
     dada_execute::Jar,
@@ -25,6 +34,13 @@ use salsa::DebugWithDb;
 #[derive(Default)]
 pub struct Db {
     storage: salsa::Storage<Self>,
+
+    /// Intrinsics that every file has besides the standard ones (see
+    /// [`Self::with_intrinsics`]).
+    intrinsics: Vec<Intrinsic>,
+
+    /// The files whose source text has been set (see [`Self::has_file`]).
+    files: Set<Filename>,
 }
 
 impl salsa::Database for Db {
@@ -37,27 +53,217 @@ impl salsa::ParallelDatabase for Db {
     fn snapshot(&self) -> salsa::Snapshot<Self> {
         salsa::Snapshot::new(Db {
             storage: self.storage.snapshot(),
+            intrinsics: self.intrinsics.clone(),
+            files: self.files.clone(),
         })
     }
 }
 
 impl Db {
-    pub fn update_file(&mut self, filename: Filename, source_text: String) {
+    /// Builder method: every file, including those already loaded, also
+    /// has the intrinsics named `names`, besides the standard ones (see
+    /// [`dada_ir::intrinsic`]). The interpreter runs each with the host
+    /// function of the same name (see `dada_execute::host_function`).
+    pub fn with_intrinsics(mut self, names: impl IntoIterator<Item = impl ToString>) -> Self {
+        let intrinsics: Vec<Intrinsic> = names
+            .into_iter()
+            .map(|name| Intrinsic::from(&self, name))
+            .filter(|intrinsic| !self.intrinsics.contains(intrinsic))
+            .collect();
+        if !intrinsics.is_empty() {
+            self.intrinsics.extend(intrinsics);
+            let filenames: Vec<Filename> = self.files.iter().copied().collect();
+            for filename in filenames {
+                self.set_intrinsics(filename);
+            }
+        }
+        self
+    }
+
+    /// Sets the intrinsics in scope in `filename` to the standard ones and
+    /// those added with [`Self::with_intrinsics`].
+    fn set_intrinsics(&mut self, filename: Filename) {
+        let db: &Db = self;
+        let intrinsics = dada_ir::intrinsic::STANDARD
+            .iter()
+            .map(|name| Intrinsic::from(db, name))
+            .chain(db.intrinsics.iter().copied())
+            .collect();
+        dada_ir::intrinsic::intrinsics::set(self, filename, intrinsics);
+    }
+
+    pub fn update_file(&mut self, filename: Filename, source_text: impl Into<SourceText>) {
+        dada_lex::reuse::reusable_tokens::set(self, filename, None);
+        dada_parse::reuse::reusable_items::set(self, filename, None);
+        // The intrinsics only change when more are added, which sets them
+        // for the files loaded by then.
+        if self.files.insert(filename) && !self.intrinsics.is_empty() {
+            self.set_intrinsics(filename);
+        }
+        dada_ir::manifest::source_text::set(self, filename, source_text.into())
+    }
+
+    /// Like [`Self::update_file`], for an edit that left the first
+    /// `unchanged` bytes of the file as they were. The tokens and items
+    /// within those bytes are reused rather than lexed and parsed again, and
+    /// so is everything that was memoized about those items.
+    ///
+    /// The edited text is usually made with [`SourceText::edit`], so that
+    /// only the edited lines are scanned again to find where lines start.
+    pub fn edit_file(&mut self, filename: Filename, source_text: SourceText, unchanged: Offset) {
+        let tokens = dada_lex::reuse::ReusableTokens::before_edit(self, filename, unchanged);
+        let items = dada_parse::reuse::ReusableItems::before_edit(self, filename, unchanged);
+        dada_lex::reuse::reusable_tokens::set(self, filename, Some(tokens));
+        dada_parse::reuse::reusable_items::set(self, filename, Some(items));
         dada_ir::manifest::source_text::set(self, filename, source_text)
     }
 
-    pub fn file_source(&self, filename: Filename) -> &String {
+    /// True if the source text of `filename` has been set, so that
+    /// [`Self::file_source`] can be called for it.
+    pub fn has_file(&self, filename: Filename) -> bool {
+        self.files.contains(&filename)
+    }
+
+    pub fn file_source(&self, filename: Filename) -> &SourceText {
         dada_ir::manifest::source_text(self, filename)
     }
 
+    /// Makes the host functions named `names` visible to the code in
+    /// `filename` (see `dada_execute::host_function`).
+    pub fn set_host_functions(&mut self, filename: Filename, names: Vec<Word>) {
+        dada_ir::manifest::host_functions::set(self, filename, names)
+    }
+
+    /// Makes the items of the packages in `dependencies` visible to the
+    /// code in `filename`, as `package.item`.
+    pub fn set_dependencies(&mut self, filename: Filename, dependencies: Vec<Dependency>) {
+        dada_ir::manifest::dependencies::set(self, filename, dependencies)
+    }
+
+    /// Sets the level of the lints in `levels` for the code in `filename`,
+    /// overriding their defaults (see [`dada_ir::lint`]).
+    pub fn set_lint_levels(&mut self, filename: Filename, levels: Vec<(Lint, LintLevel)>) {
+        dada_ir::lint::lint_levels::set(self, filename, levels)
+    }
+
+    /// Loads a program compiled by `dada build --target=bytecode` and
+    /// returns the filename of its source. The program's functions run
+    /// from the BIR stored in `bytes`, without lexing or parsing the source.
+    pub fn load_bytecode(&mut self, bytes: Vec<u8>) -> Result<Filename, DecodeError> {
+        let header = dada_bytecode::Header::read(&bytes)?;
+        let filename = Filename::from(self, header.filename);
+        dada_bytecode::bytecode::set(self, filename, bytes);
+        let module = dada_bytecode::decode_module(self, filename).clone()?;
+
+        self.update_file(filename, module.source_text);
+        dada_parse::loaded::loaded_items::set(self, filename, Some(module.items));
+        for loaded in module.functions {
+            let code = loaded.function.code(self);
+            if let Some(parameter_tokens) = code.parameter_tokens {
+                dada_parse::loaded::loaded_parameters::set(
+                    self,
+                    parameter_tokens,
+                    Some(loaded.parameters),
+                );
+            }
+            dada_parse::loaded::loaded_syntax_tree::set(self, code, Some(loaded.syntax_tree));
+            dada_brew::loaded::loaded_bir::set(self, loaded.function, Some(loaded.bir));
+        }
+        for loaded in module.classes {
+            let field_tokens = loaded.class.field_tokens(self);
+            dada_parse::loaded::loaded_parameters::set(self, field_tokens, Some(loaded.fields));
+        }
+
+        Ok(filename)
+    }
+
+    /// Encodes `filename`, and the BIR for its functions, in the format
+    /// read by [`Self::load_bytecode`].
+    pub fn bytecode(&self, filename: Filename) -> Vec<u8> {
+        dada_bytecode::encode(self, filename)
+    }
+
     /// Set the breakpoints within the given file where the interpreter stops and executes callbacks.
     pub fn set_breakpoints(&mut self, filename: Filename, locations: Vec<LineColumn>) {
         dada_breakpoint::locations::breakpoint_locations::set(self, filename, locations);
     }
 
+    /// Parses `filenames` and validates and brews their functions on `jobs`
+    /// threads, so that what needs them afterwards (like
+    /// [`Self::diagnostics`]) finds them already computed. Each thread
+    /// works on a snapshot of the database; when one needs a result that
+    /// another is computing, salsa has it wait for that result.
+    pub fn compile_in_parallel(&self, filenames: &[Filename], jobs: usize) {
+        let items = self.in_parallel(filenames, jobs, |db, &filename| {
+            filename.validate_root(db);
+            filename.items(db).clone()
+        });
+        let functions: Vec<Function> = items
+            .into_iter()
+            .flatten()
+            .filter_map(|item| match item {
+                Item::Function(function) => Some(function),
+                Item::Class(_) | Item::ExternFunction(_) => None,
+            })
+            .collect();
+        self.in_parallel(&functions, jobs, |db, &function| {
+            function.brew(db);
+        });
+    }
+
+    /// Calls `op` on each of `work` on up to `jobs` threads, returning the
+    /// results in the order of `work`.
+    fn in_parallel<T: Sync, R: Send>(
+        &self,
+        work: &[T],
+        jobs: usize,
+        op: impl Fn(&Db, &T) -> R + Sync,
+    ) -> Vec<R> {
+        // Each thread takes the next piece of work that nobody has started.
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<(usize, R)> = std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..jobs.max(1).min(work.len()))
+                .map(|_| {
+                    let db = self.snapshot();
+                    let (next, op) = (&next, &op);
+                    scope.spawn(move || {
+                        let mut results = vec![];
+                        loop {
+                            let index = next.fetch_add(1, Ordering::SeqCst);
+                            let Some(work) = work.get(index) else {
+                                return results;
+                            };
+                            results.push((index, op(&db, work)));
+                        }
+                    })
+                })
+                .collect();
+            threads
+                .into_iter()
+                .flat_map(|thread| {
+                    thread
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        });
+        results.sort_by_key(|&(index, _)| index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
     /// Checks `filename` for compilation errors and returns all relevant diagnostics.
     pub fn diagnostics(&self, filename: Filename) -> Vec<Diagnostic> {
-        dada_check::check_filename::accumulated::<dada_ir::diagnostic::Diagnostics>(self, filename)
+        dedup(dada_check::check_filename::accumulated::<
+            dada_ir::diagnostic::Diagnostics,
+        >(self, filename))
+    }
+
+    /// Runs the lints on `filename` and returns what they found, at the
+    /// level set for each lint. These are not included in [`Self::diagnostics`].
+    pub fn lint_diagnostics(&self, filename: Filename) -> Vec<Diagnostic> {
+        dedup(dada_check::lint_filename::accumulated::<
+            dada_ir::diagnostic::Diagnostics,
+        >(self, filename))
     }
 
     /// Checks `filename` for a "main" function
@@ -101,8 +307,18 @@ impl Db {
 
     /// Converts a `FileSpan` into its constituent parts.
     pub fn line_columns(&self, span: FileSpan) -> (Filename, LineColumn, LineColumn) {
-        let start = self.line_column(span.filename, span.start);
-        let end = self.line_column(span.filename, span.end);
+        let (start, end) = dada_ir::lines::line_columns(self, span);
         (span.filename, start, end)
     }
 }
+
+/// Removes the diagnostics that are the same as one earlier in
+/// `diagnostics`, as happens when a query that reports one is executed
+/// on behalf of more than one other query.
+fn dedup(diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    let mut seen = std::collections::HashSet::new();
+    diagnostics
+        .into_iter()
+        .filter(|diagnostic| seen.insert(diagnostic.clone()))
+        .collect()
+}
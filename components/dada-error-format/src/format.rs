@@ -1,7 +1,8 @@
-use std::io::Cursor;
+use std::io::{Cursor, Write};
+use std::sync::OnceLock;
 
 use ariadne::{Config, Label, Report, ReportKind, Source};
-use dada_ir::filename::Filename;
+use dada_ir::{diagnostic::Severity, filename::Filename, messages::Catalog};
 
 /// Options for controlling error formatting when they are printed.
 #[derive(Clone, Copy)]
@@ -9,28 +10,77 @@ pub struct FormatOptions {
     /// Whether or not errors should use rich formatting with colors. This is generally turned on,
     /// except in tests, where the escape codes obscure the error messages.
     with_color: bool,
+
+    /// Whether to draw the source snippets with ASCII characters, rather than
+    /// Unicode box-drawing characters, for logs and terminals that can't show
+    /// those.
+    ascii: bool,
+
+    /// The translations of the error messages, if they are not shown as
+    /// written (in English).
+    catalog: Option<&'static Catalog>,
 }
 
 impl FormatOptions {
     pub fn no_color() -> Self {
-        Self { with_color: false }
+        DEFAULT_FORMATTING.with_color(false)
+    }
+
+    pub fn with_color(self, with_color: bool) -> Self {
+        Self { with_color, ..self }
+    }
+
+    pub fn ascii(self, ascii: bool) -> Self {
+        Self { ascii, ..self }
+    }
+
+    /// Shows the error messages translated with `catalog` (see
+    /// [`dada_ir::messages`]).
+    pub fn catalog(self, catalog: Option<&'static Catalog>) -> Self {
+        Self { catalog, ..self }
+    }
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        DEFAULT_FORMATTING
     }
 }
 
-const DEFAULT_FORMATTING: FormatOptions = FormatOptions { with_color: true };
+const DEFAULT_FORMATTING: FormatOptions = FormatOptions {
+    with_color: true,
+    ascii: false,
+    catalog: None,
+};
+
+static DEFAULT_OPTIONS: OnceLock<FormatOptions> = OnceLock::new();
+
+/// Sets the options used by [`print_diagnostic`] and [`format_diagnostics`]
+/// (including for the errors that the interpreter reports), which are
+/// otherwise colored Unicode. Only the first call has any effect.
+pub fn set_default_options(options: FormatOptions) {
+    let _ = DEFAULT_OPTIONS.set(options);
+}
+
+fn default_options() -> FormatOptions {
+    DEFAULT_OPTIONS.get().copied().unwrap_or(DEFAULT_FORMATTING)
+}
 
 pub fn print_diagnostic(
     db: &dyn crate::Db,
     diagnostic: &dada_ir::diagnostic::Diagnostic,
 ) -> eyre::Result<()> {
-    Ok(ariadne_diagnostic(db, diagnostic, DEFAULT_FORMATTING)?.print(SourceCache::new(db))?)
+    let output =
+        format_diagnostics_with_options(db, std::slice::from_ref(diagnostic), default_options())?;
+    print!("{}", output);
+    Ok(())
 }
 
 pub fn format_diagnostics(
     db: &dyn crate::Db,
     diagnostics: &[dada_ir::diagnostic::Diagnostic],
 ) -> eyre::Result<String> {
-    format_diagnostics_with_options(db, diagnostics, DEFAULT_FORMATTING)
+    format_diagnostics_with_options(db, diagnostics, default_options())
 }
 
 pub fn format_diagnostics_with_options(
@@ -44,8 +94,35 @@ pub fn format_diagnostics_with_options(
     for diagnostic in diagnostics {
         let ariadne = ariadne_diagnostic(db, diagnostic, options)?;
         ariadne.write(&mut cache, &mut cursor)?;
+        cursor.write_all(format_fixes(db, diagnostic).as_bytes())?;
     }
-    Ok(String::from_utf8(output)?)
+    let output = String::from_utf8(output)?;
+    if options.ascii {
+        Ok(to_ascii(&output))
+    } else {
+        Ok(output)
+    }
+}
+
+/// Replaces the box-drawing characters in `output` with ASCII ones.
+fn to_ascii(output: &str) -> String {
+    output
+        .chars()
+        .map(|c| match c {
+            '─' => '-',
+            '│' | '├' | '┤' => '|',
+            '·' | '┆' => ':',
+            '╭' => ',',
+            '╮' => '.',
+            '╰' => '`',
+            '╯' => '\'',
+            '┬' => '|',
+            '┴' => '^',
+            '┼' => '+',
+            '▶' => '>',
+            c => c,
+        })
+        .collect()
 }
 
 fn ariadne_diagnostic(
@@ -53,13 +130,22 @@ fn ariadne_diagnostic(
     diagnostic: &dada_ir::diagnostic::Diagnostic,
     options: FormatOptions,
 ) -> eyre::Result<ariadne::Report<ASpan>> {
-    let mut builder = Report::<ASpan>::build(
-        ReportKind::Error,
-        diagnostic.span.filename,
-        diagnostic.span.start.into(),
-    )
-    .with_message(&diagnostic.message)
-    .with_config(Config::default().with_color(options.with_color));
+    let kind = match diagnostic.severity {
+        Severity::Error => ReportKind::Error,
+        Severity::Warning => ReportKind::Warning,
+        Severity::Note | Severity::Help => ReportKind::Advice,
+    };
+    let mut builder =
+        Report::<ASpan>::build(kind, diagnostic.span.filename, diagnostic.span.start.into())
+            .with_message(match options.catalog {
+                Some(catalog) => catalog.message(diagnostic),
+                None => diagnostic.message.clone(),
+            })
+            .with_config(Config::default().with_color(options.with_color));
+
+    if let Some(code) = diagnostic.code {
+        builder = builder.with_code(code);
+    }
 
     for label in &diagnostic.labels {
         builder = builder.with_label(Label::new(ASpan(label.span())).with_message(&label.message));
@@ -68,6 +154,32 @@ fn ariadne_diagnostic(
     Ok(builder.finish())
 }
 
+/// Renders the fixes of `diagnostic` as `help:` lines, each followed (if
+/// it edits a single line) by how that line would read after the edit.
+fn format_fixes(db: &dyn crate::Db, diagnostic: &dada_ir::diagnostic::Diagnostic) -> String {
+    let mut output = String::new();
+    for fix in &diagnostic.fixes {
+        output.push_str(&format!("help: {}\n", fix.message));
+
+        let (start, end) = dada_ir::lines::line_columns(db, fix.span);
+        if start.line0() != end.line0() {
+            continue;
+        }
+        let source_text = dada_ir::manifest::source_text(db, fix.span.filename);
+        let Some(line) = source_text.lines().nth(start.line0_usize()) else {
+            continue;
+        };
+        output.push_str(&format!(
+            " {} │ {}{}{}\n",
+            start.line1(),
+            &line[..start.column0() as usize],
+            fix.replacement,
+            &line[end.column0() as usize..],
+        ));
+    }
+    output
+}
+
 struct SourceCache<'me> {
     db: &'me dyn crate::Db,
     map: dada_collections::Map<Filename, Source>,
@@ -86,7 +198,7 @@ impl ariadne::Cache<Filename> for SourceCache<'_> {
     fn fetch(&mut self, id: &Filename) -> Result<&Source, Box<dyn std::fmt::Debug + '_>> {
         Ok(self.map.entry(*id).or_insert_with(|| {
             let source_text = dada_ir::manifest::source_text(self.db, *id);
-            Source::from(source_text)
+            Source::from(source_text.as_str())
         }))
     }
 
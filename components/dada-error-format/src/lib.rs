@@ -13,4 +13,5 @@ impl<T> Db for T where T: salsa::DbWithJar<Jar> + dada_ir::Db {}
 pub use format::format_diagnostics;
 pub use format::format_diagnostics_with_options;
 pub use format::print_diagnostic;
+pub use format::set_default_options;
 pub use format::FormatOptions;
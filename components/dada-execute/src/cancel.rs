@@ -0,0 +1,32 @@
+//! Cancelling execution from outside of the interpreter, e.g. when the
+//! user of the playground stops a program that runs forever. A
+//! [`CancellationToken`] is given to the interpreter in its
+//! [`ExecutionConfig`](crate::config::ExecutionConfig), and the interpreter
+//! checks it before each step.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Shared between the interpreter and whoever may want to cancel it; all
+/// clones of a token are cancelled together.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops execution, with an error, before its next step.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,107 @@
+//! Capabilities control which intrinsics a program is permitted to use.
+//! Embedders that run untrusted code can disable capabilities through the
+//! [`ExecutionConfig`](crate::config::ExecutionConfig); calling an intrinsic
+//! that requires a disabled capability is a runtime error.
+//!
+//! The `ffi` capability, which lets a program call `extern` functions, is
+//! the exception: it must be granted explicitly.
+
+use std::{fmt, str::FromStr};
+
+/// Something in the outside world that an intrinsic may access.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Capability {
+    Print,
+    Ffi,
+}
+
+impl Capability {
+    pub const ALL: &'static [Capability] = &[Capability::Print, Capability::Ffi];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Capability::Print => "print",
+            Capability::Ffi => "ffi",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << (self as u8)
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Capability {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        Capability::ALL
+            .iter()
+            .copied()
+            .find(|c| c.as_str() == s)
+            .ok_or_else(|| eyre::eyre!("unknown capability `{}`", s))
+    }
+}
+
+/// A set of capabilities. By default, every capability except
+/// [`Capability::Ffi`] is granted.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    bits: u8,
+}
+
+impl Capabilities {
+    /// The set containing every capability.
+    pub fn all() -> Self {
+        Capability::ALL
+            .iter()
+            .fold(Self::none(), |set, &c| set.with(c))
+    }
+
+    /// The empty set: only intrinsics that need no capabilities may be used.
+    pub fn none() -> Self {
+        Self { bits: 0 }
+    }
+
+    /// Returns this set with `capability` added.
+    pub fn with(self, capability: Capability) -> Self {
+        Self {
+            bits: self.bits | capability.bit(),
+        }
+    }
+
+    /// Returns this set with `capability` removed.
+    pub fn without(self, capability: Capability) -> Self {
+        Self {
+            bits: self.bits & !capability.bit(),
+        }
+    }
+
+    pub fn contains(self, capability: Capability) -> bool {
+        self.bits & capability.bit() != 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Capability> {
+        Capability::ALL
+            .iter()
+            .copied()
+            .filter(move |&c| self.contains(c))
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::all().without(Capability::Ffi)
+    }
+}
+
+impl fmt::Debug for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,49 @@
+//! The clock that the interpreter uses to timestamp profiling and
+//! tracing events.
+//!
+//! Normally this is the wall clock. In deterministic mode (see
+//! [`ExecutionConfig::deterministic`]) it is a logical clock that advances
+//! by one microsecond per step, so that repeated runs of the same program
+//! produce identical profiles and traces.
+//!
+//! [`ExecutionConfig::deterministic`]: crate::config::ExecutionConfig::deterministic
+
+use std::time::{Duration, Instant};
+
+pub(crate) struct Clock {
+    /// If true, time is measured in steps rather than read from the system.
+    deterministic: bool,
+
+    /// Number of steps taken so far.
+    steps: u64,
+
+    /// Instant at which the clock was first read. This is only
+    /// initialized on first use, because the system clock is not
+    /// available on all targets.
+    start: Option<Instant>,
+}
+
+impl Clock {
+    pub(crate) fn new(deterministic: bool) -> Self {
+        Self {
+            deterministic,
+            steps: 0,
+            start: None,
+        }
+    }
+
+    /// Records that the interpreter has completed a step.
+    pub(crate) fn tick(&mut self) {
+        self.steps += 1;
+    }
+
+    /// Returns the time elapsed since the clock was first read
+    /// (or, in deterministic mode, since execution began).
+    pub(crate) fn now(&mut self) -> Duration {
+        if self.deterministic {
+            Duration::from_micros(self.steps)
+        } else {
+            self.start.get_or_insert_with(Instant::now).elapsed()
+        }
+    }
+}
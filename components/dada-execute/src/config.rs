@@ -0,0 +1,121 @@
+//! Configuration that controls how the interpreter executes a program.
+//! The configuration is supplied by the [`Kernel`](crate::kernel::Kernel).
+
+use crate::{cancel::CancellationToken, capability::Capabilities, host_function::HostFunctions};
+
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionConfig {
+    /// See [`Self::gc_threshold`].
+    pub(crate) gc_threshold: usize,
+
+    /// See [`Self::max_objects`].
+    pub(crate) max_objects: Option<usize>,
+
+    /// See [`Self::max_string_bytes`].
+    pub(crate) max_string_bytes: Option<usize>,
+
+    /// See [`Self::capabilities`].
+    pub(crate) capabilities: Capabilities,
+
+    /// See [`Self::deterministic`].
+    pub(crate) deterministic: bool,
+
+    /// See [`Self::validate_jit`].
+    pub(crate) validate_jit: bool,
+
+    /// See [`Self::host_functions`].
+    pub(crate) host_functions: HostFunctions,
+
+    /// See [`Self::args`].
+    pub(crate) args: Vec<String>,
+
+    /// See [`Self::cancellation`].
+    pub(crate) cancellation: Option<CancellationToken>,
+}
+
+impl ExecutionConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder method: the garbage collector only runs once the heap has
+    /// grown by at least `gc_threshold` objects since the last collection
+    /// (it always runs when a function returns).
+    ///
+    /// The default threshold, 0, collects after every step. This is the
+    /// precise mode: leases are cancelled at the moment their lessor becomes
+    /// unreachable. Larger thresholds make execution faster, but such
+    /// cancellations (and the errors that follow from them) may be reported
+    /// later than they would otherwise be.
+    pub fn gc_threshold(mut self, gc_threshold: usize) -> Self {
+        self.gc_threshold = gc_threshold;
+        self
+    }
+
+    /// Builder method: execution fails with a "memory limit exceeded"
+    /// error if more than `max_objects` objects are live at once.
+    pub fn max_objects(mut self, max_objects: usize) -> Self {
+        self.max_objects = Some(max_objects);
+        self
+    }
+
+    /// Builder method: execution fails with a "memory limit exceeded"
+    /// error if the live strings occupy more than `max_string_bytes`
+    /// bytes in total.
+    pub fn max_string_bytes(mut self, max_string_bytes: usize) -> Self {
+        self.max_string_bytes = Some(max_string_bytes);
+        self
+    }
+
+    /// Builder method: sets the capabilities granted to the program.
+    /// Calling an intrinsic that requires a capability not in this set
+    /// is an error. All capabilities except `ffi` are granted by default.
+    pub fn capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Builder method: in deterministic mode, the interpreter never consults
+    /// the system clock. The timestamps recorded by the profiler and tracer
+    /// come from a logical clock that advances by one microsecond per step,
+    /// so repeated runs of a program produce identical output.
+    ///
+    /// The clock is the only thing this mode changes, because it is the
+    /// only source of nondeterminism: there are no random numbers, and the
+    /// [scheduler](crate::scheduler) runs the task of each awaited thunk to
+    /// completion, in the order that the program awaits them.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Builder method: when validating the JIT, calls that the
+    /// [JIT](crate::jit) executes are also interpreted, and execution fails
+    /// with an error if the two produce different results. Note that this
+    /// means such calls are executed twice.
+    pub fn validate_jit(mut self, validate_jit: bool) -> Self {
+        self.validate_jit = validate_jit;
+        self
+    }
+
+    /// Builder method: the [host functions](crate::host_function) that the
+    /// program can call. There are none by default.
+    pub fn host_functions(mut self, host_functions: HostFunctions) -> Self {
+        self.host_functions = host_functions;
+        self
+    }
+
+    /// Builder method: the arguments given to the program, which it reads
+    /// with the `arg` and `arg_count` intrinsics. There are none by default.
+    pub fn args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Builder method: execution stops with an error before the next step
+    /// once `token` is cancelled.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+}
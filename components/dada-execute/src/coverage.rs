@@ -0,0 +1,95 @@
+//! Code coverage: records which BIR statements and terminators are executed,
+//! so that they can be mapped back (through the BIR origins) to the source
+//! lines that were and were not run.
+//!
+//! Coverage is enabled by returning a recorder from [`Kernel::coverage`].
+//! Functions that the JIT executes are not stepped by the interpreter, and
+//! so their statements are not recorded.
+//!
+//! [`Kernel::coverage`]: crate::kernel::Kernel::coverage
+
+use std::collections::BTreeMap;
+
+use dada_brew::prelude::*;
+use dada_collections::{Map, Set};
+use dada_ir::{code::bir, filename::Filename, item::Item};
+use dada_parse::prelude::*;
+
+use crate::machine::ProgramCounter;
+
+#[derive(Debug, Default)]
+pub struct Coverage {
+    /// The number of times each statement (or terminator) was executed.
+    executed: Map<ProgramCounter, u64>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the statement (or terminator) at `pc` was executed once.
+    pub(crate) fn record(&mut self, pc: ProgramCounter) {
+        *self.executed.entry(pc).or_default() += 1;
+    }
+
+    /// For each line of `filename` (by 1-based line number) where some
+    /// statement of its functions starts, the number of times statements
+    /// starting on that line were executed, which is zero for lines that
+    /// were never run. Statements in basic blocks that control can never
+    /// reach (e.g., after a `return`) are left out.
+    pub fn line_hits(&self, db: &dyn crate::Db, filename: Filename) -> BTreeMap<u32, u64> {
+        let mut lines = BTreeMap::new();
+        for &item in filename.items(db) {
+            let Item::Function(function) = item else {
+                continue;
+            };
+            let bir = function.brew(db);
+            let bir_data = bir.data(db);
+            for basic_block in reachable_basic_blocks(bir_data) {
+                let statements = bir_data.tables[basic_block].statements.len();
+                for statement in 0..=statements {
+                    let pc = ProgramCounter {
+                        bir,
+                        basic_block,
+                        statement,
+                    };
+                    let span = pc.span(db);
+                    let start = dada_ir::lines::line_column(db, span.filename, span.start);
+                    let count = self.executed.get(&pc).copied().unwrap_or_default();
+                    *lines.entry(start.line1()).or_default() += count;
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// The basic blocks that control can reach from the start block.
+fn reachable_basic_blocks(bir_data: &bir::BirData) -> Vec<bir::BasicBlock> {
+    let mut reachable = Set::default();
+    let mut stack = vec![bir_data.start_basic_block];
+    while let Some(basic_block) = stack.pop() {
+        if !reachable.insert(basic_block) {
+            continue;
+        }
+        let terminator = bir_data.tables[basic_block].terminator;
+        match &bir_data.tables[terminator] {
+            bir::TerminatorData::Goto(next)
+            | bir::TerminatorData::StartAtomic(next)
+            | bir::TerminatorData::EndAtomic(next)
+            | bir::TerminatorData::Assign(_, _, next) => stack.push(*next),
+            bir::TerminatorData::If(_, if_true, if_false) => {
+                stack.push(*if_true);
+                stack.push(*if_false);
+            }
+            bir::TerminatorData::Return(_)
+            | bir::TerminatorData::Error
+            | bir::TerminatorData::Panic => {}
+        }
+    }
+    bir_data
+        .all_basic_blocks()
+        .filter(|basic_block| reachable.contains(basic_block))
+        .collect()
+}
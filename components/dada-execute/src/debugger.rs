@@ -0,0 +1,449 @@
+//! A programmatic interface for debuggers: run a program one step at a
+//! time and inspect its state in between.
+//!
+//! Unlike breakpoints (see [`Kernel::breakpoint_start`]), which report a
+//! heap graph when execution passes a marked expression, a [`Debugger`]
+//! owns the machine and only runs the program when asked, so a front end
+//! (a DAP server, a command-line debugger) can decide what to do after
+//! each step.
+//!
+//! Frames are numbered from the innermost outward: frame 0 is the
+//! function that is currently executing.
+//!
+//! [`Kernel::breakpoint_start`]: crate::kernel::Kernel::breakpoint_start
+
+use dada_brew::prelude::*;
+use dada_collections::IndexVec;
+use dada_ir::{
+    code::{bir, validated::LocalVariableOrigin},
+    error,
+    function::Function,
+    span::{FileSpan, Offset},
+};
+use dada_parse::prelude::*;
+
+use crate::{
+    ext::DadaExecuteClassExt,
+    heap_graph::HeapGraph,
+    host_value::HostValue,
+    kernel::Kernel,
+    machine::{
+        op::MachineOp, stringify::DefaultStringify, Frame, FrameIndex, Machine, ObjectData,
+        ProgramCounter, Value,
+    },
+    scheduler::{InlineScheduler, Scheduler},
+    snapshot::Snapshot,
+    step::{ControlFlow, Stepper},
+    thunk::RustThunk,
+};
+
+/// A program paused between steps.
+pub struct Debugger {
+    machine: Machine,
+
+    /// A Rust thunk that was about to be awaited when the debugger was
+    /// created from a snapshot; it is awaited by the next step.
+    pending_thunk: Option<RustThunk>,
+
+    /// True once the outermost function has returned.
+    completed: bool,
+}
+
+/// What happened during a step.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// The program is paused before the statement at this span.
+    Paused(FileSpan),
+
+    /// The program ran to completion; stepping further does nothing.
+    Completed,
+}
+
+/// A function on the call stack.
+#[derive(Clone, Debug)]
+pub struct FrameInfo {
+    pub function: Function,
+
+    /// The span of the statement the frame will execute next.
+    pub span: FileSpan,
+}
+
+/// A local variable in some frame.
+#[derive(Clone, Debug)]
+pub struct LocalInfo {
+    pub name: String,
+
+    /// The value formatted as `print` would format it, or `(expired)` if
+    /// the variable has no permission (e.g., because it has not been
+    /// assigned yet or its value was given away).
+    pub value: String,
+}
+
+impl Debugger {
+    /// Prepares to call `function` with `arguments`, pausing before its
+    /// first statement.
+    pub fn new(
+        db: &dyn crate::Db,
+        function: Function,
+        arguments: Vec<HostValue>,
+    ) -> eyre::Result<Self> {
+        let parameters = function.parameters(db);
+        if parameters.len() != arguments.len() {
+            return Err(error!(
+                ArgumentCountMismatch,
+                function.name(db).span(db),
+                "expected to find {} arguments, but found {}",
+                parameters.len(),
+                arguments.len(),
+            )
+            .eyre(db));
+        }
+
+        let mut machine = Machine::default();
+        let arguments = arguments
+            .into_iter()
+            .map(|argument| argument.into_value(db, function.filename(db), &mut machine))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        machine.push_frame(db, function.brew(db), arguments);
+        Ok(Self {
+            machine,
+            pending_thunk: None,
+            completed: false,
+        })
+    }
+
+    /// Prepares to continue a program that was suspended (see
+    /// [`crate::snapshot`]). The snapshot must come from a program in the
+    /// database passed to the other methods.
+    pub fn from_snapshot(snapshot: Snapshot) -> Self {
+        let Snapshot {
+            machine,
+            pending_thunk,
+        } = snapshot;
+        Self {
+            machine,
+            pending_thunk,
+            completed: false,
+        }
+    }
+
+    /// Captures the paused program, so that it can be continued from this
+    /// point again later (see [`Self::from_snapshot`]); this is how a front
+    /// end steps backwards. Returns `None` once the program has completed.
+    pub fn snapshot(&self) -> Option<Snapshot> {
+        if self.completed {
+            return None;
+        }
+        Some(Snapshot {
+            machine: self.machine.clone(),
+            pending_thunk: self.pending_thunk.clone(),
+        })
+    }
+
+    /// True once the program has run to completion.
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+
+    /// Continues the paused program in the current version of the function
+    /// it is paused in, after code was added to that function at `offset`,
+    /// where the program is paused (this is how a REPL runs a statement
+    /// that was just entered). The program must be paused in its outermost
+    /// function, between statements, and the code before `offset` must not
+    /// have changed, so that it compiles the same way it did.
+    ///
+    /// The variables declared before `offset` keep their values; the
+    /// temporaries, which are not in use between statements, are reset.
+    /// Returns an error, leaving the program as it was, if it cannot be
+    /// continued.
+    pub fn continue_after_edit(&mut self, db: &dyn crate::Db, offset: Offset) -> eyre::Result<()> {
+        if self.completed || self.pending_thunk.is_some() {
+            eyre::bail!("the program is not paused between statements");
+        }
+        let [frame] = self.machine.frames().as_raw_slice() else {
+            eyre::bail!("the program is not paused in its outermost function");
+        };
+
+        // The code before `offset` compiles the same way, so the basic block
+        // the program is paused in is the same, and the added code starts
+        // in it.
+        let function = frame.pc.bir.origin(db);
+        let bir = function.brew(db);
+        let bir_data = bir.data(db);
+        let basic_block = frame.pc.basic_block;
+        if basic_block >= bir_data.max_basic_block() {
+            eyre::bail!("the code before the edit has changed");
+        }
+        let statements = bir_data.tables[basic_block].statements.len();
+        let Some(statement) = (0..=statements).find(|&statement| {
+            let pc = ProgramCounter {
+                bir,
+                basic_block,
+                statement,
+            };
+            pc.span(db).start >= offset
+        }) else {
+            eyre::bail!("there is no code after the edit");
+        };
+
+        let origins = bir.origins(db);
+        let syntax_tree = function.syntax_tree(db);
+        let spans = syntax_tree.spans(db);
+        let declared_before = |local_variable: bir::LocalVariable| match origins[local_variable] {
+            LocalVariableOrigin::LocalVariable(decl) | LocalVariableOrigin::Parameter(decl) => {
+                syntax_tree.file_span(db, spans[decl].name_span).start < offset
+            }
+            LocalVariableOrigin::Temporary(_) => false,
+        };
+        let old_locals = frame.locals.clone();
+        let expired = Value {
+            object: self.machine.unit_object(),
+            permission: self.machine.expired_permission(None),
+        };
+        let locals: IndexVec<bir::LocalVariable, Value> = bir_data
+            .max_local_variable()
+            .iter()
+            .map(|local_variable| match old_locals.get(local_variable) {
+                Some(&value) if declared_before(local_variable) => value,
+                _ => expired,
+            })
+            .collect();
+
+        let frame = &mut self.machine.stack.frames[FrameIndex::from(0_usize)];
+        frame.pc = ProgramCounter {
+            bir,
+            basic_block,
+            statement,
+        };
+        frame.locals = locals;
+        Ok(())
+    }
+
+    /// If the program is paused at the end of its outermost function, the
+    /// value that function is about to return, formatted as `print` would
+    /// format it.
+    pub fn return_value(&self, db: &dyn crate::Db) -> Option<String> {
+        if self.completed {
+            return None;
+        }
+        let [frame] = self.machine.frames().as_raw_slice() else {
+            return None;
+        };
+        let bir_data = frame.pc.bir.data(db);
+        let basic_block_data = &bir_data.tables[frame.pc.basic_block];
+        if frame.pc.statement < basic_block_data.statements.len() {
+            return None;
+        }
+        let bir::TerminatorData::Return(place) = bir_data.tables[basic_block_data.terminator]
+        else {
+            return None;
+        };
+        let bir::PlaceData::LocalVariable(local_variable) = bir_data.tables[place] else {
+            return None;
+        };
+        Some(
+            self.machine
+                .stringify_value(db, frame.locals[local_variable]),
+        )
+    }
+
+    /// Executes a single statement (or terminator). Calling a function
+    /// stops before the first statement of the callee.
+    pub async fn step(
+        &mut self,
+        db: &dyn crate::Db,
+        kernel: &mut dyn Kernel,
+    ) -> eyre::Result<StepOutcome> {
+        self.step_while(db, kernel, |_| false).await
+    }
+
+    /// Executes a single statement of the current function, running any
+    /// function it calls to completion.
+    pub async fn step_over(
+        &mut self,
+        db: &dyn crate::Db,
+        kernel: &mut dyn Kernel,
+    ) -> eyre::Result<StepOutcome> {
+        let depth = self.machine.frames().len();
+        self.step_while(db, kernel, |machine| machine.frames().len() > depth)
+            .await
+    }
+
+    /// Steps once and then keeps stepping while `keep_going` holds.
+    async fn step_while(
+        &mut self,
+        db: &dyn crate::Db,
+        kernel: &mut dyn Kernel,
+        keep_going: impl Fn(&dyn MachineOp) -> bool,
+    ) -> eyre::Result<StepOutcome> {
+        if self.completed {
+            return Ok(StepOutcome::Completed);
+        }
+
+        let scheduler = &mut InlineScheduler;
+        let mut stepper = Stepper::new(db, &mut self.machine, kernel);
+        let result = loop {
+            if let Some(t) = self.pending_thunk.take() {
+                scheduler.run(Box::pin(t.invoke(&mut stepper))).await?;
+            }
+
+            match stepper.step()? {
+                ControlFlow::Next => (),
+                ControlFlow::Await(t) => self.pending_thunk = Some(t),
+                ControlFlow::Done(pc, v) => {
+                    scheduler
+                        .run(Box::pin(stepper.print_if_not_unit(pc, v)))
+                        .await?;
+                    break StepOutcome::Completed;
+                }
+            }
+
+            // Finish awaiting a thunk before pausing, so that the paused
+            // program is never halfway through a statement.
+            if self.pending_thunk.is_none() && !keep_going(stepper.machine()) {
+                break StepOutcome::Paused(stepper.machine().pc().span(db));
+            }
+        };
+
+        // Record allocations after every step, so that snapshots of the
+        // paused program have none left to count again.
+        stepper.record_allocations();
+        if result == StepOutcome::Completed {
+            self.completed = true;
+        }
+        Ok(result)
+    }
+
+    /// The functions on the call stack, innermost first. Empty once the
+    /// program has completed.
+    pub fn frames(&self, db: &dyn crate::Db) -> Vec<FrameInfo> {
+        if self.completed {
+            return vec![];
+        }
+        self.machine
+            .frames()
+            .iter()
+            .rev()
+            .map(|frame| FrameInfo {
+                function: frame.pc.bir.origin(db),
+                span: frame.pc.span(db),
+            })
+            .collect()
+    }
+
+    /// The named local variables of frame `frame`, in the order they are
+    /// declared. Returns `None` if there is no such frame.
+    pub fn locals(&self, db: &dyn crate::Db, frame: usize) -> Option<Vec<LocalInfo>> {
+        let frame = self.frame(frame)?;
+        let bir_data = frame.pc.bir.data(db);
+        Some(
+            frame
+                .locals
+                .iter_enumerated()
+                .filter_map(|(local_variable, &value)| {
+                    let name = local_variable.data(&bir_data.tables).name?;
+                    Some(LocalInfo {
+                        name: name.as_str(db).to_string(),
+                        value: self.machine.stringify_value(db, value),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// A graph of the stack and of the objects reachable from it, as
+    /// breakpoints report. Returns `None` once the program has completed.
+    pub fn heap_graph(&self, db: &dyn crate::Db) -> Option<HeapGraph> {
+        if self.completed {
+            return None;
+        }
+        Some(HeapGraph::new(db, &self.machine, None))
+    }
+
+    /// Evaluates `expression` in frame `frame`, returning the result
+    /// formatted as `print` would format it. Only places are supported: a
+    /// local variable followed by any number of field accesses, like
+    /// `p.x` or `pair.0`.
+    pub fn evaluate(
+        &self,
+        db: &dyn crate::Db,
+        frame: usize,
+        expression: &str,
+    ) -> eyre::Result<String> {
+        let Some(frame_data) = self.frame(frame) else {
+            eyre::bail!("there is no frame {}", frame);
+        };
+
+        let mut names = expression.split('.').map(str::trim);
+        let variable = names.next().unwrap_or_default();
+        let bir_data = frame_data.pc.bir.data(db);
+
+        // If several variables have this name (e.g., in different blocks),
+        // the one declared last is the one most likely in scope.
+        let Some(mut value) = frame_data
+            .locals
+            .iter_enumerated()
+            .rev()
+            .find(|(local_variable, _)| {
+                local_variable
+                    .data(&bir_data.tables)
+                    .name
+                    .map(|n| n.as_str(db))
+                    == Some(variable)
+            })
+            .map(|(_, &value)| value)
+        else {
+            eyre::bail!("no variable named `{}` in frame {}", variable, frame);
+        };
+
+        for name in names {
+            value = self.field(db, value, name)?;
+        }
+        Ok(self.machine.stringify_value(db, value))
+    }
+
+    /// Reads the field `name` of the object that `value` refers to.
+    fn field(&self, db: &dyn crate::Db, value: Value, name: &str) -> eyre::Result<Value> {
+        if self.machine[value.permission].valid().is_none() {
+            eyre::bail!("cannot access `{}` through an expired permission", name);
+        }
+
+        let (index, fields) = match &self.machine[value.object] {
+            ObjectData::Instance(instance) => (
+                instance
+                    .class
+                    .field_names(db)
+                    .iter()
+                    .position(|field| field.as_str(db) == name),
+                &instance.fields,
+            ),
+            ObjectData::Tuple(tuple) => (name.parse::<usize>().ok(), &tuple.fields),
+            data => eyre::bail!("{} has no fields", data.kind_str(db)),
+        };
+
+        match index.and_then(|index| fields.get(index)) {
+            Some(&field) => Ok(field),
+            None => eyre::bail!(
+                "{} has no field `{}`",
+                self.machine[value.object].kind_str(db),
+                name
+            ),
+        }
+    }
+
+    fn frame(&self, frame: usize) -> Option<&Frame> {
+        if self.completed {
+            return None;
+        }
+        self.machine.frames().iter().rev().nth(frame)
+    }
+}
+
+impl std::fmt::Debug for Debugger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Debugger")
+            .field("completed", &self.completed)
+            .field("pending_thunk", &self.pending_thunk.is_some())
+            .finish()
+    }
+}
@@ -33,3 +33,19 @@ impl std::fmt::Display for DiagnosticError {
         write!(f, "{}", self.string)
     }
 }
+
+/// The error that a program stops with when it calls `exit(code)`. This is
+/// not a failure of the interpreter: embedders can look for it (with
+/// [`eyre::Report::downcast_ref`]) to find the code the program exited with.
+#[derive(Debug)]
+pub struct Exit {
+    pub code: i32,
+}
+
+impl std::error::Error for Exit {}
+
+impl std::fmt::Display for Exit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the program exited with code {}", self.code)
+    }
+}
@@ -118,10 +118,15 @@ impl<'me> HeapGraphCapture<'me> {
             ObjectData::Class(c) => ValueEdgeTarget::Class(*c),
             ObjectData::Function(f) => ValueEdgeTarget::Function(*f),
             ObjectData::Intrinsic(_)
+            | ObjectData::HostFunction(_)
+            | ObjectData::ExternFunction(_)
             | ObjectData::Bool(_)
             | ObjectData::UnsignedInt(_)
             | ObjectData::Int(_)
             | ObjectData::SignedInt(_)
+            | ObjectData::BigInt(_)
+            | ObjectData::Weak(_)
+            | ObjectData::WeakGet(_)
             | ObjectData::Float(_)
             | ObjectData::String(_)
             | ObjectData::Unit(_) => {
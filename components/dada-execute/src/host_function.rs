@@ -0,0 +1,81 @@
+//! Host functions are functions implemented in Rust by the program that
+//! embeds Dada. They are registered in the
+//! [`ExecutionConfig`](crate::config::ExecutionConfig) and, once their names
+//! are made visible to the compiler (see `dada_ir::manifest::host_functions`),
+//! Dada code calls them like any other function. Intrinsics that the
+//! embedder adds to the database (see `dada_db::Db::with_intrinsics`) are
+//! run by the host function of the same name, too.
+//!
+//! Calling a synchronous host function runs it immediately. Calling an
+//! asynchronous one yields a thunk, like calling an `async fn` does; the
+//! Rust future runs when the thunk is awaited.
+
+use std::{fmt, future::Future, pin::Pin, sync::Arc};
+
+use dada_collections::Map;
+
+use crate::HostValue;
+
+/// The future returned by an asynchronous host function.
+pub type HostFuture = Pin<Box<dyn Future<Output = eyre::Result<HostValue>>>>;
+
+type SyncFn = dyn Fn(Vec<HostValue>) -> eyre::Result<HostValue> + Send + Sync;
+type AsyncFn = dyn Fn(Vec<HostValue>) -> HostFuture + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) enum HostFunction {
+    Sync(Arc<SyncFn>),
+    Async(Arc<AsyncFn>),
+}
+
+/// The set of host functions available to a program, by name.
+#[derive(Clone, Default)]
+pub struct HostFunctions {
+    functions: Map<String, HostFunction>,
+}
+
+impl HostFunctions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `function` under `name`, replacing any earlier host
+    /// function with that name.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        function: impl Fn(Vec<HostValue>) -> eyre::Result<HostValue> + Send + Sync + 'static,
+    ) {
+        self.functions
+            .insert(name.into(), HostFunction::Sync(Arc::new(function)));
+    }
+
+    /// Registers the asynchronous `function` under `name`, replacing any
+    /// earlier host function with that name.
+    pub fn register_async<F>(
+        &mut self,
+        name: impl Into<String>,
+        function: impl Fn(Vec<HostValue>) -> F + Send + Sync + 'static,
+    ) where
+        F: Future<Output = eyre::Result<HostValue>> + 'static,
+    {
+        let function = move |arguments| -> HostFuture { Box::pin(function(arguments)) };
+        self.functions
+            .insert(name.into(), HostFunction::Async(Arc::new(function)));
+    }
+
+    /// Names of the registered functions, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.functions.keys().map(|name| name.as_str())
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&HostFunction> {
+        self.functions.get(name)
+    }
+}
+
+impl fmt::Debug for HostFunctions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.names()).finish()
+    }
+}
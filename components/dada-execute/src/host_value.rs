@@ -0,0 +1,193 @@
+//! Values passed to and returned from dada functions by Rust code that
+//! embeds the interpreter (see [`call`](crate::call)).
+
+use dada_ir::{class::Class, filename::Filename, item::Item};
+use dada_parse::prelude::*;
+
+use crate::{
+    ext::DadaExecuteClassExt,
+    machine::{op::MachineOp, op::MachineOpExtMut, Instance, ObjectData, Tuple, Value},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HostValue {
+    Unit,
+    Bool(bool),
+
+    /// An unsuffixed integer, like `22`.
+    Int(u64),
+
+    /// An unsigned integer, like `22_u`.
+    UnsignedInt(u64),
+
+    /// A signed integer, like `22_i`.
+    SignedInt(i64),
+
+    Float(f64),
+    String(String),
+    Tuple(Vec<HostValue>),
+
+    /// An instance of the class named `class`, with each field's name
+    /// and value. When returned to Rust, the fields are in the order the
+    /// class declares them. When passed in, the class must be defined in
+    /// the same file as the function being called, but the fields can be
+    /// given in any order.
+    Instance {
+        class: String,
+        fields: Vec<(String, HostValue)>,
+    },
+
+    /// Any other value (e.g., a function or a big integer), as the
+    /// interpreter would print it. These can be returned to Rust, but
+    /// not passed in.
+    Other(String),
+}
+
+impl HostValue {
+    /// Allocates `self` in `machine`, returning a value that owns it.
+    /// Instances are of classes defined in `filename`.
+    pub(crate) fn into_value(
+        self,
+        db: &dyn crate::Db,
+        filename: Filename,
+        mut machine: &mut dyn MachineOp,
+    ) -> eyre::Result<Value> {
+        let data: ObjectData = match self {
+            HostValue::Unit => ObjectData::Unit(()),
+            HostValue::Bool(b) => ObjectData::Bool(b),
+            HostValue::Int(i) => ObjectData::Int(i),
+            HostValue::UnsignedInt(i) => ObjectData::UnsignedInt(i),
+            HostValue::SignedInt(i) => ObjectData::SignedInt(i),
+            HostValue::Float(f) => ObjectData::Float(f),
+            HostValue::String(s) => ObjectData::String(s),
+            HostValue::Tuple(values) => {
+                let fields = values
+                    .into_iter()
+                    .map(|value| value.into_value(db, filename, &mut *machine))
+                    .collect::<eyre::Result<_>>()?;
+                ObjectData::Tuple(Tuple { fields })
+            }
+            HostValue::Instance { class, mut fields } => {
+                let Some(class) = find_class(db, filename, &class) else {
+                    eyre::bail!("no class named `{}` in `{}`", class, filename.as_str(db))
+                };
+                let mut values = vec![];
+                for name in class.field_names(db) {
+                    let name = name.as_str(db);
+                    let Some(index) = fields.iter().position(|(n, _)| n == name) else {
+                        eyre::bail!(
+                            "missing field `{}` for class `{}`",
+                            name,
+                            class.name(db).as_str(db)
+                        )
+                    };
+                    let (_, value) = fields.remove(index);
+                    values.push(value.into_value(db, filename, &mut *machine)?);
+                }
+                if let Some((name, _)) = fields.first() {
+                    eyre::bail!(
+                        "class `{}` has no field named `{}`",
+                        class.name(db).as_str(db),
+                        name
+                    )
+                }
+                ObjectData::Instance(Instance {
+                    class,
+                    fields: values,
+                })
+            }
+            HostValue::Other(s) => eyre::bail!("cannot pass `{}` from Rust", s),
+        };
+        Ok(machine.my_value(data))
+    }
+
+    /// Reads the value `value` out of `machine`.
+    pub(crate) fn from_value(db: &dyn crate::Db, machine: &dyn MachineOp, value: Value) -> Self {
+        if machine.permission(value.permission).valid().is_none() {
+            return HostValue::Other("(expired)".to_string());
+        }
+
+        match machine.object(value.object) {
+            ObjectData::Unit(()) => HostValue::Unit,
+            ObjectData::Bool(b) => HostValue::Bool(*b),
+            ObjectData::Int(i) => HostValue::Int(*i),
+            ObjectData::UnsignedInt(i) => HostValue::UnsignedInt(*i),
+            ObjectData::SignedInt(i) => HostValue::SignedInt(*i),
+            ObjectData::Float(f) => HostValue::Float(*f),
+            ObjectData::String(s) => HostValue::String(s.clone()),
+            ObjectData::Tuple(tuple) => HostValue::Tuple(
+                tuple
+                    .fields
+                    .iter()
+                    .map(|field| Self::from_value(db, machine, *field))
+                    .collect(),
+            ),
+            ObjectData::Instance(instance) => HostValue::Instance {
+                class: instance.class.name(db).as_str(db).to_string(),
+                fields: instance
+                    .class
+                    .field_names(db)
+                    .iter()
+                    .zip(&instance.fields)
+                    .map(|(name, field)| {
+                        (
+                            name.as_str(db).to_string(),
+                            Self::from_value(db, machine, *field),
+                        )
+                    })
+                    .collect(),
+            },
+            ObjectData::BigInt(i) => HostValue::Other(i.to_string()),
+            ObjectData::Function(f) => HostValue::Other(f.name(db).as_str(db).to_string()),
+            ObjectData::Class(c) => HostValue::Other(c.name(db).as_str(db).to_string()),
+            ObjectData::Intrinsic(i) => HostValue::Other(i.as_str(db).to_string()),
+            ObjectData::HostFunction(name) => HostValue::Other(name.as_str(db).to_string()),
+            ObjectData::ExternFunction(f) => HostValue::Other(f.name(db).as_str(db).to_string()),
+            data => HostValue::Other(data.kind_str(db)),
+        }
+    }
+}
+
+fn find_class(db: &dyn crate::Db, filename: Filename, name: &str) -> Option<Class> {
+    filename.items(db).iter().find_map(|item| match item {
+        Item::Class(class) if class.name(db).as_str(db) == name => Some(*class),
+        _ => None,
+    })
+}
+
+impl std::fmt::Display for HostValue {
+    /// Formats the value the way the interpreter prints it (but without
+    /// permissions).
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostValue::Unit => write!(f, "()"),
+            HostValue::Bool(b) => write!(f, "{}", b),
+            HostValue::Int(i) => write!(f, "{}", i),
+            HostValue::UnsignedInt(i) => write!(f, "{}_u", i),
+            HostValue::SignedInt(i) => write!(f, "{}_i", i),
+            HostValue::Float(v) => write!(f, "{}", v),
+            HostValue::String(s) => write!(f, "{}", s),
+            HostValue::Tuple(values) => {
+                write!(f, "(")?;
+                for (value, index) in values.iter().zip(0..) {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, ")")
+            }
+            HostValue::Instance { class, fields } => {
+                write!(f, "{}(", class)?;
+                for ((_, value), index) in fields.iter().zip(0..) {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, ")")
+            }
+            HostValue::Other(s) => write!(f, "{}", s),
+        }
+    }
+}
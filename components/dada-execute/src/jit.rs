@@ -0,0 +1,80 @@
+//! Interface to a just-in-time compiler that can execute some functions
+//! as native code instead of interpreting them.
+//!
+//! The interpreter does not include a compiler itself (so that it can be
+//! built for targets, like wasm, that cannot generate code at runtime).
+//! Instead, a JIT is supplied by returning it from [`Kernel::jit`];
+//! the `dada-jit` crate provides one based on Cranelift.
+//!
+//! Before pushing a frame for a (non-async) function call, the interpreter
+//! offers the call to the JIT, provided all the arguments are scalars.
+//! If the JIT returns a value, it is used as the result of the call;
+//! otherwise the call is interpreted as usual. A JIT should decline any
+//! call that would fail (e.g., because of an overflow), so that the
+//! interpreter reports the error.
+//!
+//! [`Kernel::jit`]: crate::kernel::Kernel::jit
+
+use dada_ir::function::Function;
+
+use crate::machine::ObjectData;
+
+/// A scalar value that can be passed to or returned from native code.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NativeValue {
+    Unit,
+    Bool(bool),
+    Int(u64),
+    UnsignedInt(u64),
+    SignedInt(i64),
+}
+
+impl NativeValue {
+    pub(crate) fn from_object_data(data: &ObjectData) -> Option<Self> {
+        match *data {
+            ObjectData::Unit(()) => Some(NativeValue::Unit),
+            ObjectData::Bool(b) => Some(NativeValue::Bool(b)),
+            ObjectData::Int(i) => Some(NativeValue::Int(i)),
+            ObjectData::UnsignedInt(i) => Some(NativeValue::UnsignedInt(i)),
+            ObjectData::SignedInt(i) => Some(NativeValue::SignedInt(i)),
+            _ => None,
+        }
+    }
+}
+
+impl From<NativeValue> for ObjectData {
+    fn from(value: NativeValue) -> Self {
+        match value {
+            NativeValue::Unit => ObjectData::Unit(()),
+            NativeValue::Bool(b) => ObjectData::Bool(b),
+            NativeValue::Int(i) => ObjectData::Int(i),
+            NativeValue::UnsignedInt(i) => ObjectData::UnsignedInt(i),
+            NativeValue::SignedInt(i) => ObjectData::SignedInt(i),
+        }
+    }
+}
+
+impl std::fmt::Display for NativeValue {
+    /// Formats the value the same way the interpreter prints it.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NativeValue::Unit => write!(f, "()"),
+            NativeValue::Bool(b) => write!(f, "{}", b),
+            NativeValue::Int(i) => write!(f, "{}", i),
+            NativeValue::UnsignedInt(i) => write!(f, "{}_u", i),
+            NativeValue::SignedInt(i) => write!(f, "{}_i", i),
+        }
+    }
+}
+
+pub trait Jit: Send + Sync {
+    /// Invoked each time the interpreter is about to call `function`
+    /// with the given arguments. Returns the result of the call if the
+    /// JIT executed it, or `None` if the interpreter should execute it.
+    fn call(
+        &mut self,
+        db: &dyn crate::Db,
+        function: Function,
+        arguments: &[NativeValue],
+    ) -> Option<NativeValue>;
+}
@@ -6,10 +6,20 @@ use dada_ir::{filename::Filename, function::Function, span::FileSpan};
 use salsa::DebugWithDb;
 
 use crate::{
+    config::ExecutionConfig,
+    coverage::Coverage,
     heap_graph::HeapGraph,
+    host_value::HostValue,
+    jit::Jit,
     machine::{ProgramCounter, Value},
+    profile::Profiler,
+    scheduler::{InlineScheduler, Scheduler},
+    stats::HeapStats,
 };
 
+#[cfg(feature = "trace")]
+use crate::trace_event::TraceRecorder;
+
 #[async_trait::async_trait]
 pub trait Kernel: Send + Sync {
     /// Implementation for the `print` intrinsic, that prints a line of text.
@@ -29,6 +39,15 @@ pub trait Kernel: Send + Sync {
         self.print(await_pc, "\n").await
     }
 
+    /// Emits a line of output, along with information about where it came
+    /// from. By default, this writes the text with [`Self::print`] followed
+    /// by [`Self::print_newline`]; hosts that want to relate output back to
+    /// the source can override it.
+    async fn output(&mut self, event: OutputEvent) -> eyre::Result<()> {
+        self.print(event.await_pc, &event.text).await?;
+        self.print_newline(event.await_pc).await
+    }
+
     /// Indicates that we have reached the start of a breakpoint expression.
     fn breakpoint_start(
         &mut self,
@@ -47,12 +66,73 @@ pub trait Kernel: Send + Sync {
         breakpoint_span: FileSpan,
         generate_heap_graph: &mut dyn FnMut() -> HeapGraph,
     ) -> eyre::Result<()>;
+
+    /// Returns the configuration to use when executing a program
+    /// with this kernel.
+    fn execution_config(&self) -> ExecutionConfig {
+        ExecutionConfig::default()
+    }
+
+    /// If profiling is enabled, returns the profiler that should record
+    /// each statement the interpreter executes. Returns `None` by default.
+    fn profiler(&mut self) -> Option<&mut Profiler> {
+        None
+    }
+
+    /// If statistics are enabled, returns the `HeapStats` that should
+    /// record allocations and permission operations. Returns `None` by default.
+    fn heap_stats(&mut self) -> Option<&mut HeapStats> {
+        None
+    }
+
+    /// If coverage is enabled, returns the recorder that should note each
+    /// statement the interpreter executes. Returns `None` by default.
+    fn coverage(&mut self) -> Option<&mut Coverage> {
+        None
+    }
+
+    /// If tracing is enabled, returns the recorder that should capture
+    /// function entry/exit and await events. Returns `None` by default.
+    #[cfg(feature = "trace")]
+    fn trace_recorder(&mut self) -> Option<&mut TraceRecorder> {
+        None
+    }
+
+    /// If just-in-time compilation is enabled, returns the JIT that
+    /// should be offered each function call. Returns `None` by default.
+    fn jit(&mut self) -> Option<&mut dyn Jit> {
+        None
+    }
+
+    /// Invoked after each step when running with
+    /// [`interpret_suspendable`](crate::interpret_suspendable) or
+    /// [`resume`](crate::resume); returning true suspends execution
+    /// before the statement at `pc` is executed. Returns `false` by default.
+    fn suspend_requested(&mut self, _db: &dyn crate::Db, _pc: ProgramCounter) -> bool {
+        false
+    }
+}
+
+/// A line of output emitted by the program (e.g., by `print`).
+#[derive(Clone, Debug)]
+pub struct OutputEvent {
+    /// The text of the line, not including the trailing newline.
+    pub text: String,
+
+    /// The program counter when the output was awaited.
+    pub await_pc: ProgramCounter,
+
+    /// The span of the expression that produced the output
+    /// (e.g., the call to `print`).
+    pub span: FileSpan,
 }
 
 #[derive(Default)]
 pub struct BufferKernel {
+    config: ExecutionConfig,
     stop_at_breakpoint: bool,
     breakpoint_callback: Option<BreakpointCallback>,
+    output_callback: Option<OutputCallback>,
     track_output_ranges: bool,
 
     /// Collects the output of the program.
@@ -61,12 +141,27 @@ pub struct BufferKernel {
     /// Tracks which program counter is responsible for which output.
     buffer_pcs: Vec<OutputRange>,
 
+    /// Each line of output emitted by the program, in order.
+    output_events: Vec<OutputEvent>,
+
     /// When we start a breakpoint, we push an entry here.
     started_breakpoints: Vec<(Filename, usize, HeapGraph)>,
 
     /// When we end a breakpoint, we construct a `BreakpointHeapGraph` and
     /// either invoke `breakpoint_callback` or else buffer it here.
     heap_graphs: Vec<BreakpointRecord>,
+
+    /// If `Some`, the JIT offered each function call.
+    jit: Option<Box<dyn Jit>>,
+
+    /// If `Some`, records allocations and permission operations.
+    heap_stats: Option<HeapStats>,
+
+    /// If `Some`, records which statements are executed.
+    coverage: Option<Coverage>,
+
+    /// If `Some`, decides when to suspend (see [`Kernel::suspend_requested`]).
+    suspend_when: Option<SuspendCallback>,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -100,11 +195,20 @@ impl BreakpointRecord {
 type BreakpointCallback =
     Arc<dyn Fn(&dyn crate::Db, &mut BufferKernel, BreakpointRecord) + Send + Sync>;
 
+type OutputCallback = Arc<dyn Fn(&OutputEvent) + Send + Sync>;
+
+type SuspendCallback = Box<dyn FnMut(&dyn crate::Db, ProgramCounter) -> bool + Send + Sync>;
+
 impl BufferKernel {
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Builder method: the configuration to use when interpreting.
+    pub fn config(self, config: ExecutionConfig) -> Self {
+        Self { config, ..self }
+    }
+
     /// Builder method: if `stop_at_breakpoint` is true, then when a breakpoint
     /// is encountered we will stop with the error [`BreakpointExpressionEncountered`].
     /// If false, execution will continue (and the breakpoint may be hit more than
@@ -138,6 +242,53 @@ impl BufferKernel {
         }
     }
 
+    /// Builder method: invoke the given callback with each line of output
+    /// as soon as it is printed (it is buffered as well).
+    pub fn output_callback(self, callback: impl Fn(&OutputEvent) + Send + Sync + 'static) -> Self {
+        Self {
+            output_callback: Some(Arc::new(callback)),
+            ..self
+        }
+    }
+
+    /// Builder method: offer function calls to the given JIT.
+    pub fn jit(self, jit: impl Jit + 'static) -> Self {
+        Self {
+            jit: Some(Box::new(jit)),
+            ..self
+        }
+    }
+
+    /// Builder method: if `record_heap_stats` is true, record statistics
+    /// about the heap, which can be retrieved with [`Self::take_heap_stats`].
+    pub fn record_heap_stats(self, record_heap_stats: bool) -> Self {
+        Self {
+            heap_stats: record_heap_stats.then(HeapStats::new),
+            ..self
+        }
+    }
+
+    /// Builder method: if `record_coverage` is true, record which statements
+    /// are executed, which can be retrieved with [`Self::take_coverage`].
+    pub fn record_coverage(self, record_coverage: bool) -> Self {
+        Self {
+            coverage: record_coverage.then(Coverage::new),
+            ..self
+        }
+    }
+
+    /// Builder method: suspend execution before the statement at `pc`
+    /// when the given callback returns true for it.
+    pub fn suspend_when(
+        self,
+        callback: impl FnMut(&dyn crate::Db, ProgramCounter) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            suspend_when: Some(Box::new(callback)),
+            ..self
+        }
+    }
+
     pub async fn interpret(
         &mut self,
         db: &dyn crate::Db,
@@ -147,13 +298,42 @@ impl BufferKernel {
         crate::run::interpret(function, db, self, arguments).await
     }
 
+    /// Interprets `main` with the configured arguments (see
+    /// [`crate::interpret_main`]).
+    pub async fn interpret_main(&mut self, db: &dyn crate::Db, main: Function) -> eyre::Result<()> {
+        crate::run::interpret_main(main, db, self).await
+    }
+
+    /// Calls `function` and returns its result (see [`crate::call`]).
+    pub async fn call(
+        &mut self,
+        db: &dyn crate::Db,
+        function: Function,
+        arguments: Vec<HostValue>,
+    ) -> eyre::Result<HostValue> {
+        crate::run::call(function, db, self, arguments).await
+    }
+
     pub async fn interpret_and_buffer(
         &mut self,
         db: &dyn crate::Db,
         function: Function,
         arguments: Vec<Value>,
     ) {
-        match crate::run::interpret(function, db, self, arguments).await {
+        self.interpret_and_buffer_with_scheduler(db, function, &mut InlineScheduler, arguments)
+            .await
+    }
+
+    /// Like [`Self::interpret_and_buffer`], but awaited Rust thunks are run
+    /// by `scheduler` (see [`crate::interpret_with_scheduler`]).
+    pub async fn interpret_and_buffer_with_scheduler(
+        &mut self,
+        db: &dyn crate::Db,
+        function: Function,
+        scheduler: &mut dyn Scheduler,
+        arguments: Vec<Value>,
+    ) {
+        match crate::run::interpret_with_scheduler(function, db, self, scheduler, arguments).await {
             Ok(()) => {}
             Err(e) => {
                 self.append(&e.to_string());
@@ -178,6 +358,21 @@ impl BufferKernel {
         std::mem::take(&mut self.buffer)
     }
 
+    /// Take the heap statistics recorded so far (if they are being recorded).
+    pub fn take_heap_stats(&mut self) -> Option<HeapStats> {
+        self.heap_stats.take()
+    }
+
+    /// Take the coverage recorded so far (if it is being recorded).
+    pub fn take_coverage(&mut self) -> Option<Coverage> {
+        self.coverage.take()
+    }
+
+    /// Take the lines of output emitted by the program so far.
+    pub fn take_output_events(&mut self) -> Vec<OutputEvent> {
+        std::mem::take(&mut self.output_events)
+    }
+
     /// Append text into the output buffer
     pub fn append(&mut self, s: &str) {
         self.buffer.push_str(s);
@@ -286,6 +481,16 @@ impl Kernel for BufferKernel {
         Ok(())
     }
 
+    async fn output(&mut self, event: OutputEvent) -> eyre::Result<()> {
+        self.print(event.await_pc, &event.text).await?;
+        self.print_newline(event.await_pc).await?;
+        if let Some(callback) = &self.output_callback {
+            callback(&event);
+        }
+        self.output_events.push(event);
+        Ok(())
+    }
+
     fn breakpoint_start(
         &mut self,
         db: &dyn crate::Db,
@@ -349,4 +554,27 @@ impl Kernel for BufferKernel {
 
         Ok(())
     }
+
+    fn execution_config(&self) -> ExecutionConfig {
+        self.config.clone()
+    }
+
+    fn jit(&mut self) -> Option<&mut dyn Jit> {
+        self.jit.as_deref_mut().map(|jit| jit as &mut dyn Jit)
+    }
+
+    fn heap_stats(&mut self) -> Option<&mut HeapStats> {
+        self.heap_stats.as_mut()
+    }
+
+    fn coverage(&mut self) -> Option<&mut Coverage> {
+        self.coverage.as_mut()
+    }
+
+    fn suspend_requested(&mut self, db: &dyn crate::Db, pc: ProgramCounter) -> bool {
+        match &mut self.suspend_when {
+            Some(callback) => callback(db, pc),
+            None => false,
+        }
+    }
 }
@@ -16,15 +16,35 @@ impl<T> Db for T where
 {
 }
 
+pub mod cancel;
+pub mod capability;
+mod clock;
+pub mod config;
+pub mod coverage;
+#[cfg(feature = "debugger")]
+pub mod debugger;
 mod error;
 mod ext;
 pub mod heap_graph;
+pub mod host_function;
+mod host_value;
+pub mod jit;
 pub mod kernel;
 pub mod machine;
 mod moment;
+pub mod profile;
 mod run;
+pub mod scheduler;
+pub mod snapshot;
+pub mod stats;
 mod step;
 mod thunk;
+#[cfg(feature = "trace")]
+pub mod trace_event;
 
-pub use error::DiagnosticError;
-pub use run::interpret;
+pub use error::{DiagnosticError, Exit};
+pub use host_value::HostValue;
+pub use run::{
+    call, interpret, interpret_main, interpret_suspendable, interpret_with_scheduler,
+    main_arguments, main_function, resume,
+};
@@ -1,19 +1,22 @@
 //! Defines the "abstract machine" that executes a Dada program.
 
-use dada_collections::IndexVec;
+use dada_collections::{IndexVec, Map};
 use dada_id::id;
 use dada_ir::{
     class::Class,
     code::bir,
+    extern_function::ExternFunction,
     function::Function,
     intrinsic::Intrinsic,
     span::FileSpan,
     storage::{Joint, Leased},
+    word::Word,
 };
 use dada_parse::prelude::*;
 use generational_arena::Arena;
+use num_bigint::BigInt;
 
-use crate::thunk::RustThunk;
+use crate::{stats::AllocationKind, thunk::RustThunk};
 
 pub mod assert_invariants;
 pub mod op;
@@ -60,10 +63,38 @@ pub struct Heap {
     pub objects: Arena<ObjectData>,
     pub permissions: Arena<PermissionData>,
     pub reservations: Arena<ReservationData>,
+
+    /// Number of objects that have been allocated, by kind, while
+    /// `count_allocations` was set.
+    pub allocations: Map<AllocationKind, u64>,
+
+    /// Whether to count allocations in `allocations`; only set while the
+    /// kernel is recording heap statistics.
+    pub count_allocations: bool,
+
+    /// Total length, in bytes, of the strings currently in the heap.
+    pub string_bytes: usize,
+
+    /// Number of objects in the heap after the last garbage collection.
+    /// It is kept with the heap, rather than with whatever is stepping
+    /// the program, so that it carries over when a snapshot is resumed.
+    pub objects_after_gc: usize,
+
+    /// Where each permission was granted and revoked, used to explain
+    /// errors that arise when an expired permission is used.
+    pub permission_history: Map<Permission, PermissionHistory>,
 }
 
 impl Heap {
     fn new_object(&mut self, data: ObjectData) -> Object {
+        if self.count_allocations {
+            *self
+                .allocations
+                .entry(AllocationKind::of(&data))
+                .or_default() += 1;
+        }
+        self.string_bytes += data.string_bytes();
+
         let o = Object {
             index: self.objects.insert(data),
         };
@@ -81,6 +112,12 @@ impl Heap {
         self.objects.get(object.index)
     }
 
+    fn take_object(&mut self, object: Object) -> Option<ObjectData> {
+        let data = self.objects.remove(object.index)?;
+        self.string_bytes -= data.string_bytes();
+        Some(data)
+    }
+
     fn all_objects(&self) -> Vec<Object> {
         let mut vec: Vec<_> = self
             .objects
@@ -159,7 +196,7 @@ impl Heap {
 /// like `m[object]`.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Object {
-    index: generational_arena::Index,
+    pub(crate) index: generational_arena::Index,
 }
 
 impl std::fmt::Debug for Object {
@@ -189,6 +226,13 @@ pub enum ObjectData {
     /// A reference to an intrinsic, like `print`.
     Intrinsic(Intrinsic),
 
+    /// A reference to a [host function](crate::host_function), by name.
+    HostFunction(Word),
+
+    /// A reference to a function in a shared library, like
+    /// `extern "libm.so.6" fn cos(x: f64) -> f64`.
+    ExternFunction(ExternFunction),
+
     /// The value returned by an `async fn` -- captures the function
     /// that was called along with its arguments. When this value is
     /// awaited, the function is actually pushed onto the stack.
@@ -201,6 +245,17 @@ pub enum ObjectData {
     /// A tuple of objects like `(a, b, c)`.
     Tuple(Tuple),
 
+    /// A weak reference, created with the `weak` intrinsic. The value
+    /// always has a joint permission, and it does not keep its object
+    /// alive: once the object is freed (or the permission is revoked),
+    /// `upgrade` yields `()`.
+    Weak(Value),
+
+    /// The `get` method of a weak reference, as in `w.get`, holding the
+    /// value that the weak reference does. Calling it is the same as
+    /// calling `upgrade` on the weak reference.
+    WeakGet(Value),
+
     /// Boolean.
     Bool(bool),
 
@@ -213,6 +268,9 @@ pub enum ObjectData {
     /// Signed integer.
     SignedInt(i64),
 
+    /// Arbitrary-precision integer, created with the `big` intrinsic.
+    BigInt(BigInt),
+
     /// Floating point.
     Float(f64),
 
@@ -231,20 +289,33 @@ impl ObjectData {
             ObjectData::Class(_) => "a class".to_string(),
             ObjectData::Function(_) => "a function".to_string(),
             ObjectData::Intrinsic(_) => "a function".to_string(),
+            ObjectData::HostFunction(_) => "a function".to_string(),
+            ObjectData::ExternFunction(_) => "a function".to_string(),
             ObjectData::ThunkFn(f) => {
                 format!("a suspended call to `{}`", f.function.name(db).as_str(db))
             }
             ObjectData::ThunkRust(_) => "a thunk".to_string(),
             ObjectData::Tuple(_) => "a tuple".to_string(),
+            ObjectData::Weak(_) => "a weak reference".to_string(),
+            ObjectData::WeakGet(_) => "a method".to_string(),
             ObjectData::Bool(_) => "a boolean".to_string(),
             ObjectData::UnsignedInt(_) => "an unsigned integer".to_string(),
             ObjectData::Int(_) => "an integer".to_string(),
             ObjectData::SignedInt(_) => "a signed integer".to_string(),
+            ObjectData::BigInt(_) => "a big integer".to_string(),
             ObjectData::Float(_) => "a float".to_string(),
             ObjectData::String(_) => "a string".to_string(),
             ObjectData::Unit(()) => "nothing".to_string(),
         }
     }
+
+    /// Number of bytes of string data owned by this object.
+    fn string_bytes(&self) -> usize {
+        match self {
+            ObjectData::String(s) => s.len(),
+            _ => 0,
+        }
+    }
 }
 
 macro_rules! object_data_from_impls {
@@ -265,12 +336,14 @@ object_data_from_impls! {
     Class(Class),
     Function(Function),
     Intrinsic(Intrinsic),
+    ExternFunction(ExternFunction),
     ThunkFn(ThunkFn),
     ThunkRust(RustThunk),
     Tuple(Tuple),
     Bool(bool),
     UnsignedInt(u64),
     SignedInt(i64),
+    BigInt(BigInt),
     Float(f64),
     String(String),
     Unit(()),
@@ -314,7 +387,7 @@ pub struct Tuple {
 /// or leased or what.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Reservation {
-    index: generational_arena::Index,
+    pub(crate) index: generational_arena::Index,
 }
 
 impl std::fmt::Debug for Reservation {
@@ -343,7 +416,7 @@ pub struct ReservationData {
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Permission {
-    index: generational_arena::Index,
+    pub(crate) index: generational_arena::Index,
 }
 
 impl std::fmt::Debug for Permission {
@@ -353,6 +426,53 @@ impl std::fmt::Debug for Permission {
     }
 }
 
+/// Something that happened to a permission; see [`Heap::permission_history`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PermissionEvent {
+    /// The permission was created while executing `pc`.
+    Granted {
+        pc: ProgramCounter,
+        joint: Joint,
+        leased: Leased,
+    },
+
+    /// The permission was revoked while executing `pc`.
+    Revoked(ProgramCounter),
+}
+
+/// The events recorded for a permission: only where it was granted and
+/// where it was last revoked are kept, so each permission's history has
+/// a fixed size.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PermissionHistory {
+    /// Where the permission was granted, and whether it was joint and leased.
+    pub granted: Option<(ProgramCounter, Joint, Leased)>,
+
+    /// Where the permission was last revoked.
+    pub revoked: Option<ProgramCounter>,
+}
+
+impl PermissionHistory {
+    pub fn record(&mut self, event: PermissionEvent) {
+        match event {
+            PermissionEvent::Granted { pc, joint, leased } => {
+                self.granted = Some((pc, joint, leased))
+            }
+            PermissionEvent::Revoked(pc) => self.revoked = Some(pc),
+        }
+    }
+
+    /// The recorded events, in the order they happened.
+    pub fn events(&self) -> impl Iterator<Item = PermissionEvent> {
+        let granted = self
+            .granted
+            .map(|(pc, joint, leased)| PermissionEvent::Granted { pc, joint, leased });
+        granted
+            .into_iter()
+            .chain(self.revoked.map(PermissionEvent::Revoked))
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum PermissionData {
     /// No permission: if the place is non-none, executing this place is
@@ -513,7 +633,7 @@ pub struct Frame {
     pub locals: IndexVec<bir::LocalVariable, Value>,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ProgramCounter {
     /// The BIR we are interpreting.
     pub bir: bir::Bir,
@@ -568,8 +688,7 @@ impl ProgramCounter {
         };
 
         let code = self.bir.origin(db);
-        let filename = code.filename(db);
         let syntax_tree = code.syntax_tree(db);
-        syntax_tree.spans(db)[syntax_expr].in_file(filename)
+        syntax_tree.file_span(db, syntax_tree.spans(db)[syntax_expr])
     }
 }
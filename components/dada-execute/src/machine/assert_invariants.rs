@@ -72,10 +72,15 @@ impl<'me> AssertInvariants<'me> {
             ObjectData::Class(_)
             | ObjectData::Function(_)
             | ObjectData::Intrinsic(_)
+            | ObjectData::HostFunction(_)
+            | ObjectData::ExternFunction(_)
             | ObjectData::Bool(_)
             | ObjectData::UnsignedInt(_)
             | ObjectData::Int(_)
             | ObjectData::SignedInt(_)
+            | ObjectData::BigInt(_)
+            | ObjectData::Weak(_)
+            | ObjectData::WeakGet(_)
             | ObjectData::Float(_)
             | ObjectData::String(_)
             | ObjectData::Unit(_) => {
@@ -116,7 +121,9 @@ impl<'me> AssertInvariants<'me> {
         match &table[place] {
             bir::PlaceData::Class(_)
             | bir::PlaceData::Function(_)
-            | bir::PlaceData::Intrinsic(_) => {
+            | bir::PlaceData::Intrinsic(_)
+            | bir::PlaceData::HostFunction(_)
+            | bir::PlaceData::ExternFunction(_) => {
                 eyre::bail!(
                     "reserved place `{:?}` bottoms out in a constant `{:?}`",
                     reservation,
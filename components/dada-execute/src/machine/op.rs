@@ -1,12 +1,14 @@
 use std::fmt::Debug;
 
-use dada_collections::IndexVec;
+use dada_collections::{IndexVec, Map};
 use dada_ir::code::bir;
 
+use crate::stats::AllocationKind;
+
 use super::{
     assert_invariants::AssertInvariants, Frame, FrameIndex, Machine, Object, ObjectData,
-    Permission, PermissionData, ProgramCounter, Reservation, ReservationData, ValidPermissionData,
-    Value,
+    Permission, PermissionData, PermissionEvent, PermissionHistory, ProgramCounter, Reservation,
+    ReservationData, ValidPermissionData, Value,
 };
 
 pub(crate) trait MachineOp:
@@ -31,14 +33,27 @@ pub(crate) trait MachineOp:
     fn object_mut(&mut self, object: Object) -> &mut ObjectData;
     fn take_object(&mut self, object: Object) -> ObjectData;
     fn new_object(&mut self, data: ObjectData) -> Object;
+    fn object_exists(&self, object: Object) -> bool;
     fn unit_object(&self) -> Object;
     fn all_objects(&self) -> Vec<Object>;
 
+    /// Notes that the garbage collector just ran.
+    fn record_gc(&mut self);
+
+    /// Sets whether to count the objects allocated (see
+    /// [`Self::take_allocations`]).
+    fn count_allocations(&mut self, count: bool);
+
+    /// The objects allocated since the last call, by kind.
+    fn take_allocations(&mut self) -> Map<AllocationKind, u64>;
+
     fn permission(&self, permission: Permission) -> &PermissionData;
     fn permission_mut(&mut self, permission: Permission) -> &mut PermissionData;
     fn take_permission(&mut self, permission: Permission) -> PermissionData;
     fn new_permission(&mut self, data: ValidPermissionData) -> Permission;
     fn expired_permission(&mut self, origin: Option<ProgramCounter>) -> Permission;
+    fn record_permission_event(&mut self, permission: Permission, event: PermissionEvent);
+    fn permission_history(&self, permission: Permission) -> PermissionHistory;
     fn all_permissions(&self) -> Vec<Permission>;
 
     fn reservation(&self, reservation: Reservation) -> &ReservationData;
@@ -156,8 +171,7 @@ impl MachineOp for Machine {
     #[track_caller]
     fn take_object(&mut self, object: Object) -> ObjectData {
         self.heap
-            .objects
-            .remove(object.index)
+            .take_object(object)
             .unwrap_or_else(|| panic!("object not found: {object:?}"))
     }
 
@@ -168,6 +182,10 @@ impl MachineOp for Machine {
         self.heap.new_object(data)
     }
 
+    fn object_exists(&self, object: Object) -> bool {
+        self.heap.objects.contains(object.index)
+    }
+
     fn unit_object(&self) -> Object {
         self.unit_object
     }
@@ -176,6 +194,18 @@ impl MachineOp for Machine {
         self.heap.all_objects()
     }
 
+    fn record_gc(&mut self) {
+        self.heap.objects_after_gc = self.heap.objects.len();
+    }
+
+    fn count_allocations(&mut self, count: bool) {
+        self.heap.count_allocations = count;
+    }
+
+    fn take_allocations(&mut self) -> Map<AllocationKind, u64> {
+        std::mem::take(&mut self.heap.allocations)
+    }
+
     #[track_caller]
     fn permission(&self, permission: Permission) -> &PermissionData {
         self.heap
@@ -191,6 +221,7 @@ impl MachineOp for Machine {
 
     #[track_caller]
     fn take_permission(&mut self, permission: Permission) -> PermissionData {
+        self.heap.permission_history.remove(&permission);
         self.heap
             .permissions
             .remove(permission.index)
@@ -198,7 +229,16 @@ impl MachineOp for Machine {
     }
 
     fn new_permission(&mut self, data: ValidPermissionData) -> Permission {
-        self.heap.new_permission(PermissionData::Valid(data))
+        let event = self.opt_pc().map(|pc| PermissionEvent::Granted {
+            pc,
+            joint: data.joint,
+            leased: data.leased,
+        });
+        let permission = self.heap.new_permission(PermissionData::Valid(data));
+        if let Some(event) = event {
+            self.record_permission_event(permission, event);
+        }
+        permission
     }
 
     fn all_permissions(&self) -> Vec<Permission> {
@@ -209,6 +249,22 @@ impl MachineOp for Machine {
         self.heap.new_permission(PermissionData::Expired(place))
     }
 
+    fn record_permission_event(&mut self, permission: Permission, event: PermissionEvent) {
+        self.heap
+            .permission_history
+            .entry(permission)
+            .or_default()
+            .record(event);
+    }
+
+    fn permission_history(&self, permission: Permission) -> PermissionHistory {
+        self.heap
+            .permission_history
+            .get(&permission)
+            .copied()
+            .unwrap_or_default()
+    }
+
     #[track_caller]
     fn reservation(&self, reservation: Reservation) -> &ReservationData {
         self.heap.reservations.get(reservation.index).unwrap()
@@ -32,11 +32,14 @@ pub(crate) impl<T: ?Sized + MachineOp> DefaultStringify for T {
             ObjectData::String(s) => s.to_string(),
             ObjectData::Bool(v) => format!("{}", v),
             ObjectData::SignedInt(v) => format!("{}_i", v),
+            ObjectData::BigInt(v) => format!("{}", v),
             ObjectData::Float(v) => format!("{}", v),
             ObjectData::UnsignedInt(v) => format!("{}_u", v),
             ObjectData::Int(v) => format!("{}", v),
             ObjectData::Unit(_) => "()".to_string(),
             ObjectData::Intrinsic(i) => i.as_str(db).to_string(),
+            ObjectData::HostFunction(name) => name.as_str(db).to_string(),
+            ObjectData::ExternFunction(f) => f.name(db).as_str(db).to_string(),
             ObjectData::Function(f) => f.name(db).as_str(db).to_string(),
             ObjectData::ThunkFn(f) => self.object_string(
                 db,
@@ -50,6 +53,8 @@ pub(crate) impl<T: ?Sized + MachineOp> DefaultStringify for T {
             ObjectData::Class(c) => c.name(db).as_str(db).to_string(),
             ObjectData::ThunkRust(r) => format!("{permission} {r:?}"),
             ObjectData::Tuple(t) => self.object_string(db, permission, None, &t.fields),
+            ObjectData::Weak(_) => format!("{permission} weak"),
+            ObjectData::WeakGet(_) => "get".to_string(),
             ObjectData::Reservation(r) => format!("{r:?}"), // can prob do better than this :)
         }
     }
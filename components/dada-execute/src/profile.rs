@@ -0,0 +1,171 @@
+//! The "profiler" records how many times each BIR statement and terminator
+//! is executed and how much time is spent executing it (wall-clock time,
+//! or logical time in [deterministic mode]). The results can then be
+//! summarized into a report that shows the hottest functions and source
+//! lines, or dumped in the "folded stacks" format understood by flamegraph
+//! tools like [`inferno`].
+//!
+//! Profiling is enabled by returning a profiler from [`Kernel::profiler`].
+//!
+//! [`Kernel::profiler`]: crate::kernel::Kernel::profiler
+//! [`inferno`]: https://github.com/jonhoo/inferno
+//! [deterministic mode]: crate::config::ExecutionConfig::deterministic
+
+use std::time::Duration;
+
+use dada_collections::Map;
+use dada_ir::{filename::Filename, function::Function};
+
+use crate::machine::ProgramCounter;
+
+#[derive(Debug, Default)]
+pub struct Profiler {
+    /// Execution counts for each statement (or terminator) that was executed.
+    steps: Map<ProgramCounter, ExecutionCount>,
+
+    /// Execution counts for each distinct dada call stack, listed from the
+    /// outermost function to the function that was executing.
+    stacks: Map<Vec<Function>, ExecutionCount>,
+
+    /// The call stack of the step being executed. It is reused from step
+    /// to step, so that a step only allocates when its stack is new.
+    current_stack: Vec<Function>,
+}
+
+/// The number of times that something was executed along with the
+/// total time spent executing it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionCount {
+    pub count: u64,
+    pub elapsed: Duration,
+}
+
+impl ExecutionCount {
+    fn add(&mut self, other: ExecutionCount) {
+        self.count += other.count;
+        self.elapsed += other.elapsed;
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Notes the call stack (outermost function first) of the step that is
+    /// about to execute; its time is recorded by [`Self::record_step`].
+    pub(crate) fn enter_step(&mut self, stack: impl Iterator<Item = Function>) {
+        self.current_stack.clear();
+        self.current_stack.extend(stack);
+    }
+
+    /// Records that the statement (or terminator) at `pc` was executed once
+    /// with the stack given to [`Self::enter_step`] and took `elapsed` time.
+    pub(crate) fn record_step(&mut self, pc: ProgramCounter, elapsed: Duration) {
+        let count = ExecutionCount { count: 1, elapsed };
+        self.steps.entry(pc).or_default().add(count);
+        match self.stacks.get_mut(&self.current_stack[..]) {
+            Some(stack_count) => stack_count.add(count),
+            None => {
+                self.stacks.insert(self.current_stack.clone(), count);
+            }
+        }
+    }
+
+    /// Returns the execution counts for each individual statement/terminator,
+    /// hottest first.
+    pub fn hot_steps(&self) -> Vec<(ProgramCounter, ExecutionCount)> {
+        let mut steps: Vec<_> = self.steps.iter().map(|(pc, c)| (*pc, *c)).collect();
+        sort_hottest_first(&mut steps);
+        steps
+    }
+
+    /// Returns the execution counts summed up for each function, hottest first.
+    pub fn hot_functions(&self, db: &dyn crate::Db) -> Vec<(Function, ExecutionCount)> {
+        let mut functions: Map<Function, ExecutionCount> = Map::default();
+        for (pc, count) in &self.steps {
+            functions.entry(pc.bir.origin(db)).or_default().add(*count);
+        }
+        let mut functions: Vec<_> = functions.into_iter().collect();
+        sort_hottest_first(&mut functions);
+        functions
+    }
+
+    /// Returns the execution counts summed up for each source line
+    /// (identified by its filename and 1-based line number), hottest first.
+    pub fn hot_lines(&self, db: &dyn crate::Db) -> Vec<((Filename, u32), ExecutionCount)> {
+        let mut lines: Map<(Filename, u32), ExecutionCount> = Map::default();
+        for (pc, count) in &self.steps {
+            let span = pc.span(db);
+            let start = dada_ir::lines::line_column(db, span.filename, span.start);
+            lines
+                .entry((span.filename, start.line1()))
+                .or_default()
+                .add(*count);
+        }
+        let mut lines: Vec<_> = lines.into_iter().collect();
+        sort_hottest_first(&mut lines);
+        lines
+    }
+
+    /// Produces a human-readable report listing (at most) `limit`
+    /// of the hottest functions and source lines.
+    pub fn report(&self, db: &dyn crate::Db, limit: usize) -> String {
+        let mut output = String::new();
+
+        output.push_str("hot functions:\n");
+        push_header(&mut output, "function");
+        for (function, count) in self.hot_functions(db).into_iter().take(limit) {
+            push_row(&mut output, count, function.name(db).as_str(db));
+        }
+
+        output.push_str("\nhot lines:\n");
+        push_header(&mut output, "line");
+        for ((filename, line1), count) in self.hot_lines(db).into_iter().take(limit) {
+            let location = format!("{}:{}", filename.as_str(db), line1);
+            push_row(&mut output, count, &location);
+        }
+
+        output
+    }
+
+    /// Produces the profile in the "folded stacks" format consumed by
+    /// `inferno-flamegraph` and `flamegraph.pl`: one line per call stack,
+    /// with frames separated by `;` and followed by the number of
+    /// nanoseconds spent executing in that stack.
+    pub fn folded_stacks(&self, db: &dyn crate::Db) -> String {
+        let mut lines: Vec<String> = self
+            .stacks
+            .iter()
+            .map(|(stack, count)| {
+                let frames: Vec<&str> = stack.iter().map(|f| f.name(db).as_str(db)).collect();
+                format!("{} {}", frames.join(";"), count.elapsed.as_nanos())
+            })
+            .collect();
+        lines.sort();
+
+        let mut output = String::new();
+        for line in lines {
+            output.push_str(&line);
+            output.push('\n');
+        }
+        output
+    }
+}
+
+fn sort_hottest_first<K>(entries: &mut [(K, ExecutionCount)]) {
+    entries.sort_by(|(_, a), (_, b)| {
+        b.elapsed
+            .cmp(&a.elapsed)
+            .then_with(|| b.count.cmp(&a.count))
+    });
+}
+
+fn push_header(output: &mut String, what: &str) {
+    output.push_str(&format!("{:>12} {:>10}  {}\n", "time", "count", what));
+}
+
+fn push_row(output: &mut String, count: ExecutionCount, what: &str) {
+    let elapsed = format!("{:.3?}", count.elapsed);
+    output.push_str(&format!("{:>12} {:>10}  {}\n", elapsed, count.count, what));
+}
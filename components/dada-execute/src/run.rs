@@ -1,20 +1,37 @@
 use dada_brew::prelude::*;
-use dada_ir::function::Function;
+use dada_ir::{error, filename::Filename, function::Function, item::Item, span::Span};
+use dada_parse::prelude::*;
+use tracing::Instrument;
 
 use crate::{
+    host_value::HostValue,
     kernel::Kernel,
-    machine::{op::MachineOp, Machine, Value},
+    machine::{Machine, ProgramCounter, Value},
+    scheduler::{InlineScheduler, Scheduler, STEPS_BETWEEN_YIELDS},
+    snapshot::{RunOutcome, Snapshot},
     step::{ControlFlow, Stepper},
+    thunk::RustThunk,
 };
 
 /// Interprets a given function with the given kernel. Assumes this is the top stack frame.
 /// Prints the result if it is not `()` to stdout.
-#[tracing::instrument(level = "debug", skip(function, db, kernel, arguments))]
 pub async fn interpret(
     function: Function,
     db: &dyn crate::Db,
     kernel: &mut dyn Kernel,
     arguments: Vec<Value>,
+) -> eyre::Result<()> {
+    interpret_with_scheduler(function, db, kernel, &mut InlineScheduler, arguments).await
+}
+
+/// Like [`interpret`], but awaited Rust thunks are run by `scheduler`.
+#[tracing::instrument(level = "debug", skip(function, db, kernel, scheduler, arguments))]
+pub async fn interpret_with_scheduler(
+    function: Function,
+    db: &dyn crate::Db,
+    kernel: &mut dyn Kernel,
+    scheduler: &mut dyn Scheduler,
+    arguments: Vec<Value>,
 ) -> eyre::Result<()> {
     tracing::debug!(
         "function={} arguments={:#?}",
@@ -23,18 +40,243 @@ pub async fn interpret(
     );
     let bir = function.brew(db);
     let machine: &mut Machine = &mut Machine::default();
-    machine.push_frame(db, bir, arguments);
     let mut stepper = Stepper::new(db, machine, kernel);
+    stepper.push_frame(bir, arguments);
+
+    let result = run_to_completion(&mut stepper, scheduler, None, false)
+        .instrument(execute_span(db, function))
+        .await;
+    stepper.record_allocations();
+    result.map(|_| ())
+}
+
+/// Calls `function` with `arguments` supplied by Rust and returns its result,
+/// rather than printing it. Used by programs that embed Dada.
+#[tracing::instrument(level = "debug", skip(function, db, kernel, arguments))]
+pub async fn call(
+    function: Function,
+    db: &dyn crate::Db,
+    kernel: &mut dyn Kernel,
+    arguments: Vec<HostValue>,
+) -> eyre::Result<HostValue> {
+    let parameters = function.parameters(db);
+    if parameters.len() != arguments.len() {
+        return Err(error!(
+            ArgumentCountMismatch,
+            function.name(db).span(db),
+            "expected to find {} arguments, but found {}",
+            parameters.len(),
+            arguments.len(),
+        )
+        .eyre(db));
+    }
+
+    let bir = function.brew(db);
+    let machine: &mut Machine = &mut Machine::default();
+    let arguments = arguments
+        .into_iter()
+        .map(|argument| argument.into_value(db, function.filename(db), &mut *machine))
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    let mut stepper = Stepper::new(db, machine, kernel);
+    stepper.push_frame(bir, arguments);
+    let result = run_until_stopped(&mut stepper, &mut InlineScheduler, None, false)
+        .instrument(execute_span(db, function))
+        .await;
+    stepper.record_allocations();
+    drop(stepper);
+
+    match result? {
+        Stopped::Returned(_, value) => Ok(HostValue::from_value(db, machine, value)),
+        Stopped::Suspended(_) => unreachable!("suspended without being suspendable"),
+    }
+}
+
+/// The function named `main` in `filename`, which `dada run` starts the
+/// program with. It takes either no parameters or one, which is given the
+/// program's arguments (see [`main_arguments`]).
+pub fn main_function(db: &dyn crate::Db, filename: Filename) -> eyre::Result<Function> {
+    let main = filename.items(db).iter().find_map(|&item| match item {
+        Item::Function(function) if function.name(db).as_str(db) == "main" => Some(function),
+        _ => None,
+    });
+    let Some(main) = main else {
+        // Point at the first item, which is where `main` would usually be
+        // found; a file with no items has nothing to point at.
+        return Err(match filename.items(db).first() {
+            Some(item) => error!(
+                MissingMain,
+                item.name_span(db),
+                "there is no function named `main` to run",
+            )
+            .primary_label(format!(
+                "the file starts with `{}`, and no item is named `main`",
+                item.name(db).as_str(db)
+            )),
+            None => error!(
+                MissingMain,
+                Span::from(0_u32, 0_u32).in_file(filename),
+                "there is no function named `main` to run",
+            ),
+        }
+        .eyre(db));
+    };
+    check_main(db, main)?;
+    Ok(main)
+}
+
+fn check_main(db: &dyn crate::Db, main: Function) -> eyre::Result<()> {
+    let parameters = main.parameters(db);
+    if parameters.len() > 1 {
+        return Err(error!(
+            InvalidMain,
+            main.name(db).span(db),
+            "`main` can take at most one parameter, but it takes {}",
+            parameters.len(),
+        )
+        .eyre(db));
+    }
+    Ok(())
+}
+
+/// The arguments to call `main` with: none if it takes no parameters, and
+/// otherwise `args` as a tuple of strings.
+pub fn main_arguments(db: &dyn crate::Db, main: Function, args: &[String]) -> Vec<HostValue> {
+    if main.parameters(db).is_empty() {
+        vec![]
+    } else {
+        let args = args.iter().cloned().map(HostValue::String).collect();
+        vec![HostValue::Tuple(args)]
+    }
+}
+
+/// Interprets `main` like [`interpret`], giving it the arguments of the
+/// kernel's [`ExecutionConfig::args`](crate::config::ExecutionConfig::args)
+/// if it takes a parameter.
+#[tracing::instrument(level = "debug", skip(main, db, kernel))]
+pub async fn interpret_main(
+    main: Function,
+    db: &dyn crate::Db,
+    kernel: &mut dyn Kernel,
+) -> eyre::Result<()> {
+    check_main(db, main)?;
+    let arguments = main_arguments(db, main, &kernel.execution_config().args);
+
+    let bir = main.brew(db);
+    let machine: &mut Machine = &mut Machine::default();
+    let arguments = arguments
+        .into_iter()
+        .map(|argument| argument.into_value(db, main.filename(db), &mut *machine))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    let mut stepper = Stepper::new(db, machine, kernel);
+    stepper.push_frame(bir, arguments);
+
+    let result = run_to_completion(&mut stepper, &mut InlineScheduler, None, false)
+        .instrument(execute_span(db, main))
+        .await;
+    stepper.record_allocations();
+    result.map(|_| ())
+}
+
+/// Like [`interpret_with_scheduler`], but execution is suspended whenever
+/// [`Kernel::suspend_requested`] returns true. The returned snapshot can be
+/// passed to [`resume`] to continue execution.
+#[tracing::instrument(level = "debug", skip(function, db, kernel, scheduler, arguments))]
+pub async fn interpret_suspendable(
+    function: Function,
+    db: &dyn crate::Db,
+    kernel: &mut dyn Kernel,
+    scheduler: &mut dyn Scheduler,
+    arguments: Vec<Value>,
+) -> eyre::Result<RunOutcome> {
+    let bir = function.brew(db);
+    let machine: &mut Machine = &mut Machine::default();
+    let mut stepper = Stepper::new(db, machine, kernel);
+    stepper.push_frame(bir, arguments);
+
+    let result = run_to_completion(&mut stepper, scheduler, None, true)
+        .instrument(execute_span(db, function))
+        .await;
+    stepper.record_allocations();
+    result
+}
+
+/// Continues executing a program from a snapshot taken when it was
+/// suspended. The snapshot must come from a program in `db`.
+#[tracing::instrument(level = "debug", skip(snapshot, db, kernel, scheduler))]
+pub async fn resume(
+    snapshot: Snapshot,
+    db: &dyn crate::Db,
+    kernel: &mut dyn Kernel,
+    scheduler: &mut dyn Scheduler,
+) -> eyre::Result<RunOutcome> {
+    let Snapshot {
+        mut machine,
+        pending_thunk,
+    } = snapshot;
+    let mut stepper = Stepper::new(db, &mut machine, kernel);
+
+    let result = run_to_completion(&mut stepper, scheduler, pending_thunk, true).await;
+    stepper.record_allocations();
+    result
+}
+
+/// The span for running `function` (see [`dada_ir::phase`]).
+fn execute_span(db: &dyn crate::Db, function: Function) -> tracing::Span {
+    dada_ir::phase_span!("execute", db, function.filename(db), function.name(db))
+}
+
+async fn run_to_completion(
+    stepper: &mut Stepper<'_>,
+    scheduler: &mut dyn Scheduler,
+    pending_thunk: Option<RustThunk>,
+    suspendable: bool,
+) -> eyre::Result<RunOutcome> {
+    match run_until_stopped(stepper, scheduler, pending_thunk, suspendable).await? {
+        Stopped::Returned(pc, v) => {
+            scheduler
+                .run(Box::pin(stepper.print_if_not_unit(pc, v)))
+                .await?;
+            Ok(RunOutcome::Completed)
+        }
+        Stopped::Suspended(snapshot) => Ok(RunOutcome::Suspended(snapshot)),
+    }
+}
 
+enum Stopped {
+    /// The top frame returned the given value.
+    Returned(ProgramCounter, Value),
+
+    /// The kernel requested that execution be suspended.
+    Suspended(Snapshot),
+}
+
+async fn run_until_stopped(
+    stepper: &mut Stepper<'_>,
+    scheduler: &mut dyn Scheduler,
+    mut pending_thunk: Option<RustThunk>,
+    suspendable: bool,
+) -> eyre::Result<Stopped> {
+    let mut steps: usize = 0;
     loop {
+        if let Some(t) = pending_thunk.take() {
+            scheduler.run(Box::pin(t.invoke(stepper))).await?;
+        }
+
         tracing::trace!("machine = {:#?}", stepper);
         match stepper.step()? {
             ControlFlow::Next => (),
-            ControlFlow::Await(t) => t.invoke(&mut stepper).await?,
-            ControlFlow::Done(pc, v) => {
-                stepper.print_if_not_unit(pc, v).await?;
-                return Ok(());
-            }
+            ControlFlow::Await(t) => pending_thunk = Some(t),
+            ControlFlow::Done(pc, v) => return Ok(Stopped::Returned(pc, v)),
+        }
+
+        if suspendable && stepper.suspend_requested() {
+            return Ok(Stopped::Suspended(stepper.take_snapshot(pending_thunk)));
+        }
+
+        steps += 1;
+        if steps % STEPS_BETWEEN_YIELDS == 0 {
+            scheduler.yield_now().await;
         }
     }
 }
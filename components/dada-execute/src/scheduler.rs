@@ -0,0 +1,51 @@
+//! The "scheduler" decides how the interpreter runs the Rust futures that
+//! it creates when a program awaits a thunk implemented in Rust (e.g., the
+//! thunk returned by `print`). The default scheduler, [`InlineScheduler`],
+//! simply awaits each future in place, relying on whatever executor is
+//! driving the interpreter; embedders can supply their own scheduler via
+//! [`interpret_with_scheduler`](crate::interpret_with_scheduler) to run those
+//! futures on a particular executor or to control when they make progress.
+
+use std::{future::Future, pin::Pin};
+
+/// The work to be done when a Rust thunk is awaited. Completing the task
+/// stores its result into the awaiting frame, so the interpreter does not
+/// resume until the task is complete.
+///
+/// Tasks borrow the interpreter's state, which is not thread-safe, so they
+/// are not `Send`: a scheduler must run them on the thread that is
+/// interpreting, which is why [`Scheduler`] doesn't require its futures to
+/// be `Send` either.
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<dada_execute::scheduler::Task<'static>>();
+/// ```
+pub type Task<'a> = Pin<Box<dyn Future<Output = eyre::Result<()>> + 'a>>;
+
+#[async_trait::async_trait(?Send)]
+pub trait Scheduler {
+    /// Runs `task` to completion, returning its result.
+    async fn run<'a>(&mut self, task: Task<'a>) -> eyre::Result<()>;
+
+    /// Invoked every [`STEPS_BETWEEN_YIELDS`] steps, so that a scheduler
+    /// driven by a single-threaded event loop (like a web page's) can let
+    /// other work run in the meantime, such as a request to cancel the
+    /// program. Does nothing by default.
+    async fn yield_now(&mut self) {}
+}
+
+/// How many steps the interpreter takes between calls to
+/// [`Scheduler::yield_now`].
+pub const STEPS_BETWEEN_YIELDS: usize = 10_000;
+
+/// Runs each task by awaiting it directly.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct InlineScheduler;
+
+#[async_trait::async_trait(?Send)]
+impl Scheduler for InlineScheduler {
+    async fn run<'a>(&mut self, task: Task<'a>) -> eyre::Result<()> {
+        task.await
+    }
+}
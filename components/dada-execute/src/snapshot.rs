@@ -0,0 +1,87 @@
+//! Snapshots of the interpreter's execution state.
+//!
+//! A [`Snapshot`] captures everything needed to continue executing a
+//! program: the heap, the stack of frames, and the Rust thunk (if any)
+//! that was about to be awaited. Execution is suspended when the kernel
+//! asks for it via [`Kernel::suspend_requested`] and can be continued
+//! later with [`resume`]. Because a snapshot is just data, it can also be
+//! cloned and resumed more than once, e.g. so that a debugger can fork
+//! execution and explore what happens with different inputs.
+//!
+//! In memory, snapshots refer to functions and BIR by their salsa ids,
+//! and so they can only be resumed against the database they were taken
+//! from. [`Snapshot::encode`] turns one into bytes that refer to items by
+//! their file and name instead, so that it can be saved and resumed by
+//! another process (see [`Snapshot::decode`]), as long as the program's
+//! source has not changed.
+//!
+//! [`Kernel::suspend_requested`]: crate::kernel::Kernel::suspend_requested
+//! [`resume`]: crate::resume
+
+use dada_bytecode::DecodeError;
+
+use crate::{
+    machine::{Machine, ProgramCounter},
+    thunk::RustThunk,
+};
+
+mod decode;
+mod encode;
+
+/// Encoded snapshots start with this string.
+const MAGIC: &str = "dada-snapshot";
+
+/// Bumped whenever the encoding changes.
+const SNAPSHOT_VERSION: u32 = 2;
+
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub(crate) machine: Machine,
+
+    /// A Rust thunk that was about to be awaited when execution was
+    /// suspended; it is awaited first when execution resumes.
+    pub(crate) pending_thunk: Option<RustThunk>,
+}
+
+impl Snapshot {
+    /// The machine state at the point where execution was suspended.
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    /// The program counter of the innermost frame.
+    pub fn pc(&self) -> ProgramCounter {
+        self.machine.stack.frames.last().unwrap().pc
+    }
+
+    /// True if execution was suspended just before awaiting a Rust thunk
+    /// (e.g., a call to `print`).
+    pub fn has_pending_thunk(&self) -> bool {
+        self.pending_thunk.is_some()
+    }
+
+    /// Encodes the snapshot, so that it can be saved and later decoded
+    /// with [`Self::decode`].
+    pub fn encode(&self, db: &dyn crate::Db) -> Vec<u8> {
+        encode::encode(db, self)
+    }
+
+    /// Decodes a snapshot written by [`Self::encode`]. The files of the
+    /// program must be loaded into `db`; if any of them has changed since
+    /// the snapshot was taken, this fails with
+    /// [`DecodeError::SourceChanged`].
+    pub fn decode(db: &dyn crate::Db, bytes: &[u8]) -> Result<Snapshot, DecodeError> {
+        decode::decode(db, bytes)
+    }
+}
+
+/// The result of running a program that may be suspended.
+#[derive(Debug)]
+pub enum RunOutcome {
+    /// The program ran to completion.
+    Completed,
+
+    /// The kernel requested that execution be suspended; it can be
+    /// continued from the snapshot.
+    Suspended(Snapshot),
+}
@@ -0,0 +1,402 @@
+use dada_brew::prelude::*;
+use dada_bytecode::{DecodeError, Reader};
+use dada_id::prelude::*;
+use dada_ir::{
+    code::bir,
+    filename::Filename,
+    intrinsic::{self, Intrinsic},
+    item::Item,
+    storage::{Joint, Leased},
+    word::Word,
+};
+use dada_parse::prelude::*;
+use generational_arena::{Arena, Index};
+use num_bigint::BigInt;
+
+use crate::{
+    machine::{
+        Frame, FrameIndex, Heap, Instance, Machine, Object, ObjectData, Permission, PermissionData,
+        PermissionHistory, ProgramCounter, Reservation, ReservationData, Stack, ThunkFn, Tuple,
+        ValidPermissionData, Value,
+    },
+    thunk::{RustThunk, Target},
+};
+
+use super::{Snapshot, MAGIC, SNAPSHOT_VERSION};
+
+/// Decodes a snapshot written by [`super::encode::encode`].
+pub(super) fn decode(db: &dyn crate::Db, bytes: &[u8]) -> Result<Snapshot, DecodeError> {
+    let mut reader = Reader::new(bytes);
+    if reader.str()? != MAGIC || reader.u32()? != SNAPSHOT_VERSION {
+        return Err(DecodeError::Corrupt);
+    }
+
+    let num_filenames = reader.usize()?;
+    let mut filenames = Vec::with_capacity(num_filenames);
+    for _ in 0..num_filenames {
+        let filename = Filename::from(db, reader.str()?);
+        let source_hash = reader.u64()?;
+        let source_text = dada_ir::manifest::source_text(db, filename);
+        if dada_bytecode::source_hash(source_text.as_str()) != source_hash {
+            return Err(DecodeError::SourceChanged {
+                filename: filename.as_str(db).to_string(),
+            });
+        }
+        filenames.push(filename);
+    }
+
+    let mut decoder = Decoder {
+        db,
+        reader,
+        filenames,
+        objects: vec![],
+        permissions: vec![],
+        reservations: vec![],
+    };
+    let snapshot = decoder.snapshot()?;
+    if !decoder.reader.is_at_end() {
+        return Err(DecodeError::Corrupt);
+    }
+    Ok(snapshot)
+}
+
+struct Decoder<'db, 'b> {
+    db: &'db dyn crate::Db,
+    reader: Reader<'b>,
+    filenames: Vec<Filename>,
+
+    /// The index of each object, permission, and reservation, by the
+    /// number it was written with (minus one).
+    objects: Vec<Index>,
+    permissions: Vec<Index>,
+    reservations: Vec<Index>,
+}
+
+impl Decoder<'_, '_> {
+    fn snapshot(&mut self) -> Result<Snapshot, DecodeError> {
+        let mut heap = Heap::default();
+
+        // Reservations refer to nothing else in the heap, so they can be
+        // created as they are read. Objects and permissions refer to each
+        // other, so each gets a placeholder first, which is overwritten
+        // once they all have indices.
+        let num_reservations = self.reader.usize()?;
+        for _ in 0..num_reservations {
+            let data = self.reservation_data()?;
+            self.reservations.push(heap.reservations.insert(data));
+        }
+        let num_objects = self.reader.usize()?;
+        let num_permissions = self.reader.usize()?;
+        self.objects = placeholders(&mut heap.objects, num_objects, || ObjectData::Unit(()));
+        self.permissions = placeholders(&mut heap.permissions, num_permissions, || {
+            PermissionData::Expired(None)
+        });
+
+        for i in 0..num_objects {
+            let data = self.object_data()?;
+            heap.string_bytes += match &data {
+                ObjectData::String(s) => s.len(),
+                _ => 0,
+            };
+            heap.objects[self.objects[i]] = data;
+        }
+        for i in 0..num_permissions {
+            heap.permissions[self.permissions[i]] = self.permission_data()?;
+        }
+
+        let num_histories = self.reader.usize()?;
+        for _ in 0..num_histories {
+            let permission = self.permission()?;
+            let granted = if self.reader.bool()? {
+                let pc = self.pc()?;
+                let (joint, leased) = self.joint_leased()?;
+                Some((pc, joint, leased))
+            } else {
+                None
+            };
+            let revoked = self.optional_pc()?;
+            heap.permission_history
+                .insert(permission, PermissionHistory { granted, revoked });
+        }
+        heap.objects_after_gc = self.reader.usize()?;
+        let unit_object = self.object()?;
+
+        let num_frames = self.reader.usize()?;
+        let mut stack = Stack::default();
+        for _ in 0..num_frames {
+            let pc = self.pc()?;
+            let num_locals = self.reader.usize()?;
+            if num_locals != usize::from(pc.bir.data(self.db).max_local_variable()) {
+                return Err(DecodeError::Corrupt);
+            }
+            let locals = (0..num_locals)
+                .map(|_| self.value())
+                .collect::<Result<_, _>>()?;
+            stack.frames.push(Frame { pc, locals });
+        }
+        if heap
+            .reservations
+            .iter()
+            .any(|(_, data)| usize::from(data.frame_index) >= num_frames)
+        {
+            return Err(DecodeError::Corrupt);
+        }
+
+        let pending_thunk = if self.reader.bool()? {
+            Some(self.rust_thunk()?)
+        } else {
+            None
+        };
+
+        Ok(Snapshot {
+            machine: Machine {
+                heap,
+                stack,
+                unit_object,
+            },
+            pending_thunk,
+        })
+    }
+
+    fn filename(&mut self) -> Result<Filename, DecodeError> {
+        let index = self.reader.usize()?;
+        self.filenames
+            .get(index)
+            .copied()
+            .ok_or(DecodeError::Corrupt)
+    }
+
+    fn word(&mut self) -> Result<Word, DecodeError> {
+        Ok(Word::from(self.db, self.reader.str()?))
+    }
+
+    /// Finds the item with the name that is read in the file that is
+    /// read, provided `select` accepts it.
+    fn item<T>(&mut self, select: impl Fn(Item) -> Option<T>) -> Result<T, DecodeError> {
+        let filename = self.filename()?;
+        let name = self.word()?;
+        filename
+            .items(self.db)
+            .iter()
+            .filter(|item| item.name(self.db) == name)
+            .find_map(|&item| select(item))
+            .ok_or(DecodeError::Corrupt)
+    }
+
+    fn pc(&mut self) -> Result<ProgramCounter, DecodeError> {
+        let function = self.item(|item| match item {
+            Item::Function(function) => Some(function),
+            _ => None,
+        })?;
+        let bir = function.brew(self.db);
+        let basic_block = bir::BasicBlock::from(self.reader.usize()?);
+        let statement = self.reader.usize()?;
+        let bir_data = bir.data(self.db);
+        if basic_block >= bir_data.max_basic_block()
+            || statement > bir_data.tables[basic_block].statements.len()
+        {
+            return Err(DecodeError::Corrupt);
+        }
+        Ok(ProgramCounter {
+            bir,
+            basic_block,
+            statement,
+        })
+    }
+
+    fn optional_pc(&mut self) -> Result<Option<ProgramCounter>, DecodeError> {
+        if self.reader.bool()? {
+            Ok(Some(self.pc()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn object(&mut self) -> Result<Object, DecodeError> {
+        let index = reference(&mut self.reader, &self.objects)?;
+        Ok(Object { index })
+    }
+
+    fn permission(&mut self) -> Result<Permission, DecodeError> {
+        let index = reference(&mut self.reader, &self.permissions)?;
+        Ok(Permission { index })
+    }
+
+    fn reservation(&mut self) -> Result<Reservation, DecodeError> {
+        let index = reference(&mut self.reader, &self.reservations)?;
+        Ok(Reservation { index })
+    }
+
+    fn value(&mut self) -> Result<Value, DecodeError> {
+        Ok(Value {
+            object: self.object()?,
+            permission: self.permission()?,
+        })
+    }
+
+    fn values(&mut self) -> Result<Vec<Value>, DecodeError> {
+        let len = self.reader.usize()?;
+        (0..len).map(|_| self.value()).collect()
+    }
+
+    fn reservation_data(&mut self) -> Result<ReservationData, DecodeError> {
+        let pc = self.pc()?;
+        let frame_index = FrameIndex::from(self.reader.usize()?);
+        let place = bir::Place::from(self.reader.usize()?);
+        if place >= bir::Place::max_key(&pc.bir.data(self.db).tables) {
+            return Err(DecodeError::Corrupt);
+        }
+        Ok(ReservationData {
+            pc,
+            frame_index,
+            place,
+        })
+    }
+
+    fn object_data(&mut self) -> Result<ObjectData, DecodeError> {
+        Ok(match self.reader.u8()? {
+            0 => {
+                let class = self.class()?;
+                let fields = self.values()?;
+                ObjectData::Instance(Instance { class, fields })
+            }
+            1 => ObjectData::Reservation(self.reservation()?),
+            2 => ObjectData::Class(self.class()?),
+            3 => ObjectData::Function(self.function()?),
+            4 => ObjectData::Intrinsic(self.intrinsic()?),
+            5 => ObjectData::HostFunction(self.word()?),
+            6 => ObjectData::ExternFunction(self.item(|item| match item {
+                Item::ExternFunction(function) => Some(function),
+                _ => None,
+            })?),
+            7 => {
+                let function = self.function()?;
+                let arguments = self.values()?;
+                ObjectData::ThunkFn(ThunkFn {
+                    function,
+                    arguments,
+                })
+            }
+            8 => ObjectData::ThunkRust(self.rust_thunk()?),
+            9 => ObjectData::Tuple(Tuple {
+                fields: self.values()?,
+            }),
+            10 => ObjectData::Weak(self.value()?),
+            11 => ObjectData::Bool(self.reader.bool()?),
+            12 => ObjectData::UnsignedInt(self.reader.u64()?),
+            13 => ObjectData::Int(self.reader.u64()?),
+            14 => ObjectData::SignedInt(self.reader.i64()?),
+            15 => ObjectData::BigInt(
+                self.reader
+                    .str()?
+                    .parse::<BigInt>()
+                    .map_err(|_| DecodeError::Corrupt)?,
+            ),
+            16 => ObjectData::Float(self.reader.f64()?),
+            17 => ObjectData::String(self.reader.str()?.to_string()),
+            18 => ObjectData::Unit(()),
+            19 => ObjectData::WeakGet(self.value()?),
+            _ => return Err(DecodeError::Corrupt),
+        })
+    }
+
+    fn class(&mut self) -> Result<dada_ir::class::Class, DecodeError> {
+        self.item(|item| match item {
+            Item::Class(class) => Some(class),
+            _ => None,
+        })
+    }
+
+    fn function(&mut self) -> Result<dada_ir::function::Function, DecodeError> {
+        self.item(|item| match item {
+            Item::Function(function) => Some(function),
+            _ => None,
+        })
+    }
+
+    fn intrinsic(&mut self) -> Result<Intrinsic, DecodeError> {
+        Ok(Intrinsic::from(self.db, self.reader.str()?))
+    }
+
+    fn rust_thunk(&mut self) -> Result<RustThunk, DecodeError> {
+        let (description, target) = match self.reader.u8()? {
+            0 => {
+                // Thunks for intrinsics are described by the intrinsic's
+                // name, which must be one of the standard ones.
+                let name = self.reader.str()?;
+                let description = intrinsic::STANDARD
+                    .iter()
+                    .copied()
+                    .find(|&standard| standard == name)
+                    .ok_or(DecodeError::Corrupt)?;
+                (
+                    description,
+                    Target::Intrinsic(Intrinsic::from(self.db, name)),
+                )
+            }
+            1 => ("host function", Target::HostFunction(self.word()?)),
+            _ => return Err(DecodeError::Corrupt),
+        };
+        let pc = self.pc()?;
+        let arguments = self.values()?;
+        Ok(RustThunk {
+            description,
+            pc,
+            arguments,
+            target,
+        })
+    }
+
+    fn permission_data(&mut self) -> Result<PermissionData, DecodeError> {
+        Ok(match self.reader.u8()? {
+            0 => PermissionData::Expired(self.optional_pc()?),
+            1 => {
+                let (joint, leased) = self.joint_leased()?;
+                let num_reservations = self.reader.usize()?;
+                let reservations = (0..num_reservations)
+                    .map(|_| self.reservation())
+                    .collect::<Result<_, _>>()?;
+                let num_tenants = self.reader.usize()?;
+                let tenants = (0..num_tenants)
+                    .map(|_| self.permission())
+                    .collect::<Result<_, _>>()?;
+                PermissionData::Valid(ValidPermissionData {
+                    joint,
+                    leased,
+                    reservations,
+                    tenants,
+                })
+            }
+            _ => return Err(DecodeError::Corrupt),
+        })
+    }
+
+    fn joint_leased(&mut self) -> Result<(Joint, Leased), DecodeError> {
+        let joint = if self.reader.bool()? {
+            Joint::Yes
+        } else {
+            Joint::No
+        };
+        let leased = if self.reader.bool()? {
+            Leased::Yes
+        } else {
+            Leased::No
+        };
+        Ok((joint, leased))
+    }
+}
+
+/// Reads a reference to the `n`th entry of `indices` (written as `n + 1`),
+/// or to something that had been freed (written as `0`), which becomes an
+/// index that refers to nothing.
+fn reference(reader: &mut Reader<'_>, indices: &[Index]) -> Result<Index, DecodeError> {
+    match reader.usize()? {
+        0 => Ok(Index::from_raw_parts(usize::MAX, u64::MAX)),
+        n => indices.get(n - 1).copied().ok_or(DecodeError::Corrupt),
+    }
+}
+
+/// Inserts `len` placeholders into `arena`, returning their indices.
+fn placeholders<T>(arena: &mut Arena<T>, len: usize, placeholder: impl Fn() -> T) -> Vec<Index> {
+    (0..len).map(|_| arena.insert(placeholder())).collect()
+}
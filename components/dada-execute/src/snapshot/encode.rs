@@ -0,0 +1,332 @@
+use dada_bytecode::Writer;
+use dada_collections::Map;
+use dada_ir::{
+    filename::Filename,
+    item::Item,
+    storage::{Joint, Leased},
+    word::Word,
+};
+use generational_arena::Index;
+
+use crate::{
+    machine::{
+        Machine, Object, ObjectData, Permission, PermissionData, ProgramCounter, Reservation,
+        ReservationData, Value,
+    },
+    thunk::{RustThunk, Target},
+};
+
+use super::{Snapshot, MAGIC, SNAPSHOT_VERSION};
+
+/// Encodes `snapshot`. Objects, permissions, and reservations are
+/// numbered in the order they are written, and refer to each other by
+/// those numbers (`0` stands for one that has been freed). Items are
+/// written as the file they are in and their name.
+pub(super) fn encode(db: &dyn crate::Db, snapshot: &Snapshot) -> Vec<u8> {
+    let heap = &snapshot.machine.heap;
+    let mut encoder = Encoder {
+        db,
+        filenames: Map::default(),
+        objects: number(heap.objects.iter().map(|(index, _)| index)),
+        permissions: number(heap.permissions.iter().map(|(index, _)| index)),
+        reservations: number(heap.reservations.iter().map(|(index, _)| index)),
+    };
+    let mut body = Writer::default();
+    encoder.snapshot(&mut body, snapshot);
+
+    // The files come first, so that decoding can check that none of them
+    // have changed before it looks up any items.
+    let mut writer = Writer::default();
+    writer.str(MAGIC);
+    writer.u32(SNAPSHOT_VERSION);
+    let mut filenames: Vec<_> = encoder.filenames.into_iter().collect();
+    filenames.sort_by_key(|&(_, index)| index);
+    writer.usize(filenames.len());
+    for (filename, _) in filenames {
+        writer.str(filename.as_str(db));
+        writer.u64(dada_bytecode::source_hash(
+            dada_ir::manifest::source_text(db, filename).as_str(),
+        ));
+    }
+    writer.append(body);
+    writer.into_bytes()
+}
+
+/// Numbers the given indices `1..`, in order.
+fn number(indices: impl Iterator<Item = Index>) -> Map<Index, usize> {
+    let mut indices: Vec<Index> = indices.collect();
+    indices.sort();
+    indices.into_iter().zip(1..).collect()
+}
+
+struct Encoder<'db> {
+    db: &'db dyn crate::Db,
+    filenames: Map<Filename, usize>,
+    objects: Map<Index, usize>,
+    permissions: Map<Index, usize>,
+    reservations: Map<Index, usize>,
+}
+
+impl Encoder<'_> {
+    fn snapshot(&mut self, writer: &mut Writer, snapshot: &Snapshot) {
+        let Machine {
+            heap,
+            stack,
+            unit_object,
+        } = &snapshot.machine;
+
+        writer.usize(self.reservations.len());
+        for (_, data) in sorted(&heap.reservations) {
+            self.reservation_data(writer, data);
+        }
+
+        writer.usize(self.objects.len());
+        writer.usize(self.permissions.len());
+        for (_, data) in sorted(&heap.objects) {
+            self.object_data(writer, data);
+        }
+        for (_, data) in sorted(&heap.permissions) {
+            self.permission_data(writer, data);
+        }
+
+        let mut history: Vec<_> = heap.permission_history.iter().collect();
+        history.sort_by_key(|&(permission, _)| *permission);
+        writer.usize(history.len());
+        for (&permission, history) in history {
+            self.permission(writer, permission);
+            match history.granted {
+                Some((pc, joint, leased)) => {
+                    writer.bool(true);
+                    self.pc(writer, pc);
+                    joint_leased(writer, joint, leased);
+                }
+                None => writer.bool(false),
+            }
+            self.optional_pc(writer, history.revoked);
+        }
+        writer.usize(heap.objects_after_gc);
+        self.object(writer, *unit_object);
+
+        writer.usize(stack.frames.len());
+        for frame in &stack.frames {
+            self.pc(writer, frame.pc);
+            writer.usize(frame.locals.len());
+            for &value in &frame.locals {
+                self.value(writer, value);
+            }
+        }
+
+        match &snapshot.pending_thunk {
+            Some(thunk) => {
+                writer.bool(true);
+                self.rust_thunk(writer, thunk);
+            }
+            None => writer.bool(false),
+        }
+    }
+
+    fn filename(&mut self, writer: &mut Writer, filename: Filename) {
+        let next = self.filenames.len();
+        let index = *self.filenames.entry(filename).or_insert(next);
+        writer.usize(index);
+    }
+
+    fn word(&mut self, writer: &mut Writer, word: Word) {
+        writer.str(word.as_str(self.db));
+    }
+
+    fn item(&mut self, writer: &mut Writer, item: impl Into<Item>) {
+        let item = item.into();
+        self.filename(writer, item.span(self.db).filename);
+        self.word(writer, item.name(self.db));
+    }
+
+    fn pc(&mut self, writer: &mut Writer, pc: ProgramCounter) {
+        self.item(writer, pc.bir.origin(self.db));
+        writer.usize(pc.basic_block.into());
+        writer.usize(pc.statement);
+    }
+
+    fn object(&mut self, writer: &mut Writer, object: Object) {
+        writer.usize(self.objects.get(&object.index).copied().unwrap_or(0));
+    }
+
+    fn permission(&mut self, writer: &mut Writer, permission: Permission) {
+        writer.usize(
+            self.permissions
+                .get(&permission.index)
+                .copied()
+                .unwrap_or(0),
+        );
+    }
+
+    fn reservation(&mut self, writer: &mut Writer, reservation: Reservation) {
+        writer.usize(
+            self.reservations
+                .get(&reservation.index)
+                .copied()
+                .unwrap_or(0),
+        );
+    }
+
+    fn value(&mut self, writer: &mut Writer, value: Value) {
+        self.object(writer, value.object);
+        self.permission(writer, value.permission);
+    }
+
+    fn values(&mut self, writer: &mut Writer, values: &[Value]) {
+        writer.usize(values.len());
+        for &value in values {
+            self.value(writer, value);
+        }
+    }
+
+    fn reservation_data(&mut self, writer: &mut Writer, data: &ReservationData) {
+        self.pc(writer, data.pc);
+        writer.usize(data.frame_index.into());
+        writer.usize(data.place.into());
+    }
+
+    fn object_data(&mut self, writer: &mut Writer, data: &ObjectData) {
+        match data {
+            ObjectData::Instance(instance) => {
+                writer.u8(0);
+                self.item(writer, instance.class);
+                self.values(writer, &instance.fields);
+            }
+            ObjectData::Reservation(reservation) => {
+                writer.u8(1);
+                self.reservation(writer, *reservation);
+            }
+            ObjectData::Class(class) => {
+                writer.u8(2);
+                self.item(writer, *class);
+            }
+            ObjectData::Function(function) => {
+                writer.u8(3);
+                self.item(writer, *function);
+            }
+            ObjectData::Intrinsic(intrinsic) => {
+                writer.u8(4);
+                self.word(writer, intrinsic.name(self.db));
+            }
+            ObjectData::HostFunction(name) => {
+                writer.u8(5);
+                self.word(writer, *name);
+            }
+            ObjectData::ExternFunction(function) => {
+                writer.u8(6);
+                self.item(writer, *function);
+            }
+            ObjectData::ThunkFn(thunk) => {
+                writer.u8(7);
+                self.item(writer, thunk.function);
+                self.values(writer, &thunk.arguments);
+            }
+            ObjectData::ThunkRust(thunk) => {
+                writer.u8(8);
+                self.rust_thunk(writer, thunk);
+            }
+            ObjectData::Tuple(tuple) => {
+                writer.u8(9);
+                self.values(writer, &tuple.fields);
+            }
+            ObjectData::Weak(value) => {
+                writer.u8(10);
+                self.value(writer, *value);
+            }
+            ObjectData::Bool(b) => {
+                writer.u8(11);
+                writer.bool(*b);
+            }
+            ObjectData::UnsignedInt(i) => {
+                writer.u8(12);
+                writer.u64(*i);
+            }
+            ObjectData::Int(i) => {
+                writer.u8(13);
+                writer.u64(*i);
+            }
+            ObjectData::SignedInt(i) => {
+                writer.u8(14);
+                writer.i64(*i);
+            }
+            ObjectData::BigInt(i) => {
+                writer.u8(15);
+                writer.str(&i.to_string());
+            }
+            ObjectData::Float(f) => {
+                writer.u8(16);
+                writer.f64(*f);
+            }
+            ObjectData::String(s) => {
+                writer.u8(17);
+                writer.str(s);
+            }
+            ObjectData::Unit(()) => writer.u8(18),
+            ObjectData::WeakGet(value) => {
+                writer.u8(19);
+                self.value(writer, *value);
+            }
+        }
+    }
+
+    fn rust_thunk(&mut self, writer: &mut Writer, thunk: &RustThunk) {
+        match &thunk.target {
+            Target::Intrinsic(intrinsic) => {
+                writer.u8(0);
+                self.word(writer, intrinsic.name(self.db));
+            }
+            Target::HostFunction(name) => {
+                writer.u8(1);
+                self.word(writer, *name);
+            }
+        }
+        self.pc(writer, thunk.pc);
+        self.values(writer, &thunk.arguments);
+    }
+
+    fn permission_data(&mut self, writer: &mut Writer, data: &PermissionData) {
+        match data {
+            PermissionData::Expired(pc) => {
+                writer.u8(0);
+                self.optional_pc(writer, *pc);
+            }
+            PermissionData::Valid(valid) => {
+                writer.u8(1);
+                joint_leased(writer, valid.joint, valid.leased);
+                writer.usize(valid.reservations.len());
+                for &reservation in &valid.reservations {
+                    self.reservation(writer, reservation);
+                }
+                writer.usize(valid.tenants.len());
+                for &tenant in &valid.tenants {
+                    self.permission(writer, tenant);
+                }
+            }
+        }
+    }
+
+    fn optional_pc(&mut self, writer: &mut Writer, pc: Option<ProgramCounter>) {
+        match pc {
+            Some(pc) => {
+                writer.bool(true);
+                self.pc(writer, pc);
+            }
+            None => writer.bool(false),
+        }
+    }
+}
+
+fn joint_leased(writer: &mut Writer, joint: Joint, leased: Leased) {
+    writer.bool(joint == Joint::Yes);
+    writer.bool(leased == Leased::Yes);
+}
+
+/// The entries of `arena` in the order of their indices, which is the
+/// order they are numbered in.
+fn sorted<T>(arena: &generational_arena::Arena<T>) -> Vec<(Index, &T)> {
+    let mut entries: Vec<_> = arena.iter().collect();
+    entries.sort_by_key(|&(index, _)| index);
+    entries
+}
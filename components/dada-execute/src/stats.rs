@@ -0,0 +1,163 @@
+//! Heap and allocation statistics, gathered over the course of a program's
+//! execution to help users understand its behavior.
+//!
+//! Statistics are gathered by returning a `HeapStats` from [`Kernel::heap_stats`].
+//!
+//! [`Kernel::heap_stats`]: crate::kernel::Kernel::heap_stats
+
+use dada_collections::Map;
+use dada_ir::class::Class;
+
+use crate::machine::ObjectData;
+
+#[derive(Debug, Default)]
+pub struct HeapStats {
+    /// Largest number of objects that were live at the end of any step.
+    peak_live_objects: usize,
+
+    /// Number of objects allocated over the course of execution, by kind.
+    allocations: Map<AllocationKind, u64>,
+
+    /// Number of times each permission operation was performed.
+    permission_operations: Map<PermissionOperation, u64>,
+}
+
+/// What kind of object was allocated: classes are counted separately
+/// for each class, all other kinds of objects are grouped by type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AllocationKind {
+    Instance(Class),
+    Builtin(&'static str),
+}
+
+impl AllocationKind {
+    pub(crate) fn of(data: &ObjectData) -> Self {
+        let builtin = match data {
+            ObjectData::Instance(i) => return AllocationKind::Instance(i.class),
+            ObjectData::Reservation(_) => "reservation",
+            ObjectData::Class(_) => "class",
+            ObjectData::Function(_) => "function",
+            ObjectData::Intrinsic(_) => "intrinsic",
+            ObjectData::HostFunction(_) => "host function",
+            ObjectData::ExternFunction(_) => "extern function",
+            ObjectData::ThunkFn(_) | ObjectData::ThunkRust(_) => "thunk",
+            ObjectData::Tuple(_) => "tuple",
+            ObjectData::Weak(_) => "weak reference",
+            ObjectData::WeakGet(_) => "method",
+            ObjectData::Bool(_) => "bool",
+            ObjectData::UnsignedInt(_) => "unsigned integer",
+            ObjectData::Int(_) => "integer",
+            ObjectData::SignedInt(_) => "signed integer",
+            ObjectData::BigInt(_) => "big integer",
+            ObjectData::Float(_) => "float",
+            ObjectData::String(_) => "string",
+            ObjectData::Unit(_) => "unit",
+        };
+        AllocationKind::Builtin(builtin)
+    }
+
+    fn describe(self, db: &dyn crate::Db) -> String {
+        match self {
+            AllocationKind::Instance(class) => format!("class `{}`", class.name(db).as_str(db)),
+            AllocationKind::Builtin(s) => s.to_string(),
+        }
+    }
+}
+
+/// Operations that create a new permission from an existing place.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PermissionOperation {
+    Give,
+    Lease,
+    Share,
+    Shlease,
+}
+
+impl PermissionOperation {
+    pub const ALL: &'static [PermissionOperation] = &[
+        PermissionOperation::Give,
+        PermissionOperation::Lease,
+        PermissionOperation::Share,
+        PermissionOperation::Shlease,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PermissionOperation::Give => "give",
+            PermissionOperation::Lease => "lease",
+            PermissionOperation::Share => "share",
+            PermissionOperation::Shlease => "shlease",
+        }
+    }
+}
+
+impl HeapStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn observe_live_objects(&mut self, live_objects: usize) {
+        self.peak_live_objects = self.peak_live_objects.max(live_objects);
+    }
+
+    pub(crate) fn record_allocations(&mut self, allocations: &Map<AllocationKind, u64>) {
+        for (kind, count) in allocations {
+            *self.allocations.entry(*kind).or_default() += count;
+        }
+    }
+
+    pub(crate) fn record_permission_operation(&mut self, operation: PermissionOperation) {
+        *self.permission_operations.entry(operation).or_default() += 1;
+    }
+
+    /// Largest number of objects that were live at any point.
+    pub fn peak_live_objects(&self) -> usize {
+        self.peak_live_objects
+    }
+
+    /// Total number of objects allocated.
+    pub fn total_allocations(&self) -> u64 {
+        self.allocations.values().sum()
+    }
+
+    /// Number of objects allocated of the given kind.
+    pub fn allocations(&self, kind: AllocationKind) -> u64 {
+        self.allocations.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// Number of times the given permission operation was performed.
+    pub fn permission_operations(&self, operation: PermissionOperation) -> u64 {
+        self.permission_operations
+            .get(&operation)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Produces a human-readable report of the statistics.
+    pub fn report(&self, db: &dyn crate::Db) -> String {
+        let mut output = String::new();
+        output.push_str(&format!("peak live objects: {}\n", self.peak_live_objects));
+
+        output.push_str(&format!("\nallocations: {}\n", self.total_allocations()));
+        let mut allocations: Vec<(String, u64)> = self
+            .allocations
+            .iter()
+            .map(|(kind, count)| (kind.describe(db), *count))
+            .collect();
+        allocations.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then_with(|| a_name.cmp(b_name)));
+        for (name, count) in allocations {
+            output.push_str(&format!("{:>10}  {}\n", count, name));
+        }
+
+        output.push_str("\npermission operations:\n");
+        for &operation in PermissionOperation::ALL {
+            output.push_str(&format!(
+                "{:>10}  {}\n",
+                self.permission_operations(operation),
+                operation.as_str()
+            ));
+        }
+
+        output
+    }
+}
@@ -1,3 +1,7 @@
+#[cfg(feature = "trace")]
+use std::time::Duration;
+
+use dada_collections::Map;
 use dada_id::prelude::*;
 use dada_ir::{
     class::Class,
@@ -7,6 +11,7 @@ use dada_ir::{
     },
     error,
     in_ir_db::InIrDbExt,
+    intrinsic::Intrinsic,
     origin_table::HasOriginIn,
     span::FileSpan,
     storage::{Atomic, Joint, Leased, SpannedSpecifier, Specifier},
@@ -16,16 +21,25 @@ use dada_parse::prelude::*;
 use salsa::DebugWithDb;
 
 use crate::{
+    clock::Clock,
+    config::ExecutionConfig,
+    coverage::Coverage,
     error::DiagnosticBuilderExt,
     heap_graph::HeapGraph,
     kernel::Kernel,
     machine::{
-        op::MachineOp, Object, ObjectData, ProgramCounter, Tuple, ValidPermissionData, Value,
+        op::MachineOp, Frame, Object, ObjectData, ProgramCounter, Tuple, ValidPermissionData, Value,
     },
+    profile::Profiler,
+    snapshot::Snapshot,
+    stats::{HeapStats, PermissionOperation},
     thunk::RustThunk,
 };
 
-use self::traversal::PlaceTraversal;
+#[cfg(feature = "trace")]
+use crate::trace_event::TraceRecorder;
+
+use self::{intrinsic::IntrinsicDefinition, traversal::PlaceTraversal};
 
 mod access;
 mod address;
@@ -34,10 +48,16 @@ mod apply_unary;
 mod assert_invariants;
 mod await_thunk;
 mod call;
+#[cfg(feature = "ffi")]
+mod ffi;
 mod gc;
 mod give;
+mod host_function;
 mod intrinsic;
+mod jit;
 mod lease;
+#[cfg(not(feature = "ffi"))]
+mod no_ffi;
 mod reserve;
 mod revoke;
 mod share;
@@ -52,6 +72,20 @@ pub(crate) struct Stepper<'me> {
     /// Kernel for core operations. This is normally `Some`, but we sometimes
     /// temporarily swap with `None` for callbacks.
     kernel: Option<&'me mut dyn Kernel>,
+
+    /// Configuration supplied by the kernel when execution began.
+    config: ExecutionConfig,
+
+    /// Timestamps profiling and tracing events.
+    clock: Clock,
+
+    /// When validating the JIT, the results it produced for calls that
+    /// are still being interpreted (innermost last).
+    jit_expectations: Vec<jit::JitExpectation>,
+
+    /// The definitions of the standard intrinsics; any other intrinsic is
+    /// run by the host function of the same name.
+    intrinsics: Map<Intrinsic, IntrinsicDefinition>,
 }
 
 impl std::fmt::Debug for Stepper<'_> {
@@ -81,10 +115,16 @@ impl<'me> Stepper<'me> {
         machine: &'me mut dyn MachineOp,
         kernel: &'me mut dyn Kernel,
     ) -> Self {
+        let config = kernel.execution_config();
+        machine.count_allocations(kernel.heap_stats().is_some());
         Self {
             db,
             machine,
             kernel: Some(kernel),
+            clock: Clock::new(config.deterministic),
+            config,
+            jit_expectations: vec![],
+            intrinsics: IntrinsicDefinition::standard(db),
         }
     }
 
@@ -94,43 +134,75 @@ impl<'me> Stepper<'me> {
     /// Note that this function is synchronous: it never awaits or does I/O.
     #[tracing::instrument(level = "Debug", skip(self))]
     pub(crate) fn step(&mut self) -> eyre::Result<ControlFlow> {
-        let mut pc = self.machine.pc();
+        let pc = self.machine.pc();
+        self.check_cancelled(pc)?;
+
+        // Only walk the call stack and consult the clock when profiling,
+        // since the system clock is not available on all targets.
+        let profile_start = match self.kernel.as_mut().and_then(|kernel| kernel.profiler()) {
+            Some(profiler) => {
+                let db = self.db;
+                let frames = self.machine.frames();
+                profiler.enter_step(frames.iter().map(|frame| frame.pc.bir.origin(db)));
+                Some(self.clock.now())
+            }
+            None => None,
+        };
+
+        if let Some(coverage) = self.coverage() {
+            coverage.record(pc);
+        }
+
+        let cf = self.step_pc(pc)?;
+        self.clock.tick();
+
+        self.check_memory_limits(pc)?;
+
+        let live_objects = self.machine.view().heap.objects.len();
+        if let Some(stats) = self.heap_stats() {
+            stats.observe_live_objects(live_objects);
+        }
+
+        if let Some(start) = profile_start {
+            let elapsed = self.clock.now() - start;
+            if let Some(profiler) = self.profiler() {
+                profiler.record_step(pc, elapsed);
+            }
+        }
+
+        Ok(cf)
+    }
+
+    fn step_pc(&mut self, mut pc: ProgramCounter) -> eyre::Result<ControlFlow> {
         let bir_data = pc.bir.data(self.db);
         let table = &bir_data.tables;
 
-        let basic_block_data = pc.basic_block.data(table);
+        let code = bir_data.code(pc.basic_block);
 
         // The statement should either be the index of a statement or
         // the terminator.
         assert!(
-            pc.statement <= basic_block_data.statements.len(),
+            pc.statement <= code.statements.len(),
             "statement index out of range"
         );
 
-        let pc_span = pc.span(self.db);
-        let snippet = pc_span.snippet(self.db);
-        if snippet.len() > 50 {
-            tracing::debug!(
-                "executing: {:?}...{:?}",
-                &snippet[..25],
-                &snippet[snippet.len() - 25..]
-            );
-        } else {
-            tracing::debug!("executing {:?}", snippet);
-        }
+        // Finding the snippet means finding the span, so it is only done
+        // when the message is logged.
+        tracing::debug!("executing {}", abbreviated_snippet(self.db, pc));
 
-        if pc.statement < basic_block_data.statements.len() {
-            self.step_statement(table, pc.bir, basic_block_data.statements[pc.statement])?;
+        if let Some((statement, statement_code)) = code.statements.get(pc.statement) {
+            self.step_statement(table, pc.bir, *statement, statement_code)?;
             pc.statement += 1;
             self.machine.set_pc(pc);
-            self.gc(&[]);
+            self.maybe_gc(&[]);
             self.assert_invariants()?;
             return Ok(ControlFlow::Next);
         }
 
-        let cf = self.step_terminator(table, pc, basic_block_data.terminator)?;
+        let (terminator, terminator_data) = &code.terminator;
+        let cf = self.step_terminator(table, pc, *terminator, terminator_data)?;
         let temp;
-        self.gc(match &cf {
+        self.maybe_gc(match &cf {
             ControlFlow::Next => &[],
             ControlFlow::Await(v) => &v.arguments[..],
             ControlFlow::Done(_, v) => {
@@ -142,6 +214,156 @@ impl<'me> Stepper<'me> {
         Ok(cf)
     }
 
+    /// The machine being stepped, for callers that inspect it between steps.
+    #[cfg(feature = "debugger")]
+    pub(crate) fn machine(&self) -> &dyn MachineOp {
+        &*self.machine
+    }
+
+    /// Returns the kernel's profiler, if profiling is enabled.
+    fn profiler(&mut self) -> Option<&mut Profiler> {
+        self.kernel.as_mut()?.profiler()
+    }
+
+    /// Returns the kernel's coverage recorder, if coverage is enabled.
+    fn coverage(&mut self) -> Option<&mut Coverage> {
+        self.kernel.as_mut()?.coverage()
+    }
+
+    /// Reports an error if the heap has outgrown the limits given in the
+    /// configuration. The garbage collector has already run (if it is
+    /// going to), so any objects that remain are live.
+    fn check_memory_limits(&self, pc: ProgramCounter) -> eyre::Result<()> {
+        let heap = &self.machine.view().heap;
+
+        let label = if self.object_limit_exceeded() {
+            format!(
+                "the program has {} live objects, but at most {} are allowed",
+                heap.objects.len(),
+                self.config.max_objects.unwrap_or_default(),
+            )
+        } else if self.string_limit_exceeded() {
+            format!(
+                "the program has {} bytes of live strings, but at most {} are allowed",
+                heap.string_bytes,
+                self.config.max_string_bytes.unwrap_or_default(),
+            )
+        } else {
+            return Ok(());
+        };
+
+        Err(error!(
+            MemoryLimitExceeded,
+            pc.span(self.db),
+            "memory limit exceeded"
+        )
+        .primary_label(label)
+        .eyre(self.db))
+    }
+
+    fn check_cancelled(&self, pc: ProgramCounter) -> eyre::Result<()> {
+        if self
+            .config
+            .cancellation
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+        {
+            return Err(
+                error!(Cancelled, pc.span(self.db), "execution was cancelled").eyre(self.db),
+            );
+        }
+        Ok(())
+    }
+
+    fn object_limit_exceeded(&self) -> bool {
+        let objects = self.machine.view().heap.objects.len();
+        self.config.max_objects.is_some_and(|max| objects > max)
+    }
+
+    fn string_limit_exceeded(&self) -> bool {
+        let string_bytes = self.machine.view().heap.string_bytes;
+        self.config
+            .max_string_bytes
+            .is_some_and(|max| string_bytes > max)
+    }
+
+    /// Returns the kernel's heap statistics, if statistics are enabled.
+    fn heap_stats(&mut self) -> Option<&mut HeapStats> {
+        self.kernel.as_mut()?.heap_stats()
+    }
+
+    /// Records that a permission operation was performed, if statistics are enabled.
+    fn record_permission_operation(&mut self, operation: PermissionOperation) {
+        if let Some(stats) = self.heap_stats() {
+            stats.record_permission_operation(operation);
+        }
+    }
+
+    /// Records the allocations the machine has made since they were last
+    /// recorded, if statistics are enabled. Invoked once execution
+    /// completes or is suspended; calling it again records nothing twice.
+    pub(crate) fn record_allocations(&mut self) {
+        let allocations = self.machine.take_allocations();
+        if let Some(stats) = self.kernel.as_mut().and_then(|k| k.heap_stats()) {
+            stats.record_allocations(&allocations);
+        }
+    }
+
+    /// True if the kernel has asked for execution to be suspended.
+    pub(crate) fn suspend_requested(&mut self) -> bool {
+        let db = self.db;
+        let Some(pc) = self.machine.opt_pc() else {
+            return false;
+        };
+        self.kernel.as_mut().unwrap().suspend_requested(db, pc)
+    }
+
+    /// Captures the current execution state, along with the Rust thunk
+    /// (if any) that was about to be awaited.
+    ///
+    /// Allocations made so far are recorded first, so that the snapshot
+    /// has none to count again when it is resumed.
+    pub(crate) fn take_snapshot(&mut self, pending_thunk: Option<RustThunk>) -> Snapshot {
+        self.record_allocations();
+        Snapshot {
+            machine: self.machine.snapshot(),
+            pending_thunk,
+        }
+    }
+
+    /// If tracing is enabled, invokes `op` with the kernel's trace recorder
+    /// and the current time.
+    #[cfg(feature = "trace")]
+    pub(crate) fn trace(&mut self, op: impl FnOnce(&mut TraceRecorder, &dyn crate::Db, Duration)) {
+        let db = self.db;
+        let clock = &mut self.clock;
+        if let Some(recorder) = self.kernel.as_mut().and_then(|k| k.trace_recorder()) {
+            op(recorder, db, clock.now());
+        }
+    }
+
+    /// Pushes a new frame for `bir` onto the stack, recording the function
+    /// entry if tracing is enabled.
+    pub(crate) fn push_frame(&mut self, bir: bir::Bir, arguments: Vec<Value>) {
+        #[cfg(feature = "trace")]
+        self.trace(|recorder, db, now| {
+            recorder.begin(now, "function", bir.origin(db).name(db).as_str(db))
+        });
+        self.machine.push_frame(self.db, bir, arguments);
+    }
+
+    /// Pops the top frame from the stack, recording the function
+    /// exit if tracing is enabled.
+    fn pop_frame(&mut self) -> Frame {
+        let frame = self.machine.pop_frame();
+        #[cfg(feature = "trace")]
+        self.trace(|recorder, db, now| {
+            let function = frame.pc.bir.origin(db);
+            recorder.end(now, "function", function.name(db).as_str(db))
+        });
+        frame
+    }
+
     /// After a `ControlFlow::Await` is returned, the caller is responsible for
     /// invoking `awaken` with the resulting value. After awaken is called,
     /// the caller should start calling `step` again.
@@ -160,7 +382,8 @@ impl<'me> Stepper<'me> {
         match &self.machine[value.object] {
             ObjectData::Unit(()) => Ok(()),
             _ => {
-                self.intrinsic_print_async(await_pc, value).await?;
+                self.intrinsic_print_async(await_pc, await_pc, value)
+                    .await?;
                 Ok(())
             }
         }
@@ -171,14 +394,15 @@ impl<'me> Stepper<'me> {
         table: &bir::Tables,
         bir: bir::Bir,
         statement: bir::Statement,
+        statement_code: &bir::StatementCode,
     ) -> eyre::Result<()> {
         tracing::debug!(
             "statement = {:?}",
             statement.data(table).debug(&bir.in_ir_db(self.db))
         );
 
-        match statement.data(table) {
-            bir::StatementData::AssignExpr(place, expr) => {
+        match statement_code {
+            bir::StatementCode::AssignExpr((place, place_data), (expr, expr_data)) => {
                 // Subtle: The way this is setup, permissions for the target are not
                 // canceled until the write occurs. Consider something like this:
                 //
@@ -189,18 +413,23 @@ impl<'me> Stepper<'me> {
                 // ```
                 //
                 // This works, but the act of assigning to `p.x` cancels the lease from `q`.
-                let value = self.eval_expr(table, *expr)?;
-                self.assign_value_to_place(table, *place, value)?;
+                let value = self.eval_expr(table, *expr, expr_data)?;
+                self.assign_value_to_place(table, *place, place_data, value)?;
             }
-            bir::StatementData::AssignPlace(target_place, source_place) => {
-                self.assign_place_to_place(table, *target_place, *source_place)?;
+            bir::StatementCode::AssignPlace((target_place, target_place_data), source_place) => {
+                self.assign_place_to_place(table, *target_place, target_place_data, *source_place)?;
             }
-            bir::StatementData::Clear(lv) => {
+            bir::StatementCode::Other(
+                bir::StatementData::AssignExpr(..) | bir::StatementData::AssignPlace(..),
+            ) => {
+                unreachable!("assignments have `StatementCode`s of their own")
+            }
+            bir::StatementCode::Other(bir::StatementData::Clear(lv)) => {
                 let permission = self.machine.expired_permission(None);
                 let object = self.machine.unit_object();
                 *self.machine.local_mut(*lv) = Value { object, permission };
             }
-            bir::StatementData::BreakpointStart(filename, index) => {
+            bir::StatementCode::Other(bir::StatementData::BreakpointStart(filename, index)) => {
                 let kernel = self.kernel.take().unwrap();
                 let result = kernel.breakpoint_start(self.db, *filename, *index, &mut || {
                     HeapGraph::new(self.db, self.machine, None)
@@ -208,7 +437,12 @@ impl<'me> Stepper<'me> {
                 self.kernel = Some(kernel);
                 result?
             }
-            bir::StatementData::BreakpointEnd(filename, index, expr, in_flight_place) => {
+            bir::StatementCode::Other(bir::StatementData::BreakpointEnd(
+                filename,
+                index,
+                expr,
+                in_flight_place,
+            )) => {
                 let span = self.span_from_syntax_expr(*expr);
                 let kernel = self.kernel.take().unwrap();
                 let result = kernel.breakpoint_end(self.db, *filename, *index, span, &mut || {
@@ -235,9 +469,11 @@ impl<'me> Stepper<'me> {
         &mut self,
         table: &bir::Tables,
         target_place: bir::TargetPlace,
+        target_place_data: &bir::TargetPlaceData,
         source_place: bir::Place,
     ) -> eyre::Result<()> {
-        let target_traversal = self.evaluate_target_place(table, target_place)?;
+        let target_traversal =
+            self.evaluate_target_place(table, target_place, target_place_data)?;
 
         assert_ne!(
             target_traversal.accumulated_permissions.atomic,
@@ -256,8 +492,9 @@ impl<'me> Stepper<'me> {
         &mut self,
         table: &bir::Tables,
         target_place: bir::TargetPlace,
+        target_place_data: &bir::TargetPlaceData,
     ) -> eyre::Result<PlaceTraversal> {
-        match &table[target_place] {
+        match target_place_data {
             bir::TargetPlaceData::LocalVariable(lv) => {
                 Ok(self.traverse_to_local_variable(table, *lv))
             }
@@ -295,33 +532,46 @@ impl<'me> Stepper<'me> {
         let valid = permission
             .valid()
             .expect("value to be stored has expired permision");
+        let (joint, leased, permission_str) = (valid.joint, valid.leased, valid.as_str());
 
-        if let (true, Leased::Yes) = (specifier.must_be_owned(), valid.leased) {
+        if let (true, Leased::Yes) = (specifier.must_be_owned(), leased) {
             let source_place_span = self.span_from_bir(source_place);
-            return Err(error!(source_place_span, "more permissions needed")
-                .primary_label(format!(
-                    "this value is `{}`, which is leased, not owned",
-                    valid.as_str()
-                ))
-                .secondary_label(
-                    specifier_span,
-                    format!("`{specifier}` requires owned values"),
-                )
-                .eyre(self.db));
+            let diagnostic = error!(
+                InsufficientPermissions,
+                source_place_span, "more permissions needed"
+            )
+            .primary_label(format!(
+                "this value is `{}`, which is leased, not owned",
+                permission_str
+            ))
+            .secondary_label(
+                specifier_span,
+                format!("`{specifier}` requires owned values"),
+            );
+            let traversed = self.permissions_to(table, source_place);
+            let diagnostic =
+                self.label_grants(diagnostic, &traversed, |_, leased| leased == Leased::Yes);
+            return Err(diagnostic.eyre(self.db));
         }
 
-        if let (true, Joint::Yes) = (specifier.must_be_unique(), valid.joint) {
+        if let (true, Joint::Yes) = (specifier.must_be_unique(), joint) {
             let source_place_span = self.span_from_bir(source_place);
-            return Err(error!(source_place_span, "more permissions needed")
-                .primary_label(format!(
-                    "this value is `{}`, which is shared, not unique",
-                    valid.as_str()
-                ))
-                .secondary_label(
-                    specifier_span,
-                    format!("`{specifier}` requires unique access"),
-                )
-                .eyre(self.db));
+            let diagnostic = error!(
+                InsufficientPermissions,
+                source_place_span, "more permissions needed"
+            )
+            .primary_label(format!(
+                "this value is `{}`, which is shared, not unique",
+                permission_str
+            ))
+            .secondary_label(
+                specifier_span,
+                format!("`{specifier}` requires unique access"),
+            );
+            let traversed = self.permissions_to(table, source_place);
+            let diagnostic =
+                self.label_grants(diagnostic, &traversed, |joint, _| joint == Joint::Yes);
+            return Err(diagnostic.eyre(self.db));
         }
 
         Ok(value)
@@ -331,11 +581,13 @@ impl<'me> Stepper<'me> {
         &mut self,
         table: &bir::Tables,
         target_place: bir::TargetPlace,
+        target_place_data: &bir::TargetPlaceData,
         value: Value,
     ) -> eyre::Result<()> {
         assert!(self.machine[value.permission].valid().is_some());
 
-        let target_traversal = self.evaluate_target_place(table, target_place)?;
+        let target_traversal =
+            self.evaluate_target_place(table, target_place, target_place_data)?;
         self.assign_value_to_traversal(target_traversal, value)
     }
 
@@ -354,14 +606,13 @@ impl<'me> Stepper<'me> {
         table: &bir::Tables,
         pc: ProgramCounter,
         terminator: bir::Terminator,
+        terminator_data: &bir::TerminatorData,
     ) -> eyre::Result<ControlFlow> {
         tracing::debug!(
             "terminator = {:?}",
-            terminator.data(table).debug(&pc.bir.in_ir_db(self.db))
+            terminator_data.debug(&pc.bir.in_ir_db(self.db))
         );
 
-        let terminator_data: &bir::TerminatorData = &table[terminator];
-
         match terminator_data {
             // FIXME: implement atomics
             TerminatorData::StartAtomic(b)
@@ -387,9 +638,20 @@ impl<'me> Stepper<'me> {
                     labels,
                 },
                 next_block,
-            ) => match self.call(table, terminator, *function, arguments, labels)? {
+            ) => match self.call(
+                table,
+                terminator,
+                *function,
+                &table[*arguments],
+                &table[*labels],
+            )? {
                 call::CallResult::Returned(return_value) => {
-                    self.assign_value_to_place(table, *destination, return_value)?;
+                    self.assign_value_to_place(
+                        table,
+                        *destination,
+                        &table[*destination],
+                        return_value,
+                    )?;
                     self.machine.set_pc(pc.move_to_block(*next_block));
                     Ok(ControlFlow::Next)
                 }
@@ -418,7 +680,8 @@ impl<'me> Stepper<'me> {
                 self.gc(&[return_value]);
 
                 // Pop current frame from the stack.
-                self.machine.pop_frame();
+                self.pop_frame();
+                self.check_jit_expectation(pc, return_value)?;
 
                 // If that was the top frame, we are done.
                 // Otherwise, resume the frame we just uncovered.
@@ -431,11 +694,14 @@ impl<'me> Stepper<'me> {
             }
             TerminatorData::Error => {
                 let span = self.span_from_bir(terminator);
-                Err(error!(span, "compilation error encountered 😢").eyre(self.db))
+                Err(
+                    error!(CompilationError, span, "compilation error encountered 😢")
+                        .eyre(self.db),
+                )
             }
             TerminatorData::Panic => {
                 let span = self.span_from_bir(terminator);
-                Err(error!(span, "panic! omg! 😱").eyre(self.db))
+                Err(error!(Panic, span, "panic! omg! 😱").eyre(self.db))
             }
         }
     }
@@ -471,12 +737,14 @@ impl<'me> Stepper<'me> {
 
         // check that the value which was returned didn't get invalidated
         // by the return itself
-        if let Some(expired_at) = self.machine[value.permission].expired() {
-            return Err(self.report_traversing_expired_permission(top.pc.span(self.db), expired_at));
+        if self.machine[value.permission].expired().is_some() {
+            return Err(
+                self.report_traversing_expired_permission(top.pc.span(self.db), value.permission)
+            );
         }
 
         let new_pc = top.pc.move_to_block(*top_basic_block);
-        self.assign_value_to_place(top_table, *top_place, value)?;
+        self.assign_value_to_place(top_table, *top_place, &top_table[*top_place], value)?;
         self.machine.set_pc(new_pc);
         Ok(())
     }
@@ -500,8 +768,13 @@ impl<'me> Stepper<'me> {
         }
     }
 
-    fn eval_expr(&mut self, table: &bir::Tables, expr: bir::Expr) -> eyre::Result<Value> {
-        match expr.data(table) {
+    fn eval_expr(
+        &mut self,
+        table: &bir::Tables,
+        expr: bir::Expr,
+        expr_data: &bir::ExprData,
+    ) -> eyre::Result<Value> {
+        match expr_data {
             bir::ExprData::BooleanLiteral(v) => Ok(Value {
                 object: self.machine.new_object(ObjectData::Bool(*v)),
                 permission: self.machine.new_permission(ValidPermissionData::our()),
@@ -538,7 +811,7 @@ impl<'me> Stepper<'me> {
             bir::ExprData::Shlease(place) => self.shlease_place(table, *place),
             bir::ExprData::Give(place) => self.give_place(table, *place),
             bir::ExprData::Tuple(places) => {
-                let fields = places
+                let fields = table[*places]
                     .iter()
                     .map(|place| self.give_place(table, *place))
                     .collect::<Result<Vec<_>, _>>()?;
@@ -558,7 +831,7 @@ impl<'me> Stepper<'me> {
             }
             bir::ExprData::Error => {
                 let span = self.span_from_bir(expr);
-                return Err(error!(span, "compilation error").eyre(self.db));
+                return Err(error!(CompilationError, span, "compilation error").eyre(self.db));
             }
         }
     }
@@ -569,13 +842,21 @@ impl<'me> Stepper<'me> {
         object: &ObjectData,
         what: &str,
     ) -> eyre::Report {
-        error!(span, "expected {}, found {}", what, object.kind_str(db)).eyre(db)
+        error!(
+            TypeMismatch,
+            span,
+            "expected {}, found {}",
+            what,
+            object.kind_str(db)
+        )
+        .eyre(db)
     }
 
     fn no_such_field(db: &dyn crate::Db, span: FileSpan, class: Class, name: Word) -> eyre::Report {
         let class_name = class.name(db).as_str(db);
         let class_span = class.name(db).span(db);
         error!(
+            UnknownField,
             span,
             "the class `{}` has no field named `{}`",
             class_name,
@@ -598,12 +879,23 @@ impl<'me> Stepper<'me> {
         self.span_from_syntax_expr(syntax_expr)
     }
 
+    /// The span of the operator in `expr`, like the `+` in `a + b`, or
+    /// of all of `expr` if its origin has no operator.
+    fn operator_span_from_bir(&self, expr: bir::Expr) -> FileSpan {
+        let bir = self.machine.pc().bir;
+        let syntax_expr = bir.origins(self.db)[expr];
+        let syntax_tree = bir.origin(self.db).syntax_tree(self.db);
+        match syntax_tree.operator_spans(self.db).get(syntax_expr) {
+            Some(span) => syntax_tree.file_span(self.db, span),
+            None => self.span_from_syntax_expr(syntax_expr),
+        }
+    }
+
     fn span_from_syntax_expr(&self, syntax_expr: syntax::Expr) -> FileSpan {
         let bir = self.machine.pc().bir;
         let code = bir.origin(self.db);
-        let filename = code.filename(self.db);
         let syntax_tree = code.syntax_tree(self.db);
-        syntax_tree.spans(self.db)[syntax_expr].in_file(filename)
+        syntax_tree.file_span(self.db, syntax_tree.spans(self.db)[syntax_expr])
     }
 }
 
@@ -622,3 +914,18 @@ impl IntoSpecifierAndSpan for SpannedSpecifier {
         (self.specifier(db), self.span(db))
     }
 }
+
+/// The source of the statement or terminator at `pc`, with the middle left
+/// out if it is long.
+fn abbreviated_snippet(db: &dyn crate::Db, pc: ProgramCounter) -> String {
+    let snippet = pc.span(db).snippet(db);
+    if snippet.len() > 50 {
+        format!(
+            "{:?}...{:?}",
+            &snippet[..25],
+            &snippet[snippet.len() - 25..]
+        )
+    } else {
+        format!("{snippet:?}")
+    }
+}
@@ -76,12 +76,17 @@ impl Stepper<'_> {
                 // since we need to distinguish between an atomic location in a
                 // shared object vs a shared object in an (exclusive) atomic location.
                 let span = self.machine.pc().span(self.db);
-                return Err(error!(span, "atomic writes not implemented yet").eyre(self.db));
+                return Err(
+                    error!(Unimplemented, span, "atomic writes not implemented yet").eyre(self.db),
+                );
             }
 
             (Joint::Yes, Atomic::No) => {
                 let span = self.machine.pc().span(self.db);
-                return Err(error!(span, "cannot write to shared fields").eyre(self.db));
+                let diagnostic = error!(WriteToShared, span, "cannot write to shared fields");
+                let diagnostic =
+                    self.label_grants(diagnostic, &ap.traversed, |joint, _| joint == Joint::Yes);
+                return Err(diagnostic.eyre(self.db));
             }
 
             (Joint::No, Atomic::Yes) | (Joint::No, Atomic::No) => {
@@ -262,7 +267,12 @@ impl Stepper<'_> {
                 | ObjectData::Float(_)
                 | ObjectData::Function(_)
                 | ObjectData::Intrinsic(_)
+                | ObjectData::HostFunction(_)
+                | ObjectData::ExternFunction(_)
                 | ObjectData::SignedInt(_)
+                | ObjectData::BigInt(_)
+                | ObjectData::Weak(_)
+                | ObjectData::WeakGet(_)
                 | ObjectData::String(_)
                 | ObjectData::ThunkRust(_)
                 | ObjectData::Unit(_)
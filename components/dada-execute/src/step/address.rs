@@ -56,6 +56,7 @@ impl Stepper<'_> {
             Address::Local(lv) => self.machine[lv] = value,
             Address::Constant(_) => {
                 return Err(error!(
+                    AssignToConstant,
                     self.machine.pc().span(self.db),
                     "cannot store into a constant"
                 )
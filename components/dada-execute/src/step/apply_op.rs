@@ -2,6 +2,7 @@ use dada_ir::{
     code::{bir, validated::op::Op},
     error,
 };
+use num_bigint::BigInt;
 
 use crate::{
     error::DiagnosticBuilderExt,
@@ -22,6 +23,7 @@ impl Stepper<'_> {
         let op_error = || {
             let span = self.span_from_bir(expr);
             Err(error!(
+                InvalidOperands,
                 span,
                 "cannot apply operator {} to {} and {}",
                 op,
@@ -30,14 +32,6 @@ impl Stepper<'_> {
             )
             .eyre(self.db))
         };
-        let div_zero_error = || {
-            let span = self.span_from_bir(expr);
-            Err(error!(span, "divide by zero").eyre(self.db))
-        };
-        let overflow_error = || {
-            let span = self.span_from_bir(expr);
-            Err(error!(span, "overflow").eyre(self.db))
-        };
         match (&self.machine[lhs], &self.machine[rhs]) {
             (&ObjectData::Bool(lhs), &ObjectData::Bool(rhs)) => match op {
                 Op::EqualEqual => Ok(self.machine.our_value(lhs == rhs)),
@@ -51,22 +45,10 @@ impl Stepper<'_> {
                 Op::EqualEqual => Ok(self.machine.our_value(lhs == rhs)),
                 Op::GreaterEqual => Ok(self.machine.our_value(lhs >= rhs)),
                 Op::LessEqual => Ok(self.machine.our_value(lhs <= rhs)),
-                Op::Plus => match lhs.checked_add(rhs) {
-                    Some(value) => Ok(self.machine.our_value(value)),
-                    None => overflow_error(),
-                },
-                Op::Minus => match lhs.checked_sub(rhs) {
-                    Some(value) => Ok(self.machine.our_value(value)),
-                    None => overflow_error(),
-                },
-                Op::Times => match lhs.checked_mul(rhs) {
-                    Some(value) => Ok(self.machine.our_value(value)),
-                    None => overflow_error(),
-                },
-                Op::DividedBy => match lhs.checked_div(rhs) {
-                    Some(value) => Ok(self.machine.our_value(value)),
-                    None => div_zero_error(),
-                },
+                Op::Plus | Op::Minus | Op::Times | Op::DividedBy => {
+                    let value = self.checked_unsigned_int(expr, op, lhs, rhs)?;
+                    Ok(self.machine.our_value(value))
+                }
                 Op::LessThan => Ok(self.machine.our_value(lhs < rhs)),
                 Op::GreaterThan => Ok(self.machine.our_value(lhs > rhs)),
             },
@@ -74,22 +56,10 @@ impl Stepper<'_> {
                 Op::EqualEqual => Ok(self.machine.our_value(lhs == rhs)),
                 Op::GreaterEqual => Ok(self.machine.our_value(lhs >= rhs)),
                 Op::LessEqual => Ok(self.machine.our_value(lhs <= rhs)),
-                Op::Plus => match lhs.checked_add(rhs) {
-                    Some(value) => Ok(self.machine.our_value(ObjectData::Int(value))),
-                    None => overflow_error(),
-                },
-                Op::Minus => match lhs.checked_sub(rhs) {
-                    Some(value) => Ok(self.machine.our_value(ObjectData::Int(value))),
-                    None => overflow_error(),
-                },
-                Op::Times => match lhs.checked_mul(rhs) {
-                    Some(value) => Ok(self.machine.our_value(ObjectData::Int(value))),
-                    None => overflow_error(),
-                },
-                Op::DividedBy => match lhs.checked_div(rhs) {
-                    Some(value) => Ok(self.machine.our_value(ObjectData::Int(value))),
-                    None => div_zero_error(),
-                },
+                Op::Plus | Op::Minus | Op::Times | Op::DividedBy => {
+                    let value = self.checked_unsigned_int(expr, op, lhs, rhs)?;
+                    Ok(self.machine.our_value(ObjectData::Int(value)))
+                }
                 Op::LessThan => Ok(self.machine.our_value(lhs < rhs)),
                 Op::GreaterThan => Ok(self.machine.our_value(lhs > rhs)),
             },
@@ -98,11 +68,11 @@ impl Stepper<'_> {
             }
             (&ObjectData::Int(lhs), &ObjectData::SignedInt(rhs)) => match i64::try_from(lhs) {
                 Ok(lhs) => self.apply_signed_int(expr, op, lhs, rhs),
-                Err(_) => overflow_error(),
+                Err(_) => Err(self.conversion_overflow_error(expr, lhs)),
             },
             (&ObjectData::SignedInt(lhs), &ObjectData::Int(rhs)) => match i64::try_from(rhs) {
                 Ok(rhs) => self.apply_signed_int(expr, op, lhs, rhs),
-                Err(_) => overflow_error(),
+                Err(_) => Err(self.conversion_overflow_error(expr, rhs)),
             },
             (&ObjectData::Float(lhs), &ObjectData::Float(rhs)) => match op {
                 Op::EqualEqual => Ok(self.machine.our_value(lhs == rhs)),
@@ -136,10 +106,44 @@ impl Stepper<'_> {
                 Op::LessEqual => Ok(self.machine.our_value(lhs <= rhs)),
                 _ => op_error(),
             },
+            (ObjectData::BigInt(_), _) | (_, ObjectData::BigInt(_)) => {
+                // Big integers can be combined with any other kind of
+                // integer; the result is always a big integer.
+                match (
+                    big_int_value(&self.machine[lhs]),
+                    big_int_value(&self.machine[rhs]),
+                ) {
+                    (Some(lhs), Some(rhs)) => self.apply_big_int(expr, op, lhs, rhs),
+                    _ => op_error(),
+                }
+            }
             _ => op_error(),
         }
     }
 
+    fn apply_big_int(
+        &mut self,
+        expr: bir::Expr,
+        op: Op,
+        lhs: BigInt,
+        rhs: BigInt,
+    ) -> eyre::Result<Value> {
+        match op {
+            Op::EqualEqual => Ok(self.machine.our_value(lhs == rhs)),
+            Op::GreaterEqual => Ok(self.machine.our_value(lhs >= rhs)),
+            Op::LessEqual => Ok(self.machine.our_value(lhs <= rhs)),
+            Op::Plus => Ok(self.machine.our_value(lhs + rhs)),
+            Op::Minus => Ok(self.machine.our_value(lhs - rhs)),
+            Op::Times => Ok(self.machine.our_value(lhs * rhs)),
+            Op::DividedBy if rhs == BigInt::from(0) => {
+                Err(self.arithmetic_error(expr, ArithmeticError::DivideByZero, op, lhs, rhs))
+            }
+            Op::DividedBy => Ok(self.machine.our_value(lhs / rhs)),
+            Op::LessThan => Ok(self.machine.our_value(lhs < rhs)),
+            Op::GreaterThan => Ok(self.machine.our_value(lhs > rhs)),
+        }
+    }
+
     fn apply_signed_int(
         &mut self,
         expr: bir::Expr,
@@ -147,43 +151,129 @@ impl Stepper<'_> {
         lhs: i64,
         rhs: i64,
     ) -> eyre::Result<Value> {
-        let div_zero_error = || {
-            let span = self.span_from_bir(expr);
-            Err(error!(span, "divide by zero").eyre(self.db))
-        };
-        let overflow_error = || {
-            let span = self.span_from_bir(expr);
-            Err(error!(span, "overflow").eyre(self.db))
-        };
         match op {
             Op::EqualEqual => Ok(self.machine.our_value(lhs == rhs)),
             Op::GreaterEqual => Ok(self.machine.our_value(lhs >= rhs)),
             Op::LessEqual => Ok(self.machine.our_value(lhs <= rhs)),
-            Op::Plus => match lhs.checked_add(rhs) {
-                Some(value) => Ok(self.machine.our_value(value)),
-                None => overflow_error(),
-            },
-            Op::Minus => match lhs.checked_sub(rhs) {
-                Some(value) => Ok(self.machine.our_value(value)),
-                None => overflow_error(),
-            },
-            Op::Times => match lhs.checked_mul(rhs) {
-                Some(value) => Ok(self.machine.our_value(value)),
-                None => overflow_error(),
-            },
-            Op::DividedBy => match lhs.checked_div(rhs) {
-                Some(value) => Ok(self.machine.our_value(value)),
-                None => {
-                    if rhs != -1 {
-                        div_zero_error()
-                    } else {
-                        let span = self.span_from_bir(expr);
-                        Err(error!(span, "signed division overflow").eyre(self.db))
-                    }
-                }
-            },
+            Op::Plus | Op::Minus | Op::Times | Op::DividedBy => {
+                let value = self.checked_signed_int(expr, op, lhs, rhs)?;
+                Ok(self.machine.our_value(value))
+            }
             Op::LessThan => Ok(self.machine.our_value(lhs < rhs)),
             Op::GreaterThan => Ok(self.machine.our_value(lhs > rhs)),
         }
     }
+
+    /// Applies an arithmetic operator to two unsigned integers,
+    /// reporting an error if the result cannot be represented.
+    fn checked_unsigned_int(
+        &self,
+        expr: bir::Expr,
+        op: Op,
+        lhs: u64,
+        rhs: u64,
+    ) -> eyre::Result<u64> {
+        let result = match op {
+            Op::Plus => lhs.checked_add(rhs),
+            Op::Minus => lhs.checked_sub(rhs),
+            Op::Times => lhs.checked_mul(rhs),
+            Op::DividedBy => lhs.checked_div(rhs),
+            _ => unreachable!("{op} is not an arithmetic operator"),
+        };
+        result.ok_or_else(|| {
+            let error = match op {
+                Op::DividedBy => ArithmeticError::DivideByZero,
+                Op::Minus => ArithmeticError::Underflow,
+                _ => ArithmeticError::Overflow,
+            };
+            self.arithmetic_error(expr, error, op, lhs, rhs)
+        })
+    }
+
+    /// Applies an arithmetic operator to two signed integers,
+    /// reporting an error if the result cannot be represented.
+    fn checked_signed_int(&self, expr: bir::Expr, op: Op, lhs: i64, rhs: i64) -> eyre::Result<i64> {
+        let result = match op {
+            Op::Plus => lhs.checked_add(rhs),
+            Op::Minus => lhs.checked_sub(rhs),
+            Op::Times => lhs.checked_mul(rhs),
+            Op::DividedBy => lhs.checked_div(rhs),
+            _ => unreachable!("{op} is not an arithmetic operator"),
+        };
+        result.ok_or_else(|| {
+            // Results that would have been negative fell off the bottom of
+            // the range. (Besides division by zero, the only division that
+            // fails is `i64::MIN / -1`, which overflows.)
+            let error = match op {
+                Op::DividedBy if rhs == 0 => ArithmeticError::DivideByZero,
+                Op::Plus if rhs < 0 => ArithmeticError::Underflow,
+                Op::Minus if rhs > 0 => ArithmeticError::Underflow,
+                Op::Times if (lhs < 0) != (rhs < 0) => ArithmeticError::Underflow,
+                _ => ArithmeticError::Overflow,
+            };
+            self.arithmetic_error(expr, error, op, lhs, rhs)
+        })
+    }
+
+    fn arithmetic_error(
+        &self,
+        expr: bir::Expr,
+        error: ArithmeticError,
+        op: Op,
+        lhs: impl std::fmt::Display,
+        rhs: impl std::fmt::Display,
+    ) -> eyre::Report {
+        let span = self.operator_span_from_bir(expr);
+        let label = match error {
+            ArithmeticError::Overflow => {
+                format!("the result of `{lhs} {op} {rhs}` is too large to represent")
+            }
+            ArithmeticError::Underflow => {
+                format!("the result of `{lhs} {op} {rhs}` is too small to represent")
+            }
+            ArithmeticError::DivideByZero => format!("cannot divide `{lhs}` by zero"),
+        };
+        error!(ArithmeticError, span, "{}", error.as_str())
+            .primary_label(label)
+            .eyre(self.db)
+    }
+
+    fn conversion_overflow_error(&self, expr: bir::Expr, value: u64) -> eyre::Report {
+        let span = self.span_from_bir(expr);
+        error!(
+            ArithmeticError,
+            span,
+            "{}",
+            ArithmeticError::Overflow.as_str()
+        )
+        .primary_label(format!("`{value}` is too large to be a signed integer"))
+        .eyre(self.db)
+    }
+}
+
+/// Returns the value of an integer (of any kind) as a big integer.
+fn big_int_value(data: &ObjectData) -> Option<BigInt> {
+    match data {
+        ObjectData::Int(i) | ObjectData::UnsignedInt(i) => Some(BigInt::from(*i)),
+        ObjectData::SignedInt(i) => Some(BigInt::from(*i)),
+        ObjectData::BigInt(i) => Some(i.clone()),
+        _ => None,
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+enum ArithmeticError {
+    Overflow,
+    Underflow,
+    DivideByZero,
+}
+
+impl ArithmeticError {
+    fn as_str(self) -> &'static str {
+        match self {
+            ArithmeticError::Overflow => "overflow",
+            ArithmeticError::Underflow => "underflow",
+            ArithmeticError::DivideByZero => "divide by zero",
+        }
+    }
 }
@@ -21,6 +21,7 @@ impl Stepper<'_> {
         let op_error = || {
             let span = self.span_from_bir(expr);
             Err(error!(
+                InvalidOperands,
                 span,
                 "cannot apply operator {} to {}",
                 op,
@@ -29,15 +30,32 @@ impl Stepper<'_> {
             .eyre(self.db))
         };
         match (op, &self.machine[rhs]) {
-            (Op::Minus, &ObjectData::SignedInt(rhs)) => Ok(self.machine.our_value(-rhs)),
+            (Op::Minus, &ObjectData::SignedInt(rhs)) => match rhs.checked_neg() {
+                Some(value) => Ok(self.machine.our_value(value)),
+                None => Err(self.negation_overflow_error(expr, rhs)),
+            },
             (Op::Minus, &ObjectData::Int(rhs)) => match i64::try_from(rhs) {
                 Ok(rhs) => Ok(self.machine.our_value(-rhs)),
-                Err(_) => {
-                    let span = self.span_from_bir(expr);
-                    Err(error!(span, "overflow").eyre(self.db))
-                }
+                Err(_) => Err(self.negation_overflow_error(expr, rhs)),
             },
+            (Op::Minus, ObjectData::BigInt(rhs)) => {
+                let value = -rhs;
+                Ok(self.machine.our_value(value))
+            }
             _ => op_error(),
         }
     }
+
+    fn negation_overflow_error(
+        &self,
+        expr: bir::Expr,
+        value: impl std::fmt::Display,
+    ) -> eyre::Report {
+        let span = self.span_from_bir(expr);
+        error!(ArithmeticError, span, "overflow")
+            .primary_label(format!(
+                "`-{value}` cannot be represented as a signed integer"
+            ))
+            .eyre(self.db)
+    }
 }
@@ -41,7 +41,7 @@ impl Stepper<'_> {
                 arguments,
             }) => {
                 let bir = function.brew(self.db);
-                self.machine.push_frame(self.db, bir, arguments);
+                self.push_frame(bir, arguments);
                 Ok(AwaitResult::PushedNewFrame)
             }
 
@@ -68,8 +68,11 @@ impl Stepper<'_> {
             (Joint::No, Leased::No) => return Ok(()),
         };
         let span = self.span_from_bir(thunk_place);
-        Err(error!(span, "awaiting something requires full ownership")
-            .primary_label(primary_label)
-            .eyre(self.db))
+        Err(error!(
+            AwaitWithoutOwnership,
+            span, "awaiting something requires full ownership"
+        )
+        .primary_label(primary_label)
+        .eyre(self.db))
     }
 }
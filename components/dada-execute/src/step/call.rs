@@ -11,7 +11,6 @@ use dada_parse::prelude::*;
 use crate::{
     error::DiagnosticBuilderExt,
     machine::{op::MachineOpExtMut, Instance, ObjectData, ThunkFn, Value},
-    step::intrinsic::IntrinsicDefinition,
 };
 
 use super::{IntoSpecifierAndSpan, Stepper};
@@ -65,17 +64,42 @@ impl Stepper<'_> {
                     });
                     Ok(CallResult::Returned(thunk))
                 } else {
-                    // This is not an async function, so push it onto the stack
-                    // and begin execution immediately.
+                    // This is not an async function, so (unless the JIT can
+                    // execute it) push it onto the stack and begin execution
+                    // immediately.
+                    if let Some(value) = self.try_jit(function, &arguments) {
+                        return Ok(CallResult::Returned(value));
+                    }
                     let bir = function.brew(self.db);
-                    self.machine.push_frame(self.db, bir, arguments);
+                    self.push_frame(bir, arguments);
                     Ok(CallResult::PushedNewFrame)
                 }
             }
             &ObjectData::Intrinsic(intrinsic) => {
-                let definition = IntrinsicDefinition::for_intrinsic(self.db, intrinsic);
+                let Some(definition) = self.intrinsics.get(&intrinsic).cloned() else {
+                    // An intrinsic that the embedder added is implemented
+                    // by the host function of the same name.
+                    let name = intrinsic.name(self.db);
+                    let value =
+                        self.call_host_function(table, callee, name, argument_places, labels)?;
+                    return Ok(CallResult::Returned(value));
+                };
                 self.match_labels(callee, labels, &definition.argument_names)?;
                 let callee_span = self.span_from_bir(callee);
+                if let Some(capability) = definition.capability {
+                    if !self.config.capabilities.contains(capability) {
+                        return Err(error!(
+                            MissingCapability,
+                            callee_span, "missing capability `{}`", capability
+                        )
+                        .primary_label(format!(
+                            "`{}` requires the `{}` capability, which was not granted",
+                            intrinsic.as_str(self.db),
+                            capability
+                        ))
+                        .eyre(self.db));
+                    }
+                }
                 let arguments = self.prepare_arguments(
                     table,
                     definition
@@ -84,12 +108,33 @@ impl Stepper<'_> {
                         .map(|specifier| (*specifier, callee_span)),
                     argument_places,
                 )?;
-                let value = (definition.function)(self, arguments)?;
+                let value = (definition.function)(self, intrinsic, arguments)?;
+                Ok(CallResult::Returned(value))
+            }
+            &ObjectData::ExternFunction(function) => {
+                let parameter_names: Vec<Word> = function
+                    .signature(self.db)
+                    .parameters
+                    .iter()
+                    .map(|parameter| parameter.name)
+                    .collect();
+                self.match_labels(callee, labels, &parameter_names)?;
+                let value = self.call_extern_function(table, callee, function, argument_places)?;
                 Ok(CallResult::Returned(value))
             }
+            &ObjectData::HostFunction(name) => {
+                let value =
+                    self.call_host_function(table, callee, name, argument_places, labels)?;
+                Ok(CallResult::Returned(value))
+            }
+            &ObjectData::WeakGet(target) => {
+                self.match_labels(callee, labels, &[] as &[Word])?;
+                Ok(CallResult::Returned(self.upgrade(target)))
+            }
             data => {
                 let span = self.span_from_bir(callee);
                 Err(error!(
+                    TypeMismatch,
                     span,
                     "expected something callable, found {}",
                     data.kind_str(self.db)
@@ -116,7 +161,7 @@ impl Stepper<'_> {
     }
 
     /// Prepare the arguments according to the given specifiers.
-    fn prepare_arguments(
+    pub(super) fn prepare_arguments(
         &mut self,
         table: &bir::Tables,
         specifiers: impl Iterator<Item = impl IntoSpecifierAndSpan>,
@@ -144,6 +189,7 @@ impl Stepper<'_> {
             if let Some(actual_word) = actual_label.word(db) {
                 if expected_name != actual_word {
                     return Err(error!(
+                        ArgumentNameMismatch,
                         actual_label.span(db),
                         "expected to find an argument named `{}`, but found the name `{}`",
                         expected_name.as_str(db),
@@ -156,6 +202,7 @@ impl Stepper<'_> {
 
         if actual_labels.len() != expected_names.len() {
             return Err(error!(
+                ArgumentCountMismatch,
                 self.span_from_bir(call_terminator),
                 "expected to find {} arguments, but found {}",
                 expected_names.len(),
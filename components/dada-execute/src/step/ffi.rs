@@ -0,0 +1,305 @@
+//! Calls to `extern` functions, which live in shared libraries that are
+//! loaded the first time one of their functions is called. Calling an
+//! extern function requires the `ffi` capability, which is not granted by
+//! default: nothing stops the callee from corrupting the interpreter.
+
+use std::ffi::{c_void, CStr, CString};
+
+use dada_collections::Map;
+use dada_ir::{
+    code::bir,
+    error,
+    extern_function::{ExternFunction, FfiTy},
+    span::FileSpan,
+    storage::Specifier,
+};
+use libffi::middle::{Arg, Cif, CodePtr, Type};
+use libloading::Library;
+use parking_lot::Mutex;
+
+use crate::{capability::Capability, error::DiagnosticBuilderExt, machine::Value, HostValue};
+
+use super::Stepper;
+
+/// Libraries that have been loaded, by the name they were loaded with.
+/// Libraries are never unloaded, since C code may hold on to pointers
+/// into them.
+static LIBRARIES: Mutex<Option<Map<String, &'static Library>>> = parking_lot::const_mutex(None);
+
+impl Stepper<'_> {
+    /// Calls `function` with the values in `argument_places`; the caller
+    /// has already checked that the labels and the number of arguments match.
+    pub(super) fn call_extern_function(
+        &mut self,
+        table: &bir::Tables,
+        callee: bir::Place,
+        function: ExternFunction,
+        argument_places: &[bir::Place],
+    ) -> eyre::Result<Value> {
+        let db = self.db;
+        let callee_span = self.span_from_bir(callee);
+        let name = function.name(db).as_str(db);
+
+        if !self.config.capabilities.contains(Capability::Ffi) {
+            return Err(error!(
+                MissingCapability,
+                callee_span,
+                "missing capability `{}`",
+                Capability::Ffi
+            )
+            .primary_label(format!(
+                "`{}` is an extern function, which requires the `{}` capability",
+                name,
+                Capability::Ffi
+            ))
+            .eyre(db));
+        }
+
+        let arguments = self.prepare_arguments(
+            table,
+            argument_places
+                .iter()
+                .map(|_| (Specifier::Any, callee_span)),
+            argument_places,
+        )?;
+
+        let signature = function.signature(db);
+        let mut marshalled = Vec::with_capacity(arguments.len());
+        for (parameter, argument) in signature.parameters.iter().zip(arguments) {
+            let argument = HostValue::from_value(db, &*self.machine, argument);
+            let value = marshal(parameter.ty, argument).map_err(|message| {
+                error!(ForeignFunction, callee_span, "{}", message)
+                    .primary_label(format!(
+                        "in the argument `{}` to `{}`",
+                        parameter.name.as_str(db),
+                        name
+                    ))
+                    .eyre(db)
+            })?;
+            marshalled.push(value);
+        }
+
+        let code_ptr = self.extern_symbol(function, callee_span)?;
+        let cif = Cif::new(
+            signature.parameters.iter().flat_map(|p| ffi_types(p.ty)),
+            signature.return_ty.map(ffi_type).unwrap_or_else(Type::void),
+        );
+        let mut args: Vec<Arg> = Vec::with_capacity(marshalled.len());
+        for value in &marshalled {
+            value.push_args(&mut args);
+        }
+
+        // SAFETY: The declaration of the extern function is trusted to match
+        // the symbol's actual signature; that trust is what the `ffi`
+        // capability grants.
+        let result = unsafe {
+            match signature.return_ty {
+                None => {
+                    cif.call::<()>(code_ptr, &args);
+                    HostValue::Unit
+                }
+                // Integers smaller than a register are returned widened,
+                // so read the full register and then truncate.
+                Some(FfiTy::I32) => {
+                    HostValue::SignedInt(cif.call::<i64>(code_ptr, &args) as i32 as i64)
+                }
+                Some(FfiTy::I64) => HostValue::SignedInt(cif.call::<i64>(code_ptr, &args)),
+                Some(FfiTy::U32) => {
+                    HostValue::UnsignedInt(cif.call::<u64>(code_ptr, &args) as u32 as u64)
+                }
+                Some(FfiTy::U64) => HostValue::UnsignedInt(cif.call::<u64>(code_ptr, &args)),
+                Some(FfiTy::F32) => HostValue::Float(cif.call::<f32>(code_ptr, &args).into()),
+                Some(FfiTy::F64) => HostValue::Float(cif.call::<f64>(code_ptr, &args)),
+                Some(FfiTy::Str) => {
+                    let ptr = cif.call::<*const std::os::raw::c_char>(code_ptr, &args);
+                    if ptr.is_null() {
+                        HostValue::Unit
+                    } else {
+                        HostValue::String(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+                    }
+                }
+                Some(FfiTy::Bytes) => unreachable!("extern functions cannot return `bytes`"),
+            }
+        };
+
+        // The buffers that the arguments point into must outlive the call.
+        drop(marshalled);
+
+        result.into_value(db, callee_span.filename, &mut *self.machine)
+    }
+
+    /// Loads the library for `function` (if it is not loaded already) and
+    /// looks up the function's symbol in it.
+    fn extern_symbol(&self, function: ExternFunction, span: FileSpan) -> eyre::Result<CodePtr> {
+        let db = self.db;
+        let library_name = function.library(db).as_str(db);
+        let symbol_name = function.name(db).as_str(db);
+
+        let mut libraries = LIBRARIES.lock();
+        let libraries = libraries.get_or_insert_with(Map::default);
+        let library = match libraries.get(library_name) {
+            Some(library) => *library,
+            None => {
+                // SAFETY: Loading a library runs its initializers, which can
+                // do anything; again, that is what the `ffi` capability grants.
+                let library = unsafe { Library::new(library_name) }.map_err(|e| {
+                    error!(
+                        ForeignFunction,
+                        span, "failed to load `{}`: {}", library_name, e
+                    )
+                    .primary_label(format!(
+                        "`{}` is declared in `{}`",
+                        symbol_name, library_name
+                    ))
+                    .eyre(db)
+                })?;
+                let library: &'static Library = Box::leak(Box::new(library));
+                libraries.insert(library_name.to_string(), library);
+                library
+            }
+        };
+
+        // SAFETY: The symbol is only used as an address to call.
+        let symbol =
+            unsafe { library.get::<*mut c_void>(symbol_name.as_bytes()) }.map_err(|e| {
+                error!(
+                    ForeignFunction,
+                    span, "failed to find `{}` in `{}`: {}", symbol_name, library_name, e
+                )
+                .eyre(db)
+            })?;
+        Ok(CodePtr::from_ptr(*symbol))
+    }
+}
+
+/// An argument converted to its C representation.
+enum Marshalled {
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+
+    /// A pointer into a string that is kept here, so that it lives as long
+    /// as the argument.
+    Pointer {
+        pointer: *const c_void,
+        _buffer: Vec<u8>,
+    },
+
+    /// A pointer to a buffer that is kept here, followed by its length.
+    Buffer {
+        pointer: *const c_void,
+        len: usize,
+        _buffer: Vec<u8>,
+    },
+}
+
+impl Marshalled {
+    fn pointer_to(buffer: Vec<u8>) -> Self {
+        Marshalled::Pointer {
+            pointer: buffer.as_ptr() as *const c_void,
+            _buffer: buffer,
+        }
+    }
+
+    fn buffer(buffer: Vec<u8>) -> Self {
+        Marshalled::Buffer {
+            pointer: buffer.as_ptr() as *const c_void,
+            len: buffer.len(),
+            _buffer: buffer,
+        }
+    }
+
+    /// Pushes the C arguments for this value (two, for a buffer) onto `args`.
+    fn push_args(&self, args: &mut Vec<Arg>) {
+        match self {
+            Marshalled::I32(v) => args.push(Arg::new(v)),
+            Marshalled::I64(v) => args.push(Arg::new(v)),
+            Marshalled::U32(v) => args.push(Arg::new(v)),
+            Marshalled::U64(v) => args.push(Arg::new(v)),
+            Marshalled::F32(v) => args.push(Arg::new(v)),
+            Marshalled::F64(v) => args.push(Arg::new(v)),
+            Marshalled::Pointer { pointer, .. } => args.push(Arg::new(pointer)),
+            Marshalled::Buffer { pointer, len, .. } => {
+                args.push(Arg::new(pointer));
+                args.push(Arg::new(len));
+            }
+        }
+    }
+}
+
+/// The C types of the arguments that a parameter of type `ty` is passed as.
+fn ffi_types(ty: FfiTy) -> Vec<Type> {
+    match ty {
+        FfiTy::Bytes => vec![Type::pointer(), Type::usize()],
+        ty => vec![ffi_type(ty)],
+    }
+}
+
+/// The C type of a value of type `ty`; for `bytes`, that of its pointer.
+fn ffi_type(ty: FfiTy) -> Type {
+    match ty {
+        FfiTy::I32 => Type::i32(),
+        FfiTy::I64 => Type::i64(),
+        FfiTy::U32 => Type::u32(),
+        FfiTy::U64 => Type::u64(),
+        FfiTy::F32 => Type::f32(),
+        FfiTy::F64 => Type::f64(),
+        FfiTy::Str | FfiTy::Bytes => Type::pointer(),
+    }
+}
+
+/// Converts `value` to the C type `ty`, or returns a message explaining
+/// why it cannot be.
+fn marshal(ty: FfiTy, value: HostValue) -> Result<Marshalled, String> {
+    match ty {
+        FfiTy::I32 => integer(ty, &value).map(Marshalled::I32),
+        FfiTy::I64 => integer(ty, &value).map(Marshalled::I64),
+        FfiTy::U32 => integer(ty, &value).map(Marshalled::U32),
+        FfiTy::U64 => integer(ty, &value).map(Marshalled::U64),
+        FfiTy::F32 => match value {
+            HostValue::Float(f) => Ok(Marshalled::F32(f as f32)),
+            value => Err(format!("expected a float for `{}`, found `{}`", ty, value)),
+        },
+        FfiTy::F64 => match value {
+            HostValue::Float(f) => Ok(Marshalled::F64(f)),
+            value => Err(format!("expected a float for `{}`, found `{}`", ty, value)),
+        },
+        FfiTy::Str => match value {
+            HostValue::String(s) => match CString::new(s) {
+                Ok(s) => Ok(Marshalled::pointer_to(s.into_bytes_with_nul())),
+                Err(_) => Err("strings passed as `str` cannot contain NUL".to_string()),
+            },
+            value => Err(format!("expected a string for `{}`, found `{}`", ty, value)),
+        },
+        FfiTy::Bytes => match value {
+            HostValue::String(s) => Ok(Marshalled::buffer(s.into_bytes())),
+            HostValue::Tuple(values) => values
+                .iter()
+                .map(|value| integer(ty, value))
+                .collect::<Result<Vec<u8>, String>>()
+                .map(Marshalled::buffer),
+            value => Err(format!(
+                "expected a string or a tuple of bytes for `{}`, found `{}`",
+                ty, value
+            )),
+        },
+    }
+}
+
+/// Converts the integer `value` to `T`, the Rust equivalent of `ty`.
+fn integer<T: TryFrom<i128>>(ty: FfiTy, value: &HostValue) -> Result<T, String> {
+    let i: i128 = match *value {
+        HostValue::Int(i) | HostValue::UnsignedInt(i) => i.into(),
+        HostValue::SignedInt(i) => i.into(),
+        _ => {
+            return Err(format!(
+                "expected an integer for `{}`, found `{}`",
+                ty, value
+            ))
+        }
+    };
+    T::try_from(i).map_err(|_| format!("`{}` does not fit in `{}`", value, ty))
+}
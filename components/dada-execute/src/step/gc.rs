@@ -2,7 +2,14 @@
 //! and collects them. The intent is that "compiled dada" would not
 //! have a gc, but that it would be equivalent to the interpreter.
 //!
-//! The gc currently runs after every step, keeping things tidy.
+//! By default, the gc runs after every step, keeping things tidy.
+//! Because it traces from the roots, it also reclaims groups of objects
+//! that refer to one another but are no longer reachable. The
+//! [`ExecutionConfig::gc_threshold`] setting can be used to defer
+//! collection until the heap has grown; the gc still runs early if the
+//! heap exceeds one of the configured memory limits.
+//!
+//! [`ExecutionConfig::gc_threshold`]: crate::config::ExecutionConfig::gc_threshold
 
 use dada_collections::Set;
 use dada_ir::storage::Leased;
@@ -14,6 +21,20 @@ use crate::machine::{
 use super::Stepper;
 
 impl Stepper<'_> {
+    /// Runs the garbage collector if the heap has grown by at least the
+    /// configured threshold since the last collection, or if it has
+    /// exceeded one of the configured memory limits (in which case
+    /// collecting may bring it back under the limit).
+    pub(super) fn maybe_gc(&mut self, in_flight_values: &[Value]) {
+        let heap = &self.machine.view().heap;
+        if heap.objects.len() >= heap.objects_after_gc + self.config.gc_threshold
+            || self.object_limit_exceeded()
+            || self.string_limit_exceeded()
+        {
+            self.gc(in_flight_values);
+        }
+    }
+
     /// Garbage collector: removes any objects that do not have an owner.
     /// Removes any permissions that do not appear in a live spot.
     ///
@@ -26,6 +47,7 @@ impl Stepper<'_> {
         let mut marks = Marks::default();
         Marker::new(self.machine, &mut marks).mark(in_flight_values);
         self.sweep(&marks).unwrap();
+        self.machine.record_gc();
     }
 }
 
@@ -133,12 +155,19 @@ impl<'me> Marker<'me> {
 
             ObjectData::Reservation(r) => self.mark_reservation(*r),
 
+            // A weak reference (or its `get` method) keeps its permission
+            // live (so that it can observe being revoked) but never its object.
+            ObjectData::Weak(v) | ObjectData::WeakGet(v) => self.mark_permission(v.permission),
+
             ObjectData::Class(_)
             | ObjectData::Function(_)
             | ObjectData::Intrinsic(_)
+            | ObjectData::HostFunction(_)
+            | ObjectData::ExternFunction(_)
             | ObjectData::Bool(_)
             | ObjectData::UnsignedInt(_)
             | ObjectData::SignedInt(_)
+            | ObjectData::BigInt(_)
             | ObjectData::Int(_)
             | ObjectData::Float(_)
             | ObjectData::String(_)
@@ -0,0 +1,135 @@
+use dada_ir::{code::bir, error, storage::Specifier, word::SpannedOptionalWord, word::Word};
+
+use crate::{
+    error::DiagnosticBuilderExt,
+    host_function::HostFunction,
+    machine::{op::MachineOpExtMut, ProgramCounter, Value},
+    thunk::RustThunk,
+    HostValue,
+};
+
+use super::Stepper;
+
+impl Stepper<'_> {
+    /// Calls the host function `name`. Synchronous host functions run
+    /// immediately; asynchronous ones yield a thunk, which runs the host
+    /// function when awaited (see [`Self::async_host_function`]).
+    pub(super) fn call_host_function(
+        &mut self,
+        table: &bir::Tables,
+        callee: bir::Place,
+        name: Word,
+        argument_places: &[bir::Place],
+        labels: &[SpannedOptionalWord],
+    ) -> eyre::Result<Value> {
+        let db = self.db;
+        let callee_span = self.span_from_bir(callee);
+        let function = self.host_function(name, || callee_span)?;
+
+        if let Some(label) = labels.iter().find(|label| label.word(db).is_some()) {
+            return Err(error!(
+                HostFunctionNamedArguments,
+                label.span(db),
+                "host functions do not take named arguments"
+            )
+            .primary_label(format!("`{}` is provided by the host", name.as_str(db)))
+            .eyre(db));
+        }
+
+        let arguments = self.prepare_arguments(
+            table,
+            argument_places
+                .iter()
+                .map(|_| (Specifier::Any, callee_span)),
+            argument_places,
+        )?;
+
+        match function {
+            HostFunction::Sync(function) => {
+                let arguments = self.host_arguments(arguments);
+                let result = function(arguments);
+                self.host_result(name, callee_span, result)
+            }
+            HostFunction::Async(_) => {
+                let pc = self.machine.pc();
+                Ok(self
+                    .machine
+                    .my_value(RustThunk::host_function(name, pc, arguments)))
+            }
+        }
+    }
+
+    /// Invoked when the thunk returned by an asynchronous host function is
+    /// awaited: runs the host function and yields its result.
+    pub(crate) async fn async_host_function(
+        &mut self,
+        name: Word,
+        call_pc: ProgramCounter,
+        arguments: Vec<Value>,
+    ) -> eyre::Result<Value> {
+        let span = call_pc.span(self.db);
+        let function = match self.host_function(name, || span)? {
+            HostFunction::Async(function) => function,
+            HostFunction::Sync(_) => {
+                // The function was replaced by a synchronous one after the
+                // thunk was created.
+                return Err(error!(
+                    MissingHostFunction,
+                    span,
+                    "no asynchronous host function named `{}` was provided",
+                    name.as_str(self.db)
+                )
+                .primary_label("the host function is no longer asynchronous")
+                .eyre(self.db));
+            }
+        };
+        let arguments = self.host_arguments(arguments);
+        let result = function(arguments).await;
+        self.host_result(name, span, result)
+    }
+
+    fn host_function(
+        &self,
+        name: Word,
+        span: impl FnOnce() -> dada_ir::span::FileSpan,
+    ) -> eyre::Result<HostFunction> {
+        match self.config.host_functions.get(name.as_str(self.db)) {
+            Some(function) => Ok(function.clone()),
+            None => Err(error!(
+                MissingHostFunction,
+                span(),
+                "no host function named `{}` was provided",
+                name.as_str(self.db)
+            )
+            .eyre(self.db)),
+        }
+    }
+
+    fn host_arguments(&self, arguments: Vec<Value>) -> Vec<HostValue> {
+        arguments
+            .into_iter()
+            .map(|value| HostValue::from_value(self.db, &*self.machine, value))
+            .collect()
+    }
+
+    /// Converts the result of the host function `name` into a value,
+    /// reporting errors at `span`.
+    fn host_result(
+        &mut self,
+        name: Word,
+        span: dada_ir::span::FileSpan,
+        result: eyre::Result<HostValue>,
+    ) -> eyre::Result<Value> {
+        let db = self.db;
+        let value: eyre::Result<Value> =
+            try { result?.into_value(db, span.filename, &mut *self.machine)? };
+        value.map_err(|report| {
+            error!(HostFunctionError, span, "{}", format!("{:#}", report))
+                .primary_label(format!(
+                    "error from the host function `{}`",
+                    name.as_str(db)
+                ))
+                .eyre(db)
+        })
+    }
+}
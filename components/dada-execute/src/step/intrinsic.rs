@@ -1,33 +1,117 @@
-use dada_ir::{error, intrinsic::Intrinsic, storage::Specifier, word::Word};
+use dada_collections::Map;
+use dada_ir::{
+    error,
+    intrinsic::{Intrinsic, STANDARD},
+    storage::Specifier,
+    word::Word,
+};
 use eyre::Context;
+#[cfg(feature = "extra-intrinsics")]
+use num_bigint::BigInt;
 
 use crate::{
+    capability::Capability,
     error::DiagnosticBuilderExt,
+    kernel::OutputEvent,
     machine::stringify::DefaultStringify,
     machine::{op::MachineOpExtMut, ProgramCounter, Value},
     thunk::RustThunk,
 };
+#[cfg(feature = "extra-intrinsics")]
+use crate::{error::Exit, machine::ObjectData};
 
 use super::Stepper;
 
-pub(crate) type IntrinsicFn = fn(&mut Stepper<'_>, Vec<Value>) -> eyre::Result<Value>;
+/// Runs an intrinsic, given the intrinsic being called and its arguments.
+pub(crate) type IntrinsicFn = fn(&mut Stepper<'_>, Intrinsic, Vec<Value>) -> eyre::Result<Value>;
+
+/// The function of an intrinsic other than `print`. Builds without the
+/// `extra-intrinsics` feature leave those out, and calling one is an error.
+macro_rules! extra_intrinsic {
+    ($name:expr, $function:expr) => {{
+        #[cfg(feature = "extra-intrinsics")]
+        let function: IntrinsicFn = $function;
+        #[cfg(not(feature = "extra-intrinsics"))]
+        let function: IntrinsicFn = |s, _, _| s.intrinsic_unavailable($name);
+        function
+    }};
+}
 
+#[derive(Clone)]
 pub(crate) struct IntrinsicDefinition {
     pub(crate) argument_names: Vec<Word>,
     pub(crate) argument_specifiers: Vec<Specifier>,
+
+    /// Capability the program must have been granted to call this intrinsic, if any.
+    pub(crate) capability: Option<Capability>,
+
     pub(crate) function: IntrinsicFn,
 }
 
 impl IntrinsicDefinition {
-    pub(crate) fn for_intrinsic(db: &dyn crate::Db, intrinsic: Intrinsic) -> IntrinsicDefinition {
-        match intrinsic {
-            Intrinsic::Print => IntrinsicDefinition {
+    /// The definitions of the standard intrinsics (see [`STANDARD`]). The
+    /// stepper looks them up here, so that calling one doesn't compare its
+    /// name with each of theirs.
+    pub(crate) fn standard(db: &dyn crate::Db) -> Map<Intrinsic, IntrinsicDefinition> {
+        STANDARD
+            .iter()
+            .map(|name| {
+                let definition = Self::named(db, name)
+                    .unwrap_or_else(|| panic!("no definition for the intrinsic `{}`", name));
+                (Intrinsic::from(db, name), definition)
+            })
+            .collect()
+    }
+
+    /// The definition of the standard intrinsic named `name`, if any.
+    fn named(db: &dyn crate::Db, name: &str) -> Option<IntrinsicDefinition> {
+        let definition = match name {
+            "print" => IntrinsicDefinition {
                 argument_names: vec![Word::from(db, "message")],
                 argument_specifiers: vec![Specifier::Any],
-                function: |s, v| s.intrinsic_print(v),
+                capability: Some(Capability::Print),
+                function: |s, print, v| s.intrinsic_print(print, v),
                 // FIXME: Stepper::intrinsic_write doesn't type check, why?
             },
-        }
+            "big" => IntrinsicDefinition {
+                argument_names: vec![Word::from(db, "value")],
+                argument_specifiers: vec![Specifier::Any],
+                capability: None,
+                function: extra_intrinsic!("big", |s, v| s.intrinsic_big(v)),
+            },
+            "weak" => IntrinsicDefinition {
+                argument_names: vec![Word::from(db, "value")],
+                argument_specifiers: vec![Specifier::Shleased],
+                capability: None,
+                function: extra_intrinsic!("weak", |s, v| s.intrinsic_weak(v)),
+            },
+            "upgrade" => IntrinsicDefinition {
+                argument_names: vec![Word::from(db, "weak")],
+                argument_specifiers: vec![Specifier::Shleased],
+                capability: None,
+                function: extra_intrinsic!("upgrade", |s, v| s.intrinsic_upgrade(v)),
+            },
+            "arg" => IntrinsicDefinition {
+                argument_names: vec![Word::from(db, "index")],
+                argument_specifiers: vec![Specifier::Any],
+                capability: None,
+                function: extra_intrinsic!("arg", |s, v| s.intrinsic_arg(v)),
+            },
+            "arg_count" => IntrinsicDefinition {
+                argument_names: vec![],
+                argument_specifiers: vec![],
+                capability: None,
+                function: extra_intrinsic!("arg_count", |s, v| s.intrinsic_arg_count(v)),
+            },
+            "exit" => IntrinsicDefinition {
+                argument_names: vec![Word::from(db, "code")],
+                argument_specifiers: vec![Specifier::Any],
+                capability: None,
+                function: extra_intrinsic!("exit", |s, v| s.intrinsic_exit(v)),
+            },
+            _ => return None,
+        };
+        Some(definition)
     }
 }
 
@@ -38,44 +122,190 @@ impl Stepper<'_> {
     pub(crate) async fn async_intrinsic(
         &mut self,
         intrinsic: Intrinsic,
+        call_pc: ProgramCounter,
         mut values: Vec<Value>,
     ) -> eyre::Result<Value> {
-        match intrinsic {
-            Intrinsic::Print => {
+        match intrinsic.as_str(self.db) {
+            "print" => {
                 let value = values.pop().unwrap();
                 let await_pc = self.machine.pc();
-                self.intrinsic_print_async(await_pc, value).await
+                self.intrinsic_print_async(await_pc, call_pc, value).await
+            }
+            name => panic!("`{}` does not yield a thunk", name),
+        }
+    }
+
+    /// Converts an integer (of any kind), or a string of decimal digits,
+    /// into a big integer.
+    #[cfg(feature = "extra-intrinsics")]
+    fn intrinsic_big(&mut self, values: Vec<Value>) -> eyre::Result<Value> {
+        let value = values[0];
+        let big = match &self.machine[value.object] {
+            ObjectData::Int(i) | ObjectData::UnsignedInt(i) => Some(BigInt::from(*i)),
+            ObjectData::SignedInt(i) => Some(BigInt::from(*i)),
+            ObjectData::BigInt(i) => Some(i.clone()),
+            ObjectData::String(s) => s.parse::<BigInt>().ok(),
+            _ => None,
+        };
+        match big {
+            Some(big) => Ok(self.machine.our_value(big)),
+            None => {
+                let span = self.machine.pc().span(self.db);
+                let found = match &self.machine[value.object] {
+                    ObjectData::String(s) => format!("the string `{}`", s),
+                    data => data.kind_str(self.db),
+                };
+                Err(error!(
+                    TypeMismatch,
+                    span, "expected an integer or a string of digits, found {}", found
+                )
+                .eyre(self.db))
             }
         }
     }
 
-    fn intrinsic_print(&mut self, values: Vec<Value>) -> eyre::Result<Value> {
+    /// Creates a weak reference to the (shleased) value. The weak reference
+    /// does not keep the object alive; see [`ObjectData::Weak`].
+    #[cfg(feature = "extra-intrinsics")]
+    fn intrinsic_weak(&mut self, values: Vec<Value>) -> eyre::Result<Value> {
+        Ok(self.machine.my_value(ObjectData::Weak(values[0])))
+    }
+
+    /// Yields the object that a weak reference refers to, or `()` if it
+    /// has been freed (or the permission it was created from has been
+    /// cancelled).
+    #[cfg(feature = "extra-intrinsics")]
+    fn intrinsic_upgrade(&mut self, values: Vec<Value>) -> eyre::Result<Value> {
+        let value = values[0];
+        let ObjectData::Weak(target) = &self.machine[value.object] else {
+            let span = self.machine.pc().span(self.db);
+            let found = self.machine[value.object].kind_str(self.db);
+            return Err(error!(
+                TypeMismatch,
+                span, "expected a weak reference, found {}", found
+            )
+            .eyre(self.db));
+        };
+        let target = *target;
+        Ok(self.upgrade(target))
+    }
+
+    /// Yields `target`, the value that a weak reference holds, if its object
+    /// is still alive, or else `()`. This is both `upgrade(w)` and `w.get()`.
+    pub(super) fn upgrade(&mut self, target: Value) -> Value {
+        if self.machine[target.permission].valid().is_some()
+            && self.machine.object_exists(target.object)
+        {
+            target
+        } else {
+            self.machine.our_value(())
+        }
+    }
+
+    /// Yields the program argument at `index` (counting from 0) as a
+    /// string, or `()` if there are not that many.
+    #[cfg(feature = "extra-intrinsics")]
+    fn intrinsic_arg(&mut self, values: Vec<Value>) -> eyre::Result<Value> {
+        let index = self.integer_argument(values[0])?;
+        let arg = usize::try_from(index)
+            .ok()
+            .and_then(|index| self.config.args.get(index).cloned());
+        match arg {
+            Some(arg) => Ok(self.machine.our_value(arg)),
+            None => Ok(self.machine.our_value(())),
+        }
+    }
+
+    /// Yields the number of program arguments.
+    #[cfg(feature = "extra-intrinsics")]
+    fn intrinsic_arg_count(&mut self, _values: Vec<Value>) -> eyre::Result<Value> {
+        let count = self.config.args.len() as u64;
+        Ok(self.machine.our_value(ObjectData::Int(count)))
+    }
+
+    /// Stops the program with an [`Exit`] error carrying `code`, which
+    /// `dada run` uses as its exit status.
+    #[cfg(feature = "extra-intrinsics")]
+    fn intrinsic_exit(&mut self, values: Vec<Value>) -> eyre::Result<Value> {
+        let code = self.integer_argument(values[0])?;
+        match i32::try_from(code) {
+            Ok(code) => Err(eyre::Report::new(Exit { code })),
+            Err(_) => {
+                let span = self.machine.pc().span(self.db);
+                Err(error!(
+                    ExitCodeOutOfRange,
+                    span, "exit code `{}` is out of range", code
+                )
+                .eyre(self.db))
+            }
+        }
+    }
+
+    /// The value of `value`, which should be an integer (of any kind that
+    /// fits in an `i64`).
+    #[cfg(feature = "extra-intrinsics")]
+    fn integer_argument(&self, value: Value) -> eyre::Result<i64> {
+        let integer = match &self.machine[value.object] {
+            ObjectData::Int(i) | ObjectData::UnsignedInt(i) => i64::try_from(*i).ok(),
+            ObjectData::SignedInt(i) => Some(*i),
+            _ => None,
+        };
+        integer.ok_or_else(|| {
+            let span = self.machine.pc().span(self.db);
+            let found = self.machine[value.object].kind_str(self.db);
+            error!(TypeMismatch, span, "expected an integer, found {}", found).eyre(self.db)
+        })
+    }
+
+    /// Reports a call to the intrinsic `name`, which this build leaves out.
+    #[cfg(not(feature = "extra-intrinsics"))]
+    fn intrinsic_unavailable(&mut self, name: &str) -> eyre::Result<Value> {
+        let span = self.machine.pc().span(self.db);
+        Err(error!(
+            UnsupportedByBackend,
+            span, "`{}` is not supported in this build", name
+        )
+        .eyre(self.db))
+    }
+
+    fn intrinsic_print(&mut self, print: Intrinsic, values: Vec<Value>) -> eyre::Result<Value> {
+        let pc = self.machine.pc();
         Ok(self
             .machine
-            .my_value(RustThunk::new("print", values, Intrinsic::Print)))
+            .my_value(RustThunk::new("print", pc, values, print)))
     }
 
-    #[tracing::instrument(level = "Debug", skip(self, await_pc))]
+    /// Prints `value` on its own line. `print_pc` is the point in the
+    /// program responsible for the output (e.g., the call to `print`).
+    #[tracing::instrument(level = "Debug", skip(self, await_pc, print_pc))]
     pub(super) async fn intrinsic_print_async(
         &mut self,
         await_pc: ProgramCounter,
+        print_pc: ProgramCounter,
         value: Value,
     ) -> eyre::Result<Value> {
         let message_str = DefaultStringify::stringify_value(&*self.machine, self.db, value);
 
-        async {
-            self.kernel
-                .as_mut()
-                .unwrap()
-                .print(await_pc, &message_str)
-                .await?;
-            self.kernel.as_mut().unwrap().print_newline(await_pc).await
-        }
-        .await
-        .with_context(|| {
-            let span_now = self.machine.pc().span(self.db);
-            error!(span_now, "error printing `{:?}`", message_str).eyre(self.db)
-        })?;
+        let event = OutputEvent {
+            text: message_str.clone(),
+            await_pc,
+            span: print_pc.span(self.db),
+        };
+        self.kernel
+            .as_mut()
+            .unwrap()
+            .output(event)
+            .await
+            .with_context(|| {
+                let span_now = self.machine.pc().span(self.db);
+                error!(
+                    OutputFailed,
+                    span_now,
+                    "error printing `{}`",
+                    format!("{:?}", message_str)
+                )
+                .eyre(self.db)
+            })?;
 
         Ok(self.machine.our_value(()))
     }
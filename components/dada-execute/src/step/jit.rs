@@ -0,0 +1,87 @@
+use dada_ir::{error, function::Function};
+
+use crate::{
+    error::DiagnosticBuilderExt,
+    jit::NativeValue,
+    machine::{op::MachineOpExtMut, ProgramCounter, Value},
+};
+
+use super::Stepper;
+
+/// When validating the JIT, records the result that the JIT produced
+/// for a call that is being interpreted, so that it can be compared
+/// with the interpreter's result when the callee returns.
+pub(super) struct JitExpectation {
+    /// Number of frames on the stack before the callee was pushed.
+    depth: usize,
+
+    function: Function,
+
+    value: NativeValue,
+}
+
+impl Stepper<'_> {
+    /// Offers a call of `function` to the kernel's JIT, if any. Returns
+    /// the result if the JIT executed the call; returns `None` if the
+    /// call should be interpreted instead.
+    pub(super) fn try_jit(&mut self, function: Function, arguments: &[Value]) -> Option<Value> {
+        let db = self.db;
+        let native_arguments = arguments
+            .iter()
+            .map(|argument| NativeValue::from_object_data(&self.machine[argument.object]))
+            .collect::<Option<Vec<_>>>()?;
+        let jit = self.kernel.as_mut()?.jit()?;
+        let value = jit.call(db, function, &native_arguments)?;
+
+        if self.config.validate_jit {
+            self.jit_expectations.push(JitExpectation {
+                depth: self.machine.frames().len(),
+                function,
+                value,
+            });
+            return None;
+        }
+
+        Some(self.machine.our_value(value))
+    }
+
+    /// Invoked when a frame has returned `value` (after the frame is popped).
+    /// If the JIT had also executed this call, reports an error if it
+    /// produced a different result.
+    pub(super) fn check_jit_expectation(
+        &mut self,
+        pc: ProgramCounter,
+        value: Value,
+    ) -> eyre::Result<()> {
+        let depth = self.machine.frames().len();
+        match self.jit_expectations.last() {
+            Some(expectation) if expectation.depth == depth => {}
+            _ => return Ok(()),
+        }
+        let expectation = self.jit_expectations.pop().unwrap();
+
+        let actual = NativeValue::from_object_data(&self.machine[value.object]);
+        if actual == Some(expectation.value) {
+            return Ok(());
+        }
+
+        let actual = match actual {
+            Some(actual) => actual.to_string(),
+            None => self.machine[value.object].kind_str(self.db),
+        };
+        let function_name = expectation.function.name(self.db);
+        Err(error!(
+            JitMismatch,
+            pc.span(self.db),
+            "JIT result does not match the interpreter"
+        )
+        .primary_label(format!(
+            "the interpreter returned `{}`, but the compiled code for `{}` returned `{}`",
+            actual,
+            function_name.as_str(self.db),
+            expectation.value,
+        ))
+        .secondary_label(function_name.span(self.db), "function compiled here")
+        .eyre(self.db))
+    }
+}
@@ -3,7 +3,10 @@ use dada_ir::{
     storage::{Joint, Leased},
 };
 
-use crate::machine::{ValidPermissionData, Value};
+use crate::{
+    machine::{ValidPermissionData, Value},
+    stats::PermissionOperation,
+};
 
 use super::{traversal::ObjectTraversal, Stepper};
 
@@ -35,6 +38,7 @@ impl Stepper<'_> {
         table: &bir::Tables,
         place: bir::Place,
     ) -> eyre::Result<Value> {
+        self.record_permission_operation(PermissionOperation::Lease);
         let object_traversal = self.traverse_to_object(table, place)?;
         let object_traversal = self.confirm_reservation_if_any(table, object_traversal)?;
         self.lease_traversal(object_traversal)
@@ -0,0 +1,31 @@
+//! Calls to `extern` functions in builds without the `ffi` feature (such
+//! as the playground), which can't load shared libraries: each call is an
+//! error.
+
+use dada_ir::{code::bir, error, extern_function::ExternFunction};
+
+use crate::{error::DiagnosticBuilderExt, machine::Value};
+
+use super::Stepper;
+
+impl Stepper<'_> {
+    pub(super) fn call_extern_function(
+        &mut self,
+        _table: &bir::Tables,
+        callee: bir::Place,
+        function: ExternFunction,
+        _argument_places: &[bir::Place],
+    ) -> eyre::Result<Value> {
+        let db = self.db;
+        let callee_span = self.span_from_bir(callee);
+        Err(error!(
+            UnsupportedByBackend,
+            callee_span, "extern functions are not supported in this build"
+        )
+        .primary_label(format!(
+            "`{}` is an extern function",
+            function.name(db).as_str(db)
+        ))
+        .eyre(db))
+    }
+}
@@ -2,7 +2,7 @@ use dada_ir::{error, storage::Joint};
 
 use crate::{
     error::DiagnosticBuilderExt,
-    machine::{Permission, PermissionData, ValidPermissionData},
+    machine::{Permission, PermissionData, PermissionEvent, ValidPermissionData},
 };
 
 use super::Stepper;
@@ -13,6 +13,10 @@ impl Stepper<'_> {
     pub(super) fn revoke(&mut self, permission: Permission) -> eyre::Result<()> {
         let pc = self.machine.opt_pc();
         let p = std::mem::replace(&mut self.machine[permission], PermissionData::Expired(pc));
+        if let Some(pc) = pc {
+            self.machine
+                .record_permission_event(permission, PermissionEvent::Revoked(pc));
+        }
 
         if let PermissionData::Valid(ValidPermissionData {
             tenants,
@@ -25,8 +29,8 @@ impl Stepper<'_> {
                 let error_pc = pc.unwrap_or(data.pc);
                 let error_span = error_pc.span(self.db);
                 return Err(error!(
-                    error_span,
-                    "you can't overwrite this value, it is reserved right now"
+                    OverwriteReserved,
+                    error_span, "you can't overwrite this value, it is reserved right now"
                 )
                 .primary_label("attempting to invalidate reservation here")
                 .secondary_label(data.pc.span(self.db), "reservation was placed here")
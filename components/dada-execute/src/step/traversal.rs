@@ -5,6 +5,7 @@ use dada_ir::{
         bir::{self, LocalVariable},
         syntax,
     },
+    diagnostic::DiagnosticBuilder,
     error,
     origin_table::HasOriginIn,
     span::FileSpan,
@@ -17,7 +18,8 @@ use crate::{
     error::DiagnosticBuilderExt,
     ext::DadaExecuteClassExt,
     machine::{
-        op::MachineOpExtMut, Object, ObjectData, Permission, PermissionData, ProgramCounter,
+        op::{MachineOp, MachineOpExtMut},
+        Object, ObjectData, Permission, PermissionData, PermissionEvent, ProgramCounter,
         ReservationData, Value,
     },
 };
@@ -134,12 +136,26 @@ impl Stepper<'_> {
             bir::PlaceData::Intrinsic(i) => {
                 Ok(self.traverse_to_constant(ObjectData::Intrinsic(*i)))
             }
+            bir::PlaceData::HostFunction(name) => {
+                Ok(self.traverse_to_constant(ObjectData::HostFunction(*name)))
+            }
+            bir::PlaceData::ExternFunction(f) => {
+                Ok(self.traverse_to_constant(ObjectData::ExternFunction(*f)))
+            }
             bir::PlaceData::Dot(owner_place, field_name) => {
                 let db = self.db;
                 let ObjectTraversal {
                     mut accumulated_permissions,
                     object: owner_object,
                 } = self.traverse_to_object(table, *owner_place)?;
+
+                // Weak references have a `get` method, but no fields.
+                if let ObjectData::Weak(target) = self.machine[owner_object] {
+                    if field_name.as_str(db) == "get" {
+                        return Ok(self.traverse_to_constant(ObjectData::WeakGet(target)));
+                    }
+                }
+
                 let (owner_class, field_index) =
                     self.object_field(place, owner_object, *field_name)?;
 
@@ -322,7 +338,7 @@ impl Stepper<'_> {
             PermissionData::Expired(expired_at) => {
                 tracing::debug!("encountered expired permission: {:?}", permission);
                 let place_span = self.span_from_bir(place);
-                Err(self.report_traversing_expired_permission(place_span, *expired_at))
+                Err(self.report_traversing_expired_permission(place_span, permission))
             }
             PermissionData::Valid(v) => {
                 match v.joint {
@@ -366,25 +382,103 @@ impl Stepper<'_> {
     pub(super) fn report_traversing_expired_permission(
         &self,
         place_span: FileSpan,
-        expired_at: Option<ProgramCounter>,
+        permission: Permission,
     ) -> eyre::Report {
+        let expired_at = self.machine[permission].expired().flatten();
         match expired_at {
-            None => error!(place_span, "accessing uninitialized memory").eyre(self.db),
+            None => error!(
+                UninitializedMemory,
+                place_span, "accessing uninitialized memory"
+            )
+            .eyre(self.db),
             Some(expired_at) => {
-                let expired_at_span = expired_at.span(self.db);
+                let mut diagnostic = error!(
+                    CancelledLease,
+                    place_span, "your lease to this object was cancelled"
+                )
+                .primary_label("cancelled lease used here");
+
+                // Explain how the permission came to be cancelled, using its history.
+                let mut revoked = false;
+                let mut owned = false;
+                for event in self.machine.permission_history(permission).events() {
+                    match event {
+                        PermissionEvent::Granted { pc, joint, leased } => {
+                            owned = (joint, leased) == (Joint::No, Leased::No);
+                            diagnostic = diagnostic
+                                .secondary_label(pc.span(self.db), grant_label(joint, leased));
+                        }
+                        PermissionEvent::Revoked(pc) => {
+                            revoked = true;
+                            diagnostic = diagnostic
+                                .secondary_label(pc.span(self.db), self.cancelled_label(pc, owned));
+                        }
+                    }
+                }
 
-                let secondary_label = if expired_at.is_return(self.db) {
-                    "lease was cancelled when this function returned"
-                } else {
-                    "lease was cancelled here"
-                };
+                if !revoked {
+                    diagnostic = diagnostic.secondary_label(
+                        expired_at.span(self.db),
+                        self.cancelled_label(expired_at, owned),
+                    );
+                }
+
+                diagnostic.eyre(self.db)
+            }
+        }
+    }
+
+    /// Where the permission was revoked: for an owned value, that is where
+    /// it was given away (or dropped, on return), and otherwise, where the
+    /// lease was cancelled.
+    fn cancelled_label(&self, expired_at: ProgramCounter, owned: bool) -> &'static str {
+        match (expired_at.is_return(self.db), owned) {
+            (true, true) => "object was dropped when this function returned",
+            (true, false) => "lease was cancelled when this function returned",
+            (false, true) => "object was given away here",
+            (false, false) => "lease was cancelled here",
+        }
+    }
+
+    /// The permissions traversed to reach the object in `place`, for
+    /// explaining an error; empty if the object can't be reached.
+    pub(super) fn permissions_to(
+        &mut self,
+        table: &bir::Tables,
+        place: bir::Place,
+    ) -> Vec<Permission> {
+        self.traverse_to_object(table, place)
+            .map(|traversal| traversal.accumulated_permissions.traversed)
+            .unwrap_or_default()
+    }
 
-                error!(place_span, "your lease to this object was cancelled")
-                    .primary_label("cancelled lease used here")
-                    .secondary_label(expired_at_span, secondary_label)
-                    .eyre(self.db)
+    /// Adds a label to `diagnostic` for where each of `permissions` that is
+    /// `leased` or `joint` (as `filter` picks) was granted, explaining how
+    /// the value came to have less than full permissions.
+    pub(super) fn label_grants(
+        &self,
+        mut diagnostic: DiagnosticBuilder,
+        permissions: &[Permission],
+        filter: impl Fn(Joint, Leased) -> bool,
+    ) -> DiagnosticBuilder {
+        for &permission in permissions {
+            if let Some((pc, joint, leased)) = self.machine.permission_history(permission).granted {
+                if filter(joint, leased) {
+                    diagnostic =
+                        diagnostic.secondary_label(pc.span(self.db), grant_label(joint, leased));
+                }
             }
         }
+        diagnostic
+    }
+}
+
+fn grant_label(joint: Joint, leased: Leased) -> &'static str {
+    match (joint, leased) {
+        (Joint::No, Leased::Yes) => "lease was granted here",
+        (Joint::Yes, Leased::Yes) => "shared lease was granted here",
+        (Joint::Yes, Leased::No) => "object was shared here",
+        (Joint::No, Leased::No) => "object was acquired here",
     }
 }
 
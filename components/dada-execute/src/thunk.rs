@@ -1,6 +1,9 @@
-use dada_ir::intrinsic::Intrinsic;
+use dada_ir::{intrinsic::Intrinsic, word::Word};
 
-use crate::{machine::Value, step::Stepper};
+use crate::{
+    machine::{ProgramCounter, Value},
+    step::Stepper,
+};
 
 /// A "RustThunk" is a thunk implemented in Rust.
 /// These are constructed from intrinsics.
@@ -8,8 +11,22 @@ use crate::{machine::Value, step::Stepper};
 #[derive(Clone, Debug, PartialEq)]
 pub struct RustThunk {
     pub(crate) description: &'static str,
+
+    /// The program counter at which the thunk was created
+    /// (e.g., the call to the intrinsic).
+    pub(crate) pc: ProgramCounter,
+
     pub(crate) arguments: Vec<Value>,
-    intrinsic: Intrinsic,
+    pub(crate) target: Target,
+}
+
+/// The Rust code that runs when a [`RustThunk`] is awaited.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Target {
+    Intrinsic(Intrinsic),
+
+    /// An asynchronous [host function](crate::host_function), by name.
+    HostFunction(Word),
 }
 
 impl RustThunk {
@@ -17,20 +34,48 @@ impl RustThunk {
     /// with the given values, and the resulting future awaited.
     pub(crate) fn new(
         description: &'static str,
+        pc: ProgramCounter,
         arguments: Vec<Value>,
         intrinsic: Intrinsic,
     ) -> Self {
         RustThunk {
             description,
+            pc,
+            arguments,
+            target: Target::Intrinsic(intrinsic),
+        }
+    }
+
+    /// Creates a thunk that, when awaited, calls the asynchronous host
+    /// function `name` with the given values.
+    pub(crate) fn host_function(name: Word, pc: ProgramCounter, arguments: Vec<Value>) -> Self {
+        RustThunk {
+            description: "host function",
+            pc,
             arguments,
-            intrinsic,
+            target: Target::HostFunction(name),
         }
     }
 
     pub(crate) async fn invoke(self, stepper: &mut Stepper<'_>) -> eyre::Result<()> {
-        let value = stepper
-            .async_intrinsic(self.intrinsic, self.arguments)
-            .await?;
+        #[cfg(feature = "trace")]
+        let description = self.description;
+        #[cfg(feature = "trace")]
+        stepper.trace(|recorder, _, now| recorder.begin(now, "await", description));
+        let value = match self.target {
+            Target::Intrinsic(intrinsic) => {
+                stepper
+                    .async_intrinsic(intrinsic, self.pc, self.arguments)
+                    .await?
+            }
+            Target::HostFunction(name) => {
+                stepper
+                    .async_host_function(name, self.pc, self.arguments)
+                    .await?
+            }
+        };
+        #[cfg(feature = "trace")]
+        stepper.trace(|recorder, _, now| recorder.end(now, "await", description));
         stepper.awaken(value)?;
         Ok(())
     }
@@ -0,0 +1,115 @@
+//! Records a timeline of interpreter events (function entry/exit and
+//! suspension while awaiting Rust thunks) that can be exported in the
+//! Chrome [trace event format], for viewing in `about:tracing`,
+//! Perfetto, and similar tools.
+//!
+//! Tracing is enabled by returning a recorder from [`Kernel::trace_recorder`].
+//!
+//! [trace event format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+//! [`Kernel::trace_recorder`]: crate::kernel::Kernel::trace_recorder
+
+use std::time::Duration;
+
+#[derive(Default)]
+pub struct TraceRecorder {
+    /// Events recorded so far, in order.
+    events: Vec<TraceEvent>,
+}
+
+struct TraceEvent {
+    name: String,
+    category: &'static str,
+    phase: Phase,
+    timestamp: Duration,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Phase {
+    Begin,
+    End,
+}
+
+impl Phase {
+    fn as_str(self) -> &'static str {
+        match self {
+            Phase::Begin => "B",
+            Phase::End => "E",
+        }
+    }
+}
+
+impl TraceRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the start of a duration event (e.g., entering a function)
+    /// at `timestamp`, which is measured from the start of execution.
+    pub(crate) fn begin(
+        &mut self,
+        timestamp: Duration,
+        category: &'static str,
+        name: impl ToString,
+    ) {
+        self.push(timestamp, category, name, Phase::Begin);
+    }
+
+    /// Records the end of a duration event begun by [`Self::begin`].
+    pub(crate) fn end(&mut self, timestamp: Duration, category: &'static str, name: impl ToString) {
+        self.push(timestamp, category, name, Phase::End);
+    }
+
+    fn push(
+        &mut self,
+        timestamp: Duration,
+        category: &'static str,
+        name: impl ToString,
+        phase: Phase,
+    ) {
+        self.events.push(TraceEvent {
+            name: name.to_string(),
+            category,
+            phase,
+            timestamp,
+        });
+    }
+
+    /// Serializes the recorded events as a JSON document in the Chrome
+    /// trace event format.
+    pub fn to_json(&self) -> String {
+        let mut output = String::new();
+        output.push_str("{\"traceEvents\":[\n");
+        for (event, index) in self.events.iter().zip(0..) {
+            if index > 0 {
+                output.push_str(",\n");
+            }
+            output.push_str("{\"name\":");
+            push_json_string(&mut output, &event.name);
+            output.push_str(",\"cat\":");
+            push_json_string(&mut output, event.category);
+            output.push_str(&format!(
+                ",\"ph\":\"{}\",\"ts\":{},\"pid\":1,\"tid\":1}}",
+                event.phase.as_str(),
+                event.timestamp.as_micros()
+            ));
+        }
+        output.push_str("\n]}\n");
+        output
+    }
+}
+
+fn push_json_string(output: &mut String, s: &str) {
+    output.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
@@ -29,6 +29,12 @@ impl<K: salsa::AsId, V> AllocTable<K, V> {
         K::from_id(salsa::Id::from_u32(index))
     }
 
+    /// Makes room for `additional` more values, so that adding them
+    /// doesn't grow the table one reallocation at a time.
+    pub fn reserve(&mut self, additional: usize) {
+        self.vec.reserve(additional);
+    }
+
     pub fn add(&mut self, value: V) -> K {
         let key = self.next_key();
         self.vec.push(value);
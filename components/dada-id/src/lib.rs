@@ -3,12 +3,15 @@
 //! interning and entities, but they are meant to be used for "internal" items (for example,
 //! within a function, tracking the tree of expressions).
 //!
-//! In general each id I maps to some value V, but ids come in two forms:
+//! In general each id I maps to some value V, but ids come in three forms:
 //!
 //! * allocating ids -- each time you add a value V, you get a fresh id I back.
 //!   This is appropriate when you will be adding other "metadata" attached to the id,
 //!   such as a span.
 //! * interning ids -- if you add the same value V twice, you get back the same id I twice.
+//! * list ids -- each time you add a list of values V, you get a fresh id I back that
+//!   stands for the slice `[V]`. The elements of all the lists in a table share one
+//!   arena, so small lists (like the arguments of a call) don't each need a `Vec`.
 //!
 //! To use these utilities, you make use of two macros:
 //!
@@ -18,9 +21,11 @@
 
 pub mod alloc_table;
 pub mod intern_table;
+pub mod list_table;
 pub mod prelude {
     pub use crate::InternAllocKey;
     pub use crate::InternKey;
+    pub use crate::InternListKey;
     pub use crate::InternValue;
 }
 
@@ -29,6 +34,7 @@ pub mod table_types {
     #![allow(non_camel_case_types)]
     pub type alloc<K, V> = crate::alloc_table::AllocTable<K, V>;
     pub type intern<K, V> = crate::intern_table::InternTable<K, V>;
+    pub type list<K, V> = crate::list_table::ListTable<K, V>;
 }
 
 /// Declares a struct usable as an id within a table.
@@ -122,6 +128,7 @@ macro_rules! id {
 ///     pub struct Foo {
 ///         exprs: alloc Expr => ExprData,
 ///         tys: intern Ty => TyData,
+///         expr_lists: list ExprList => Expr,
 ///     }
 /// }
 /// ```
@@ -174,6 +181,23 @@ macro_rules! tables {
             {
                 dada_id::InternValue::add(value, self)
             }
+
+            /// Makes room for `additional` more values with keys of type
+            /// `K`, when about that many are going to be added.
+            pub fn reserve<K>(&mut self, additional: usize)
+            where
+                K: $crate::InternAllocKey<Table = Self>,
+            {
+                K::reserve(self, additional)
+            }
+
+            /// Adds a list with the given elements, returning its key.
+            pub fn add_list<K>(&mut self, elements: impl IntoIterator<Item = K::Element>) -> K
+            where
+                K: $crate::InternListKey<Table = Self>,
+            {
+                K::add_list(self, elements)
+            }
         }
 
         $(
@@ -196,6 +220,10 @@ macro_rules! tables {
             fn data_mut(self, table: &mut Self::Table) -> &mut Self::Value {
                 table.$f.data_mut(self)
             }
+
+            fn reserve(table: &mut Self::Table, additional: usize) {
+                table.$f.reserve(additional)
+            }
         }
     };
 
@@ -205,6 +233,25 @@ macro_rules! tables {
         }
     };
 
+    (@field_impl[$n:ident] $f:ident: list $k:ty => $v:ty) => {
+        impl dada_id::InternKey for $k {
+            type Table = $n;
+            type Value = [$v];
+
+            fn data(self, table: &Self::Table) -> &Self::Value {
+                table.$f.data(self)
+            }
+        }
+
+        impl dada_id::InternListKey for $k {
+            type Element = $v;
+
+            fn add_list(table: &mut Self::Table, elements: impl IntoIterator<Item = $v>) -> Self {
+                table.$f.add_list(elements)
+            }
+        }
+    };
+
     (@any_field_impl[$n:ident] $f:ident: $k:ty => $v:ty) => {
         impl $crate::InternValue for $v {
             type Table = $n;
@@ -236,7 +283,7 @@ pub trait InternValue {
 
 pub trait InternKey: salsa::AsId + 'static {
     type Table;
-    type Value;
+    type Value: ?Sized;
 
     /// Get the data for this key from the given table.
     fn data(self, table: &Self::Table) -> &Self::Value;
@@ -249,4 +296,16 @@ pub trait InternAllocKey: InternKey {
 
     /// Get mut ref to data for this key from the given table.
     fn data_mut(self, table: &mut Self::Table) -> &mut Self::Value;
+
+    /// Makes room in the given table for `additional` more keys.
+    fn reserve(table: &mut Self::Table, additional: usize);
+}
+
+pub trait InternListKey: InternKey {
+    /// The type of the list's elements (its value is `[Self::Element]`).
+    type Element;
+
+    /// Adds a list with the given elements to the given table.
+    fn add_list(table: &mut Self::Table, elements: impl IntoIterator<Item = Self::Element>)
+        -> Self;
 }
@@ -0,0 +1,54 @@
+use std::marker::PhantomData;
+
+/// An individual list table, where each list added to the table gets a
+/// unique index. The elements of all the lists live one after the other
+/// in a single vector (an arena), so adding a list doesn't allocate a
+/// vector of its own, and the key that stands for it is `Copy`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListTable<K: salsa::AsId, V> {
+    elements: Vec<V>,
+
+    /// For each list, the index in `elements` where it starts; it ends
+    /// where the next one starts.
+    starts: Vec<usize>,
+
+    phantom: PhantomData<K>,
+}
+
+impl<K: salsa::AsId, V> Default for ListTable<K, V> {
+    fn default() -> Self {
+        Self {
+            elements: vec![],
+            starts: vec![],
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<K: salsa::AsId, V> ListTable<K, V> {
+    pub fn add_list(&mut self, elements: impl IntoIterator<Item = V>) -> K {
+        let index: u32 = self.starts.len().try_into().unwrap();
+        self.starts.push(self.elements.len());
+        self.elements.extend(elements);
+        K::from_id(salsa::Id::from_u32(index))
+    }
+
+    pub fn data(&self, key: K) -> &[V] {
+        let index: usize = key.as_id().into();
+        let start = self.starts[index];
+        let end = self
+            .starts
+            .get(index + 1)
+            .copied()
+            .unwrap_or(self.elements.len());
+        &self.elements[start..end]
+    }
+}
+
+impl<K: salsa::AsId, V> std::ops::Index<K> for ListTable<K, V> {
+    type Output = [V];
+
+    fn index(&self, key: K) -> &Self::Output {
+        self.data(key)
+    }
+}
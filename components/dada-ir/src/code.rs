@@ -1,4 +1,7 @@
-use crate::{effect::Effect, filename::Filename, return_type::ReturnType, token_tree::TokenTree};
+use crate::{
+    effect::Effect, filename::Filename, return_type::ReturnType, span::Anchor,
+    token_tree::TokenTree, word::Word,
+};
 
 /// "Code" represents a block of code attached to a method.
 /// After parsing, it just contains a token tree, but you can...
@@ -7,6 +10,9 @@ use crate::{effect::Effect, filename::Filename, return_type::ReturnType, token_t
 ///   parse it into an `Ast`.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Code {
+    /// Name of the function the code belongs to, for logs.
+    pub name: Word,
+
     /// Declared effect for the function body -- e.g., `async fn` would have
     /// this be `async`. This can affect validation and code generation.
     pub effect: Effect,
@@ -23,12 +29,14 @@ pub struct Code {
 
 impl Code {
     pub fn new(
+        name: Word,
         effect: Effect,
         parameter_tokens: Option<TokenTree>,
         return_type: ReturnType,
         body_tokens: TokenTree,
     ) -> Self {
         Self {
+            name,
             effect,
             parameter_tokens,
             return_type,
@@ -39,6 +47,12 @@ impl Code {
     pub fn filename(self, db: &dyn crate::Db) -> Filename {
         self.body_tokens.filename(db)
     }
+
+    /// The anchor that the spans of the code's tokens and syntax tree are
+    /// relative to.
+    pub fn anchor(self, db: &dyn crate::Db) -> Anchor {
+        self.body_tokens.anchor(db)
+    }
 }
 
 impl<Db: ?Sized + crate::Db> salsa::DebugWithDb<Db> for Code {
@@ -4,6 +4,7 @@
 use crate::{
     class::Class,
     code::validated::op::Op,
+    extern_function::ExternFunction,
     filename::Filename,
     function::Function,
     in_ir_db::InIrDb,
@@ -50,6 +51,10 @@ pub struct BirData {
 
     /// The starting block
     pub start_basic_block: BasicBlock,
+
+    /// The code of each basic block, indexed by block; see
+    /// [`BasicBlockCode`].
+    code: Vec<BasicBlockCode>,
 }
 
 impl DebugWithDb<InIrDb<'_, Bir>> for BirData {
@@ -69,14 +74,25 @@ impl DebugWithDb<InIrDb<'_, Bir>> for BirData {
 }
 
 impl BirData {
+    /// Panics if an id in `tables` is out of range.
     pub fn new(tables: Tables, num_parameters: usize, start_basic_block: BasicBlock) -> Self {
+        let code = BasicBlock::max_key(&tables)
+            .iter()
+            .map(|basic_block| BasicBlockCode::new(&tables, basic_block))
+            .collect();
         Self {
             tables,
             num_parameters,
             start_basic_block,
+            code,
         }
     }
 
+    /// The code of `basic_block`, for executing it.
+    pub fn code(&self, basic_block: BasicBlock) -> &BasicBlockCode {
+        &self.code[usize::from(basic_block)]
+    }
+
     pub fn tables(&self) -> &Tables {
         &self.tables
     }
@@ -114,6 +130,8 @@ tables! {
         exprs: alloc Expr => ExprData,
         places: alloc Place => PlaceData,
         target_places: alloc TargetPlace => TargetPlaceData,
+        place_lists: list PlaceList => Place,
+        label_lists: list LabelList => SpannedOptionalWord,
     }
 }
 
@@ -201,6 +219,61 @@ impl DebugWithDb<InIrDb<'_, Bir>> for BasicBlockData {
     }
 }
 
+/// The statements and terminator of a basic block, copied out of the tables
+/// when the BIR is built, so that the interpreter reaches each with a single
+/// index rather than looking up the block and then the statement. The ids
+/// are kept for the origins of the statements and terminator.
+#[derive(PartialEq, Eq, Clone, Hash, Debug)]
+pub struct BasicBlockCode {
+    pub statements: Vec<(Statement, StatementCode)>,
+    pub terminator: (Terminator, TerminatorData),
+}
+
+impl BasicBlockCode {
+    fn new(tables: &Tables, basic_block: BasicBlock) -> Self {
+        let data = &tables[basic_block];
+        BasicBlockCode {
+            statements: data
+                .statements
+                .iter()
+                .map(|&statement| (statement, StatementCode::new(tables, &tables[statement])))
+                .collect(),
+            terminator: (data.terminator, tables[data.terminator].clone()),
+        }
+    }
+}
+
+/// A statement in a [`BasicBlockCode`]. The operands of an assignment (the
+/// place assigned to and, for [`StatementData::AssignExpr`], the expression)
+/// are copied out of the tables too, each beside its id, which is kept for
+/// its origin. The places that the operands refer to are still ids.
+#[derive(PartialEq, Eq, Clone, Hash, Debug)]
+pub enum StatementCode {
+    /// [`StatementData::AssignExpr`].
+    AssignExpr((TargetPlace, TargetPlaceData), (Expr, ExprData)),
+
+    /// [`StatementData::AssignPlace`].
+    AssignPlace((TargetPlace, TargetPlaceData), Place),
+
+    /// Any other statement, which has no operands to copy.
+    Other(StatementData),
+}
+
+impl StatementCode {
+    fn new(tables: &Tables, data: &StatementData) -> Self {
+        match *data {
+            StatementData::AssignExpr(target_place, expr) => StatementCode::AssignExpr(
+                (target_place, tables[target_place].clone()),
+                (expr, tables[expr].clone()),
+            ),
+            StatementData::AssignPlace(target_place, place) => {
+                StatementCode::AssignPlace((target_place, tables[target_place].clone()), place)
+            }
+            _ => StatementCode::Other(data.clone()),
+        }
+    }
+}
+
 /// An "element" of a basic block is a statement or terminator.
 ///
 /// (In case you are curious, I made this term up, it's not standard.)
@@ -366,8 +439,8 @@ pub enum TerminatorExpr {
     /// argument are present as well.
     Call {
         function: Place,
-        arguments: Vec<Place>,
-        labels: Vec<SpannedOptionalWord>,
+        arguments: PlaceList,
+        labels: LabelList,
     },
 }
 
@@ -383,7 +456,7 @@ impl DebugWithDb<InIrDb<'_, Bir>> for TerminatorExpr {
                 .debug_tuple("Call")
                 .field(&function.debug(db))
                 .field(&arguments.debug(db))
-                .field(&labels.debug(db.db()))
+                .field(&labels.debug(db))
                 .finish(),
         }
     }
@@ -437,7 +510,7 @@ pub enum ExprData {
     Unit,
 
     /// `(a, b, ...)` (i.e., at least 2)
-    Tuple(Vec<Place>),
+    Tuple(PlaceList),
 
     /// `a + b`
     Op(Place, Op, Place),
@@ -464,7 +537,7 @@ impl DebugWithDb<InIrDb<'_, Bir>> for ExprData {
             ExprData::Shlease(p) => write!(f, "{:?}.shlease", p.debug(db)),
             ExprData::Give(p) => write!(f, "{:?}.give", p.debug(db)),
             ExprData::Unit => write!(f, "()"),
-            ExprData::Tuple(vars) => write_parenthesized_places(f, vars, db),
+            ExprData::Tuple(vars) => write_parenthesized_places(f, vars.data(db.tables()), db),
             ExprData::Op(lhs, op, rhs) => {
                 write!(f, "{:?} {} {:?}", lhs.debug(db), op.str(), rhs.debug(db))
             }
@@ -494,6 +567,30 @@ fn write_parenthesized_places(
 
 id!(pub struct Place);
 
+id!(pub struct PlaceList);
+
+impl DebugWithDb<InIrDb<'_, Bir>> for PlaceList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>, db: &InIrDb<'_, Bir>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.data(db.tables()).iter().map(|place| place.debug(db)))
+            .finish()
+    }
+}
+
+id!(pub struct LabelList);
+
+impl DebugWithDb<InIrDb<'_, Bir>> for LabelList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>, db: &InIrDb<'_, Bir>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(
+                self.data(db.tables())
+                    .iter()
+                    .map(|label| label.debug(db.db())),
+            )
+            .finish()
+    }
+}
+
 impl DebugWithDb<InIrDb<'_, Bir>> for Place {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>, db: &InIrDb<'_, Bir>) -> std::fmt::Result {
         write!(f, "{:?}", self.data(db.tables()).debug(db))
@@ -506,6 +603,12 @@ pub enum PlaceData {
     Function(Function),
     Class(Class),
     Intrinsic(Intrinsic),
+
+    /// A function provided by the program embedding Dada.
+    HostFunction(Word),
+
+    ExternFunction(ExternFunction),
+
     Dot(Place, Word),
 }
 
@@ -515,7 +618,9 @@ impl DebugWithDb<InIrDb<'_, Bir>> for PlaceData {
             PlaceData::LocalVariable(v) => write!(f, "{:?}", v.debug(db)),
             PlaceData::Function(func) => write!(f, "{:?}", func.debug(db.db())),
             PlaceData::Class(class) => write!(f, "{:?}", class.debug(db.db())),
-            PlaceData::Intrinsic(intrinsic) => write!(f, "{:?}", intrinsic),
+            PlaceData::ExternFunction(func) => write!(f, "{:?}", func.debug(db.db())),
+            PlaceData::Intrinsic(intrinsic) => write!(f, "{}", intrinsic.as_str(db.db())),
+            PlaceData::HostFunction(name) => write!(f, "{}", name.as_str(db.db())),
             PlaceData::Dot(p, id) => write!(f, "{:?}.{}", p.debug(db), id.as_str(db.db())),
         }
     }
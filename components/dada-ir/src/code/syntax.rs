@@ -2,7 +2,7 @@ use crate::{
     code::syntax::op::Op,
     in_ir_db::InIrDb,
     in_ir_db::InIrDbExt,
-    span::Span,
+    span::{FileSpan, Span},
     storage::{Atomic, SpannedSpecifier},
     word::{SpannedOptionalWord, Word},
 };
@@ -16,6 +16,7 @@ salsa::entity2! {
         origin: Code,
         #[value ref] data: TreeData,
         #[value ref] spans: Spans,
+        #[value ref] operator_spans: OperatorSpans,
     }
 }
 
@@ -28,6 +29,13 @@ impl DebugWithDb<dyn crate::Db> for Tree {
     }
 }
 
+impl Tree {
+    /// Where `span`, one of the [`Spans`] of this tree, is in the file.
+    pub fn file_span(self, db: &dyn crate::Db, span: Span) -> FileSpan {
+        self.origin(db).anchor(db).file_span(db, span)
+    }
+}
+
 impl InIrDb<'_, Tree> {
     fn tables(&self) -> &Tables {
         &self.data(self.db()).tables
@@ -69,7 +77,8 @@ tables! {
 origin_table! {
     /// Side table that contains the spans for everything in a syntax tree.
     /// This isn't normally needed except for diagnostics, so it's
-    /// kept separate to avoid reducing incremental reuse.
+    /// kept separate to avoid reducing incremental reuse. The spans are
+    /// relative to the anchor of the tree's code; see [`Tree::file_span`].
     /// You can request it by invoking the `spans`
     /// method in the `dada_parse` prelude.
     #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
@@ -80,6 +89,36 @@ origin_table! {
     }
 }
 
+/// Side table with the span of the operator in each binary `Op` and
+/// `OpEq` expression, like the `+` in `a + b`, for runtime errors such
+/// as overflow to point at. Like [`Spans`], the spans are relative to
+/// the anchor of the tree's code.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct OperatorSpans {
+    /// Sorted by expression, since an expression is created after its
+    /// operands.
+    spans: Vec<(Expr, Span)>,
+}
+
+impl OperatorSpans {
+    pub fn get(&self, expr: Expr) -> Option<Span> {
+        let index = self
+            .spans
+            .binary_search_by_key(&expr, |&(expr, _)| expr)
+            .ok()?;
+        Some(self.spans[index].1)
+    }
+
+    pub fn push(&mut self, expr: Expr, span: Span) {
+        assert!(self.spans.last().map_or(true, |&(last, _)| last < expr));
+        self.spans.push((expr, span));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Expr, Span)> + '_ {
+        self.spans.iter().copied()
+    }
+}
+
 id!(pub struct Expr);
 
 impl DebugWithDb<InIrDb<'_, Tree>> for Expr {
@@ -206,7 +245,9 @@ impl DebugWithDb<InIrDb<'_, Tree>> for ExprData {
                 .field(&v.debug(db))
                 .field(&e.debug(db))
                 .finish(),
-            ExprData::Parenthesized(e) => f.debug_tuple("Share").field(&e.debug(db)).finish(),
+            ExprData::Parenthesized(e) => {
+                f.debug_tuple("Parenthesized").field(&e.debug(db)).finish()
+            }
             ExprData::Tuple(e) => f.debug_tuple("Tuple").field(&e.debug(db)).finish(),
             ExprData::If(c, t, e) => f
                 .debug_tuple("If")
@@ -5,6 +5,7 @@
 use crate::{
     class::Class,
     code::validated::op::Op,
+    extern_function::ExternFunction,
     function::Function,
     in_ir_db::InIrDb,
     intrinsic::Intrinsic,
@@ -425,6 +426,12 @@ pub enum PlaceData {
     LocalVariable(LocalVariable),
     Function(Function),
     Intrinsic(Intrinsic),
+
+    /// A function provided by the program embedding Dada.
+    HostFunction(Word),
+
+    ExternFunction(ExternFunction),
+
     Class(Class),
     Dot(Place, Word),
 }
@@ -434,7 +441,12 @@ impl DebugWithDb<InIrDb<'_, Tree>> for PlaceData {
         match self {
             PlaceData::LocalVariable(lv) => DebugWithDb::fmt(lv, f, db),
             PlaceData::Function(function) => DebugWithDb::fmt(function, f, db.db()),
-            PlaceData::Intrinsic(intrinsic) => std::fmt::Debug::fmt(intrinsic, f),
+            PlaceData::Intrinsic(intrinsic) => DebugWithDb::fmt(intrinsic, f, db.db()),
+            PlaceData::HostFunction(name) => f
+                .debug_tuple("HostFunction")
+                .field(&name.debug(db.db()))
+                .finish(),
+            PlaceData::ExternFunction(function) => DebugWithDb::fmt(function, f, db.db()),
             PlaceData::Class(class) => DebugWithDb::fmt(class, f, db.db()),
             PlaceData::Dot(place, field) => f
                 .debug_tuple("Dot")
@@ -1,7 +1,9 @@
 use salsa::DebugWithDb;
 
 use crate::{
+    error_code::ErrorCode,
     filename::Filename,
+    messages::MessageTemplate,
     span::{FileSpan, Span},
 };
 
@@ -14,10 +16,19 @@ pub struct ErrorReported;
 #[non_exhaustive]
 pub struct Diagnostic {
     pub severity: Severity,
+
+    /// The code of the error, for looking up its explanation; see
+    /// [`ErrorCode`]. Only errors have codes.
+    pub code: Option<ErrorCode>,
     pub span: FileSpan,
     pub message: String,
+
+    /// For errors, the message as it was written, for looking up its
+    /// translation; see [`crate::messages`].
+    pub message_template: Option<MessageTemplate>,
     pub labels: Vec<Label>,
     pub children: Vec<Diagnostic>,
+    pub fixes: Vec<Fix>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
@@ -35,6 +46,37 @@ pub struct Label {
     pub message: String,
 }
 
+/// An edit suggested to fix the problem a diagnostic reports.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+pub struct Fix {
+    /// Describes the edit, like "make the function `async`".
+    pub message: String,
+
+    /// The text to replace; empty to insert `replacement` at its start.
+    pub span: FileSpan,
+    pub replacement: String,
+
+    /// How confident we are that applying the edit is what the user wants.
+    pub applicability: Applicability,
+}
+
+/// Whether a [`Fix`] can be applied automatically.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Applicability {
+    /// The edit is definitely what was meant, and editors (or tools) can
+    /// apply it without asking the user anything more.
+    MachineApplicable,
+
+    /// The edit may not be what was meant, or may not compile; the user
+    /// should look it over.
+    MaybeIncorrect,
+
+    /// The replacement contains placeholders, like `name`, that the user
+    /// has to fill in.
+    HasPlaceholders,
+}
+
 #[salsa::accumulator(in crate::Jar)]
 pub struct Diagnostics(Diagnostic);
 
@@ -46,11 +88,20 @@ macro_rules! diag {
     }
 }
 
-/// Convenience macro for avoiding `format!`
+/// Convenience macro for avoiding `format!`; the first argument is the
+/// [`ErrorCode`](crate::error_code::ErrorCode) variant, like `UnresolvedName`.
+/// The message can only use `{}` placeholders, so that it can be translated
+/// (see [`crate::messages`]).
 #[macro_export]
 macro_rules! error {
-    ($span:expr, $($message:tt)*) => {
-        $crate::diagnostic::Diagnostic::builder($crate::diagnostic::Severity::Error, $span, format!($($message)*))
+    ($code:ident, $span:expr, $template:literal $(, $arg:expr)* $(,)?) => {
+        $crate::diagnostic::Diagnostic::builder_from_template(
+            $crate::diagnostic::Severity::Error,
+            $span,
+            $template,
+            vec![$($arg.to_string()),*],
+        )
+        .code($crate::error_code::ErrorCode::$code)
     }
 }
 
@@ -85,6 +136,20 @@ impl Diagnostic {
         DiagnosticBuilder::new(severity, span, message)
     }
 
+    /// Create a new diagnostic builder whose message is `template` with
+    /// `args` filled in, and that can be translated; see [`crate::messages`].
+    pub fn builder_from_template(
+        severity: Severity,
+        span: FileSpan,
+        template: &'static str,
+        args: Vec<String>,
+    ) -> DiagnosticBuilder {
+        let mut builder =
+            DiagnosticBuilder::new(severity, span, crate::messages::fill(template, &args));
+        builder.message_template = Some(MessageTemplate { template, args });
+        builder
+    }
+
     /// Emit the diagnostic to the [`Diagnostics`] accumulator.
     /// You can fetch the diagnostics produced by a query (and its
     /// dependencies) by invoking `query::accumulated::<Diagnostics>(..)`.
@@ -108,12 +173,15 @@ impl Label {
 #[must_use]
 pub struct DiagnosticBuilder {
     severity: Severity,
+    code: Option<ErrorCode>,
     span: FileSpan,
     message: String,
+    message_template: Option<MessageTemplate>,
 
     /// All labels added by user so far (primary or secondary).
     labels: Vec<Label>,
     children: Vec<Diagnostic>,
+    fixes: Vec<Fix>,
 
     /// Initially true. Indicates if we should add a default primary
     /// label ("here") when the diagnostic is emitted. Set to false
@@ -125,14 +193,24 @@ impl DiagnosticBuilder {
     fn new(severity: Severity, span: FileSpan, message: impl ToString) -> Self {
         Self {
             severity,
+            code: None,
             span,
             message: message.to_string(),
+            message_template: None,
             labels: vec![],
             children: vec![],
+            fixes: vec![],
             add_primary_label: true,
         }
     }
 
+    /// Sets the code of the error (see [`ErrorCode`]).
+    #[must_use = "you have not emitted the diagnostic"]
+    pub fn code(mut self, code: ErrorCode) -> Self {
+        self.code = Some(code);
+        self
+    }
+
     /// Replaces the "primary label", which is always placed on the source
     /// of the diagnostic. The default primary label, if nothing else is given,
     /// is just "here".
@@ -179,6 +257,40 @@ impl DiagnosticBuilder {
         self
     }
 
+    /// Add a fix: replacing the text at `span` with `replacement` (or
+    /// inserting it, if `span` is empty). As with labels, a [`Span`] is
+    /// assumed to be in the same file as the primary location. The fix is
+    /// [`Applicability::MachineApplicable`]; use [`Self::suggestion`] for
+    /// edits that the user should check.
+    #[must_use = "you have not emitted the diagnostic"]
+    pub fn fix(
+        self,
+        span: impl IntoFileSpan,
+        replacement: impl ToString,
+        message: impl ToString,
+    ) -> Self {
+        self.suggestion(span, replacement, message, Applicability::MachineApplicable)
+    }
+
+    /// Add a fix, as with [`Self::fix`], with the given applicability.
+    #[must_use = "you have not emitted the diagnostic"]
+    pub fn suggestion(
+        mut self,
+        span: impl IntoFileSpan,
+        replacement: impl ToString,
+        message: impl ToString,
+        applicability: Applicability,
+    ) -> Self {
+        let span = span.maybe_in_file(self.span.filename);
+        self.fixes.push(Fix {
+            message: message.to_string(),
+            span,
+            replacement: replacement.to_string(),
+            applicability,
+        });
+        self
+    }
+
     /// Return the completed diagnostic.
     pub fn finish(mut self) -> Diagnostic {
         if self.add_primary_label {
@@ -187,10 +299,13 @@ impl DiagnosticBuilder {
 
         Diagnostic {
             severity: self.severity,
+            code: self.code,
             span: self.span,
             message: self.message,
+            message_template: self.message_template,
             labels: self.labels,
             children: self.children,
+            fixes: self.fixes,
         }
     }
 
@@ -0,0 +1,194 @@
+//! Error codes: each kind of error that [`error!`](crate::error) reports has
+//! a stable code, like `E0010`, that users can look up (with `dada explain`)
+//! for a longer explanation and examples. The explanations live in the
+//! `error_codes` directory, one Markdown file per code.
+//!
+//! Codes are never reused or renumbered: when an error goes away, its code
+//! stays reserved, and new errors get new codes at the end.
+
+macro_rules! error_codes {
+    ($($(#[$attr:meta])* $code:ident => $name:ident,)*) => {
+        #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        pub enum ErrorCode {
+            $($(#[$attr])* $name,)*
+        }
+
+        impl ErrorCode {
+            pub const ALL: &'static [ErrorCode] = &[$(ErrorCode::$name,)*];
+
+            /// The code, like `E0010`.
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(ErrorCode::$name => stringify!($code),)*
+                }
+            }
+
+            /// The long-form explanation of the error, in Markdown.
+            pub fn explanation(self) -> &'static str {
+                match self {
+                    $(ErrorCode::$name => include_str!(concat!("error_codes/", stringify!($code), ".md")),)*
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    /// A delimiter that is not matched by one of the same kind.
+    E0001 => UnbalancedDelimiter,
+
+    /// A `#|` comment with no `|#`.
+    E0002 => UnterminatedComment,
+
+    /// A string literal with no closing `"`.
+    E0003 => UnterminatedString,
+
+    /// A `{` in a string literal with no `}`.
+    E0004 => UnclosedInterpolation,
+
+    /// Tokens that don't fit the grammar.
+    E0005 => SyntaxError,
+
+    /// `=` used to assign to a field.
+    E0006 => AssignWithEqual,
+
+    /// Two items in a file with the same name.
+    E0007 => DuplicateItem,
+
+    /// A function with a return type whose body is empty.
+    E0008 => EmptyFunctionBody,
+
+    /// A number literal that can't be represented.
+    E0009 => InvalidNumber,
+
+    /// A name that doesn't refer to anything in scope.
+    E0010 => UnresolvedName,
+
+    /// An assignment to something other than a variable or field.
+    E0011 => InvalidAssignmentTarget,
+
+    /// `await` in a function that is not `async`.
+    E0012 => AwaitOutsideAsync,
+
+    /// `await` in an `atomic` section.
+    E0013 => AwaitInAtomic,
+
+    /// A function call that names a later argument but not an earlier one.
+    E0014 => ParameterNameRequired,
+
+    /// A `return` with no value in a function with a return type.
+    E0015 => MissingReturnValue,
+
+    /// A `return` with a value in a function without a return type.
+    E0016 => UnexpectedReturnValue,
+
+    /// A package used as a value.
+    E0017 => PackageAsValue,
+
+    /// A name that a package doesn't define.
+    E0018 => UnresolvedPackageItem,
+
+    /// A call with more or fewer arguments than parameters.
+    E0019 => ArgumentCountMismatch,
+
+    /// A call naming an argument differently from its parameter.
+    E0020 => ArgumentNameMismatch,
+
+    /// Something that a backend can't compile yet.
+    E0021 => UnsupportedByBackend,
+
+    /// Running code that had compilation errors.
+    E0022 => CompilationError,
+
+    /// A panic.
+    E0023 => Panic,
+
+    /// A value of the wrong kind, like a string where an integer is needed.
+    E0024 => TypeMismatch,
+
+    /// A field access naming a field that the class doesn't have.
+    E0025 => UnknownField,
+
+    /// An operator applied to values it doesn't work on.
+    E0026 => InvalidOperands,
+
+    /// Arithmetic that overflows or divides by zero.
+    E0027 => ArithmeticError,
+
+    /// Giving away a value that is only leased or shared.
+    E0028 => InsufficientPermissions,
+
+    /// Using a lease after it was cancelled.
+    E0029 => CancelledLease,
+
+    /// Reading a variable that has no value.
+    E0030 => UninitializedMemory,
+
+    /// Writing to a field of a shared object.
+    E0031 => WriteToShared,
+
+    /// Overwriting a value that is reserved.
+    E0032 => OverwriteReserved,
+
+    /// Storing into a constant.
+    E0033 => AssignToConstant,
+
+    /// Awaiting something that is not fully owned.
+    E0034 => AwaitWithoutOwnership,
+
+    /// Doing something that needs a capability the program was not granted.
+    E0035 => MissingCapability,
+
+    /// A call to an `extern` function that could not be made.
+    E0036 => ForeignFunction,
+
+    /// A call to a host function that the host did not provide.
+    E0037 => MissingHostFunction,
+
+    /// Named arguments in a call to a host function.
+    E0038 => HostFunctionNamedArguments,
+
+    /// An error returned by a host function.
+    E0039 => HostFunctionError,
+
+    /// A program that used more memory than it was allowed.
+    E0040 => MemoryLimitExceeded,
+
+    /// An `exit` code that doesn't fit in an exit status.
+    E0041 => ExitCodeOutOfRange,
+
+    /// Output that could not be written.
+    E0042 => OutputFailed,
+
+    /// A function compiled by the JIT that returned something different
+    /// from the interpreter.
+    E0043 => JitMismatch,
+
+    /// Something the interpreter doesn't do yet.
+    E0044 => Unimplemented,
+
+    /// A program that was stopped before it finished.
+    E0045 => Cancelled,
+
+    /// A program with no function named `main` to run.
+    E0046 => MissingMain,
+
+    /// A `main` function that takes more than one parameter.
+    E0047 => InvalidMain,
+}
+
+impl ErrorCode {
+    /// The error code written as `code`, like `E0010` (or `e0010`).
+    pub fn from_name(code: &str) -> Option<ErrorCode> {
+        ErrorCode::ALL
+            .iter()
+            .copied()
+            .find(|c| c.as_str().eq_ignore_ascii_case(code))
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{
+    filename::Filename,
+    span::FileSpan,
+    word::{SpannedWord, Word},
+};
+
+salsa::entity2! {
+    /// A function implemented in a shared library, declared like
+    /// `extern "libm.so.6" fn cos(x: f64) -> f64`. The library is loaded
+    /// (and the symbol looked up) when the function is first called.
+    entity ExternFunction in crate::Jar {
+        #[id] name: SpannedWord,
+
+        /// The library to load, as given in the declaration.
+        library: Word,
+
+        signature: ExternSignature,
+
+        /// Overall span of the declaration
+        span: FileSpan,
+    }
+}
+
+impl<Db: ?Sized + crate::Db> salsa::DebugWithDb<Db> for ExternFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>, db: &Db) -> std::fmt::Result {
+        let db = db.as_dyn_ir_db();
+        write!(f, "{}", self.name(db).as_str(db))
+    }
+}
+
+impl ExternFunction {
+    pub fn filename(self, db: &dyn crate::Db) -> Filename {
+        self.span(db).filename
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExternSignature {
+    pub parameters: Vec<ExternParameter>,
+
+    /// `None` if the function returns nothing (C's `void`).
+    pub return_ty: Option<FfiTy>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExternParameter {
+    pub name: Word,
+    pub ty: FfiTy,
+}
+
+/// The types that values can have when they cross into C.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FfiTy {
+    I32,
+    I64,
+    U32,
+    U64,
+    F32,
+    F64,
+
+    /// A NUL-terminated string (`const char *`).
+    Str,
+
+    /// A buffer of bytes, made from a string or a tuple of integers and
+    /// passed as two C arguments: a pointer to it (`const uint8_t *`) and
+    /// its length (`size_t`). The callee only sees a copy, so writes to the
+    /// buffer are not visible in Dada. Only parameters can have this type.
+    Bytes,
+}
+
+impl FfiTy {
+    pub const ALL: &'static [FfiTy] = &[
+        FfiTy::I32,
+        FfiTy::I64,
+        FfiTy::U32,
+        FfiTy::U64,
+        FfiTy::F32,
+        FfiTy::F64,
+        FfiTy::Str,
+        FfiTy::Bytes,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FfiTy::I32 => "i32",
+            FfiTy::I64 => "i64",
+            FfiTy::U32 => "u32",
+            FfiTy::U64 => "u64",
+            FfiTy::F32 => "f32",
+            FfiTy::F64 => "f64",
+            FfiTy::Str => "str",
+            FfiTy::Bytes => "bytes",
+        }
+    }
+}
+
+impl std::fmt::Display for FfiTy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
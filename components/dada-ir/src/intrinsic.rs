@@ -1,33 +1,67 @@
-// Intrinsic functions or other items
-use crate::word::Word;
-
-macro_rules! intrinsic {
-    ($($name:ident => $s:expr,)*) => {
-        #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
-        pub enum Intrinsic {
-            $($name,)*
-        }
+//! Intrinsics: built-in functions, like `print`, that are in scope in every
+//! file (an item of the file with the same name shadows them). Which
+//! intrinsics there are is not fixed by the IR: each file has the
+//! [`intrinsics`] it is given, which start out as the [`STANDARD`] ones.
+//! A program embedding Dada can add its own when it creates the database
+//! (see `dada_db::Db::with_intrinsics`); the interpreter runs those with
+//! the host function of the same name (see `dada_execute::host_function`).
+
+use crate::{filename::Filename, word::Word};
 
-        impl Intrinsic {
-            pub const ALL: &'static [Intrinsic] = &[
-                $(Intrinsic::$name,)*
-            ];
-
-            pub fn as_str(self, _db: &dyn crate::Db) -> &'static str {
-                match self {
-                    $(
-                        Intrinsic::$name => $s,
-                    )*
-                }
-            }
-
-            pub fn name(self, db: &dyn $crate::Db) -> Word {
-                Word::from(db, self.as_str(db))
-            }
+/// The intrinsics that every file has, which the interpreter implements
+/// itself.
+pub const STANDARD: &[&str] = &[
+    "print",
+    "big",
+    "weak",
+    "upgrade",
+    "arg",
+    "arg_count",
+    "exit",
+];
+
+#[salsa::interned(Intrinsic in crate::Jar)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IntrinsicData {
+    pub name: Word,
+}
+
+impl Intrinsic {
+    pub fn from(db: &dyn crate::Db, name: impl ToString) -> Self {
+        IntrinsicData {
+            name: Word::from(db, name),
         }
+        .intern(db)
+    }
+
+    pub fn name(self, db: &dyn crate::Db) -> Word {
+        self.data(db).name
+    }
+
+    pub fn as_str(self, db: &dyn crate::Db) -> &str {
+        self.name(db).as_str(db)
+    }
+
+    /// True for the intrinsics in [`STANDARD`].
+    pub fn is_standard(self, db: &dyn crate::Db) -> bool {
+        STANDARD.contains(&self.as_str(db))
+    }
+}
+
+impl<Db: ?Sized + crate::Db> salsa::DebugWithDb<Db> for Intrinsic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>, db: &Db) -> std::fmt::Result {
+        f.write_str(self.as_str(db.as_dyn_ir_db()))
     }
 }
 
-intrinsic! {
-    Print => "print",
+/// Salsa input: the intrinsics in scope in `filename`.
+///
+/// Defaults to the [`STANDARD`] ones.
+#[salsa::memoized(in crate::Jar ref)]
+#[allow(clippy::needless_lifetimes)]
+pub fn intrinsics(db: &dyn crate::Db, _filename: Filename) -> Vec<Intrinsic> {
+    STANDARD
+        .iter()
+        .map(|name| Intrinsic::from(db, name))
+        .collect()
 }
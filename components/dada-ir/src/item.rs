@@ -1,9 +1,13 @@
-use crate::{class::Class, code::Code, function::Function, span::FileSpan, word::Word};
+use crate::{
+    class::Class, code::Code, extern_function::ExternFunction, function::Function, span::FileSpan,
+    word::Word,
+};
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub enum Item {
     Function(Function),
     Class(Class),
+    ExternFunction(ExternFunction),
 }
 
 impl Item {
@@ -11,6 +15,7 @@ impl Item {
         match self {
             Item::Function(f) => f.span(db),
             Item::Class(c) => c.span(db),
+            Item::ExternFunction(e) => e.span(db),
         }
     }
 
@@ -18,6 +23,7 @@ impl Item {
         match self {
             Item::Function(f) => f.name(db).word(db),
             Item::Class(c) => c.name(db).word(db),
+            Item::ExternFunction(e) => e.name(db).word(db),
         }
     }
 
@@ -25,6 +31,7 @@ impl Item {
         match self {
             Item::Function(f) => f.name(db).span(db),
             Item::Class(c) => c.name(db).span(db),
+            Item::ExternFunction(e) => e.name(db).span(db),
         }
     }
 
@@ -32,6 +39,7 @@ impl Item {
         match self {
             Item::Function(_) => "function",
             Item::Class(_) => "class",
+            Item::ExternFunction(_) => "extern function",
         }
     }
 
@@ -40,7 +48,7 @@ impl Item {
     pub fn code(self, db: &dyn crate::Db) -> Option<Code> {
         match self {
             Item::Function(f) => Some(f.code(db)),
-            Item::Class(_) => None,
+            Item::Class(_) | Item::ExternFunction(_) => None,
         }
     }
 }
@@ -57,11 +65,18 @@ impl From<Class> for Item {
     }
 }
 
+impl From<ExternFunction> for Item {
+    fn from(value: ExternFunction) -> Self {
+        Self::ExternFunction(value)
+    }
+}
+
 impl<Db: ?Sized + crate::Db> salsa::DebugWithDb<Db> for Item {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>, db: &Db) -> std::fmt::Result {
         match self {
             Item::Function(v) => std::fmt::Debug::fmt(&v.debug(db), f),
             Item::Class(v) => std::fmt::Debug::fmt(&v.debug(db), f),
+            Item::ExternFunction(v) => std::fmt::Debug::fmt(&v.debug(db), f),
         }
     }
 }
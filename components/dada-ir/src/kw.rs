@@ -49,6 +49,7 @@ define_keywords! {
     Await => "await",
     Class => "class",
     Else => "else",
+    Extern => "extern",
     False => "false",
     Fn => "fn",
     Give => "give",
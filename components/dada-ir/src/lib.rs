@@ -5,6 +5,8 @@ pub mod class;
 pub mod code;
 pub mod diagnostic;
 pub mod effect;
+pub mod error_code;
+pub mod extern_function;
 pub mod filename;
 pub mod format_string;
 pub mod function;
@@ -13,10 +15,14 @@ pub mod intrinsic;
 pub mod item;
 pub mod kw;
 pub mod lines;
+pub mod lint;
 pub mod manifest;
+pub mod messages;
 pub mod parameter;
+pub mod phase;
 pub mod prelude;
 pub mod return_type;
+pub mod source_text;
 pub mod span;
 pub mod storage;
 pub mod token;
@@ -32,13 +38,18 @@ pub struct Jar(
     code::validated::Tree,
     class::Class,
     diagnostic::Diagnostics,
+    extern_function::ExternFunction,
     format_string::FormatString,
     format_string::FormatStringSection,
     function::Function,
     function::Variable,
+    intrinsic::Intrinsic,
+    intrinsic::intrinsics,
     kw::keywords,
-    lines::line_table,
+    lint::lint_levels,
     manifest::source_text,
+    manifest::host_functions,
+    manifest::dependencies,
     parameter::Parameter,
     storage::SpannedSpecifier,
     token_tree::TokenTree,
@@ -47,6 +58,7 @@ pub struct Jar(
     word::SpannedWord,
     word::SpannedOptionalWord,
     return_type::ReturnType,
+    span::Anchor,
 );
 
 pub trait Db: salsa::DbWithJar<Jar> {
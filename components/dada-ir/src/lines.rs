@@ -1,9 +1,17 @@
+//! Converting between byte offsets and (line, column) positions. The line
+//! table of each file is kept with its text (see [`line_table`]), so that
+//! the CLI renderer, the LSP, and anything else that shows positions to
+//! users can convert as many offsets as they like without rescanning the
+//! text, and an edit only rescans the text it inserted.
+
+use std::ops::Range;
+
 use crate::{
     filename::Filename,
-    span::{LineColumn, Offset},
+    span::{FileSpan, LineColumn, Offset},
 };
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct LineTable {
     /// Stores the index of the `\n` for each line.
     /// So `0..line_endings[0]` represents the range of characters for the first line
@@ -13,6 +21,39 @@ pub struct LineTable {
 }
 
 impl LineTable {
+    pub(crate) fn new(text: &str) -> Self {
+        LineTable {
+            line_endings: newlines(text, 0).collect(),
+            end_offset: Offset::from(text.len()),
+        }
+    }
+
+    /// The line table of the text after the bytes in `range` are replaced
+    /// by `replacement`: the line endings before the edit are kept, those
+    /// after it are moved, and only `replacement` is scanned.
+    pub(crate) fn edit(&self, range: Range<usize>, replacement: &str) -> Self {
+        let moved =
+            |offset: Offset| Offset::from(usize::from(offset) - range.len() + replacement.len());
+        let before = self
+            .line_endings
+            .iter()
+            .copied()
+            .take_while(|&ending| usize::from(ending) < range.start);
+        let after = self
+            .line_endings
+            .iter()
+            .copied()
+            .skip_while(|&ending| usize::from(ending) < range.end)
+            .map(moved);
+        LineTable {
+            line_endings: before
+                .chain(newlines(replacement, range.start))
+                .chain(after)
+                .collect(),
+            end_offset: moved(self.end_offset),
+        }
+    }
+
     /// Given a (1-based) line number, find the start of the line.
     ///
     /// If `line` is out of range, panics.
@@ -25,46 +66,54 @@ impl LineTable {
         }
     }
 
-    fn num_lines(&self) -> usize {
+    pub fn num_lines(&self) -> usize {
         self.line_endings.len() + 1
     }
+
+    /// Converts a character index `position` into a line and column tuple.
+    pub fn line_column(&self, position: Offset) -> LineColumn {
+        match self.line_endings.binary_search(&position) {
+            Ok(line0) | Err(line0) => {
+                let line_start = self.line_start(line0);
+                LineColumn::new0(line0, position - line_start)
+            }
+        }
+    }
+
+    /// Given a line/column tuple, returns a character index.
+    pub fn offset(&self, position: LineColumn) -> Offset {
+        if position.line0_usize() >= self.num_lines() {
+            return self.end_offset;
+        }
+        let line_start = self.line_start(position.line0_usize());
+        (line_start + position.column0()).min(self.end_offset)
+    }
 }
 
 /// Converts a character index `position` into a line and column tuple.
 pub fn line_column(db: &dyn crate::Db, filename: Filename, position: Offset) -> LineColumn {
-    let table = line_table(db, filename);
-    match table.line_endings.binary_search(&position) {
-        Ok(line0) | Err(line0) => {
-            let line_start = table.line_start(line0);
-            LineColumn::new0(line0, position - line_start)
-        }
-    }
+    line_table(db, filename).line_column(position)
+}
+
+/// The line and column of the start and of the end of `span`.
+pub fn line_columns(db: &dyn crate::Db, span: FileSpan) -> (LineColumn, LineColumn) {
+    let table = line_table(db, span.filename);
+    (table.line_column(span.start), table.line_column(span.end))
 }
 
 /// Given a (1-based) line/column tuple, returns a character index.
 pub fn offset(db: &dyn crate::Db, filename: Filename, position: LineColumn) -> Offset {
-    let table = line_table(db, filename);
+    line_table(db, filename).offset(position)
+}
 
-    if position.line0_usize() >= table.num_lines() {
-        return table.end_offset;
-    }
-    let line_start = table.line_start(position.line0_usize());
-    (line_start + position.column0()).min(table.end_offset)
+/// The line table of `filename`, for converting between offsets and lines
+/// and columns in it.
+pub fn line_table(db: &dyn crate::Db, filename: Filename) -> &LineTable {
+    crate::manifest::source_text(db, filename).line_table()
 }
 
-#[salsa::memoized(in crate::Jar ref)]
-#[allow(clippy::needless_lifetimes)]
-fn line_table(db: &dyn crate::Db, filename: Filename) -> LineTable {
-    let source_text = crate::manifest::source_text(db, filename);
-    let mut p: usize = 0;
-    let mut table = LineTable {
-        line_endings: vec![],
-        end_offset: Offset::from(source_text.len()),
-    };
-    for line in source_text.lines() {
-        p += line.len();
-        table.line_endings.push(Offset::from(p));
-        p += 1;
-    }
-    table
+/// The offsets of the line breaks in `text`, which starts at `start`.
+fn newlines(text: &str, start: usize) -> impl Iterator<Item = Offset> + '_ {
+    text.match_indices('\n')
+        .map(move |(index, _)| Offset::from(start + index))
 }
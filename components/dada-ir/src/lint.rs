@@ -0,0 +1,112 @@
+//! Lints: warnings about code that compiles but is probably not what was
+//! meant. Each lint has a name, by which its level can be set, and a
+//! default level. They are checked by `dada lint` (see
+//! `dada_check::lint_filename`), not as part of checking a file for errors.
+
+use crate::filename::Filename;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Lint {
+    /// A variable or parameter that is never read.
+    UnusedVariable,
+
+    /// A local variable with the same name as another one in scope.
+    Shadowing,
+
+    /// Code after a `return`, `break`, or `continue`.
+    UnreachableCode,
+
+    /// A lease of a value that is not stored anywhere, like
+    /// `Pair(22, 44).lease.share`, or a new value that is shared and then
+    /// stored in a `my` or `leased` variable, like `my p = Pair(22, 44).share`.
+    ShareFresh,
+}
+
+/// What to do when a lint finds something.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LintLevel {
+    /// Report nothing.
+    Allow,
+
+    /// Report a warning.
+    Warn,
+
+    /// Report an error.
+    Deny,
+}
+
+impl Lint {
+    pub const ALL: &'static [Lint] = &[
+        Lint::UnusedVariable,
+        Lint::Shadowing,
+        Lint::UnreachableCode,
+        Lint::ShareFresh,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unused_variable",
+            Lint::Shadowing => "shadowing",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::ShareFresh => "share_fresh",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.iter().copied().find(|lint| lint.name() == name)
+    }
+
+    /// A one-line description, for listing the lints.
+    pub fn description(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "variables and parameters that are never read",
+            Lint::Shadowing => "local variables named like another variable in scope",
+            Lint::UnreachableCode => "code after a `return`, `break`, or `continue`",
+            Lint::ShareFresh => "sharing new values or leases of them where that has no use",
+        }
+    }
+
+    pub fn default_level(self) -> LintLevel {
+        match self {
+            Lint::UnusedVariable | Lint::UnreachableCode | Lint::ShareFresh => LintLevel::Warn,
+
+            // Declaring `x` again is a common way to replace a value.
+            Lint::Shadowing => LintLevel::Allow,
+        }
+    }
+}
+
+impl LintLevel {
+    pub fn name(self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LintLevel> {
+        [LintLevel::Allow, LintLevel::Warn, LintLevel::Deny]
+            .into_iter()
+            .find(|level| level.name() == name)
+    }
+}
+
+/// Salsa input: the lints whose level for `filename` is not their default,
+/// with the level to use instead.
+///
+/// Defaults to none.
+#[salsa::memoized(in crate::Jar ref)]
+#[allow(clippy::needless_lifetimes)]
+pub fn lint_levels(_db: &dyn crate::Db, _filename: Filename) -> Vec<(Lint, LintLevel)> {
+    vec![]
+}
+
+/// The level of `lint` for the code in `filename`.
+pub fn lint_level(db: &dyn crate::Db, filename: Filename, lint: Lint) -> LintLevel {
+    lint_levels(db, filename)
+        .iter()
+        .rev()
+        .find(|(l, _)| *l == lint)
+        .map_or(lint.default_level(), |&(_, level)| level)
+}
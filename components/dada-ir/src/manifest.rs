@@ -1,7 +1,40 @@
-use crate::filename::Filename;
+use crate::{filename::Filename, source_text::SourceText, word::Word};
 
 #[salsa::memoized(in crate::Jar ref)]
 #[allow(clippy::needless_lifetimes)]
-pub fn source_text(_db: &dyn crate::Db, _filename: Filename) -> String {
+pub fn source_text(_db: &dyn crate::Db, _filename: Filename) -> SourceText {
     panic!("input")
 }
+
+/// Salsa input: names of the functions that the program embedding Dada
+/// provides to `filename` (see `dada_execute::host_function`). They are
+/// in scope like intrinsics are.
+///
+/// Defaults to none.
+#[salsa::memoized(in crate::Jar ref)]
+#[allow(clippy::needless_lifetimes)]
+pub fn host_functions(_db: &dyn crate::Db, _filename: Filename) -> Vec<Word> {
+    vec![]
+}
+
+/// A package that the code in a file depends on: its items are in scope
+/// as `name.item`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Dependency {
+    /// The name that the package is referred to by.
+    pub name: Word,
+
+    /// The files of the package.
+    pub filenames: Vec<Filename>,
+}
+
+/// Salsa input: the packages that `filename` depends on, as declared by
+/// the manifest of the package that contains it (see `dada-lang`'s
+/// `package` module).
+///
+/// Defaults to none.
+#[salsa::memoized(in crate::Jar ref)]
+#[allow(clippy::needless_lifetimes)]
+pub fn dependencies(_db: &dyn crate::Db, _filename: Filename) -> Vec<Dependency> {
+    vec![]
+}
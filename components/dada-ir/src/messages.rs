@@ -0,0 +1,157 @@
+//! The message catalog: translations of error messages into other
+//! languages. Errors are reported in English, with the message written
+//! where the error is found (see [`error!`](crate::error)). A message pack,
+//! one file per locale in the `messages` directory, gives the translation of
+//! each of those messages, under the code of the error that uses it; the
+//! `{}` in a message stand for the names, values, and so forth that are
+//! filled in, in the same order in the translation as in the English.
+//!
+//! A message pack looks like this:
+//!
+//! ```text
+//! # Lines starting with `#` are comments.
+//! [E0010]
+//! can't find anything named `{}`
+//! = rien ne s'appelle `{}` ici
+//! ```
+//!
+//! Messages that a pack doesn't translate are shown in English.
+
+use std::sync::OnceLock;
+
+use dada_collections::Map;
+
+use crate::{diagnostic::Diagnostic, error_code::ErrorCode};
+
+/// The locale of the messages as they are written, which needs no pack.
+pub const SOURCE_LOCALE: &str = "en";
+
+/// The message packs, by locale.
+const PACKS: &[(&str, &str)] = &[("fr", include_str!("messages/fr.txt"))];
+
+/// The message of an error as it was written, before the arguments were
+/// filled in, so that it can be looked up in a [`Catalog`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MessageTemplate {
+    /// The English message, with `{}` for each argument.
+    pub template: &'static str,
+    pub args: Vec<String>,
+}
+
+/// The locales that messages can be shown in.
+pub fn locales() -> impl Iterator<Item = &'static str> {
+    std::iter::once(SOURCE_LOCALE).chain(PACKS.iter().map(|(locale, _)| *locale))
+}
+
+/// The catalog of translations for `locale`; `None` for the
+/// [`SOURCE_LOCALE`] and for locales that have no message pack.
+pub fn catalog(locale: &str) -> Option<&'static Catalog> {
+    static CATALOGS: OnceLock<Vec<(&'static str, Catalog)>> = OnceLock::new();
+    CATALOGS
+        .get_or_init(|| {
+            PACKS
+                .iter()
+                .map(|&(locale, pack)| (locale, Catalog::parse(locale, pack)))
+                .collect()
+        })
+        .iter()
+        .find(|(l, _)| *l == locale)
+        .map(|(_, catalog)| catalog)
+}
+
+/// The message as written that is the same as `template`, if any pack
+/// translates it. This recovers the template of a diagnostic that was
+/// stored on disk (see `dada_bytecode::decode_diagnostics`); a message that
+/// no pack translates doesn't need its template.
+pub fn known_template(code: ErrorCode, template: &str) -> Option<&'static str> {
+    PACKS.iter().find_map(|&(locale, _)| {
+        catalog(locale)?
+            .messages
+            .keys()
+            .find(|&&(c, english)| c == code && english == template)
+            .map(|&(_, english)| english)
+    })
+}
+
+/// Replaces each `{}` in `template` with the next of `args`. As with
+/// `format!`, `{{` and `}}` stand for `{` and `}`.
+pub fn fill(template: &str, args: &[String]) -> String {
+    let mut output = String::new();
+    let mut args = args.iter();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('}')) => {
+                chars.next();
+                output.push_str(args.next().map_or("", |arg| &arg[..]));
+            }
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+    debug_assert!(args.next().is_none(), "too many arguments for `{template}`");
+    output
+}
+
+/// The translations of one message pack.
+#[derive(Debug)]
+pub struct Catalog {
+    messages: Map<(ErrorCode, &'static str), &'static str>,
+}
+
+impl Catalog {
+    /// Parses the message pack `pack`, panicking if it is malformed: the
+    /// packs are compiled in, so a mistake in one is a bug in the compiler.
+    fn parse(locale: &str, pack: &'static str) -> Catalog {
+        let mut messages = Map::default();
+        let mut code = None;
+        let mut english = None;
+        for (line, line1) in pack.lines().zip(1..) {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                code = ErrorCode::from_name(name);
+                assert!(
+                    code.is_some(),
+                    "{locale}:{line1}: unknown error code `{name}`"
+                );
+                english = None;
+            } else if let Some(translation) = line.strip_prefix("= ") {
+                let (Some(code), Some(english)) = (code, english.take()) else {
+                    panic!("{locale}:{line1}: translation without a message to translate");
+                };
+                messages.insert((code, english), translation);
+            } else {
+                assert!(
+                    code.is_some(),
+                    "{locale}:{line1}: message outside of an error code"
+                );
+                english = Some(line);
+            }
+        }
+        Catalog { messages }
+    }
+
+    /// The messages that the catalog translates, as written, each with the
+    /// code of the error it is under.
+    pub fn messages(&self) -> impl Iterator<Item = (ErrorCode, &'static str)> + '_ {
+        self.messages.keys().copied()
+    }
+
+    /// The message of `diagnostic`, translated if the catalog has a
+    /// translation for it, and otherwise as written.
+    pub fn message(&self, diagnostic: &Diagnostic) -> String {
+        let (Some(code), Some(template)) = (diagnostic.code, &diagnostic.message_template) else {
+            return diagnostic.message.clone();
+        };
+        match self.messages.get(&(code, template.template)) {
+            Some(translation) => fill(translation, &template.args),
+            None => diagnostic.message.clone(),
+        }
+    }
+}
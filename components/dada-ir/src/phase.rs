@@ -0,0 +1,30 @@
+//! Tracing spans for the phases of compiling and running a program: `lex`,
+//! `parse`, `validate`, `brew`, and `execute`. They all have the target
+//! [`TARGET`] and the level `info`, so that `--log-filter dada::phase=info`
+//! turns them on (and nothing else), and they all record the `file` they
+//! work on and, except for lexing and parsing a whole file, the `item`.
+
+/// The target of the phase spans.
+pub const TARGET: &str = "dada::phase";
+
+/// Creates the span for a phase, like `phase_span!("lex", db, filename)` or
+/// `phase_span!("brew", db, filename, function.name(db))`. The `file` and
+/// `item` fields are only computed if the span is enabled.
+#[macro_export]
+macro_rules! phase_span {
+    ($phase:literal, $db:expr, $filename:expr) => {
+        tracing::info_span!(
+            target: $crate::phase::TARGET,
+            $phase,
+            file = $filename.as_str($db)
+        )
+    };
+    ($phase:literal, $db:expr, $filename:expr, $item:expr) => {
+        tracing::info_span!(
+            target: $crate::phase::TARGET,
+            $phase,
+            file = $filename.as_str($db),
+            item = $item.as_str($db)
+        )
+    };
+}
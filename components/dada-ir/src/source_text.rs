@@ -0,0 +1,83 @@
+//! The text of a source file, as stored in the [`source_text`] input.
+//!
+//! [`source_text`]: crate::manifest::source_text
+
+use std::{
+    ops::{Deref, Range},
+    sync::Arc,
+};
+
+use crate::lines::LineTable;
+
+/// The text of a source file, along with its line table. Clones share both,
+/// so handing the text to the database (or taking it back out to edit it)
+/// copies nothing. [`SourceText::edit`] copies the text once, and only
+/// scans the new text for line breaks.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SourceText {
+    text: Arc<String>,
+    line_table: Arc<LineTable>,
+}
+
+impl SourceText {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The line table, for converting between offsets and lines and
+    /// columns in the text.
+    pub fn line_table(&self) -> &LineTable {
+        &self.line_table
+    }
+
+    /// The text with the bytes in `range` replaced by `replacement`.
+    ///
+    /// Panics if `range` is out of bounds or does not fall on character
+    /// boundaries.
+    #[must_use]
+    pub fn edit(&self, range: Range<usize>, replacement: &str) -> SourceText {
+        let mut text = String::with_capacity(self.text.len() - range.len() + replacement.len());
+        text.push_str(&self.text[..range.start]);
+        text.push_str(replacement);
+        text.push_str(&self.text[range.end..]);
+        SourceText {
+            line_table: Arc::new(self.line_table.edit(range, replacement)),
+            text: Arc::new(text),
+        }
+    }
+}
+
+impl From<String> for SourceText {
+    fn from(text: String) -> Self {
+        SourceText {
+            line_table: Arc::new(LineTable::new(&text)),
+            text: Arc::new(text),
+        }
+    }
+}
+
+impl From<&str> for SourceText {
+    fn from(text: &str) -> Self {
+        SourceText::from(text.to_string())
+    }
+}
+
+impl Deref for SourceText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl std::fmt::Debug for SourceText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl std::fmt::Display for SourceText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.as_str(), f)
+    }
+}
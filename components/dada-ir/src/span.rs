@@ -17,13 +17,22 @@ impl FileSpan {
     pub fn contains(&self, offset: Offset) -> bool {
         self.start <= offset && offset < self.end
     }
+
+    /// Returns a 0-length span at the start of this span
+    #[must_use]
+    pub fn span_at_start(self) -> FileSpan {
+        FileSpan {
+            filename: self.filename,
+            start: self.start,
+            end: self.start,
+        }
+    }
 }
 
 impl<Db: ?Sized + crate::Db> salsa::DebugWithDb<Db> for FileSpan {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>, db: &Db) -> std::fmt::Result {
         let db = db.as_dyn_ir_db();
-        let start = crate::lines::line_column(db, self.filename, self.start);
-        let end = crate::lines::line_column(db, self.filename, self.end);
+        let (start, end) = crate::lines::line_columns(db, *self);
         write!(
             f,
             "{}:{}:{}:{}:{}",
@@ -164,6 +173,44 @@ impl Span {
     }
 }
 
+salsa::entity2! {
+    /// Where an item starts in its file. The spans within an item (those
+    /// of its token trees and syntax tree, for example) are relative to
+    /// its anchor, so they stay the same when an edit moves the item, and
+    /// nothing computed from them has to be computed again.
+    entity Anchor in crate::Jar {
+        filename: Filename,
+        offset: Offset,
+    }
+}
+
+impl Anchor {
+    /// Where `span`, which is relative to this anchor, is in the file.
+    /// This depends on where the item is, so call it only when that is
+    /// needed (to report a diagnostic, say).
+    pub fn file_span(self, db: &dyn crate::Db, span: Span) -> FileSpan {
+        let offset = self.offset(db);
+        FileSpan {
+            filename: self.filename(db),
+            start: offset + span.start.0,
+            end: offset + span.end.0,
+        }
+    }
+}
+
+/// A span relative to an [`Anchor`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AnchoredSpan {
+    pub anchor: Anchor,
+    pub span: Span,
+}
+
+impl AnchoredSpan {
+    pub fn file_span(self, db: &dyn crate::Db) -> FileSpan {
+        self.anchor.file_span(db, self.span)
+    }
+}
+
 impl std::ops::Add<u32> for Offset {
     type Output = Offset;
 
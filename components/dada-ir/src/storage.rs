@@ -1,4 +1,4 @@
-use crate::span::FileSpan;
+use crate::span::{AnchoredSpan, FileSpan};
 
 salsa::entity2! {
     /// A "spanned spanned" is a `Specifier` that carries a span for diagnostics.
@@ -11,16 +11,20 @@ salsa::entity2! {
 
         /// Span of the specifier keywords, or storage name if specified was
         /// defaulted.
-        span: FileSpan,
+        anchored_span: AnchoredSpan,
     }
 }
 
 impl SpannedSpecifier {
     /// Creates a new `SpannedSpecifier` for a variable/field that didn't
     /// have an explicit specifier.
-    pub fn new_defaulted(db: &dyn crate::Db, name_span: FileSpan) -> Self {
+    pub fn new_defaulted(db: &dyn crate::Db, name_span: AnchoredSpan) -> Self {
         Self::new(db, Specifier::Shleased, true, name_span)
     }
+
+    pub fn span(self, db: &dyn crate::Db) -> FileSpan {
+        self.anchored_span(db).file_span(db)
+    }
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash, Debug)]
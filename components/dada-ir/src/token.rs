@@ -37,7 +37,7 @@ pub enum Token {
     /// Some unclassifiable, non-whitespace char
     Unknown(char),
 
-    /// `# ...`, argument is the length (including `#`).
+    /// `# ...` or `#| ... |#`, argument is the length (including `#`).
     /// Note that the newline that comes after a comment is
     /// considered a separate whitespace token.
     Comment(u32),
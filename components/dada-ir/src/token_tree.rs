@@ -1,13 +1,16 @@
 use crate::{
     filename::Filename,
-    span::{FileSpan, Span},
+    span::{Anchor, FileSpan, Span},
     token::Token,
     Jar,
 };
 
 salsa::entity2! {
+    /// The tokens between a pair of delimiters (or in a whole file).
+    /// `span` is relative to `anchor`, which is the start of the item the
+    /// tree is in (or of the file, for a tree outside any item).
     entity TokenTree in Jar {
-        filename: Filename,
+        anchor: Anchor,
         span: Span,
         #[value ref] tokens: Vec<Token>,
     }
@@ -16,17 +19,26 @@ salsa::entity2! {
 impl<Db: ?Sized + crate::Db> salsa::DebugWithDb<Db> for TokenTree {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>, db: &Db) -> std::fmt::Result {
         let db = db.as_dyn_ir_db();
-        let file_span: FileSpan = self.span(db).in_file(self.filename(db));
-        write!(f, "Tokens({:?})", file_span.into_debug(db))
+        write!(f, "Tokens({:?})", self.file_span(db).into_debug(db))
     }
 }
 
 impl TokenTree {
+    pub fn filename(self, db: &dyn crate::Db) -> Filename {
+        self.anchor(db).filename(db)
+    }
+
+    /// Where the tree is in the file; see [`Anchor::file_span`].
+    pub fn file_span(self, db: &dyn crate::Db) -> FileSpan {
+        self.anchor(db).file_span(db, self.span(db))
+    }
+
     #[allow(clippy::len_without_is_empty)]
     pub fn len(self, db: &dyn crate::Db) -> u32 {
         self.span(db).len()
     }
 
+    /// The tokens with their spans, which are relative to the anchor.
     pub fn spanned_tokens(self, db: &dyn crate::Db) -> impl Iterator<Item = (Span, Token)> + '_ {
         let mut start = self.span(db).start;
         self.tokens(db).iter().map(move |token| {
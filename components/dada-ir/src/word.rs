@@ -1,4 +1,6 @@
-use crate::span::FileSpan;
+use dada_collections::Map;
+
+use crate::span::{AnchoredSpan, FileSpan};
 
 use super::{Db, Jar};
 
@@ -32,6 +34,43 @@ impl<Db: ?Sized + crate::Db> salsa::DebugWithDb<Db> for Word {
     }
 }
 
+/// A cache in front of the database's interner for code that interns many
+/// words, like the lexer, which interns every identifier it sees. Looking
+/// a word up in the database locks its tables and allocates a `String`
+/// even when the word is already there; a word that was interned through
+/// the cache before is found without either. Words of up to
+/// [`Self::INLINE_LEN`] bytes, which are most identifiers, are kept inline
+/// rather than in a `String` of their own.
+///
+/// A cache belongs to one database, and each lexer has its own, so threads
+/// lexing different files don't contend over it.
+#[derive(Default)]
+pub struct WordCache {
+    short: Map<(u8, [u8; WordCache::INLINE_LEN]), Word>,
+    long: Map<String, Word>,
+}
+
+impl WordCache {
+    pub const INLINE_LEN: usize = 15;
+
+    pub fn intern(&mut self, db: &dyn Db, string: &str) -> Word {
+        if string.len() <= Self::INLINE_LEN {
+            let mut bytes = [0; Self::INLINE_LEN];
+            bytes[..string.len()].copy_from_slice(string.as_bytes());
+            *self
+                .short
+                .entry((string.len() as u8, bytes))
+                .or_insert_with(|| Word::from(db, string))
+        } else if let Some(&word) = self.long.get(string) {
+            word
+        } else {
+            let word = Word::from(db, string);
+            self.long.insert(string.to_owned(), word);
+            word
+        }
+    }
+}
+
 pub trait ToString {
     fn to_string(self) -> String;
 }
@@ -66,7 +105,7 @@ salsa::entity2! {
     /// of compilation.
     entity SpannedWord in crate::Jar {
         #[id] word: Word,
-        span: FileSpan,
+        anchored_span: AnchoredSpan,
     }
 }
 
@@ -74,6 +113,10 @@ impl SpannedWord {
     pub fn as_str(self, db: &dyn crate::Db) -> &str {
         self.word(db).as_str(db)
     }
+
+    pub fn span(self, db: &dyn crate::Db) -> FileSpan {
+        self.anchored_span(db).file_span(db)
+    }
 }
 
 impl<Db: ?Sized + crate::Db> salsa::DebugWithDb<Db> for SpannedWord {
@@ -88,7 +131,7 @@ salsa::entity2! {
     /// an `Option<Label>`).
     entity SpannedOptionalWord in crate::Jar {
         #[id] word: Option<Word>,
-        span: FileSpan,
+        anchored_span: AnchoredSpan,
     }
 }
 
@@ -96,6 +139,10 @@ impl SpannedOptionalWord {
     pub fn as_str(self, db: &dyn crate::Db) -> Option<&str> {
         Some(self.word(db)?.as_str(db))
     }
+
+    pub fn span(self, db: &dyn crate::Db) -> FileSpan {
+        self.anchored_span(db).file_span(db)
+    }
 }
 
 impl<Db: ?Sized + crate::Db> salsa::DebugWithDb<Db> for SpannedOptionalWord {
@@ -0,0 +1,415 @@
+use cranelift_codegen::ir::{
+    condcodes::IntCC, types, AbiParam, Block, InstBuilder, MemFlags, Value as ClifValue,
+};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::JITModule;
+use cranelift_module::Module;
+use dada_collections::Map;
+use dada_id::prelude::*;
+use dada_ir::code::{
+    bir::{self, ExprData, PlaceData, StatementData, TargetPlaceData, TerminatorData},
+    validated::op::Op,
+};
+
+use crate::kinds::{self, Kind, Kinds, Unsupported};
+
+/// Status returned by compiled code when it has stored its result.
+pub(crate) const STATUS_RETURNED: u64 = 0;
+
+/// Status returned by compiled code when it reached something it cannot
+/// handle (an overflow, say); the call must be interpreted instead.
+pub(crate) const STATUS_BAILED: u64 = 1;
+
+/// The signature of compiled code: it is given the address of its
+/// arguments (one 64-bit word each) and the address to store its result,
+/// and returns [`STATUS_RETURNED`] or [`STATUS_BAILED`].
+pub(crate) type CompiledCode = unsafe extern "C" fn(*const u64, *mut u64) -> u64;
+
+/// Compiles `bir_data`, whose locals have the given kinds, into a
+/// function in `module`. Returns the address of the finalized code.
+pub(crate) fn compile(
+    module: &mut JITModule,
+    bir_data: &bir::BirData,
+    kinds: &Kinds,
+) -> Result<CompiledCode, Unsupported> {
+    let pointer_type = module.target_config().pointer_type();
+    let mut context = module.make_context();
+    let signature = &mut context.func.signature;
+    signature.params.push(AbiParam::new(pointer_type));
+    signature.params.push(AbiParam::new(pointer_type));
+    signature.returns.push(AbiParam::new(types::I64));
+
+    let mut builder_context = FunctionBuilderContext::new();
+    let builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+    FunctionTranslator::new(builder, bir_data, kinds).translate()?;
+
+    let id = module
+        .declare_anonymous_function(&context.func.signature)
+        .map_err(|_| Unsupported)?;
+    module
+        .define_function(id, &mut context)
+        .map_err(|_| Unsupported)?;
+    module.clear_context(&mut context);
+    module.finalize_definitions();
+
+    let code = module.get_finalized_function(id);
+
+    // Safety: the function was declared with the signature of `CompiledCode`.
+    Ok(unsafe { std::mem::transmute::<*const u8, CompiledCode>(code) })
+}
+
+/// Translates BIR into Cranelift IR. Each BIR local variable becomes a
+/// Cranelift variable holding a 64-bit integer, and each basic block
+/// becomes a Cranelift block.
+struct FunctionTranslator<'b, 'k> {
+    builder: FunctionBuilder<'b>,
+    bir_data: &'k bir::BirData,
+    kinds: &'k Kinds,
+    blocks: Map<bir::BasicBlock, Block>,
+
+    /// Block that returns [`STATUS_BAILED`].
+    bail_block: Block,
+
+    /// The address where the result is stored.
+    output: ClifValue,
+}
+
+impl<'b, 'k> FunctionTranslator<'b, 'k> {
+    fn new(mut builder: FunctionBuilder<'b>, bir_data: &'k bir::BirData, kinds: &'k Kinds) -> Self {
+        let entry_block = builder.create_block();
+        builder.append_block_params_for_function_params(entry_block);
+        builder.switch_to_block(entry_block);
+        let arguments = builder.block_params(entry_block)[0];
+        let output = builder.block_params(entry_block)[1];
+
+        // Parameters are loaded from the arguments; other locals start
+        // out as zero (they are always assigned before they are used).
+        for local_variable in bir_data.max_local_variable().iter() {
+            let variable = variable(local_variable);
+            builder.declare_var(variable, types::I64);
+            let value = if usize::from(local_variable) < bir_data.num_parameters() {
+                let offset = (usize::from(local_variable) * 8) as i32;
+                builder
+                    .ins()
+                    .load(types::I64, MemFlags::trusted(), arguments, offset)
+            } else {
+                builder.ins().iconst(types::I64, 0)
+            };
+            builder.def_var(variable, value);
+        }
+
+        let blocks: Map<bir::BasicBlock, Block> = bir_data
+            .all_basic_blocks()
+            .map(|basic_block| (basic_block, builder.create_block()))
+            .collect();
+        builder.ins().jump(blocks[&bir_data.start_basic_block], &[]);
+
+        let bail_block = builder.create_block();
+        builder.switch_to_block(bail_block);
+        let status = builder.ins().iconst(types::I64, STATUS_BAILED as i64);
+        builder.ins().return_(&[status]);
+
+        Self {
+            builder,
+            bir_data,
+            kinds,
+            blocks,
+            bail_block,
+            output,
+        }
+    }
+
+    fn translate(mut self) -> Result<(), Unsupported> {
+        let bir_data = self.bir_data;
+        let tables = &bir_data.tables;
+        for basic_block in bir_data.all_basic_blocks() {
+            self.builder.switch_to_block(self.blocks[&basic_block]);
+            let basic_block_data = basic_block.data(tables);
+            for &statement in &basic_block_data.statements {
+                self.translate_statement(tables, statement)?;
+            }
+            self.translate_terminator(tables, basic_block_data.terminator)?;
+        }
+        self.builder.seal_all_blocks();
+        self.builder.finalize();
+        Ok(())
+    }
+
+    fn translate_statement(
+        &mut self,
+        tables: &bir::Tables,
+        statement: bir::Statement,
+    ) -> Result<(), Unsupported> {
+        match statement.data(tables) {
+            StatementData::AssignExpr(target, expr) => {
+                let (value, _) = self.translate_expr(tables, *expr)?;
+                self.assign(tables, *target, value)
+            }
+            StatementData::AssignPlace(target, place) => {
+                let (value, _) = self.read_place(tables, *place)?;
+                self.assign(tables, *target, value)
+            }
+            StatementData::Clear(_) => Ok(()),
+            StatementData::BreakpointStart(..) | StatementData::BreakpointEnd(..) => {
+                Err(Unsupported)
+            }
+        }
+    }
+
+    fn translate_terminator(
+        &mut self,
+        tables: &bir::Tables,
+        terminator: bir::Terminator,
+    ) -> Result<(), Unsupported> {
+        match terminator.data(tables) {
+            TerminatorData::Goto(basic_block)
+            | TerminatorData::StartAtomic(basic_block)
+            | TerminatorData::EndAtomic(basic_block) => {
+                self.builder.ins().jump(self.blocks[basic_block], &[]);
+            }
+            TerminatorData::If(condition, if_true, if_false) => {
+                let (value, kind) = self.read_place(tables, *condition)?;
+                if kind != Kind::Bool {
+                    return Err(Unsupported);
+                }
+                self.builder.ins().brnz(value, self.blocks[if_true], &[]);
+                self.builder.ins().jump(self.blocks[if_false], &[]);
+            }
+            TerminatorData::Return(place) => {
+                let (value, _) = self.read_place(tables, *place)?;
+                self.builder
+                    .ins()
+                    .store(MemFlags::trusted(), value, self.output, 0);
+                let status = self
+                    .builder
+                    .ins()
+                    .iconst(types::I64, STATUS_RETURNED as i64);
+                self.builder.ins().return_(&[status]);
+            }
+
+            // Let the interpreter report the error.
+            TerminatorData::Error | TerminatorData::Panic => {
+                self.builder.ins().jump(self.bail_block, &[]);
+            }
+
+            TerminatorData::Assign(..) => return Err(Unsupported),
+        }
+        Ok(())
+    }
+
+    fn translate_expr(
+        &mut self,
+        tables: &bir::Tables,
+        expr: bir::Expr,
+    ) -> Result<(ClifValue, Kind), Unsupported> {
+        match expr.data(tables) {
+            ExprData::BooleanLiteral(value) => Ok((self.constant(*value as i64), Kind::Bool)),
+            ExprData::IntegerLiteral(value) => Ok((self.constant(*value as i64), Kind::Int)),
+            ExprData::UnsignedIntegerLiteral(value) => {
+                Ok((self.constant(*value as i64), Kind::UnsignedInt))
+            }
+            ExprData::SignedIntegerLiteral(value) => Ok((self.constant(*value), Kind::SignedInt)),
+            ExprData::Unit => Ok((self.constant(0), Kind::Unit)),
+            ExprData::Reserve(place)
+            | ExprData::Share(place)
+            | ExprData::Lease(place)
+            | ExprData::Shlease(place)
+            | ExprData::Give(place) => self.read_place(tables, *place),
+            ExprData::Op(lhs, op, rhs) => {
+                let lhs = self.read_place(tables, *lhs)?;
+                let rhs = self.read_place(tables, *rhs)?;
+                self.translate_op(lhs, *op, rhs)
+            }
+            ExprData::Unary(op, rhs) => {
+                let (value, kind) = self.read_place(tables, *rhs)?;
+                let result_kind = kinds::unary_result(*op, kind).ok_or(Unsupported)?;
+
+                // Negating `i64::MIN` (as a signed integer) overflows, and
+                // unsigned integers above `i64::MAX` have no negation.
+                match kind {
+                    Kind::SignedInt => {
+                        let overflow = self.builder.ins().icmp_imm(IntCC::Equal, value, i64::MIN);
+                        self.bail_if(overflow);
+                    }
+                    _ => self.bail_if_above_i64_max(value),
+                }
+                Ok((self.builder.ins().ineg(value), result_kind))
+            }
+            ExprData::FloatLiteral(_)
+            | ExprData::StringLiteral(_)
+            | ExprData::Tuple(_)
+            | ExprData::Error => Err(Unsupported),
+        }
+    }
+
+    fn translate_op(
+        &mut self,
+        (lhs, lhs_kind): (ClifValue, Kind),
+        op: Op,
+        (rhs, rhs_kind): (ClifValue, Kind),
+    ) -> Result<(ClifValue, Kind), Unsupported> {
+        let result_kind = kinds::op_result(lhs_kind, op, rhs_kind).ok_or(Unsupported)?;
+
+        // Like the interpreter, convert integers of unspecified sign to
+        // signed integers when combined with one (which fails if they are
+        // too large).
+        let signed = lhs_kind == Kind::SignedInt || rhs_kind == Kind::SignedInt;
+        if signed {
+            for (value, kind) in [(lhs, lhs_kind), (rhs, rhs_kind)] {
+                if kind == Kind::Int {
+                    self.bail_if_above_i64_max(value);
+                }
+            }
+        }
+
+        let value = match op {
+            Op::EqualEqual => self.compare(IntCC::Equal, lhs, rhs),
+            Op::GreaterEqual if signed => self.compare(IntCC::SignedGreaterThanOrEqual, lhs, rhs),
+            Op::GreaterEqual => self.compare(IntCC::UnsignedGreaterThanOrEqual, lhs, rhs),
+            Op::LessEqual if signed => self.compare(IntCC::SignedLessThanOrEqual, lhs, rhs),
+            Op::LessEqual => self.compare(IntCC::UnsignedLessThanOrEqual, lhs, rhs),
+            Op::LessThan if signed => self.compare(IntCC::SignedLessThan, lhs, rhs),
+            Op::LessThan => self.compare(IntCC::UnsignedLessThan, lhs, rhs),
+            Op::GreaterThan if signed => self.compare(IntCC::SignedGreaterThan, lhs, rhs),
+            Op::GreaterThan => self.compare(IntCC::UnsignedGreaterThan, lhs, rhs),
+            Op::Plus if signed => self.signed_add(lhs, rhs),
+            Op::Plus => self.unsigned_add(lhs, rhs),
+            Op::Minus if signed => self.signed_sub(lhs, rhs),
+            Op::Minus => self.unsigned_sub(lhs, rhs),
+            Op::Times if signed => self.signed_mul(lhs, rhs),
+            Op::Times => self.unsigned_mul(lhs, rhs),
+            Op::DividedBy if signed => self.signed_div(lhs, rhs),
+            Op::DividedBy => self.unsigned_div(lhs, rhs),
+        };
+        Ok((value, result_kind))
+    }
+
+    fn compare(&mut self, cc: IntCC, lhs: ClifValue, rhs: ClifValue) -> ClifValue {
+        let condition = self.builder.ins().icmp(cc, lhs, rhs);
+        self.builder.ins().bint(types::I64, condition)
+    }
+
+    fn unsigned_add(&mut self, lhs: ClifValue, rhs: ClifValue) -> ClifValue {
+        let result = self.builder.ins().iadd(lhs, rhs);
+        let overflow = self
+            .builder
+            .ins()
+            .icmp(IntCC::UnsignedLessThan, result, lhs);
+        self.bail_if(overflow);
+        result
+    }
+
+    fn unsigned_sub(&mut self, lhs: ClifValue, rhs: ClifValue) -> ClifValue {
+        let underflow = self.builder.ins().icmp(IntCC::UnsignedLessThan, lhs, rhs);
+        self.bail_if(underflow);
+        self.builder.ins().isub(lhs, rhs)
+    }
+
+    fn unsigned_mul(&mut self, lhs: ClifValue, rhs: ClifValue) -> ClifValue {
+        let high = self.builder.ins().umulhi(lhs, rhs);
+        let overflow = self.builder.ins().icmp_imm(IntCC::NotEqual, high, 0);
+        self.bail_if(overflow);
+        self.builder.ins().imul(lhs, rhs)
+    }
+
+    fn unsigned_div(&mut self, lhs: ClifValue, rhs: ClifValue) -> ClifValue {
+        let divide_by_zero = self.builder.ins().icmp_imm(IntCC::Equal, rhs, 0);
+        self.bail_if(divide_by_zero);
+        self.builder.ins().udiv(lhs, rhs)
+    }
+
+    fn signed_add(&mut self, lhs: ClifValue, rhs: ClifValue) -> ClifValue {
+        // Overflow occurred if the result's sign differs from both operands'.
+        let result = self.builder.ins().iadd(lhs, rhs);
+        let lhs_changed = self.builder.ins().bxor(lhs, result);
+        let rhs_changed = self.builder.ins().bxor(rhs, result);
+        let both_changed = self.builder.ins().band(lhs_changed, rhs_changed);
+        let overflow = self
+            .builder
+            .ins()
+            .icmp_imm(IntCC::SignedLessThan, both_changed, 0);
+        self.bail_if(overflow);
+        result
+    }
+
+    fn signed_sub(&mut self, lhs: ClifValue, rhs: ClifValue) -> ClifValue {
+        // Overflow occurred if the operands' signs differ and the
+        // result's sign differs from `lhs`.
+        let result = self.builder.ins().isub(lhs, rhs);
+        let signs_differ = self.builder.ins().bxor(lhs, rhs);
+        let lhs_changed = self.builder.ins().bxor(lhs, result);
+        let both = self.builder.ins().band(signs_differ, lhs_changed);
+        let overflow = self.builder.ins().icmp_imm(IntCC::SignedLessThan, both, 0);
+        self.bail_if(overflow);
+        result
+    }
+
+    fn signed_mul(&mut self, lhs: ClifValue, rhs: ClifValue) -> ClifValue {
+        // Overflow occurred unless the high half of the full product is
+        // just the sign extension of the low half.
+        let high = self.builder.ins().smulhi(lhs, rhs);
+        let result = self.builder.ins().imul(lhs, rhs);
+        let sign = self.builder.ins().sshr_imm(result, 63);
+        let overflow = self.builder.ins().icmp(IntCC::NotEqual, high, sign);
+        self.bail_if(overflow);
+        result
+    }
+
+    fn signed_div(&mut self, lhs: ClifValue, rhs: ClifValue) -> ClifValue {
+        let divide_by_zero = self.builder.ins().icmp_imm(IntCC::Equal, rhs, 0);
+        self.bail_if(divide_by_zero);
+        let lhs_is_min = self.builder.ins().icmp_imm(IntCC::Equal, lhs, i64::MIN);
+        let rhs_is_minus_one = self.builder.ins().icmp_imm(IntCC::Equal, rhs, -1);
+        let overflow = self.builder.ins().band(lhs_is_min, rhs_is_minus_one);
+        self.bail_if(overflow);
+        self.builder.ins().sdiv(lhs, rhs)
+    }
+
+    /// Bails unless `value`, treated as unsigned, fits in an `i64`.
+    fn bail_if_above_i64_max(&mut self, value: ClifValue) {
+        let too_large = self.builder.ins().icmp_imm(IntCC::SignedLessThan, value, 0);
+        self.bail_if(too_large);
+    }
+
+    /// Jumps to the bail block if `condition` is true, and otherwise
+    /// continues in a new block.
+    fn bail_if(&mut self, condition: ClifValue) {
+        let next_block = self.builder.create_block();
+        self.builder.ins().brnz(condition, self.bail_block, &[]);
+        self.builder.ins().jump(next_block, &[]);
+        self.builder.switch_to_block(next_block);
+    }
+
+    fn constant(&mut self, value: i64) -> ClifValue {
+        self.builder.ins().iconst(types::I64, value)
+    }
+
+    fn read_place(
+        &mut self,
+        tables: &bir::Tables,
+        place: bir::Place,
+    ) -> Result<(ClifValue, Kind), Unsupported> {
+        let kind = kinds::place_kind(tables, &self.kinds.locals, place)?.ok_or(Unsupported)?;
+        let PlaceData::LocalVariable(local_variable) = place.data(tables) else {
+            return Err(Unsupported);
+        };
+        Ok((self.builder.use_var(variable(*local_variable)), kind))
+    }
+
+    fn assign(
+        &mut self,
+        tables: &bir::Tables,
+        target: bir::TargetPlace,
+        value: ClifValue,
+    ) -> Result<(), Unsupported> {
+        let TargetPlaceData::LocalVariable(local_variable) = target.data(tables) else {
+            return Err(Unsupported);
+        };
+        self.builder.def_var(variable(*local_variable), value);
+        Ok(())
+    }
+}
+
+fn variable(local_variable: bir::LocalVariable) -> Variable {
+    Variable::new(usize::from(local_variable))
+}
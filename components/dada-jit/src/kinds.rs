@@ -0,0 +1,247 @@
+//! Infers the kind of value stored in each local variable of a function,
+//! given the kinds of its arguments. Only functions whose locals always
+//! hold a single kind of scalar can be compiled.
+
+use dada_collections::Map;
+use dada_execute::jit::NativeValue;
+use dada_id::prelude::*;
+use dada_ir::code::{
+    bir::{self, ExprData, PlaceData, StatementData, TargetPlaceData, TerminatorData},
+    validated::op::Op,
+};
+
+/// The kinds of scalar values that compiled code can work with.
+/// At runtime, each is represented as a 64-bit integer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Kind {
+    Unit,
+    Bool,
+    Int,
+    UnsignedInt,
+    SignedInt,
+}
+
+impl Kind {
+    pub(crate) fn of(value: NativeValue) -> Self {
+        match value {
+            NativeValue::Unit => Kind::Unit,
+            NativeValue::Bool(_) => Kind::Bool,
+            NativeValue::Int(_) => Kind::Int,
+            NativeValue::UnsignedInt(_) => Kind::UnsignedInt,
+            NativeValue::SignedInt(_) => Kind::SignedInt,
+        }
+    }
+
+    /// The representation of `value` in compiled code.
+    pub(crate) fn bits(value: NativeValue) -> u64 {
+        match value {
+            NativeValue::Unit => 0,
+            NativeValue::Bool(b) => b as u64,
+            NativeValue::Int(i) | NativeValue::UnsignedInt(i) => i,
+            NativeValue::SignedInt(i) => i as u64,
+        }
+    }
+
+    /// The value of this kind represented by `bits` in compiled code.
+    pub(crate) fn native_value(self, bits: u64) -> NativeValue {
+        match self {
+            Kind::Unit => NativeValue::Unit,
+            Kind::Bool => NativeValue::Bool(bits != 0),
+            Kind::Int => NativeValue::Int(bits),
+            Kind::UnsignedInt => NativeValue::UnsignedInt(bits),
+            Kind::SignedInt => NativeValue::SignedInt(bits as i64),
+        }
+    }
+}
+
+/// Indicates that a function cannot be compiled.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Unsupported;
+
+pub(crate) struct Kinds {
+    pub(crate) locals: Map<bir::LocalVariable, Kind>,
+    pub(crate) result: Kind,
+}
+
+/// Computes the kind of each local variable in `bir_data` by propagating
+/// the kinds of the parameters until nothing changes.
+pub(crate) fn infer(bir_data: &bir::BirData, parameters: &[Kind]) -> Result<Kinds, Unsupported> {
+    let tables = &bir_data.tables;
+    let mut locals: Map<bir::LocalVariable, Kind> = bir_data
+        .parameters()
+        .zip(parameters.iter().copied())
+        .collect();
+    let mut result = None;
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for basic_block in bir_data.all_basic_blocks() {
+            let basic_block_data = basic_block.data(tables);
+            for &statement in &basic_block_data.statements {
+                match statement.data(tables) {
+                    StatementData::AssignExpr(target, expr) => {
+                        let target = target_local(tables, *target)?;
+                        if let Some(kind) = expr_kind(tables, &locals, *expr)? {
+                            changed |= assign(&mut locals, target, kind)?;
+                        }
+                    }
+                    StatementData::AssignPlace(target, place) => {
+                        let target = target_local(tables, *target)?;
+                        if let Some(kind) = place_kind(tables, &locals, *place)? {
+                            changed |= assign(&mut locals, target, kind)?;
+                        }
+                    }
+                    StatementData::Clear(_) => {}
+
+                    // Breakpoints have to be reported to the kernel.
+                    StatementData::BreakpointStart(..) | StatementData::BreakpointEnd(..) => {
+                        return Err(Unsupported)
+                    }
+                }
+            }
+
+            match basic_block_data.terminator.data(tables) {
+                TerminatorData::Goto(_)
+                | TerminatorData::StartAtomic(_)
+                | TerminatorData::EndAtomic(_)
+                | TerminatorData::If(..)
+                | TerminatorData::Error
+                | TerminatorData::Panic => {}
+                TerminatorData::Return(place) => {
+                    if let Some(kind) = place_kind(tables, &locals, *place)? {
+                        match result {
+                            None => result = Some(kind),
+                            Some(result) if result == kind => {}
+                            Some(_) => return Err(Unsupported),
+                        }
+                    }
+                }
+
+                // Calls and awaits are left to the interpreter.
+                TerminatorData::Assign(..) => return Err(Unsupported),
+            }
+        }
+    }
+
+    Ok(Kinds {
+        locals,
+        result: result.ok_or(Unsupported)?,
+    })
+}
+
+/// Records that `local` holds values of the given kind, returning true if
+/// that is new information. A local that holds different kinds of values
+/// at different times cannot be compiled.
+fn assign(
+    locals: &mut Map<bir::LocalVariable, Kind>,
+    local: bir::LocalVariable,
+    kind: Kind,
+) -> Result<bool, Unsupported> {
+    match locals.insert(local, kind) {
+        None => Ok(true),
+        Some(previous) if previous == kind => Ok(false),
+        Some(_) => Err(Unsupported),
+    }
+}
+
+fn target_local(
+    tables: &bir::Tables,
+    target: bir::TargetPlace,
+) -> Result<bir::LocalVariable, Unsupported> {
+    match target.data(tables) {
+        TargetPlaceData::LocalVariable(local) => Ok(*local),
+        TargetPlaceData::Dot(..) => Err(Unsupported),
+    }
+}
+
+/// Returns the kind of value stored in `place`, if it is known yet.
+pub(crate) fn place_kind(
+    tables: &bir::Tables,
+    locals: &Map<bir::LocalVariable, Kind>,
+    place: bir::Place,
+) -> Result<Option<Kind>, Unsupported> {
+    match place.data(tables) {
+        PlaceData::LocalVariable(local) => Ok(locals.get(local).copied()),
+        PlaceData::Dot(..)
+        | PlaceData::Class(_)
+        | PlaceData::Function(_)
+        | PlaceData::ExternFunction(_)
+        | PlaceData::Intrinsic(_)
+        | PlaceData::HostFunction(_) => Err(Unsupported),
+    }
+}
+
+/// Returns the kind of value produced by `expr`, if it is known yet.
+fn expr_kind(
+    tables: &bir::Tables,
+    locals: &Map<bir::LocalVariable, Kind>,
+    expr: bir::Expr,
+) -> Result<Option<Kind>, Unsupported> {
+    match expr.data(tables) {
+        ExprData::BooleanLiteral(_) => Ok(Some(Kind::Bool)),
+        ExprData::IntegerLiteral(_) => Ok(Some(Kind::Int)),
+        ExprData::UnsignedIntegerLiteral(_) => Ok(Some(Kind::UnsignedInt)),
+        ExprData::SignedIntegerLiteral(_) => Ok(Some(Kind::SignedInt)),
+        ExprData::Unit => Ok(Some(Kind::Unit)),
+
+        // Scalars are never mutated in place, so giving, sharing, or
+        // leasing them are all just copies.
+        ExprData::Reserve(place)
+        | ExprData::Share(place)
+        | ExprData::Lease(place)
+        | ExprData::Shlease(place)
+        | ExprData::Give(place) => place_kind(tables, locals, *place),
+
+        ExprData::Op(lhs, op, rhs) => {
+            match (
+                place_kind(tables, locals, *lhs)?,
+                place_kind(tables, locals, *rhs)?,
+            ) {
+                (Some(lhs), Some(rhs)) => Ok(Some(op_result(lhs, *op, rhs).ok_or(Unsupported)?)),
+                _ => Ok(None),
+            }
+        }
+        ExprData::Unary(op, rhs) => match place_kind(tables, locals, *rhs)? {
+            Some(rhs) => Ok(Some(unary_result(*op, rhs).ok_or(Unsupported)?)),
+            None => Ok(None),
+        },
+
+        ExprData::FloatLiteral(_)
+        | ExprData::StringLiteral(_)
+        | ExprData::Tuple(_)
+        | ExprData::Error => Err(Unsupported),
+    }
+}
+
+/// The kind of value that the interpreter produces when applying `op`
+/// to values of the given kinds, or `None` if it reports an error.
+pub(crate) fn op_result(lhs: Kind, op: Op, rhs: Kind) -> Option<Kind> {
+    let arithmetic = matches!(op, Op::Plus | Op::Minus | Op::Times | Op::DividedBy);
+    match (lhs, rhs) {
+        (Kind::Bool, Kind::Bool) => match op {
+            Op::EqualEqual | Op::GreaterEqual | Op::LessEqual => Some(Kind::Bool),
+            _ => None,
+        },
+        (Kind::Int, Kind::Int) if arithmetic => Some(Kind::Int),
+        (Kind::UnsignedInt | Kind::Int, Kind::UnsignedInt | Kind::Int) if arithmetic => {
+            Some(Kind::UnsignedInt)
+        }
+        (Kind::SignedInt | Kind::Int, Kind::SignedInt | Kind::Int) if arithmetic => {
+            Some(Kind::SignedInt)
+        }
+        (Kind::UnsignedInt | Kind::Int, Kind::UnsignedInt | Kind::Int)
+        | (Kind::SignedInt | Kind::Int, Kind::SignedInt | Kind::Int) => Some(Kind::Bool),
+        _ => None,
+    }
+}
+
+/// The kind of value that the interpreter produces when applying the
+/// unary operator `op` to a value of the given kind, or `None` if it
+/// reports an error.
+pub(crate) fn unary_result(op: Op, rhs: Kind) -> Option<Kind> {
+    match (op, rhs) {
+        (Op::Minus, Kind::SignedInt | Kind::Int) => Some(Kind::SignedInt),
+        _ => None,
+    }
+}
@@ -0,0 +1,173 @@
+//! An experimental just-in-time compiler that uses Cranelift to compile
+//! hot functions to native code. It plugs into the interpreter through
+//! the [`Jit`](dada_execute::jit::Jit) trait.
+//!
+//! Once a function has been called [`threshold`](CraneliftJit::threshold)
+//! times, the JIT tries to compile its BIR, specialized to the kinds of
+//! its arguments. Only functions that work purely with scalars (integers,
+//! booleans, and `()`) and make no calls can be compiled; the rest are
+//! left to the interpreter. Compiled code checks for overflow, division by
+//! zero, and the like just as the interpreter does, but rather than
+//! reporting the error it "bails", and the interpreter executes the call
+//! instead (thereby reporting the error).
+//!
+//! To check the compiled code against the interpreter, enable
+//! [`validate_jit`](dada_execute::config::ExecutionConfig::validate_jit).
+
+#![feature(let_else)]
+#![feature(trait_upcasting)]
+#![feature(try_blocks)]
+#![allow(incomplete_features)]
+
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_jit::{JITBuilder, JITModule};
+use dada_brew::prelude::*;
+use dada_collections::Map;
+use dada_execute::jit::{Jit, NativeValue};
+use dada_ir::function::Function;
+
+use crate::{
+    codegen::CompiledCode,
+    kinds::{Kind, Unsupported},
+};
+
+mod codegen;
+mod kinds;
+
+pub struct CraneliftJit {
+    /// See [`Self::threshold`].
+    threshold: u64,
+
+    /// Number of times each function has been called.
+    call_counts: Map<Function, u64>,
+
+    /// The result of compiling each function for arguments of the given
+    /// kinds; `None` if it could not be compiled.
+    compiled: Map<(Function, Vec<Kind>), Option<CompiledFunction>>,
+
+    /// Created when the first function is compiled; `None` if the
+    /// host is not supported by Cranelift.
+    module: Option<JITModule>,
+
+    /// True once we have tried to create `module`.
+    module_initialized: bool,
+}
+
+#[derive(Copy, Clone)]
+struct CompiledFunction {
+    code: CompiledCode,
+    result: Kind,
+}
+
+// Safety: `JITModule` is not `Send` or `Sync` because it holds raw pointers
+// to the memory containing the compiled code. That memory is owned by the
+// module and never accessed through a shared reference, so it is fine
+// for the JIT to move between threads.
+unsafe impl Send for CraneliftJit {}
+unsafe impl Sync for CraneliftJit {}
+
+impl Default for CraneliftJit {
+    fn default() -> Self {
+        Self {
+            threshold: 100,
+            call_counts: Map::default(),
+            compiled: Map::default(),
+            module: None,
+            module_initialized: false,
+        }
+    }
+}
+
+impl CraneliftJit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder method: functions are only compiled once they have been
+    /// called `threshold` times. The default is 100; a threshold of 0
+    /// compiles every function that can be compiled on its first call.
+    pub fn threshold(mut self, threshold: u64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    fn compile(
+        &mut self,
+        db: &dyn dada_execute::Db,
+        function: Function,
+        kinds: &[Kind],
+    ) -> Option<CompiledFunction> {
+        let module = self.module()?;
+        let bir = function.brew(db);
+        let bir_data = bir.data(db);
+        if bir_data.num_parameters() != kinds.len() {
+            return None;
+        }
+        let result: Result<_, Unsupported> = try {
+            let kinds = kinds::infer(bir_data, kinds)?;
+            let code = codegen::compile(module, bir_data, &kinds)?;
+            CompiledFunction {
+                code,
+                result: kinds.result,
+            }
+        };
+        tracing::debug!(
+            "compiled `{}`: {}",
+            function.name(db).as_str(db),
+            result.is_ok()
+        );
+        result.ok()
+    }
+
+    fn module(&mut self) -> Option<&mut JITModule> {
+        if !self.module_initialized {
+            self.module_initialized = true;
+            let mut flags = settings::builder();
+            flags.set("use_colocated_libcalls", "false").unwrap();
+            flags.set("is_pic", "false").unwrap();
+            if let Ok(isa_builder) = cranelift_native::builder() {
+                let isa = isa_builder.finish(settings::Flags::new(flags));
+                let builder = JITBuilder::with_isa(isa, cranelift_module::default_libcall_names());
+                self.module = Some(JITModule::new(builder));
+            }
+        }
+        self.module.as_mut()
+    }
+}
+
+impl Jit for CraneliftJit {
+    fn call(
+        &mut self,
+        db: &dyn dada_execute::Db,
+        function: Function,
+        arguments: &[NativeValue],
+    ) -> Option<NativeValue> {
+        let call_count = self.call_counts.entry(function).or_default();
+        *call_count += 1;
+        if *call_count <= self.threshold {
+            return None;
+        }
+
+        let kinds: Vec<Kind> = arguments.iter().map(|&a| Kind::of(a)).collect();
+        let key = (function, kinds);
+        let compiled = match self.compiled.get(&key) {
+            Some(compiled) => *compiled,
+            None => {
+                let compiled = self.compile(db, function, &key.1);
+                self.compiled.insert(key, compiled);
+                compiled
+            }
+        }?;
+
+        let arguments: Vec<u64> = arguments.iter().map(|&a| Kind::bits(a)).collect();
+        let mut result = 0;
+        // Safety: the code was compiled for arguments of these kinds,
+        // and only reads as many arguments as the function has parameters.
+        let status = unsafe { (compiled.code)(arguments.as_ptr(), &mut result) };
+        if status == codegen::STATUS_RETURNED {
+            Some(compiled.result.native_value(result))
+        } else {
+            None
+        }
+    }
+}
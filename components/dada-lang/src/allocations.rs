@@ -0,0 +1,24 @@
+//! How `dada bench` counts heap allocations. Counting needs a global
+//! allocator, which is the choice of the program rather than of this
+//! library, so the `dada` binary installs one and registers it here with
+//! [`count_allocations_with`]. Without one, `dada bench` reports only times.
+
+use std::sync::OnceLock;
+
+static COUNTER: OnceLock<fn() -> u64> = OnceLock::new();
+
+/// Makes `dada bench` count allocations with `counter`, which returns how
+/// many have been made so far. Only the first call has an effect.
+pub fn count_allocations_with(counter: fn() -> u64) {
+    let _ = COUNTER.set(counter);
+}
+
+/// True if something is counting allocations.
+pub(crate) fn counting() -> bool {
+    COUNTER.get().is_some()
+}
+
+/// The number of allocations made so far, if something is counting them.
+pub(crate) fn allocations() -> Option<u64> {
+    COUNTER.get().map(|counter| counter())
+}
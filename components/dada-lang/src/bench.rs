@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use dada_execute::kernel::BufferKernel;
+use dada_ir::filename::Filename;
+use eyre::Context;
+
+use crate::check::dada_files;
+
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// Paths to `.dada` files to time, or to directories to search for them
+    /// (defaults to the current directory)
+    paths: Vec<PathBuf>,
+
+    /// How many times to compile and run each program; the fastest time
+    /// for each phase is reported
+    #[structopt(long, default_value = "5")]
+    iterations: u32,
+}
+
+/// What each phase took for one program.
+#[derive(Default)]
+struct Phases {
+    lex: Measurement,
+    parse: Measurement,
+    validate: Measurement,
+    brew: Measurement,
+    execute: Measurement,
+}
+
+/// The time a phase took, and how many heap allocations it made.
+#[derive(Default, Clone, Copy)]
+struct Measurement {
+    time: Duration,
+    allocations: u64,
+}
+
+impl Measurement {
+    fn min(self, other: Measurement) -> Measurement {
+        Measurement {
+            time: self.time.min(other.time),
+            allocations: self.allocations.min(other.allocations),
+        }
+    }
+}
+
+impl Phases {
+    /// The lesser of `self` and `other`, phase by phase.
+    fn min(self, other: Phases) -> Phases {
+        Phases {
+            lex: self.lex.min(other.lex),
+            parse: self.parse.min(other.parse),
+            validate: self.validate.min(other.validate),
+            brew: self.brew.min(other.brew),
+            execute: self.execute.min(other.execute),
+        }
+    }
+
+    fn by_name(&self) -> [(&'static str, Measurement); 5] {
+        [
+            ("lex", self.lex),
+            ("parse", self.parse),
+            ("validate", self.validate),
+            ("brew", self.brew),
+            ("execute", self.execute),
+        ]
+    }
+
+    fn total(&self) -> Duration {
+        self.by_name().iter().map(|(_, m)| m.time).sum()
+    }
+
+    fn nanoseconds(&self) -> BTreeMap<&'static str, u128> {
+        self.by_name()
+            .into_iter()
+            .map(|(phase, m)| (phase, m.time.as_nanos()))
+            .collect()
+    }
+
+    fn allocations(&self) -> BTreeMap<&'static str, u64> {
+        self.by_name()
+            .into_iter()
+            .map(|(phase, m)| (phase, m.allocations))
+            .collect()
+    }
+}
+
+#[derive(serde::Serialize)]
+struct Benchmark {
+    path: PathBuf,
+
+    /// Nanoseconds per phase.
+    phases: BTreeMap<&'static str, u128>,
+
+    /// Heap allocations per phase, if they were counted (see
+    /// [`crate::count_allocations_with`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allocations: Option<BTreeMap<&'static str, u64>>,
+}
+
+/// Starts measuring a phase; [`Measuring::stop`] returns what it took.
+struct Measuring {
+    start: Instant,
+    allocations: u64,
+}
+
+impl Measuring {
+    fn start() -> Self {
+        Self {
+            start: Instant::now(),
+            allocations: crate::allocations::allocations().unwrap_or(0),
+        }
+    }
+
+    fn stop(self) -> Measurement {
+        Measurement {
+            time: self.start.elapsed(),
+            allocations: crate::allocations::allocations().unwrap_or(0) - self.allocations,
+        }
+    }
+}
+
+impl Options {
+    pub async fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        let mut benchmarks = vec![];
+        for path in dada_files(&self.paths)? {
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading `{}`", path.display()))?;
+            let mut fastest: Option<Phases> = None;
+            for _ in 0..self.iterations.max(1) {
+                let phases = time_phases(&path, &contents).await;
+                fastest = Some(match fastest {
+                    Some(fastest) => fastest.min(phases),
+                    None => phases,
+                });
+            }
+            let phases = fastest.unwrap();
+            tracing::info!("{}: {:?}", path.display(), phases.total());
+            benchmarks.push(Benchmark {
+                phases: phases.nanoseconds(),
+                allocations: crate::allocations::counting().then(|| phases.allocations()),
+                path,
+            });
+        }
+        println!("{}", serde_json::to_string_pretty(&benchmarks)?);
+        Ok(())
+    }
+}
+
+/// Compiles the program at `path` in a fresh database, one phase at a
+/// time, and then runs its `main` function (if it has one), measuring each.
+/// Each phase's queries only compute what the earlier phases haven't, so
+/// what a phase takes doesn't include the earlier ones.
+async fn time_phases(path: &Path, contents: &str) -> Phases {
+    let mut db = dada_db::Db::default();
+    let filename = Filename::from(&db, path);
+    db.update_file(filename, contents.to_string());
+    let mut phases = Phases::default();
+
+    let measuring = Measuring::start();
+    dada_lex::lex_file(&db, filename);
+    phases.lex = measuring.stop();
+
+    let measuring = Measuring::start();
+    let items = db.items(filename);
+    for &item in &items {
+        db.debug_syntax_tree(item);
+    }
+    phases.parse = measuring.stop();
+
+    let measuring = Measuring::start();
+    for &item in &items {
+        db.debug_validated_tree(item);
+    }
+    phases.validate = measuring.stop();
+
+    let measuring = Measuring::start();
+    for &item in &items {
+        db.debug_bir(item);
+    }
+    phases.brew = measuring.stop();
+
+    if let Some(main) = db.function_named(filename, "main") {
+        let measuring = Measuring::start();
+        BufferKernel::new()
+            .interpret_and_buffer(&db, main, vec![])
+            .await;
+        phases.execute = measuring.stop();
+    }
+
+    phases
+}
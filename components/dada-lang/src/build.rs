@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use dada_ir::diagnostic::Severity;
+use eyre::Context;
+
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// Path to `.dada` file to compile
+    path: PathBuf,
+
+    /// What to compile the program to (`wasm`, `wasi`, `js`, or `bytecode`)
+    #[structopt(long, default_value = "wasm")]
+    target: Target,
+
+    /// Where to write the output (defaults to the input path, with
+    /// an extension appropriate for the target)
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Target {
+    /// A WebAssembly module, in the text format.
+    Wasm,
+
+    /// A WebAssembly module, in the text format, that runs on its own
+    /// under a WASI runtime like wasmtime.
+    Wasi,
+
+    /// A JavaScript program, including the runtime it needs.
+    Js,
+
+    /// Brewed BIR that `dada run` can load without parsing the source.
+    Bytecode,
+}
+
+impl Target {
+    fn extension(self) -> &'static str {
+        match self {
+            Target::Wasm | Target::Wasi => "wat",
+            Target::Js => "js",
+            Target::Bytecode => "dadac",
+        }
+    }
+}
+
+impl std::str::FromStr for Target {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wasm" => Ok(Target::Wasm),
+            "wasi" => Ok(Target::Wasi),
+            "js" => Ok(Target::Js),
+            "bytecode" => Ok(Target::Bytecode),
+            _ => Err(eyre::eyre!(
+                "unknown target `{}` (expected `wasm`, `wasi`, `js`, or `bytecode`)",
+                s
+            )),
+        }
+    }
+}
+
+impl Options {
+    pub fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        let mut db = dada_db::Db::default();
+
+        let contents = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("reading `{}`", self.path.display()))?;
+        let filename = dada_ir::filename::Filename::from(&db, &self.path);
+        db.update_file(filename, contents);
+
+        let diagnostics = db.diagnostics(filename);
+        for diagnostic in &diagnostics {
+            dada_error_format::print_diagnostic(&db, diagnostic)?;
+        }
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            eyre::bail!("could not compile `{}`", self.path.display());
+        }
+
+        let output = match self.target {
+            Target::Wasm => {
+                dada_codegen_wasm::compile_module(&db, filename).map(String::into_bytes)
+            }
+            Target::Wasi => {
+                dada_codegen_wasm::compile_wasi_module(&db, filename).map(String::into_bytes)
+            }
+            Target::Js => dada_codegen_js::compile_module(&db, filename).map(String::into_bytes),
+            Target::Bytecode => Ok(db.bytecode(filename)),
+        };
+        let output = match output {
+            Ok(output) => output,
+            Err(diagnostics) => {
+                for diagnostic in &diagnostics {
+                    dada_error_format::print_diagnostic(&db, diagnostic)?;
+                }
+                eyre::bail!("could not compile `{}`", self.path.display());
+            }
+        };
+
+        let output_path = match &self.output {
+            Some(path) => path.clone(),
+            None => self.path.with_extension(self.target.extension()),
+        };
+        std::fs::write(&output_path, output)
+            .with_context(|| format!("writing `{}`", output_path.display()))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+//! The on-disk cache of `dada check --cache-dir`: the diagnostics of each
+//! file that was checked, so that the next `dada check` (in another run of
+//! `dada`) only compiles the files that changed since. A file's diagnostics
+//! are reused if its source text, and that of the files of the packages it
+//! depends on, are the same as when they were stored, and they were stored
+//! by this version of dada.
+
+use std::path::{Path, PathBuf};
+
+use dada_ir::{diagnostic::Diagnostic, filename::Filename};
+use eyre::Context;
+
+pub(crate) struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub(crate) fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// The stored diagnostics of `filename`, if they are up to date.
+    pub(crate) fn get(&self, db: &dada_db::Db, filename: Filename) -> Option<Vec<Diagnostic>> {
+        let bytes = std::fs::read(self.path(db, filename)).ok()?;
+        let (header, diagnostics) = dada_bytecode::decode_diagnostics(db, &bytes).ok()?;
+        if header.filename != filename.as_str(db) || header.source_hash != key(db, filename) {
+            return None;
+        }
+        Some(diagnostics)
+    }
+
+    /// Stores the diagnostics of `filename`.
+    pub(crate) fn put(
+        &self,
+        db: &dada_db::Db,
+        filename: Filename,
+        diagnostics: &[Diagnostic],
+    ) -> eyre::Result<()> {
+        let header = dada_bytecode::Header {
+            filename: filename.as_str(db).to_string(),
+            source_hash: key(db, filename),
+        };
+        let bytes = dada_bytecode::encode_diagnostics(db, &header, diagnostics);
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("creating `{}`", self.dir.display()))?;
+        let path = self.path(db, filename);
+        std::fs::write(&path, bytes).with_context(|| format!("writing `{}`", path.display()))
+    }
+
+    /// Each file is stored under a hash of its name.
+    fn path(&self, db: &dada_db::Db, filename: Filename) -> PathBuf {
+        let hash = dada_bytecode::source_hash(filename.as_str(db));
+        self.dir.join(format!("{:016x}.dadad", hash))
+    }
+}
+
+/// A hash of everything that the diagnostics of `filename` depend on: its
+/// source text and the files of the packages it depends on.
+fn key(db: &dada_db::Db, filename: Filename) -> u64 {
+    let mut text = db.file_source(filename).to_string();
+    for dependency in dada_ir::manifest::dependencies(db, filename) {
+        text.push('\0');
+        text.push_str(dependency.name.as_str(db));
+        for &filename in &dependency.filenames {
+            text.push('\0');
+            text.push_str(filename.as_str(db));
+            text.push('\0');
+            text.push_str(db.file_source(filename));
+        }
+    }
+    dada_bytecode::source_hash(&text)
+}
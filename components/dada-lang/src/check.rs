@@ -1,66 +1,197 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use dada_ir::diagnostic::{Diagnostic, Severity};
+use dada_ir::filename::Filename;
 use eyre::Context;
-use salsa::DebugWithDb;
+
+use crate::cache::Cache;
+use crate::package::Packages;
+use crate::watch::Watcher;
 
 #[derive(structopt::StructOpt)]
 pub struct Options {
-    /// Paths to `.dada` files to check
+    /// Paths to `.dada` files to check, or to directories to search for
+    /// them (defaults to the current directory). Nothing is executed; the
+    /// command fails if there are any errors.
     paths: Vec<PathBuf>,
 
-    /// Log the syntax tree
+    /// After checking, wait for a file to change and check again
+    #[structopt(long)]
+    watch: bool,
+
+    /// Show at most this many errors
     #[structopt(long)]
-    log_syntax_tree: bool,
+    error_limit: Option<usize>,
 
-    /// Log the validated tree
+    /// Number of threads to compile with (defaults to the number of CPUs)
     #[structopt(long)]
-    log_validated_tree: bool,
+    jobs: Option<usize>,
 
-    /// Log the BIR
+    /// Keep the diagnostics of each file in this directory, and only
+    /// compile the files that changed since they were stored
     #[structopt(long)]
-    log_bir: bool,
+    cache_dir: Option<PathBuf>,
 }
 
 impl Options {
-    pub fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+    pub async fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
         let mut db = dada_db::Db::default();
-        let mut all_diagnostics = vec![];
-        for path in &self.paths {
+        if !self.watch {
+            return self.check(&mut db);
+        }
+
+        let mut watcher = Watcher::new(self.paths.clone())?;
+        loop {
+            if let Err(err) = self.check(&mut db) {
+                eprintln!("Error: {:?}", err);
+            }
+            watcher.wait_for_change().await?;
+        }
+    }
+
+    /// Checks the files in `self.paths`, reusing what `db` has computed
+    /// for the files that have not changed.
+    fn check(&self, db: &mut dada_db::Db) -> eyre::Result<()> {
+        let paths = dada_files(&self.paths)?;
+        let mut packages = Packages::default();
+        let mut filenames = vec![];
+        for path in &paths {
             let contents = std::fs::read_to_string(path)
                 .with_context(|| format!("reading `{}`", path.display()))?;
-            let filename = dada_ir::filename::Filename::from(&db, path);
-            db.update_file(filename, contents);
-            all_diagnostics.extend(db.diagnostics(filename));
-
-            if self.log_syntax_tree {
-                for item in db.items(filename) {
-                    if let Some(tree) = db.debug_syntax_tree(item) {
-                        tracing::info!("syntax tree for {:?} is {:#?}", item.debug(&db), tree);
-                    }
-                }
-            }
+            let filename = dada_ir::filename::Filename::from(db, path);
+            crate::watch::update_file(db, filename, contents);
+            packages.load_dependencies(db, path, filename)?;
+            filenames.push(filename);
+        }
 
-            if self.log_validated_tree {
-                for item in db.items(filename) {
-                    if let Some(tree) = db.debug_validated_tree(item) {
-                        tracing::info!("validated tree for {:?} is {:#?}", item.debug(&db), tree);
-                    }
-                }
-            }
+        let cache = self.cache_dir.as_deref().map(Cache::new);
+        let cached: Vec<Option<Vec<Diagnostic>>> = filenames
+            .iter()
+            .map(|&filename| cache.as_ref()?.get(db, filename))
+            .collect();
+
+        let jobs = match self.jobs {
+            Some(jobs) => jobs,
+            None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        };
+        let uncached: Vec<Filename> = filenames
+            .iter()
+            .zip(&cached)
+            .filter(|(_, cached)| cached.is_none())
+            .map(|(&filename, _)| filename)
+            .collect();
+        db.compile_in_parallel(&uncached, jobs);
 
-            if self.log_bir {
-                for item in db.items(filename) {
-                    if let Some(tree) = db.debug_bir(item) {
-                        tracing::info!("BIR for {:?} is {:#?}", item.debug(&db), tree);
+        let mut all_diagnostics = vec![];
+        for (&filename, cached) in filenames.iter().zip(cached) {
+            let diagnostics = match cached {
+                Some(diagnostics) => diagnostics,
+                None => {
+                    let diagnostics = db.diagnostics(filename);
+                    if let Some(cache) = &cache {
+                        cache.put(db, filename, &diagnostics)?;
                     }
+                    diagnostics
                 }
-            }
+            };
+            all_diagnostics.extend(diagnostics);
         }
 
-        for diagnostic in all_diagnostics {
-            dada_error_format::print_diagnostic(&db, &diagnostic)?;
+        print_diagnostics(db, &all_diagnostics, self.error_limit)?;
+
+        let count = |severity| {
+            all_diagnostics
+                .iter()
+                .filter(|d| d.severity == severity)
+                .count()
+        };
+        let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+        eprintln!(
+            "checked {} file{}: {} error{}, {} warning{}",
+            paths.len(),
+            plural(paths.len()),
+            errors,
+            plural(errors),
+            warnings,
+            plural(warnings),
+        );
+        if let Some(code) = all_diagnostics.iter().find_map(|d| d.code) {
+            eprintln!(
+                "for more information about an error, try `dada explain {}`",
+                code
+            );
+        }
+        if errors > 0 {
+            eyre::bail!("checking failed");
         }
 
         Ok(())
     }
 }
+
+/// Prints `diagnostics`, or, if there are more than `error_limit` errors,
+/// all but the errors after the first `error_limit`, followed by how many
+/// errors were left out.
+pub(crate) fn print_diagnostics(
+    db: &dada_db::Db,
+    diagnostics: &[Diagnostic],
+    error_limit: Option<usize>,
+) -> eyre::Result<()> {
+    let mut errors = 0;
+    for diagnostic in diagnostics {
+        if diagnostic.severity == Severity::Error {
+            errors += 1;
+            if error_limit.is_some_and(|limit| errors > limit) {
+                continue;
+            }
+        }
+        dada_error_format::print_diagnostic(db, diagnostic)?;
+    }
+
+    if let Some(limit) = error_limit {
+        if errors > limit {
+            let hidden = errors - limit;
+            println!("{} additional error{} not shown", hidden, plural(hidden));
+        }
+    }
+    Ok(())
+}
+
+/// The `.dada` files named by `paths`, searching directories (while
+/// respecting `.gitignore`) for them. With no paths, searches the current
+/// directory.
+pub(crate) fn dada_files(paths: &[PathBuf]) -> eyre::Result<Vec<PathBuf>> {
+    let default_paths = [PathBuf::from(".")];
+    let roots = if paths.is_empty() {
+        &default_paths[..]
+    } else {
+        paths
+    };
+
+    let mut files = vec![];
+    for root in roots {
+        if !root.is_dir() {
+            files.push(root.clone());
+            continue;
+        }
+        for entry in ignore::Walk::new(root) {
+            let path = entry?.into_path();
+            if is_dada_file(&path) {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn is_dada_file(path: &Path) -> bool {
+    path.is_file() && path.extension() == Some("dada".as_ref())
+}
+
+pub(crate) fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
@@ -0,0 +1,404 @@
+//! `dada debug`: run a program under a command-line debugger.
+//!
+//! The program pauses before the first statement of `main`. From there it
+//! can be run to the next breakpoint (given with `--break`), or stepped a
+//! statement at a time, and its variables and heap inspected. Breakpoints
+//! use the same BIR statements that the heap-graph tests rely on: the kernel
+//! notes when one starts, and the debugger pauses after that step.
+//!
+//! Before each step, the state of the program is saved, and `back` goes
+//! back to it. Only the program's state goes back: what it printed stays
+//! printed, and anything else it did outside the interpreter stays done.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use dada_execute::{
+    config::ExecutionConfig,
+    debugger::{Debugger, StepOutcome},
+    heap_graph::HeapGraph,
+    machine::ProgramCounter,
+    snapshot::Snapshot,
+    trace_event::TraceRecorder,
+};
+use dada_ir::{
+    diagnostic::Severity,
+    filename::Filename,
+    span::{FileSpan, LineColumn},
+};
+use eyre::Context;
+
+use crate::package::Packages;
+
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// Path to `.dada` file to debug
+    path: PathBuf,
+
+    /// Pause when execution reaches the code at `file:line`; may be repeated
+    #[structopt(long = "break", parse(try_from_str = parse_breakpoint))]
+    breakpoints: Vec<(PathBuf, u32)>,
+
+    /// Write a Chrome trace (viewable in `about:tracing` or Perfetto) of
+    /// the function calls and awaits executed while debugging to the given path
+    #[structopt(long, parse(from_os_str))]
+    chrome_trace: Option<PathBuf>,
+
+    /// Arguments for the program, given after `--`
+    #[structopt(last = true)]
+    args: Vec<String>,
+}
+
+const HELP: &str = "\
+continue, c    run until the next breakpoint (or the end of the program)
+step, s        execute one statement, stopping inside any function it calls
+next, n        execute one statement, running any function it calls to the end
+back, b        go back to before the last `continue`, `step`, or `next`
+print, p [x]   show the variables of the current function, or just `x`
+               (which can be followed by fields, like `p.x`)
+heap           show the stack and the objects it refers to, as a Graphviz graph
+where, w       show the functions on the call stack
+help           show this message
+quit, q        exit (as does end of input)";
+
+impl Options {
+    pub async fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        let mut db = dada_db::Db::default();
+        let contents = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("reading `{}`", self.path.display()))?;
+        let filename = Filename::from(&db, &self.path);
+        db.update_file(filename, contents);
+        let mut packages = Packages::default();
+        packages.load_dependencies(&mut db, &self.path, filename)?;
+
+        let mut errors = false;
+        for filename in packages.filenames().chain(Some(filename)) {
+            for diagnostic in db.diagnostics(filename) {
+                dada_error_format::print_diagnostic(&db, &diagnostic)?;
+                errors |= diagnostic.severity == Severity::Error;
+            }
+        }
+        if errors {
+            eyre::bail!("cannot debug a program with errors");
+        }
+
+        let breakpoints = self.set_breakpoints(&mut db, &packages, filename)?;
+        let db: &dada_db::Db = &db;
+        let main = dada_execute::main_function(db, filename)?;
+        let arguments = dada_execute::main_arguments(db, main, &self.args);
+
+        let mut kernel = Kernel {
+            config: ExecutionConfig::new().args(self.args.clone()),
+            trace_recorder: self.chrome_trace.as_ref().map(|_| TraceRecorder::new()),
+            breakpoint_hit: None,
+        };
+        let mut session = Session {
+            db,
+            breakpoints,
+            debugger: Debugger::new(db, main, arguments)?,
+            history: vec![],
+            failed: false,
+        };
+        println!("Dada debugger; enter `help` for help");
+        session.print_location();
+
+        let stdin = std::io::stdin();
+        loop {
+            print!("(debug) ");
+            std::io::stdout().flush()?;
+
+            let mut line = String::new();
+            if stdin.read_line(&mut line)? == 0 {
+                break;
+            }
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (None, _) => {}
+                (Some("continue" | "c"), None) => {
+                    session.advance(&mut kernel, Motion::Continue).await
+                }
+                (Some("step" | "s"), None) => session.advance(&mut kernel, Motion::Step).await,
+                (Some("next" | "n"), None) => session.advance(&mut kernel, Motion::Next).await,
+                (Some("back" | "b"), None) => session.back(),
+                (Some("print" | "p"), expression) => session.print(expression),
+                (Some("heap"), None) => session.print_heap(),
+                (Some("where" | "w"), None) => session.print_frames(),
+                (Some("help"), None) => println!("{}", HELP),
+                (Some("quit" | "q"), None) => break,
+                _ => println!("unknown command `{}`; enter `help` for help", line.trim()),
+            }
+        }
+
+        if let (Some(recorder), Some(path)) = (&kernel.trace_recorder, &self.chrome_trace) {
+            std::fs::write(path, recorder.to_json())
+                .with_context(|| format!("writing `{}`", path.display()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the breakpoints given with `--break`, returning a description
+    /// of each: those for a file are in the order of their indices.
+    fn set_breakpoints(
+        &self,
+        db: &mut dada_db::Db,
+        packages: &Packages,
+        filename: Filename,
+    ) -> eyre::Result<Vec<(Filename, Vec<String>)>> {
+        let mut breakpoints: Vec<(Filename, Vec<LineColumn>, Vec<String>)> = vec![];
+        for (path, line) in &self.breakpoints {
+            let breakpoint_filename = Filename::from(&*db, path);
+            if !packages
+                .filenames()
+                .chain(Some(filename))
+                .any(|f| f == breakpoint_filename)
+            {
+                eyre::bail!("`{}` is not part of the program", path.display());
+            }
+
+            // Break on the first thing on the line.
+            let source = db.file_source(breakpoint_filename);
+            let Some(text) = source.lines().nth(*line as usize - 1) else {
+                eyre::bail!("`{}` has no line {}", path.display(), line);
+            };
+            let column = text.len() - text.trim_start().len();
+            let location = LineColumn::new0(*line - 1, column);
+            if dada_breakpoint::breakpoint::find(&*db, breakpoint_filename, location).is_none() {
+                eyre::bail!("there is no code at `{}:{}`", path.display(), line);
+            }
+
+            let description = format!("{}:{}", path.display(), line);
+            match breakpoints
+                .iter_mut()
+                .find(|(f, _, _)| *f == breakpoint_filename)
+            {
+                Some((_, locations, descriptions)) => {
+                    locations.push(location);
+                    descriptions.push(description);
+                }
+                None => breakpoints.push((breakpoint_filename, vec![location], vec![description])),
+            }
+        }
+
+        Ok(breakpoints
+            .into_iter()
+            .map(|(filename, locations, descriptions)| {
+                db.set_breakpoints(filename, locations);
+                (filename, descriptions)
+            })
+            .collect())
+    }
+}
+
+fn parse_breakpoint(s: &str) -> eyre::Result<(PathBuf, u32)> {
+    let Some((path, line)) = s.rsplit_once(':') else {
+        eyre::bail!(
+            "expected a breakpoint of the form `file:line`, found `{}`",
+            s
+        );
+    };
+    match line.parse::<u32>() {
+        Ok(line) if line > 0 => Ok((PathBuf::from(path), line)),
+        _ => eyre::bail!("expected a line number after `:`, found `{}`", line),
+    }
+}
+
+enum Motion {
+    Continue,
+    Step,
+    Next,
+}
+
+struct Session<'db> {
+    db: &'db dada_db::Db,
+
+    /// The description of each breakpoint, by file and index.
+    breakpoints: Vec<(Filename, Vec<String>)>,
+
+    debugger: Debugger,
+
+    /// The state of the program before each `continue`, `step`, or `next`
+    /// so far, for going back.
+    history: Vec<Snapshot>,
+
+    /// True if the program stopped with an error in the middle of a
+    /// statement; it can only go back from there.
+    failed: bool,
+}
+
+impl Session<'_> {
+    async fn advance(&mut self, kernel: &mut Kernel, motion: Motion) {
+        if self.failed {
+            println!("the program stopped with an error; enter `back` to go back");
+            return;
+        }
+        let Some(snapshot) = self.debugger.snapshot() else {
+            println!("the program has completed; enter `back` to go back");
+            return;
+        };
+        self.history.push(snapshot);
+
+        kernel.breakpoint_hit = None;
+        let result = match motion {
+            Motion::Step => self.debugger.step(self.db, kernel).await,
+            Motion::Next => self.debugger.step_over(self.db, kernel).await,
+            Motion::Continue => loop {
+                let result = self.debugger.step(self.db, kernel).await;
+                if !matches!(result, Ok(StepOutcome::Paused(_))) || kernel.breakpoint_hit.is_some()
+                {
+                    break result;
+                }
+            },
+        };
+
+        match result {
+            Ok(StepOutcome::Paused(_)) => {
+                if let Some((filename, index)) = kernel.breakpoint_hit {
+                    println!("reached breakpoint at {}", self.breakpoint(filename, index));
+                }
+                self.print_location();
+            }
+            Ok(StepOutcome::Completed) => println!("the program has completed"),
+            Err(err) => {
+                self.failed = true;
+                println!("{}", err);
+            }
+        }
+    }
+
+    fn back(&mut self) {
+        match self.history.pop() {
+            Some(snapshot) => {
+                self.debugger = Debugger::from_snapshot(snapshot);
+                self.failed = false;
+                self.print_location();
+            }
+            None => println!("already at the start of the program"),
+        }
+    }
+
+    /// Prints the variables of the current function, or the value of
+    /// `expression` in it.
+    fn print(&self, expression: Option<&str>) {
+        match expression {
+            Some(expression) => match self.debugger.evaluate(self.db, 0, expression) {
+                Ok(value) => println!("{}", value),
+                Err(err) => println!("{}", err),
+            },
+            None => match self.debugger.locals(self.db, 0) {
+                Some(locals) if locals.is_empty() => println!("no variables"),
+                Some(locals) => {
+                    for local in locals {
+                        println!("{} = {}", local.name, local.value);
+                    }
+                }
+                None => println!("the program has completed"),
+            },
+        }
+    }
+
+    fn print_heap(&self) {
+        match self.debugger.heap_graph(self.db) {
+            Some(heap_graph) => print!("{}", heap_graph.graphviz_alone(self.db, false, None)),
+            None => println!("the program has completed"),
+        }
+    }
+
+    fn print_frames(&self) {
+        let frames = self.debugger.frames(self.db);
+        if frames.is_empty() {
+            println!("the program has completed");
+        }
+        for (index, frame) in frames.iter().enumerate() {
+            println!(
+                "{}: {} at {}",
+                index,
+                frame.function.name(self.db).as_str(self.db),
+                self.location(frame.span)
+            );
+        }
+    }
+
+    /// Prints where the program is paused, with the line of code there.
+    fn print_location(&self) {
+        let Some(frame) = self.debugger.frames(self.db).into_iter().next() else {
+            println!("the program has completed");
+            return;
+        };
+        let (_, start, _) = self.db.line_columns(frame.span);
+        let source = self.db.file_source(frame.span.filename);
+        let text = source.lines().nth(start.line0_usize()).unwrap_or_default();
+        println!("paused at {}", self.location(frame.span));
+        println!("{:>5} | {}", start.line1(), text);
+    }
+
+    fn location(&self, span: FileSpan) -> String {
+        let (filename, start, _) = self.db.line_columns(span);
+        format!(
+            "{}:{}:{}",
+            filename.as_str(self.db),
+            start.line1(),
+            start.column1()
+        )
+    }
+
+    fn breakpoint(&self, filename: Filename, index: usize) -> &str {
+        self.breakpoints
+            .iter()
+            .find(|(f, _)| *f == filename)
+            .and_then(|(_, descriptions)| descriptions.get(index))
+            .map_or("an unknown location", |d| d.as_str())
+    }
+}
+
+struct Kernel {
+    /// Configuration for the interpreter.
+    config: ExecutionConfig,
+
+    /// If `Some`, records function and await events for the Chrome trace.
+    trace_recorder: Option<TraceRecorder>,
+
+    /// The breakpoint (by file and index) whose start was reached since
+    /// this was last cleared, if any.
+    breakpoint_hit: Option<(Filename, usize)>,
+}
+
+#[async_trait::async_trait]
+impl dada_execute::kernel::Kernel for Kernel {
+    async fn print(&mut self, _await_pc: ProgramCounter, text: &str) -> eyre::Result<()> {
+        let mut stdout = std::io::stdout();
+        stdout.write_all(text.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn breakpoint_start(
+        &mut self,
+        _db: &dyn dada_execute::Db,
+        breakpoint_filename: Filename,
+        breakpoint_index: usize,
+        _generate_heap_graph: &mut dyn FnMut() -> HeapGraph,
+    ) -> eyre::Result<()> {
+        self.breakpoint_hit = Some((breakpoint_filename, breakpoint_index));
+        Ok(())
+    }
+
+    fn breakpoint_end(
+        &mut self,
+        _db: &dyn dada_execute::Db,
+        _breakpoint_filename: Filename,
+        _breakpoint_index: usize,
+        _breakpoint_span: FileSpan,
+        _generate_heap_graph: &mut dyn FnMut() -> HeapGraph,
+    ) -> eyre::Result<()> {
+        Ok(())
+    }
+
+    fn execution_config(&self) -> ExecutionConfig {
+        self.config.clone()
+    }
+
+    fn trace_recorder(&mut self) -> Option<&mut TraceRecorder> {
+        self.trace_recorder.as_mut()
+    }
+}
@@ -0,0 +1,168 @@
+//! `dada doc`: Markdown documentation for the items of some `.dada` files,
+//! from the comments just before each item. There is a page for each
+//! file, mirroring the source tree, and an `index.md` listing them.
+//!
+//! A name in backticks in a comment, like `` `Point` ``, links to the item
+//! with that name: the one in the same file, if there is one, or else one
+//! in another file being documented.
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use dada_ir::filename::Filename;
+use eyre::Context;
+use regex::{Captures, Regex};
+
+use crate::check::dada_files;
+
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// Paths to `.dada` files to document, or to directories to search for
+    /// them (defaults to the current directory)
+    paths: Vec<PathBuf>,
+
+    /// Directory to write the documentation to
+    #[structopt(long, parse(from_os_str), default_value = "target/doc")]
+    output: PathBuf,
+}
+
+/// A file being documented.
+struct Page {
+    filename: Filename,
+
+    /// The path of the file's page, relative to the output directory.
+    page: PathBuf,
+}
+
+impl Options {
+    pub fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        let mut db = dada_db::Db::default();
+        let mut pages = vec![];
+        for path in dada_files(&self.paths)? {
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading `{}`", path.display()))?;
+            let filename = Filename::from(&db, &path);
+            db.update_file(filename, contents);
+            pages.push(Page {
+                filename,
+                page: page_path(&path),
+            });
+        }
+
+        // Where each item name links to, when there is no item of that
+        // name in the same file.
+        let mut pages_by_item: BTreeMap<String, &Path> = BTreeMap::new();
+        for page in &pages {
+            for item in db.items(page.filename) {
+                let name = item.name(&db).as_str(&db).to_string();
+                pages_by_item.entry(name).or_insert(&page.page);
+            }
+        }
+
+        std::fs::create_dir_all(&self.output)
+            .with_context(|| format!("creating `{}`", self.output.display()))?;
+        for page in &pages {
+            let path = self.output.join(&page.page);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("creating `{}`", parent.display()))?;
+            }
+            let markdown = file_page(&db, page, &pages_by_item);
+            std::fs::write(&path, markdown)
+                .with_context(|| format!("writing `{}`", path.display()))?;
+        }
+
+        let index = self.output.join("index.md");
+        std::fs::write(&index, index_page(&db, &pages))
+            .with_context(|| format!("writing `{}`", index.display()))?;
+        eprintln!(
+            "documented {} file{} in `{}`",
+            pages.len(),
+            crate::check::plural(pages.len()),
+            self.output.display()
+        );
+
+        Ok(())
+    }
+}
+
+/// The page for the source file at `path`: the same path, relative to the
+/// output directory, with a `.md` extension.
+fn page_path(path: &Path) -> PathBuf {
+    let relative: PathBuf = path
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect();
+    relative.with_extension("md")
+}
+
+fn file_page(db: &dada_db::Db, page: &Page, pages_by_item: &BTreeMap<String, &Path>) -> String {
+    let items = db.items(page.filename);
+
+    // Links from this page go up to the output directory first.
+    let up = "../".repeat(page.page.components().count() - 1);
+    let link = |name: &str| -> Option<String> {
+        if items.iter().any(|item| item.name(db).as_str(db) == name) {
+            Some(format!("#{}", name))
+        } else {
+            let page = pages_by_item.get(name)?;
+            Some(format!("{}{}#{}", up, page.display(), name))
+        }
+    };
+
+    let mut markdown = format!("# `{}`\n", page.filename.as_str(db));
+    for &item in &items {
+        let name = item.name(db).as_str(db);
+        markdown.push_str(&format!("\n<a id=\"{}\"></a>\n\n", name));
+        markdown.push_str(&format!("## {} `{}`\n\n", item.kind_str(), name));
+        markdown.push_str(&format!(
+            "```dada\n{}\n```\n",
+            dada_lsp::item_signature(db, item)
+        ));
+
+        let span = item.span(db);
+        let documentation = dada_lex::trivia::documentation(db, span.filename, span.start);
+        if !documentation.is_empty() {
+            markdown.push('\n');
+            markdown.push_str(&link_names(&documentation, &link));
+        }
+    }
+    markdown
+}
+
+/// Replaces each name in backticks in `documentation` that `link` gives a
+/// target for with a link to it.
+fn link_names(documentation: &str, link: impl Fn(&str) -> Option<String>) -> String {
+    let name = Regex::new(r"`([A-Za-z_][A-Za-z0-9_]*)`").unwrap();
+    name.replace_all(documentation, |captures: &Captures<'_>| {
+        match link(&captures[1]) {
+            Some(target) => format!("[{}]({})", &captures[0], target),
+            None => captures[0].to_string(),
+        }
+    })
+    .into_owned()
+}
+
+/// The index: a list of the files, each with a list of its items.
+fn index_page(db: &dada_db::Db, pages: &[Page]) -> String {
+    let mut markdown = String::from("# Documentation\n\n");
+    for page in pages {
+        let page_link = page.page.display();
+        markdown.push_str(&format!(
+            "- [`{}`]({})\n",
+            page.filename.as_str(db),
+            page_link
+        ));
+        for item in db.items(page.filename) {
+            let name = item.name(db).as_str(db);
+            markdown.push_str(&format!(
+                "  - {} [`{}`]({}#{})\n",
+                item.kind_str(),
+                name,
+                page_link,
+                name
+            ));
+        }
+    }
+    markdown
+}
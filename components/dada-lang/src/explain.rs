@@ -0,0 +1,26 @@
+use dada_ir::error_code::ErrorCode;
+
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// The code of the error to explain, like `E0010`
+    #[structopt(parse(try_from_str = parse_code))]
+    code: ErrorCode,
+}
+
+impl Options {
+    pub fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        print!("{}", self.code.explanation());
+        Ok(())
+    }
+}
+
+fn parse_code(code: &str) -> eyre::Result<ErrorCode> {
+    ErrorCode::from_name(code).ok_or_else(|| {
+        eyre::eyre!(
+            "unknown error code `{}` (error codes run from `{}` to `{}`)",
+            code,
+            ErrorCode::ALL[0],
+            ErrorCode::ALL[ErrorCode::ALL.len() - 1]
+        )
+    })
+}
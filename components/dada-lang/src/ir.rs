@@ -0,0 +1,148 @@
+use std::path::PathBuf;
+
+use dada_ir::{
+    filename::Filename, item::Item, span::FileSpan, token::Token, token_tree::TokenTree,
+};
+use eyre::Context;
+use salsa::DebugWithDb;
+
+use crate::package::Packages;
+
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// Path to `.dada` file to dump
+    path: PathBuf,
+
+    /// What to dump (`tokens`, `syntax`, `validated`, or `bir`)
+    #[structopt(long, default_value = "bir")]
+    stage: Stage,
+
+    /// Only dump the function (or class) with this name
+    #[structopt(long = "fn")]
+    name: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Stage {
+    /// The tokens from the lexer (without whitespace).
+    Tokens,
+
+    /// The syntax tree from the parser.
+    Syntax,
+
+    /// The validated tree, with names resolved.
+    Validated,
+
+    /// The BIR that is interpreted and compiled.
+    Bir,
+}
+
+impl std::str::FromStr for Stage {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tokens" => Ok(Stage::Tokens),
+            "syntax" => Ok(Stage::Syntax),
+            "validated" => Ok(Stage::Validated),
+            "bir" => Ok(Stage::Bir),
+            _ => Err(eyre::eyre!(
+                "unknown stage `{}` (expected `tokens`, `syntax`, `validated`, or `bir`)",
+                s
+            )),
+        }
+    }
+}
+
+impl Options {
+    pub fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        let mut db = dada_db::Db::default();
+
+        let contents = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("reading `{}`", self.path.display()))?;
+        let filename = Filename::from(&db, &self.path);
+        db.update_file(filename, contents);
+        Packages::default().load_dependencies(&mut db, &self.path, filename)?;
+
+        for diagnostic in db.diagnostics(filename) {
+            dada_error_format::print_diagnostic(&db, &diagnostic)?;
+        }
+
+        let items: Vec<Item> = db
+            .items(filename)
+            .into_iter()
+            .filter(|item| match &self.name {
+                Some(name) => item.name(&db).as_str(&db) == name,
+                None => true,
+            })
+            .collect();
+        if let (Some(name), true) = (&self.name, items.is_empty()) {
+            eyre::bail!(
+                "could not find a function or class named `{}` in `{}`",
+                name,
+                self.path.display()
+            );
+        }
+
+        // Without `--fn`, the tokens of the whole file are dumped, including
+        // those outside any item.
+        if self.stage == Stage::Tokens && self.name.is_none() {
+            print_tokens(&db, dada_lex::lex_file(&db, filename), None, 0);
+            return Ok(());
+        }
+
+        for item in items {
+            println!("# {} `{}`", item.kind_str(), item.name(&db).as_str(&db));
+            match self.stage {
+                Stage::Tokens => {
+                    let tokens = dada_lex::lex_file(&db, filename);
+                    print_tokens(&db, tokens, Some(item.span(&db)), 0);
+                }
+                Stage::Syntax => print_tree(db.debug_syntax_tree(item)),
+                Stage::Validated => print_tree(db.debug_validated_tree(item)),
+                Stage::Bir => print_tree(db.debug_bir(item)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Prints `tree`, if the item has one at this stage (classes, for example,
+/// have no BIR).
+fn print_tree(tree: Option<impl std::fmt::Debug>) {
+    match tree {
+        Some(tree) => println!("{:#?}", tree),
+        None => println!("(none)"),
+    }
+}
+
+/// Prints each token in `tree` (or the part of it within `within`), one
+/// per line with its position, indenting the contents of delimiters.
+fn print_tokens(db: &dada_db::Db, tree: TokenTree, within: Option<FileSpan>, depth: usize) {
+    let anchor = tree.anchor(db);
+    for (span, token) in tree.spanned_tokens(db) {
+        let span = anchor.file_span(db, span);
+        if let Some(within) = within {
+            if span.end <= within.start || span.start >= within.end {
+                continue;
+            }
+        }
+
+        match token {
+            Token::Whitespace(_) => {}
+            Token::Tree(tree) => print_tokens(db, tree, within, depth + 1),
+            _ => {
+                let (_, start, _) = db.line_columns(span);
+                println!(
+                    "{:indent$}{}:{}: {:?}",
+                    "",
+                    start.line1(),
+                    start.column1(),
+                    token.debug(db),
+                    indent = depth * 2
+                );
+            }
+        }
+    }
+}
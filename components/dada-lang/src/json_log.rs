@@ -0,0 +1,153 @@
+//! The `--log-json` output: one JSON object per line on stderr for each log
+//! event, and one for each span when it closes, with the time spent in it.
+//! With `--log-filter dada::phase=info`, that is how long each compiler
+//! phase took for each file and item (see [`dada_ir::phase`]).
+
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Map, Value};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+pub(crate) struct JsonLayer;
+
+/// The fields recorded for a span or event.
+#[derive(Default)]
+struct Fields(Map<String, Value>);
+
+impl Visit for Fields {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}").into());
+    }
+}
+
+/// The time spent inside a span (not counting time it was idle, such as an
+/// async function that was waiting).
+#[derive(Default)]
+struct Busy {
+    total: Duration,
+    entered: Option<Instant>,
+}
+
+impl<S> Layer<S> for JsonLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("new span not in registry");
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        let mut extensions = span.extensions_mut();
+        extensions.insert(fields);
+        extensions.insert(Busy::default());
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("recorded span not in registry");
+        if let Some(fields) = span.extensions_mut().get_mut::<Fields>() {
+            values.record(fields);
+        }
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("entered span not in registry");
+        if let Some(busy) = span.extensions_mut().get_mut::<Busy>() {
+            busy.entered = Some(Instant::now());
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("exited span not in registry");
+        if let Some(busy) = span.extensions_mut().get_mut::<Busy>() {
+            if let Some(entered) = busy.entered.take() {
+                busy.total += entered.elapsed();
+            }
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(&id).expect("closed span not in registry");
+        let metadata = span.metadata();
+        let extensions = span.extensions();
+        let busy_ns = extensions
+            .get::<Busy>()
+            .map_or(0, |busy| busy.total.as_nanos() as u64);
+        let spans: Vec<Value> = span
+            .scope()
+            .skip(1)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .map(|parent| span_json(&parent))
+            .collect();
+        write_line(json!({
+            "kind": "span",
+            "level": metadata.level().as_str(),
+            "target": metadata.target(),
+            "name": metadata.name(),
+            "fields": fields_json(extensions.get::<Fields>()),
+            "busy_ns": busy_ns,
+            "spans": spans,
+        }));
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let spans: Vec<Value> = match ctx.event_scope(event) {
+            Some(scope) => scope.from_root().map(|span| span_json(&span)).collect(),
+            None => vec![],
+        };
+        write_line(json!({
+            "kind": "event",
+            "level": metadata.level().as_str(),
+            "target": metadata.target(),
+            "fields": Value::Object(fields.0),
+            "spans": spans,
+        }));
+    }
+}
+
+fn span_json<S>(span: &tracing_subscriber::registry::SpanRef<'_, S>) -> Value
+where
+    S: for<'a> LookupSpan<'a>,
+{
+    json!({
+        "name": span.name(),
+        "fields": fields_json(span.extensions().get::<Fields>()),
+    })
+}
+
+fn fields_json(fields: Option<&Fields>) -> Value {
+    Value::Object(fields.map(|f| f.0.clone()).unwrap_or_default())
+}
+
+fn write_line(value: Value) {
+    let mut stderr = std::io::stderr().lock();
+    // Logging must not fail the program, so errors writing are ignored.
+    let _ = writeln!(stderr, "{value}");
+}
@@ -2,23 +2,68 @@
 #![feature(try_blocks)]
 #![allow(incomplete_features)]
 
+use std::io::IsTerminal;
+
 use structopt::StructOpt;
 use tracing_subscriber::prelude::*;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::{filter::filter_fn, EnvFilter};
 
+mod allocations;
+mod bench;
+mod build;
+mod cache;
 mod check;
+mod debug;
+mod doc;
+mod explain;
 mod ide;
+mod ir;
+mod json_log;
+mod lint;
+mod package;
+mod repl;
 mod run;
+mod test_fns;
 mod test_harness;
+mod timings;
+mod watch;
+
+pub use allocations::count_allocations_with;
 
 const DEFAULT_LOG: &str = "warn,dada_lang=info";
 
 #[derive(StructOpt)]
 pub struct Options {
-    /// The logging configuration
-    #[structopt(long, default_value = DEFAULT_LOG)]
+    /// The logging configuration: which targets to log, at which levels,
+    /// like `warn,dada::phase=info` (also accepted as `--log-filter`)
+    #[structopt(long, alias = "log-filter", default_value = DEFAULT_LOG)]
     log: String,
 
+    /// Write logs to stderr as JSON, one object per line, including how long
+    /// each span took
+    #[structopt(long)]
+    log_json: bool,
+
+    /// Report how long each phase of the compiler took and how many times it
+    /// ran, and which items were the slowest to compile, to stderr when the
+    /// command is done: as a table, or with `--timings=json`, as JSON
+    #[structopt(long, require_equals = true, possible_values = &["table", "json"])]
+    timings: Option<Option<String>>,
+
+    /// When to color diagnostics: `auto` (only when printing to a
+    /// terminal), `always`, or `never`
+    #[structopt(long, default_value = "auto", possible_values = &["auto", "always", "never"])]
+    color: String,
+
+    /// The characters to draw diagnostics with: `unicode`, or `ascii` for
+    /// logs and terminals that can't show Unicode
+    #[structopt(long, default_value = "unicode", possible_values = &["unicode", "ascii"])]
+    charset: String,
+
+    /// The language to show error messages in, like `fr`
+    #[structopt(long, default_value = dada_ir::messages::SOURCE_LOCALE)]
+    locale: String,
+
     #[structopt(subcommand)] // Note that we mark a field as a subcommand
     cmd: Command,
 }
@@ -28,23 +73,41 @@ impl Options {
     pub fn test_harness() -> Self {
         Options {
             log: DEFAULT_LOG.to_string(),
+            log_json: false,
+            timings: None,
+            color: "auto".to_string(),
+            charset: "unicode".to_string(),
+            locale: dada_ir::messages::SOURCE_LOCALE.to_string(),
             cmd: Command::Test(test_harness::Options::from_args()),
         }
     }
 
     pub async fn main(&self) -> eyre::Result<()> {
         // Configure logging:
+        let log = std::env::var("DADA_LOG").unwrap_or_else(|_| self.log.clone());
+        let timings = timings::Timings::default();
+        // The phase spans may only be enabled for `--timings`, in which case
+        // they are left out of the logs.
+        let log_phases = self.timings.is_none() || log.contains(dada_ir::phase::TARGET);
+        let log_filter =
+            filter_fn(move |metadata| log_phases || metadata.target() != dada_ir::phase::TARGET);
         let subscriber = tracing_subscriber::Registry::default()
             .with({
                 // Configure which modules/level/etc using `DADA_LOG`
                 // environment variable if present,
                 // else the `--log` parameter.
-                match std::env::var("DADA_LOG") {
-                    Ok(env) => EnvFilter::new(env),
-                    Err(_) => EnvFilter::new(&self.log),
+                let filter = EnvFilter::new(&log);
+                if self.timings.is_some() {
+                    filter.add_directive(format!("{}=info", dada_ir::phase::TARGET).parse()?)
+                } else {
+                    filter
                 }
             })
-            .with({
+            .with(
+                self.log_json
+                    .then(|| json_log::JsonLayer.with_filter(log_filter.clone())),
+            )
+            .with((!self.log_json).then(|| {
                 // Configure the hierarchical display.
                 tracing_tree::HierarchicalLayer::default()
                     .with_writer(std::io::stderr)
@@ -52,17 +115,59 @@ impl Options {
                     .with_ansi(true)
                     .with_targets(true)
                     .with_indent_amount(2)
-            });
+                    .with_filter(log_filter.clone())
+            }))
+            .with(self.timings.is_some().then(|| {
+                timings.layer().with_filter(filter_fn(|metadata| {
+                    metadata.target() == dada_ir::phase::TARGET
+                }))
+            }));
         tracing::subscriber::set_global_default(subscriber).unwrap();
         tracing_log::LogTracer::init()?;
 
+        let with_color = match &self.color[..] {
+            "always" => true,
+            "never" => false,
+            _ => std::io::stdout().is_terminal(),
+        };
+        let catalog = dada_ir::messages::catalog(&self.locale);
+        if catalog.is_none() && self.locale != dada_ir::messages::SOURCE_LOCALE {
+            eyre::bail!(
+                "no messages for the locale `{}`; the locales are {}",
+                self.locale,
+                dada_ir::messages::locales()
+                    .map(|locale| format!("`{}`", locale))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        dada_error_format::set_default_options(
+            dada_error_format::FormatOptions::default()
+                .with_color(with_color)
+                .ascii(self.charset == "ascii")
+                .catalog(catalog),
+        );
+
         match &self.cmd {
             Command::Ide(command_options) => {
                 ide::main(self, command_options)?;
             }
-            Command::Check(command_options) => command_options.main(self)?,
+            Command::Check(command_options) => command_options.main(self).await?,
             Command::Test(command_options) => command_options.main(self).await?,
+            Command::TestFns(command_options) => command_options.main(self)?,
             Command::Run(command_options) => command_options.main(self).await?,
+            Command::Repl(command_options) => command_options.main(self).await?,
+            Command::Debug(command_options) => command_options.main(self).await?,
+            Command::Build(command_options) => command_options.main(self)?,
+            Command::Ir(command_options) => command_options.main(self)?,
+            Command::Doc(command_options) => command_options.main(self)?,
+            Command::Lint(command_options) => command_options.main(self)?,
+            Command::Explain(command_options) => command_options.main(self)?,
+            Command::Bench(command_options) => command_options.main(self).await?,
+        }
+
+        if let Some(format) = &self.timings {
+            timings.report(format.as_deref().unwrap_or("table"));
         }
         Ok(())
     }
@@ -72,10 +177,29 @@ impl Options {
 pub enum Command {
     /// Run an LSP server
     Ide(ide::Options),
-    /// Run the compiler and log diagnostics
+    /// Check programs for errors without running them
     Check(check::Options),
     /// Run the test suite
     Test(test_harness::Options),
+    /// Run the `test_` functions in programs
+    TestFns(test_fns::Options),
     /// Run the interpreter
     Run(run::Options),
+    /// Enter statements and items interactively
+    Repl(repl::Options),
+    /// Run a program under a debugger, pausing at breakpoints
+    Debug(debug::Options),
+    /// Compile a program ahead of time (e.g., to WebAssembly)
+    Build(build::Options),
+    /// Dump the tokens, syntax tree, validated tree, or BIR of a program
+    Ir(ir::Options),
+    /// Generate documentation from the comments on items
+    Doc(doc::Options),
+    /// Check programs for code that is probably not what was meant
+    Lint(lint::Options),
+    /// Explain an error code, with examples
+    Explain(explain::Options),
+    /// Time each phase of compiling and running programs, printing the
+    /// times as JSON
+    Bench(bench::Options),
 }
@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+
+use dada_ir::{
+    diagnostic::Severity,
+    filename::Filename,
+    lint::{Lint, LintLevel},
+};
+use eyre::Context;
+
+use crate::check::{dada_files, plural, print_diagnostics};
+use crate::package::Packages;
+
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// Paths to `.dada` files to lint, or to directories to search for
+    /// them (defaults to the current directory). The command fails if
+    /// there are any errors, including from lints set to `deny`.
+    paths: Vec<PathBuf>,
+
+    /// Don't report what the given lint finds (or, with `warnings`,
+    /// any warnings); may be repeated
+    #[structopt(long, parse(try_from_str = parse_lint_target))]
+    allow: Vec<LintTarget>,
+
+    /// Report what the given lint finds as a warning; may be repeated
+    #[structopt(long, parse(try_from_str = parse_lint_target))]
+    warn: Vec<LintTarget>,
+
+    /// Report what the given lint finds (or, with `warnings`, anything
+    /// that would be a warning) as an error; may be repeated
+    #[structopt(long, parse(try_from_str = parse_lint_target))]
+    deny: Vec<LintTarget>,
+
+    /// Show at most this many errors
+    #[structopt(long)]
+    error_limit: Option<usize>,
+
+    /// List the lints, with their default levels, instead of linting
+    #[structopt(long)]
+    list: bool,
+}
+
+impl Options {
+    pub fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        if self.list {
+            for &lint in Lint::ALL {
+                println!(
+                    "{:<20} {:<6} {}",
+                    lint.name(),
+                    lint.default_level().name(),
+                    lint.description()
+                );
+            }
+            return Ok(());
+        }
+
+        // The levels given on the command line override those in the
+        // manifest; of those, the most severe wins.
+        let targets = self
+            .allow
+            .iter()
+            .map(|&target| (target, LintLevel::Allow))
+            .chain(self.warn.iter().map(|&target| (target, LintLevel::Warn)))
+            .chain(self.deny.iter().map(|&target| (target, LintLevel::Deny)));
+        let mut levels = vec![];
+        let mut warnings_level = None;
+        for (target, level) in targets {
+            match target {
+                LintTarget::Lint(lint) => levels.push((lint, level)),
+                LintTarget::Warnings => warnings_level = Some(level),
+            }
+        }
+
+        let mut db = dada_db::Db::default();
+        let mut packages = Packages::default();
+        let mut all_diagnostics = vec![];
+        let paths = dada_files(&self.paths)?;
+        for path in &paths {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("reading `{}`", path.display()))?;
+            let filename = Filename::from(&db, path);
+            db.update_file(filename, contents);
+            packages.load_dependencies(&mut db, path, filename)?;
+
+            let mut file_levels = crate::package::lint_levels(path)?;
+            file_levels.extend(levels.iter().copied());
+            db.set_lint_levels(filename, file_levels);
+
+            all_diagnostics.extend(db.diagnostics(filename));
+            all_diagnostics.extend(db.lint_diagnostics(filename));
+        }
+
+        match warnings_level {
+            Some(LintLevel::Allow) => all_diagnostics.retain(|d| d.severity != Severity::Warning),
+            Some(LintLevel::Deny) => {
+                for diagnostic in &mut all_diagnostics {
+                    if diagnostic.severity == Severity::Warning {
+                        diagnostic.severity = Severity::Error;
+                    }
+                }
+            }
+            Some(LintLevel::Warn) | None => {}
+        }
+
+        print_diagnostics(&db, &all_diagnostics, self.error_limit)?;
+
+        let count = |severity| {
+            all_diagnostics
+                .iter()
+                .filter(|d| d.severity == severity)
+                .count()
+        };
+        let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+        eprintln!(
+            "linted {} file{}: {} error{}, {} warning{}",
+            paths.len(),
+            plural(paths.len()),
+            errors,
+            plural(errors),
+            warnings,
+            plural(warnings),
+        );
+        if errors > 0 {
+            eyre::bail!("linting failed");
+        }
+
+        Ok(())
+    }
+}
+
+/// What `--allow`, `--warn`, and `--deny` apply to.
+#[derive(Copy, Clone)]
+enum LintTarget {
+    Lint(Lint),
+
+    /// `warnings`: everything reported as a warning, whichever lint
+    /// found it.
+    Warnings,
+}
+
+fn parse_lint_target(name: &str) -> eyre::Result<LintTarget> {
+    if name == "warnings" {
+        return Ok(LintTarget::Warnings);
+    }
+    parse_lint(name).map(LintTarget::Lint)
+}
+
+pub(crate) fn parse_lint(name: &str) -> eyre::Result<Lint> {
+    Lint::from_name(name).ok_or_else(|| {
+        eyre::eyre!(
+            "unknown lint `{}` (run `dada lint --list` to see them)",
+            name
+        )
+    })
+}
+
+pub(crate) fn parse_level(name: &str) -> eyre::Result<LintLevel> {
+    LintLevel::from_name(name).ok_or_else(|| {
+        eyre::eyre!(
+            "unknown lint level `{}` (expected `allow`, `warn`, or `deny`)",
+            name
+        )
+    })
+}
@@ -0,0 +1,212 @@
+//! Packages: a directory with a `dada.json` manifest, like
+//!
+//! ```json
+//! {
+//!     "name": "app",
+//!     "dependencies": {
+//!         "util": { "path": "../util" }
+//!     }
+//! }
+//! ```
+//!
+//! The items of a dependency are referred to as `util.item`. Each
+//! dependency is a package of its own, found at `path` (relative to the
+//! manifest); all of its `.dada` files are loaded into the same database as
+//! the program, along with the packages it depends on in turn. Dependencies
+//! from a registry or a git repository are not supported yet.
+//!
+//! A manifest can also set the level of lints for the package, like
+//! `"lints": { "shadowing": "warn" }` (see `dada lint`).
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use dada_ir::{
+    filename::Filename,
+    lint::{Lint, LintLevel},
+    manifest::Dependency,
+    word::Word,
+};
+use eyre::Context;
+
+const MANIFEST: &str = "dada.json";
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    /// The name of the package.
+    name: String,
+
+    /// The packages that this one depends on, by the name they are
+    /// referred to by.
+    #[serde(default)]
+    dependencies: BTreeMap<String, DependencySource>,
+
+    /// The level of the lints whose level is not their default, like
+    /// `"shadowing": "warn"` (see `dada lint`).
+    #[serde(default)]
+    lints: BTreeMap<String, String>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DependencySource {
+    /// The directory of the package, relative to the manifest.
+    path: PathBuf,
+}
+
+impl Manifest {
+    /// Reads the manifest of the package in `directory`.
+    fn read(directory: &Path) -> eyre::Result<Self> {
+        let path = directory.join(MANIFEST);
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("reading `{}`", path.display()))?;
+        serde_json::from_str(&contents).with_context(|| format!("parsing `{}`", path.display()))
+    }
+}
+
+/// The packages loaded so far, so that a package that several files (or
+/// packages) depend on is only loaded once.
+#[derive(Default)]
+pub(crate) struct Packages {
+    /// The files of each package that has been loaded, keyed by the
+    /// canonical path of its directory.
+    loaded: BTreeMap<PathBuf, Vec<Filename>>,
+
+    /// The packages being loaded, innermost last, to detect cycles.
+    loading: Vec<PathBuf>,
+}
+
+impl Packages {
+    /// Loads the packages that the package containing the file at `path`
+    /// depends on, and makes them visible to `filename` (the file at
+    /// `path`). Does nothing if the file is not in a package.
+    pub(crate) fn load_dependencies(
+        &mut self,
+        db: &mut dada_db::Db,
+        path: &Path,
+        filename: Filename,
+    ) -> eyre::Result<()> {
+        let Some(directory) = package_directory(path) else {
+            return Ok(());
+        };
+        let dependencies = self.dependencies(db, directory)?;
+        set_dependencies(db, filename, dependencies);
+        Ok(())
+    }
+
+    /// The files of the dependencies loaded so far.
+    pub(crate) fn filenames(&self) -> impl Iterator<Item = Filename> + '_ {
+        self.loaded.values().flatten().copied()
+    }
+
+    /// Loads the packages that the package in `directory` depends on.
+    fn dependencies(
+        &mut self,
+        db: &mut dada_db::Db,
+        directory: &Path,
+    ) -> eyre::Result<Vec<Dependency>> {
+        let manifest = Manifest::read(directory)?;
+        let mut dependencies = vec![];
+        for (name, source) in &manifest.dependencies {
+            let filenames = self
+                .load(db, &directory.join(&source.path))
+                .with_context(|| {
+                    format!("loading the dependency `{}` of `{}`", name, manifest.name)
+                })?;
+            dependencies.push(Dependency {
+                name: Word::from(&*db, name),
+                filenames,
+            });
+        }
+        Ok(dependencies)
+    }
+
+    /// Loads the `.dada` files of the package in `directory` (and the
+    /// packages it depends on), returning them.
+    fn load(&mut self, db: &mut dada_db::Db, directory: &Path) -> eyre::Result<Vec<Filename>> {
+        let key = directory
+            .canonicalize()
+            .with_context(|| format!("finding `{}`", directory.display()))?;
+        if let Some(filenames) = self.loaded.get(&key) {
+            return Ok(filenames.clone());
+        }
+        if self.loading.contains(&key) {
+            eyre::bail!(
+                "`{}` depends on itself (through its dependencies)",
+                directory.display()
+            );
+        }
+
+        self.loading.push(key.clone());
+        let dependencies = self.dependencies(db, directory);
+        self.loading.pop();
+        let dependencies = dependencies?;
+
+        let mut filenames = vec![];
+        for path in package_files(directory)? {
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading `{}`", path.display()))?;
+            let filename = Filename::from(&*db, &path);
+            crate::watch::update_file(db, filename, contents);
+            set_dependencies(db, filename, dependencies.clone());
+            filenames.push(filename);
+        }
+
+        self.loaded.insert(key, filenames.clone());
+        Ok(filenames)
+    }
+}
+
+/// The directory of the package that contains the file at `path`, if any:
+/// the closest one with a manifest.
+fn package_directory(path: &Path) -> Option<&Path> {
+    let directory = path.parent().unwrap_or(path);
+    directory
+        .ancestors()
+        .find(|dir| dir.join(MANIFEST).is_file())
+}
+
+/// The lint levels set by the manifest of the package that contains the
+/// file at `path`, if any.
+pub(crate) fn lint_levels(path: &Path) -> eyre::Result<Vec<(Lint, LintLevel)>> {
+    let Some(directory) = package_directory(path) else {
+        return Ok(vec![]);
+    };
+    let manifest = Manifest::read(directory)?;
+    manifest
+        .lints
+        .iter()
+        .map(|(lint, level)| {
+            let context = || format!("in `{}`", directory.join(MANIFEST).display());
+            let lint = crate::lint::parse_lint(lint).with_context(context)?;
+            let level = crate::lint::parse_level(level).with_context(context)?;
+            Ok((lint, level))
+        })
+        .collect()
+}
+
+/// The `.dada` files of the package in `directory`, leaving out those of
+/// packages nested within it.
+fn package_files(directory: &Path) -> eyre::Result<Vec<PathBuf>> {
+    let walk = ignore::WalkBuilder::new(directory)
+        .filter_entry(|entry| entry.depth() == 0 || !entry.path().join(MANIFEST).is_file())
+        .build();
+
+    let mut files = vec![];
+    for entry in walk {
+        let path = entry?.into_path();
+        if path.is_file() && path.extension() == Some("dada".as_ref()) {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Sets the dependencies of `filename`, unless they are already
+/// `dependencies` (see [`crate::watch::update_file`]).
+fn set_dependencies(db: &mut dada_db::Db, filename: Filename, dependencies: Vec<Dependency>) {
+    if *dada_ir::manifest::dependencies(&*db, filename) != dependencies {
+        db.set_dependencies(filename, dependencies);
+    }
+}
@@ -0,0 +1,293 @@
+//! `dada repl`: enter statements and items one at a time.
+//!
+//! The REPL keeps what has been entered as the source of a program: an
+//! `async fn main` whose body is the statements, followed by the items.
+//! The program runs in a [`Debugger`] session that stays paused at the end
+//! of `main`. When a statement is entered, it is added to the end of
+//! `main` and the session continues from where it was paused, so only the
+//! new statement runs, and it sees the variables (and the objects they
+//! refer to) of the earlier ones. If it fails, the session goes back to
+//! where it was before.
+
+use std::io::Write;
+
+use dada_execute::{config::ExecutionConfig, debugger::Debugger, kernel::BufferKernel};
+use dada_ir::{diagnostic::Severity, filename::Filename, item::Item, span::Offset};
+
+#[derive(structopt::StructOpt)]
+pub struct Options {}
+
+const HELP: &str = "\
+Enter a statement to run it, or an item (`fn`, `class`) to define it.
+Statements and items can span several lines if their delimiters are open.
+
+:help          show this message
+:ir [name]     show the BIR of the function `name` (default: `main`)
+:heap          show the variables of `main` and what they refer to
+:quit          exit (as does end of input)";
+
+impl Options {
+    pub async fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        let mut repl = Repl::new();
+        println!("Dada REPL; enter `:help` for help");
+
+        let stdin = std::io::stdin();
+        let mut input = String::new();
+        loop {
+            print!("{}", if input.is_empty() { "> " } else { "... " });
+            std::io::stdout().flush()?;
+
+            let mut line = String::new();
+            if stdin.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            input.push_str(&line);
+            if open_delimiters(&input) > 0 {
+                continue;
+            }
+
+            let entry = std::mem::take(&mut input);
+            let entry = entry.trim();
+            if let Some(command) = entry.strip_prefix(':') {
+                let mut words = command.split_whitespace();
+                match (words.next(), words.next()) {
+                    (Some("help"), None) => println!("{}", HELP),
+                    (Some("ir"), name) => repl.print_ir(name.unwrap_or("main")),
+                    (Some("heap"), None) => repl.print_heap(),
+                    (Some("quit"), None) => return Ok(()),
+                    _ => println!("unknown command `:{}`; enter `:help` for help", command),
+                }
+            } else if is_item(entry) {
+                repl.define(entry);
+            } else if !entry.is_empty() {
+                repl.run(entry).await?;
+            }
+        }
+    }
+}
+
+struct Repl {
+    db: dada_db::Db,
+    filename: Filename,
+
+    /// The source of each item entered so far.
+    items: Vec<String>,
+
+    /// The source of each statement entered so far.
+    statements: Vec<String>,
+
+    /// The program made of the statements entered so far, paused at the
+    /// end of `main`. `None` until a statement is entered, or once the
+    /// program has completed (e.g., because a statement returned).
+    session: Option<Debugger>,
+}
+
+const MAIN_START: &str = "async fn main() {\n";
+
+impl Repl {
+    fn new() -> Self {
+        let db = dada_db::Db::default();
+        let filename = Filename::from(&db, "repl.dada");
+        Self {
+            db,
+            filename,
+            items: vec![],
+            statements: vec![],
+            session: None,
+        }
+    }
+
+    /// The program with the given items and statements. `main` comes
+    /// first, so that entering an item doesn't move it.
+    fn source(items: &[String], statements: &[String]) -> String {
+        let mut source = MAIN_START.to_string();
+        for statement in statements {
+            source.push_str(statement);
+            source.push('\n');
+        }
+        source.push_str("}\n");
+        for item in items {
+            source.push('\n');
+            source.push_str(item);
+            source.push('\n');
+        }
+        source
+    }
+
+    /// The offset of the end of `statements` in the program, which is
+    /// where a statement entered after them starts.
+    fn end_of(statements: &[String]) -> Offset {
+        let len: usize = statements.iter().map(|s| s.len() + 1).sum();
+        Offset::from(MAIN_START.len() + len)
+    }
+
+    /// Compiles `source`, printing any diagnostics. If there are errors,
+    /// the program is put back the way it was and false is returned.
+    fn compile(&mut self, source: String) -> bool {
+        self.db.update_file(self.filename, source);
+        let diagnostics = self.db.diagnostics(self.filename);
+        for diagnostic in &diagnostics {
+            if let Err(err) = dada_error_format::print_diagnostic(&self.db, diagnostic) {
+                eprintln!("{:?}", err);
+            }
+        }
+
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            self.restore();
+            return false;
+        }
+        true
+    }
+
+    /// Puts back the program made of what has been entered so far.
+    fn restore(&mut self) {
+        let source = Self::source(&self.items, &self.statements);
+        self.db.update_file(self.filename, source);
+    }
+
+    /// Adds the item `entry`, if it compiles.
+    fn define(&mut self, entry: &str) {
+        let mut items = self.items.clone();
+        items.push(entry.to_string());
+        if self.compile(Self::source(&items, &self.statements)) {
+            self.items = items;
+        }
+    }
+
+    /// Adds the statement `entry` and runs it, printing its output and its
+    /// value (unless it is `()`). If `entry` doesn't compile, or it stops
+    /// with an error, it is dropped, and the session goes back to where it
+    /// was before.
+    async fn run(&mut self, entry: &str) -> eyre::Result<()> {
+        let mut statements = self.statements.clone();
+        statements.push(entry.to_string());
+        if !self.compile(Self::source(&self.items, &statements)) {
+            return Ok(());
+        }
+
+        let before = self.session.as_ref().and_then(Debugger::snapshot);
+        let mut kernel = BufferKernel::new().config(ExecutionConfig::new().deterministic(true));
+        let start = Self::end_of(&self.statements);
+        match self.run_statement(start, &mut kernel).await {
+            Ok(value) => {
+                self.statements = statements;
+                if let Some(value) = value.filter(|value| value != "()") {
+                    println!("{}", value);
+                }
+            }
+            Err(err) => {
+                print!("{}", kernel.take_buffer());
+                eprintln!("{}", err);
+                self.restore();
+                self.session = before.map(Debugger::from_snapshot);
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the statement that starts at `start`, the last one in `main`,
+    /// printing its output as it goes. Returns its value, which is what
+    /// `main` returns, or `None` if the program completed.
+    async fn run_statement(
+        &mut self,
+        start: Offset,
+        kernel: &mut BufferKernel,
+    ) -> eyre::Result<Option<String>> {
+        let session = self.session.take().and_then(|mut debugger| {
+            debugger.continue_after_edit(&self.db, start).ok()?;
+            Some(debugger)
+        });
+        let mut debugger = match session {
+            Some(debugger) => debugger,
+            None => self.replay(start, kernel).await?,
+        };
+
+        loop {
+            if debugger.is_completed() {
+                return Ok(None);
+            }
+            if let Some(value) = debugger.return_value(&self.db) {
+                self.session = Some(debugger);
+                return Ok(Some(value));
+            }
+            debugger.step_over(&self.db, kernel).await?;
+            print!("{}", kernel.take_buffer());
+            std::io::stdout().flush()?;
+        }
+    }
+
+    /// Starts a new session, running `main` up to the statement that starts
+    /// at `start` without showing what it prints (that was shown when those
+    /// statements were entered). This runs them again, so it is only done
+    /// when there is no session that can be continued.
+    async fn replay(&self, start: Offset, kernel: &mut BufferKernel) -> eyre::Result<Debugger> {
+        let mut debugger = Debugger::new(&self.db, self.main(), vec![])?;
+        while debugger.return_value(&self.db).is_none() {
+            match debugger.frames(&self.db).first() {
+                Some(frame) if frame.span.start < start => {}
+                _ => break,
+            }
+            debugger.step_over(&self.db, kernel).await?;
+        }
+        kernel.take_buffer();
+        Ok(debugger)
+    }
+
+    fn main(&self) -> dada_ir::function::Function {
+        self.db
+            .function_named(self.filename, "main")
+            .expect("the REPL always defines `main`")
+    }
+
+    /// Prints the BIR of the function `name`.
+    fn print_ir(&self, name: &str) {
+        match self.db.function_named(self.filename, name) {
+            Some(function) => match self.db.debug_bir(Item::Function(function)) {
+                Some(bir) => println!("{:#?}", bir),
+                None => println!("`{}` has no BIR", name),
+            },
+            None => println!("no function named `{}`", name),
+        }
+    }
+
+    /// Prints each variable of `main` with the value it has after the
+    /// statements entered so far, permissions included.
+    fn print_heap(&self) {
+        let locals = self
+            .session
+            .as_ref()
+            .and_then(|debugger| debugger.locals(&self.db, 0))
+            .unwrap_or_default();
+        if locals.is_empty() {
+            println!("no variables");
+        }
+        for local in locals {
+            println!("{} = {}", local.name, local.value);
+        }
+    }
+}
+
+/// True if `entry` defines an item rather than being a statement.
+fn is_item(entry: &str) -> bool {
+    let first_word = entry.split_whitespace().next().unwrap_or_default();
+    matches!(first_word, "async" | "class" | "extern" | "fn")
+}
+
+/// How many more delimiters `input` opens than it closes (ignoring those in
+/// strings and comments, roughly).
+fn open_delimiters(input: &str) -> i32 {
+    let mut depth = 0;
+    for line in input.lines() {
+        let mut in_string = false;
+        for ch in line.chars() {
+            match ch {
+                '"' => in_string = !in_string,
+                '#' if !in_string => break,
+                '(' | '[' | '{' if !in_string => depth += 1,
+                ')' | ']' | '}' if !in_string => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    depth
+}
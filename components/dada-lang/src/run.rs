@@ -1,89 +1,263 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use dada_execute::{heap_graph::HeapGraph, machine::ProgramCounter};
-use dada_ir::span::FileSpan;
+use dada_execute::{
+    capability::{Capabilities, Capability},
+    config::ExecutionConfig,
+    heap_graph::HeapGraph,
+    jit::Jit,
+    machine::ProgramCounter,
+    profile::Profiler,
+    stats::HeapStats,
+    trace_event::TraceRecorder,
+    Exit,
+};
+use dada_ir::{filename::Filename, span::FileSpan};
+use dada_jit::CraneliftJit;
 use eyre::Context;
-use regex::Regex;
-use salsa::DebugWithDb;
 use tokio::io::AsyncWriteExt;
 
+use crate::package::Packages;
+use crate::watch::Watcher;
+
 #[derive(structopt::StructOpt)]
 pub struct Options {
-    /// Path to `.dada` file to execute
+    /// Path to `.dada` file to execute, or to a `.dadac` file produced by
+    /// `dada build --target=bytecode` (a `.dadac` file next to a `.dada` file
+    /// is used automatically if it is up to date)
     path: PathBuf,
 
-    /// Instead of executing, print BIR for items whose names match the given regex
+    /// Profile execution and print the hottest functions and lines to stderr
+    #[structopt(long)]
+    profile: bool,
+
+    /// Number of entries to include in each section of the profile report
+    #[structopt(long, default_value = "10")]
+    profile_limit: usize,
+
+    /// Profile execution and write the folded call stacks (for use with
+    /// `inferno-flamegraph` or `flamegraph.pl`) to the given path
+    #[structopt(long, parse(from_os_str))]
+    flamegraph: Option<PathBuf>,
+
+    /// Write a Chrome trace (viewable in `about:tracing` or Perfetto) of
+    /// function calls and awaits to the given path
+    #[structopt(long, parse(from_os_str))]
+    chrome_trace: Option<PathBuf>,
+
+    /// Only run the garbage collector once this many objects have been
+    /// allocated since the last collection (0 collects after every step)
+    #[structopt(long, default_value = "0")]
+    gc_threshold: usize,
+
+    /// Stop execution with an error if more than this many objects are live at once
+    #[structopt(long)]
+    max_objects: Option<usize>,
+
+    /// Stop execution with an error if live strings occupy more than this many bytes
+    #[structopt(long)]
+    max_string_bytes: Option<usize>,
+
+    /// Grant the program the given capability, which it would not have by
+    /// default (currently only `ffi`, for calling `extern` functions); may be repeated
+    #[structopt(long = "allow")]
+    allowed_capabilities: Vec<Capability>,
+
+    /// Deny the program the given capability (`print` or `ffi`); may be repeated
+    #[structopt(long = "deny")]
+    denied_capabilities: Vec<Capability>,
+
+    /// Use a logical clock for profiling and tracing, so that repeated
+    /// runs produce identical output
+    #[structopt(long)]
+    deterministic: bool,
+
+    /// Print heap statistics (peak live objects, allocations by class,
+    /// and permission operations) to stderr when the program exits
+    #[structopt(long)]
+    stats: bool,
+
+    /// Compile hot functions to native code (experimental)
+    #[structopt(long)]
+    jit: bool,
+
+    /// With `--jit`, compile functions once they have been called this many times
+    #[structopt(long, default_value = "100")]
+    jit_threshold: u64,
+
+    /// With `--jit`, also interpret the calls that the JIT executes, and
+    /// stop with an error if the results differ
     #[structopt(long)]
-    bir: Option<Regex>,
+    jit_validate: bool,
 
-    /// Instead of executing, print validated tree for items whose names match the given regex
+    /// After running, wait for the file to change and run it again
     #[structopt(long)]
-    validated: Option<Regex>,
+    watch: bool,
+
+    /// Arguments for the program, given after `--`; it reads them with
+    /// the `arg` and `arg_count` intrinsics
+    #[structopt(last = true)]
+    args: Vec<String>,
 }
 
 impl Options {
     pub async fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
         let mut db = dada_db::Db::default();
+        if !self.watch {
+            // If the program calls `exit`, exit with the code it gave.
+            return match self.run(&mut db).await {
+                Err(err) => match err.downcast_ref::<Exit>() {
+                    Some(exit) => {
+                        tokio::io::stdout().flush().await?;
+                        std::process::exit(exit.code)
+                    }
+                    None => Err(err),
+                },
+                Ok(()) => Ok(()),
+            };
+        }
 
-        let contents = std::fs::read_to_string(&self.path)
-            .with_context(|| format!("reading `{}`", self.path.display()))?;
-        let filename = dada_ir::filename::Filename::from(&db, &self.path);
-        db.update_file(filename, contents);
-
-        for diagnostic in db.diagnostics(filename) {
-            dada_error_format::print_diagnostic(&db, &diagnostic)?;
+        if self.path.extension() == Some("dadac".as_ref()) {
+            eyre::bail!("`--watch` cannot be used with a compiled `.dadac` file");
         }
+        let mut watcher = Watcher::new(vec![self.path.clone()])?;
+        loop {
+            match self.run(&mut db).await {
+                Err(err) if err.is::<Exit>() => eprintln!("{}", err),
+                Err(err) => eprintln!("Error: {:?}", err),
+                Ok(()) => {}
+            }
+            watcher.wait_for_change().await?;
+        }
+    }
+
+    /// Runs the program at `self.path`, reusing what
+    /// `db` has computed if it is unchanged.
+    async fn run(&self, db: &mut dada_db::Db) -> eyre::Result<()> {
+        let filename = if self.path.extension() == Some("dadac".as_ref()) {
+            load_bytecode(db, &self.path)?
+        } else {
+            let contents = std::fs::read_to_string(&self.path)
+                .with_context(|| format!("reading `{}`", self.path.display()))?;
 
-        let mut should_execute = true;
+            // If the program has been compiled with `dada build --target=bytecode`
+            // since it last changed, run the compiled program instead.
+            let compiled_path = self.path.with_extension("dadac");
+            let compiled = std::fs::read(&compiled_path).unwrap_or_default();
+            if dada_bytecode::is_up_to_date(&compiled, &contents) {
+                load_bytecode(db, &compiled_path)?
+            } else {
+                let filename = Filename::from(db, &self.path);
+                crate::watch::update_file(db, filename, contents);
+                let mut packages = Packages::default();
+                packages.load_dependencies(db, &self.path, filename)?;
 
-        if let Some(name_regex) = &self.validated {
-            for item in db.items(filename) {
-                let name = item.name(&db).as_str(&db);
-                if name_regex.is_match(name) {
-                    if let Some(tree) = db.debug_validated_tree(item) {
-                        tracing::info!("Validated tree for {:?} is {:#?}", item.debug(&db), tree);
+                // Errors in the dependencies matter as much as errors in
+                // the program itself.
+                for filename in packages.filenames().chain(Some(filename)) {
+                    for diagnostic in db.diagnostics(filename) {
+                        dada_error_format::print_diagnostic(db, &diagnostic)?;
                     }
                 }
+
+                filename
             }
-            should_execute = false;
+        };
+        let db: &dada_db::Db = db;
+
+        let main = dada_execute::main_function(db, filename)?;
+        let mut kernel = Kernel::new();
+        kernel.config = ExecutionConfig::new()
+            .gc_threshold(self.gc_threshold)
+            .deterministic(self.deterministic)
+            .validate_jit(self.jit_validate)
+            .args(self.args.clone());
+        if let Some(max_objects) = self.max_objects {
+            kernel.config = kernel.config.max_objects(max_objects);
+        }
+        if let Some(max_string_bytes) = self.max_string_bytes {
+            kernel.config = kernel.config.max_string_bytes(max_string_bytes);
+        }
+        let capabilities = self
+            .allowed_capabilities
+            .iter()
+            .fold(Capabilities::default(), |set, &c| set.with(c));
+        let capabilities = self
+            .denied_capabilities
+            .iter()
+            .fold(capabilities, |set, &c| set.without(c));
+        kernel.config = kernel.config.capabilities(capabilities);
+        if self.profile || self.flamegraph.is_some() {
+            kernel.profiler = Some(Profiler::new());
+        }
+        if self.chrome_trace.is_some() {
+            kernel.trace_recorder = Some(TraceRecorder::new());
+        }
+        if self.stats {
+            kernel.heap_stats = Some(HeapStats::new());
+        }
+        if self.jit {
+            kernel.jit = Some(CraneliftJit::new().threshold(self.jit_threshold));
         }
 
-        if let Some(name_regex) = &self.bir {
-            for item in db.items(filename) {
-                let name = item.name(&db).as_str(&db);
-                if name_regex.is_match(name) {
-                    if let Some(tree) = db.debug_bir(item) {
-                        tracing::info!("BIR for {:?} is {:#?}", item.debug(&db), tree);
-                    }
-                }
+        let result = dada_execute::interpret_main(main, db, &mut kernel).await;
+
+        if let Some(profiler) = &kernel.profiler {
+            if self.profile {
+                eprint!("{}", profiler.report(db, self.profile_limit));
             }
-            should_execute = false;
-        }
 
-        // Find the "main" function
-        if should_execute {
-            match db.function_named(filename, "main") {
-                Some(function) => {
-                    dada_execute::interpret(function, &db, &mut Kernel::new(), vec![]).await?;
-                }
-                None => {
-                    return Err(eyre::eyre!(
-                        "could not find a function named `main` in `{}`",
-                        self.path.display()
-                    ));
-                }
+            if let Some(path) = &self.flamegraph {
+                std::fs::write(path, profiler.folded_stacks(db))
+                    .with_context(|| format!("writing `{}`", path.display()))?;
             }
         }
 
-        Ok(())
+        if let (Some(recorder), Some(path)) = (&kernel.trace_recorder, &self.chrome_trace) {
+            std::fs::write(path, recorder.to_json())
+                .with_context(|| format!("writing `{}`", path.display()))?;
+        }
+
+        if let Some(stats) = &kernel.heap_stats {
+            eprint!("{}", stats.report(db));
+        }
+
+        result
     }
 }
 
-struct Kernel {}
+/// Loads the compiled program at `path` into `db`.
+fn load_bytecode(db: &mut dada_db::Db, path: &Path) -> eyre::Result<Filename> {
+    let bytes = std::fs::read(path).with_context(|| format!("reading `{}`", path.display()))?;
+    db.load_bytecode(bytes)
+        .with_context(|| format!("loading `{}`", path.display()))
+}
+
+struct Kernel {
+    /// Configuration for the interpreter.
+    config: ExecutionConfig,
+
+    /// If `Some`, records execution statistics for the profile report.
+    profiler: Option<Profiler>,
+
+    /// If `Some`, records function and await events for the Chrome trace.
+    trace_recorder: Option<TraceRecorder>,
+
+    /// If `Some`, records allocations and permission operations for the stats report.
+    heap_stats: Option<HeapStats>,
+
+    /// If `Some`, compiles hot functions to native code.
+    jit: Option<CraneliftJit>,
+}
 
 impl Kernel {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            config: ExecutionConfig::default(),
+            profiler: None,
+            trace_recorder: None,
+            heap_stats: None,
+            jit: None,
+        }
     }
 }
 
@@ -119,4 +293,24 @@ impl dada_execute::kernel::Kernel for Kernel {
     ) -> eyre::Result<()> {
         panic!("no breakpoints set")
     }
+
+    fn execution_config(&self) -> ExecutionConfig {
+        self.config.clone()
+    }
+
+    fn profiler(&mut self) -> Option<&mut Profiler> {
+        self.profiler.as_mut()
+    }
+
+    fn trace_recorder(&mut self) -> Option<&mut TraceRecorder> {
+        self.trace_recorder.as_mut()
+    }
+
+    fn heap_stats(&mut self) -> Option<&mut HeapStats> {
+        self.heap_stats.as_mut()
+    }
+
+    fn jit(&mut self) -> Option<&mut dyn Jit> {
+        self.jit.as_mut().map(|jit| jit as &mut dyn Jit)
+    }
 }
@@ -0,0 +1,209 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+use dada_execute::{config::ExecutionConfig, kernel::BufferKernel};
+use dada_ir::{diagnostic::Severity, filename::Filename, function::Function, item::Item};
+use dada_parse::prelude::*;
+use eyre::Context;
+use regex::Regex;
+use salsa::ParallelDatabase;
+
+use crate::check::{dada_files, plural};
+use crate::package::Packages;
+
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// Paths to `.dada` files whose test functions should be run, or to
+    /// directories to search for them (defaults to the current directory).
+    /// A test function is one whose name starts with `test_` and that takes
+    /// no parameters; it fails if it stops with an error.
+    paths: Vec<PathBuf>,
+
+    /// Only run the test functions whose names match the given regex
+    #[structopt(long)]
+    filter: Option<Regex>,
+
+    /// Number of test functions to run at once (defaults to the number of CPUs)
+    #[structopt(long)]
+    jobs: Option<usize>,
+}
+
+/// A test function to run: `function`, named `name`, in the file at `path`.
+struct TestFn {
+    path: PathBuf,
+    name: String,
+    function: Function,
+}
+
+/// The result of running a [`TestFn`].
+struct Outcome {
+    /// Everything that the test printed.
+    output: String,
+
+    /// If the test failed, the error that it stopped with (formatted with
+    /// the source it points at, for runtime errors).
+    error: Option<String>,
+}
+
+impl Options {
+    pub fn main(&self, _crate_options: &crate::Options) -> eyre::Result<()> {
+        let mut db = dada_db::Db::default();
+        let mut packages = Packages::default();
+        let mut test_fns = vec![];
+        let mut errors = 0;
+        let mut filtered_out = 0;
+        for path in dada_files(&self.paths)? {
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading `{}`", path.display()))?;
+            let filename = Filename::from(&db, &path);
+            db.update_file(filename, contents);
+            packages.load_dependencies(&mut db, &path, filename)?;
+            for diagnostic in db.diagnostics(filename) {
+                dada_error_format::print_diagnostic(&db, &diagnostic)?;
+                if diagnostic.severity == Severity::Error {
+                    errors += 1;
+                }
+            }
+
+            for item in db.items(filename) {
+                let Item::Function(function) = item else {
+                    continue;
+                };
+                let name = function.name(&db).as_str(&db);
+                if !name.starts_with("test_") || !function.parameters(&db).is_empty() {
+                    continue;
+                }
+                if let Some(filter) = &self.filter {
+                    if !filter.is_match(name) {
+                        filtered_out += 1;
+                        continue;
+                    }
+                }
+                test_fns.push(TestFn {
+                    path: path.clone(),
+                    name: name.to_string(),
+                    function,
+                });
+            }
+        }
+
+        // A test that fails to compile should not look like one that passed.
+        if errors > 0 {
+            eyre::bail!("{} error{} in the tests", errors, plural(errors));
+        }
+
+        eprintln!(
+            "running {} test function{}",
+            test_fns.len(),
+            plural(test_fns.len())
+        );
+        let outcomes = self.run_all(&db, &test_fns)?;
+
+        let mut failures = vec![];
+        for (test_fn, outcome) in test_fns.iter().zip(&outcomes) {
+            let status = if outcome.error.is_some() {
+                failures.push((test_fn, outcome));
+                "FAILED"
+            } else {
+                "ok"
+            };
+            eprintln!(
+                "test {}::{} ... {}",
+                test_fn.path.display(),
+                test_fn.name,
+                status
+            );
+        }
+
+        for (test_fn, outcome) in &failures {
+            eprintln!();
+            eprintln!("---- {}::{} ----", test_fn.path.display(), test_fn.name);
+            if !outcome.output.is_empty() {
+                eprintln!("output:");
+                eprint!("{}", outcome.output);
+            }
+            if let Some(error) = &outcome.error {
+                eprintln!("{}", error);
+            }
+        }
+
+        eprintln!();
+        eprintln!(
+            "{} passed, {} failed, {} filtered out",
+            outcomes.len() - failures.len(),
+            failures.len(),
+            filtered_out
+        );
+        if !failures.is_empty() {
+            eyre::bail!(
+                "{} test function{} failed",
+                failures.len(),
+                plural(failures.len())
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Runs `test_fns` on `self.jobs` threads, each with a snapshot of
+    /// `db`, returning their outcomes in the order given.
+    fn run_all(&self, db: &dada_db::Db, test_fns: &[TestFn]) -> eyre::Result<Vec<Outcome>> {
+        let jobs = match self.jobs {
+            Some(jobs) => jobs.max(1),
+            None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        };
+
+        // Each thread takes the next test that nobody has started.
+        let next = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+        std::thread::scope(|scope| {
+            let mut threads = vec![];
+            for _ in 0..jobs.min(test_fns.len()) {
+                let db = db.snapshot();
+                let next = &next;
+                let sender = sender.clone();
+                threads.push(scope.spawn(move || -> eyre::Result<()> {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?;
+                    loop {
+                        let index = next.fetch_add(1, Ordering::SeqCst);
+                        let Some(test_fn) = test_fns.get(index) else {
+                            return Ok(());
+                        };
+                        let outcome = runtime.block_on(run_test_fn(&db, test_fn));
+                        if sender.send((index, outcome)).is_err() {
+                            return Ok(());
+                        }
+                    }
+                }));
+            }
+            drop(sender);
+
+            let mut outcomes: Vec<Option<Outcome>> = test_fns.iter().map(|_| None).collect();
+            for (index, outcome) in receiver {
+                outcomes[index] = Some(outcome);
+            }
+            for thread in threads {
+                match thread.join() {
+                    Ok(result) => result?,
+                    Err(_) => eyre::bail!("a thread running test functions panicked"),
+                }
+            }
+            Ok(outcomes.into_iter().map(Option::unwrap).collect())
+        })
+    }
+}
+
+/// Runs `test_fn` in an interpreter of its own, so that tests cannot
+/// affect one another, capturing what it prints. Only the compiled
+/// program, in `db`, is shared.
+async fn run_test_fn(db: &dada_db::Db, test_fn: &TestFn) -> Outcome {
+    let mut kernel = BufferKernel::new().config(ExecutionConfig::new().deterministic(true));
+    let result = kernel.interpret(db, test_fn.function, vec![]).await;
+    Outcome {
+        output: kernel.take_buffer(),
+        error: result.err().map(|err| err.to_string()),
+    }
+}
@@ -2,15 +2,31 @@ use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
 use std::{env, fs};
 
+use dada_execute::capability::Capabilities;
+use dada_execute::config::ExecutionConfig;
 use dada_execute::kernel::BufferKernel;
 use dada_execute::machine::ProgramCounter;
-use dada_ir::{filename::Filename, item::Item};
+use dada_ir::{
+    filename::Filename,
+    item::Item,
+    lint::{Lint, LintLevel},
+    messages::{self, Catalog},
+};
+use dada_jit::CraneliftJit;
 use eyre::Context;
 use lsp_types::Diagnostic;
 use regex::Regex;
 
+use self::annotations::BlessedAnnotations;
+use self::coverage::CoverageReport;
+
+mod annotations;
+mod coverage;
+mod differential;
 mod heap_graph_query;
 mod lsp_client;
+mod lsp_query;
+mod wasm;
 
 #[derive(structopt::StructOpt)]
 pub struct Options {
@@ -21,6 +37,40 @@ pub struct Options {
     /// Instead of validating `.ref` files, generate them
     #[structopt(long)]
     bless: bool,
+
+    /// Run the tests with the JIT compiling every function that it can,
+    /// checking its results against the interpreter
+    #[structopt(long)]
+    jit: bool,
+
+    /// Run each test from bytecode: encode it as with
+    /// `dada build --target=bytecode`, load that into a fresh database,
+    /// and interpret the loaded program
+    #[structopt(long)]
+    bytecode: bool,
+
+    /// Also run each test under every backend (the JIT, bytecode, wasm, and
+    /// the JavaScript backend, which needs `node`), and check that its
+    /// output, runtime errors, and heap statistics match the interpreter's
+    /// (a backend that cannot compile a test is skipped with a warning)
+    #[structopt(long)]
+    differential: bool,
+
+    /// Also compile each test to WebAssembly for WASI, run it with
+    /// wasmtime, and check its output against `stdout.ref` (tests the
+    /// wasm backend cannot compile are skipped)
+    #[structopt(long)]
+    wasm: bool,
+
+    /// Record which lines of each test are executed, and write a report
+    /// of the lines that never ran to `--coverage-output`
+    #[structopt(long)]
+    coverage: bool,
+
+    /// With `--coverage`, where to write the report: an HTML page if the
+    /// path ends in `.html`, and otherwise an lcov tracefile
+    #[structopt(long, parse(from_os_str), default_value = "target/coverage/lcov.info")]
+    coverage_output: PathBuf,
 }
 
 impl Options {
@@ -28,6 +78,7 @@ impl Options {
         let mut total = 0;
         let mut errors = Errors::default();
         let mut tests_with_fixmes = 0;
+        let mut coverage = CoverageReport::default();
 
         if self.dada_path.is_empty() {
             eyre::bail!("no test paths given; try --dada-path");
@@ -52,7 +103,7 @@ impl Options {
                         if ext == "dada" {
                             total += 1;
                             let fixmes = self
-                                .test_dada_file(&mut lsp_client, path)
+                                .test_dada_file(&mut lsp_client, &mut coverage, path)
                                 .await
                                 .with_context(|| format!("testing `{}`", path.display()))?;
 
@@ -120,6 +171,15 @@ impl Options {
 
         tracing::info!("{total} tests executed");
 
+        if self.coverage {
+            coverage.write(&self.coverage_output)?;
+            let (hit, found) = coverage.summary();
+            tracing::info!(
+                "{hit} of {found} lines covered; report written to `{}`",
+                self.coverage_output.display()
+            );
+        }
+
         if tests_with_fixmes > 0 {
             tracing::info!("{tests_with_fixmes} test(s) encountered known bugs");
         }
@@ -131,32 +191,44 @@ impl Options {
         }
     }
 
-    #[tracing::instrument(level = "debug", skip(self, lsp_client))]
+    #[tracing::instrument(level = "debug", skip(self, lsp_client, coverage))]
     async fn test_dada_file(
         &self,
         lsp_client: &mut lsp_client::ChildSession,
+        coverage: &mut CoverageReport,
         path: &Path,
     ) -> eyre::Result<Vec<String>> {
         let expected_queries = &expected_queries(path)?;
-        let expected_diagnostics = expected_diagnostics(path)?;
+        let mut expected_diagnostics = expected_diagnostics(path)?;
         let path_without_extension = path.with_extension("");
         fs::create_dir_all(&path_without_extension)?;
         self.test_dada_file_normal(
             &path_without_extension,
             &expected_diagnostics,
             expected_queries,
+            coverage,
         )
         .await?;
-        self.test_dada_file_in_ide(lsp_client, &path_without_extension, &expected_diagnostics)?;
+        if self.bless {
+            // The annotations may have been blessed.
+            expected_diagnostics = self::expected_diagnostics(path)?;
+        }
+        self.test_dada_file_in_ide(
+            lsp_client,
+            &path_without_extension,
+            &expected_diagnostics,
+            expected_queries,
+        )?;
         Ok(expected_diagnostics.fixmes)
     }
 
-    #[tracing::instrument(level = "debug", skip(self))]
+    #[tracing::instrument(level = "debug", skip(self, coverage))]
     async fn test_dada_file_normal(
         &self,
         path: &Path,
         expected_diagnostics: &ExpectedDiagnostics,
         expected_queries: &[Query],
+        coverage: &mut CoverageReport,
     ) -> eyre::Result<()> {
         let mut db = dada_db::Db::default();
         let source_path = path.with_extension("dada");
@@ -164,9 +236,18 @@ impl Options {
             .with_context(|| format!("reading `{}`", &source_path.display()))?;
         let filename = dada_ir::filename::Filename::from(&db, &source_path);
         db.update_file(filename, contents);
+        if let Some(levels) = &expected_diagnostics.lint_levels {
+            db.set_lint_levels(filename, levels.clone());
+        }
         let diagnostics = db.diagnostics(filename);
 
         let mut errors = Errors::default();
+        let mut blessed = BlessedAnnotations::default();
+        let mut sorted_diagnostics = diagnostics.clone();
+        sorted_diagnostics.sort_by_key(|d| d.start(&db));
+        for diagnostic in &sorted_diagnostics {
+            blessed.diagnostic(&db, diagnostic, None);
+        }
         self.match_diagnostics_against_expectations(
             &db,
             &diagnostics,
@@ -177,11 +258,20 @@ impl Options {
             dada_error_format::format_diagnostics_with_options(
                 &db,
                 &diagnostics,
-                dada_error_format::FormatOptions::no_color(),
+                dada_error_format::FormatOptions::no_color().catalog(expected_diagnostics.catalog),
             )?,
             &path.join("compiler-output.ref"),
             &mut errors,
         )?;
+        if expected_diagnostics.lint_levels.is_some() {
+            self.check_lints(
+                &db,
+                filename,
+                &expected_diagnostics.lint,
+                &mut blessed,
+                &mut errors,
+            )?;
+        }
         self.check_compiled(
             &db,
             &[filename],
@@ -200,21 +290,41 @@ impl Options {
             |item| db.debug_bir(item),
             &path.join("bir.debug"),
         )?;
+        if expected_diagnostics.js {
+            self.check_js(&db, filename, &path.join("js.ref"), &mut errors)?;
+        }
         self.check_interpreted(
             &db,
             filename,
             &path.join("stdout.ref"),
-            &expected_diagnostics.runtime,
-            &expected_diagnostics.output,
+            expected_diagnostics,
+            &mut blessed,
             &mut errors,
+            coverage,
         )
         .await?;
+        if self.differential {
+            self.check_differential(&db, filename, &mut errors).await?;
+        }
+        if self.wasm && !expected_diagnostics.configured {
+            self.check_wasm(
+                &db,
+                filename,
+                &path.join("stdout.ref"),
+                &expected_diagnostics.runtime,
+                &mut errors,
+            )?;
+        }
 
         for (query, query_index) in expected_queries.iter().zip(0..) {
             self.perform_query_on_db(&mut db, path, filename, query, query_index, &mut errors)
                 .await?;
         }
 
+        if self.bless && errors.remove_annotation_mismatches() {
+            blessed.write(&source_path)?;
+        }
+
         errors.into_result()
     }
 
@@ -224,9 +334,11 @@ impl Options {
         lsp_client: &mut lsp_client::ChildSession,
         path: &Path,
         expected_diagnostics: &ExpectedDiagnostics,
+        expected_queries: &[Query],
     ) -> eyre::Result<()> {
-        lsp_client.send_open(&path.with_extension("dada"))?;
-        let diagnostics = lsp_client.receive_errors()?;
+        let source_path = path.with_extension("dada");
+        lsp_client.send_open(&source_path)?;
+        let diagnostics = lsp_client.receive_errors(&source_path)?;
 
         let mut errors = Errors::default();
         self.match_diagnostics_against_expectations(
@@ -236,6 +348,16 @@ impl Options {
             &mut errors,
         )?;
         self.bless_debug_file(format!("{:#?}", diagnostics), &path.join("lsp.debug"))?;
+
+        for (query, query_index) in expected_queries.iter().zip(0..) {
+            if query.kind.is_lsp() {
+                self.perform_lsp_query(lsp_client, path, query, query_index, &mut errors)
+                    .with_context(|| {
+                        format!("{:?} query from line `{}`", query.kind, query.line)
+                    })?;
+            }
+        }
+
         errors.into_result()
     }
 
@@ -253,6 +375,23 @@ impl Options {
                 .perform_heap_graph_query_on_db(db, path, query_index, filename, query, errors)
                 .await
                 .with_context(|| format!("heap query from line `{}`", query.line)),
+
+            // These are sent to the language server (see `test_dada_file_in_ide`).
+            QueryKind::Definition
+            | QueryKind::References
+            | QueryKind::Rename
+            | QueryKind::Completion
+            | QueryKind::Hover
+            | QueryKind::DocumentSymbols
+            | QueryKind::WorkspaceSymbols
+            | QueryKind::SemanticTokens
+            | QueryKind::SignatureHelp
+            | QueryKind::InlayHints
+            | QueryKind::CodeActions
+            | QueryKind::FoldingRanges
+            | QueryKind::SelectionRanges
+            | QueryKind::Close
+            | QueryKind::Edit => Ok(()),
         }
     }
 
@@ -347,6 +486,25 @@ impl Options {
         Ok(())
     }
 
+    /// Runs the lint pass on a test with a `#! LINTS` line, checking what
+    /// it finds against the `#! LINT` annotations.
+    fn check_lints(
+        &self,
+        db: &dada_db::Db,
+        filename: Filename,
+        expected_diagnostics: &[ExpectedDiagnostic],
+        blessed: &mut BlessedAnnotations,
+        errors: &mut Errors,
+    ) -> eyre::Result<()> {
+        let diagnostics = db.lint_diagnostics(filename);
+        let mut sorted_diagnostics = diagnostics.clone();
+        sorted_diagnostics.sort_by_key(|d| d.start(db));
+        for diagnostic in &sorted_diagnostics {
+            blessed.diagnostic(db, diagnostic, Some("LINT"));
+        }
+        self.match_diagnostics_against_expectations(db, &diagnostics, expected_diagnostics, errors)
+    }
+
     fn check_compiled<D>(
         &self,
         db: &dada_db::Db,
@@ -371,23 +529,79 @@ impl Options {
         Ok(())
     }
 
+    /// Compiles `filename` with the JavaScript backend and checks the
+    /// compiled program, without the runtime that the script starts with,
+    /// against `ref_path`.
+    fn check_js(
+        &self,
+        db: &dada_db::Db,
+        filename: Filename,
+        ref_path: &Path,
+        errors: &mut Errors,
+    ) -> eyre::Result<()> {
+        let script = match dada_codegen_js::compile_module(db, filename) {
+            Ok(script) => script,
+            Err(diagnostics) => eyre::bail!(
+                "the JavaScript backend could not compile the test:\n{}",
+                dada_error_format::format_diagnostics_with_options(
+                    db,
+                    &diagnostics,
+                    dada_error_format::FormatOptions::no_color(),
+                )?
+            ),
+        };
+        let program = match script.split_once(dada_codegen_js::RUNTIME) {
+            Some((_, program)) => program.trim_start().to_string(),
+            None => script,
+        };
+        self.check_output_against_ref_file(program, ref_path, errors)
+    }
+
     async fn check_interpreted(
         &self,
         db: &dada_db::Db,
         filename: Filename,
         ref_path: &Path,
-        expected_diagnostics: &[ExpectedDiagnostic],
-        expected_outputs: &Option<Vec<ExpectedOutput>>,
+        expected: &ExpectedDiagnostics,
+        blessed: &mut BlessedAnnotations,
         errors: &mut Errors,
+        coverage: &mut CoverageReport,
     ) -> eyre::Result<()> {
+        let loaded_db;
+        let (db, filename) = if self.bytecode {
+            let mut fresh_db = dada_db::Db::default();
+            let filename = fresh_db.load_bytecode(db.bytecode(filename))?;
+            loaded_db = fresh_db;
+            (&loaded_db, filename)
+        } else {
+            (db, filename)
+        };
+
         let mut diagnostics = vec![];
         let actual_output = match db.function_named(filename, "main") {
             Some(function) => {
-                let mut kernel = BufferKernel::new().track_output_ranges(true);
-                let res = kernel.interpret(db, function, vec![]).await;
+                let mut kernel = BufferKernel::new()
+                    .track_output_ranges(true)
+                    .record_coverage(self.coverage)
+                    .record_heap_stats(!expected.stats.is_empty())
+                    .config(
+                        expected
+                            .config
+                            .clone()
+                            .deterministic(true)
+                            .validate_jit(self.jit),
+                    );
+                if self.jit {
+                    kernel = kernel.jit(CraneliftJit::new().threshold(0));
+                }
+                let res = kernel.interpret_main(db, function).await;
+                if let Some(recorded) = kernel.take_coverage() {
+                    coverage.add(db, filename, &recorded);
+                }
                 if let Err(err) = res {
                     match err.downcast_ref::<dada_execute::DiagnosticError>() {
                         Some(err) => {
+                            blessed.diagnostic(db, err.diagnostic(), Some("RUN"));
                             diagnostics.push(err.diagnostic().clone());
                         }
                         None => {
@@ -396,12 +610,17 @@ impl Options {
                     }
                 }
 
-                if let Some(expected_outputs) = expected_outputs {
+                if let Some(stats) = kernel.take_heap_stats() {
+                    check_stats(&stats.report(db), &expected.stats, errors);
+                }
+
+                if let Some(expected_outputs) = &expected.output {
                     self.match_output_against_expectations(
                         db,
                         filename,
                         kernel.buffer_with_pcs(),
                         expected_outputs,
+                        blessed,
                         errors,
                     )?;
                 }
@@ -412,12 +631,7 @@ impl Options {
                 format!("no `main` function in `{}`", filename.as_str(db))
             }
         };
-        self.match_diagnostics_against_expectations(
-            db,
-            &diagnostics,
-            expected_diagnostics,
-            errors,
-        )?;
+        self.match_diagnostics_against_expectations(db, &diagnostics, &expected.runtime, errors)?;
         self.check_output_against_ref_file(actual_output, ref_path, errors)?;
         Ok(())
     }
@@ -428,6 +642,7 @@ impl Options {
         filename: Filename,
         output_with_pcs: impl Iterator<Item = (&'a str, Option<ProgramCounter>)>,
         expected_outputs: &[ExpectedOutput],
+        blessed: &mut BlessedAnnotations,
         errors: &mut Errors,
     ) -> eyre::Result<()> {
         let mut actual_diffs = vec![];
@@ -440,6 +655,7 @@ impl Options {
             let pc = pc.ok_or_else(|| eyre::eyre!("untracked output `{}` from text", text))?;
             let span = pc.span(db);
             let (_, start_line_column, _) = db.line_columns(span);
+            blessed.output(start_line_column.line1(), text);
             output_by_line
                 .entry(start_line_column.line1())
                 .or_insert(vec![])
@@ -521,6 +737,15 @@ impl Errors {
         self.reports.push(eyre::Report::new(m));
     }
 
+    /// Removes the reports of diagnostics or output that don't match the
+    /// `#!` annotations, returning true if there were any.
+    fn remove_annotation_mismatches(&mut self) -> bool {
+        let len = self.reports.len();
+        self.reports
+            .retain(|r| !r.is::<DiagnosticsDoNotMatch>() && !r.is::<OutputsDoNotMatch>());
+        self.reports.len() != len
+    }
+
     fn into_result(mut self) -> eyre::Result<()> {
         if self.reports.is_empty() {
             return Ok(());
@@ -698,17 +923,130 @@ struct Query {
 enum QueryKind {
     /// Interpret the code to this point and dump the heap-graph.
     HeapGraph,
+
+    /// Ask the language server where the name at this point is defined.
+    Definition,
+
+    /// Ask the language server for each use of the name at this point,
+    /// its definition included.
+    References,
+
+    /// Ask the language server to rename the name at this point to
+    /// `renamed`.
+    Rename,
+
+    /// Ask the language server for the completions at this point.
+    Completion,
+
+    /// Ask the language server what to show when hovering over this point.
+    Hover,
+
+    /// Ask the language server for the outline of the file.
+    DocumentSymbols,
+
+    /// Ask the language server to search the workspace for symbols like
+    /// the name at this point (only those in this file are shown, as the
+    /// server has every earlier test loaded too).
+    WorkspaceSymbols,
+
+    /// Ask the language server how to highlight the file.
+    SemanticTokens,
+
+    /// Ask the language server for the signature of the call whose
+    /// arguments this point is in.
+    SignatureHelp,
+
+    /// Ask the language server for the inlay hints of the file.
+    InlayHints,
+
+    /// Ask the language server for the fixes to the diagnostics at this
+    /// point.
+    CodeActions,
+
+    /// Ask the language server which lines of the file can be folded.
+    FoldingRanges,
+
+    /// Ask the language server for the ranges to select, from the
+    /// innermost outward, when expanding the selection from this point.
+    SelectionRanges,
+
+    /// Close the file, then search the workspace for symbols like the name
+    /// at this point, as `WorkspaceSymbols` does. The server should read a
+    /// closed file back from disk rather than forget it. The file is opened
+    /// again afterward.
+    Close,
+
+    /// Replace the name at this point with `renamed`, and then undo that,
+    /// each as a change to just that part of the text, and report the
+    /// diagnostics after each.
+    Edit,
+}
+
+impl QueryKind {
+    fn parse(kind: &str, line_number: u32) -> eyre::Result<Self> {
+        Ok(match kind {
+            "HeapGraph" => QueryKind::HeapGraph,
+            "Definition" => QueryKind::Definition,
+            "References" => QueryKind::References,
+            "Rename" => QueryKind::Rename,
+            "Completion" => QueryKind::Completion,
+            "Hover" => QueryKind::Hover,
+            "DocumentSymbols" => QueryKind::DocumentSymbols,
+            "WorkspaceSymbols" => QueryKind::WorkspaceSymbols,
+            "SemanticTokens" => QueryKind::SemanticTokens,
+            "SignatureHelp" => QueryKind::SignatureHelp,
+            "InlayHints" => QueryKind::InlayHints,
+            "CodeActions" => QueryKind::CodeActions,
+            "FoldingRanges" => QueryKind::FoldingRanges,
+            "SelectionRanges" => QueryKind::SelectionRanges,
+            "Close" => QueryKind::Close,
+            "Edit" => QueryKind::Edit,
+            k => eyre::bail!("unexpected query kind `{}` on line {}", k, line_number),
+        })
+    }
+
+    /// True for the queries that are sent to the language server.
+    fn is_lsp(&self) -> bool {
+        !matches!(self, QueryKind::HeapGraph)
+    }
 }
 
-/// There are both compile-time and runtime-emitted diagnostics
+/// There are both compile-time and runtime-emitted diagnostics, and those
+/// found by the lint pass
 #[derive(Debug)]
 struct ExpectedDiagnostics {
     compile: Vec<ExpectedDiagnostic>,
     runtime: Vec<ExpectedDiagnostic>,
+    lint: Vec<ExpectedDiagnostic>,
+
+    // If `None`, the lint pass is not run; otherwise the levels given by a
+    // `#! LINTS` line, like `#! LINTS shadowing=warn`.
+    lint_levels: Option<Vec<(Lint, LintLevel)>>,
 
     // If `None`, do not check the output.
     output: Option<Vec<ExpectedOutput>>,
 
+    // The configuration to run the test with, adjusted by `#! CONFIG`
+    // lines like `#! CONFIG gc_threshold=10` or `#! CONFIG deny=print`.
+    config: ExecutionConfig,
+
+    // True if there was a `#! CONFIG` line. The wasm backend can't honor
+    // its settings, so such tests are only run under the interpreter.
+    configured: bool,
+
+    // Regexes from `#! STATS` lines, each of which must match a line of the
+    // heap statistics report; heap statistics are only recorded if there are any.
+    stats: Vec<Regex>,
+
+    // True if there was a `#! JS` line; the JavaScript the test compiles
+    // to is then checked against `js.ref`.
+    js: bool,
+
+    // The messages from a `#! LOCALE` line, like `#! LOCALE fr`, which
+    // `compiler-output.ref` shows the diagnostics in. The annotations are
+    // still matched against the messages as written.
+    catalog: Option<&'static Catalog>,
+
     // Any `#! FIXME` annotations found
     fixmes: Vec<String>,
 }
@@ -718,7 +1056,7 @@ fn expected_diagnostics(path: &Path) -> eyre::Result<ExpectedDiagnostics> {
     let file_contents = std::fs::read_to_string(path)?;
 
     let diagnostic_marker = regex::Regex::new(
-        r"^(?P<prefix>[^#]*)#!\s*(?P<highlight>\^+)?\s*(?P<type>RUN)?\s*(?P<severity>ERROR|WARNING|INFO)\s*(?P<msg>.*)",
+        r"^(?P<prefix>[^#]*)#!\s*(?P<highlight>\^+)?\s*(?P<type>RUN|LINT)?\s*(?P<severity>ERROR|WARNING|INFO)\s*(?P<msg>.*)",
     )
     .unwrap();
 
@@ -730,11 +1068,29 @@ fn expected_diagnostics(path: &Path) -> eyre::Result<ExpectedDiagnostics> {
 
     let any_output_marker = regex::Regex::new(r"^(?P<prefix>[^#]*)#!\s*OUTPUT ANY").unwrap();
 
+    let lints_marker = regex::Regex::new(r"^\s*#!\s*LINTS(?P<levels>(\s+\S+)*)\s*$").unwrap();
+
+    let stats_marker = regex::Regex::new(r"^\s*#!\s*STATS\s+(?P<msg>.*)").unwrap();
+
+    let config_marker = regex::Regex::new(r"^\s*#!\s*CONFIG(?P<settings>(\s+\S+)*)\s*$").unwrap();
+
+    let js_marker = regex::Regex::new(r"^\s*#!\s*JS\s*$").unwrap();
+
+    let locale_marker = regex::Regex::new(r"^\s*#!\s*LOCALE\s+(?P<locale>\S+)\s*$").unwrap();
+
     let any_marker = regex::Regex::new(r"^[^#]*#!").unwrap();
 
     let mut last_code_line = 1;
     let mut compile_diagnostics = vec![];
     let mut runtime_diagnostics = vec![];
+    let mut lint_diagnostics = vec![];
+    let mut lint_levels = None;
+    let mut config = ExecutionConfig::new();
+    let mut capabilities = Capabilities::default();
+    let mut configured = false;
+    let mut stats = vec![];
+    let mut js = false;
+    let mut catalog = None;
     let mut output = vec![];
     let mut fixmes = vec![];
     let mut any_output_marker_seen = None;
@@ -771,10 +1127,57 @@ fn expected_diagnostics(path: &Path) -> eyre::Result<ExpectedDiagnostics> {
                 "RUN" => {
                     runtime_diagnostics.push(expected);
                 }
+                "LINT" => {
+                    lint_diagnostics.push(expected);
+                }
                 wrong => {
                     eyre::bail!("unexpected diagnostic type {} in {:?}", wrong, path);
                 }
             }
+        } else if let Some(c) = lints_marker.captures(line) {
+            let levels: &mut Vec<_> = lint_levels.get_or_insert_with(Vec::new);
+            for level in c["levels"].split_whitespace() {
+                let Some((lint, level)) = level.split_once('=') else {
+                    eyre::bail!(
+                        "expected `lint=level` in `#! LINTS` on line {}, found `{}`",
+                        line_number,
+                        level
+                    );
+                };
+                levels.push((
+                    crate::lint::parse_lint(lint)?,
+                    crate::lint::parse_level(level)?,
+                ));
+            }
+        } else if let Some(c) = config_marker.captures(line) {
+            configured = true;
+            for setting in c["settings"].split_whitespace() {
+                let Some((key, value)) = setting.split_once('=') else {
+                    eyre::bail!(
+                        "expected `key=value` in `#! CONFIG` on line {}, found `{}`",
+                        line_number,
+                        setting
+                    );
+                };
+                if key == "deny" {
+                    capabilities = capabilities.without(value.parse()?);
+                    continue;
+                }
+                config = configure(config, key, value)
+                    .with_context(|| format!("`#! CONFIG` on line {}", line_number))?;
+            }
+        } else if let Some(c) = stats_marker.captures(line) {
+            stats.push(Regex::new(&c["msg"])?);
+        } else if js_marker.is_match(line) {
+            js = true;
+        } else if let Some(c) = locale_marker.captures(line) {
+            catalog = Some(messages::catalog(&c["locale"]).ok_or_else(|| {
+                eyre::eyre!(
+                    "no messages for the locale `{}` on line {}",
+                    &c["locale"],
+                    line_number
+                )
+            })?);
         } else if any_output_marker.is_match(line) {
             any_output_marker_seen = Some(line_number);
         } else if let Some(c) = output_marker.captures(line) {
@@ -813,9 +1216,23 @@ fn expected_diagnostics(path: &Path) -> eyre::Result<ExpectedDiagnostics> {
         }
     }
 
+    if lint_levels.is_none() && !lint_diagnostics.is_empty() {
+        eyre::bail!(
+            "`#! LINT` annotations in {:?}, which has no `#! LINTS` line",
+            path
+        );
+    }
+
     Ok(ExpectedDiagnostics {
         compile: compile_diagnostics,
         runtime: runtime_diagnostics,
+        lint: lint_diagnostics,
+        lint_levels,
+        config: config.capabilities(capabilities),
+        configured,
+        stats,
+        js,
+        catalog,
         output: if any_output_marker_seen.is_some() {
             None
         } else {
@@ -825,6 +1242,30 @@ fn expected_diagnostics(path: &Path) -> eyre::Result<ExpectedDiagnostics> {
     })
 }
 
+/// Checks that each of the `expected` regexes matches a line of the heap
+/// statistics `report`.
+fn check_stats(report: &str, expected: &[Regex], errors: &mut Errors) {
+    for regex in expected {
+        if !report.lines().any(|line| regex.is_match(line)) {
+            errors.push_result(Err(eyre::eyre!(
+                "no line of the heap statistics matches `{}`:\n{}",
+                regex,
+                report
+            )));
+        }
+    }
+}
+
+/// Applies one `key=value` setting from a `#! CONFIG` line to `config`.
+fn configure(config: ExecutionConfig, key: &str, value: &str) -> eyre::Result<ExecutionConfig> {
+    Ok(match key {
+        "gc_threshold" => config.gc_threshold(value.parse()?),
+        "max_objects" => config.max_objects(value.parse()?),
+        "max_string_bytes" => config.max_string_bytes(value.parse()?),
+        _ => eyre::bail!("unknown setting `{}`", key),
+    })
+}
+
 /// Searches for a `#?` annotation, which indicates that we want to do a
 /// query at a particular point.
 fn expected_queries(path: &Path) -> eyre::Result<Vec<Query>> {
@@ -852,10 +1293,7 @@ fn expected_queries(path: &Path) -> eyre::Result<Vec<Query>> {
             // The column comes from the position of the `^`.
             let column = u32::try_from(c.name("pointer").unwrap().start() + 1).unwrap();
 
-            let query_kind = match &c["kind"] {
-                "HeapGraph" => QueryKind::HeapGraph,
-                k => eyre::bail!("unexpected query kind `{}` on line {}", k, line_number),
-            };
+            let query_kind = QueryKind::parse(&c["kind"], line_number)?;
 
             result.push(Query {
                 line: last_code_line,
@@ -870,10 +1308,7 @@ fn expected_queries(path: &Path) -> eyre::Result<Vec<Query>> {
             let given_column_number: u32 = str::parse(&c["column"])
                 .with_context(|| format!("in query on line {}", line_number))?;
 
-            let query_kind = match &c["kind"] {
-                "HeapGraph" => QueryKind::HeapGraph,
-                k => eyre::bail!("unexpected query kind `{}` on line {}", k, line_number),
-            };
+            let query_kind = QueryKind::parse(&c["kind"], line_number)?;
 
             result.push(Query {
                 line: given_line_number,
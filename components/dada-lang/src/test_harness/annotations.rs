@@ -0,0 +1,143 @@
+//! With `--bless`, a test whose diagnostics or output don't match its `#!`
+//! annotations has those annotations rewritten, the way its `.ref` files
+//! are. The new annotations match the actual diagnostics and output
+//! exactly, so regexes written by hand are replaced.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use dada_ir::diagnostic::Diagnostic;
+use eyre::Context;
+use regex::Regex;
+
+use super::ActualDiagnostic;
+
+/// The annotations that describe what a test actually did, keyed by the
+/// (1-based) line that each applies to.
+#[derive(Debug, Default)]
+pub(super) struct BlessedAnnotations {
+    by_line: BTreeMap<u32, Vec<Annotation>>,
+}
+
+#[derive(Debug)]
+struct Annotation {
+    /// The (1-based) column and width to mark with `^`, if any.
+    highlight: Option<(usize, usize)>,
+
+    /// What follows the `#!` (and the `^` markers), like `ERROR ...`.
+    text: String,
+}
+
+impl BlessedAnnotations {
+    /// Records a `#! ERROR` annotation for `diagnostic`, with its `type_`
+    /// (like `#! RUN ERROR`) if given. If it is on one line, `^` markers
+    /// give its span.
+    pub(super) fn diagnostic(
+        &mut self,
+        db: &dada_db::Db,
+        diagnostic: &Diagnostic,
+        type_: Option<&str>,
+    ) {
+        let (_, start, end) = db.line_columns(diagnostic.span);
+        let highlight = if start.line1() == end.line1() && end.column1() > start.column1() {
+            Some((
+                start.column1() as usize,
+                (end.column1() - start.column1()) as usize,
+            ))
+        } else {
+            None
+        };
+        let text = format!(
+            "{}{} {}",
+            type_.map_or(String::new(), |type_| format!("{type_} ")),
+            diagnostic.severity(db),
+            escape(&diagnostic.message)
+        );
+        self.push(start.line1(), Annotation { highlight, text });
+    }
+
+    /// Records a `#! OUTPUT` annotation for `text`, printed by `line1`.
+    pub(super) fn output(&mut self, line1: u32, text: &str) {
+        let text = format!("OUTPUT {}", escape(text.trim_end_matches('\n')));
+        self.push(
+            line1,
+            Annotation {
+                highlight: None,
+                text,
+            },
+        );
+    }
+
+    fn push(&mut self, line1: u32, annotation: Annotation) {
+        self.by_line.entry(line1).or_default().push(annotation);
+    }
+
+    /// Rewrites the test at `path`, removing its `#!` diagnostic and output
+    /// annotations and putting the recorded ones after the lines they apply
+    /// to. Other annotations, like `#! OUTPUT ANY` and `#! FIXME`, are kept.
+    pub(super) fn write(&self, path: &Path) -> eyre::Result<()> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading `{}`", path.display()))?;
+
+        let annotation_marker = Regex::new(
+            r"^(?P<prefix>[^#]*)#!\s*(\^+)?\s*(RUN|LINT)?\s*(ERROR|WARNING|INFO|OUTPUT\s)",
+        )
+        .unwrap();
+        let any_output_marker = Regex::new(r"^[^#]*#!\s*OUTPUT ANY").unwrap();
+        let any_marker = Regex::new(r"^[^#]*#!").unwrap();
+
+        let mut blessed = String::new();
+        for (line, line_number) in contents.lines().zip(1..) {
+            let code = match annotation_marker.captures(line) {
+                Some(c) if !any_output_marker.is_match(line) => {
+                    let prefix = c["prefix"].trim_end();
+                    if prefix.is_empty() {
+                        continue;
+                    }
+                    prefix
+                }
+                _ => line,
+            };
+            blessed.push_str(code);
+            blessed.push('\n');
+
+            // As in `expected_diagnostics`, annotations on lines of their
+            // own apply to the code line before them.
+            if !any_marker.is_match(code) {
+                let indent = code.len() - code.trim_start().len();
+                for annotation in self.by_line.get(&line_number).into_iter().flatten() {
+                    blessed.push_str(&annotation.render(&code[..indent]));
+                    blessed.push('\n');
+                }
+            }
+        }
+        if !contents.ends_with('\n') {
+            blessed.pop();
+        }
+
+        std::fs::write(path, blessed).with_context(|| format!("writing `{}`", path.display()))
+    }
+}
+
+impl Annotation {
+    /// The annotation on a line of its own, starting with `indent`. The
+    /// `^` markers are left out if they would have to overlap the `#!`.
+    fn render(&self, indent: &str) -> String {
+        let mut line = format!("{}#!", indent);
+        match self.highlight {
+            Some((column1, width)) if column1 > line.len() => {
+                line.push_str(&" ".repeat(column1 - 1 - line.len()));
+                line.push_str(&"^".repeat(width));
+                line.push(' ');
+            }
+            _ => line.push(' '),
+        }
+        line.push_str(&self.text);
+        line
+    }
+}
+
+/// A regex matching `text`, on one line.
+fn escape(text: &str) -> String {
+    regex::escape(text).replace('\n', r"\n")
+}
@@ -0,0 +1,133 @@
+//! `dada test --coverage`: which lines of the tests ran, written as an
+//! lcov tracefile (for `genhtml`, editor plugins, and CI services) or as a
+//! standalone HTML page that highlights the lines that never ran.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use dada_execute::coverage::Coverage;
+use dada_ir::filename::Filename;
+use eyre::Context;
+
+/// The number of times each line with code on it was executed, for each
+/// source file, summed over all the tests that ran it.
+#[derive(Debug, Default)]
+pub(super) struct CoverageReport {
+    files: BTreeMap<String, BTreeMap<u32, u64>>,
+}
+
+impl CoverageReport {
+    /// Adds what `coverage` recorded for the code in `filename`.
+    pub(super) fn add(&mut self, db: &dada_db::Db, filename: Filename, coverage: &Coverage) {
+        let lines = self
+            .files
+            .entry(filename.as_str(db).to_string())
+            .or_default();
+        for (line, count) in coverage.line_hits(db, filename) {
+            *lines.entry(line).or_default() += count;
+        }
+    }
+
+    /// The number of lines with code on them that were executed, and the
+    /// number of lines with code on them.
+    pub(super) fn summary(&self) -> (usize, usize) {
+        let counts: Vec<u64> = self
+            .files
+            .values()
+            .flat_map(|lines| lines.values().copied())
+            .collect();
+        let hit = counts.iter().filter(|&&count| count > 0).count();
+        (hit, counts.len())
+    }
+
+    /// Writes the report to `path`: as HTML if its extension is `.html`,
+    /// and otherwise as an lcov tracefile.
+    pub(super) fn write(&self, path: &Path) -> eyre::Result<()> {
+        let report = if path.extension() == Some("html".as_ref()) {
+            self.html()?
+        } else {
+            self.lcov()
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("creating `{}`", parent.display()))?;
+        }
+        std::fs::write(path, report).with_context(|| format!("writing `{}`", path.display()))
+    }
+
+    fn lcov(&self) -> String {
+        let mut output = String::from("TN:\n");
+        for (path, lines) in &self.files {
+            output.push_str(&format!("SF:{}\n", path));
+            for (line, count) in lines {
+                output.push_str(&format!("DA:{},{}\n", line, count));
+            }
+            output.push_str(&format!("LF:{}\n", lines.len()));
+            let hit = lines.values().filter(|&&count| count > 0).count();
+            output.push_str(&format!("LH:{}\n", hit));
+            output.push_str("end_of_record\n");
+        }
+        output
+    }
+
+    /// A page with the source of each file, where lines that ran are green
+    /// and lines that never ran are red.
+    fn html(&self) -> eyre::Result<String> {
+        let (hit, found) = self.summary();
+        let mut output = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Coverage</title>\n<style>\n\
+             pre { margin: 0; }\n\
+             .hit { background: #dfd; }\n\
+             .missed { background: #fdd; }\n\
+             .line { color: #888; display: inline-block; width: 4em; }\n\
+             </style>\n</head>\n<body>\n",
+        );
+        output.push_str(&format!(
+            "<h1>Coverage: {} of {} lines ({})</h1>\n",
+            hit,
+            found,
+            percentage(hit, found)
+        ));
+
+        for (path, lines) in &self.files {
+            let source =
+                std::fs::read_to_string(path).with_context(|| format!("reading `{}`", path))?;
+            let file_hit = lines.values().filter(|&&count| count > 0).count();
+            output.push_str(&format!(
+                "<h2>{} ({})</h2>\n",
+                escape(path),
+                percentage(file_hit, lines.len())
+            ));
+            for (text, line) in source.lines().zip(1..) {
+                let class = match lines.get(&line) {
+                    Some(0) => " class=\"missed\"",
+                    Some(_) => " class=\"hit\"",
+                    None => "",
+                };
+                output.push_str(&format!(
+                    "<pre{}><span class=\"line\">{}</span>{}</pre>\n",
+                    class,
+                    line,
+                    escape(text)
+                ));
+            }
+        }
+
+        output.push_str("</body>\n</html>\n");
+        Ok(output)
+    }
+}
+
+fn percentage(hit: usize, found: usize) -> String {
+    if found == 0 {
+        return "no code".to_string();
+    }
+    format!("{:.1}%", 100.0 * hit as f64 / found as f64)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
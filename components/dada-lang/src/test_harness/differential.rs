@@ -0,0 +1,321 @@
+//! Differential testing: runs each test under the interpreter and under
+//! each backend, and reports any difference in what the program did.
+
+use std::process::Command;
+
+use dada_execute::config::ExecutionConfig;
+use dada_execute::kernel::BufferKernel;
+use dada_ir::{filename::Filename, function::Function};
+use dada_jit::CraneliftJit;
+use regex::Regex;
+
+use super::Errors;
+
+/// A way of running a program other than the plain interpreter.
+#[derive(Copy, Clone, Debug)]
+enum Backend {
+    /// The interpreter, with the JIT compiling every function that it can.
+    Jit,
+
+    /// The interpreter, running the program loaded from bytecode.
+    Bytecode,
+
+    /// The JavaScript backend, run with `node` (if it is installed).
+    Js,
+
+    /// The wasm backend, compiled for WASI and run with wasmtime.
+    Wasm,
+}
+
+impl Backend {
+    const ALL: &'static [Backend] = &[Backend::Jit, Backend::Bytecode, Backend::Js, Backend::Wasm];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Backend::Jit => "jit",
+            Backend::Bytecode => "bytecode",
+            Backend::Js => "js",
+            Backend::Wasm => "wasm",
+        }
+    }
+}
+
+/// What a program did when it ran.
+#[derive(Debug)]
+struct Observation {
+    output: String,
+
+    /// The message of the runtime error that stopped the program, if any.
+    error: Option<String>,
+
+    /// The heap statistics report, if the backend records them (only
+    /// the interpreter does, when it runs without the JIT).
+    heap_stats: Option<String>,
+}
+
+/// What came of trying to run a program under a backend.
+#[derive(Debug)]
+enum Run {
+    Observed(Observation),
+
+    /// The backend could not run this program, for the given reason.
+    Skipped(&'static str),
+}
+
+impl super::Options {
+    /// Runs the `main` function of `filename` under the interpreter and
+    /// under each backend, pushing an error for each difference.
+    ///
+    /// Not everything is compared for every backend: the JavaScript
+    /// backend does not track permissions, so its output omits them and it
+    /// is only expected to agree with the interpreter on programs that run
+    /// without errors; and the wasm backend traps rather than reporting
+    /// errors, so only whether it stopped with an error is compared. Heap
+    /// statistics are compared for every backend that reports them.
+    ///
+    /// A backend that cannot compile the program is skipped, with a
+    /// warning naming the backend; but as every backend was asked for,
+    /// it is an error if `node` is not installed to run the JavaScript.
+    pub(super) async fn check_differential(
+        &self,
+        db: &dada_db::Db,
+        filename: Filename,
+        errors: &mut Errors,
+    ) -> eyre::Result<()> {
+        let Some(function) = db.function_named(filename, "main") else {
+            return Ok(());
+        };
+        let expected = observe_interpreter(db, function, false).await?;
+
+        for &backend in Backend::ALL {
+            let actual = match self
+                .observe_backend(db, filename, function, backend)
+                .await?
+            {
+                Run::Observed(actual) => actual,
+                Run::Skipped(reason) => {
+                    tracing::warn!(
+                        "test `{}` skipped the {} backend: {}",
+                        filename.as_str(db),
+                        backend.as_str(),
+                        reason
+                    );
+                    continue;
+                }
+            };
+
+            match backend {
+                Backend::Jit | Backend::Bytecode => {
+                    push_if_different(errors, backend, "output", &expected.output, &actual.output);
+                    push_if_different(
+                        errors,
+                        backend,
+                        "error",
+                        &format!("{:?}", expected.error),
+                        &format!("{:?}", actual.error),
+                    );
+                }
+                Backend::Js => {
+                    if expected.error.is_some() {
+                        continue;
+                    }
+                    push_if_different(
+                        errors,
+                        backend,
+                        "output",
+                        &strip_permissions(&expected.output),
+                        &strip_permissions(&actual.output),
+                    );
+                    push_if_different(
+                        errors,
+                        backend,
+                        "error",
+                        "None",
+                        &format!("{:?}", actual.error),
+                    );
+                }
+                Backend::Wasm => {
+                    push_if_different(errors, backend, "output", &expected.output, &actual.output);
+                    push_if_different(
+                        errors,
+                        backend,
+                        "error",
+                        stopped(&expected.error),
+                        stopped(&actual.error),
+                    );
+                }
+            }
+
+            if let (Some(expected_stats), Some(actual_stats)) =
+                (&expected.heap_stats, &actual.heap_stats)
+            {
+                push_if_different(errors, backend, "heap stats", expected_stats, actual_stats);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs `function` under `backend`, or says why the backend cannot
+    /// run this program.
+    async fn observe_backend(
+        &self,
+        db: &dada_db::Db,
+        filename: Filename,
+        function: Function,
+        backend: Backend,
+    ) -> eyre::Result<Run> {
+        match backend {
+            Backend::Jit => Ok(Run::Observed(observe_interpreter(db, function, true).await?)),
+            Backend::Bytecode => {
+                let mut loaded_db = dada_db::Db::default();
+                let loaded_filename = loaded_db.load_bytecode(db.bytecode(filename))?;
+                let Some(function) = loaded_db.function_named(loaded_filename, "main") else {
+                    eyre::bail!("no `main` function after loading bytecode");
+                };
+                Ok(Run::Observed(observe_interpreter(&loaded_db, function, false).await?))
+            }
+            Backend::Js => {
+                let Ok(script) = dada_codegen_js::compile_module(db, filename) else {
+                    return Ok(Run::Skipped("uses something the backend does not support"));
+                };
+                Ok(Run::Observed(observe_node(filename.as_str(db), &script)?))
+            }
+            Backend::Wasm => {
+                let Ok(wat) = dada_codegen_wasm::compile_wasi_module(db, filename) else {
+                    return Ok(Run::Skipped("uses something the backend does not support"));
+                };
+                let (output, trap) = super::wasm::run_wasi(&wat)?;
+                Ok(Run::Observed(Observation {
+                    output,
+                    error: trap,
+                    heap_stats: None,
+                }))
+            }
+        }
+    }
+}
+
+async fn observe_interpreter(
+    db: &dada_db::Db,
+    function: Function,
+    jit: bool,
+) -> eyre::Result<Observation> {
+    // Code compiled by the JIT keeps values out of the heap, so its heap
+    // statistics would not match.
+    let mut kernel = BufferKernel::new()
+        .config(ExecutionConfig::new().deterministic(true))
+        .record_heap_stats(!jit);
+    if jit {
+        kernel = kernel.jit(CraneliftJit::new().threshold(0));
+    }
+    let error = match kernel.interpret_main(db, function).await {
+        Ok(()) => None,
+        Err(err) => match err.downcast_ref::<dada_execute::DiagnosticError>() {
+            Some(err) => Some(err.diagnostic().message.clone()),
+            None => eyre::bail!("unexpected runtime error type: {:?}", err),
+        },
+    };
+    Ok(Observation {
+        output: kernel.take_buffer(),
+        error,
+        heap_stats: kernel.take_heap_stats().map(|stats| stats.report(db)),
+    })
+}
+
+/// Runs the compiled `script` with `node`.
+fn observe_node(name: &str, script: &str) -> eyre::Result<Observation> {
+    let script_path = std::env::temp_dir().join(format!(
+        "dada-differential-{}-{}.js",
+        std::process::id(),
+        name.replace(|c: char| !c.is_alphanumeric(), "_"),
+    ));
+    std::fs::write(&script_path, script)?;
+    let result = Command::new("node").arg(&script_path).output();
+    std::fs::remove_file(&script_path)?;
+
+    let output = match result {
+        Ok(output) => output,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            eyre::bail!("`node` not found; it is needed to run the js backend with --differential")
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    // The runtime reports errors from the program as `error: ...` on
+    // stderr; anything else there means the script itself failed.
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let error = if !output.status.success() {
+        Some(format!("node exited with {}: {}", output.status, stderr))
+    } else {
+        stderr
+            .lines()
+            .find_map(|line| line.strip_prefix("error: "))
+            .map(str::to_string)
+    };
+    Ok(Observation {
+        output: String::from_utf8_lossy(&output.stdout).into_owned(),
+        error,
+        heap_stats: None,
+    })
+}
+
+/// Describes whether a program stopped with an error, for comparison
+/// with backends whose errors are not reported the same way.
+fn stopped(error: &Option<String>) -> &'static str {
+    match error {
+        Some(_) => "stopped with an error",
+        None => "ran to completion",
+    }
+}
+
+/// Removes the permissions (like `my`) that the interpreter includes when
+/// printing objects, for comparison with backends that do not track them.
+fn strip_permissions(output: &str) -> String {
+    let permission = Regex::new(r"\b(?:my|our|leased|shleased)(?: |(\())").unwrap();
+    permission.replace_all(output, "$1").into_owned()
+}
+
+fn push_if_different(
+    errors: &mut Errors,
+    backend: Backend,
+    what: &'static str,
+    expected: &str,
+    actual: &str,
+) {
+    if expected != actual {
+        errors.push(BackendDiffers {
+            backend: backend.as_str(),
+            what,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        });
+    }
+}
+
+#[derive(Debug)]
+struct BackendDiffers {
+    backend: &'static str,
+    what: &'static str,
+    expected: String,
+    actual: String,
+}
+
+impl std::error::Error for BackendDiffers {}
+
+impl std::fmt::Display for BackendDiffers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} differs between the interpreter and the {} backend",
+            self.what, self.backend
+        )?;
+        write!(
+            f,
+            "{}",
+            similar::TextDiff::from_lines(&self.expected, &self.actual)
+                .unified_diff()
+                .header("interpreter", self.backend)
+        )
+    }
+}
@@ -1,13 +1,32 @@
 use lsp_server::{Notification, Request, RequestId};
-use lsp_types::notification::{DidOpenTextDocument, PublishDiagnostics};
-use lsp_types::request::Initialize;
-use lsp_types::{ClientCapabilities, Diagnostic, DidOpenTextDocumentParams, TextDocumentItem, Url};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, PublishDiagnostics,
+};
+use lsp_types::request::{
+    CodeActionRequest, Completion, DocumentSymbolRequest, FoldingRangeRequest, GotoDefinition,
+    HoverRequest, Initialize, References, Rename, SelectionRangeRequest, SemanticTokensFullRequest,
+    SignatureHelpRequest, WorkspaceSymbol,
+};
+use lsp_types::{
+    ClientCapabilities, CodeActionContext, CodeActionParams, CodeActionResponse, CompletionParams,
+    CompletionResponse, Diagnostic, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentSymbolParams, DocumentSymbolResponse, FoldingRange,
+    FoldingRangeParams, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverParams, Location,
+    Position, Range, ReferenceContext, ReferenceParams, RenameParams, SelectionRange,
+    SelectionRangeParams, SemanticTokensParams, SemanticTokensResult, SignatureHelp,
+    SignatureHelpParams, SymbolInformation, TextDocumentContentChangeEvent, TextDocumentIdentifier,
+    TextDocumentItem, TextDocumentPositionParams, Url, VersionedTextDocumentIdentifier,
+    WorkspaceEdit, WorkspaceSymbolParams,
+};
 use serde::{Deserialize, Serialize};
 use std::io::{Read, Write};
 use std::path::Path;
 use std::process::{Command, Stdio};
 pub(crate) struct ChildSession {
     child: std::process::Child,
+
+    /// The id of the last request sent.
+    last_id: i32,
 }
 
 impl Drop for ChildSession {
@@ -26,7 +45,7 @@ impl ChildSession {
             .spawn()
             .expect("Failed to spawn child process");
 
-        ChildSession { child }
+        ChildSession { child, last_id: 0 }
     }
     /// Helper function to do the work of sending a result back to the IDE
     fn send_notification<T: lsp_types::notification::Notification>(
@@ -54,10 +73,22 @@ impl ChildSession {
 
         self.send_any(msg)?;
 
-        let response: JsonRpcResponse<T::Result> = self.receive()?;
+        // The server publishes diagnostics whenever it likes, so skip any
+        // notifications that come before the response.
+        loop {
+            let message: serde_json::Value = self.receive()?;
+            if message.get("method").is_some() {
+                continue;
+            }
+            let response: JsonRpcResponse<T::Result> = serde_json::from_value(message)?;
+            assert_eq!(response.id, id);
+            return Ok(response.result);
+        }
+    }
 
-        assert_eq!(response.id, id);
-        Ok(response.result)
+    fn next_id(&mut self) -> RequestId {
+        self.last_id += 1;
+        RequestId::from(self.last_id)
     }
 
     fn send_any(&mut self, msg: impl Serialize) -> eyre::Result<()> {
@@ -151,17 +182,9 @@ impl ChildSession {
 
     pub fn send_open(&mut self, filepath: &Path) -> eyre::Result<()> {
         let contents = std::fs::read_to_string(filepath)?;
-        let path = std::path::Path::new(filepath).canonicalize()?;
-
         self.send_notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
             text_document: TextDocumentItem {
-                uri: Url::parse(&format!(
-                    "file:///{}",
-                    path.to_str().ok_or_else(|| std::io::Error::new(
-                        std::io::ErrorKind::InvalidData,
-                        "Bad filepath"
-                    ))?
-                ))?,
+                uri: file_uri(filepath)?,
                 language_id: "dada".into(),
                 version: 1,
                 text: contents,
@@ -169,10 +192,321 @@ impl ChildSession {
         })
     }
 
-    pub fn receive_errors(&mut self) -> eyre::Result<Vec<Diagnostic>> {
-        let result = self.receive_notification::<PublishDiagnostics>()?;
-        Ok(result.diagnostics)
+    /// Replaces `range` of the text of `filepath` with `text`, as a client
+    /// does for each edit.
+    pub fn send_change(
+        &mut self,
+        filepath: &Path,
+        version: i32,
+        range: Range,
+        text: &str,
+    ) -> eyre::Result<()> {
+        self.send_notification::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier {
+                uri: file_uri(filepath)?,
+                version,
+            },
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: Some(range),
+                range_length: None,
+                text: text.to_string(),
+            }],
+        })
+    }
+
+    pub fn send_close(&mut self, filepath: &Path) -> eyre::Result<()> {
+        self.send_notification::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier {
+                uri: file_uri(filepath)?,
+            },
+        })
+    }
+
+    /// The diagnostics for `filepath`. The server publishes them for every
+    /// file it has loaded, so those for other files are skipped.
+    pub fn receive_errors(&mut self, filepath: &Path) -> eyre::Result<Vec<Diagnostic>> {
+        let uri = file_uri(filepath)?;
+        loop {
+            let result = self.receive_notification::<PublishDiagnostics>()?;
+            if result.uri == uri {
+                return Ok(result.diagnostics);
+            }
+        }
+    }
+
+    pub fn definition(
+        &mut self,
+        filepath: &Path,
+        position: Position,
+    ) -> eyre::Result<Option<GotoDefinitionResponse>> {
+        let id = self.next_id();
+        self.send_request::<GotoDefinition>(
+            id,
+            GotoDefinitionParams {
+                text_document_position_params: text_document_position(filepath, position)?,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+        )
     }
+
+    pub fn references(
+        &mut self,
+        filepath: &Path,
+        position: Position,
+    ) -> eyre::Result<Option<Vec<Location>>> {
+        let id = self.next_id();
+        self.send_request::<References>(
+            id,
+            ReferenceParams {
+                text_document_position: text_document_position(filepath, position)?,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+                context: ReferenceContext {
+                    include_declaration: true,
+                },
+            },
+        )
+    }
+
+    pub fn rename(
+        &mut self,
+        filepath: &Path,
+        position: Position,
+        new_name: &str,
+    ) -> eyre::Result<Option<WorkspaceEdit>> {
+        let id = self.next_id();
+        self.send_request::<Rename>(
+            id,
+            RenameParams {
+                text_document_position: text_document_position(filepath, position)?,
+                new_name: new_name.to_string(),
+                work_done_progress_params: Default::default(),
+            },
+        )
+    }
+
+    pub fn completion(
+        &mut self,
+        filepath: &Path,
+        position: Position,
+    ) -> eyre::Result<Option<CompletionResponse>> {
+        let id = self.next_id();
+        self.send_request::<Completion>(
+            id,
+            CompletionParams {
+                text_document_position: text_document_position(filepath, position)?,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+                context: None,
+            },
+        )
+    }
+
+    pub fn hover(&mut self, filepath: &Path, position: Position) -> eyre::Result<Option<Hover>> {
+        let id = self.next_id();
+        self.send_request::<HoverRequest>(
+            id,
+            HoverParams {
+                text_document_position_params: text_document_position(filepath, position)?,
+                work_done_progress_params: Default::default(),
+            },
+        )
+    }
+
+    pub fn document_symbols(
+        &mut self,
+        filepath: &Path,
+    ) -> eyre::Result<Option<DocumentSymbolResponse>> {
+        let id = self.next_id();
+        self.send_request::<DocumentSymbolRequest>(
+            id,
+            DocumentSymbolParams {
+                text_document: TextDocumentIdentifier {
+                    uri: file_uri(filepath)?,
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+        )
+    }
+
+    pub fn workspace_symbols(
+        &mut self,
+        query: &str,
+    ) -> eyre::Result<Option<Vec<SymbolInformation>>> {
+        let id = self.next_id();
+        self.send_request::<WorkspaceSymbol>(
+            id,
+            WorkspaceSymbolParams {
+                query: query.to_string(),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+        )
+    }
+
+    pub fn semantic_tokens(
+        &mut self,
+        filepath: &Path,
+    ) -> eyre::Result<Option<SemanticTokensResult>> {
+        let id = self.next_id();
+        self.send_request::<SemanticTokensFullRequest>(
+            id,
+            SemanticTokensParams {
+                text_document: TextDocumentIdentifier {
+                    uri: file_uri(filepath)?,
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+        )
+    }
+
+    pub fn signature_help(
+        &mut self,
+        filepath: &Path,
+        position: Position,
+    ) -> eyre::Result<Option<SignatureHelp>> {
+        let id = self.next_id();
+        self.send_request::<SignatureHelpRequest>(
+            id,
+            SignatureHelpParams {
+                context: None,
+                text_document_position_params: text_document_position(filepath, position)?,
+                work_done_progress_params: Default::default(),
+            },
+        )
+    }
+
+    /// The code actions for the diagnostics at `position`.
+    pub fn code_actions(
+        &mut self,
+        filepath: &Path,
+        position: Position,
+    ) -> eyre::Result<Option<CodeActionResponse>> {
+        let id = self.next_id();
+        self.send_request::<CodeActionRequest>(
+            id,
+            CodeActionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: file_uri(filepath)?,
+                },
+                range: Range::new(position, position),
+                context: CodeActionContext {
+                    diagnostics: vec![],
+                    only: None,
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+        )
+    }
+
+    pub fn folding_ranges(&mut self, filepath: &Path) -> eyre::Result<Option<Vec<FoldingRange>>> {
+        let id = self.next_id();
+        self.send_request::<FoldingRangeRequest>(
+            id,
+            FoldingRangeParams {
+                text_document: TextDocumentIdentifier {
+                    uri: file_uri(filepath)?,
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+        )
+    }
+
+    pub fn selection_ranges(
+        &mut self,
+        filepath: &Path,
+        position: Position,
+    ) -> eyre::Result<Option<Vec<SelectionRange>>> {
+        let id = self.next_id();
+        self.send_request::<SelectionRangeRequest>(
+            id,
+            SelectionRangeParams {
+                text_document: TextDocumentIdentifier {
+                    uri: file_uri(filepath)?,
+                },
+                positions: vec![position],
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+        )
+    }
+
+    /// The inlay hints for all of `filepath`, whose text is `source`.
+    pub fn inlay_hints(
+        &mut self,
+        filepath: &Path,
+        source: &str,
+    ) -> eyre::Result<Option<Vec<InlayHint>>> {
+        let id = self.next_id();
+        let last_line = source.lines().last().unwrap_or_default();
+        let end = Position::new(
+            source.lines().count().saturating_sub(1) as u32,
+            last_line.encode_utf16().count() as u32,
+        );
+        self.send_request::<InlayHintRequest>(
+            id,
+            InlayHintParams {
+                text_document: TextDocumentIdentifier {
+                    uri: file_uri(filepath)?,
+                },
+                range: Range::new(Position::new(0, 0), end),
+            },
+        )
+    }
+}
+
+/// The URI that the server knows `filepath` by.
+pub(crate) fn file_uri(filepath: &Path) -> eyre::Result<Url> {
+    let path = filepath.canonicalize()?;
+    Ok(Url::parse(&format!(
+        "file:///{}",
+        path.to_str()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "Bad filepath"))?
+    ))?)
+}
+
+fn text_document_position(
+    filepath: &Path,
+    position: Position,
+) -> eyre::Result<TextDocumentPositionParams> {
+    Ok(TextDocumentPositionParams {
+        text_document: TextDocumentIdentifier {
+            uri: file_uri(filepath)?,
+        },
+        position,
+    })
+}
+
+/// The `textDocument/inlayHint` request, which is newer than the version
+/// of `lsp-types` we use (see `dada_lsp::inlay_hints`).
+pub(crate) enum InlayHintRequest {}
+
+impl lsp_types::request::Request for InlayHintRequest {
+    type Params = InlayHintParams;
+    type Result = Option<Vec<InlayHint>>;
+    const METHOD: &'static str = "textDocument/inlayHint";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InlayHintParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InlayHint {
+    pub position: Position,
+    pub label: String,
+
+    /// 1 for a type (which is how specifiers are sent) or 2 for a parameter.
+    pub kind: u32,
 }
 
 /// The command given by the IDE to the LSP server. These represent the actions of the user in the IDE,
@@ -0,0 +1,490 @@
+//! Queries answered by the language server (see [`QueryKind::is_lsp`]).
+//! The response is written out with the text of each range in it, so a
+//! range that is off (e.g., because columns were counted in bytes rather
+//! than in UTF-16 code units) shows up in the `.ref` file.
+
+use std::path::Path;
+
+use lsp_types::{
+    CodeActionOrCommand, CompletionResponse, Diagnostic, DocumentSymbol, DocumentSymbolResponse,
+    FoldingRange, GotoDefinitionResponse, HoverContents, Location, ParameterLabel, Position, Range,
+    SemanticToken, SemanticTokensResult, SignatureHelp, SymbolInformation,
+};
+
+use super::{
+    lsp_client::{self, ChildSession},
+    Errors, Query, QueryKind,
+};
+
+impl super::Options {
+    #[tracing::instrument(level = "Debug", skip(self, lsp_client, errors))]
+    pub(super) fn perform_lsp_query(
+        &self,
+        lsp_client: &mut ChildSession,
+        path: &Path,
+        query: &Query,
+        query_index: usize,
+        errors: &mut Errors,
+    ) -> eyre::Result<()> {
+        let source_path = path.with_extension("dada");
+        let source = std::fs::read_to_string(&source_path)?;
+        let position = lsp_position(&source, query.line, query.column);
+
+        let actual_output = match query.kind {
+            QueryKind::Definition => match lsp_client.definition(&source_path, position)? {
+                Some(GotoDefinitionResponse::Scalar(location)) => {
+                    describe_locations(&source, vec![location])
+                }
+                Some(GotoDefinitionResponse::Array(locations)) => {
+                    describe_locations(&source, locations)
+                }
+                Some(GotoDefinitionResponse::Link(links)) => describe_ranges(
+                    &source,
+                    links.iter().map(|link| link.target_selection_range),
+                ),
+                None => "no definition\n".to_string(),
+            },
+            QueryKind::References => match lsp_client.references(&source_path, position)? {
+                Some(locations) => describe_locations(&source, locations),
+                None => "no references\n".to_string(),
+            },
+            QueryKind::Rename => match lsp_client.rename(&source_path, position, "renamed")? {
+                Some(edit) => describe_ranges(
+                    &source,
+                    edit.changes
+                        .iter()
+                        .flat_map(|changes| changes.values().flatten())
+                        .map(|edit| edit.range),
+                ),
+                None => "nothing to rename\n".to_string(),
+            },
+            QueryKind::Completion => {
+                let mut items = match lsp_client.completion(&source_path, position)? {
+                    Some(CompletionResponse::Array(items)) => items,
+                    Some(CompletionResponse::List(list)) => list.items,
+                    None => vec![],
+                };
+                items.sort_by(|a, b| a.label.cmp(&b.label));
+                items
+                    .iter()
+                    .map(|item| match item.kind {
+                        Some(kind) => format!("{} ({:?})\n", item.label, kind),
+                        None => format!("{}\n", item.label),
+                    })
+                    .collect()
+            }
+            QueryKind::Hover => match lsp_client.hover(&source_path, position)? {
+                Some(hover) => {
+                    let mut output = describe_ranges(&source, hover.range.into_iter());
+                    match hover.contents {
+                        HoverContents::Markup(markup) => output.push_str(&markup.value),
+                        contents => output.push_str(&format!("{:?}", contents)),
+                    }
+                    if !output.ends_with('\n') {
+                        output.push('\n');
+                    }
+                    output
+                }
+                None => "no hover\n".to_string(),
+            },
+            QueryKind::DocumentSymbols => match lsp_client.document_symbols(&source_path)? {
+                Some(DocumentSymbolResponse::Nested(symbols)) => {
+                    let mut output = String::new();
+                    describe_document_symbols(&source, &symbols, 0, &mut output);
+                    output
+                }
+                Some(DocumentSymbolResponse::Flat(symbols)) => {
+                    describe_symbol_information(&source, &symbols)
+                }
+                None => "no symbols\n".to_string(),
+            },
+            QueryKind::WorkspaceSymbols => {
+                let name = word_at(&source, query.line, query.column);
+                describe_workspace_symbols(lsp_client, &source_path, &source, name)?
+            }
+            QueryKind::SemanticTokens => match lsp_client.semantic_tokens(&source_path)? {
+                Some(SemanticTokensResult::Tokens(tokens)) => {
+                    describe_semantic_tokens(&source, &tokens.data)
+                }
+                Some(SemanticTokensResult::Partial(tokens)) => {
+                    describe_semantic_tokens(&source, &tokens.data)
+                }
+                None => "no semantic tokens\n".to_string(),
+            },
+            QueryKind::SignatureHelp => match lsp_client.signature_help(&source_path, position)? {
+                Some(help) => describe_signature_help(&help),
+                None => "no signature help\n".to_string(),
+            },
+            QueryKind::InlayHints => match lsp_client.inlay_hints(&source_path, &source)? {
+                Some(hints) => describe_inlay_hints(&hints),
+                None => "no inlay hints\n".to_string(),
+            },
+            QueryKind::CodeActions => match lsp_client.code_actions(&source_path, position)? {
+                Some(actions) if !actions.is_empty() => describe_code_actions(&source, &actions),
+                _ => "no code actions\n".to_string(),
+            },
+            QueryKind::FoldingRanges => match lsp_client.folding_ranges(&source_path)? {
+                Some(ranges) => describe_folding_ranges(&ranges),
+                None => "no folding ranges\n".to_string(),
+            },
+            QueryKind::SelectionRanges => {
+                let ranges = lsp_client.selection_ranges(&source_path, position)?;
+                let mut output = String::new();
+                let mut range = ranges.unwrap_or_default().into_iter().next().map(Box::new);
+                while let Some(selection_range) = range {
+                    output.push_str(&describe_range(&source, selection_range.range));
+                    output.push('\n');
+                    range = selection_range.parent;
+                }
+                output
+            }
+            QueryKind::Close => {
+                lsp_client.send_close(&source_path)?;
+                lsp_client.receive_errors(&source_path)?;
+                let name = word_at(&source, query.line, query.column);
+                let output = describe_workspace_symbols(lsp_client, &source_path, &source, name)?;
+                lsp_client.send_open(&source_path)?;
+                lsp_client.receive_errors(&source_path)?;
+                output
+            }
+            QueryKind::Edit => {
+                let range = word_range(&source, query.line, query.column);
+                let (start, end) = (
+                    byte_offset(&source, range.start),
+                    byte_offset(&source, range.end),
+                );
+                let edited = format!("{}renamed{}", &source[..start], &source[end..]);
+                lsp_client.send_change(&source_path, 2, range, "renamed")?;
+                let edited_diagnostics = lsp_client.receive_errors(&source_path)?;
+
+                let renamed_range = Range::new(
+                    range.start,
+                    Position::new(
+                        range.start.line,
+                        range.start.character + "renamed".len() as u32,
+                    ),
+                );
+                lsp_client.send_change(&source_path, 3, renamed_range, &source[start..end])?;
+                let undone_diagnostics = lsp_client.receive_errors(&source_path)?;
+
+                let mut output = describe_diagnostics(&edited, &edited_diagnostics);
+                output.push_str("after undoing the edit:\n");
+                output.push_str(&describe_diagnostics(&source, &undone_diagnostics));
+                output
+            }
+            QueryKind::HeapGraph => unreachable!("heap graph queries are not sent to the server"),
+        };
+
+        let output_matched = query.message.is_match(&actual_output);
+
+        let ref_path = path.join(format!("{:?}-{query_index}.ref", query.kind));
+        self.check_output_against_ref_file(actual_output, &ref_path, errors)?;
+
+        if !output_matched {
+            eyre::bail!("query regex `{:?}` did not match the output", query.message);
+        }
+
+        Ok(())
+    }
+}
+
+/// The LSP position of the 1-based `line` and `column` in `source`, where
+/// the column counts characters (as the `^` of a query does).
+fn lsp_position(source: &str, line: u32, column: u32) -> Position {
+    let text = source.lines().nth(line as usize - 1).unwrap_or_default();
+    let character: usize = text
+        .chars()
+        .take(column as usize - 1)
+        .map(char::len_utf16)
+        .sum();
+    Position::new(line - 1, character as u32)
+}
+
+/// The byte offset of the LSP position `position` in `source`.
+fn byte_offset(source: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for line in source.split_inclusive('\n').take(position.line as usize) {
+        line_start += line.len();
+    }
+    let mut utf16_column = 0;
+    for (index, ch) in source[line_start..].char_indices() {
+        if utf16_column >= position.character as usize || ch == '\n' {
+            return line_start + index;
+        }
+        utf16_column += ch.len_utf16();
+    }
+    source.len()
+}
+
+/// The identifier at the 1-based `line` and `column` in `source` (empty if
+/// there is none).
+fn word_at(source: &str, line: u32, column: u32) -> &str {
+    let text = source.lines().nth(line as usize - 1).unwrap_or_default();
+    let (start, end) = word_bounds(text, column);
+    &text[start..end]
+}
+
+/// The LSP range of the identifier at the 1-based `line` and `column` in
+/// `source` (empty if there is none).
+fn word_range(source: &str, line: u32, column: u32) -> Range {
+    let text = source.lines().nth(line as usize - 1).unwrap_or_default();
+    let (start, end) = word_bounds(text, column);
+    let character = |offset: usize| text[..offset].encode_utf16().count() as u32;
+    Range::new(
+        Position::new(line - 1, character(start)),
+        Position::new(line - 1, character(end)),
+    )
+}
+
+/// The byte offsets in `text`, a line, of the start and end of the
+/// identifier at the 1-based `column`.
+fn word_bounds(text: &str, column: u32) -> (usize, usize) {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let Some((offset, _)) = text.char_indices().nth(column as usize - 1) else {
+        return (text.len(), text.len());
+    };
+    let start = text[..offset]
+        .rfind(|c| !is_word(c))
+        .map_or(0, |index| index + 1);
+    let end = text[offset..]
+        .find(|c| !is_word(c))
+        .map_or(text.len(), |index| offset + index);
+    (start, end)
+}
+
+/// Each symbol of an outline, with its children indented beneath it.
+fn describe_document_symbols(
+    source: &str,
+    symbols: &[DocumentSymbol],
+    depth: usize,
+    output: &mut String,
+) {
+    for symbol in symbols {
+        output.push_str(&format!(
+            "{}{} ({:?}): {}\n",
+            "  ".repeat(depth),
+            symbol.name,
+            symbol.kind,
+            describe_range(source, symbol.selection_range),
+        ));
+        if let Some(children) = &symbol.children {
+            describe_document_symbols(source, children, depth + 1, output);
+        }
+    }
+}
+
+/// The symbols like `name` in the workspace that are in `source_path`
+/// (the server has every earlier test loaded too).
+fn describe_workspace_symbols(
+    lsp_client: &mut ChildSession,
+    source_path: &Path,
+    source: &str,
+    name: &str,
+) -> eyre::Result<String> {
+    let uri = lsp_client::file_uri(source_path)?;
+    let mut symbols = lsp_client.workspace_symbols(name)?.unwrap_or_default();
+    symbols.retain(|symbol| symbol.location.uri == uri);
+    Ok(describe_symbol_information(source, &symbols))
+}
+
+fn describe_symbol_information(source: &str, symbols: &[SymbolInformation]) -> String {
+    symbols
+        .iter()
+        .map(|symbol| {
+            format!(
+                "{} ({:?}): {}\n",
+                symbol.name,
+                symbol.kind,
+                describe_range(source, symbol.location.range),
+            )
+        })
+        .collect()
+}
+
+/// Each token, decoded from the positions relative to one another that LSP
+/// sends, with the names of its type and modifiers from the server's legend.
+fn describe_semantic_tokens(source: &str, tokens: &[SemanticToken]) -> String {
+    let legend = dada_lsp::semantic_tokens_legend();
+    let mut output = String::new();
+    let (mut line, mut character) = (0, 0);
+    for token in tokens {
+        if token.delta_line == 0 {
+            character += token.delta_start;
+        } else {
+            line += token.delta_line;
+            character = token.delta_start;
+        }
+        let range = Range::new(
+            Position::new(line, character),
+            Position::new(line, character + token.length),
+        );
+        output.push_str(&describe_range(source, range));
+        output.push(' ');
+        output.push_str(legend.token_types[token.token_type as usize].as_str());
+        for (bit, modifier) in legend.token_modifiers.iter().enumerate() {
+            if token.token_modifiers_bitset & (1 << bit) != 0 {
+                output.push(' ');
+                output.push_str(modifier.as_str());
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Each signature, followed by its parameters (indented), with the active
+/// ones marked.
+fn describe_signature_help(help: &SignatureHelp) -> String {
+    let mut output = String::new();
+    for (index, signature) in help.signatures.iter().enumerate() {
+        let active = help.active_signature == Some(index as u32);
+        output.push_str(&signature.label);
+        if active && help.signatures.len() > 1 {
+            output.push_str(" (active)");
+        }
+        output.push('\n');
+        for (index, parameter) in signature.parameters.iter().flatten().enumerate() {
+            let label = match &parameter.label {
+                ParameterLabel::Simple(label) => label.clone(),
+                ParameterLabel::LabelOffsets([start, end]) => {
+                    signature.label[*start as usize..*end as usize].to_string()
+                }
+            };
+            output.push_str("  ");
+            output.push_str(&label);
+            if active && help.active_parameter == Some(index as u32) {
+                output.push_str(" (active)");
+            }
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// Each hint as `line:column label (kind)`, with the position 1-based as
+/// in [`describe_ranges`].
+fn describe_inlay_hints(hints: &[lsp_client::InlayHint]) -> String {
+    hints
+        .iter()
+        .map(|hint| {
+            let kind = match hint.kind {
+                1 => "type".to_string(),
+                2 => "parameter".to_string(),
+                kind => kind.to_string(),
+            };
+            format!(
+                "{}:{} {} ({})\n",
+                hint.position.line + 1,
+                hint.position.character + 1,
+                hint.label,
+                kind,
+            )
+        })
+        .collect()
+}
+
+/// Each action's title and kind, then (indented) the diagnostics it fixes
+/// and the edits it makes.
+fn describe_code_actions(source: &str, actions: &[CodeActionOrCommand]) -> String {
+    let mut output = String::new();
+    for action in actions {
+        let action = match action {
+            CodeActionOrCommand::CodeAction(action) => action,
+            CodeActionOrCommand::Command(command) => {
+                output.push_str(&format!("{} (command)\n", command.title));
+                continue;
+            }
+        };
+        output.push_str(&action.title);
+        if let Some(kind) = &action.kind {
+            output.push_str(&format!(" ({})", kind.as_str()));
+        }
+        if action.is_preferred == Some(true) {
+            output.push_str(" (preferred)");
+        }
+        output.push('\n');
+        for diagnostic in action.diagnostics.iter().flatten() {
+            output.push_str(&format!("  fixes: {}\n", diagnostic.message));
+        }
+        let edits = action.edit.iter().flat_map(|edit| {
+            edit.changes
+                .iter()
+                .flat_map(|changes| changes.values().flatten())
+        });
+        for edit in edits {
+            output.push_str(&format!(
+                "  {} => `{}`\n",
+                describe_range(source, edit.range),
+                edit.new_text,
+            ));
+        }
+    }
+    output
+}
+
+/// Each range as `line..line` (1-based) with its kind.
+fn describe_folding_ranges(ranges: &[FoldingRange]) -> String {
+    ranges
+        .iter()
+        .map(|range| match &range.kind {
+            Some(kind) => format!(
+                "{}..{} ({:?})\n",
+                range.start_line + 1,
+                range.end_line + 1,
+                kind
+            ),
+            None => format!("{}..{}\n", range.start_line + 1, range.end_line + 1),
+        })
+        .collect()
+}
+
+fn describe_diagnostics(source: &str, diagnostics: &[Diagnostic]) -> String {
+    if diagnostics.is_empty() {
+        return "no diagnostics\n".to_string();
+    }
+    diagnostics
+        .iter()
+        .map(|diagnostic| {
+            format!(
+                "{}: {}\n",
+                describe_range(source, diagnostic.range),
+                diagnostic.message,
+            )
+        })
+        .collect()
+}
+
+fn describe_locations(source: &str, locations: Vec<Location>) -> String {
+    describe_ranges(source, locations.iter().map(|location| location.range))
+}
+
+/// Each range as `line:column..line:column` (1-based, with columns in
+/// UTF-16 code units, as LSP counts them) along with the text in it, in
+/// order.
+fn describe_ranges(source: &str, ranges: impl Iterator<Item = Range>) -> String {
+    let mut ranges: Vec<Range> = ranges.collect();
+    ranges.sort_by_key(|range| (range.start.line, range.start.character));
+    ranges
+        .iter()
+        .map(|&range| format!("{}\n", describe_range(source, range)))
+        .collect()
+}
+
+/// The range as `line:column..line:column` (see [`describe_ranges`]) along
+/// with the text in it (only the first line of it, if there are several).
+fn describe_range(source: &str, range: Range) -> String {
+    let text = &source[byte_offset(source, range.start)..byte_offset(source, range.end)];
+    let (text, more) = match text.split_once('\n') {
+        Some((first_line, _)) => (first_line, " ..."),
+        None => (text, ""),
+    };
+    format!(
+        "{}:{}..{}:{} `{}`{}",
+        range.start.line + 1,
+        range.start.character + 1,
+        range.end.line + 1,
+        range.end.character + 1,
+        text,
+        more,
+    )
+}
@@ -0,0 +1,81 @@
+//! Runs each test compiled to WebAssembly (for WASI) under wasmtime, and
+//! checks what it prints against `stdout.ref`.
+
+use std::path::Path;
+
+use dada_ir::filename::Filename;
+use wasi_common::pipe::WritePipe;
+use wasmtime_wasi::sync::WasiCtxBuilder;
+
+use super::{Errors, ExpectedDiagnostic, RefOutputDoesNotMatch};
+
+impl super::Options {
+    /// Compiles `filename` with [`dada_codegen_wasm::compile_wasi_module`]
+    /// and runs it. Tests that the backend cannot compile are skipped.
+    ///
+    /// The compiled program traps where the interpreter would report an
+    /// error, so a trap is expected exactly when `expected_diagnostics`
+    /// (the runtime errors the test expects) is not empty; either way,
+    /// what was printed must match the interpreter's output.
+    pub(super) fn check_wasm(
+        &self,
+        db: &dada_db::Db,
+        filename: Filename,
+        ref_path: &Path,
+        expected_diagnostics: &[ExpectedDiagnostic],
+        errors: &mut Errors,
+    ) -> eyre::Result<()> {
+        if db.function_named(filename, "main").is_none() {
+            return Ok(());
+        }
+        let Ok(wat) = dada_codegen_wasm::compile_wasi_module(db, filename) else {
+            // Uses something the backend does not support.
+            return Ok(());
+        };
+
+        let (output, trap) = run_wasi(&wat)?;
+
+        let expected_output = std::fs::read_to_string(ref_path)?;
+        if output != expected_output {
+            errors.push(RefOutputDoesNotMatch {
+                ref_path: ref_path.to_owned(),
+                expected: expected_output,
+                actual: output,
+            });
+        }
+        match (trap, expected_diagnostics.is_empty()) {
+            (Some(trap), true) => eyre::bail!("compiled to wasm, the test trapped: {trap}"),
+            (None, false) => eyre::bail!("compiled to wasm, the test ran without trapping"),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Runs a module compiled for WASI, returning what it wrote to stdout and
+/// the trap that stopped it, if any.
+pub(super) fn run_wasi(wat: &str) -> eyre::Result<(String, Option<String>)> {
+    let engine = wasmtime::Engine::default();
+    let module = wasmtime::Module::new(&engine, wat)?;
+    let mut linker = wasmtime::Linker::new(&engine);
+    wasmtime_wasi::add_to_linker(&mut linker, |wasi| wasi)?;
+
+    let stdout = WritePipe::new_in_memory();
+    let wasi = WasiCtxBuilder::new()
+        .stdout(Box::new(stdout.clone()))
+        .build();
+    let mut store = wasmtime::Store::new(&engine, wasi);
+    let instance = linker.instantiate(&mut store, &module)?;
+    let start = instance.get_typed_func::<(), (), _>(&mut store, "_start")?;
+    let trap = start
+        .call(&mut store, ())
+        .err()
+        .map(|trap| trap.to_string());
+    drop(store);
+
+    let stdout = stdout
+        .try_into_inner()
+        .expect("the store has been dropped")
+        .into_inner();
+    Ok((String::from_utf8(stdout)?, trap))
+}
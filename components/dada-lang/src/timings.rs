@@ -0,0 +1,220 @@
+//! `--timings`: measures the compiler's own performance from the phase spans
+//! (see [`dada_ir::phase`]). Since each phase runs inside a memoized query,
+//! a span is only created when its query actually executes, so counting the
+//! spans counts the query executions. The time of a phase excludes the time
+//! of the phases it ran within it, such as validating a function parsing its
+//! body first.
+
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use serde_json::json;
+use tracing::{
+    field::{Field, Visit},
+    span, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// How many of the slowest items the table shows.
+const SLOWEST_ITEMS: usize = 10;
+
+/// What the phase spans recorded, shared between the [`TimingsLayer`] and
+/// the code that reports it once the command is done.
+#[derive(Clone, Default)]
+pub(crate) struct Timings {
+    data: Arc<Mutex<TimingsData>>,
+}
+
+#[derive(Default)]
+struct TimingsData {
+    /// For each phase, how many times it ran and how long it took in all.
+    phases: BTreeMap<&'static str, (u64, Duration)>,
+
+    /// For each (file, item), how long each phase took for it.
+    items: BTreeMap<(String, String), BTreeMap<&'static str, Duration>>,
+}
+
+/// Kept in the extensions of each phase span.
+#[derive(Default)]
+struct PhaseSpan {
+    file: String,
+    item: Option<String>,
+    busy: Duration,
+    entered: Option<Instant>,
+    /// Time spent in phases that ran within this one.
+    nested: Duration,
+}
+
+impl Visit for PhaseSpan {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "file" => self.file = value.to_string(),
+            "item" => self.item = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record_str(field, &format!("{value:?}"))
+    }
+}
+
+impl Timings {
+    /// The layer that records the phase spans into `self`; it should only be
+    /// given the spans whose target is [`dada_ir::phase::TARGET`].
+    pub(crate) fn layer(&self) -> TimingsLayer {
+        TimingsLayer {
+            timings: self.clone(),
+        }
+    }
+
+    /// Writes the report to stderr, as a table or (with `format` = `json`)
+    /// as a JSON object.
+    pub(crate) fn report(&self, format: &str) {
+        let data = self.data.lock();
+        if format == "json" {
+            eprintln!("{}", data.json());
+        } else {
+            eprint!("{}", data.table());
+        }
+    }
+}
+
+impl TimingsData {
+    fn json(&self) -> serde_json::Value {
+        let phases: Vec<_> = self
+            .phases
+            .iter()
+            .map(|(phase, (runs, time))| {
+                json!({
+                    "phase": phase,
+                    "runs": runs,
+                    "time_ns": time.as_nanos() as u64,
+                })
+            })
+            .collect();
+        let items: Vec<_> = self
+            .items
+            .iter()
+            .map(|((file, item), phases)| {
+                let phases: serde_json::Map<_, _> = phases
+                    .iter()
+                    .map(|(phase, time)| (phase.to_string(), (time.as_nanos() as u64).into()))
+                    .collect();
+                json!({
+                    "file": file,
+                    "item": item,
+                    "time_ns": phases,
+                })
+            })
+            .collect();
+        json!({ "phases": phases, "items": items })
+    }
+
+    fn table(&self) -> String {
+        let mut table = format!("{:<10} {:>8} {:>12}\n", "phase", "runs", "time");
+        for (phase, (runs, time)) in &self.phases {
+            table += &format!("{:<10} {:>8} {:>12}\n", phase, runs, millis(*time));
+        }
+
+        // The items that took the longest to validate and brew.
+        let compile_time = |phases: &BTreeMap<&str, Duration>| -> Duration {
+            ["validate", "brew"]
+                .iter()
+                .filter_map(|phase| phases.get(phase))
+                .sum()
+        };
+        let mut items: Vec<_> = self.items.iter().collect();
+        items.sort_by_key(|(_, phases)| std::cmp::Reverse(compile_time(phases)));
+        items.truncate(SLOWEST_ITEMS);
+        if !items.is_empty() {
+            table += &format!(
+                "\n{:<30} {:>12} {:>12}\n",
+                "slowest items", "validate", "brew"
+            );
+        }
+        for ((file, item), phases) in items {
+            let time = |phase: &str| phases.get(phase).copied().map_or(String::new(), millis);
+            table += &format!(
+                "{:<30} {:>12} {:>12}\n",
+                format!("{file}:{item}"),
+                time("validate"),
+                time("brew")
+            );
+        }
+        table
+    }
+}
+
+fn millis(time: Duration) -> String {
+    format!("{:.3}ms", time.as_secs_f64() * 1000.0)
+}
+
+pub(crate) struct TimingsLayer {
+    timings: Timings,
+}
+
+impl<S> Layer<S> for TimingsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("new span not in registry");
+        let mut phase_span = PhaseSpan::default();
+        attrs.record(&mut phase_span);
+        span.extensions_mut().insert(phase_span);
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("entered span not in registry");
+        if let Some(phase_span) = span.extensions_mut().get_mut::<PhaseSpan>() {
+            phase_span.entered = Some(Instant::now());
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("exited span not in registry");
+        if let Some(phase_span) = span.extensions_mut().get_mut::<PhaseSpan>() {
+            if let Some(entered) = phase_span.entered.take() {
+                phase_span.busy += entered.elapsed();
+            }
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(&id).expect("closed span not in registry");
+        let phase = span.name();
+        let Some(phase_span) = span.extensions_mut().remove::<PhaseSpan>() else {
+            return;
+        };
+        let time = phase_span.busy.saturating_sub(phase_span.nested);
+
+        // Don't count this phase's time again in the phase it ran within.
+        if let Some(parent) = span
+            .scope()
+            .skip(1)
+            .find(|parent| parent.extensions().get::<PhaseSpan>().is_some())
+        {
+            if let Some(parent_span) = parent.extensions_mut().get_mut::<PhaseSpan>() {
+                parent_span.nested += phase_span.busy;
+            }
+        }
+
+        let mut data = self.timings.data.lock();
+        let (runs, total) = data.phases.entry(phase).or_default();
+        *runs += 1;
+        *total += time;
+        if let Some(item) = phase_span.item {
+            *data
+                .items
+                .entry((phase_span.file, item))
+                .or_default()
+                .entry(phase)
+                .or_default() += time;
+        }
+    }
+}
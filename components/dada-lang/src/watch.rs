@@ -0,0 +1,84 @@
+//! `--watch`, for `dada run` and `dada check`: after each run, wait for a
+//! source file to change and then run again, with the same database, so
+//! that only what depends on the changed files is computed again.
+//!
+//! Changes are found by polling modification times, so editors that save
+//! by replacing the file are handled like any other.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use dada_ir::filename::Filename;
+
+/// How long to wait between looking for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub(crate) struct Watcher {
+    /// The `.dada` files, or directories containing them, to watch.
+    roots: Vec<PathBuf>,
+
+    /// When each file being watched was last modified.
+    modified: BTreeMap<PathBuf, Option<SystemTime>>,
+}
+
+impl Watcher {
+    /// Starts watching `roots`. Create the watcher before reading the files
+    /// for the first time, so that changes made while they are being
+    /// checked or run are not missed.
+    pub(crate) fn new(roots: Vec<PathBuf>) -> eyre::Result<Self> {
+        let mut watcher = Self {
+            roots,
+            modified: BTreeMap::new(),
+        };
+        watcher.modified = watcher.modification_times()?;
+        Ok(watcher)
+    }
+
+    /// Waits until a file is changed, added, or removed, and prints a
+    /// separator between the output of the last run and the next one.
+    pub(crate) async fn wait_for_change(&mut self) -> eyre::Result<()> {
+        eprintln!("[watching for changes]");
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let modified = self.modification_times()?;
+            if modified == self.modified {
+                continue;
+            }
+
+            let changed = modified
+                .keys()
+                .chain(self.modified.keys())
+                .find(|path| modified.get(*path) != self.modified.get(*path))
+                .cloned();
+            if let Some(changed) = changed {
+                eprintln!();
+                eprintln!("======== `{}` changed ========", changed.display());
+                eprintln!();
+            }
+            self.modified = modified;
+            return Ok(());
+        }
+    }
+
+    fn modification_times(&self) -> eyre::Result<BTreeMap<PathBuf, Option<SystemTime>>> {
+        Ok(crate::check::dada_files(&self.roots)?
+            .into_iter()
+            .map(|path| {
+                let modified = std::fs::metadata(&path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok();
+                (path, modified)
+            })
+            .collect())
+    }
+}
+
+/// Sets the source text of `filename`, unless it was loaded before and is
+/// already `source_text`: setting it would mean recomputing everything
+/// derived from the file, even though nothing has changed.
+pub(crate) fn update_file(db: &mut dada_db::Db, filename: Filename, source_text: String) {
+    if !db.has_file(filename) || db.file_source(filename).as_str() != source_text {
+        db.update_file(filename, source_text);
+    }
+}
@@ -2,15 +2,70 @@ use dada_ir::filename::Filename;
 use dada_ir::format_string::{
     FormatString, FormatStringData, FormatStringSection, FormatStringSectionData,
 };
-use dada_ir::span::{FileSpan, Offset, Span};
+use dada_ir::kw::Keyword;
+use dada_ir::span::{Anchor, FileSpan, Offset, Span};
 use dada_ir::token::Token;
 use dada_ir::token_tree::TokenTree;
-use dada_ir::word::Word;
+use dada_ir::word::{Word, WordCache};
 use std::iter::Peekable;
 
+use crate::reuse::ReusableTokens;
+
+#[salsa::memoized(in crate::Jar)]
 pub fn lex_file(db: &dyn crate::Db, filename: Filename) -> TokenTree {
+    let _span = dada_ir::phase_span!("lex", db, filename).entered();
     let source_text = dada_ir::manifest::source_text(db, filename);
-    lex_text(db, filename, source_text, 0)
+    match crate::reuse::reusable_tokens(db, filename) {
+        Some(reusable) => relex(db, filename, source_text, reusable),
+        None => lex_text(db, filename, source_text, 0),
+    }
+}
+
+/// Lexes `source_text`, reusing the top-level tokens from `reusable` that
+/// end before its first changed byte. (Ending *before* it matters: to find
+/// where a token ends, the lexer looks at the character after it.)
+fn relex(
+    db: &dyn crate::Db,
+    filename: Filename,
+    source_text: &str,
+    reusable: &ReusableTokens,
+) -> TokenTree {
+    let mut reused: Vec<(Span, Token)> = reusable
+        .tokens
+        .spanned_tokens(db)
+        .take_while(|(span, _)| span.end < reusable.unchanged)
+        .collect();
+
+    // Lexing resumes at the top level, so it can't resume inside a tree.
+    while let Some((_, Token::Delimiter('(' | '[' | '{') | Token::Tree(_))) = reused.last() {
+        reused.pop();
+    }
+
+    // Nor can it resume within an item, as the trees in the rest of the
+    // item must be anchored at its keyword.
+    if let Some(keyword) = reused
+        .iter()
+        .rposition(|&(_, token)| starts_item(db, token))
+    {
+        reused.truncate(keyword);
+    }
+    let resume = reused.last().map_or(0, |(span, _)| usize::from(span.end));
+
+    for diagnostic in &reusable.diagnostics {
+        if usize::from(diagnostic.span.end) <= resume {
+            diagnostic.clone().emit(db);
+        }
+    }
+
+    let rest = lex_text(db, filename, &source_text[resume..], resume);
+    let mut tokens: Vec<Token> = reused.into_iter().map(|(_, token)| token).collect();
+    tokens.extend(rest.tokens(db).iter().copied());
+    TokenTree::new(
+        db,
+        rest.anchor(db),
+        Span::from(0_usize, source_text.len()),
+        tokens,
+    )
 }
 
 pub(crate) fn lex_filespan(db: &dyn crate::Db, span: FileSpan) -> TokenTree {
@@ -34,12 +89,29 @@ fn lex_text(
     let mut lexer = Lexer {
         db,
         filename,
+        source_text,
+        start_offset,
         chars,
         file_len: start_offset + source_text.len(),
+        anchor: Anchor::new(db, filename, Offset::from(0_usize)),
+        open_delimiters: vec![],
+        reported_eof: false,
+        words: WordCache::default(),
     };
     lexer.lex_tokens(None)
 }
 
+/// True if `token` is a keyword that starts an item.
+fn starts_item(db: &dyn crate::Db, token: Token) -> bool {
+    match token {
+        Token::Alphabetic(word) => matches!(
+            dada_ir::kw::keywords(db).get(&word),
+            Some(Keyword::Fn | Keyword::Class)
+        ),
+        _ => false,
+    }
+}
+
 #[track_caller]
 pub fn closing_delimiter(ch: char) -> char {
     match ch {
@@ -62,8 +134,30 @@ where
 {
     db: &'me dyn crate::Db,
     filename: Filename,
+
+    /// The text being lexed, which starts at `start_offset` in the file.
+    source_text: &'me str,
+    start_offset: usize,
+
     chars: &'me mut Peekable<I>,
     file_len: usize,
+
+    /// The anchor of the item being lexed, which the spans of the token
+    /// trees within it are relative to: the start of its `fn` or `class`
+    /// keyword. Before the first item, this is the start of the file.
+    anchor: Anchor,
+
+    /// The opening delimiters of the trees being lexed, with their
+    /// offsets, innermost last.
+    open_delimiters: Vec<(char, usize)>,
+
+    /// True once a string or comment that runs to the end of the file has
+    /// been reported; the delimiters left open by it are not reported too.
+    reported_eof: bool,
+
+    /// The identifiers and numbers are interned through this, since the
+    /// same ones come up again and again.
+    words: WordCache,
 }
 
 impl<'me, I> Lexer<'me, I>
@@ -72,6 +166,9 @@ where
 {
     #[tracing::instrument(level = "debug", skip(self))]
     fn lex_tokens(&mut self, end_ch: Option<char>) -> TokenTree {
+        // A tree is anchored where it starts; at the top level, the anchor
+        // moves on to each item in turn.
+        let anchor = self.anchor;
         let mut tokens = vec![];
         let mut push_token = |t: Token| {
             tracing::debug!("push token: {:?}", t);
@@ -87,22 +184,66 @@ where
                 break;
             }
 
+            // Any other closing delimiter ends this tree too; the caller
+            // reports the mismatch.
+            if end_ch.is_some() && matches!(ch, ')' | ']' | '}') {
+                break;
+            }
+
             self.chars.next();
 
             match ch {
                 '(' | '[' | '{' => {
                     push_token(Token::Delimiter(ch));
                     let closing_ch = closing_delimiter(ch);
+                    self.open_delimiters.push((ch, pos));
                     let tree = self.lex_tokens(Some(closing_ch));
+                    self.open_delimiters.pop();
                     push_token(Token::Tree(tree));
 
-                    if let Some((_, next_ch)) = self.chars.peek() {
-                        if *next_ch == closing_ch {
+                    match self.chars.peek().copied() {
+                        Some((_, next_ch)) if next_ch == closing_ch => {
+                            self.chars.next();
+                            push_token(Token::Delimiter(closing_ch));
+                        }
+                        Some((next_pos, next_ch)) if !self.closes_open_tree(next_ch) => {
+                            // Like `(]`: report the `]`, but otherwise treat
+                            // it as the `)` that was meant.
                             self.chars.next();
+                            dada_ir::error!(
+                                UnbalancedDelimiter,
+                                Span::from(next_pos, next_pos + 1).in_file(self.filename),
+                                "mismatched closing delimiter"
+                            )
+                            .primary_label(format!("expected `{}`", closing_ch))
+                            .secondary_label(
+                                Span::from(pos, pos + 1).in_file(self.filename),
+                                format!("to close this `{}`", ch),
+                            )
+                            .emit(self.db);
                             push_token(Token::Delimiter(closing_ch));
                         }
+                        next => self.report_unclosed(ch, pos, next),
                     }
                 }
+                ')' | ']' | '}' => {
+                    // Outside of any tree, so there is nothing to close.
+                    // The parser skips these, as they have been reported here.
+                    let open_ch = match ch {
+                        ')' => '(',
+                        ']' => '[',
+                        _ => '{',
+                    };
+                    dada_ir::error!(
+                        UnbalancedDelimiter,
+                        Span::from(pos, pos + 1).in_file(self.filename),
+                        "unmatched `{}`",
+                        ch
+                    )
+                    .primary_label(format!("there is no `{}` for this to close", open_ch))
+                    .emit(self.db);
+                    push_token(Token::Unknown(ch));
+                }
                 'a'..='z' | 'A'..='Z' | '_' => {
                     let text = self
                         .accumulate(ch, |c| matches!(c, 'a'..='z' | 'A'..='Z' | '_' | '0'..='9'));
@@ -118,13 +259,21 @@ where
                     if is_prefix {
                         push_token(Token::Prefix(text));
                     } else {
+                        if self.open_delimiters.is_empty()
+                            && starts_item(self.db, Token::Alphabetic(text))
+                        {
+                            self.anchor = Anchor::new(self.db, self.filename, Offset::from(pos));
+                        }
                         push_token(Token::Alphabetic(text));
                     }
                 }
                 '#' => {
-                    let s = self.accumulate_string(ch, |c| c != '\n');
-                    let len: u32 = s.len().try_into().unwrap();
-                    push_token(Token::Comment(len));
+                    let len = if let Some(&(_, '|')) = self.chars.peek() {
+                        self.block_comment(pos)
+                    } else {
+                        self.accumulate_string(ch, |c| c != '\n').len()
+                    };
+                    push_token(Token::Comment(len.try_into().unwrap()));
                 }
                 ',' => {
                     push_token(Token::Comma);
@@ -155,14 +304,50 @@ where
 
         end_pos = end_pos.max(start_pos);
 
+        let anchor_offset = usize::from(anchor.offset(self.db));
         TokenTree::new(
             self.db,
-            self.filename,
-            Span::from(start_pos, end_pos),
+            anchor,
+            Span::from(start_pos - anchor_offset, end_pos - anchor_offset),
             tokens,
         )
     }
 
+    /// True if `ch` closes one of the trees being lexed.
+    fn closes_open_tree(&self, ch: char) -> bool {
+        self.open_delimiters
+            .iter()
+            .any(|&(open_ch, _)| closing_delimiter(open_ch) == ch)
+    }
+
+    /// Reports that the `open_ch` at `open_pos` was never closed; `next` is
+    /// the delimiter that closed an enclosing tree instead, if any.
+    fn report_unclosed(&self, open_ch: char, open_pos: usize, next: Option<(usize, char)>) {
+        if next.is_none() && self.reported_eof {
+            return;
+        }
+
+        let closing_ch = closing_delimiter(open_ch);
+        let diagnostic = dada_ir::error!(
+            UnbalancedDelimiter,
+            Span::from(open_pos, open_pos + 1).in_file(self.filename),
+            "unclosed `{}`",
+            open_ch
+        )
+        .primary_label(format!("this `{}` is never closed", open_ch));
+        let diagnostic = match next {
+            Some((pos, ch)) => diagnostic.secondary_label(
+                Span::from(pos, pos + 1).in_file(self.filename),
+                format!("expected `{}` before this `{}`", closing_ch, ch),
+            ),
+            None => diagnostic.secondary_label(
+                Span::from(self.file_len, self.file_len).in_file(self.filename),
+                format!("expected `{}` before the end of the file", closing_ch),
+            ),
+        };
+        diagnostic.emit(self.db);
+    }
+
     /// Returns the offset of the next character within the file.
     fn peek_offset(&mut self) -> usize {
         match self.chars.peek() {
@@ -187,20 +372,71 @@ where
         string
     }
 
-    /// Like [`Self::accumulate_string`], but interns the result.
+    /// Like [`Self::accumulate_string`], but interns the result. The word is
+    /// sliced out of the source text, rather than built up character by
+    /// character.
     fn accumulate(&mut self, ch0: char, matches: impl Fn(char) -> bool) -> Word {
-        let string = self.accumulate_string(ch0, matches);
-        Word::from(self.db, string)
+        let start = self.peek_offset() - ch0.len_utf8();
+        while let Some(&(_, ch1)) = self.chars.peek() {
+            if !matches(ch1) {
+                break;
+            }
+            self.chars.next();
+        }
+        let end = self.peek_offset();
+        let text = &self.source_text[start - self.start_offset..end - self.start_offset];
+        self.words.intern(self.db, text)
+    }
+
+    /// Invoked after consuming the `#` of a `#|` at `start`. Consumes the
+    /// rest of the block comment, including any nested block comments, and
+    /// returns its length (up to the end of the file if it is never closed).
+    fn block_comment(&mut self, start: usize) -> usize {
+        self.chars.next(); // the `|`
+
+        let mut depth = 1;
+        let mut previous = None;
+        while let Some((_, ch)) = self.chars.next() {
+            match (previous, ch) {
+                (Some('#'), '|') => {
+                    depth += 1;
+                    previous = None;
+                }
+                (Some('|'), '#') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return self.peek_offset() - start;
+                    }
+                    previous = None;
+                }
+                _ => previous = Some(ch),
+            }
+        }
+
+        dada_ir::error!(
+            UnterminatedComment,
+            Span::from(start, start + 2).in_file(self.filename),
+            "unterminated block comment"
+        )
+        .primary_label("this `#|` is never closed by a `|#`")
+        .emit(self.db);
+        self.reported_eof = true;
+        self.file_len - start
     }
 
     /// Invoked after consuming a `"`
     fn string_literal(&mut self, start: Offset) -> FormatString {
         let mut buffer = StringFormatBuffer::new(self.db);
         let mut is_backslash_previous = false;
+
+        // True once the closing `"` is found, or once some other error
+        // about the string has been reported.
+        let mut finished = false;
         while let Some((ch_offset, ch)) = self.chars.next() {
             let ch_offset = Offset::from(ch_offset);
 
             if ch == '"' && !is_backslash_previous {
+                finished = true;
                 break;
             }
 
@@ -211,8 +447,14 @@ where
             }
 
             if ch == '{' {
-                // Format string! Grab a token tree.
+                // Format string! Grab a token tree. Delimiters opened
+                // outside the string can't be closed within it.
+                let enclosing = std::mem::replace(
+                    &mut self.open_delimiters,
+                    vec![('{', usize::from(ch_offset))],
+                );
                 let tree = self.lex_tokens(Some('}'));
+                self.open_delimiters = enclosing;
                 buffer.push_tree(tree);
 
                 if let Some(&(_, '}')) = self.chars.peek() {
@@ -225,6 +467,7 @@ where
                             .unwrap_or(self.file_len),
                     );
                     dada_ir::error!(
+                        UnclosedInterpolation,
                         Span {
                             start: ch_offset,
                             end,
@@ -233,6 +476,7 @@ where
                         "format string missing closing brace in code section"
                     )
                     .emit(self.db);
+                    finished = true;
                     break;
                 }
                 continue;
@@ -241,6 +485,21 @@ where
             buffer.push_char(ch);
         }
 
+        if !finished {
+            dada_ir::error!(
+                UnterminatedString,
+                Span::from(start, start + 1_u32).in_file(self.filename),
+                "unterminated string literal"
+            )
+            .primary_label("this string is never closed")
+            .secondary_label(
+                Span::from(self.file_len, self.file_len).in_file(self.filename),
+                "the file ends here",
+            )
+            .emit(self.db);
+            self.reported_eof = true;
+        }
+
         buffer.flush_text();
 
         let end = Offset::from(self.peek_offset());
@@ -3,9 +3,11 @@
 
 mod lex;
 pub mod prelude;
+pub mod reuse;
+pub mod trivia;
 
 #[salsa::jar(Db)]
-pub struct Jar();
+pub struct Jar(lex::lex_file, reuse::reusable_tokens, trivia::trivia);
 
 pub trait Db: salsa::DbWithJar<Jar> + dada_ir::Db {
     fn lex(&self) -> &dyn Db;
@@ -0,0 +1,50 @@
+//! Reusing the tokens of a file after an edit. A host that knows where an
+//! edit starts (like the language server) records the tokens from before
+//! the edit, and [`lex_file`](crate::lex_file) then only lexes the text
+//! from the edit onward.
+//!
+//! Tokens after the edit can't be reused: the anchors of the token trees
+//! within them record where they are in the file, and the edit may have
+//! moved them.
+
+use dada_ir::{
+    diagnostic::{Diagnostic, Diagnostics},
+    filename::Filename,
+    span::Offset,
+    token_tree::TokenTree,
+};
+
+/// The tokens of an earlier version of a file whose first `unchanged`
+/// bytes are the same as they are now.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReusableTokens {
+    pub(crate) tokens: TokenTree,
+    pub(crate) unchanged: Offset,
+
+    /// The errors reported while lexing `tokens`; those about tokens that
+    /// are reused are reported again.
+    pub(crate) diagnostics: Vec<Diagnostic>,
+}
+
+impl ReusableTokens {
+    /// Captures the tokens of `filename` as it is now, before an edit that
+    /// leaves its first `unchanged` bytes as they are.
+    pub fn before_edit(db: &dyn crate::Db, filename: Filename, unchanged: Offset) -> Self {
+        Self {
+            tokens: crate::lex_file(db, filename),
+            unchanged,
+            diagnostics: crate::lex_file::accumulated::<Diagnostics>(db, filename),
+        }
+    }
+}
+
+/// Salsa input: tokens that [`lex_file`](crate::lex_file) may reuse for
+/// `filename`. This must be set along with the source text that it
+/// describes, and cleared when the source text is replaced wholesale.
+///
+/// Defaults to `None`, in which case the whole file is lexed.
+#[salsa::memoized(in crate::Jar ref)]
+#[allow(clippy::needless_lifetimes)]
+pub fn reusable_tokens(_db: &dyn crate::Db, _filename: Filename) -> Option<ReusableTokens> {
+    None
+}
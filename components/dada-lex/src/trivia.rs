@@ -0,0 +1,110 @@
+//! Comments and whitespace ("trivia"). The parser skips over these, so
+//! they are kept in a side table, keyed by span, for tools that need the
+//! source as written: a formatter has to put comments back, and
+//! documentation comes from the comments just before an item.
+
+use dada_ir::{
+    filename::Filename,
+    span::{FileSpan, Offset},
+    token::Token,
+    token_tree::TokenTree,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TriviaKind {
+    /// `# ...`, up to but not including the newline, or a block comment
+    /// `#| ... |#`.
+    Comment,
+
+    /// A run of whitespace, which may span several lines.
+    Whitespace,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub span: FileSpan,
+}
+
+/// The trivia in `filename`, in order of position. Adjacent whitespace
+/// tokens are merged into one entry. Together with the other tokens from
+/// [`lex_file`](crate::lex_file), these spans cover the whole file.
+#[salsa::memoized(in crate::Jar ref)]
+pub fn trivia(db: &dyn crate::Db, filename: Filename) -> Vec<Trivia> {
+    let mut trivia = vec![];
+    push_trivia(db, crate::lex_file(db, filename), &mut trivia);
+    trivia
+}
+
+fn push_trivia(db: &dyn crate::Db, tree: TokenTree, trivia: &mut Vec<Trivia>) {
+    let anchor = tree.anchor(db);
+    for (span, token) in tree.spanned_tokens(db) {
+        let kind = match token {
+            Token::Tree(tree) => {
+                push_trivia(db, tree, trivia);
+                continue;
+            }
+            Token::Comment(_) => TriviaKind::Comment,
+            Token::Whitespace(_) => TriviaKind::Whitespace,
+            _ => continue,
+        };
+        let span = anchor.file_span(db, span);
+        match trivia.last_mut() {
+            Some(last)
+                if kind == TriviaKind::Whitespace
+                    && last.kind == TriviaKind::Whitespace
+                    && last.span.end == span.start =>
+            {
+                last.span.end = span.end;
+            }
+            _ => trivia.push(Trivia { kind, span }),
+        }
+    }
+}
+
+/// The comments directly before `offset`, in order: those separated from
+/// it (and from each other) only by whitespace without a blank line. For
+/// an item, these are its documentation.
+pub fn comments_before(db: &dyn crate::Db, filename: Filename, offset: Offset) -> Vec<FileSpan> {
+    let mut comments = vec![];
+    let mut end = offset;
+    for t in trivia(db, filename).iter().rev() {
+        if t.span.end > end {
+            continue;
+        }
+        if t.span.end < end {
+            // Something other than trivia is in between.
+            break;
+        }
+        match t.kind {
+            TriviaKind::Comment => comments.push(t.span),
+            TriviaKind::Whitespace => {
+                if t.span.snippet(db).matches('\n').count() > 1 {
+                    break;
+                }
+            }
+        }
+        end = t.span.start;
+    }
+    comments.reverse();
+    comments
+}
+
+/// The text of the comments directly before `offset` (see
+/// [`comments_before`]), without the `#` or `#| ... |#` markers, one line
+/// of text per line.
+pub fn documentation(db: &dyn crate::Db, filename: Filename, offset: Offset) -> String {
+    let mut text = String::new();
+    for comment in comments_before(db, filename, offset) {
+        let comment = comment.snippet(db);
+        let body = match comment.strip_prefix("#|") {
+            Some(body) => body.strip_suffix("|#").unwrap_or(body),
+            None => comment.trim_start_matches('#'),
+        };
+        for line in body.trim().lines() {
+            text.push_str(line.trim_start());
+            text.push('\n');
+        }
+    }
+    text
+}
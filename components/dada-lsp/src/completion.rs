@@ -0,0 +1,83 @@
+//! Completions. Names come from the validator's scopes (see
+//! [`dada_validate::symbols::names_in_scope`]), so what is offered is
+//! exactly what would resolve at the cursor.
+//!
+//! After a `.`, Dada has no type checker to tell us the class of the
+//! value before the dot, so we offer the fields of every class in the file
+//! along with the permission operations (`give`, `share`, etc.).
+
+use dada_ir::{filename::Filename, item::Item, kw::Keyword, span::Offset};
+use dada_parse::prelude::*;
+use dada_validate::symbols::Symbol;
+use lsp_types::{CompletionItem, CompletionItemKind};
+
+/// The keywords that may follow a `.`.
+const DOT_KEYWORDS: &[Keyword] = &[
+    Keyword::Await,
+    Keyword::Give,
+    Keyword::Lease,
+    Keyword::Share,
+    Keyword::Shlease,
+];
+
+/// The completions at `offset` in `filename`. The client filters them by
+/// the partial name before the cursor, so we don't.
+pub fn completions(db: &dada_db::Db, filename: Filename, offset: Offset) -> Vec<CompletionItem> {
+    if follows_dot(db, filename, offset) {
+        let fields = filename.items(db).iter().flat_map(|&item| match item {
+            Item::Class(class) => class
+                .fields(db)
+                .iter()
+                .map(|field| CompletionItem {
+                    label: field.name(db).as_str(db).to_string(),
+                    kind: Some(CompletionItemKind::Field),
+                    detail: Some(format!("field of `{}`", class.name(db).as_str(db))),
+                    ..CompletionItem::default()
+                })
+                .collect(),
+            Item::Function(_) | Item::ExternFunction(_) => vec![],
+        });
+        return fields
+            .chain(DOT_KEYWORDS.iter().map(|&keyword| keyword_item(keyword)))
+            .collect();
+    }
+
+    dada_validate::symbols::names_in_scope(db, filename, offset)
+        .into_iter()
+        .map(|(name, symbol)| CompletionItem {
+            label: name.as_str(db).to_string(),
+            kind: Some(completion_kind(symbol)),
+            ..CompletionItem::default()
+        })
+        .chain(Keyword::all().map(keyword_item))
+        .collect()
+}
+
+/// True if the cursor is on a name (possibly empty) that comes right after
+/// a `.`, as in `p.` or `p.x`.
+fn follows_dot(db: &dada_db::Db, filename: Filename, offset: Offset) -> bool {
+    let source_text = dada_ir::manifest::source_text(db, filename);
+    let Some(before) = source_text.get(..usize::from(offset)) else {
+        return false;
+    };
+    let name_start = before.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_');
+    name_start.ends_with('.')
+}
+
+fn keyword_item(keyword: Keyword) -> CompletionItem {
+    CompletionItem {
+        label: keyword.str().to_string(),
+        kind: Some(CompletionItemKind::Keyword),
+        ..CompletionItem::default()
+    }
+}
+
+fn completion_kind(symbol: Symbol) -> CompletionItemKind {
+    match symbol {
+        Symbol::LocalVariable(..) => CompletionItemKind::Variable,
+        Symbol::Item(Item::Class(_)) => CompletionItemKind::Class,
+        Symbol::Item(Item::Function(_) | Item::ExternFunction(_))
+        | Symbol::Intrinsic(_)
+        | Symbol::HostFunction(_) => CompletionItemKind::Function,
+    }
+}
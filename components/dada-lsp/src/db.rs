@@ -1,17 +1,52 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
 use crossbeam_channel::Sender;
-use dada_ir::{filename::Filename, span::Offset};
-use lsp_server::Message;
+use dada_ir::{
+    diagnostic::Applicability,
+    filename::Filename,
+    source_text::SourceText,
+    span::{LineColumn, Offset},
+};
+use lsp_server::{ErrorCode, Message, RequestId, Response};
 use lsp_types::{
-    notification::PublishDiagnostics, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity,
-    DidChangeTextDocumentParams, DidOpenTextDocumentParams, Location, Position,
-    PublishDiagnosticsParams, Range, Url,
+    notification::{DidChangeWatchedFiles, Notification as _, PublishDiagnostics},
+    request::{
+        CodeActionRequest, Completion, DocumentSymbolRequest, FoldingRangeRequest, GotoDefinition,
+        HoverRequest, References, Rename, SelectionRangeRequest, SemanticTokensFullRequest,
+        SignatureHelpRequest, WorkspaceSymbol,
+    },
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CompletionParams,
+    CompletionResponse, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity,
+    DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+    DidChangeWatchedFilesRegistrationOptions, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentSymbolParams, DocumentSymbolResponse, FileChangeType,
+    FileSystemWatcher, FoldingRangeParams, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, Location, MarkupContent, MarkupKind, NumberOrString, Position,
+    PublishDiagnosticsParams, Range, ReferenceParams, Registration, RenameParams,
+    SelectionRangeParams, SemanticTokens, SemanticTokensParams, SemanticTokensResult,
+    SignatureHelpParams, TextEdit, Url, WorkspaceEdit, WorkspaceSymbolParams,
 };
 use salsa::ParallelDatabase;
 
+use crate::inlay_hints::{InlayHintParams, InlayHintRequest, InlayHintsConfig};
+
 pub struct LspServerDatabase {
     db: dada_db::Db,
     threads: threadpool::ThreadPool,
     sender: Sender<Message>,
+
+    /// Every file that has been loaded: those in the workspace, and any
+    /// others the client has opened.
+    filenames: Vec<Filename>,
+
+    /// The files the client has open, with their versions. The client owns
+    /// the text of these, so changes to them on disk are ignored.
+    open_files: HashMap<Filename, i32>,
+
+    inlay_hints_config: InlayHintsConfig,
 }
 
 impl LspServerDatabase {
@@ -20,7 +55,60 @@ impl LspServerDatabase {
             db: Default::default(),
             threads: Default::default(),
             sender,
+            filenames: vec![],
+            open_files: HashMap::new(),
+            inlay_hints_config: InlayHintsConfig::default(),
+        }
+    }
+
+    pub fn set_inlay_hints_config(&mut self, config: InlayHintsConfig) {
+        self.inlay_hints_config = config;
+    }
+
+    /// Loads the `.dada` files found (recursively) under each of `roots`.
+    /// Files that can't be read are skipped.
+    pub fn load_workspace(&mut self, roots: Vec<Url>) {
+        let mut paths = vec![];
+        for root in roots {
+            if let Ok(path) = root.to_file_path() {
+                find_dada_files(&path, &mut paths);
+            }
+        }
+        for path in paths {
+            let (Ok(uri), Ok(source_text)) =
+                (Url::from_file_path(&path), std::fs::read_to_string(&path))
+            else {
+                continue;
+            };
+            let filename = self.filename_from_uri(&uri);
+            self.update_file(filename, source_text);
         }
+        self.check_all();
+    }
+
+    fn update_file(&mut self, filename: Filename, source_text: impl Into<SourceText>) {
+        self.db.update_file(filename, source_text);
+        if !self.filenames.contains(&filename) {
+            self.filenames.push(filename);
+        }
+    }
+
+    /// Stops tracking a file that no longer exists, and clears its
+    /// diagnostics.
+    fn forget_file(&mut self, uri: Url, filename: Filename) {
+        // Salsa inputs can't be removed, but with no text the file has no
+        // items for other files to refer to.
+        self.db.update_file(filename, String::new());
+        self.filenames.retain(|&f| f != filename);
+        let diagnostics = PublishDiagnosticsParams {
+            uri,
+            diagnostics: vec![],
+            version: None,
+        };
+        let notification = super::new_notification::<PublishDiagnostics>(diagnostics);
+        self.sender
+            .send(Message::Notification(notification))
+            .unwrap();
     }
 
     fn filename_from_uri(&self, uri: &Url) -> Filename {
@@ -31,28 +119,117 @@ impl LspServerDatabase {
     pub fn did_open(&mut self, params: DidOpenTextDocumentParams) {
         let filename = self.filename_from_uri(&params.text_document.uri);
         let source_text = params.text_document.text;
-        self.db.update_file(filename, source_text);
-        self.spawn_check(
-            params.text_document.uri,
-            params.text_document.version,
-            filename,
-        );
+        self.open_files
+            .insert(filename, params.text_document.version);
+        self.update_file(filename, source_text);
+        self.check_all();
     }
 
     pub fn did_change(&mut self, params: DidChangeTextDocumentParams) {
         let filename = self.filename_from_uri(&params.text_document.uri);
-        // Since we asked for Sync full, just grab all the text from params
-        let change = params.content_changes.into_iter().next().unwrap();
-        let source_text = change.text;
-        self.db.update_file(filename, source_text);
-        self.spawn_check(
-            params.text_document.uri,
-            params.text_document.version,
-            filename,
-        );
+        // We asked for incremental sync, so each change replaces a range of
+        // the text as it was after the changes before it (or, if it has no
+        // range, the whole text).
+        //
+        // No change touches the text before the earliest start of any of
+        // them, so the tokens and items there can be reused.
+        //
+        // Taking the text out of the database copies nothing, and each
+        // change copies it once.
+        let mut source_text = dada_ir::manifest::source_text(&self.db, filename).clone();
+        let mut unchanged = Some(source_text.len());
+        for change in params.content_changes {
+            match change.range {
+                Some(range) => {
+                    let start = byte_offset(&source_text, range.start);
+                    let end = byte_offset(&source_text, range.end).max(start);
+                    source_text = source_text.edit(start..end, &change.text);
+                    unchanged = unchanged.map(|unchanged| unchanged.min(start));
+                }
+                None => {
+                    source_text = SourceText::from(change.text);
+                    unchanged = None;
+                }
+            }
+        }
+        self.open_files
+            .insert(filename, params.text_document.version);
+        match unchanged {
+            Some(unchanged) => self
+                .db
+                .edit_file(filename, source_text, Offset::from(unchanged)),
+            None => self.update_file(filename, source_text),
+        }
+        self.check_all();
     }
 
-    fn spawn_check(&self, uri: Url, version: i32, filename: Filename) {
+    /// Once the client closes a file, the file on disk is the truth again;
+    /// if there is none (e.g., the file was never saved), forget it.
+    pub fn did_close(&mut self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let filename = self.filename_from_uri(&uri);
+        self.open_files.remove(&filename);
+        match uri.to_file_path().map(std::fs::read_to_string) {
+            Ok(Ok(source_text)) => self.update_file(filename, source_text),
+            _ => self.forget_file(uri, filename),
+        }
+        self.check_all();
+    }
+
+    /// Applies changes to `.dada` files on disk that the client reports
+    /// (see [`Self::file_watcher_registration`]).
+    pub fn did_change_watched_files(&mut self, params: DidChangeWatchedFilesParams) {
+        for change in params.changes {
+            let filename = self.filename_from_uri(&change.uri);
+            if self.open_files.contains_key(&filename) {
+                continue;
+            }
+            let source_text = match change.typ {
+                FileChangeType::Created | FileChangeType::Changed => change
+                    .uri
+                    .to_file_path()
+                    .ok()
+                    .and_then(|path| std::fs::read_to_string(path).ok()),
+                FileChangeType::Deleted => None,
+            };
+            match source_text {
+                Some(source_text) => self.update_file(filename, source_text),
+                None => self.forget_file(change.uri, filename),
+            }
+        }
+        self.check_all();
+    }
+
+    /// Asks the client to report changes to `.dada` files on disk, so that
+    /// files the user hasn't opened stay up to date.
+    pub fn file_watcher_registration() -> Registration {
+        let options = DidChangeWatchedFilesRegistrationOptions {
+            watchers: vec![FileSystemWatcher {
+                glob_pattern: "**/*.dada".to_string(),
+                kind: None,
+            }],
+        };
+        Registration {
+            id: "dada-file-watcher".to_string(),
+            method: DidChangeWatchedFiles::METHOD.to_string(),
+            register_options: Some(serde_json::to_value(options).unwrap()),
+        }
+    }
+
+    /// Publishes diagnostics for every loaded file. A change to one file
+    /// can affect the diagnostics of others (once files can refer to one
+    /// another), and salsa reuses the results for files that are unaffected.
+    fn check_all(&self) {
+        for &filename in &self.filenames {
+            let Ok(uri) = Url::parse(filename.as_str(&self.db)) else {
+                continue;
+            };
+            let version = self.open_files.get(&filename).copied();
+            self.spawn_check(uri, version, filename);
+        }
+    }
+
+    fn spawn_check(&self, uri: Url, version: Option<i32>, filename: Filename) {
         let sender = self.sender.clone();
         let db = self.db.snapshot();
         self.threads.execute(move || {
@@ -65,16 +242,229 @@ impl LspServerDatabase {
             let diagnostic = PublishDiagnosticsParams {
                 uri,
                 diagnostics,
-                version: Some(version),
+                version,
             };
 
             let notification = super::new_notification::<PublishDiagnostics>(diagnostic);
             sender.send(Message::Notification(notification)).unwrap();
         });
     }
+
+    pub fn goto_definition(&self, id: RequestId, params: GotoDefinitionParams) {
+        let position = params.text_document_position_params;
+        let filename = self.filename_from_uri(&position.text_document.uri);
+        self.spawn_request::<GotoDefinition>(id, move |db| {
+            let offset = db.dada_offset(filename, position.position);
+            let symbol_use = dada_validate::symbols::symbol_at(db, filename, offset)?;
+            let span = symbol_use.symbol.definition_span(db)?;
+            Some(GotoDefinitionResponse::Scalar(db.lsp_location(span)))
+        });
+    }
+
+    pub fn references(&self, id: RequestId, params: ReferenceParams) {
+        let position = params.text_document_position;
+        let include_declaration = params.context.include_declaration;
+        let filename = self.filename_from_uri(&position.text_document.uri);
+        let filenames = self.filenames.clone();
+        self.spawn_request::<References>(id, move |db| {
+            let offset = db.dada_offset(filename, position.position);
+            let symbol = dada_validate::symbols::symbol_at(db, filename, offset)?.symbol;
+            let locations = filenames
+                .iter()
+                .flat_map(|&filename| dada_validate::symbols::symbol_uses(db, filename))
+                .filter(|u| u.symbol == symbol && (include_declaration || !u.is_definition))
+                .map(|u| db.lsp_location(u.span))
+                .collect();
+            Some(locations)
+        });
+    }
+
+    pub fn hover(&self, id: RequestId, params: HoverParams) {
+        let position = params.text_document_position_params;
+        let filename = self.filename_from_uri(&position.text_document.uri);
+        self.spawn_request::<HoverRequest>(id, move |db| {
+            let offset = db.dada_offset(filename, position.position);
+            let symbol_use = dada_validate::symbols::symbol_at(db, filename, offset)?;
+            Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: crate::hover::hover_text(db, symbol_use.symbol),
+                }),
+                range: Some(db.lsp_range(symbol_use.span)),
+            })
+        });
+    }
+
+    pub fn document_symbols(&self, id: RequestId, params: DocumentSymbolParams) {
+        let filename = self.filename_from_uri(&params.text_document.uri);
+        self.spawn_request::<DocumentSymbolRequest>(id, move |db| {
+            let symbols = crate::outline::document_symbols(db, filename);
+            Some(DocumentSymbolResponse::Nested(symbols))
+        });
+    }
+
+    pub fn workspace_symbols(&self, id: RequestId, params: WorkspaceSymbolParams) {
+        let filenames = self.filenames.clone();
+        self.spawn_request::<WorkspaceSymbol>(id, move |db| {
+            Some(crate::outline::workspace_symbols(
+                db,
+                &filenames,
+                &params.query,
+            ))
+        });
+    }
+
+    /// Offers the fixes attached to the diagnostics in the requested
+    /// range as quick fixes.
+    pub fn code_actions(&self, id: RequestId, params: CodeActionParams) {
+        let uri = params.text_document.uri;
+        let filename = self.filename_from_uri(&uri);
+        self.spawn_request::<CodeActionRequest>(id, move |db| {
+            let start = db.dada_offset(filename, params.range.start);
+            let end = db.dada_offset(filename, params.range.end);
+            let mut actions = vec![];
+            let mut dada_diagnostics = db.diagnostics(filename);
+            while let Some(dada_diagnostic) = dada_diagnostics.pop() {
+                dada_diagnostics.extend(dada_diagnostic.children.iter().cloned());
+                let span = dada_diagnostic.span;
+                if dada_diagnostic.fixes.is_empty() || span.end < start || end < span.start {
+                    continue;
+                }
+                let diagnostic = db.lsp_diagnostic(dada_diagnostic.clone());
+                for fix in &dada_diagnostic.fixes {
+                    let edit = TextEdit {
+                        range: db.lsp_range(fix.span),
+                        new_text: fix.replacement.clone(),
+                    };
+                    let changes = std::iter::once((uri.clone(), vec![edit])).collect();
+                    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                        title: fix.message.clone(),
+                        kind: Some(CodeActionKind::QUICKFIX),
+                        diagnostics: Some(vec![diagnostic.clone()]),
+                        edit: Some(WorkspaceEdit::new(changes)),
+                        is_preferred: Some(fix.applicability == Applicability::MachineApplicable),
+                        ..CodeAction::default()
+                    }));
+                }
+            }
+            Some(actions)
+        });
+    }
+
+    pub fn completion(&self, id: RequestId, params: CompletionParams) {
+        let position = params.text_document_position;
+        let filename = self.filename_from_uri(&position.text_document.uri);
+        self.spawn_request::<Completion>(id, move |db| {
+            let offset = db.dada_offset(filename, position.position);
+            Some(CompletionResponse::Array(crate::completion::completions(
+                db, filename, offset,
+            )))
+        });
+    }
+
+    pub fn rename(&self, id: RequestId, params: RenameParams) {
+        let position = params.text_document_position;
+        let filename = self.filename_from_uri(&position.text_document.uri);
+        self.spawn_fallible_request::<Rename>(id, move |db| {
+            let offset = db.dada_offset(filename, position.position);
+            let uses = crate::rename::rename(db, filename, offset, &params.new_name)?;
+            let edits = uses
+                .iter()
+                .map(|u| TextEdit {
+                    range: db.lsp_range(u.span),
+                    new_text: params.new_name.clone(),
+                })
+                .collect();
+            let changes = std::iter::once((position.text_document.uri, edits)).collect();
+            Ok(Some(WorkspaceEdit::new(changes)))
+        });
+    }
+
+    pub fn signature_help(&self, id: RequestId, params: SignatureHelpParams) {
+        let position = params.text_document_position_params;
+        let filename = self.filename_from_uri(&position.text_document.uri);
+        self.spawn_request::<SignatureHelpRequest>(id, move |db| {
+            let offset = db.dada_offset(filename, position.position);
+            crate::signature_help::signature_help(db, filename, offset)
+        });
+    }
+
+    pub fn inlay_hints(&self, id: RequestId, params: InlayHintParams) {
+        let filename = self.filename_from_uri(&params.text_document.uri);
+        let config = self.inlay_hints_config;
+        self.spawn_request::<InlayHintRequest>(id, move |db| {
+            let range = (
+                db.dada_offset(filename, params.range.start),
+                db.dada_offset(filename, params.range.end),
+            );
+            Some(crate::inlay_hints::inlay_hints(db, filename, range, config))
+        });
+    }
+
+    pub fn folding_ranges(&self, id: RequestId, params: FoldingRangeParams) {
+        let filename = self.filename_from_uri(&params.text_document.uri);
+        self.spawn_request::<FoldingRangeRequest>(id, move |db| {
+            Some(crate::ranges::folding_ranges(db, filename))
+        });
+    }
+
+    pub fn selection_ranges(&self, id: RequestId, params: SelectionRangeParams) {
+        let filename = self.filename_from_uri(&params.text_document.uri);
+        self.spawn_request::<SelectionRangeRequest>(id, move |db| {
+            let offsets: Vec<_> = params
+                .positions
+                .iter()
+                .map(|&position| db.dada_offset(filename, position))
+                .collect();
+            Some(crate::ranges::selection_ranges(db, filename, &offsets))
+        });
+    }
+
+    pub fn semantic_tokens(&self, id: RequestId, params: SemanticTokensParams) {
+        let filename = self.filename_from_uri(&params.text_document.uri);
+        self.spawn_request::<SemanticTokensFullRequest>(id, move |db| {
+            Some(SemanticTokensResult::Tokens(SemanticTokens {
+                result_id: None,
+                data: crate::semantic_tokens::semantic_tokens(db, filename),
+            }))
+        });
+    }
+
+    /// Computes the response to a request on the thread pool, using a
+    /// snapshot of the database, and sends it.
+    fn spawn_request<R>(
+        &self,
+        id: RequestId,
+        op: impl FnOnce(&dada_db::Db) -> R::Result + Send + 'static,
+    ) where
+        R: lsp_types::request::Request,
+    {
+        self.spawn_fallible_request::<R>(id, move |db| Ok(op(db)))
+    }
+
+    /// Like [`Self::spawn_request`], but if `op` fails, responds with an
+    /// error carrying its message (which clients show to the user).
+    fn spawn_fallible_request<R>(
+        &self,
+        id: RequestId,
+        op: impl FnOnce(&dada_db::Db) -> Result<R::Result, String> + Send + 'static,
+    ) where
+        R: lsp_types::request::Request,
+    {
+        let sender = self.sender.clone();
+        let db = self.db.snapshot();
+        self.threads.execute(move || {
+            let response = match op(&db) {
+                Ok(result) => Response::new_ok(id, result),
+                Err(message) => Response::new_err(id, ErrorCode::InvalidParams as i32, message),
+            };
+            sender.send(Message::Response(response)).unwrap();
+        });
+    }
 }
 
-trait DadaLspMethods {
+pub(crate) trait DadaLspMethods {
+    fn dada_offset(&self, filename: Filename, position: Position) -> Offset;
     fn lsp_position(&self, filename: Filename, offset: Offset) -> Position;
     fn lsp_range(&self, span: dada_ir::span::FileSpan) -> Range;
     fn lsp_location(&self, span: dada_ir::span::FileSpan) -> Location;
@@ -82,11 +472,19 @@ trait DadaLspMethods {
 }
 
 impl DadaLspMethods for dada_db::Db {
+    // LSP positions are 0-based, and count columns in UTF-16 code units;
+    // all conversions go through `byte_offset` and `utf16_column`.
+    fn dada_offset(&self, filename: Filename, position: Position) -> Offset {
+        let source_text = dada_ir::manifest::source_text(self, filename);
+        Offset::from(byte_offset(source_text, position))
+    }
+
     fn lsp_position(&self, filename: Filename, offset: Offset) -> Position {
-        let line_column = dada_ir::lines::line_column(self, filename, offset);
+        let source_text = dada_ir::manifest::source_text(self, filename);
+        let line = source_text.line_table().line_column(offset).line0();
         Position {
-            line: line_column.line1(),
-            character: line_column.column1(),
+            line,
+            character: utf16_column(source_text, line, offset),
         }
     }
 
@@ -112,7 +510,9 @@ impl DadaLspMethods for dada_db::Db {
             dada_ir::diagnostic::Severity::Warning => DiagnosticSeverity::Warning,
             dada_ir::diagnostic::Severity::Error => DiagnosticSeverity::Error,
         });
-        let code = None;
+        let code = dada_diagnostic
+            .code
+            .map(|code| NumberOrString::String(code.to_string()));
         let source = None;
         let message = dada_diagnostic.message.clone();
         let related_information = Some(
@@ -137,3 +537,55 @@ impl DadaLspMethods for dada_db::Db {
         }
     }
 }
+
+/// Converts an LSP position to a byte offset in `text`. LSP counts columns
+/// in UTF-16 code units. Positions past the end of a line (or of the text)
+/// are clamped to it.
+fn byte_offset(text: &SourceText, position: Position) -> usize {
+    let line_start = usize::from(
+        text.line_table()
+            .offset(LineColumn::new0(position.line, 0_u32)),
+    );
+    let line = &text[line_start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+    let mut utf16_column = 0;
+    for (index, ch) in line.char_indices() {
+        if utf16_column >= position.character as usize {
+            return line_start + index;
+        }
+        utf16_column += ch.len_utf16();
+    }
+    line_start + line.len()
+}
+
+/// The column of `offset`, which is on the 0-based line `line` of `text`,
+/// in UTF-16 code units.
+fn utf16_column(text: &SourceText, line: u32, offset: Offset) -> u32 {
+    let line_start = usize::from(text.line_table().offset(LineColumn::new0(line, 0_u32)));
+    let offset = usize::from(offset).clamp(line_start, text.len());
+    text[line_start..]
+        .char_indices()
+        .take_while(|&(index, _)| line_start + index < offset)
+        .map(|(_, ch)| ch.len_utf16() as u32)
+        .sum()
+}
+
+/// Pushes the paths of the `.dada` files under `dir` onto `paths`,
+/// skipping hidden directories (like `.git`) and build output.
+fn find_dada_files(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" && name != "node_modules" {
+                find_dada_files(&path, paths);
+            }
+        } else if path.extension().is_some_and(|e| e == "dada") {
+            paths.push(path);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! The text shown when hovering over a name: its declaration, with the
+//! storage specifier spelled out even where it was defaulted. Items are
+//! followed by the comments just before them.
+//!
+//! Dada has no type checker yet, so there are no types to show.
+
+use dada_ir::{
+    code::validated,
+    effect::Effect,
+    item::Item,
+    storage::{Atomic, Specifier},
+    word::Word,
+};
+use dada_parse::prelude::*;
+use dada_validate::symbols::Symbol;
+
+/// Describes `symbol` in Markdown.
+pub(crate) fn hover_text(db: &dada_db::Db, symbol: Symbol) -> String {
+    match symbol {
+        Symbol::LocalVariable(tree, local_variable) => {
+            let data = &tree.data(db).tables[local_variable];
+            let kind = match tree.origins(db)[local_variable] {
+                validated::LocalVariableOrigin::Parameter(_) => "parameter",
+                validated::LocalVariableOrigin::LocalVariable(_)
+                | validated::LocalVariableOrigin::Temporary(_) => "local variable",
+            };
+            let function = tree.origin(db).name(db).as_str(db);
+            let (specifier, defaulted) = match data.specifier {
+                Some(specifier) => (specifier.specifier(db), specifier.defaulted(db)),
+                None => (Specifier::Any, false),
+            };
+            let mut text = code_block(&declaration(db, data.atomic, specifier, data.name));
+            text.push_str(&format!("{} of `{}`", kind, function));
+            if defaulted {
+                text.push_str(&format!(
+                    "; no specifier was given, so it is `{}`",
+                    specifier
+                ));
+            }
+            text
+        }
+        Symbol::Item(item) => {
+            let mut text = code_block(&item_signature(db, item));
+            let span = item.span(db);
+            text.push_str(&dada_lex::trivia::documentation(
+                db,
+                span.filename,
+                span.start,
+            ));
+            text
+        }
+        Symbol::Intrinsic(intrinsic) => {
+            format!("`{}` is a built-in function", intrinsic.as_str(db))
+        }
+        Symbol::HostFunction(name) => format!(
+            "`{}` is a function provided by the program embedding Dada",
+            name.as_str(db)
+        ),
+    }
+}
+
+/// The declaration of `item`, like `fn foo(my x)`, with the storage
+/// specifier of each parameter spelled out.
+pub fn item_signature(db: &dada_db::Db, item: Item) -> String {
+    match item {
+        Item::Function(function) => {
+            let effect = match function.code(db).effect {
+                Effect::Async => "async ",
+                Effect::Atomic => "atomic ",
+                Effect::Default => "",
+            };
+            let parameters = parameter_list(db, function.parameters(db));
+            format!(
+                "{}fn {}({})",
+                effect,
+                function.name(db).as_str(db),
+                parameters
+            )
+        }
+        Item::Class(class) => format!(
+            "class {}({})",
+            class.name(db).as_str(db),
+            parameter_list(db, class.fields(db))
+        ),
+        Item::ExternFunction(function) => {
+            let signature = function.signature(db);
+            let parameters: Vec<String> = signature
+                .parameters
+                .iter()
+                .map(|p| format!("{}: {}", p.name.as_str(db), p.ty))
+                .collect();
+            let return_ty = match signature.return_ty {
+                Some(ty) => format!(" -> {}", ty),
+                None => String::new(),
+            };
+            format!(
+                "extern \"{}\" fn {}({}){}",
+                function.library(db).as_str(db),
+                function.name(db).as_str(db),
+                parameters.join(", "),
+                return_ty
+            )
+        }
+    }
+}
+
+fn parameter_list(db: &dada_db::Db, parameters: &[dada_ir::parameter::Parameter]) -> String {
+    let parameters: Vec<String> = parameters
+        .iter()
+        .map(|parameter| {
+            let decl = parameter.decl(db);
+            declaration(
+                db,
+                decl.atomic,
+                decl.specifier.specifier(db),
+                Some(decl.name),
+            )
+        })
+        .collect();
+    parameters.join(", ")
+}
+
+/// Formats a variable declaration, like `atomic my x`.
+pub(crate) fn declaration(
+    db: &dada_db::Db,
+    atomic: Atomic,
+    specifier: Specifier,
+    name: Option<Word>,
+) -> String {
+    let atomic = match atomic {
+        Atomic::Yes => "atomic ",
+        Atomic::No => "",
+    };
+    let name = name.map_or("<temporary>", |name| name.as_str(db));
+    format!("{}{} {}", atomic, specifier, name)
+}
+
+fn code_block(code: &str) -> String {
+    format!("```dada\n{}\n```\n", code)
+}
@@ -0,0 +1,244 @@
+//! Inlay hints: the specifiers that were defaulted on declarations, the
+//! specifier of the temporaries the validator introduces, and the names of
+//! the parameters that positional arguments are passed to.
+//!
+//! Inlay hints are newer than the version of `lsp-types` we use, so the
+//! request and its types are declared here, following LSP 3.17.
+
+use dada_id::prelude::*;
+use dada_ir::{
+    code::{syntax, validated},
+    filename::Filename,
+    function::Function,
+    item::Item,
+    span::Offset,
+    storage::Specifier,
+};
+use dada_parse::prelude::*;
+use dada_validate::symbols::Symbol;
+use lsp_types::{Position, Range, TextDocumentIdentifier};
+use serde::{Deserialize, Serialize};
+
+use crate::db::DadaLspMethods;
+
+/// The `textDocument/inlayHint` request.
+pub(crate) enum InlayHintRequest {}
+
+impl lsp_types::request::Request for InlayHintRequest {
+    type Params = InlayHintParams;
+    type Result = Option<Vec<InlayHint>>;
+    const METHOD: &'static str = "textDocument/inlayHint";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InlayHintParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InlayHint {
+    pub position: Position,
+    pub label: String,
+
+    /// 1 for a hint about a type (which is how we present specifiers) or
+    /// 2 for a hint about a parameter.
+    pub kind: u32,
+    pub padding_left: bool,
+    pub padding_right: bool,
+}
+
+const TYPE: u32 = 1;
+const PARAMETER: u32 = 2;
+
+/// Which hints to show. Clients can set these in the `inlayHints` field
+/// of the initialization options; if none are enabled, the server doesn't
+/// advertise inlay hints at all.
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub(crate) struct InlayHintsConfig {
+    /// Show the specifier of declarations that didn't give one.
+    pub specifiers: bool,
+
+    /// Show the (`any`) specifier of each temporary. Off by default, as
+    /// almost every call and field access creates one.
+    pub temporaries: bool,
+
+    /// Show parameter names before positional arguments.
+    pub parameter_names: bool,
+}
+
+impl Default for InlayHintsConfig {
+    fn default() -> Self {
+        Self {
+            specifiers: true,
+            temporaries: false,
+            parameter_names: true,
+        }
+    }
+}
+
+impl InlayHintsConfig {
+    /// Reads the configuration from the client's initialization options,
+    /// using the defaults for anything missing or malformed.
+    pub(crate) fn from_initialization_options(options: Option<&serde_json::Value>) -> Self {
+        options
+            .and_then(|options| options.get("inlayHints"))
+            .and_then(|config| serde_json::from_value(config.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn any_enabled(&self) -> bool {
+        self.specifiers || self.temporaries || self.parameter_names
+    }
+}
+
+/// The hints in `filename` between `start` and `end`.
+pub(crate) fn inlay_hints(
+    db: &dada_db::Db,
+    filename: Filename,
+    (start, end): (Offset, Offset),
+    config: InlayHintsConfig,
+) -> Vec<InlayHint> {
+    let mut hints = vec![];
+    for &item in filename.items(db) {
+        let span = item.span(db);
+        if span.end < start || end < span.start {
+            continue;
+        }
+        match item {
+            Item::Function(function) => {
+                function_hints(db, function, config, &mut hints);
+            }
+            Item::Class(class) => {
+                if config.specifiers {
+                    for field in class.fields(db) {
+                        let specifier = field.decl(db).specifier;
+                        if specifier.defaulted(db) {
+                            hints.push(specifier_hint(
+                                db,
+                                filename,
+                                specifier.span(db).start,
+                                specifier.specifier(db),
+                            ));
+                        }
+                    }
+                }
+            }
+            Item::ExternFunction(_) => {}
+        }
+    }
+
+    let (start, end) = (
+        db.lsp_position(filename, start),
+        db.lsp_position(filename, end),
+    );
+    hints.retain(|hint| start <= hint.position && hint.position <= end);
+    hints.sort_by_key(|hint| hint.position);
+    hints.dedup_by_key(|hint| (hint.position, hint.label.clone()));
+    hints
+}
+
+fn function_hints(
+    db: &dada_db::Db,
+    function: Function,
+    config: InlayHintsConfig,
+    hints: &mut Vec<InlayHint>,
+) {
+    let filename = function.filename(db);
+    let tree = function.validated_tree(db);
+    let data = tree.data(db);
+    let origins = tree.origins(db);
+    let syntax_tree = function.syntax_tree(db);
+    let syntax_data = syntax_tree.data(db);
+    let spans = syntax_tree.spans(db);
+
+    for local_variable in data.max_local_variable().iter() {
+        let local_data = local_variable.data(&data.tables);
+        match (local_data.specifier, origins[local_variable]) {
+            (Some(specifier), _) if config.specifiers && specifier.defaulted(db) => {
+                hints.push(specifier_hint(
+                    db,
+                    filename,
+                    specifier.span(db).start,
+                    specifier.specifier(db),
+                ));
+            }
+            (None, validated::LocalVariableOrigin::Temporary(expr)) if config.temporaries => {
+                hints.push(InlayHint {
+                    position: db.lsp_position(filename, syntax_tree.file_span(db, spans[expr]).end),
+                    label: Specifier::Any.to_string(),
+                    kind: TYPE,
+                    padding_left: true,
+                    padding_right: false,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    if config.parameter_names {
+        let uses = dada_validate::symbols::symbol_uses(db, filename);
+        for expr in syntax::Expr::max_key(&syntax_data.tables).iter() {
+            let syntax::ExprData::Call(callee, arguments) = expr.data(&syntax_data.tables) else {
+                continue;
+            };
+            let callee_span = syntax_tree.file_span(db, spans[*callee]);
+            let Some(callee_use) = uses.iter().find(|u| u.span == callee_span) else {
+                continue;
+            };
+            let names: Vec<&str> = match callee_use.symbol {
+                Symbol::Item(Item::Function(f)) => f
+                    .parameters(db)
+                    .iter()
+                    .map(|p| p.name(db).as_str(db))
+                    .collect(),
+                Symbol::Item(Item::Class(c)) => {
+                    c.fields(db).iter().map(|p| p.name(db).as_str(db)).collect()
+                }
+                Symbol::Item(Item::ExternFunction(f)) => f
+                    .signature(db)
+                    .parameters
+                    .iter()
+                    .map(|p| p.name.as_str(db))
+                    .collect(),
+                Symbol::LocalVariable(..) | Symbol::Intrinsic(_) | Symbol::HostFunction(_) => {
+                    continue
+                }
+            };
+            for (&argument, name) in arguments.iter().zip(names) {
+                let argument_data = argument.data(&syntax_data.tables);
+                let argument_span = syntax_tree.file_span(db, spans[argument]);
+                // Named arguments already say it, and so does a variable
+                // with the same name as the parameter.
+                if argument_data.name.word(db).is_some() || argument_span.snippet(db) == name {
+                    continue;
+                }
+                hints.push(InlayHint {
+                    position: db.lsp_position(filename, argument_span.start),
+                    label: format!("{}:", name),
+                    kind: PARAMETER,
+                    padding_left: false,
+                    padding_right: true,
+                });
+            }
+        }
+    }
+}
+
+fn specifier_hint(
+    db: &dada_db::Db,
+    filename: Filename,
+    name_start: Offset,
+    specifier: Specifier,
+) -> InlayHint {
+    InlayHint {
+        position: db.lsp_position(filename, name_start),
+        label: specifier.to_string(),
+        kind: TYPE,
+        padding_left: false,
+        padding_right: true,
+    }
+}
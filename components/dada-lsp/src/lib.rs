@@ -1,13 +1,39 @@
+#![feature(let_else)]
+
 use db::LspServerDatabase;
+use inlay_hints::{InlayHintRequest, InlayHintsConfig};
 use lsp_types::{
-    notification::{DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument},
-    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    notification::{
+        DidChangeTextDocument, DidChangeWatchedFiles, DidCloseTextDocument, DidOpenTextDocument,
+    },
+    request::{
+        CodeActionRequest, Completion, DocumentSymbolRequest, FoldingRangeRequest, GotoDefinition,
+        HoverRequest, References, RegisterCapability, Rename, Request as _, SelectionRangeRequest,
+        SemanticTokensFullRequest, SignatureHelpRequest, WorkspaceSymbol,
+    },
+    CodeActionProviderCapability, CompletionOptions, FoldingRangeProviderCapability,
+    HoverProviderCapability, InitializeParams, OneOf, RegistrationParams,
+    SelectionRangeProviderCapability, SemanticTokensFullOptions, SemanticTokensOptions,
+    SemanticTokensServerCapabilities, ServerCapabilities, SignatureHelpOptions,
+    TextDocumentSyncCapability, TextDocumentSyncKind,
 };
 use serde::de::DeserializeOwned;
 
-use lsp_server::{Connection, IoThreads, Message, Notification};
+use lsp_server::{Connection, IoThreads, Message, Notification, Request, RequestId};
 
+mod completion;
 mod db;
+mod hover;
+mod inlay_hints;
+mod outline;
+mod ranges;
+mod rename;
+mod semantic_tokens;
+mod signature_help;
+
+pub use completion::completions;
+pub use hover::item_signature;
+pub use semantic_tokens::{legend as semantic_tokens_legend, semantic_tokens};
 
 pub struct LspServer {
     connection: Connection,
@@ -23,11 +49,18 @@ impl LspServer {
         let (connection, io_threads) = Connection::stdio();
 
         // Run the server
-        let (id, _params) = connection.initialize_start()?;
+        let (id, params) = connection.initialize_start()?;
 
-        // let init_params: InitializeParams = serde_json::from_value(params).unwrap();
+        let init_params: InitializeParams = serde_json::from_value(params)?;
         // let client_capabilities: ClientCapabilities = init_params.capabilities;
-        let server_capabilities = Self::server_capabilities();
+        let inlay_hints_config = InlayHintsConfig::from_initialization_options(
+            init_params.initialization_options.as_ref(),
+        );
+        let mut server_capabilities = serde_json::to_value(Self::server_capabilities())?;
+        if inlay_hints_config.any_enabled() {
+            // Not yet a field of `ServerCapabilities` in our `lsp-types`.
+            server_capabilities["inlayHintProvider"] = true.into();
+        }
 
         let initialize_data = serde_json::json!({
             "capabilities": server_capabilities,
@@ -39,7 +72,37 @@ impl LspServer {
 
         connection.initialize_finish(id, initialize_data)?;
 
-        let db = LspServerDatabase::new(connection.sender.clone());
+        // Without file watching, files the user hasn't opened are only
+        // read once, below.
+        let can_watch_files = init_params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.did_change_watched_files.as_ref())
+            .and_then(|capability| capability.dynamic_registration)
+            .unwrap_or(false);
+        if can_watch_files {
+            let params = RegistrationParams {
+                registrations: vec![LspServerDatabase::file_watcher_registration()],
+            };
+            let request = Request::new(
+                RequestId::from("register-file-watcher".to_string()),
+                RegisterCapability::METHOD.to_string(),
+                params,
+            );
+            connection.sender.send(Message::Request(request))?;
+        }
+
+        let mut db = LspServerDatabase::new(connection.sender.clone());
+        db.set_inlay_hints_config(inlay_hints_config);
+
+        // Load every file in the workspace, so that (e.g.) references can
+        // be found in files the user hasn't opened.
+        let roots = match init_params.workspace_folders {
+            Some(folders) => folders.into_iter().map(|folder| folder.uri).collect(),
+            None => init_params.root_uri.into_iter().collect(),
+        };
+        db.load_workspace(roots);
 
         Ok(Self {
             connection,
@@ -50,7 +113,33 @@ impl LspServer {
 
     fn server_capabilities() -> ServerCapabilities {
         ServerCapabilities {
-            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::Full)),
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                TextDocumentSyncKind::Incremental,
+            )),
+            definition_provider: Some(OneOf::Left(true)),
+            references_provider: Some(OneOf::Left(true)),
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            workspace_symbol_provider: Some(OneOf::Left(true)),
+            rename_provider: Some(OneOf::Left(true)),
+            folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+            selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            completion_provider: Some(CompletionOptions {
+                trigger_characters: Some(vec![".".to_string()]),
+                ..CompletionOptions::default()
+            }),
+            signature_help_provider: Some(SignatureHelpOptions {
+                trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
+                ..SignatureHelpOptions::default()
+            }),
+            semantic_tokens_provider: Some(
+                SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
+                    legend: semantic_tokens::legend(),
+                    full: Some(SemanticTokensFullOptions::Bool(true)),
+                    ..SemanticTokensOptions::default()
+                }),
+            ),
             ..ServerCapabilities::default()
         }
     }
@@ -62,19 +151,49 @@ impl LspServer {
                     if self.connection.handle_shutdown(&req)? {
                         return Ok(());
                     }
-                    // Currently don't handle any other requests
+                    if let Some((id, params)) = as_request::<GotoDefinition>(&req) {
+                        self.db.goto_definition(id, params)
+                    } else if let Some((id, params)) = as_request::<References>(&req) {
+                        self.db.references(id, params)
+                    } else if let Some((id, params)) = as_request::<HoverRequest>(&req) {
+                        self.db.hover(id, params)
+                    } else if let Some((id, params)) = as_request::<DocumentSymbolRequest>(&req) {
+                        self.db.document_symbols(id, params)
+                    } else if let Some((id, params)) = as_request::<WorkspaceSymbol>(&req) {
+                        self.db.workspace_symbols(id, params)
+                    } else if let Some((id, params)) = as_request::<FoldingRangeRequest>(&req) {
+                        self.db.folding_ranges(id, params)
+                    } else if let Some((id, params)) = as_request::<SelectionRangeRequest>(&req) {
+                        self.db.selection_ranges(id, params)
+                    } else if let Some((id, params)) = as_request::<CodeActionRequest>(&req) {
+                        self.db.code_actions(id, params)
+                    } else if let Some((id, params)) = as_request::<Completion>(&req) {
+                        self.db.completion(id, params)
+                    } else if let Some((id, params)) = as_request::<Rename>(&req) {
+                        self.db.rename(id, params)
+                    } else if let Some((id, params)) = as_request::<SignatureHelpRequest>(&req) {
+                        self.db.signature_help(id, params)
+                    } else if let Some((id, params)) = as_request::<InlayHintRequest>(&req) {
+                        self.db.inlay_hints(id, params)
+                    } else if let Some((id, params)) = as_request::<SemanticTokensFullRequest>(&req)
+                    {
+                        self.db.semantic_tokens(id, params)
+                    }
                 }
                 Message::Notification(x) => {
                     if let Some(params) = as_notification::<DidOpenTextDocument>(&x) {
                         self.db.did_open(params)
                     } else if let Some(params) = as_notification::<DidChangeTextDocument>(&x) {
                         self.db.did_change(params)
-                    } else if let Some(_params) = as_notification::<DidCloseTextDocument>(&x) {
-                        // FIXME self.did_close(params)
+                    } else if let Some(params) = as_notification::<DidCloseTextDocument>(&x) {
+                        self.db.did_close(params)
+                    } else if let Some(params) = as_notification::<DidChangeWatchedFiles>(&x) {
+                        self.db.did_change_watched_files(params)
                     }
                 }
                 Message::Response(_) => {
-                    // Don't expect any of these
+                    // The only requests we send are registrations, whose
+                    // responses we don't need
                 }
             }
         }
@@ -101,6 +220,20 @@ where
     }
 }
 
+fn as_request<T>(x: &Request) -> Option<(RequestId, T::Params)>
+where
+    T: lsp_types::request::Request,
+    T::Params: DeserializeOwned,
+{
+    if x.method == T::METHOD {
+        let params = serde_json::from_value(x.params.clone())
+            .unwrap_or_else(|err| panic!("Invalid request\nMethod: {}\n error: {}", x.method, err));
+        Some((x.id.clone(), params))
+    } else {
+        None
+    }
+}
+
 fn new_notification<T>(params: T::Params) -> Notification
 where
     T: lsp_types::notification::Notification,
@@ -0,0 +1,83 @@
+//! The functions and classes in a file (for the outline view) and in the
+//! whole workspace (for symbol search).
+
+use dada_ir::{filename::Filename, item::Item};
+use dada_parse::prelude::*;
+use lsp_types::{DocumentSymbol, SymbolInformation, SymbolKind};
+
+use crate::db::DadaLspMethods;
+
+/// The items in `filename`, with the fields of each class as its children.
+#[allow(deprecated)] // `DocumentSymbol::deprecated` must still be given
+pub(crate) fn document_symbols(db: &dada_db::Db, filename: Filename) -> Vec<DocumentSymbol> {
+    filename
+        .items(db)
+        .iter()
+        .map(|&item| {
+            let children = match item {
+                Item::Class(class) => Some(
+                    class
+                        .fields(db)
+                        .iter()
+                        .map(|field| {
+                            let anchor = class.field_tokens(db).anchor(db);
+                            let name_span = field.decl_span(db).name_span;
+                            let range = db.lsp_range(anchor.file_span(db, name_span));
+                            DocumentSymbol {
+                                name: field.name(db).as_str(db).to_string(),
+                                detail: None,
+                                kind: SymbolKind::Field,
+                                tags: None,
+                                deprecated: None,
+                                range,
+                                selection_range: range,
+                                children: None,
+                            }
+                        })
+                        .collect(),
+                ),
+                Item::Function(_) | Item::ExternFunction(_) => None,
+            };
+            DocumentSymbol {
+                name: item.name(db).as_str(db).to_string(),
+                detail: None,
+                kind: symbol_kind(item),
+                tags: None,
+                deprecated: None,
+                range: db.lsp_range(item.span(db)),
+                selection_range: db.lsp_range(item.name_span(db)),
+                children,
+            }
+        })
+        .collect()
+}
+
+/// The items in `filenames` whose names contain `query`, ignoring case.
+#[allow(deprecated)] // `SymbolInformation::deprecated` must still be given
+pub(crate) fn workspace_symbols(
+    db: &dada_db::Db,
+    filenames: &[Filename],
+    query: &str,
+) -> Vec<SymbolInformation> {
+    let query = query.to_lowercase();
+    filenames
+        .iter()
+        .flat_map(|&filename| filename.items(db))
+        .filter(|item| item.name(db).as_str(db).to_lowercase().contains(&query))
+        .map(|&item| SymbolInformation {
+            name: item.name(db).as_str(db).to_string(),
+            kind: symbol_kind(item),
+            tags: None,
+            deprecated: None,
+            location: db.lsp_location(item.name_span(db)),
+            container_name: None,
+        })
+        .collect()
+}
+
+fn symbol_kind(item: Item) -> SymbolKind {
+    match item {
+        Item::Function(_) | Item::ExternFunction(_) => SymbolKind::Function,
+        Item::Class(_) => SymbolKind::Class,
+    }
+}
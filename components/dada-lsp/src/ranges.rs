@@ -0,0 +1,125 @@
+//! Folding ranges and selection ranges, both read off the spans that the
+//! parser records for items and expressions.
+
+use dada_id::prelude::*;
+use dada_ir::{
+    code::syntax,
+    filename::Filename,
+    item::Item,
+    span::{FileSpan, Offset},
+};
+use dada_parse::prelude::*;
+use lsp_types::{FoldingRange, FoldingRangeKind, SelectionRange};
+
+use crate::db::DadaLspMethods;
+
+/// A folding range for each item and each block that spans more than
+/// one line.
+pub(crate) fn folding_ranges(db: &dada_db::Db, filename: Filename) -> Vec<FoldingRange> {
+    let mut spans = vec![];
+    for &item in filename.items(db) {
+        spans.push(item.span(db));
+        if let Item::Function(function) = item {
+            let syntax_tree = function.syntax_tree(db);
+            let data = syntax_tree.data(db);
+            let syntax_spans = syntax_tree.spans(db);
+            for expr in syntax::Expr::max_key(&data.tables).iter() {
+                if let syntax::ExprData::Seq(_) = expr.data(&data.tables) {
+                    spans.push(syntax_tree.file_span(db, syntax_spans[expr]));
+                }
+            }
+        }
+    }
+
+    let mut ranges: Vec<FoldingRange> = spans
+        .into_iter()
+        .map(|span| {
+            let start = db.lsp_position(filename, span.start);
+            let end = db.lsp_position(filename, span.end);
+            FoldingRange {
+                start_line: start.line,
+                start_character: None,
+                end_line: end.line,
+                end_character: None,
+                kind: Some(FoldingRangeKind::Region),
+            }
+        })
+        .filter(|range| range.start_line < range.end_line)
+        .collect();
+
+    // A function body starts on the same line as the function, so the two
+    // would fold the same lines; keep one range per starting line.
+    ranges.sort_by_key(|range| (range.start_line, std::cmp::Reverse(range.end_line)));
+    ranges.dedup_by_key(|range| range.start_line);
+    ranges
+}
+
+/// For each of `offsets`, the spans around it from the innermost outward:
+/// an expression, the expressions enclosing it, and the item.
+pub(crate) fn selection_ranges(
+    db: &dada_db::Db,
+    filename: Filename,
+    offsets: &[Offset],
+) -> Vec<SelectionRange> {
+    offsets
+        .iter()
+        .map(|&offset| {
+            let mut spans = enclosing_spans(db, filename, offset);
+
+            // Build the chain from the outside in, so each range can own
+            // its parent.
+            spans.sort_by_key(|span| (std::cmp::Reverse(span.end - span.start), span.start));
+            spans.dedup();
+            let mut selection_range = None;
+            for span in spans {
+                selection_range = Some(SelectionRange {
+                    range: db.lsp_range(span),
+                    parent: selection_range.map(Box::new),
+                });
+            }
+
+            // The client expects a range for every position, even one
+            // outside any item.
+            selection_range.unwrap_or_else(|| {
+                let position = db.lsp_position(filename, offset);
+                SelectionRange {
+                    range: lsp_types::Range::new(position, position),
+                    parent: None,
+                }
+            })
+        })
+        .collect()
+}
+
+/// The spans of the items, expressions, and arguments that contain
+/// `offset`, in no particular order.
+fn enclosing_spans(db: &dada_db::Db, filename: Filename, offset: Offset) -> Vec<FileSpan> {
+    let contains = |span: FileSpan| span.start <= offset && offset <= span.end;
+    let mut spans = vec![];
+    for &item in filename.items(db) {
+        let item_span = item.span(db);
+        if !contains(item_span) {
+            continue;
+        }
+        spans.push(item_span);
+
+        if let Item::Function(function) = item {
+            let syntax_tree = function.syntax_tree(db);
+            let data = syntax_tree.data(db);
+            let syntax_spans = syntax_tree.spans(db);
+            let expr_spans = syntax::Expr::max_key(&data.tables)
+                .iter()
+                .map(|expr| syntax_spans[expr]);
+            let named_expr_spans = syntax::NamedExpr::max_key(&data.tables)
+                .iter()
+                .map(|named_expr| syntax_spans[named_expr]);
+            spans.extend(
+                expr_spans
+                    .chain(named_expr_spans)
+                    .map(|span| syntax_tree.file_span(db, span))
+                    .filter(|&span| contains(span)),
+            );
+        }
+    }
+    spans
+}
@@ -0,0 +1,92 @@
+//! Renaming a local variable, parameter, function, or class. The edits are
+//! the spans of every declaration and use of the symbol (see
+//! [`dada_validate::symbols::symbol_uses`]).
+//!
+//! A rename is refused if it would change what some name refers to: if
+//! the new name is already in scope somewhere the symbol is used, or if
+//! the renamed symbol would be in scope somewhere the new name is already
+//! used.
+
+use dada_ir::{filename::Filename, span::Offset, word::Word};
+use dada_validate::symbols::{Symbol, SymbolUse};
+
+/// The spans to replace with `new_name` to rename the symbol at `offset`,
+/// or an explanation of why it can't be renamed.
+pub(crate) fn rename(
+    db: &dada_db::Db,
+    filename: Filename,
+    offset: Offset,
+    new_name: &str,
+) -> Result<Vec<SymbolUse>, String> {
+    let Some(symbol_use) = dada_validate::symbols::symbol_at(db, filename, offset) else {
+        return Err("there is no name to rename here".to_string());
+    };
+    let symbol = symbol_use.symbol;
+    match symbol {
+        Symbol::LocalVariable(..) | Symbol::Item(_) => {}
+        Symbol::Intrinsic(_) | Symbol::HostFunction(_) => {
+            return Err(format!(
+                "`{}` is not declared in Dada code, so it can't be renamed",
+                symbol_use.span.snippet(db)
+            ));
+        }
+    }
+
+    if !is_identifier(new_name) {
+        return Err(format!("`{}` is not a valid name", new_name));
+    }
+    let new_word = Word::from(db, new_name);
+    if dada_ir::kw::keywords(db).contains_key(&new_word) {
+        return Err(format!("`{}` is a keyword", new_name));
+    }
+    if symbol.name(db) == Some(new_word) {
+        return Ok(vec![]);
+    }
+
+    // Functions and classes can only be named from within their own file,
+    // so every use of any symbol we care about is in `filename`.
+    let all_uses = dada_validate::symbols::symbol_uses(db, filename);
+    let uses: Vec<SymbolUse> = all_uses
+        .iter()
+        .filter(|u| u.symbol == symbol)
+        .copied()
+        .collect();
+
+    for u in &uses {
+        let in_scope = dada_validate::symbols::names_in_scope(db, filename, u.span.start);
+        if in_scope
+            .iter()
+            .any(|&(name, other)| name == new_word && other != symbol)
+        {
+            return Err(format!(
+                "`{}` is already in scope at line {}, so it would collide",
+                new_name,
+                db.line_column(filename, u.span.start).line1()
+            ));
+        }
+    }
+
+    for u in all_uses
+        .iter()
+        .filter(|u| u.symbol != symbol && u.symbol.name(db) == Some(new_word))
+    {
+        let in_scope = dada_validate::symbols::names_in_scope(db, filename, u.span.start);
+        if in_scope.iter().any(|&(_, other)| other == symbol) {
+            return Err(format!(
+                "the renamed `{}` would shadow the `{}` used at line {}",
+                symbol_use.span.snippet(db),
+                new_name,
+                db.line_column(filename, u.span.start).line1()
+            ));
+        }
+    }
+
+    Ok(uses)
+}
+
+/// True if `name` would be lexed as a single alphabetic token.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some('a'..='z' | 'A'..='Z' | '_'))
+        && chars.all(|c| matches!(c, 'a'..='z' | 'A'..='Z' | '_' | '0'..='9'))
+}
@@ -0,0 +1,181 @@
+//! Semantic highlighting. The lexer finds keywords, literals, and
+//! comments; identifiers are classified by what the validator resolved
+//! them to (see [`dada_validate::symbols`]).
+
+use std::collections::HashMap;
+
+use dada_ir::{
+    code::validated,
+    filename::Filename,
+    item::Item,
+    kw::Keyword,
+    span::{Offset, Span},
+    token::Token,
+    token_tree::TokenTree,
+};
+use dada_validate::symbols::{Symbol, SymbolUse};
+use lsp_types::{SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensLegend};
+
+use crate::db::DadaLspMethods;
+
+/// The token types, in the order of their indices in the legend.
+const TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::CLASS,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::STRING,
+    SemanticTokenType::COMMENT,
+];
+
+const KEYWORD: u32 = 0;
+const FUNCTION: u32 = 1;
+const CLASS: u32 = 2;
+const PARAMETER: u32 = 3;
+const VARIABLE: u32 = 4;
+const NUMBER: u32 = 5;
+const STRING: u32 = 6;
+const COMMENT: u32 = 7;
+
+/// The token modifiers, in the order of their bits in the legend.
+/// Permission keywords (like `my` or `lease`) are keywords with the
+/// `permission` modifier, so editors that don't know the modifier still
+/// highlight them as keywords.
+const TOKEN_MODIFIERS: &[SemanticTokenModifier] = &[
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::new("permission"),
+];
+
+const DECLARATION: u32 = 1 << 0;
+const PERMISSION: u32 = 1 << 1;
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+/// The semantic tokens for `filename`, encoded relative to one another
+/// as LSP requires.
+pub fn semantic_tokens(db: &dada_db::Db, filename: Filename) -> Vec<SemanticToken> {
+    let uses: HashMap<Offset, SymbolUse> = dada_validate::symbols::symbol_uses(db, filename)
+        .iter()
+        .map(|u| (u.span.start, *u))
+        .collect();
+
+    let mut classified = vec![];
+    classify(db, dada_lex::lex_file(db, filename), &uses, &mut classified);
+
+    let mut tokens = Vec::with_capacity(classified.len());
+    let (mut previous_line, mut previous_column) = (0, 0);
+    for (span, token_type, modifiers) in classified {
+        let start = db.lsp_position(filename, span.start);
+        let end = db.lsp_position(filename, span.end);
+        if start.line != end.line {
+            // Not all clients support tokens that span lines; leave
+            // multi-line strings unhighlighted rather than split them.
+            continue;
+        }
+        let delta_line = start.line - previous_line;
+        let delta_start = if delta_line == 0 {
+            start.character - previous_column
+        } else {
+            start.character
+        };
+        tokens.push(SemanticToken {
+            delta_line,
+            delta_start,
+            length: end.character - start.character,
+            token_type,
+            token_modifiers_bitset: modifiers,
+        });
+        previous_line = start.line;
+        previous_column = start.character;
+    }
+    tokens
+}
+
+/// Pushes `(span, token type, modifiers)` for each highlighted token in
+/// `tree` onto `classified`, in order.
+fn classify(
+    db: &dada_db::Db,
+    tree: TokenTree,
+    uses: &HashMap<Offset, SymbolUse>,
+    classified: &mut Vec<(Span, u32, u32)>,
+) {
+    let keywords = dada_ir::kw::keywords(db);
+    let anchor = tree.anchor(db);
+    for (span, token) in tree.spanned_tokens(db) {
+        let span: Span = anchor.file_span(db, span).into();
+        let (token_type, modifiers) = match token {
+            Token::Alphabetic(word) => {
+                if let Some(symbol_use) = uses.get(&span.start) {
+                    let modifiers = if symbol_use.is_definition {
+                        DECLARATION
+                    } else {
+                        0
+                    };
+                    (symbol_type(db, symbol_use.symbol), modifiers)
+                } else if let Some(&keyword) = keywords.get(&word) {
+                    let modifiers = if is_permission_keyword(keyword) {
+                        PERMISSION
+                    } else {
+                        0
+                    };
+                    (KEYWORD, modifiers)
+                } else {
+                    continue;
+                }
+            }
+            Token::Number(_) => (NUMBER, 0),
+            Token::FormatString(_) => (STRING, 0),
+            Token::Comment(_) => (COMMENT, 0),
+            Token::Tree(tree) => {
+                classify(db, tree, uses, classified);
+                continue;
+            }
+            Token::Comma
+            | Token::Op(_)
+            | Token::Delimiter(_)
+            | Token::Prefix(_)
+            | Token::Whitespace(_)
+            | Token::Unknown(_) => continue,
+        };
+        classified.push((span, token_type, modifiers));
+    }
+}
+
+fn symbol_type(db: &dada_db::Db, symbol: Symbol) -> u32 {
+    match symbol {
+        Symbol::LocalVariable(tree, local_variable) => match tree.origins(db)[local_variable] {
+            validated::LocalVariableOrigin::Parameter(_) => PARAMETER,
+            validated::LocalVariableOrigin::LocalVariable(_)
+            | validated::LocalVariableOrigin::Temporary(_) => VARIABLE,
+        },
+        Symbol::Item(Item::Class(_)) => CLASS,
+        Symbol::Item(Item::Function(_) | Item::ExternFunction(_))
+        | Symbol::Intrinsic(_)
+        | Symbol::HostFunction(_) => FUNCTION,
+    }
+}
+
+/// True for the keywords that name a permission (like `my`) or an
+/// operation on permissions (like `lease`).
+fn is_permission_keyword(keyword: Keyword) -> bool {
+    matches!(
+        keyword,
+        Keyword::Any
+            | Keyword::Give
+            | Keyword::Lease
+            | Keyword::Leased
+            | Keyword::My
+            | Keyword::Our
+            | Keyword::Share
+            | Keyword::Shared
+            | Keyword::Shlease
+            | Keyword::Shleased
+    )
+}
@@ -0,0 +1,137 @@
+//! Signature help: while typing the arguments to a call, show the
+//! parameters of the function (or class) being called and which one the
+//! cursor is on.
+
+use dada_id::prelude::*;
+use dada_ir::{
+    code::syntax, filename::Filename, item::Item, parameter::Parameter, span::Offset, word::Word,
+};
+use dada_parse::prelude::*;
+use dada_validate::symbols::Symbol;
+use lsp_types::{ParameterInformation, ParameterLabel, SignatureHelp, SignatureInformation};
+
+/// The signature help for the innermost call around `offset` in
+/// `filename`, if the callee is a function, class, or extern function.
+pub(crate) fn signature_help(
+    db: &dada_db::Db,
+    filename: Filename,
+    offset: Offset,
+) -> Option<SignatureHelp> {
+    let function = filename.items(db).iter().find_map(|&item| match item {
+        Item::Function(function)
+            if item.span(db).start <= offset && offset <= item.span(db).end =>
+        {
+            Some(function)
+        }
+        _ => None,
+    })?;
+    let syntax_tree = function.syntax_tree(db);
+    let data = syntax_tree.data(db);
+    let spans = syntax_tree.spans(db);
+    let file_span = |expr: syntax::Expr| syntax_tree.file_span(db, spans[expr]);
+
+    // The innermost call whose argument list contains the cursor.
+    let (callee, arguments) = syntax::Expr::max_key(&data.tables)
+        .iter()
+        .filter_map(|expr| match expr.data(&data.tables) {
+            syntax::ExprData::Call(callee, arguments) => {
+                let in_arguments = file_span(*callee).end < offset && offset <= file_span(expr).end;
+                if in_arguments {
+                    Some((spans[expr], *callee, arguments))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+        .min_by_key(|(span, ..)| span.len())
+        .map(|(_, callee, arguments)| (callee, arguments))?;
+
+    let callee_span = file_span(callee);
+    let symbol = dada_validate::symbols::symbol_uses(db, filename)
+        .iter()
+        .find(|u| u.span == callee_span)?
+        .symbol;
+    let (label, parameters) = signature(db, symbol)?;
+
+    // A named argument is matched with the parameter of that name;
+    // otherwise the cursor is on the first argument that doesn't end
+    // before it (or on the next one, if it is after all of them).
+    let active_parameter = match arguments
+        .iter()
+        .find(|&&argument| offset <= file_span(argument).end)
+    {
+        Some(argument) => match argument.data(&data.tables).name.word(db) {
+            Some(name) => parameters.iter().position(|&(n, _)| n == name),
+            None => arguments.iter().position(|a| a == argument),
+        },
+        None => Some(arguments.len()),
+    };
+
+    Some(SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label,
+            documentation: None,
+            parameters: Some(
+                parameters
+                    .into_iter()
+                    .map(|(_, label)| ParameterInformation {
+                        label: ParameterLabel::Simple(label),
+                        documentation: None,
+                    })
+                    .collect(),
+            ),
+        }],
+        active_signature: Some(0),
+        active_parameter: active_parameter.map(|p| p as u32),
+    })
+}
+
+/// The label for the signature of `symbol`, and the name and label of
+/// each of its parameters. Intrinsics and host functions have no declared
+/// parameters, so they have no signature.
+fn signature(db: &dada_db::Db, symbol: Symbol) -> Option<(String, Vec<(Word, String)>)> {
+    let (keyword, name, parameters) = match symbol {
+        Symbol::Item(Item::Function(function)) => (
+            "fn",
+            function.name(db),
+            parameter_labels(db, function.parameters(db)),
+        ),
+        Symbol::Item(Item::Class(class)) => (
+            "class",
+            class.name(db),
+            parameter_labels(db, class.fields(db)),
+        ),
+        Symbol::Item(Item::ExternFunction(function)) => (
+            "extern fn",
+            function.name(db),
+            function
+                .signature(db)
+                .parameters
+                .iter()
+                .map(|p| (p.name, format!("{}: {}", p.name.as_str(db), p.ty)))
+                .collect(),
+        ),
+        Symbol::LocalVariable(..) | Symbol::Intrinsic(_) | Symbol::HostFunction(_) => return None,
+    };
+    let labels: Vec<&str> = parameters.iter().map(|(_, l)| &l[..]).collect();
+    let label = format!("{} {}({})", keyword, name.as_str(db), labels.join(", "));
+    Some((label, parameters))
+}
+
+/// Labels each parameter with its declaration, like `my x`.
+fn parameter_labels(db: &dada_db::Db, parameters: &[Parameter]) -> Vec<(Word, String)> {
+    parameters
+        .iter()
+        .map(|parameter| {
+            let decl = parameter.decl(db);
+            let label = crate::hover::declaration(
+                db,
+                decl.atomic,
+                decl.specifier.specifier(db),
+                Some(decl.name),
+            );
+            (decl.name, label)
+        })
+        .collect()
+}
@@ -4,6 +4,10 @@ use dada_ir::code::{syntax::Tree, Code};
 
 #[salsa::memoized(in crate::Jar)]
 pub fn parse_code(db: &dyn crate::Db, code: Code) -> Tree {
+    if let Some(tree) = crate::loaded::loaded_syntax_tree(db, code) {
+        return tree;
+    }
+    let _span = dada_ir::phase_span!("parse", db, code.filename(db), code.name).entered();
     let body = code.body_tokens;
     Parser::new(db, body).parse_code_body(code)
 }
@@ -5,7 +5,18 @@ use dada_ir::{filename::Filename, item::Item};
 #[salsa::memoized(in crate::Jar ref)]
 #[allow(clippy::needless_lifetimes)]
 pub fn parse_file(db: &dyn crate::Db, filename: Filename) -> Vec<Item> {
+    if let Some(items) = crate::loaded::loaded_items(db, filename) {
+        return items.clone();
+    }
+    let _span = dada_ir::phase_span!("parse", db, filename).entered();
     let token_tree = dada_lex::lex_file(db, filename);
     let mut parser = Parser::new(db, token_tree);
-    parser.parse_items()
+    let items = parser.parse_items();
+    match crate::reuse::reusable_items(db, filename) {
+        Some(reusable) => items
+            .into_iter()
+            .map(|item| reusable.reuse(db, item))
+            .collect(),
+        None => items,
+    }
 }
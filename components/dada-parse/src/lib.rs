@@ -4,8 +4,10 @@
 
 mod code_parser;
 mod file_parser;
+pub mod loaded;
 mod parameter_parser;
 mod parser;
+pub mod reuse;
 mod token_test;
 mod tokens;
 
@@ -13,7 +15,11 @@ mod tokens;
 pub struct Jar(
     code_parser::parse_code,
     file_parser::parse_file,
+    loaded::loaded_items,
+    loaded::loaded_parameters,
+    loaded::loaded_syntax_tree,
     parameter_parser::parse_parameters,
+    reuse::reusable_items,
 );
 
 pub trait Db: salsa::DbWithJar<Jar> + dada_lex::Db + dada_ir::Db {}
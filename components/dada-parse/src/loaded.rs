@@ -0,0 +1,39 @@
+//! Salsa inputs for programs loaded from compiled bytecode (see the
+//! `dada-bytecode` crate). When set, they take the place of parsing.
+
+use dada_ir::{
+    code::{syntax, Code},
+    filename::Filename,
+    item::Item,
+    parameter::Parameter,
+    token_tree::TokenTree,
+};
+
+/// Salsa input: the items in a file that was loaded from bytecode.
+///
+/// Defaults to `None`, in which case the file is parsed.
+#[salsa::memoized(in crate::Jar ref)]
+#[allow(clippy::needless_lifetimes)]
+pub fn loaded_items(_db: &dyn crate::Db, _filename: Filename) -> Option<Vec<Item>> {
+    None
+}
+
+/// Salsa input: the parameters (or fields) for a token tree that was loaded
+/// from bytecode.
+///
+/// Defaults to `None`, in which case the tokens are parsed.
+#[salsa::memoized(in crate::Jar ref)]
+#[allow(clippy::needless_lifetimes)]
+pub fn loaded_parameters(_db: &dyn crate::Db, _token_tree: TokenTree) -> Option<Vec<Parameter>> {
+    None
+}
+
+/// Salsa input: the syntax tree for code that was loaded from bytecode.
+/// Only the spans of its expressions are meaningful (they are used to
+/// report errors); the expressions themselves are all `Error`.
+///
+/// Defaults to `None`, in which case the code is parsed.
+#[salsa::memoized(in crate::Jar)]
+pub fn loaded_syntax_tree(_db: &dyn crate::Db, _code: Code) -> Option<syntax::Tree> {
+    None
+}
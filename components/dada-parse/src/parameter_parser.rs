@@ -5,5 +5,8 @@ use dada_ir::{parameter::Parameter, token_tree::TokenTree};
 #[salsa::memoized(in crate::Jar ref)]
 #[allow(clippy::needless_lifetimes)]
 pub fn parse_parameters(db: &dyn crate::Db, token_tree: TokenTree) -> Vec<Parameter> {
+    if let Some(parameters) = crate::loaded::loaded_parameters(db, token_tree) {
+        return parameters.clone();
+    }
     Parser::new(db, token_tree).parse_only_parameters()
 }
@@ -3,8 +3,11 @@ use std::string::ToString;
 use crate::{token_test::*, tokens::Tokens};
 
 use dada_ir::{
-    code::syntax::op::Op, diagnostic::DiagnosticBuilder, filename::Filename, span::Span,
-    token::Token, token_tree::TokenTree,
+    code::syntax::op::Op,
+    diagnostic::DiagnosticBuilder,
+    span::{Anchor, AnchoredSpan, FileSpan, Span},
+    token::Token,
+    token_tree::TokenTree,
 };
 
 mod code;
@@ -14,25 +17,52 @@ mod ty;
 
 pub(crate) struct Parser<'me> {
     db: &'me dyn crate::Db,
-    filename: Filename,
+
+    /// The anchor of the tree being parsed, which all the spans the
+    /// parser works with are relative to.
+    anchor: Anchor,
+
+    /// The whitespace tokens in the tree being parsed (not counting those
+    /// in nested trees), in order.
+    whitespace: Vec<Span>,
+
     tokens: Tokens<'me>,
 }
 
 impl<'me> Parser<'me> {
     pub(crate) fn new(db: &'me dyn crate::Db, token_tree: TokenTree) -> Self {
         let tokens = Tokens::new(db, token_tree);
-        let filename = token_tree.filename(db);
+        let anchor = token_tree.anchor(db);
+        let whitespace = token_tree
+            .spanned_tokens(db)
+            .filter(|(_, token)| matches!(token, Token::Whitespace(_)))
+            .map(|(span, _)| span)
+            .collect();
         Self {
             db,
+            anchor,
+            whitespace,
             tokens,
-            filename,
+        }
+    }
+
+    /// Where `span` is in the file. This depends on where the item being
+    /// parsed is, so only use it for diagnostics and for items.
+    fn file_span(&self, span: Span) -> FileSpan {
+        self.anchor.file_span(self.db, span)
+    }
+
+    fn anchored(&self, span: Span) -> AnchoredSpan {
+        AnchoredSpan {
+            anchor: self.anchor,
+            span,
         }
     }
 
     /// Returns `Some` if the next pending token matches `is`, along
     /// with the narrowed view of the next token.
     fn peek<TT: TokenTest>(&mut self, test: TT) -> Option<TT::Narrow> {
-        let span = self.tokens.peek_span().in_file(self.filename);
+        let span = self.anchored(self.tokens.peek_span());
         test.test(self.db, self.tokens.peek()?, span)
     }
 
@@ -122,7 +152,7 @@ impl<'me> Parser<'me> {
     /// If the next token is an opening delimiter, like `(` or `{`,
     /// then consumes it, the token-tree that follows, and the closing delimiter (if present).
     /// Returns the token tree + the span including delimiters.
-    /// Reports an error if there is no closing delimiter.
+    /// A missing closing delimiter has already been reported by the lexer.
     fn delimited(&mut self, delimiter: char) -> Option<(Span, TokenTree)> {
         let (open_span, _) = self.eat(Token::Delimiter(delimiter))?;
 
@@ -131,8 +161,7 @@ impl<'me> Parser<'me> {
 
         // Consume closing delimiter (if present)
         let closing_delimiter = dada_lex::closing_delimiter(delimiter);
-        self.eat(Token::Delimiter(closing_delimiter))
-            .or_report_error(self, || format!("expected `{closing_delimiter}`"));
+        self.eat(Token::Delimiter(closing_delimiter));
 
         let span = open_span.to(self.tokens.last_span());
         Some((span, token_tree))
@@ -144,30 +173,54 @@ impl<'me> Parser<'me> {
         self.tighten_span(span.to(self.tokens.last_span()))
     }
 
+    /// Strips the whitespace from the end of `span`, and all but the last
+    /// whitespace character from its start. This looks at the whitespace
+    /// tokens rather than the source text, which would make the syntax
+    /// tree depend on the whole file.
     fn tighten_span(&self, mut span: Span) -> Span {
-        let strip_from_start = span
-            .snippet(self.db, self.filename)
-            .char_indices()
-            .take_while(|(_, ch)| ch.is_whitespace())
-            .map(|(offset, _)| offset)
-            .last()
-            .unwrap_or(0);
-        span.start = span.start + strip_from_start;
-
-        if let Some(new_len) = span
-            .snippet(self.db, self.filename)
-            .char_indices()
-            .rev()
-            .take_while(|(_, ch)| ch.is_whitespace())
-            .map(|(offset, _)| offset)
-            .last()
-        {
-            span.end = span.start + new_len;
+        let first = self
+            .whitespace
+            .partition_point(|whitespace| whitespace.start < span.start);
+        let mut run_end = span.start;
+        for whitespace in &self.whitespace[first..] {
+            if whitespace.start != run_end || whitespace.end > span.end {
+                break;
+            }
+            span.start = whitespace.start;
+            run_end = whitespace.end;
         }
 
+        let last = self
+            .whitespace
+            .partition_point(|whitespace| whitespace.end <= span.end);
+        let mut run_start = span.end;
+        for whitespace in self.whitespace[..last].iter().rev() {
+            if whitespace.end != run_start || whitespace.start < span.start {
+                break;
+            }
+            run_start = whitespace.start;
+        }
+        span.end = run_start;
+
         span
     }
 
+    /// Consumes tokens up to the end of the line (or, if `stop_at_comma`
+    /// is true, up to and including the next comma), so that parsing can
+    /// resume after a syntax error. Returns the span of the tokens consumed.
+    fn skip_to_end_of_line(&mut self, stop_at_comma: bool) -> Span {
+        let start = self.tokens.peek_span();
+        while self.tokens.consume().is_some() {
+            if self.tokens.skipped_newline() {
+                break;
+            }
+            if stop_at_comma && self.eat(Token::Comma).is_some() {
+                break;
+            }
+        }
+        self.span_consumed_since(start)
+    }
+
     fn emit_error_if_more_tokens(&self, message: impl ToString) {
         self.emit_labeled_error_if_more_tokens(message, |d| d)
     }
@@ -188,7 +241,7 @@ impl<'me> Parser<'me> {
     }
 
     fn error(&self, span: Span, message: impl ToString) -> DiagnosticBuilder {
-        dada_ir::error!(span.in_file(self.filename), "{}", message.to_string())
+        dada_ir::error!(SyntaxError, self.file_span(span), "{}", message.to_string())
     }
 }
 
@@ -228,6 +281,9 @@ trait ParseList {
     /// The following is not accepted:
     ///
     /// * `foo bar`
+    ///
+    /// Every comma-separated list in the grammar (parameters, arguments, and tuples) is
+    /// parsed with this, so they all accept a trailing comma; new kinds of lists should use it too.
     #[tracing::instrument(level = "debug", skip(self, parse_item))]
     fn parse_list<T>(
         &mut self,
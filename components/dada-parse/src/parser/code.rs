@@ -10,7 +10,7 @@ use dada_ir::{
         syntax::op::Op,
         syntax::{
             Expr, ExprData, LocalVariableDeclData, LocalVariableDeclSpan, NamedExpr, NamedExprData,
-            Spans, Tables, Tree, TreeData,
+            OperatorSpans, Spans, Tables, Tree, TreeData,
         },
         Code,
     },
@@ -21,7 +21,7 @@ use dada_ir::{
     storage::Atomic,
     token::Token,
     token_tree::TokenTree,
-    word::SpannedOptionalWord,
+    word::{SpannedOptionalWord, Word},
 };
 use salsa::AsId;
 
@@ -32,11 +32,13 @@ impl Parser<'_> {
         let db = self.db;
         let mut tables = Tables::default();
         let mut spans = Spans::default();
+        let mut operator_spans = OperatorSpans::default();
 
         let mut code_parser = CodeParser {
             parser: self,
             tables: &mut tables,
             spans: &mut spans,
+            operator_spans: &mut operator_spans,
         };
 
         let parameter_decls = origin
@@ -54,7 +56,7 @@ impl Parser<'_> {
             parameter_decls,
             root_expr,
         };
-        Tree::new(self.db, origin, tree_data, spans)
+        Tree::new(self.db, origin, tree_data, spans, operator_spans)
     }
 }
 
@@ -62,6 +64,7 @@ struct CodeParser<'me, 'db> {
     parser: &'me mut Parser<'db>,
     tables: &'me mut Tables,
     spans: &'me mut Spans,
+    operator_spans: &'me mut OperatorSpans,
 }
 
 impl<'db> std::ops::Deref for CodeParser<'_, 'db> {
@@ -80,19 +83,37 @@ impl<'db> std::ops::DerefMut for CodeParser<'_, 'db> {
 
 impl CodeParser<'_, '_> {
     /// Parses a series of expressions; expects to consume all available tokens (and errors if there are extra).
+    ///
+    /// Extra tokens don't end the series: the rest of their line becomes an
+    /// `Error` expression and parsing resumes on the next line, so that
+    /// one mistake doesn't hide the code after it.
     #[tracing::instrument(level = "debug", skip(self))]
     pub(crate) fn parse_only_expr_seq(&mut self) -> Vec<Expr> {
         tracing::debug!("parse_only_expr_seq");
-        let exprs = self.parse_list(true, CodeParser::parse_expr);
+        let mut exprs = self.parse_list(true, CodeParser::parse_expr);
+        while self.tokens.peek().is_some() {
+            self.error_at_current_token("extra tokens after end of expression")
+                .emit(self.db);
+            let span = self.skip_to_end_of_line(false);
+            exprs.push(self.add(ExprData::Error, span));
+            exprs.extend(self.parse_list(true, CodeParser::parse_expr));
+        }
         tracing::debug!("exprs = {:?}", exprs);
-        self.emit_error_if_more_tokens("extra tokens after end of expression");
         exprs
     }
 
     /// Parses a series of named expressions (`id: expr`); expects to consume all available tokens (and errors if there are extra).
+    ///
+    /// As with [`Self::parse_only_expr_seq`], parsing resumes after extra
+    /// tokens, at the next comma or line.
     pub(crate) fn parse_only_named_exprs(&mut self) -> Vec<NamedExpr> {
-        let exprs = self.parse_list(true, CodeParser::parse_named_expr);
-        self.emit_error_if_more_tokens("extra tokens after end of arguments");
+        let mut exprs = self.parse_list(true, CodeParser::parse_named_expr);
+        while self.tokens.peek().is_some() {
+            self.error_at_current_token("extra tokens after end of arguments")
+                .emit(self.db);
+            self.skip_to_end_of_line(true);
+            exprs.extend(self.parse_list(true, CodeParser::parse_named_expr));
+        }
         exprs
     }
 
@@ -132,7 +153,7 @@ impl CodeParser<'_, '_> {
         } else {
             label_span = self.tokens.peek_span().span_at_start();
             expr = self.parse_expr()?;
-            label = SpannedOptionalWord::new(self.db, None, label_span.in_file(self.filename));
+            label = SpannedOptionalWord::new(self.db, None, self.anchored(label_span));
         };
 
         Some(self.add(
@@ -148,7 +169,7 @@ impl CodeParser<'_, '_> {
             let _colon_span = this.eat_op(Op::Colon)?;
             Some((
                 name_span,
-                SpannedOptionalWord::new(this.db, Some(name), name_span.in_file(this.filename)),
+                SpannedOptionalWord::new(this.db, Some(name), this.anchored(name_span)),
             ))
         })
     }
@@ -209,6 +230,26 @@ impl CodeParser<'_, '_> {
                 continue;
             }
 
+            // `x = ...` declares a variable (see `parse_local_variable_decl`),
+            // so a `=` here is an attempt to assign to an existing place.
+            if let Some(equal_span) = self.eat_op(Op::Equal) {
+                dada_ir::error!(
+                    AssignWithEqual,
+                    self.file_span(equal_span),
+                    "use `:=` to assign to an existing place",
+                )
+                .primary_label("`=` only declares new variables")
+                .fix(self.file_span(equal_span), ":=", "replace `=` with `:=`")
+                .emit(self.db);
+                let rhs = self
+                    .parse_expr_5()
+                    .or_report_error(self, || "expected expression after `=`")
+                    .or_dummy_expr(self);
+                let span = self.spans[expr].to(self.spans[rhs]);
+                expr = self.add(ExprData::Assign(expr, rhs), span);
+                continue;
+            }
+
             break;
         }
 
@@ -416,9 +457,16 @@ impl CodeParser<'_, '_> {
                 None
             }
         } else if let Some((span, token_tree)) = self.delimited('(') {
-            let expr =
+            let exprs =
                 self.with_sub_parser(token_tree, |subparser| subparser.parse_only_expr_seq());
-            Some(self.add(ExprData::Tuple(expr), span))
+            // `(expr)` is just `expr` in parentheses, but `(expr,)` is a
+            // tuple of one element.
+            if let [expr] = exprs[..] {
+                if !ends_with_comma(self.db, token_tree) {
+                    return Some(self.add(ExprData::Parenthesized(expr), span));
+                }
+            }
+            Some(self.add(ExprData::Tuple(exprs), span))
         } else {
             None
         }
@@ -489,16 +537,28 @@ impl CodeParser<'_, '_> {
     fn parse_format_string(&mut self) -> Option<Expr> {
         let (span, format_string) = self.eat(FormatStringLiteral)?;
 
-        // Special case for a string with no code like `"foo"`:
-        if format_string.data(self.db).sections.len() == 1 {
-            if let FormatStringSectionData::Text(word) =
-                format_string.data(self.db).sections[0].data(self.db)
-            {
-                return Some(self.add(ExprData::StringLiteral(*word), span));
+        // Special case for a string with no code like `"foo"` (or `""`):
+        match &format_string.data(self.db).sections[..] {
+            [] => {
+                let word = Word::from(self.db, "");
+                return Some(self.add(ExprData::StringLiteral(word), span));
+            }
+            [section] => {
+                if let FormatStringSectionData::Text(word) = section.data(self.db) {
+                    return Some(self.add(ExprData::StringLiteral(*word), span));
+                }
             }
+            _ => {}
         }
 
-        todo!()
+        dada_ir::error!(
+            Unimplemented,
+            self.file_span(span),
+            "interpolated strings are not supported yet",
+        )
+        .primary_label("this string contains code")
+        .emit(self.db);
+        Some(self.add(ExprData::Error, span))
     }
 
     fn parse_binop(
@@ -508,18 +568,20 @@ impl CodeParser<'_, '_> {
         mut parse_rhs: impl FnMut(&mut Self) -> Option<Expr>,
     ) -> Option<Expr> {
         for &op in ops {
-            if self.eat_op(op).is_some() {
+            if let Some(op_span) = self.eat_op(op) {
                 let rhs = parse_rhs(self)
                     .or_report_error(self, || format!("expected expression after {op}"))
                     .or_dummy_expr(self);
                 let span = self.spans[base].to(self.spans[rhs]);
-                match op {
+                let expr = match op {
                     Op::ColonEqual => return Some(self.add(ExprData::Assign(base, rhs), span)),
                     Op::PlusEqual | Op::MinusEqual | Op::DividedByEqual | Op::TimesEqual => {
-                        return Some(self.add(ExprData::OpEq(base, op, rhs), span))
+                        self.add(ExprData::OpEq(base, op, rhs), span)
                     }
-                    _ => return Some(self.add(ExprData::Op(base, op, rhs), span)),
-                }
+                    _ => self.add(ExprData::Op(base, op, rhs), span),
+                };
+                self.operator_spans.push(expr, op_span);
+                return Some(expr);
             }
         }
         None
@@ -552,11 +614,23 @@ impl CodeParser<'_, '_> {
             parser: &mut parser,
             tables: self.tables,
             spans: self.spans,
+            operator_spans: self.operator_spans,
         };
         stacker::maybe_grow(32 * 1024, 1024 * 1024, || op(&mut sub_parser))
     }
 }
 
+/// True if the last token of `token_tree`, leaving out whitespace and
+/// comments, is a `,`.
+fn ends_with_comma(db: &dyn crate::Db, token_tree: TokenTree) -> bool {
+    token_tree
+        .tokens(db)
+        .iter()
+        .rev()
+        .find(|token| !matches!(token, Token::Whitespace(_) | Token::Comment(_)))
+        == Some(&Token::Comma)
+}
+
 trait OrDummyExpr {
     fn or_dummy_expr(self, parser: &mut CodeParser<'_, '_>) -> Expr;
 }
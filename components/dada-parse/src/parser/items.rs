@@ -1,36 +1,59 @@
-use crate::{parser::Parser, token_test::SpannedIdentifier};
+use crate::{
+    parser::Parser,
+    token_test::{FormatStringLiteral, Identifier, SpannedIdentifier},
+};
 
 use dada_ir::{
     class::Class,
     code::{syntax::op::Op, Code},
     effect::Effect,
+    extern_function::{ExternFunction, ExternParameter, ExternSignature, FfiTy},
+    format_string::FormatStringSectionData,
     function::Function,
     item::Item,
     kw::Keyword,
     return_type::{ReturnType, ReturnTypeKind},
     span::Span,
+    word::Word,
 };
 
-use super::OrReportError;
+use super::{OrReportError, ParseList};
 
 impl<'db> Parser<'db> {
     pub(crate) fn parse_items(&mut self) -> Vec<Item> {
         let mut items = vec![];
         while self.tokens.peek().is_some() {
+            let start = self.tokens.peek_span();
             if let Some(item) = self.parse_item() {
                 items.push(item);
             } else {
-                let span = self.tokens.last_span();
+                // If the item was malformed, an error was already reported
+                // for it; otherwise, report the stray token. Either way,
+                // skip ahead to whatever looks like the next item.
+                if self.tokens.last_span().end <= start.start {
+                    self.error(start, "unexpected token").emit(self.db);
+                }
                 self.tokens.consume();
-                dada_ir::error!(span.in_file(self.filename), "unexpected token").emit(self.db);
+                while self.tokens.peek().is_some() && !self.at_item_start() {
+                    self.tokens.consume();
+                }
             }
         }
         items
     }
 
+    /// True if the next token is a keyword that begins an item.
+    fn at_item_start(&mut self) -> bool {
+        [Keyword::Async, Keyword::Class, Keyword::Extern, Keyword::Fn]
+            .into_iter()
+            .any(|kw| self.peek(kw).is_some())
+    }
+
     fn parse_item(&mut self) -> Option<Item> {
         if let Some(class) = self.parse_class() {
             Some(Item::Class(class))
+        } else if let Some(function) = self.parse_extern_function() {
+            Some(Item::ExternFunction(function))
         } else {
             self.parse_function().map(Item::Function)
         }
@@ -48,7 +71,7 @@ impl<'db> Parser<'db> {
             self.db,
             class_name,
             field_tokens,
-            self.span_consumed_since(class_span).in_file(self.filename),
+            self.file_span(self.span_consumed_since(class_span)),
         ))
     }
 
@@ -58,9 +81,13 @@ impl<'db> Parser<'db> {
         } else {
             (None, Effect::Default)
         };
-        let (fn_span, _) = self
-            .eat(Keyword::Fn)
-            .or_report_error(self, || "expected `fn`".to_string())?;
+        let (fn_span, _) = if effect_span.is_some() {
+            self.eat(Keyword::Fn)
+                .or_report_error(self, || "expected `fn`".to_string())?
+        } else {
+            // Not an item at all; `parse_items` reports that.
+            self.eat(Keyword::Fn)?
+        };
         let (_, func_name) = self
             .eat(SpannedIdentifier)
             .or_report_error(self, || "expected function name".to_string())?;
@@ -69,13 +96,11 @@ impl<'db> Parser<'db> {
             .or_report_error(self, || "expected function parameters".to_string())?;
         let return_type = {
             let right_arrow = self.eat_op(Op::RightArrow);
-            let span = right_arrow
-                .unwrap_or_else(|| Span {
-                    // span between last non skipped token and next non skippable token
-                    start: self.tokens.last_span().end,
-                    end: self.tokens.peek_span().start,
-                })
-                .in_file(self.filename);
+            let span = self.file_span(right_arrow.unwrap_or_else(|| Span {
+                // span between last non skipped token and next non skippable token
+                start: self.tokens.last_span().end,
+                end: self.tokens.peek_span().start,
+            }));
             ReturnType::new(
                 self.db,
                 if right_arrow.is_some() {
@@ -89,14 +114,114 @@ impl<'db> Parser<'db> {
         let (_, body_tokens) = self
             .delimited('{')
             .or_report_error(self, || "expected function body".to_string())?;
-        let code = Code::new(effect, Some(parameter_tokens), return_type, body_tokens);
+        let code = Code::new(
+            func_name.word(self.db),
+            effect,
+            Some(parameter_tokens),
+            return_type,
+            body_tokens,
+        );
         let start_span = effect_span.unwrap_or(fn_span);
         Some(Function::new(
             self.db,
             func_name,
             code,
-            self.span_consumed_since(start_span).in_file(self.filename),
-            effect_span.unwrap_or(fn_span).in_file(self.filename),
+            self.file_span(self.span_consumed_since(start_span)),
+            self.file_span(start_span),
         ))
     }
+
+    /// Parses `extern "library" fn name(parameters) -> ty`.
+    fn parse_extern_function(&mut self) -> Option<ExternFunction> {
+        let (extern_span, _) = self.eat(Keyword::Extern)?;
+        let library = self.parse_library_name()?;
+        self.eat(Keyword::Fn)
+            .or_report_error(self, || "expected `fn`".to_string())?;
+        let (_, func_name) = self
+            .eat(SpannedIdentifier)
+            .or_report_error(self, || "expected function name".to_string())?;
+        let (_, parameter_tokens) = self
+            .delimited('(')
+            .or_report_error(self, || "expected function parameters".to_string())?;
+        let parameters = Parser::new(self.db, parameter_tokens).parse_extern_parameters();
+        let return_ty = if let Some(arrow_span) = self.eat_op(Op::RightArrow) {
+            let (ty_span, ty) = self.parse_ffi_ty()?;
+            if ty == FfiTy::Bytes {
+                self.error(ty_span, "extern functions cannot return `bytes`")
+                    .secondary_label(self.file_span(arrow_span), "return type declared here")
+                    .emit(self.db);
+                return None;
+            }
+            Some(ty)
+        } else {
+            None
+        };
+        Some(ExternFunction::new(
+            self.db,
+            func_name,
+            library,
+            ExternSignature {
+                parameters,
+                return_ty,
+            },
+            self.file_span(self.span_consumed_since(extern_span)),
+        ))
+    }
+
+    /// Parses the string literal naming the library of an extern function.
+    fn parse_library_name(&mut self) -> Option<Word> {
+        let (span, literal) = self.eat(FormatStringLiteral).or_report_error(self, || {
+            "expected the name of a library, like `\"libm.so.6\"`".to_string()
+        })?;
+        match &literal.data(self.db).sections[..] {
+            [section] => match section.data(self.db) {
+                FormatStringSectionData::Text(word) => Some(*word),
+                FormatStringSectionData::TokenTree(_) => {
+                    self.error(span, "library names cannot contain expressions")
+                        .emit(self.db);
+                    None
+                }
+            },
+            _ => {
+                self.error(span, "expected the name of a library")
+                    .emit(self.db);
+                None
+            }
+        }
+    }
+
+    fn parse_extern_parameters(&mut self) -> Vec<ExternParameter> {
+        let parameters = self.parse_list(true, Parser::parse_extern_parameter);
+        self.emit_error_if_more_tokens("extra tokens after parameters");
+        parameters
+    }
+
+    fn parse_extern_parameter(&mut self) -> Option<ExternParameter> {
+        let (_, name) = self.eat(Identifier)?;
+        self.eat_op(Op::Colon).or_report_error(self, || {
+            "expected `:` (the parameters of extern functions must have types)".to_string()
+        })?;
+        let (_, ty) = self.parse_ffi_ty()?;
+        Some(ExternParameter { name, ty })
+    }
+
+    /// Parses one of the types in [`FfiTy`], reporting an error if the next
+    /// token is not one.
+    fn parse_ffi_ty(&mut self) -> Option<(Span, FfiTy)> {
+        let (span, word) = self
+            .eat(Identifier)
+            .or_report_error(self, || "expected a type".to_string())?;
+        let name = word.as_str(self.db);
+        match FfiTy::ALL.iter().find(|ty| ty.as_str() == name) {
+            Some(&ty) => Some((span, ty)),
+            None => {
+                let expected: Vec<String> =
+                    FfiTy::ALL.iter().map(|ty| format!("`{}`", ty)).collect();
+                self.error(span, format!("unknown type `{}`", name))
+                    .primary_label(format!("expected one of {}", expected.join(", ")))
+                    .emit(self.db);
+                None
+            }
+        }
+    }
 }
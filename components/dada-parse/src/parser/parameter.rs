@@ -5,7 +5,7 @@ use dada_ir::{
     code::syntax::{LocalVariableDeclData, LocalVariableDeclSpan},
     kw::Keyword,
     parameter::Parameter,
-    span::Span,
+    span::{AnchoredSpan, Span},
     storage::{Atomic, SpannedSpecifier, Specifier},
 };
 
@@ -27,7 +27,7 @@ impl<'db> Parser<'db> {
 
                 if opt_ty.is_none() {
                     self.error_at_current_token(&"expected type after `:`".to_string())
-                        .secondary_label(colon_span, "`:` is here".to_string())
+                        .secondary_label(self.file_span(colon_span), "`:` is here".to_string())
                         .emit(self.db);
                 }
 
@@ -61,7 +61,7 @@ impl<'db> Parser<'db> {
             // that's an error.
             if let Some(span) = opt_storage_mode {
                 self.error_at_current_token("expected parameter name after `atomic`")
-                    .secondary_label(span, "`atomic` specified here")
+                    .secondary_label(self.file_span(span), "`atomic` specified here")
                     .emit(self.db);
             }
 
@@ -78,13 +78,13 @@ impl<'db> Parser<'db> {
     }
 
     pub(crate) fn parse_permission_specifier(&mut self) -> Option<SpannedSpecifier> {
-        let filename = self.filename;
+        let anchor = self.anchor;
         let some_specifier = |specifier, span: Span| {
             Some(SpannedSpecifier::new(
                 self.db,
                 specifier,
                 false,
-                span.in_file(filename),
+                AnchoredSpan { anchor, span },
             ))
         };
         if let Some((my_span, _)) = self.eat(Keyword::My) {
@@ -108,7 +108,7 @@ pub(crate) impl SpannedSpecifierExt for Option<SpannedSpecifier> {
     fn or_defaulted(self, parser: &Parser<'_>, name_span: Span) -> SpannedSpecifier {
         match self {
             Some(s) => s,
-            None => SpannedSpecifier::new_defaulted(parser.db, name_span.in_file(parser.filename)),
+            None => SpannedSpecifier::new_defaulted(parser.db, parser.anchored(name_span)),
         }
     }
 }
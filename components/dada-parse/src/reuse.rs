@@ -0,0 +1,51 @@
+//! Reusing the items of a file after an edit (see `dada_lex::reuse`).
+//! Parsing the items of a file is cheap, as their bodies are parsed on
+//! demand, but it creates new items, and everything memoized about the old
+//! ones (their syntax trees, validated trees, and so on) is computed again.
+//! An item that ends before the edit is unchanged, so the old one is kept.
+
+use dada_ir::{filename::Filename, item::Item, span::Offset};
+
+/// The items of an earlier version of a file whose first `unchanged`
+/// bytes are the same as they are now.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReusableItems {
+    items: Vec<Item>,
+    unchanged: Offset,
+}
+
+impl ReusableItems {
+    /// Captures the items of `filename` as it is now, before an edit that
+    /// leaves its first `unchanged` bytes as they are.
+    pub fn before_edit(db: &dyn crate::Db, filename: Filename, unchanged: Offset) -> Self {
+        Self {
+            items: crate::file_parser::parse_file(db, filename).clone(),
+            unchanged,
+        }
+    }
+
+    /// The old item with the same span as `item`, if that span ends before
+    /// the first changed byte; otherwise `item` itself.
+    pub(crate) fn reuse(&self, db: &dyn crate::Db, item: Item) -> Item {
+        let span = item.span(db);
+        if span.end >= self.unchanged {
+            return item;
+        }
+        self.items
+            .iter()
+            .copied()
+            .find(|old| old.span(db) == span && old.kind_str() == item.kind_str())
+            .unwrap_or(item)
+    }
+}
+
+/// Salsa input: items that [`parse_file`](crate::file_parser::parse_file)
+/// may reuse for `filename`. Like `dada_lex::reuse::reusable_tokens`, this
+/// must be set along with the source text that it describes.
+///
+/// Defaults to `None`, in which case all the items are new.
+#[salsa::memoized(in crate::Jar ref)]
+#[allow(clippy::needless_lifetimes)]
+pub fn reusable_items(_db: &dyn crate::Db, _filename: Filename) -> Option<ReusableItems> {
+    None
+}
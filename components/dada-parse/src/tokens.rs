@@ -71,6 +71,10 @@ impl<'me> Tokens<'me> {
             Token::Whitespace('\n') => Some(Skipped::Newline),
             Token::Whitespace(_) => Some(Skipped::Any),
             Token::Comment(_) => Some(Skipped::Any),
+
+            // Closing delimiters that don't close anything; the lexer
+            // reports them.
+            Token::Unknown(')' | ']' | '}') => Some(Skipped::Any),
             _ => None,
         }
     }
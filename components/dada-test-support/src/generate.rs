@@ -0,0 +1,388 @@
+//! Generates random programs that should compile without errors: names are
+//! only used where they are in scope, calls pass as many arguments as the
+//! callee has parameters, `await` only appears in `async` functions (and
+//! not in `atomic` blocks), and `return` only has a value in functions
+//! declared with `->`.
+//!
+//! The programs are only meant to be compiled, not run: loops don't end and
+//! values are used with whatever permissions happen to come up.
+
+use dada_ir::{code::syntax::op::Op, storage::Specifier};
+
+use crate::{
+    program::{Argument, Class, Decl, Expr, Function, Item, Program},
+    rng::Rng,
+};
+
+/// How deeply expressions and blocks nest, at most.
+const MAX_DEPTH: usize = 3;
+
+const SPECIFIERS: &[Specifier] = &[
+    Specifier::My,
+    Specifier::Our,
+    Specifier::Leased,
+    Specifier::Shleased,
+    Specifier::Any,
+];
+
+const BINARY_OPS: &[Op] = &[
+    Op::Plus,
+    Op::Minus,
+    Op::Times,
+    Op::DividedBy,
+    Op::EqualEqual,
+    Op::LessThan,
+    Op::GreaterThan,
+    Op::LessEqual,
+    Op::GreaterEqual,
+];
+
+const COMPARISON_OPS: &[Op] = &[
+    Op::EqualEqual,
+    Op::LessThan,
+    Op::GreaterThan,
+    Op::LessEqual,
+    Op::GreaterEqual,
+];
+
+const ASSIGN_OPS: &[Op] = &[
+    Op::PlusEqual,
+    Op::MinusEqual,
+    Op::TimesEqual,
+    Op::DividedByEqual,
+];
+
+/// Generates the program for `seed`; the same seed always gives the same
+/// program.
+pub fn generate(seed: u64) -> Program {
+    Generator {
+        rng: Rng::new(seed),
+        callables: vec![Callable {
+            name: "print".to_string(),
+            parameters: vec![],
+            arity: 1,
+            is_async: true,
+        }],
+        fields: vec![],
+        function: FunctionState::default(),
+    }
+    .program()
+}
+
+/// Something that can be called: a class, a function, or `print`.
+struct Callable {
+    name: String,
+
+    /// The parameter names, which calls can use as labels; empty for
+    /// `print`, which is never called with labels.
+    parameters: Vec<String>,
+    arity: usize,
+
+    /// True if a call returns something to await.
+    is_async: bool,
+}
+
+/// What the code being generated, in the function being generated, can
+/// refer to and do.
+#[derive(Default)]
+struct FunctionState {
+    /// The parameters and the local variables in scope.
+    variables: Vec<String>,
+    next_variable: usize,
+    is_async: bool,
+    returns_value: bool,
+    in_atomic: bool,
+}
+
+struct Generator {
+    rng: Rng,
+    callables: Vec<Callable>,
+
+    /// The names of the fields of all the classes.
+    fields: Vec<String>,
+    function: FunctionState,
+}
+
+impl Generator {
+    fn program(mut self) -> Program {
+        let mut items = vec![];
+
+        for class_index in 0..self.rng.below(3) {
+            let mut fields = vec![];
+            for field_index in 0..self.rng.below(4) {
+                fields.push(self.decl(format!("f{field_index}")));
+            }
+            let field_names: Vec<String> = fields.iter().map(|f| f.name.clone()).collect();
+            let class = Class {
+                name: format!("C{class_index}"),
+                fields,
+            };
+            self.callables.push(Callable {
+                name: class.name.clone(),
+                arity: field_names.len(),
+                parameters: field_names.clone(),
+                is_async: false,
+            });
+            self.fields.extend(field_names);
+            items.push(Item::Class(class));
+        }
+        self.fields.sort();
+        self.fields.dedup();
+
+        // Give every function its signature first, so that any function
+        // can call any other.
+        let mut functions = vec![];
+        for function_index in 0..1 + self.rng.below(4) {
+            let mut parameters = vec![];
+            for parameter_index in 0..self.rng.below(4) {
+                parameters.push(self.decl(format!("p{parameter_index}")));
+            }
+            let function = Function {
+                name: format!("func{function_index}"),
+                is_async: self.rng.one_in(2),
+                parameters,
+                returns_value: self.rng.one_in(3),
+                body: vec![],
+            };
+            self.callables.push(Callable {
+                name: function.name.clone(),
+                parameters: function.parameters.iter().map(|p| p.name.clone()).collect(),
+                arity: function.parameters.len(),
+                is_async: function.is_async,
+            });
+            functions.push(function);
+        }
+        for mut function in functions {
+            self.function = FunctionState {
+                variables: function.parameters.iter().map(|p| p.name.clone()).collect(),
+                next_variable: 0,
+                is_async: function.is_async,
+                returns_value: function.returns_value,
+                in_atomic: false,
+            };
+            function.body = self.statements(MAX_DEPTH);
+            if function.returns_value && function.body.is_empty() {
+                let value = self.expr(1);
+                function.body.push(Expr::Return(Some(Box::new(value))));
+            }
+            items.push(Item::Function(function));
+        }
+
+        // Mix the classes in among the functions.
+        for index in (1..items.len()).rev() {
+            let other = self.rng.below(index + 1);
+            items.swap(index, other);
+        }
+
+        Program { items }
+    }
+
+    fn decl(&mut self, name: String) -> Decl {
+        Decl {
+            specifier: if self.rng.one_in(2) {
+                Some(*self.rng.choose(SPECIFIERS))
+            } else {
+                None
+            },
+            atomic: self.rng.one_in(8),
+            name,
+        }
+    }
+
+    /// A block: statements in a scope of their own.
+    fn block(&mut self, depth: usize) -> Vec<Expr> {
+        let variables = self.function.variables.len();
+        let statements = self.statements(depth);
+        self.function.variables.truncate(variables);
+        statements
+    }
+
+    fn statements(&mut self, depth: usize) -> Vec<Expr> {
+        let mut statements = vec![];
+        for _ in 0..self.rng.below(4) {
+            statements.push(self.statement(depth));
+        }
+
+        // `return` without a value would take the next line as its value,
+        // so it can only come last.
+        if self.rng.one_in(6) {
+            let value = if self.function.returns_value {
+                Some(Box::new(self.expr(depth)))
+            } else {
+                None
+            };
+            statements.push(Expr::Return(value));
+        }
+        statements
+    }
+
+    /// An expression to write on a line of its own. None of them start
+    /// with `(` or `-`, which would continue the line before as a call or
+    /// a subtraction.
+    fn statement(&mut self, depth: usize) -> Expr {
+        let choices = if depth == 0 { 4 } else { 9 };
+        match self.rng.below(choices) {
+            0 => {
+                let value = self.expr(depth);
+                let name = format!("v{}", self.function.next_variable);
+                self.function.next_variable += 1;
+                let decl = self.decl(name.clone());
+                self.function.variables.push(name);
+                Expr::Var(decl, Box::new(value))
+            }
+            1 => match self.place() {
+                Some(place) => Expr::Assign(Box::new(place), Box::new(self.operand(depth))),
+                None => self.call(depth),
+            },
+            2 => match self.place() {
+                Some(place) => {
+                    let op = *self.rng.choose(ASSIGN_OPS);
+                    Expr::OpEq(Box::new(place), op, Box::new(self.operand(depth)))
+                }
+                None => self.call(depth),
+            },
+            3 => self.call(depth),
+            4 => {
+                let condition = self.condition();
+                let then_block = self.block(depth - 1);
+                let else_block = if self.rng.one_in(2) {
+                    Some(self.block(depth - 1))
+                } else {
+                    None
+                };
+                Expr::If(Box::new(condition), then_block, else_block)
+            }
+            5 => {
+                let condition = self.condition();
+                Expr::While(Box::new(condition), self.block(depth - 1))
+            }
+            6 => Expr::Loop(self.block(depth - 1)),
+            7 => {
+                let in_atomic = std::mem::replace(&mut self.function.in_atomic, true);
+                let block = self.block(depth - 1);
+                self.function.in_atomic = in_atomic;
+                Expr::Atomic(block)
+            }
+            _ => Expr::Block(self.block(depth - 1)),
+        }
+    }
+
+    /// An expression for a value.
+    fn expr(&mut self, depth: usize) -> Expr {
+        if depth == 0 || self.rng.one_in(3) {
+            return self.atom();
+        }
+        match self.rng.below(7) {
+            0 => self.operand(depth),
+            1 => Expr::Unary(Op::Minus, Box::new(self.expr(depth - 1))),
+            2 => match self.place() {
+                Some(place) => match self.rng.below(4) {
+                    0 => Expr::Share(Box::new(place)),
+                    1 => Expr::Lease(Box::new(place)),
+                    2 => Expr::Shlease(Box::new(place)),
+                    _ => Expr::Give(Box::new(place)),
+                },
+                None => self.atom(),
+            },
+            3 => {
+                let condition = self.condition();
+                let then_block = self.block(depth - 1);
+                let else_block = self.block(depth - 1);
+                Expr::If(Box::new(condition), then_block, Some(else_block))
+            }
+            4 => {
+                let exprs = (0..2 + self.rng.below(2))
+                    .map(|_| self.expr(depth - 1))
+                    .collect();
+                Expr::Tuple(exprs)
+            }
+            5 => Expr::Block(self.block(depth - 1)),
+            _ => self.call(depth),
+        }
+    }
+
+    /// An expression for a value that uses no blocks: an atom, a call, or
+    /// an operator applied to those.
+    fn operand(&mut self, depth: usize) -> Expr {
+        if depth == 0 || self.rng.one_in(3) {
+            return self.atom();
+        }
+        if self.rng.one_in(2) {
+            return self.call(depth);
+        }
+        let op = *self.rng.choose(BINARY_OPS);
+        let lhs = self.operand(depth - 1);
+        let rhs = self.operand(depth - 1);
+        Expr::Op(Box::new(lhs), op, Box::new(rhs))
+    }
+
+    /// A condition for `if` or `while`. It must not start with a block,
+    /// which the parser would take as the body.
+    fn condition(&mut self) -> Expr {
+        match self.rng.below(3) {
+            0 => Expr::Boolean(self.rng.one_in(2)),
+            1 => self.operand(1),
+            _ => {
+                let op = *self.rng.choose(COMPARISON_OPS);
+                let lhs = self.operand(1);
+                let rhs = self.operand(1);
+                Expr::Op(Box::new(lhs), op, Box::new(rhs))
+            }
+        }
+    }
+
+    fn atom(&mut self) -> Expr {
+        match self.rng.below(6) {
+            0 if !self.function.variables.is_empty() => {
+                Expr::Id(self.rng.choose(&self.function.variables).clone())
+            }
+            0 | 1 => Expr::Integer(self.rng.below(1000) as u64),
+            2 => Expr::Boolean(self.rng.one_in(2)),
+            3 => Expr::Float(self.rng.below(100) as u64, self.rng.below(100) as u64),
+            4 => Expr::String(format!("s{}", self.rng.below(100))),
+            _ => Expr::Tuple(vec![]),
+        }
+    }
+
+    /// A local variable or parameter, or a field of one, if there are any.
+    fn place(&mut self) -> Option<Expr> {
+        if self.function.variables.is_empty() {
+            return None;
+        }
+        let variable = Expr::Id(self.rng.choose(&self.function.variables).clone());
+        if !self.fields.is_empty() && self.rng.one_in(3) {
+            let field = self.rng.choose(&self.fields).clone();
+            return Some(Expr::Dot(Box::new(variable), field));
+        }
+        Some(variable)
+    }
+
+    /// A call, awaited if it can and should be.
+    fn call(&mut self, depth: usize) -> Expr {
+        let index = self.rng.below(self.callables.len());
+        let arity = self.callables[index].arity;
+        let labeled = !self.callables[index].parameters.is_empty() && self.rng.one_in(3);
+
+        let mut arguments = vec![];
+        for argument_index in 0..arity {
+            let name = if labeled {
+                Some(self.callables[index].parameters[argument_index].clone())
+            } else {
+                None
+            };
+            let value = self.expr(depth.saturating_sub(1));
+            arguments.push(Argument { name, value });
+        }
+        let call = Expr::Call(
+            Box::new(Expr::Id(self.callables[index].name.clone())),
+            arguments,
+        );
+
+        let can_await = self.function.is_async && !self.function.in_atomic;
+        if self.callables[index].is_async && can_await && self.rng.one_in(2) {
+            Expr::Await(Box::new(call))
+        } else {
+            call
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Support for testing the compiler with random programs. [`generate`]
+//! makes a random [`Program`] that should compile without errors, its
+//! `Display` impl prints it as Dada source, and [`read`] reads a program
+//! back from the syntax trees that the parser builds from that source.
+//! The tests in `tests` use them to check that printing and then parsing a
+//! program gives back the same program, and that validating and brewing
+//! it works.
+
+use dada_ir::filename::Filename;
+
+mod generate;
+mod print;
+mod program;
+mod read;
+mod rng;
+
+pub use generate::generate;
+pub use program::{Argument, Class, Decl, Expr, Function, Item, Program};
+pub use read::read;
+
+/// A database with `source` as the contents of a file, and that file.
+pub fn db_with_source(source: &str) -> (dada_db::Db, Filename) {
+    let mut db = dada_db::Db::default();
+    let filename = Filename::from(&db, "generated.dada");
+    db.update_file(filename, source.to_string());
+    (db, filename)
+}
@@ -0,0 +1,276 @@
+//! Prints programs as Dada source, one statement per line, adding
+//! parentheses wherever the parser would otherwise group an expression
+//! differently.
+
+use std::fmt;
+
+use dada_ir::code::syntax::op::Op;
+
+use crate::program::{Class, Decl, Expr, Function, Item, Program};
+
+const INDENT: &str = "    ";
+
+/// The loosest level, for expressions that need no parentheses.
+const ANY_LEVEL: u8 = 7;
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, item) in self.items.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            match item {
+                Item::Class(class) => writeln!(f, "{class}")?,
+                Item::Function(function) => writeln!(f, "{function}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "class {}(", self.name)?;
+        comma_separated(f, &self.fields)?;
+        write!(f, ")")
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_async {
+            write!(f, "async ")?;
+        }
+        write!(f, "fn {}(", self.name)?;
+        comma_separated(f, &self.parameters)?;
+        write!(f, ") ")?;
+        if self.returns_value {
+            write!(f, "-> ")?;
+        }
+        Printer { f, indent: 0 }.block(&self.body)
+    }
+}
+
+impl fmt::Display for Decl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(specifier) = self.specifier {
+            write!(f, "{specifier} ")?;
+        }
+        if self.atomic {
+            write!(f, "atomic ")?;
+        }
+        write!(f, "{}", self.name)
+    }
+}
+
+fn comma_separated(f: &mut fmt::Formatter<'_>, decls: &[Decl]) -> fmt::Result {
+    for (index, decl) in decls.iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{decl}")?;
+    }
+    Ok(())
+}
+
+struct Printer<'p, 'f> {
+    f: &'p mut fmt::Formatter<'f>,
+    indent: usize,
+}
+
+impl Printer<'_, '_> {
+    fn block(&mut self, exprs: &[Expr]) -> fmt::Result {
+        if exprs.is_empty() {
+            return write!(self.f, "{{}}");
+        }
+        writeln!(self.f, "{{")?;
+        self.indent += 1;
+        for expr in exprs {
+            write!(self.f, "{}", INDENT.repeat(self.indent))?;
+            self.expr(expr, ANY_LEVEL)?;
+            writeln!(self.f)?;
+        }
+        self.indent -= 1;
+        write!(self.f, "{}}}", INDENT.repeat(self.indent))
+    }
+
+    /// Prints `expr`, in parentheses if it binds more loosely than
+    /// `max_level` (see [`level`]).
+    fn expr(&mut self, expr: &Expr, max_level: u8) -> fmt::Result {
+        if level(expr) > max_level {
+            write!(self.f, "(")?;
+            self.expr(expr, ANY_LEVEL)?;
+            return write!(self.f, ")");
+        }
+
+        match expr {
+            Expr::Id(name) => write!(self.f, "{name}"),
+            Expr::Boolean(value) => write!(self.f, "{value}"),
+            Expr::Integer(value) => write!(self.f, "{value}"),
+            Expr::Float(integer, fraction) => write!(self.f, "{integer}.{fraction}"),
+            Expr::String(text) => write!(self.f, "\"{text}\""),
+            Expr::Dot(owner, field) => {
+                self.postfix_base(owner)?;
+                write!(self.f, ".{field}")
+            }
+            Expr::Await(expr) => {
+                self.postfix_base(expr)?;
+                write!(self.f, ".await")
+            }
+            Expr::Call(callee, arguments) => {
+                self.postfix_base(callee)?;
+                write!(self.f, "(")?;
+                for (index, argument) in arguments.iter().enumerate() {
+                    if index > 0 {
+                        write!(self.f, ", ")?;
+                    }
+                    if let Some(name) = &argument.name {
+                        write!(self.f, "{name}: ")?;
+                    }
+                    self.expr(&argument.value, ANY_LEVEL)?;
+                }
+                write!(self.f, ")")
+            }
+            Expr::Share(expr) => {
+                self.postfix_base(expr)?;
+                write!(self.f, ".share")
+            }
+            Expr::Lease(expr) => {
+                self.postfix_base(expr)?;
+                write!(self.f, ".lease")
+            }
+            Expr::Shlease(expr) => {
+                self.postfix_base(expr)?;
+                write!(self.f, ".shlease")
+            }
+            Expr::Give(expr) => {
+                self.postfix_base(expr)?;
+                write!(self.f, ".give")
+            }
+            Expr::Var(decl, value) => {
+                write!(self.f, "{decl} = ")?;
+                self.expr(value, ANY_LEVEL)
+            }
+            Expr::Tuple(exprs) => {
+                write!(self.f, "(")?;
+                for (index, expr) in exprs.iter().enumerate() {
+                    if index > 0 {
+                        write!(self.f, ", ")?;
+                    }
+                    self.expr(expr, ANY_LEVEL)?;
+                }
+                if exprs.len() == 1 {
+                    write!(self.f, ",")?;
+                }
+                write!(self.f, ")")
+            }
+            Expr::If(condition, then_block, else_block) => {
+                write!(self.f, "if ")?;
+                self.expr(condition, ANY_LEVEL)?;
+                write!(self.f, " ")?;
+                self.block(then_block)?;
+                if let Some(else_block) = else_block {
+                    write!(self.f, " else ")?;
+                    self.block(else_block)?;
+                }
+                Ok(())
+            }
+            Expr::Atomic(block) => {
+                write!(self.f, "atomic ")?;
+                self.block(block)
+            }
+            Expr::Loop(block) => {
+                write!(self.f, "loop ")?;
+                self.block(block)
+            }
+            Expr::While(condition, block) => {
+                write!(self.f, "while ")?;
+                self.expr(condition, ANY_LEVEL)?;
+                write!(self.f, " ")?;
+                self.block(block)
+            }
+            Expr::Block(block) => self.block(block),
+            Expr::Op(lhs, op, rhs) => {
+                // Binary operators are left-associative.
+                let op_level = binary_level(*op);
+                self.expr(lhs, op_level)?;
+                write!(self.f, " {op} ")?;
+                self.expr(rhs, op_level - 1)
+            }
+            Expr::OpEq(lhs, op, rhs) => {
+                self.expr(lhs, 5)?;
+                write!(self.f, " {op} ")?;
+                self.expr(rhs, 5)
+            }
+            Expr::Unary(op, expr) => {
+                write!(self.f, "{op}")?;
+                // `--x` would be read as an unknown operator `--`.
+                if let Expr::Unary(..) = **expr {
+                    write!(self.f, " ")?;
+                }
+                self.expr(expr, 2)
+            }
+            Expr::Assign(lhs, rhs) => {
+                self.expr(lhs, 5)?;
+                write!(self.f, " := ")?;
+                self.expr(rhs, 5)
+            }
+            Expr::Return(None) => write!(self.f, "return"),
+            Expr::Return(Some(expr)) => {
+                write!(self.f, "return ")?;
+                self.expr(expr, ANY_LEVEL)
+            }
+        }
+    }
+
+    /// Prints the expression before a `.` or a call. Numbers get
+    /// parentheses, since `1.share` would be read as a malformed float.
+    fn postfix_base(&mut self, expr: &Expr) -> fmt::Result {
+        match expr {
+            Expr::Integer(_) | Expr::Float(..) => {
+                write!(self.f, "(")?;
+                self.expr(expr, ANY_LEVEL)?;
+                write!(self.f, ")")
+            }
+            _ => self.expr(expr, 1),
+        }
+    }
+}
+
+/// How loosely `expr` binds, following the levels of `parse_expr_N` in the
+/// parser: 0 for literals, names, and bracketed forms, up to 7 for the
+/// forms that can only appear at the start of an expression.
+fn level(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Var(..) | Expr::Return(..) => 7,
+        Expr::OpEq(..) | Expr::Assign(..) => 6,
+        Expr::Op(_, op, _) => binary_level(*op),
+        Expr::Unary(..) => 2,
+        Expr::Dot(..)
+        | Expr::Await(..)
+        | Expr::Call(..)
+        | Expr::Share(..)
+        | Expr::Lease(..)
+        | Expr::Shlease(..)
+        | Expr::Give(..) => 1,
+        Expr::Id(_)
+        | Expr::Boolean(_)
+        | Expr::Integer(_)
+        | Expr::Float(..)
+        | Expr::String(_)
+        | Expr::Tuple(_)
+        | Expr::If(..)
+        | Expr::Atomic(_)
+        | Expr::Loop(_)
+        | Expr::While(..)
+        | Expr::Block(_) => 0,
+    }
+}
+
+fn binary_level(op: Op) -> u8 {
+    match op {
+        Op::Times | Op::DividedBy => 3,
+        Op::Plus | Op::Minus => 4,
+        _ => 5,
+    }
+}
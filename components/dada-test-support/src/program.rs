@@ -0,0 +1,127 @@
+use dada_ir::{code::syntax::op::Op, storage::Specifier};
+
+/// A program, with its classes and functions in the order they appear in
+/// the source. Unlike the compiler's syntax trees, it owns all of its data
+/// and needs no database, so that programs can be built and compared
+/// directly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Program {
+    pub items: Vec<Item>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+    Class(Class),
+    Function(Function),
+}
+
+/// `class Name(fields)`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Class {
+    pub name: String,
+    pub fields: Vec<Decl>,
+}
+
+/// `[async] fn name(parameters) [->] { body }`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub is_async: bool,
+    pub parameters: Vec<Decl>,
+
+    /// True if the function is declared with `->`.
+    pub returns_value: bool,
+    pub body: Vec<Expr>,
+}
+
+/// A parameter, field, or local variable: `[specifier] [atomic] name`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decl {
+    /// The specifier, if one is written (rather than defaulted).
+    pub specifier: Option<Specifier>,
+    pub atomic: bool,
+    pub name: String,
+}
+
+/// An expression; the variants follow those of
+/// [`dada_ir::code::syntax::ExprData`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Id(String),
+
+    /// true, false
+    Boolean(bool),
+
+    /// `22`
+    Integer(u64),
+
+    /// `integer-part.fractional-part`
+    Float(u64, u64),
+
+    /// `"foo"`, with no format strings
+    String(String),
+
+    /// `expr.ident`
+    Dot(Box<Expr>, String),
+
+    /// `expr.await`
+    Await(Box<Expr>),
+
+    /// `expr(id: expr, ...)`
+    Call(Box<Expr>, Vec<Argument>),
+
+    /// `expr.share`
+    Share(Box<Expr>),
+
+    /// `expr.lease`
+    Lease(Box<Expr>),
+
+    /// `expr.shlease`
+    Shlease(Box<Expr>),
+
+    /// `expr.give`
+    Give(Box<Expr>),
+
+    /// `[specifier] [atomic] x = expr`
+    Var(Decl, Box<Expr>),
+
+    /// `(expr, ...)` of len != 1
+    Tuple(Vec<Expr>),
+
+    /// `if condition { block } [else { block }]`
+    If(Box<Expr>, Vec<Expr>, Option<Vec<Expr>>),
+
+    /// `atomic { block }`
+    Atomic(Vec<Expr>),
+
+    /// `loop { block }`
+    Loop(Vec<Expr>),
+
+    /// `while condition { block }`
+    While(Box<Expr>, Vec<Expr>),
+
+    /// `{ block }`
+    Block(Vec<Expr>),
+
+    /// `a + b`
+    Op(Box<Expr>, Op, Box<Expr>),
+
+    /// `a += b`
+    OpEq(Box<Expr>, Op, Box<Expr>),
+
+    /// `-a`
+    Unary(Op, Box<Expr>),
+
+    /// `a := b`
+    Assign(Box<Expr>, Box<Expr>),
+
+    /// `return [expr]`
+    Return(Option<Box<Expr>>),
+}
+
+/// `[name:] value`, in a call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Argument {
+    pub name: Option<String>,
+    pub value: Expr,
+}
@@ -0,0 +1,157 @@
+use dada_ir::{
+    code::syntax, effect::Effect, filename::Filename, return_type::ReturnTypeKind, storage::Atomic,
+    word::Word,
+};
+use dada_parse::prelude::*;
+
+use crate::program::{Argument, Class, Decl, Expr, Function, Item, Program};
+
+/// Reads the program in `filename` from the syntax trees that the parser
+/// builds, or describes the first thing in them that no generated program
+/// has (such as a syntax error).
+pub fn read(db: &dada_db::Db, filename: Filename) -> Result<Program, String> {
+    let mut items = vec![];
+    for item in db.items(filename) {
+        match item {
+            dada_ir::item::Item::Class(class) => items.push(Item::Class(Class {
+                name: class.name(db).as_str(db).to_string(),
+                fields: class
+                    .fields(db)
+                    .iter()
+                    .map(|field| decl(db, &field.decl(db)))
+                    .collect(),
+            })),
+            dada_ir::item::Item::Function(function) => {
+                let code = function.code(db);
+                let tree = function.syntax_tree(db);
+                let reader = Reader {
+                    db,
+                    tables: &tree.data(db).tables,
+                };
+                items.push(Item::Function(Function {
+                    name: function.name(db).as_str(db).to_string(),
+                    is_async: code.effect == Effect::Async,
+                    parameters: function
+                        .parameters(db)
+                        .iter()
+                        .map(|parameter| decl(db, &parameter.decl(db)))
+                        .collect(),
+                    returns_value: code.return_type.kind(db) == ReturnTypeKind::Value,
+                    body: reader.block(tree.data(db).root_expr)?,
+                }));
+            }
+            dada_ir::item::Item::ExternFunction(_) => {
+                return Err("unexpected extern function".to_string())
+            }
+        }
+    }
+    Ok(Program { items })
+}
+
+fn decl(db: &dada_db::Db, data: &syntax::LocalVariableDeclData) -> Decl {
+    Decl {
+        specifier: if data.specifier.defaulted(db) {
+            None
+        } else {
+            Some(data.specifier.specifier(db))
+        },
+        atomic: data.atomic == Atomic::Yes,
+        name: data.name.as_str(db).to_string(),
+    }
+}
+
+struct Reader<'r> {
+    db: &'r dada_db::Db,
+    tables: &'r syntax::Tables,
+}
+
+impl Reader<'_> {
+    fn block(&self, expr: syntax::Expr) -> Result<Vec<Expr>, String> {
+        match &self.tables[expr] {
+            syntax::ExprData::Seq(exprs) => self.exprs(exprs),
+            data => Err(format!("expected a block, found {data:?}")),
+        }
+    }
+
+    fn exprs(&self, exprs: &[syntax::Expr]) -> Result<Vec<Expr>, String> {
+        exprs.iter().map(|expr| self.expr(*expr)).collect()
+    }
+
+    fn boxed(&self, expr: syntax::Expr) -> Result<Box<Expr>, String> {
+        Ok(Box::new(self.expr(expr)?))
+    }
+
+    fn expr(&self, expr: syntax::Expr) -> Result<Expr, String> {
+        let db = self.db;
+        Ok(match &self.tables[expr] {
+            syntax::ExprData::Id(name) => Expr::Id(name.as_str(db).to_string()),
+            syntax::ExprData::BooleanLiteral(value) => Expr::Boolean(*value),
+            syntax::ExprData::IntegerLiteral(digits, None) => Expr::Integer(self.number(*digits)?),
+            syntax::ExprData::FloatLiteral(integer, fraction) => {
+                Expr::Float(self.number(*integer)?, self.number(*fraction)?)
+            }
+            syntax::ExprData::StringLiteral(text) => Expr::String(text.as_str(db).to_string()),
+            syntax::ExprData::Dot(owner, field) => {
+                Expr::Dot(self.boxed(*owner)?, field.as_str(db).to_string())
+            }
+            syntax::ExprData::Await(expr) => Expr::Await(self.boxed(*expr)?),
+            syntax::ExprData::Call(callee, arguments) => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| -> Result<Argument, String> {
+                        let data = &self.tables[*argument];
+                        Ok(Argument {
+                            name: data.name.as_str(db).map(str::to_string),
+                            value: self.expr(data.expr)?,
+                        })
+                    })
+                    .collect::<Result<_, String>>()?;
+                Expr::Call(self.boxed(*callee)?, arguments)
+            }
+            syntax::ExprData::Share(expr) => Expr::Share(self.boxed(*expr)?),
+            syntax::ExprData::Lease(expr) => Expr::Lease(self.boxed(*expr)?),
+            syntax::ExprData::Shlease(expr) => Expr::Shlease(self.boxed(*expr)?),
+            syntax::ExprData::Give(expr) => Expr::Give(self.boxed(*expr)?),
+            syntax::ExprData::Var(local_variable_decl, value) => Expr::Var(
+                decl(db, &self.tables[*local_variable_decl]),
+                self.boxed(*value)?,
+            ),
+            syntax::ExprData::Parenthesized(expr) => self.expr(*expr)?,
+            syntax::ExprData::Tuple(exprs) => Expr::Tuple(self.exprs(exprs)?),
+            syntax::ExprData::If(condition, then_expr, else_expr) => Expr::If(
+                self.boxed(*condition)?,
+                self.block(*then_expr)?,
+                else_expr.map(|expr| self.block(expr)).transpose()?,
+            ),
+            syntax::ExprData::Atomic(body) => Expr::Atomic(self.block(*body)?),
+            syntax::ExprData::Loop(body) => Expr::Loop(self.block(*body)?),
+            syntax::ExprData::While(condition, body) => {
+                Expr::While(self.boxed(*condition)?, self.block(*body)?)
+            }
+            syntax::ExprData::Seq(exprs) => Expr::Block(self.exprs(exprs)?),
+            syntax::ExprData::Op(lhs, op, rhs) => {
+                Expr::Op(self.boxed(*lhs)?, *op, self.boxed(*rhs)?)
+            }
+            syntax::ExprData::OpEq(lhs, op, rhs) => {
+                Expr::OpEq(self.boxed(*lhs)?, *op, self.boxed(*rhs)?)
+            }
+            syntax::ExprData::Unary(op, expr) => Expr::Unary(*op, self.boxed(*expr)?),
+            syntax::ExprData::Assign(lhs, rhs) => {
+                Expr::Assign(self.boxed(*lhs)?, self.boxed(*rhs)?)
+            }
+            syntax::ExprData::Return(value) => {
+                Expr::Return(value.map(|expr| self.boxed(expr)).transpose()?)
+            }
+            data @ (syntax::ExprData::IntegerLiteral(_, Some(_)) | syntax::ExprData::Error) => {
+                return Err(format!("unexpected {data:?}"))
+            }
+        })
+    }
+
+    fn number(&self, digits: Word) -> Result<u64, String> {
+        let digits = digits.as_str(self.db);
+        digits
+            .parse()
+            .map_err(|e| format!("bad number `{digits}`: {e}"))
+    }
+}
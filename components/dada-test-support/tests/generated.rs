@@ -0,0 +1,43 @@
+//! Property tests over random programs from `dada_test_support::generate`.
+//! A failure names the seed of the program that failed, and prints it.
+
+use dada_ir::diagnostic::Severity;
+use dada_test_support::{db_with_source, generate, read};
+
+/// How many programs each test generates.
+const PROGRAMS: u64 = 300;
+
+#[test]
+fn print_and_parse_round_trip() {
+    for seed in 0..PROGRAMS {
+        let program = generate(seed);
+        let source = program.to_string();
+        let (db, filename) = db_with_source(&source);
+        match read(&db, filename) {
+            Ok(parsed) => assert_eq!(parsed, program, "seed {seed} changed:\n{source}"),
+            Err(e) => panic!("seed {seed} did not parse: {e}\n{source}"),
+        }
+    }
+}
+
+#[test]
+fn validate_and_brew() {
+    for seed in 0..PROGRAMS {
+        let source = generate(seed).to_string();
+        let (db, filename) = db_with_source(&source);
+        for item in db.items(filename) {
+            db.debug_validated_tree(item);
+            db.debug_bir(item);
+        }
+        let errors: Vec<String> = db
+            .diagnostics(filename)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        assert!(
+            errors.is_empty(),
+            "seed {seed} has errors {errors:?}:\n{source}"
+        );
+    }
+}
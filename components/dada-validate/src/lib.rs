@@ -8,10 +8,17 @@
 mod validate;
 
 #[salsa::jar(Db)]
-pub struct Jar(validate::root_definitions, validate::validate_function);
+pub struct Jar(
+    validate::root_definitions,
+    validate::RootName,
+    validate::root_definition,
+    validate::validate_function,
+    symbols::symbol_uses,
+);
 
 pub trait Db: salsa::DbWithJar<Jar> + dada_ir::Db + dada_parse::Db {}
 
 impl<T> Db for T where T: salsa::DbWithJar<Jar> + dada_ir::Db + dada_parse::Db {}
 
 pub mod prelude;
+pub mod symbols;
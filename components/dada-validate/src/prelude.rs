@@ -19,7 +19,7 @@ pub impl DadaValidateItemExt for Item {
     fn validated_tree(self, db: &dyn crate::Db) -> Option<validated::Tree> {
         match self {
             Item::Function(f) => Some(f.validated_tree(db)),
-            Item::Class(_) => None,
+            Item::Class(_) | Item::ExternFunction(_) => None,
         }
     }
 }
@@ -0,0 +1,233 @@
+//! Finds what the names in a file refer to, for editor features like
+//! go-to-definition. Names are resolved by the validator, so this just
+//! reads the resolved places back out of the validated trees and maps
+//! them to spans using the origin tables.
+
+use dada_collections::Map;
+use dada_id::prelude::*;
+use dada_ir::{
+    code::validated,
+    filename::Filename,
+    intrinsic::Intrinsic,
+    item::Item,
+    span::{FileSpan, Offset},
+    word::Word,
+};
+use dada_parse::prelude::*;
+
+use crate::{prelude::*, validate::name_lookup::Definition};
+
+/// Something that a name can refer to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    /// A local variable (or parameter) of the function with this tree.
+    LocalVariable(validated::Tree, validated::LocalVariable),
+    Item(Item),
+    Intrinsic(Intrinsic),
+    HostFunction(Word),
+}
+
+impl Symbol {
+    /// The span of the name where the symbol is declared. Intrinsics and
+    /// host functions are not declared in Dada code, so they have none.
+    pub fn definition_span(self, db: &dyn crate::Db) -> Option<FileSpan> {
+        match self {
+            Symbol::LocalVariable(tree, local_variable) => {
+                let function = tree.origin(db);
+                let decl = match tree.origins(db)[local_variable] {
+                    validated::LocalVariableOrigin::LocalVariable(decl)
+                    | validated::LocalVariableOrigin::Parameter(decl) => decl,
+                    validated::LocalVariableOrigin::Temporary(_) => return None,
+                };
+                let syntax_tree = function.syntax_tree(db);
+                Some(syntax_tree.file_span(db, syntax_tree.spans(db)[decl].name_span))
+            }
+            Symbol::Item(item) => Some(item.name_span(db)),
+            Symbol::Intrinsic(_) | Symbol::HostFunction(_) => None,
+        }
+    }
+
+    /// The name the symbol is declared with; `None` for temporaries.
+    pub fn name(self, db: &dyn crate::Db) -> Option<Word> {
+        match self {
+            Symbol::LocalVariable(tree, local_variable) => {
+                tree.data(db).tables[local_variable].name
+            }
+            Symbol::Item(item) => Some(item.name(db)),
+            Symbol::Intrinsic(intrinsic) => Some(intrinsic.name(db)),
+            Symbol::HostFunction(name) => Some(name),
+        }
+    }
+}
+
+/// A place in the source where a symbol is named.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SymbolUse {
+    pub symbol: Symbol,
+
+    /// The span of the name.
+    pub span: FileSpan,
+
+    /// True if this is where the symbol is declared, rather than a use.
+    pub is_definition: bool,
+}
+
+/// Returns the symbol named at `offset` in `filename`, if any.
+pub fn symbol_at(db: &dyn crate::Db, filename: Filename, offset: Offset) -> Option<SymbolUse> {
+    // A cursor just past the end of a name is still "on" it.
+    symbol_uses(db, filename)
+        .iter()
+        .filter(|u| u.span.start <= offset && offset <= u.span.end)
+        .min_by_key(|u| u.span.end - u.span.start)
+        .copied()
+}
+
+/// The names in scope at `offset` in `filename`, as the validator would
+/// resolve them there: the items, intrinsics, and host functions of the
+/// file, plus (within a function) the parameters and the local variables
+/// already declared in an enclosing block.
+pub fn names_in_scope(
+    db: &dyn crate::Db,
+    filename: Filename,
+    offset: Offset,
+) -> Vec<(Word, Symbol)> {
+    let mut names: Map<Word, Symbol> = Map::default();
+    for (name, definition) in crate::validate::root_definitions(db, filename).definitions() {
+        let symbol = match definition {
+            Definition::Function(function) => Symbol::Item(Item::Function(function)),
+            Definition::ExternFunction(function) => Symbol::Item(Item::ExternFunction(function)),
+            Definition::Class(class) => Symbol::Item(Item::Class(class)),
+            Definition::Intrinsic(intrinsic) => Symbol::Intrinsic(intrinsic),
+            Definition::HostFunction(name) => Symbol::HostFunction(name),
+            Definition::LocalVariable(_) | Definition::Package(_) => continue,
+        };
+        names.insert(name, symbol);
+    }
+
+    let function = filename.items(db).iter().find_map(|&item| match item {
+        Item::Function(function)
+            if item.span(db).start <= offset && offset <= item.span(db).end =>
+        {
+            Some(function)
+        }
+        _ => None,
+    });
+    if let Some(function) = function {
+        let tree = function.validated_tree(db);
+        let syntax_tree = function.syntax_tree(db);
+        let spans = syntax_tree.spans(db);
+        let data = tree.data(db);
+        let origins = tree.origins(db);
+
+        // Local variables are scoped to the block whose `Declare` introduces
+        // them, and only visible after their declaration. Later variables
+        // shadow earlier ones, and local variables are numbered in the
+        // order they are declared.
+        let mut visible = vec![];
+        for expr in validated::Expr::max_key(&data.tables).iter() {
+            if let validated::ExprData::Declare(vars, _) = expr.data(&data.tables) {
+                let block_span = syntax_tree.file_span(db, spans[origins[expr].syntax_expr]);
+                if block_span.start <= offset && offset <= block_span.end {
+                    visible.extend(vars.iter().copied());
+                }
+            }
+        }
+        for local_variable in data.max_local_variable().iter() {
+            let symbol = Symbol::LocalVariable(tree, local_variable);
+            let Some(name) = local_variable.data(&data.tables).name else {
+                continue;
+            };
+            let in_scope = match origins[local_variable] {
+                validated::LocalVariableOrigin::Parameter(_) => true,
+                validated::LocalVariableOrigin::LocalVariable(_) => {
+                    visible.contains(&local_variable)
+                        && symbol
+                            .definition_span(db)
+                            .is_some_and(|span| span.end <= offset)
+                }
+                validated::LocalVariableOrigin::Temporary(_) => false,
+            };
+            if in_scope {
+                names.insert(name, symbol);
+            }
+        }
+    }
+
+    names.into_iter().collect()
+}
+
+/// All the declarations of symbols in `filename`, and all the uses of
+/// them, in no particular order.
+#[salsa::memoized(in crate::Jar ref)]
+pub fn symbol_uses(db: &dyn crate::Db, filename: Filename) -> Vec<SymbolUse> {
+    let mut uses = vec![];
+    for &item in filename.items(db) {
+        uses.push(SymbolUse {
+            symbol: Symbol::Item(item),
+            span: item.name_span(db),
+            is_definition: true,
+        });
+        if let Item::Function(function) = item {
+            push_uses_in_tree(db, function.validated_tree(db), &mut uses);
+        }
+    }
+    uses
+}
+
+fn push_uses_in_tree(db: &dyn crate::Db, tree: validated::Tree, uses: &mut Vec<SymbolUse>) {
+    let function = tree.origin(db);
+    let syntax_tree = function.syntax_tree(db);
+    let spans = syntax_tree.spans(db);
+    let data = tree.data(db);
+    let origins = tree.origins(db);
+
+    for local_variable in data.max_local_variable().iter() {
+        let symbol = Symbol::LocalVariable(tree, local_variable);
+        if let Some(span) = symbol.definition_span(db) {
+            uses.push(SymbolUse {
+                symbol,
+                span,
+                is_definition: true,
+            });
+        }
+    }
+
+    // Places synthesized while lowering (e.g., for `x += 1`) share the span
+    // of a real place, so skip them to avoid reporting a use twice.
+    let mut push_use = |symbol: Symbol, origin: validated::ExprOrigin| {
+        if !origin.synthesized {
+            uses.push(SymbolUse {
+                symbol,
+                span: syntax_tree.file_span(db, spans[origin.syntax_expr]),
+                is_definition: false,
+            });
+        }
+    };
+
+    for place in validated::Place::max_key(&data.tables).iter() {
+        let symbol = match place.data(&data.tables) {
+            validated::PlaceData::LocalVariable(local_variable) => {
+                Symbol::LocalVariable(tree, *local_variable)
+            }
+            validated::PlaceData::Function(function) => Symbol::Item(Item::Function(*function)),
+            validated::PlaceData::ExternFunction(function) => {
+                Symbol::Item(Item::ExternFunction(*function))
+            }
+            validated::PlaceData::Class(class) => Symbol::Item(Item::Class(*class)),
+            validated::PlaceData::Intrinsic(intrinsic) => Symbol::Intrinsic(*intrinsic),
+            validated::PlaceData::HostFunction(name) => Symbol::HostFunction(*name),
+            validated::PlaceData::Dot(..) => continue,
+        };
+        push_use(symbol, origins[place]);
+    }
+
+    for target_place in validated::TargetPlace::max_key(&data.tables).iter() {
+        match target_place.data(&data.tables) {
+            validated::TargetPlaceData::LocalVariable(local_variable) => push_use(
+                Symbol::LocalVariable(tree, *local_variable),
+                origins[target_place],
+            ),
+            validated::TargetPlaceData::Dot(..) => {}
+        }
+    }
+}
@@ -1,11 +1,13 @@
-use dada_ir::code::validated;
+use dada_id::prelude::*;
+use dada_ir::code::{syntax, validated};
 use dada_ir::filename::Filename;
 use dada_ir::function::Function;
+use dada_ir::word::Word;
 use dada_parse::prelude::*;
 
 use self::name_lookup::Scope;
 
-mod name_lookup;
+pub(crate) mod name_lookup;
 mod validator;
 
 /// Computes a validated tree for the given code (may produce errors).
@@ -13,12 +15,14 @@ mod validator;
 #[tracing::instrument(level = "debug", skip(db))]
 pub fn validate_function(db: &dyn crate::Db, function: Function) -> validated::Tree {
     let code = function.code(db);
+    let _span = dada_ir::phase_span!("validate", db, code.filename(db), code.name).entered();
     let syntax_tree = code.syntax_tree(db);
 
+    // Each syntax expression becomes about one validated expression.
     let mut tables = validated::Tables::default();
+    tables.reserve::<validated::Expr>(syntax::Expr::max_key(&syntax_tree.data(db).tables).into());
     let mut origins = validated::Origins::default();
-    let root_definitions = root_definitions(db, code.filename(db));
-    let scope = Scope::root(db, root_definitions);
+    let scope = Scope::root(db, code.filename(db));
 
     let mut validator = validator::Validator::new(
         db,
@@ -49,3 +53,20 @@ pub fn validate_function(db: &dyn crate::Db, function: Function) -> validated::T
 pub fn root_definitions(db: &dyn crate::Db, filename: Filename) -> name_lookup::RootDefinitions {
     name_lookup::RootDefinitions::new(db, filename)
 }
+
+/// A name in the root scope of a file.
+#[salsa::interned(RootName in crate::Jar)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RootNameData {
+    pub filename: Filename,
+    pub name: Word,
+}
+
+/// The root definition of a single name. Validating a function only looks
+/// up the root definitions of the names it uses, through this, so that it is
+/// not done again when the file changes unless one of those did.
+#[salsa::memoized(in crate::Jar)]
+pub fn root_definition(db: &dyn crate::Db, root_name: RootName) -> Option<name_lookup::Definition> {
+    let RootNameData { filename, name } = root_name.data(db);
+    root_definitions(db, *filename).lookup(*name)
+}
@@ -1,12 +1,22 @@
 use dada_collections::Map;
 use dada_ir::{
-    class::Class, code::validated, filename::Filename, function::Function, intrinsic::Intrinsic,
-    item::Item, word::Word,
+    class::Class, code::validated, extern_function::ExternFunction, filename::Filename,
+    function::Function, intrinsic::Intrinsic, item::Item, word::Word,
 };
 use dada_parse::prelude::*;
 
+use crate::validate::RootNameData;
+
 pub(crate) struct Scope<'me> {
     db: &'me dyn crate::Db,
+    filename: Filename,
+
+    /// The scope this one is nested in; names that aren't defined here are
+    /// looked up there, and then at the root of the file. Linking to it,
+    /// rather than copying its names, keeps entering a block cheap.
+    parent: Option<&'me Scope<'me>>,
+
+    /// The local variables declared in this scope.
     names: Map<Word, Definition>,
     inserted: Vec<validated::LocalVariable>,
 }
@@ -17,11 +27,16 @@ pub struct RootDefinitions {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
-pub(crate) enum Definition {
+pub enum Definition {
     LocalVariable(validated::LocalVariable),
     Function(Function),
+    ExternFunction(ExternFunction),
     Class(Class),
     Intrinsic(Intrinsic),
+    HostFunction(Word),
+
+    /// A package that the file depends on (see `dada_ir::manifest::dependencies`).
+    Package(Word),
 }
 
 impl Definition {
@@ -29,8 +44,11 @@ impl Definition {
         match self {
             Definition::LocalVariable(_) => "variables",
             Definition::Function(_) => "functions",
+            Definition::ExternFunction(_) => "functions",
             Definition::Class(_) => "classes",
             Definition::Intrinsic(_) => "functions",
+            Definition::HostFunction(_) => "functions",
+            Definition::Package(_) => "packages",
         }
     }
 }
@@ -39,6 +57,7 @@ impl From<Item> for Definition {
     fn from(value: Item) -> Self {
         match value {
             Item::Function(f) => Definition::Function(f),
+            Item::ExternFunction(f) => Definition::ExternFunction(f),
             Item::Class(c) => Definition::Class(c),
         }
     }
@@ -50,28 +69,34 @@ impl TryInto<Item> for Definition {
         match self {
             Definition::LocalVariable(_) => Err(()),
             Definition::Intrinsic(_) => Err(()),
+            Definition::HostFunction(_) => Err(()),
+            Definition::Package(_) => Err(()),
             Definition::Function(f) => Ok(Item::Function(f)),
+            Definition::ExternFunction(f) => Ok(Item::ExternFunction(f)),
             Definition::Class(c) => Ok(Item::Class(c)),
         }
     }
 }
 
 impl<'me> Scope<'me> {
-    /// Constructs the root scope for a file, reporting errors if there are
-    /// duplicate items.
-    pub(crate) fn root(db: &'me dyn crate::Db, root_definitions: &RootDefinitions) -> Self {
-        let names = root_definitions.names.clone();
+    /// Constructs the root scope for a file, where only the root
+    /// definitions are in scope.
+    pub(crate) fn root(db: &'me dyn crate::Db, filename: Filename) -> Self {
         Self {
             db,
-            names,
+            filename,
+            parent: None,
+            names: Map::default(),
             inserted: vec![],
         }
     }
 
-    pub(crate) fn subscope(&self) -> Self {
-        Self {
+    pub(crate) fn subscope(&self) -> Scope<'_> {
+        Scope {
             db: self.db,
-            names: self.names.clone(),
+            filename: self.filename,
+            parent: Some(self),
+            names: Map::default(),
             inserted: vec![],
         }
     }
@@ -84,8 +109,10 @@ impl<'me> Scope<'me> {
         local_variable: validated::LocalVariable,
     ) -> Option<Definition> {
         self.inserted.push(local_variable);
+        let shadowed = self.lookup(name);
         self.names
-            .insert(name, Definition::LocalVariable(local_variable))
+            .insert(name, Definition::LocalVariable(local_variable));
+        shadowed
     }
 
     /// Tracks a temporary that is created; they don't affect name resolution, but they get
@@ -97,7 +124,22 @@ impl<'me> Scope<'me> {
 
     /// Lookup the given name in the scope.
     pub(crate) fn lookup(&self, name: Word) -> Option<Definition> {
-        self.names.get(&name).copied()
+        let mut scope = self;
+        loop {
+            if let Some(&definition) = scope.names.get(&name) {
+                return Some(definition);
+            }
+            match scope.parent {
+                Some(parent) => scope = parent,
+                None => break,
+            }
+        }
+        let root_name = RootNameData {
+            filename: self.filename,
+            name,
+        }
+        .intern(self.db);
+        crate::validate::root_definition(self.db, root_name)
     }
 
     /// Get the vector of inserted names from this scope (replacing it with `vec![]`);
@@ -119,6 +161,7 @@ impl RootDefinitions {
             if let Some(&other_definition) = names.get(&name) {
                 let other_item: Item = other_definition.try_into().unwrap();
                 dada_ir::error!(
+                    DuplicateItem,
                     item.name_span(db),
                     "already have a {} named `{}`",
                     other_item.kind_str(),
@@ -135,12 +178,54 @@ impl RootDefinitions {
             }
         }
 
-        // Populate with intrinsics from the prelude (these can be shadowed, so don't error if
+        // Populate with the file's intrinsics (these can be shadowed, so don't error if
         // user generates something with the same name)
-        for &intrinsic in Intrinsic::ALL {
+        for &intrinsic in dada_ir::intrinsic::intrinsics(db, filename) {
             names.insert(intrinsic.name(db), Definition::Intrinsic(intrinsic));
         }
 
+        // Functions provided by the embedder, which take precedence over intrinsics
+        for &name in dada_ir::manifest::host_functions(db, filename) {
+            names.insert(name, Definition::HostFunction(name));
+        }
+
+        // Packages that the file depends on; an item of the file with the
+        // same name takes precedence
+        for dependency in dada_ir::manifest::dependencies(db, filename) {
+            names
+                .entry(dependency.name)
+                .or_insert(Definition::Package(dependency.name));
+        }
+
         RootDefinitions { names }
     }
+
+    /// The definition of `name` at the root of the file, if any.
+    pub(crate) fn lookup(&self, name: Word) -> Option<Definition> {
+        self.names.get(&name).copied()
+    }
+
+    /// Looks up the item named `name` in `package`, one of the packages
+    /// that `filename` depends on.
+    pub(crate) fn lookup_in_package(
+        db: &dyn crate::Db,
+        filename: Filename,
+        package: Word,
+        name: Word,
+    ) -> Option<Item> {
+        let dependency = dada_ir::manifest::dependencies(db, filename)
+            .iter()
+            .find(|dependency| dependency.name == package)?;
+        dependency
+            .filenames
+            .iter()
+            .flat_map(|&filename| filename.items(db))
+            .find(|item| item.name(db) == name)
+            .copied()
+    }
+
+    /// The names defined at the root of the file, in no particular order.
+    pub(crate) fn definitions(&self) -> impl Iterator<Item = (Word, Definition)> + '_ {
+        self.names.iter().map(|(&name, &definition)| (name, definition))
+    }
 }
@@ -5,8 +5,10 @@ use dada_ir::code::validated;
 use dada_ir::code::validated::ExprOrigin;
 use dada_ir::code::validated::LocalVariableOrigin;
 use dada_ir::code::Code;
+use dada_ir::diagnostic::Applicability;
 use dada_ir::diagnostic::ErrorReported;
 use dada_ir::effect::Effect;
+use dada_ir::item::Item;
 use dada_ir::kw::Keyword;
 use dada_ir::origin_table::HasOriginIn;
 use dada_ir::origin_table::PushOriginIn;
@@ -22,6 +24,7 @@ use std::rc::Rc;
 use std::str::FromStr;
 
 use super::name_lookup::Definition;
+use super::name_lookup::RootDefinitions;
 use super::name_lookup::Scope;
 
 pub(crate) struct Validator<'me> {
@@ -140,7 +143,8 @@ impl<'me> Validator<'me> {
     }
 
     fn span(&self, e: impl HasOriginIn<syntax::Spans, Origin = Span>) -> FileSpan {
-        self.code.syntax_tree(self.db).spans(self.db)[e].in_file(self.code.filename(self.db))
+        let syntax_tree = self.code.syntax_tree(self.db);
+        syntax_tree.file_span(self.db, syntax_tree.spans(self.db)[e])
     }
 
     fn empty_tuple(&mut self, origin: syntax::Expr) -> validated::Expr {
@@ -168,6 +172,7 @@ impl<'me> Validator<'me> {
             if let validated::ExprData::Seq(exprs) = validated_expr.data(self.tables) {
                 if exprs.is_empty() {
                     dada_ir::error!(
+                        EmptyFunctionBody,
                         self.code.return_type.span(self.db),
                         "function body cannot be empty",
                     )
@@ -225,7 +230,7 @@ impl<'me> Validator<'me> {
                 let raw_str = w.as_str(self.db);
                 let without_underscore: String = raw_str.chars().filter(|&c| c != '_').collect();
                 let parse_error = |this: &mut Validator, e| {
-                    dada_ir::error!(this.span(expr), "{}", e,).emit(this.db);
+                    dada_ir::error!(InvalidNumber, this.span(expr), "{}", e,).emit(this.db);
                     this.add(validated::ExprData::Error, expr)
                 };
                 match suffix {
@@ -284,6 +289,7 @@ impl<'me> Validator<'me> {
                     Ok(v) => self.add(validated::ExprData::FloatLiteral(eq_float::F64(v)), expr),
                     Err(e) => {
                         dada_ir::error!(
+                            InvalidNumber,
                             self.span(expr),
                             "`{}.{}` is not a valid float: {}",
                             w_int.as_str(self.db),
@@ -309,6 +315,7 @@ impl<'me> Validator<'me> {
                     match self.effect {
                         Effect::Atomic => {
                             dada_ir::error!(
+                                AwaitInAtomic,
                                 await_span,
                                 "await is not permitted inside atomic sections",
                             )
@@ -318,11 +325,17 @@ impl<'me> Validator<'me> {
                         }
                         Effect::Default => {
                             dada_ir::error!(
+                                AwaitOutsideAsync,
                                 await_span,
                                 "await is not permitted outside of async functions",
                             )
                             .primary_label("await is here")
                             .secondary_label(self.effect_span(), "fn not declared `async`")
+                            .fix(
+                                self.effect_span().span_at_start(),
+                                "async ",
+                                "make the function `async`",
+                            )
                             .emit(self.db);
                         }
                         Effect::Async => {
@@ -339,14 +352,34 @@ impl<'me> Validator<'me> {
                 let validated_func_expr = self.reserve_validated_expr(*func_expr);
                 let validated_named_exprs = self.validate_named_exprs(named_exprs);
                 let mut name_required = false;
-                for named_expr in &validated_named_exprs {
+                for (index, named_expr) in validated_named_exprs.iter().enumerate() {
                     let name = named_expr.data(self.tables).name;
                     if name.word(self.db).is_some() {
                         name_required = true;
                     } else if name_required {
-                        dada_ir::error!(name.span(self.db), "parameter name required",)
-                            .primary_label("parameter name required here")
-                            .emit(self.db);
+                        let mut error = dada_ir::error!(
+                            ParameterNameRequired,
+                            name.span(self.db),
+                            "parameter name required",
+                        )
+                        .primary_label("parameter name required here");
+                        error = match self.parameter_name(*func_expr, index) {
+                            Some(parameter) => {
+                                let parameter = parameter.as_str(self.db);
+                                error.fix(
+                                    name.span(self.db),
+                                    format!("{parameter}: "),
+                                    format!("name the argument `{parameter}`"),
+                                )
+                            }
+                            None => error.suggestion(
+                                name.span(self.db),
+                                "name: ",
+                                "name the argument",
+                                Applicability::HasPlaceholders,
+                            ),
+                        };
+                        error.emit(self.db);
                     }
                 }
 
@@ -500,20 +533,30 @@ impl<'me> Validator<'me> {
             syntax::ExprData::Op(lhs_expr, op, rhs_expr) => {
                 let validated_lhs_expr = self.give_validated_expr(*lhs_expr);
                 let validated_rhs_expr = self.give_validated_expr(*rhs_expr);
-                let validated_op = self.validated_op(*op);
-                self.add(
-                    validated::ExprData::Op(validated_lhs_expr, validated_op, validated_rhs_expr),
-                    expr,
-                )
+                let result = try {
+                    let validated_op = self.validated_op(*op, expr)?;
+                    self.add(
+                        validated::ExprData::Op(
+                            validated_lhs_expr,
+                            validated_op,
+                            validated_rhs_expr,
+                        ),
+                        expr,
+                    )
+                };
+                self.or_error(result, expr)
             }
 
             syntax::ExprData::Unary(op, rhs_expr) => {
                 let validated_rhs_expr = self.give_validated_expr(*rhs_expr);
-                let validated_op = self.validated_op(*op);
-                self.add(
-                    validated::ExprData::Unary(validated_op, validated_rhs_expr),
-                    expr,
-                )
+                let result = try {
+                    let validated_op = self.validated_op(*op, expr)?;
+                    self.add(
+                        validated::ExprData::Unary(validated_op, validated_rhs_expr),
+                        expr,
+                    )
+                };
+                self.or_error(result, expr)
             }
 
             syntax::ExprData::OpEq(..) => {
@@ -545,18 +588,21 @@ impl<'me> Validator<'me> {
             syntax::ExprData::Return(with_value) => {
                 match (self.code.return_type.kind(self.db), with_value) {
                     (ReturnTypeKind::Value, None) => {
-                        dada_ir::error!(self.span(expr), "return requires an expression")
-                            .primary_label(
-                                "cannot just have `return` without an expression afterwards",
-                            )
-                            .secondary_label(
-                                self.code.return_type.span(self.db),
-                                "because the function returns a value",
-                            )
-                            .emit(self.db);
+                        dada_ir::error!(
+                            MissingReturnValue,
+                            self.span(expr),
+                            "return requires an expression"
+                        )
+                        .primary_label("cannot just have `return` without an expression afterwards")
+                        .secondary_label(
+                            self.code.return_type.span(self.db),
+                            "because the function returns a value",
+                        )
+                        .emit(self.db);
                     }
-                    (ReturnTypeKind::Unit, Some(return_expr)) => {
+                    (ReturnTypeKind::Unit, Some(return_expr)) if !self.is_error(*return_expr) => {
                         dada_ir::error!(
+                            UnexpectedReturnValue,
                             self.span(*return_expr),
                             "cannot return a value in this function"
                         )
@@ -610,7 +656,7 @@ impl<'me> Validator<'me> {
 
         // `temp_value = x + <rhs>` or `temp_value = temp_leased_owner.x + <rhs>`
         let (temporary_assign_expr, temporary_place) = {
-            let validated_op = self.validated_op(op);
+            let validated_op = self.validated_op(op, op_eq_expr)?;
 
             // `x` or `temp_leased_owner.x`
             let validated_lhs_expr = {
@@ -732,16 +778,34 @@ impl<'me> Validator<'me> {
                 }
 
                 Some(definition @ Definition::Function(_))
+                | Some(definition @ Definition::ExternFunction(_))
                 | Some(definition @ Definition::Class(_))
-                | Some(definition @ Definition::Intrinsic(_)) => Err(dada_ir::error!(
-                    self.span(expr),
-                    "you can only assign to local variables or fields, not {} like `{}`",
-                    definition.plural_description(),
-                    name.as_str(self.db),
-                )
-                .emit(self.db)),
+                | Some(definition @ Definition::Intrinsic(_))
+                | Some(definition @ Definition::HostFunction(_))
+                | Some(definition @ Definition::Package(_)) => {
+                    let mut error = dada_ir::error!(
+                        InvalidAssignmentTarget,
+                        self.span(expr),
+                        "you can only assign to local variables or fields, not {} like `{}`",
+                        definition.plural_description(),
+                        name.as_str(self.db),
+                    );
+                    if let Some(assign_span) = self.assign_op_span(expr) {
+                        error = error.suggestion(
+                            assign_span,
+                            "=",
+                            format!(
+                                "use `=` to declare a new variable named `{}`",
+                                name.as_str(self.db)
+                            ),
+                            Applicability::MaybeIncorrect,
+                        );
+                    }
+                    Err(error.emit(self.db))
+                }
 
                 None => Err(dada_ir::error!(
+                    UnresolvedName,
                     self.span(expr),
                     "can't find anything named `{}`",
                     name.as_str(self.db)
@@ -753,9 +817,12 @@ impl<'me> Validator<'me> {
                 self.validate_expr_as_target_place(*target_expr, owner_mode)
             }
 
+            syntax::ExprData::Error => Err(ErrorReported),
+
             _ => {
                 let _ = self.give_validated_expr(expr);
                 Err(dada_ir::error!(
+                    InvalidAssignmentTarget,
                     self.span(expr),
                     "you can only assign to local variables and fields, not arbitrary expressions",
                 )
@@ -764,6 +831,78 @@ impl<'me> Validator<'me> {
         }
     }
 
+    /// True if `expr` failed to parse. The parser has reported why, so
+    /// errors about `expr` would only pile more on the same mistake.
+    fn is_error(&self, expr: syntax::Expr) -> bool {
+        match expr.data(self.syntax_tables()) {
+            syntax::ExprData::Error => true,
+            syntax::ExprData::Parenthesized(expr) => self.is_error(*expr),
+            _ => false,
+        }
+    }
+
+    /// The span of the `:=` just after `target_expr`, if that is how it
+    /// is assigned (rather than with an operator like `+=`).
+    fn assign_op_span(&self, target_expr: syntax::Expr) -> Option<FileSpan> {
+        let target_span = self.span(target_expr);
+        let source_text = dada_ir::manifest::source_text(self.db, target_span.filename);
+        let after = &source_text[usize::from(target_span.end)..];
+        let spaces = after.len() - after.trim_start_matches([' ', '\t']).len();
+        after[spaces..].starts_with(":=").then(|| {
+            let start = target_span.end + spaces;
+            FileSpan {
+                filename: target_span.filename,
+                start,
+                end: start + 2_u32,
+            }
+        })
+    }
+
+    /// If `func_expr` names a function or class (possibly one in a package),
+    /// returns the name of its parameter (or field) at `index`.
+    fn parameter_name(&self, func_expr: syntax::Expr, index: usize) -> Option<Word> {
+        let definition = match &self.syntax_tables()[func_expr] {
+            syntax::ExprData::Id(name) => self.scope.lookup(*name)?,
+            syntax::ExprData::Dot(..) => {
+                let (package, name) = self.package_path(func_expr)?;
+                Definition::from(self.lookup_in_package(package, name)?)
+            }
+            _ => return None,
+        };
+        match definition {
+            Definition::Function(function) => {
+                Some(function.parameters(self.db).get(index)?.name(self.db))
+            }
+            Definition::Class(class) => Some(class.fields(self.db).get(index)?.name(self.db)),
+            Definition::ExternFunction(function) => {
+                Some(function.signature(self.db).parameters.get(index)?.name)
+            }
+            Definition::LocalVariable(_)
+            | Definition::Intrinsic(_)
+            | Definition::HostFunction(_)
+            | Definition::Package(_) => None,
+        }
+    }
+
+    /// If `expr` is `package.name`, where `package` is one of the packages
+    /// that the file depends on, returns the package and the name.
+    fn package_path(&self, expr: syntax::Expr) -> Option<(Word, Word)> {
+        let syntax::ExprData::Dot(owner, name) = &self.syntax_tables()[expr] else {
+            return None;
+        };
+        let syntax::ExprData::Id(owner_name) = &self.syntax_tables()[*owner] else {
+            return None;
+        };
+        match self.scope.lookup(*owner_name)? {
+            Definition::Package(package) => Some((package, *name)),
+            _ => None,
+        }
+    }
+
+    fn lookup_in_package(&self, package: Word, name: Word) -> Option<Item> {
+        RootDefinitions::lookup_in_package(self.db, self.code.filename(self.db), package, name)
+    }
+
     /// Validate the expression and then exit the subscope (consumes self).
     /// See [`Self::exit`].
     fn validate_expr_and_exit(mut self, expr: syntax::Expr, mode: ExprMode) -> validated::Expr {
@@ -878,14 +1017,34 @@ impl<'me> Validator<'me> {
                     Some(Definition::Function(f)) => {
                         self.add(validated::PlaceData::Function(f), expr)
                     }
+                    Some(Definition::ExternFunction(f)) => {
+                        self.add(validated::PlaceData::ExternFunction(f), expr)
+                    }
                     Some(Definition::LocalVariable(lv)) => {
                         self.add(validated::PlaceData::LocalVariable(lv), expr)
                     }
                     Some(Definition::Intrinsic(i)) => {
                         self.add(validated::PlaceData::Intrinsic(i), expr)
                     }
+                    Some(Definition::HostFunction(name)) => {
+                        self.add(validated::PlaceData::HostFunction(name), expr)
+                    }
+                    Some(Definition::Package(_)) => {
+                        return Err(dada_ir::error!(
+                            PackageAsValue,
+                            self.span(expr),
+                            "`{}` is a package, not a value",
+                            name.as_str(self.db)
+                        )
+                        .primary_label(format!(
+                            "use `{}.name` to refer to one of its items",
+                            name.as_str(self.db)
+                        ))
+                        .emit(self.db))
+                    }
                     None => {
                         return Err(dada_ir::error!(
+                            UnresolvedName,
                             self.span(expr),
                             "can't find anything named `{}`",
                             name.as_str(self.db)
@@ -895,6 +1054,11 @@ impl<'me> Validator<'me> {
                 },
             )),
             syntax::ExprData::Dot(owner_expr, field) => {
+                if let Some((package, name)) = self.package_path(expr) {
+                    let place = self.validate_package_item_as_place(expr, package, name)?;
+                    return Ok((None, place));
+                }
+
                 let (opt_temporary_expr, validated_owner_place) =
                     self.validate_expr_as_place(*owner_expr)?;
                 Ok((
@@ -917,6 +1081,31 @@ impl<'me> Validator<'me> {
         }
     }
 
+    /// Validates `expr`, which is `package.name`, as a reference to the item
+    /// `name` in `package`.
+    fn validate_package_item_as_place(
+        &mut self,
+        expr: syntax::Expr,
+        package: Word,
+        name: Word,
+    ) -> Result<validated::Place, ErrorReported> {
+        match self.lookup_in_package(package, name) {
+            Some(Item::Class(c)) => Ok(self.add(validated::PlaceData::Class(c), expr)),
+            Some(Item::Function(f)) => Ok(self.add(validated::PlaceData::Function(f), expr)),
+            Some(Item::ExternFunction(f)) => {
+                Ok(self.add(validated::PlaceData::ExternFunction(f), expr))
+            }
+            None => Err(dada_ir::error!(
+                UnresolvedPackageItem,
+                self.span(expr),
+                "package `{}` has no item named `{}`",
+                package.as_str(self.db),
+                name.as_str(self.db)
+            )
+            .emit(self.db)),
+        }
+    }
+
     /// Given an expression E, create a new temporary variable V and return a `V = E` expression.
     fn validate_expr_in_temporary(
         &mut self,
@@ -975,8 +1164,14 @@ impl<'me> Validator<'me> {
         )
     }
 
-    fn validated_op(&self, op: syntax::op::Op) -> validated::op::Op {
-        match op {
+    /// The validated form of `op`, which appears in `expr`; an error if it
+    /// is not an operator that the parser produces in expressions.
+    fn validated_op(
+        &self,
+        op: syntax::op::Op,
+        expr: syntax::Expr,
+    ) -> Result<validated::op::Op, ErrorReported> {
+        let validated_op = match op {
             // Compound binops become a binop + assignment
             syntax::op::Op::PlusEqual => validated::op::Op::Plus,
             syntax::op::Op::MinusEqual => validated::op::Op::Minus,
@@ -1004,14 +1199,27 @@ impl<'me> Validator<'me> {
             | syntax::op::Op::Dot
             | syntax::op::Op::Equal
             | syntax::op::Op::RightArrow => {
-                unreachable!("unexpected op")
+                return Err(dada_ir::error!(
+                    SyntaxError,
+                    self.span(expr),
+                    "`{}` is not an operator that can be used here",
+                    op
+                )
+                .emit(self.db))
             }
-        }
+        };
+        Ok(validated_op)
     }
 }
 
-fn count_bytes_in_common(s1: &[u8], s2: &[u8]) -> usize {
-    s1.iter().zip(s2).take_while(|(c1, c2)| c1 == c2).count()
+/// The number of bytes that `s1` and `s2` start with in common, counting
+/// only whole characters, so that either can be sliced there.
+fn count_bytes_in_common(s1: &str, s2: &str) -> usize {
+    s1.chars()
+        .zip(s2.chars())
+        .take_while(|(c1, c2)| c1 == c2)
+        .map(|(c, _)| c.len_utf8())
+        .sum()
 }
 
 #[track_caller]
@@ -1064,7 +1272,7 @@ fn convert_to_dada_string(s: &str) -> String {
             .take_while(|c| c.is_whitespace())
             .collect::<String>();
         let common_indent = non_empty_line_iter
-            .map(|s| count_bytes_in_common(prefix.as_bytes(), s.as_bytes()))
+            .map(|s| count_bytes_in_common(&prefix, s))
             .min()
             .unwrap_or(0);
 
@@ -0,0 +1,56 @@
+//! Stopping a program that the playground is running, e.g. one with a loop
+//! that never ends, without stopping the worker that runs it.
+
+use dada_execute::{
+    cancel::CancellationToken,
+    scheduler::{Scheduler, Task},
+};
+use wasm_bindgen::{prelude::*, JsCast};
+
+/// A handle for cancelling the execution of a [`DadaCompiler`](crate::DadaCompiler).
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct DadaCancellation {
+    token: CancellationToken,
+}
+
+impl DadaCancellation {
+    pub(crate) fn new(token: CancellationToken) -> Self {
+        Self { token }
+    }
+}
+
+#[wasm_bindgen]
+impl DadaCancellation {
+    /// Stops the program being executed (or, if none is, the next one)
+    /// with an error, before its next step.
+    #[wasm_bindgen]
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+}
+
+/// Runs awaited thunks in place, like the default scheduler, but also
+/// yields to the JavaScript event loop every so often, so that a call to
+/// [`DadaCancellation::cancel`] gets to run while the program does.
+pub(crate) struct YieldingScheduler;
+
+#[async_trait::async_trait(?Send)]
+impl Scheduler for YieldingScheduler {
+    async fn run<'a>(&mut self, task: Task<'a>) -> eyre::Result<()> {
+        task.await
+    }
+
+    async fn yield_now(&mut self) {
+        // A `setTimeout` lets the messages and events that are waiting be
+        // handled first, where a resolved promise would not.
+        let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+            let global = js_sys::global();
+            let set_timeout: js_sys::Function = js_sys::Reflect::get(&global, &"setTimeout".into())
+                .unwrap()
+                .unchecked_into();
+            set_timeout.call2(&global, &resolve, &0.into()).unwrap();
+        });
+        let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+    }
+}
@@ -1,5 +1,9 @@
-use crate::range::DadaLineColumn;
-use dada_ir::span::FileSpan;
+use crate::range::{DadaLineColumn, DadaRange};
+use dada_ir::{
+    diagnostic::{Applicability, Diagnostic, Severity},
+    span::FileSpan,
+};
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -45,13 +49,7 @@ impl DadaDiagnostic {
 
     #[wasm_bindgen(getter)]
     pub fn severity(&self) -> String {
-        match self.severity {
-            dada_ir::diagnostic::Severity::Help => "help",
-            dada_ir::diagnostic::Severity::Note => "note",
-            dada_ir::diagnostic::Severity::Warning => "warning",
-            dada_ir::diagnostic::Severity::Error => "error",
-        }
-        .to_string()
+        severity_name(self.severity).to_string()
     }
 
     #[wasm_bindgen(getter)]
@@ -63,8 +61,7 @@ impl DadaDiagnostic {
 #[wasm_bindgen]
 impl DadaLabel {
     pub(crate) fn from(db: &dada_db::Db, span: FileSpan, message: &str) -> Self {
-        let start = DadaLineColumn::from(db, span.filename, span.start);
-        let end = DadaLineColumn::from(db, span.filename, span.end);
+        let DadaRange { start, end } = DadaRange::from(db, span);
         DadaLabel {
             start,
             end,
@@ -77,3 +74,130 @@ impl DadaLabel {
         self.message.clone()
     }
 }
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Help => "help",
+        Severity::Note => "note",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
+
+/// The diagnostics as a JSON array, for editors that only need to show
+/// markers (see [`DadaCompiler::check`](crate::DadaCompiler::check)).
+pub(crate) fn to_json(db: &dada_db::Db, diagnostics: &[Diagnostic]) -> String {
+    let diagnostics: Vec<JsonDiagnostic> = diagnostics
+        .iter()
+        .map(|diagnostic| JsonDiagnostic::from(db, diagnostic))
+        .collect();
+    serde_json::to_string(&diagnostics).unwrap()
+}
+
+#[derive(Serialize)]
+pub(crate) struct JsonDiagnostic {
+    severity: &'static str,
+    code: Option<&'static str>,
+    message: String,
+
+    /// The primary span first, with the label on it if there is one, and
+    /// then the other labels.
+    spans: Vec<JsonSpan>,
+    suggestions: Vec<JsonSuggestion>,
+    children: Vec<JsonDiagnostic>,
+}
+
+#[derive(Serialize)]
+struct JsonSpan {
+    start: JsonLineColumn,
+    end: JsonLineColumn,
+    primary: bool,
+    label: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonSuggestion {
+    message: String,
+    start: JsonLineColumn,
+    end: JsonLineColumn,
+    replacement: String,
+
+    /// `machine-applicable` if the suggestion can be applied without
+    /// asking, `maybe-incorrect`, or `has-placeholders`.
+    applicability: &'static str,
+}
+
+#[derive(Serialize)]
+struct JsonLineColumn {
+    line0: u32,
+    column0: u32,
+}
+
+impl JsonDiagnostic {
+    pub(crate) fn from(db: &dada_db::Db, diagnostic: &Diagnostic) -> Self {
+        let primary_label = diagnostic
+            .labels
+            .iter()
+            .find(|label| label.span == diagnostic.span);
+        let mut spans = vec![JsonSpan::from(
+            db,
+            diagnostic.span,
+            true,
+            primary_label.map(|label| label.message.clone()),
+        )];
+        spans.extend(
+            diagnostic
+                .labels
+                .iter()
+                .filter(|label| label.span != diagnostic.span)
+                .map(|label| JsonSpan::from(db, label.span, false, Some(label.message.clone()))),
+        );
+        JsonDiagnostic {
+            severity: severity_name(diagnostic.severity),
+            code: diagnostic.code.map(|code| code.as_str()),
+            message: diagnostic.message.clone(),
+            spans,
+            suggestions: diagnostic
+                .fixes
+                .iter()
+                .map(|fix| {
+                    let DadaRange { start, end } = DadaRange::from(db, fix.span);
+                    JsonSuggestion {
+                        message: fix.message.clone(),
+                        start: start.into(),
+                        end: end.into(),
+                        replacement: fix.replacement.clone(),
+                        applicability: match fix.applicability {
+                            Applicability::MachineApplicable => "machine-applicable",
+                            Applicability::MaybeIncorrect => "maybe-incorrect",
+                            Applicability::HasPlaceholders => "has-placeholders",
+                        },
+                    }
+                })
+                .collect(),
+            children: diagnostic
+                .children
+                .iter()
+                .map(|child| JsonDiagnostic::from(db, child))
+                .collect(),
+        }
+    }
+}
+
+impl JsonSpan {
+    fn from(db: &dada_db::Db, span: FileSpan, primary: bool, label: Option<String>) -> Self {
+        let DadaRange { start, end } = DadaRange::from(db, span);
+        JsonSpan {
+            start: start.into(),
+            end: end.into(),
+            primary,
+            label,
+        }
+    }
+}
+
+impl From<DadaLineColumn> for JsonLineColumn {
+    fn from(DadaLineColumn { line0, column0 }: DadaLineColumn) -> Self {
+        JsonLineColumn { line0, column0 }
+    }
+}
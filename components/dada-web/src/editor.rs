@@ -0,0 +1,61 @@
+//! Highlighting and completion for the playground's editor, from the same
+//! code that the language server uses to provide them to other editors.
+
+use dada_ir::{filename::Filename, span::Offset};
+use serde::Serialize;
+
+/// A highlighted token, with its position given in full rather than
+/// relative to the token before it as in LSP.
+#[derive(Serialize)]
+struct JsonToken {
+    line0: u32,
+    column0: u32,
+    length: u32,
+
+    /// An LSP token type, like `keyword` or `variable`.
+    #[serde(rename = "type")]
+    token_type: String,
+
+    /// LSP token modifiers, like `declaration`, or `permission` for
+    /// permission keywords.
+    modifiers: Vec<String>,
+}
+
+/// The semantic tokens of `filename`, as a JSON array.
+pub(crate) fn semantic_tokens(db: &dada_db::Db, filename: Filename) -> String {
+    let legend = dada_lsp::semantic_tokens_legend();
+    let (mut line0, mut column0) = (0, 0);
+    let tokens: Vec<JsonToken> = dada_lsp::semantic_tokens(db, filename)
+        .into_iter()
+        .map(|token| {
+            if token.delta_line == 0 {
+                column0 += token.delta_start;
+            } else {
+                line0 += token.delta_line;
+                column0 = token.delta_start;
+            }
+            JsonToken {
+                line0,
+                column0,
+                length: token.length,
+                token_type: legend.token_types[token.token_type as usize]
+                    .as_str()
+                    .to_string(),
+                modifiers: legend
+                    .token_modifiers
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| token.token_modifiers_bitset & (1 << bit) != 0)
+                    .map(|(_, modifier)| modifier.as_str().to_string())
+                    .collect(),
+            }
+        })
+        .collect();
+    serde_json::to_string(&tokens).unwrap()
+}
+
+/// The completions at `offset` in `filename`, as a JSON array of LSP
+/// completion items.
+pub(crate) fn completions(db: &dada_db::Db, filename: Filename, offset: u32) -> String {
+    serde_json::to_string(&dada_lsp::completions(db, filename, Offset::from(offset))).unwrap()
+}
@@ -1,15 +1,32 @@
 #![allow(clippy::unused_unit)] // wasm-bindgen seems to trigger this
 
+use cancel::{DadaCancellation, YieldingScheduler};
 use dada_error_format::format_diagnostics;
-use dada_execute::kernel::BufferKernel;
-use dada_ir::{filename::Filename, span::LineColumn};
+use dada_execute::{cancel::CancellationToken, config::ExecutionConfig, kernel::BufferKernel};
+use dada_ir::{
+    filename::Filename,
+    item::Item,
+    span::{LineColumn, Offset},
+};
 use diagnostics::DadaDiagnostic;
+use output::{DadaOutputLine, OutputCallback};
+pub use permalink::{decode_permalink, encode_permalink, DadaPermalink};
 use range::DadaRange;
+#[cfg(feature = "debugger")]
+pub use session::DadaSession;
 use tracing_wasm::WASMLayerConfigBuilder;
 use wasm_bindgen::prelude::*;
 
+mod cancel;
 mod diagnostics;
+#[cfg(feature = "editor")]
+mod editor;
+mod output;
+mod permalink;
 mod range;
+mod results;
+#[cfg(feature = "debugger")]
+mod session;
 
 #[wasm_bindgen(start)]
 pub fn start() -> Result<(), JsValue> {
@@ -35,11 +52,24 @@ pub struct DadaCompiler {
     /// Current output emitted by the program.
     output: String,
 
+    /// Each line of output emitted by the program, with its source range.
+    output_lines: Vec<DadaOutputLine>,
+
     /// If a breakpoint was set, contains graphviz source
     /// for the heap at that point (else empty).
     heap_capture: Vec<(String, String)>,
 
     breakpoint_ranges: Vec<DadaRange>,
+
+    /// Configuration (e.g., memory limits) used when executing the program.
+    config: ExecutionConfig,
+
+    /// If set, called with each line of output as soon as it is printed.
+    output_callback: Option<OutputCallback>,
+
+    /// Cancels the next program to be executed; each execution takes it
+    /// and leaves a new one in its place.
+    cancellation: CancellationToken,
 }
 
 #[wasm_bindgen]
@@ -53,6 +83,15 @@ impl DadaCompiler {
         Filename::from(&self.db, "input.dada")
     }
 
+    /// The function (or class) whose text includes the byte `offset`.
+    fn item_at(&self, offset: u32) -> Option<Item> {
+        let offset = Offset::from(offset);
+        self.db.items(self.filename()).into_iter().find(|item| {
+            let span = item.span(&self.db);
+            span.start <= offset && offset <= span.end
+        })
+    }
+
     #[wasm_bindgen]
     pub fn with_source_text(mut self, source: String) -> Self {
         // FIXME: reset the database for now
@@ -62,6 +101,20 @@ impl DadaCompiler {
         self
     }
 
+    /// Compiles `source` without executing it, and returns its
+    /// diagnostics as a JSON array. Each diagnostic has a `severity`, a
+    /// `code` (or `null`), a `message`, its `spans` (the primary one first),
+    /// the `suggestions` for fixing it, and its `children`; positions are
+    /// 0-based lines and columns. Unlike [`Self::with_source_text`], the
+    /// database is kept, so only what depends on the change is recompiled,
+    /// which makes this cheap enough to call on every keystroke.
+    #[wasm_bindgen]
+    pub fn check(&mut self, source: String) -> String {
+        let filename = self.filename();
+        self.db.update_file(filename, source);
+        diagnostics::to_json(&self.db, &self.db.diagnostics(filename))
+    }
+
     #[wasm_bindgen]
     pub fn with_breakpoint(mut self, line0: u32, column0: u32) -> Self {
         self.db
@@ -69,22 +122,99 @@ impl DadaCompiler {
         self
     }
 
+    /// Sets a breakpoint on the expression at the byte `offset` (e.g.,
+    /// where the editor's cursor is) and executes the program, so that the
+    /// heap there can be shown; see [`Self::heap_snapshots`].
+    #[wasm_bindgen]
+    pub async fn heap_at(self, offset: u32) -> Self {
+        let position = dada_ir::lines::line_column(&self.db, self.filename(), Offset::from(offset));
+        self.with_breakpoint(position.line0(), position.column0())
+            .execute()
+            .await
+    }
+
     #[wasm_bindgen]
     pub fn without_breakpoint(mut self) -> Self {
         self.db.set_breakpoints(self.filename(), vec![]);
         self
     }
 
+    /// Limits the number of live objects and the total bytes of live
+    /// strings that the program may use before execution is stopped.
+    #[wasm_bindgen]
+    pub fn with_memory_limits(mut self, max_objects: usize, max_string_bytes: usize) -> Self {
+        self.config = self
+            .config
+            .max_objects(max_objects)
+            .max_string_bytes(max_string_bytes);
+        self
+    }
+
+    /// Calls `callback` with each line of output (a `DadaOutputLine`, with
+    /// the range of the source that printed it) as soon as the program
+    /// prints it, rather than only making the output available once the
+    /// program is done.
+    #[wasm_bindgen]
+    pub fn with_output_callback(mut self, callback: js_sys::Function) -> Self {
+        self.output_callback = Some(OutputCallback::new(callback));
+        self
+    }
+
+    /// The validated tree (with names resolved) of the function at the
+    /// byte `offset`, pretty-printed as `dada ir --stage validated` prints
+    /// it; empty if there is no function there.
+    #[wasm_bindgen]
+    pub fn validated_tree_at(&self, offset: u32) -> String {
+        self.item_at(offset)
+            .and_then(|item| self.db.debug_validated_tree(item))
+            .map(|tree| format!("{:#?}", tree))
+            .unwrap_or_default()
+    }
+
+    /// The BIR (the control-flow graph that is executed) of the function
+    /// at the byte `offset`, pretty-printed as `dada ir --stage bir` prints
+    /// it; empty if there is no function there.
+    #[wasm_bindgen]
+    pub fn bir_at(&self, offset: u32) -> String {
+        self.item_at(offset)
+            .and_then(|item| self.db.debug_bir(item))
+            .map(|bir| format!("{:#?}", bir))
+            .unwrap_or_default()
+    }
+
+    /// A handle for stopping the program run by the next call to
+    /// [`Self::execute`] while it runs. The program stops with an error
+    /// before its next step, and the output so far is kept.
+    #[wasm_bindgen(getter)]
+    pub fn cancellation(&self) -> DadaCancellation {
+        DadaCancellation::new(self.cancellation.clone())
+    }
+
     #[wasm_bindgen]
     pub async fn execute(mut self) -> Self {
         let filename = self.filename();
         let diagnostics = self.db.diagnostics(filename);
 
-        let mut kernel = BufferKernel::new().stop_at_breakpoint(false);
+        let mut kernel = BufferKernel::new().stop_at_breakpoint(false).config(
+            self.config
+                .clone()
+                .cancellation(std::mem::take(&mut self.cancellation)),
+        );
+        if let Some(callback) = self.output_callback.clone() {
+            let table = dada_ir::lines::line_table(&self.db, filename).clone();
+            kernel = kernel.output_callback(move |event| {
+                callback.call(DadaOutputLine::from_line_table(&table, event))
+            });
+        }
         match self.db.function_named(filename, "main") {
             Some(function) => {
                 kernel
-                    .interpret_and_buffer(&self.db, function, vec![])
+                    .interpret_and_buffer_with_scheduler(
+                        &self.db,
+                        function,
+                        &mut YieldingScheduler,
+                        vec![],
+                    )
                     .await;
             }
             None => {
@@ -96,6 +226,11 @@ impl DadaCompiler {
         };
 
         self.output = kernel.take_buffer();
+        self.output_lines = kernel
+            .take_output_events()
+            .iter()
+            .map(|event| DadaOutputLine::from(&self.db, event))
+            .collect();
         let heap_graphs = kernel.take_recorded_breakpoints();
 
         tracing::info!(
@@ -164,6 +299,34 @@ impl DadaCompiler {
         self.output.clone()
     }
 
+    /// Everything the last execution produced (the output, with the range
+    /// that printed each line, the diagnostics, the breakpoint ranges, and
+    /// the heap snapshots), as UTF-8 JSON in a `Uint8Array`. A worker that
+    /// runs the compiler can transfer its buffer to the page with
+    /// `postMessage` rather than copy it.
+    #[wasm_bindgen]
+    pub fn results(&self) -> Vec<u8> {
+        results::to_json(self)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn num_output_lines(&self) -> usize {
+        self.output_lines.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn output_line(&self, index: usize) -> DadaOutputLine {
+        self.output_lines[index].clone()
+    }
+
+    /// The heap each time the breakpoint was reached, as a JSON array of
+    /// objects whose `before` and `after` are graphviz sources for the heap
+    /// before and after the expression at the breakpoint ran.
+    #[wasm_bindgen(getter)]
+    pub fn heap_snapshots(&self) -> String {
+        serde_json::to_string(&results::heap_snapshots(&self.heap_capture)).unwrap()
+    }
+
     #[wasm_bindgen(getter)]
     pub fn heap_before(&self) -> String {
         if self.heap_capture.is_empty() {
@@ -182,3 +345,41 @@ impl DadaCompiler {
         self.heap_capture[0].1.clone()
     }
 }
+
+#[cfg(feature = "editor")]
+#[wasm_bindgen]
+impl DadaCompiler {
+    /// The tokens to highlight, as a JSON array of objects with the
+    /// `line0`, `column0`, and `length` of each token, its `type` (like
+    /// `keyword` or `variable`), and its `modifiers` (like `declaration`).
+    /// Names are highlighted by what they refer to, as in editors that use
+    /// the language server.
+    #[wasm_bindgen(getter)]
+    pub fn semantic_tokens(&self) -> String {
+        editor::semantic_tokens(&self.db, self.filename())
+    }
+
+    /// The completions at the byte `offset`, as a JSON array of LSP
+    /// completion items (with a `label` and a numeric `kind` each).
+    #[wasm_bindgen]
+    pub fn completions(&self, offset: u32) -> String {
+        editor::completions(&self.db, self.filename(), offset)
+    }
+}
+
+#[cfg(feature = "debugger")]
+#[wasm_bindgen]
+impl DadaCompiler {
+    /// Prepares to run `main` one statement at a time; see [`DadaSession`].
+    /// Fails if there is no `main` function.
+    #[wasm_bindgen]
+    pub fn session(mut self) -> Result<DadaSession, JsValue> {
+        let filename = self.filename();
+        let kernel = BufferKernel::new().config(
+            self.config
+                .clone()
+                .cancellation(std::mem::take(&mut self.cancellation)),
+        );
+        DadaSession::new(self.db, filename, kernel)
+    }
+}
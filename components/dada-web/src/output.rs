@@ -0,0 +1,57 @@
+use crate::range::DadaRange;
+use dada_execute::kernel::OutputEvent;
+use dada_ir::lines::LineTable;
+use wasm_bindgen::prelude::*;
+
+/// A line of output printed by the program, along with the
+/// range of the source that printed it.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct DadaOutputLine {
+    pub range: DadaRange,
+    pub(crate) text: String,
+}
+
+#[wasm_bindgen]
+impl DadaOutputLine {
+    pub(crate) fn from(db: &dada_db::Db, event: &OutputEvent) -> Self {
+        Self::from_line_table(dada_ir::lines::line_table(db, event.span.filename), event)
+    }
+
+    /// Like [`Self::from`]; `table` is the line table of the file that
+    /// printed the line.
+    pub(crate) fn from_line_table(table: &LineTable, event: &OutputEvent) -> Self {
+        DadaOutputLine {
+            range: DadaRange::from_line_table(table, event.span),
+            text: event.text.clone(),
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+}
+
+/// A JavaScript function to call with each [`DadaOutputLine`] as soon as
+/// it is printed.
+#[derive(Clone)]
+pub(crate) struct OutputCallback(js_sys::Function);
+
+// Safety: JavaScript values can't be sent between threads, but the
+// interpreter only runs on the thread of the page (or worker) that gave
+// us the callback, as wasm has only the one thread.
+unsafe impl Send for OutputCallback {}
+unsafe impl Sync for OutputCallback {}
+
+impl OutputCallback {
+    pub(crate) fn new(function: js_sys::Function) -> Self {
+        Self(function)
+    }
+
+    pub(crate) fn call(&self, line: DadaOutputLine) {
+        if let Err(error) = self.0.call1(&JsValue::NULL, &line.into()) {
+            tracing::warn!("output callback failed: {error:?}");
+        }
+    }
+}
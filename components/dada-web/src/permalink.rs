@@ -0,0 +1,162 @@
+//! Permalinks: a program (and the playground's options for it) packed into
+//! a string that can go in a URL, so that the playground can make links to
+//! share programs without a server to store them on.
+//!
+//! The string is the URL-safe base64 of a version byte followed by the
+//! options and the source, compressed. The compression finds repeated
+//! text, of which programs have plenty (`print(`, `.await`, indentation).
+
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+/// The first byte of each permalink, to be changed if the format ever is.
+const VERSION: u8 = 1;
+
+/// The shortest repetition worth replacing with a reference back to it.
+const MIN_MATCH: usize = 4;
+
+/// A program decoded from a permalink.
+#[wasm_bindgen]
+pub struct DadaPermalink {
+    source: String,
+    flags: String,
+}
+
+#[wasm_bindgen]
+impl DadaPermalink {
+    #[wasm_bindgen(getter)]
+    pub fn source(&self) -> String {
+        self.source.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn flags(&self) -> String {
+        self.flags.clone()
+    }
+}
+
+/// Packs `source` and `flags` (the playground's options, in whatever form
+/// it likes) into a string that can be used in a URL as it is.
+#[wasm_bindgen]
+pub fn encode_permalink(source: &str, flags: &str) -> String {
+    let mut payload = vec![];
+    push_varint(&mut payload, flags.len());
+    payload.extend_from_slice(flags.as_bytes());
+    payload.extend_from_slice(source.as_bytes());
+
+    let mut bytes = vec![VERSION];
+    bytes.extend(compress(&payload));
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+/// Unpacks a string made by [`encode_permalink`]; fails if it was not
+/// made that way (e.g., if the link was cut short).
+#[wasm_bindgen]
+pub fn decode_permalink(permalink: &str) -> Result<DadaPermalink, JsValue> {
+    decode(permalink).ok_or_else(|| JsValue::from("this link to a program is not valid"))
+}
+
+fn decode(permalink: &str) -> Option<DadaPermalink> {
+    let bytes = base64::decode_config(permalink, base64::URL_SAFE_NO_PAD).ok()?;
+    let (&version, compressed) = bytes.split_first()?;
+    if version != VERSION {
+        return None;
+    }
+    let payload = decompress(compressed)?;
+    let mut position = 0;
+    let flags_len = read_varint(&payload, &mut position)?;
+    let flags = payload.get(position..position.checked_add(flags_len)?)?;
+    let source = &payload[position + flags_len..];
+    Some(DadaPermalink {
+        source: String::from_utf8(source.to_vec()).ok()?,
+        flags: String::from_utf8(flags.to_vec()).ok()?,
+    })
+}
+
+/// Compresses `input` into a series of runs of literal bytes, each but the
+/// last followed by a reference to earlier text to repeat: how far back it
+/// starts, and how long it is. All numbers are varints.
+fn compress(input: &[u8]) -> Vec<u8> {
+    let mut output = vec![];
+
+    // The last position at which each sequence of `MIN_MATCH` bytes began.
+    let mut last_seen: HashMap<&[u8], usize> = HashMap::new();
+
+    let mut literal_start = 0;
+    let mut position = 0;
+    while position + MIN_MATCH <= input.len() {
+        let key = &input[position..position + MIN_MATCH];
+        let Some(earlier) = last_seen.insert(key, position) else {
+            position += 1;
+            continue;
+        };
+        let len = input[earlier..]
+            .iter()
+            .zip(&input[position..])
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        push_varint(&mut output, position - literal_start);
+        output.extend_from_slice(&input[literal_start..position]);
+        push_varint(&mut output, position - earlier);
+        push_varint(&mut output, len - MIN_MATCH);
+
+        for start in position + 1..(position + len).min(input.len() + 1 - MIN_MATCH) {
+            last_seen.insert(&input[start..start + MIN_MATCH], start);
+        }
+        position += len;
+        literal_start = position;
+    }
+
+    push_varint(&mut output, input.len() - literal_start);
+    output.extend_from_slice(&input[literal_start..]);
+    output
+}
+
+/// Undoes [`compress`]; `None` if `input` is malformed.
+fn decompress(input: &[u8]) -> Option<Vec<u8>> {
+    let mut output = vec![];
+    let mut position = 0;
+    loop {
+        let literal_len = read_varint(input, &mut position)?;
+        let literals = input.get(position..position.checked_add(literal_len)?)?;
+        output.extend_from_slice(literals);
+        position += literal_len;
+        if position == input.len() {
+            return Some(output);
+        }
+
+        let distance = read_varint(input, &mut position)?;
+        let len = read_varint(input, &mut position)?.checked_add(MIN_MATCH)?;
+        if distance == 0 || distance > output.len() {
+            return None;
+        }
+        // The repeated text may overlap the text being written (as in a
+        // run of spaces), so copy it a byte at a time.
+        for _ in 0..len {
+            output.push(output[output.len() - distance]);
+        }
+    }
+}
+
+fn push_varint(output: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        output.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn read_varint(input: &[u8], position: &mut usize) -> Option<usize> {
+    let mut value: usize = 0;
+    for shift in (0..usize::BITS).step_by(7) {
+        let byte = *input.get(*position)?;
+        *position += 1;
+        value |= usize::from(byte & 0x7f).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
@@ -1,40 +1,40 @@
-use dada_ir::{
-    filename::Filename,
-    span::{FileSpan, Offset},
-};
+use dada_ir::{lines::LineTable, span::FileSpan};
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub struct DadaRange {
     pub start: DadaLineColumn,
     pub end: DadaLineColumn,
 }
 
 #[wasm_bindgen]
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub struct DadaLineColumn {
     pub line0: u32,
     pub column0: u32,
 }
 
-#[wasm_bindgen]
-impl DadaLineColumn {
-    pub(crate) fn from(db: &dada_db::Db, filename: Filename, position: Offset) -> Self {
-        let lc = dada_ir::lines::line_column(db, filename, position);
-        Self {
-            line0: lc.line0(),
-            column0: lc.column0(),
-        }
-    }
-}
-
 #[wasm_bindgen]
 impl DadaRange {
     pub(crate) fn from(db: &dada_db::Db, span: FileSpan) -> Self {
+        Self::from_line_table(dada_ir::lines::line_table(db, span.filename), span)
+    }
+
+    /// Like [`Self::from`], for when the database is not at hand; `table`
+    /// is the line table of the file that `span` is in.
+    pub(crate) fn from_line_table(table: &LineTable, span: FileSpan) -> Self {
+        let (start, end) = (table.line_column(span.start), table.line_column(span.end));
         Self {
-            start: DadaLineColumn::from(db, span.filename, span.start),
-            end: DadaLineColumn::from(db, span.filename, span.end),
+            start: DadaLineColumn {
+                line0: start.line0(),
+                column0: start.column0(),
+            },
+            end: DadaLineColumn {
+                line0: end.line0(),
+                column0: end.column0(),
+            },
         }
     }
 }
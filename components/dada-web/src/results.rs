@@ -0,0 +1,68 @@
+//! Everything that executing a program produced, in one buffer. This is
+//! for running the compiler in a web worker: a byte array returned to
+//! JavaScript has an `ArrayBuffer` of its own, which the worker can
+//! transfer to the page with `postMessage` rather than copy, and the page
+//! gets all of the results in one message rather than asking the worker
+//! for each of them.
+
+use serde::Serialize;
+
+use crate::{diagnostics::JsonDiagnostic, range::DadaRange, DadaCompiler};
+
+#[derive(Serialize)]
+struct Results<'a> {
+    output: &'a str,
+    output_lines: Vec<OutputLine<'a>>,
+    diagnostics: Vec<JsonDiagnostic>,
+
+    /// The diagnostics as `dada check` prints them.
+    formatted_diagnostics: String,
+    breakpoint_ranges: &'a [DadaRange],
+    heap_snapshots: Vec<HeapSnapshot<'a>>,
+}
+
+#[derive(Serialize)]
+struct OutputLine<'a> {
+    text: &'a str,
+    range: DadaRange,
+}
+
+/// The heap when a breakpoint was reached, as graphviz sources for
+/// before and after the expression at the breakpoint ran.
+#[derive(Serialize)]
+pub(crate) struct HeapSnapshot<'a> {
+    before: &'a str,
+    after: &'a str,
+}
+
+/// The graphviz sources in `heap_capture`, which has them as pairs.
+pub(crate) fn heap_snapshots(heap_capture: &[(String, String)]) -> Vec<HeapSnapshot<'_>> {
+    heap_capture
+        .iter()
+        .map(|(before, after)| HeapSnapshot { before, after })
+        .collect()
+}
+
+/// The results of the last execution of `compiler` as UTF-8 JSON.
+pub(crate) fn to_json(compiler: &DadaCompiler) -> Vec<u8> {
+    let results = Results {
+        output: &compiler.output,
+        output_lines: compiler
+            .output_lines
+            .iter()
+            .map(|line| OutputLine {
+                text: &line.text,
+                range: line.range,
+            })
+            .collect(),
+        diagnostics: compiler
+            .diagnostics
+            .iter()
+            .map(|diagnostic| JsonDiagnostic::from(&compiler.db, diagnostic))
+            .collect(),
+        formatted_diagnostics: compiler.diagnostics(),
+        breakpoint_ranges: &compiler.breakpoint_ranges,
+        heap_snapshots: heap_snapshots(&compiler.heap_capture),
+    };
+    serde_json::to_vec(&results).unwrap()
+}
@@ -0,0 +1,130 @@
+//! Running a program in the playground one statement at a time, so that
+//! the page can show where it is, its variables, and its heap after each
+//! step (see [`dada_execute::debugger`]).
+
+use dada_execute::{
+    debugger::{Debugger, StepOutcome},
+    heap_graph::HeapGraph,
+    kernel::BufferKernel,
+};
+use dada_ir::filename::Filename;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::range::DadaRange;
+
+/// The `main` function of a program, paused between statements.
+#[wasm_bindgen]
+pub struct DadaSession {
+    db: dada_db::Db,
+    debugger: Debugger,
+    kernel: BufferKernel,
+
+    /// The heap before the last step, to show what the step changed.
+    previous_heap: Option<HeapGraph>,
+
+    /// The error the program stopped with, if any.
+    error: Option<String>,
+}
+
+impl DadaSession {
+    pub(crate) fn new(
+        db: dada_db::Db,
+        filename: Filename,
+        kernel: BufferKernel,
+    ) -> Result<Self, JsValue> {
+        let Some(function) = db.function_named(filename, "main") else {
+            return Err(format!("no `main` function in `{}`", filename.as_str(&db)).into());
+        };
+        let debugger = Debugger::new(&db, function, vec![])
+            .map_err(|error| JsValue::from(error.to_string()))?;
+        Ok(Self {
+            db,
+            debugger,
+            kernel,
+            previous_heap: None,
+            error: None,
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl DadaSession {
+    /// Executes the next statement, stopping before the first statement
+    /// of any function it calls. Does nothing once the program is done.
+    #[wasm_bindgen]
+    pub async fn step(mut self) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        self.previous_heap = self.debugger.heap_graph(&self.db);
+        match self.debugger.step(&self.db, &mut self.kernel).await {
+            Ok(StepOutcome::Paused(_)) | Ok(StepOutcome::Completed) => {}
+            Err(error) => self.error = Some(error.to_string()),
+        }
+        self
+    }
+
+    /// True once the program has returned from `main` or stopped with an
+    /// error.
+    #[wasm_bindgen(getter)]
+    pub fn is_completed(&self) -> bool {
+        self.error.is_some() || self.debugger.is_completed()
+    }
+
+    /// The error that the program stopped with, or an empty string.
+    #[wasm_bindgen(getter)]
+    pub fn error(&self) -> String {
+        self.error.clone().unwrap_or_default()
+    }
+
+    /// The range of the statement that will be executed next, if any.
+    #[wasm_bindgen(getter)]
+    pub fn current_span(&self) -> Option<DadaRange> {
+        if self.error.is_some() {
+            return None;
+        }
+        let frame = self.debugger.frames(&self.db).into_iter().next()?;
+        Some(DadaRange::from(&self.db, frame.span))
+    }
+
+    /// The variables of the function that is executing, as a JSON array
+    /// of objects with the `name` and `value` (as `print` would show it)
+    /// of each.
+    #[wasm_bindgen(getter)]
+    pub fn locals(&self) -> String {
+        #[derive(Serialize)]
+        struct Local {
+            name: String,
+            value: String,
+        }
+
+        let locals: Vec<Local> = self
+            .debugger
+            .locals(&self.db, 0)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|local| Local {
+                name: local.name,
+                value: local.value,
+            })
+            .collect();
+        serde_json::to_string(&locals).unwrap()
+    }
+
+    /// Graphviz source for the stack and heap, with what the last step
+    /// changed highlighted; empty once the program is done.
+    #[wasm_bindgen(getter)]
+    pub fn heap(&self) -> String {
+        match self.debugger.heap_graph(&self.db) {
+            Some(heap) => heap.graphviz_alone(&self.db, false, self.previous_heap.as_ref()),
+            None => String::new(),
+        }
+    }
+
+    /// What the program has printed so far.
+    #[wasm_bindgen(getter)]
+    pub fn output(&self) -> String {
+        self.kernel.buffer().to_string()
+    }
+}
@@ -0,0 +1,81 @@
+//! Compiles programs with `DadaCompiler::check`, as the playground does
+//! on each keystroke, and checks the diagnostics it returns as JSON.
+
+use serde_json::{json, Value};
+
+const UNKNOWN_NAME: &str = "\
+async fn main() {
+    print(missing).await
+}
+";
+
+const AWAIT_OUTSIDE_ASYNC: &str = "\
+fn main() {
+    print(\"hi\").await
+}
+";
+
+fn check(compiler: &mut dada_web::DadaCompiler, source: &str) -> Vec<Value> {
+    serde_json::from_str(&compiler.check(source.to_string())).unwrap()
+}
+
+#[test]
+fn unknown_name() {
+    let mut compiler = dada_web::compiler();
+    let diagnostics = check(&mut compiler, UNKNOWN_NAME);
+    assert_eq!(
+        diagnostics,
+        [json!({
+            "severity": "error",
+            "code": "E0010",
+            "message": "can't find anything named `missing`",
+            "spans": [{
+                "start": { "line0": 1, "column0": 10 },
+                "end": { "line0": 1, "column0": 17 },
+                "primary": true,
+                "label": null,
+            }],
+            "suggestions": [],
+            "children": [],
+        })]
+    );
+}
+
+#[test]
+fn suggestions_and_labels() {
+    let mut compiler = dada_web::compiler();
+    let diagnostics = check(&mut compiler, AWAIT_OUTSIDE_ASYNC);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic["code"], "E0012");
+
+    let spans = diagnostic["spans"].as_array().unwrap();
+    assert_eq!(spans.len(), 2, "{:?}", spans);
+    assert_eq!(spans[0]["primary"], true);
+    assert_eq!(spans[0]["start"]["line0"], 1);
+    assert_eq!(spans[0]["label"], "await is here");
+    assert_eq!(spans[1]["primary"], false);
+    assert_eq!(spans[1]["label"], "fn not declared `async`");
+
+    assert_eq!(
+        diagnostic["suggestions"],
+        json!([{
+            "message": "make the function `async`",
+            "start": { "line0": 0, "column0": 0 },
+            "end": { "line0": 0, "column0": 0 },
+            "replacement": "async ",
+            "applicability": "machine-applicable",
+        }])
+    );
+}
+
+#[test]
+fn check_again_after_an_edit() {
+    // The compiler keeps its database between checks, so each one only
+    // recompiles what the edit changed; the results must not go stale.
+    let mut compiler = dada_web::compiler();
+    assert_eq!(check(&mut compiler, UNKNOWN_NAME).len(), 1);
+    let fixed = UNKNOWN_NAME.replace("missing", "22");
+    assert_eq!(check(&mut compiler, &fixed), Vec::<Value>::new());
+    assert_eq!(check(&mut compiler, UNKNOWN_NAME).len(), 1);
+}
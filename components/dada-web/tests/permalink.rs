@@ -0,0 +1,63 @@
+//! Packs programs into permalinks and unpacks them again.
+
+use dada_web::{decode_permalink, encode_permalink};
+
+const PROGRAM: &str = "\
+class Point(x, y)
+
+async fn main() {
+    p = Point(x: 22, y: 44)
+    print(p.x).await
+    print(p.y).await
+    print(\"done\").await
+}
+";
+
+/// Encodes `source` and `flags`, checks that the result can go in a URL
+/// as it is, and decodes it again.
+fn round_trip(source: &str, flags: &str) -> String {
+    let permalink = encode_permalink(source, flags);
+    assert!(
+        permalink
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+        "{}",
+        permalink
+    );
+    let Ok(decoded) = decode_permalink(&permalink) else {
+        panic!("could not decode {}", permalink);
+    };
+    assert_eq!(decoded.source(), source);
+    assert_eq!(decoded.flags(), flags);
+    permalink
+}
+
+#[test]
+fn program() {
+    let permalink = round_trip(PROGRAM, "heap");
+    // The repeated `print(` and `.await` are only stored once, so the link
+    // is shorter than the program would be in base64 without compression.
+    assert!(permalink.len() < PROGRAM.len() * 4 / 3, "{}", permalink);
+}
+
+#[test]
+fn empty() {
+    round_trip("", "");
+}
+
+#[test]
+fn flags_that_look_like_source() {
+    round_trip("print(22).await", "print(22).await");
+}
+
+#[test]
+fn overlapping_repetition() {
+    // The reference back to the earlier spaces overlaps the text it writes.
+    round_trip(&" ".repeat(1000), "");
+    round_trip(&"ab".repeat(500), "");
+}
+
+#[test]
+fn non_ascii() {
+    round_trip("print(\"héllo, 世界 🎉\").await\n".repeat(3).as_str(), "ü");
+}
@@ -0,0 +1,114 @@
+//! Runs programs one step at a time through `DadaCompiler::session`, as
+//! the playground's debugger does.
+
+#![cfg(feature = "debugger")]
+
+use serde_json::{json, Value};
+
+const CALL: &str = "\
+async fn main() {
+    x = 22
+    y = add(x, 1)
+    print(y).await
+}
+
+fn add(a, b) -> {
+    a + b
+}
+";
+
+const DIVIDE_BY_ZERO: &str = "\
+async fn main() {
+    print(\"start\").await
+    x = 0
+    print(1 / x).await
+}
+";
+
+/// Steps through the `main` function of `source` until it completes,
+/// calling `on_pause` each time it is paused. Gives up after 1000 steps.
+async fn run(source: &str, mut on_pause: impl FnMut(&Paused)) -> Paused {
+    let Ok(mut session) = dada_web::compiler()
+        .with_source_text(source.to_string())
+        .session()
+    else {
+        panic!("no session for {:?}", source);
+    };
+    for _ in 0..1000 {
+        let paused = Paused::of(&session);
+        if session.is_completed() {
+            return paused;
+        }
+        on_pause(&paused);
+        session = session.step().await;
+    }
+    panic!("{:?} did not complete", source);
+}
+
+/// What a session shows while it is paused (or once it is done).
+#[derive(Debug)]
+struct Paused {
+    /// The 0-based line of the statement to execute next.
+    line0: Option<u32>,
+    locals: Value,
+    heap: String,
+    output: String,
+    error: String,
+}
+
+impl Paused {
+    fn of(session: &dada_web::DadaSession) -> Self {
+        Paused {
+            line0: session.current_span().map(|range| range.start.line0),
+            locals: serde_json::from_str(&session.locals()).unwrap(),
+            heap: session.heap(),
+            output: session.output(),
+            error: session.error(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn step_into_a_call() {
+    let mut lines = vec![];
+    let mut locals_at_print = None;
+    let done = run(CALL, |paused| {
+        let line0 = paused.line0.unwrap();
+        if lines.last() != Some(&line0) {
+            lines.push(line0);
+        }
+        if line0 == 3 && locals_at_print.is_none() {
+            locals_at_print = Some(paused.locals.clone());
+        }
+        assert!(!paused.heap.is_empty());
+        assert_eq!(paused.output, "");
+    })
+    .await;
+
+    // Stepping stops in `add`, between the call and the print.
+    let position = |line0| lines.iter().position(|&l| l == line0).unwrap();
+    assert_eq!(lines[0], 1, "{:?}", lines);
+    assert!(position(2) < position(7), "{:?}", lines);
+    assert!(position(7) < position(3), "{:?}", lines);
+
+    assert_eq!(
+        locals_at_print.unwrap(),
+        json!([
+            { "name": "x", "value": "22" },
+            { "name": "y", "value": "23" },
+        ])
+    );
+
+    assert_eq!(done.line0, None);
+    assert_eq!(done.output, "23\n");
+    assert_eq!(done.error, "");
+    assert_eq!(done.heap, "");
+}
+
+#[tokio::test]
+async fn stop_at_an_error() {
+    let done = run(DIVIDE_BY_ZERO, |_| ()).await;
+    assert_eq!(done.line0, None);
+    assert_eq!(done.output, "start\n");
+    assert!(done.error.contains("divide by zero"), "{}", done.error);
+}
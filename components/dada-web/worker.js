@@ -0,0 +1,49 @@
+// Runs the compiler in a web worker, so that the editor stays responsive
+// while a long program runs. The page posts messages like
+//
+//     { kind: "execute", source, breakpoint }
+//     { kind: "cancel" }
+//
+// and, for each `execute`, gets back `{ kind: "results", results }`, where
+// `results` is the JSON from `DadaCompiler.results`, as an `ArrayBuffer`
+// that is transferred rather than copied. Lines of output are posted as
+// `{ kind: "output", text }` as soon as they are printed.
+import init, { compiler } from "./pkg/dada_web.js";
+
+const ready = init();
+let dada = null;
+let cancellation = null;
+
+onmessage = async (event) => {
+    await ready;
+    if (!dada) {
+        dada = compiler().with_output_callback((line) => {
+            postMessage({ kind: "output", text: line.text });
+        });
+    }
+
+    const message = event.data;
+    switch (message.kind) {
+        case "execute": {
+            dada = dada.with_source_text(message.source);
+            if (message.breakpoint) {
+                dada = dada.with_breakpoint(message.breakpoint.row, message.breakpoint.column);
+            } else {
+                dada = dada.without_breakpoint();
+            }
+            cancellation = dada.cancellation;
+            dada = await dada.execute();
+            cancellation = null;
+
+            const results = dada.results();
+            postMessage({ kind: "results", results: results.buffer }, [results.buffer]);
+            break;
+        }
+
+        case "cancel":
+            if (cancellation) {
+                cancellation.cancel();
+            }
+            break;
+    }
+};
@@ -0,0 +1,155 @@
+//! `cargo xtask bench`: times each phase of compiling and running the
+//! programs in a benchmark corpus (with `dada bench`, built optimized),
+//! writes the times as JSON, and compares them with a baseline from an
+//! earlier run to find the phases that got slower.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use structopt::StructOpt;
+
+use crate::build_dada;
+
+/// Nanoseconds per phase, per benchmark program.
+type Results = BTreeMap<String, BTreeMap<String, u128>>;
+
+/// Phases that got slower by less than this many nanoseconds are not
+/// reported, however much slower they got in percent: for the quickest
+/// phases, that is just noise.
+const NOISE_FLOOR: u128 = 100_000;
+
+#[derive(StructOpt)]
+pub struct Bench {
+    /// Directories and/or `.dada` files to time
+    #[structopt(parse(from_os_str), default_value = "benches")]
+    paths: Vec<PathBuf>,
+
+    /// How many times to compile and run each program; the fastest time
+    /// for each phase is kept
+    #[structopt(long, default_value = "5")]
+    iterations: u32,
+
+    /// Where to write the results
+    #[structopt(long, parse(from_os_str), default_value = "target/bench/results.json")]
+    output: PathBuf,
+
+    /// The results of an earlier run to compare with
+    #[structopt(long, parse(from_os_str), default_value = "target/bench/baseline.json")]
+    baseline: PathBuf,
+
+    /// Also save the results as the baseline, for later runs to compare with
+    #[structopt(long)]
+    save_baseline: bool,
+
+    /// How much slower (in percent) a phase can get before it is reported
+    /// as a regression
+    #[structopt(long, default_value = "10")]
+    threshold: f64,
+}
+
+impl Bench {
+    pub fn main(&self) -> eyre::Result<()> {
+        let dada = build_dada(true)?;
+        let iterations = self.iterations.to_string();
+        let paths = &self.paths;
+        let json = xshell::cmd!("{dada} bench --iterations {iterations} {paths...}").read()?;
+        let results = parse_results(&json)?;
+
+        write(&self.output, &json)?;
+        tracing::info!("results written to `{}`", self.output.display());
+
+        let regressions = if self.baseline.exists() {
+            let baseline = parse_results(&xshell::read_file(&self.baseline)?)?;
+            self.compare(&baseline, &results)
+        } else {
+            tracing::info!(
+                "no baseline at `{}` to compare with",
+                self.baseline.display()
+            );
+            0
+        };
+
+        if self.save_baseline {
+            write(&self.baseline, &json)?;
+            tracing::info!("baseline saved to `{}`", self.baseline.display());
+        }
+
+        if regressions == 0 {
+            Ok(())
+        } else {
+            eyre::bail!("{} phases got slower", regressions)
+        }
+    }
+
+    /// Prints the time of each phase next to its time in the baseline, and
+    /// returns the number of phases that got slower by more than the
+    /// threshold.
+    fn compare(&self, baseline: &Results, results: &Results) -> usize {
+        let mut regressions = 0;
+        for (path, phases) in results {
+            let baseline_phases = match baseline.get(path) {
+                Some(baseline_phases) => baseline_phases,
+                None => {
+                    println!("{}: not in the baseline", path);
+                    continue;
+                }
+            };
+            for (phase, &time) in phases {
+                let baseline_time = match baseline_phases.get(phase) {
+                    Some(&baseline_time) => baseline_time,
+                    None => continue,
+                };
+                let change = percent_change(baseline_time, time);
+                let regressed = time > baseline_time + NOISE_FLOOR && change > self.threshold;
+                println!(
+                    "{}: {}: {} -> {} ({:+.1}%){}",
+                    path,
+                    phase,
+                    format_nanoseconds(baseline_time),
+                    format_nanoseconds(time),
+                    change,
+                    if regressed { "  REGRESSION" } else { "" }
+                );
+                if regressed {
+                    regressions += 1;
+                }
+            }
+        }
+        regressions
+    }
+}
+
+/// Reads the output of `dada bench`: an array of objects with the `path`
+/// of each program and the nanoseconds of each of its `phases`.
+fn parse_results(json: &str) -> eyre::Result<Results> {
+    #[derive(serde::Deserialize)]
+    struct Benchmark {
+        path: String,
+        phases: BTreeMap<String, u128>,
+    }
+
+    let benchmarks: Vec<Benchmark> = serde_json::from_str(json)?;
+    Ok(benchmarks
+        .into_iter()
+        .map(|benchmark| (benchmark.path, benchmark.phases))
+        .collect())
+}
+
+fn write(path: &Path, contents: &str) -> eyre::Result<()> {
+    if let Some(parent) = path.parent() {
+        xshell::mkdir_p(parent)?;
+    }
+    xshell::write_file(path, contents)?;
+    Ok(())
+}
+
+fn percent_change(before: u128, after: u128) -> f64 {
+    if before == 0 {
+        return 0.0;
+    }
+    (after as f64 - before as f64) * 100.0 / before as f64
+}
+
+fn format_nanoseconds(nanoseconds: u128) -> String {
+    format!("{:.3}ms", nanoseconds as f64 / 1_000_000.0)
+}
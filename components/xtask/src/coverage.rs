@@ -0,0 +1,91 @@
+//! `cargo xtask coverage`: runs the workspace's tests (including the test
+//! corpus) with a build instrumented for coverage, then has
+//! [grcov](https://github.com/mozilla/grcov) turn the profiles that the
+//! tests wrote into an HTML report of which lines they ran.
+
+use std::path::Path;
+
+use structopt::StructOpt;
+
+use crate::{target_dir, workspace_dir};
+
+#[derive(StructOpt)]
+pub struct Coverage {
+    /// Also write an `lcov.info` file, for editors and other tools that
+    /// show coverage
+    #[structopt(long)]
+    lcov: bool,
+}
+
+impl Coverage {
+    pub fn main(&self) -> eyre::Result<()> {
+        if xshell::cmd!("grcov --version").read().is_err() {
+            eyre::bail!("grcov is not installed; install it with `cargo install grcov`");
+        }
+        if xshell::cmd!("rustup component list --installed")
+            .read()
+            .map_or(true, |components| !components.contains("llvm-tools"))
+        {
+            eyre::bail!(
+                "llvm-tools are not installed; install them with `rustup component add llvm-tools-preview`"
+            );
+        }
+
+        let workspace_dir = workspace_dir()?;
+        let _directory = xshell::pushd(&workspace_dir)?;
+
+        // The instrumented build goes in a directory of its own, so that it
+        // doesn't replace the usual one.
+        let coverage_dir = target_dir()?.join("rust-coverage");
+        let build_dir = coverage_dir.join("build");
+        let profile_dir = coverage_dir.join("profiles");
+        xshell::rm_rf(&profile_dir)?;
+        xshell::mkdir_p(&profile_dir)?;
+
+        xshell::cmd!("cargo test --workspace --target-dir {build_dir}")
+            .env("RUSTFLAGS", "-C instrument-coverage")
+            .env("LLVM_PROFILE_FILE", profile_dir.join("dada-%p-%m.profraw"))
+            .run()?;
+
+        let binary_dir = build_dir.join("debug");
+        let html_dir = coverage_dir.join("html");
+        grcov(&profile_dir, &binary_dir, "html", &html_dir)?;
+        tracing::info!(
+            "coverage report written to `{}`",
+            html_dir.join("index.html").display()
+        );
+        if self.lcov {
+            let lcov_file = coverage_dir.join("lcov.info");
+            grcov(&profile_dir, &binary_dir, "lcov", &lcov_file)?;
+            tracing::info!("lcov written to `{}`", lcov_file.display());
+        }
+        Ok(())
+    }
+}
+
+/// Runs grcov on the profiles in `profile_dir`, writing a report of the
+/// given `format` to `output`. Only the workspace's own code is reported,
+/// not its dependencies or the standard library.
+fn grcov(profile_dir: &Path, binary_dir: &Path, format: &str, output: &Path) -> eyre::Result<()> {
+    xshell::Cmd::new("grcov")
+        .arg(profile_dir)
+        .arg("--source-dir")
+        .arg(".")
+        .arg("--binary-path")
+        .arg(binary_dir)
+        .arg("--output-type")
+        .arg(format)
+        .arg("--output-path")
+        .arg(output)
+        .arg("--ignore-not-existing")
+        .args([
+            "--ignore",
+            "target/*",
+            "--ignore",
+            "*/.cargo/*",
+            "--ignore",
+            "*/rustc/*",
+        ])
+        .run()?;
+    Ok(())
+}
@@ -1,13 +1,19 @@
 use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 
+use crate::workspace_dir;
+
 #[derive(StructOpt)]
-pub struct Deploy {}
+pub struct Deploy {
+    /// The SHA-256 checksum (in hex) that the downloaded wasm-pack archive
+    /// must have, instead of the published one pinned for this host
+    #[structopt(long)]
+    wasm_pack_sha256: Option<String>,
+}
 
 impl Deploy {
     pub fn main(&self) -> eyre::Result<()> {
-        let xtask_dir = cargo_path("CARGO_MANIFEST_DIR")?;
-        let manifest_dir = xtask_dir.parent().unwrap().parent().unwrap();
+        let manifest_dir = workspace_dir()?;
         tracing::debug!("manifest directory: {manifest_dir:?}");
         let book_dir = manifest_dir.join("book");
         let target_dir = manifest_dir.join("target");
@@ -16,7 +22,7 @@ impl Deploy {
         xshell::mkdir_p(&dada_downloads)?;
         tracing::debug!("dada download directory: {dada_downloads:?}");
 
-        let wasm_pack_path = download_wasm_pack(&dada_downloads)?;
+        let wasm_pack_path = download_wasm_pack(&dada_downloads, self.wasm_pack_sha256.as_deref())?;
 
         {
             let dada_web_dir = xshell::cwd()?.join("components/dada-web");
@@ -28,6 +34,12 @@ impl Deploy {
                 .arg("--dev")
                 .arg("--out-dir")
                 .arg(dada_web_target_dir)
+                // The book's playground only runs programs, so leave out
+                // the debugger and the language server to keep it small.
+                .arg("--")
+                .arg("--no-default-features")
+                .arg("--features")
+                .arg("extra-intrinsics")
                 .run()?;
         }
 
@@ -41,35 +53,156 @@ impl Deploy {
     }
 }
 
-fn download_wasm_pack(dada_downloads: &Path) -> eyre::Result<PathBuf> {
-    let version = "v0.10.2";
-    let prefix = format!("wasm-pack-{version}-x86_64-unknown-linux-musl");
-    let filename = format!("{prefix}.tar.gz");
+/// How wasm-pack is packaged for one kind of host.
+struct WasmPackRelease {
+    /// The target triple in the name of the archive.
+    target: &'static str,
+    archive: Archive,
+    executable: &'static str,
+
+    /// The published SHA-256 checksum of the archive for
+    /// [`WASM_PACK_VERSION`], in lowercase hex; empty if none is pinned.
+    sha256: &'static str,
+}
+
+/// The version of wasm-pack to download. When updating it, update the
+/// checksums in [`wasm_pack_release`] too.
+const WASM_PACK_VERSION: &str = "v0.10.2";
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Archive {
+    TarGz,
+    Zip,
+}
+
+impl Archive {
+    fn extension(self) -> &'static str {
+        match self {
+            Archive::TarGz => "tar.gz",
+            Archive::Zip => "zip",
+        }
+    }
+}
+
+/// The wasm-pack release for the host we're running on.
+fn wasm_pack_release() -> eyre::Result<WasmPackRelease> {
+    use std::env::consts::{ARCH, OS};
+    let release = match (OS, ARCH) {
+        ("linux", "x86_64") => WasmPackRelease {
+            target: "x86_64-unknown-linux-musl",
+            archive: Archive::TarGz,
+            executable: "wasm-pack",
+            sha256: "",
+        },
+        // There is no build for ARM Macs, but the x86_64 one runs under Rosetta.
+        ("macos", "x86_64" | "aarch64") => WasmPackRelease {
+            target: "x86_64-apple-darwin",
+            archive: Archive::TarGz,
+            executable: "wasm-pack",
+            sha256: "",
+        },
+        ("windows", "x86_64") => WasmPackRelease {
+            target: "x86_64-pc-windows-msvc",
+            archive: Archive::Zip,
+            executable: "wasm-pack.exe",
+            sha256: "",
+        },
+        _ => eyre::bail!(
+            "no wasm-pack release for {} {}; install it with `cargo install wasm-pack`",
+            ARCH,
+            OS
+        ),
+    };
+    Ok(release)
+}
+
+pub(crate) fn download_wasm_pack(
+    dada_downloads: &Path,
+    sha256: Option<&str>,
+) -> eyre::Result<PathBuf> {
+    let version = WASM_PACK_VERSION;
+    let release = wasm_pack_release()?;
+    let prefix = format!("wasm-pack-{version}-{}", release.target);
+    let filename = format!("{prefix}.{}", release.archive.extension());
     let url =
         format!("https://github.com/rustwasm/wasm-pack/releases/download/{version}/{filename}");
-    download_and_untar(dada_downloads, &url, &filename)?;
-    Ok(dada_downloads.join(&prefix).join("wasm-pack"))
+    let sha256 = sha256.unwrap_or(release.sha256);
+    download_and_unpack(dada_downloads, &url, &filename, release.archive, sha256)?;
+    Ok(dada_downloads.join(&prefix).join(release.executable))
 }
 
-fn download_and_untar(dada_downloads: &Path, url: &str, file: &str) -> eyre::Result<()> {
-    tracing::debug!("download_and_untar(url={url}, file={file})");
-    let _pushd = xshell::pushd(dada_downloads);
+fn download_and_unpack(
+    dada_downloads: &Path,
+    url: &str,
+    file: &str,
+    archive: Archive,
+    sha256: &str,
+) -> eyre::Result<()> {
+    tracing::debug!("download_and_unpack(url={url}, file={file})");
+    let _pushd = xshell::pushd(dada_downloads)?;
     let file = Path::new(file);
-    if !file.exists() {
-        xshell::cmd!("curl -L -o {file} {url}").run()?;
-        xshell::cmd!("tar zxf {file}").run()?;
+    let downloaded = !file.exists();
+    if downloaded {
+        xshell::cmd!("curl --fail -L -o {file} {url}").run()?;
     } else {
         tracing::debug!("file already exists");
     }
+
+    if let Err(e) = verify_checksum(file, sha256) {
+        if downloaded {
+            // Don't leave a bad download behind to be unpacked next time.
+            xshell::rm_rf(file)?;
+        }
+        return Err(e);
+    }
+
+    if downloaded {
+        match archive {
+            Archive::TarGz => xshell::cmd!("tar zxf {file}").run()?,
+            Archive::Zip => xshell::cmd!(
+                "powershell -NoProfile -Command Expand-Archive -Force -Path {file} -DestinationPath ."
+            )
+            .run()?,
+        }
+    }
     Ok(())
 }
 
-fn cargo_path(env_var: &str) -> eyre::Result<PathBuf> {
-    match std::env::var(env_var) {
-        Ok(s) => {
-            tracing::debug!("cargo_path({env_var}) = {s}");
-            Ok(PathBuf::from(s))
-        }
-        Err(_) => eyre::bail!("`{}` not set", env_var),
+/// Checks the SHA-256 checksum of `file` against `expected`, failing if
+/// they differ or if there is no checksum to check against.
+fn verify_checksum(file: &Path, expected: &str) -> eyre::Result<()> {
+    if expected.is_empty() {
+        eyre::bail!(
+            "no checksum is pinned for `{}`; pass `--wasm-pack-sha256`",
+            file.display()
+        );
+    }
+    let actual = sha256(file)?;
+    let expected = expected.to_ascii_lowercase();
+    if actual != expected {
+        eyre::bail!(
+            "checksum mismatch for `{}`: expected {}, found {}",
+            file.display(),
+            expected,
+            actual
+        );
     }
+    Ok(())
+}
+
+/// The SHA-256 checksum of `file`, in lowercase hex, computed by whichever
+/// tool the host has for it.
+pub(crate) fn sha256(file: &Path) -> eyre::Result<String> {
+    let output = match std::env::consts::OS {
+        "windows" => xshell::cmd!("certutil -hashfile {file} SHA256").read()?,
+        "macos" => xshell::cmd!("shasum -a 256 {file}").read()?,
+        _ => xshell::cmd!("sha256sum {file}").read()?,
+    };
+    // `sha256sum` and `shasum` print the checksum followed by the file
+    // name; `certutil` prints it on a line of its own, between others.
+    output
+        .split_whitespace()
+        .find(|word| word.len() == 64 && word.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|word| word.to_ascii_lowercase())
+        .ok_or_else(|| eyre::eyre!("could not compute the checksum of `{}`", file.display()))
 }
@@ -0,0 +1,49 @@
+//! `cargo xtask fuzz`: builds and runs the fuzz targets in `fuzz` with
+//! [cargo-fuzz](https://github.com/rust-fuzz/cargo-fuzz).
+
+use structopt::StructOpt;
+
+use crate::workspace_dir;
+
+/// The fuzz targets (see `fuzz/Cargo.toml`).
+const TARGETS: &[&str] = &["lex", "parse", "validate"];
+
+#[derive(StructOpt)]
+pub struct Fuzz {
+    /// The target to run (`lex`, `parse`, or `validate`); without one, all
+    /// of them are built, but none is run
+    target: Option<String>,
+
+    /// Stop fuzzing after this many seconds (by default, fuzzing goes on
+    /// until it finds a crash or is interrupted)
+    #[structopt(long)]
+    seconds: Option<u64>,
+}
+
+impl Fuzz {
+    pub fn main(&self) -> eyre::Result<()> {
+        if xshell::cmd!("cargo fuzz --version").read().is_err() {
+            eyre::bail!("cargo-fuzz is not installed; install it with `cargo install cargo-fuzz`");
+        }
+
+        let _directory = xshell::pushd(workspace_dir()?.join("fuzz"))?;
+        let target = match &self.target {
+            Some(target) => target,
+            None => {
+                xshell::cmd!("cargo fuzz build").run()?;
+                return Ok(());
+            }
+        };
+        if !TARGETS.contains(&&target[..]) {
+            eyre::bail!(
+                "unknown fuzz target `{}` (expected one of {})",
+                target,
+                TARGETS.join(", ")
+            );
+        }
+
+        let max_total_time = self.seconds.map(|s| format!("-max_total_time={}", s));
+        xshell::cmd!("cargo fuzz run {target} -- {max_total_time...}").run()?;
+        Ok(())
+    }
+}
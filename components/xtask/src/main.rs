@@ -1,7 +1,14 @@
+use std::path::PathBuf;
+
 use structopt::StructOpt;
 use tracing_subscriber::{prelude::*, EnvFilter};
 
+mod bench;
+mod coverage;
 mod deploy;
+mod fuzz;
+mod release;
+mod test;
 
 fn main() -> eyre::Result<()> {
     Options::from_args().main()
@@ -18,7 +25,12 @@ pub struct Options {
 
 #[derive(StructOpt)]
 pub enum Command {
+    Bench(bench::Bench),
+    Coverage(coverage::Coverage),
     Deploy(deploy::Deploy),
+    Fuzz(fuzz::Fuzz),
+    Release(release::Release),
+    Test(test::Test),
 }
 
 impl Options {
@@ -45,7 +57,49 @@ impl Options {
         tracing::subscriber::set_global_default(subscriber).unwrap();
 
         match &self.command {
+            Command::Bench(c) => c.main(),
+            Command::Coverage(c) => c.main(),
             Command::Deploy(c) => c.main(),
+            Command::Fuzz(c) => c.main(),
+            Command::Release(c) => c.main(),
+            Command::Test(c) => c.main(),
+        }
+    }
+}
+
+fn cargo_path(env_var: &str) -> eyre::Result<PathBuf> {
+    match std::env::var(env_var) {
+        Ok(s) => {
+            tracing::debug!("cargo_path({env_var}) = {s}");
+            Ok(PathBuf::from(s))
         }
+        Err(_) => eyre::bail!("`{}` not set", env_var),
+    }
+}
+
+/// The root of the workspace (where the top-level `Cargo.toml` is).
+fn workspace_dir() -> eyre::Result<PathBuf> {
+    let xtask_dir = cargo_path("CARGO_MANIFEST_DIR")?;
+    Ok(xtask_dir.parent().unwrap().parent().unwrap().to_path_buf())
+}
+
+/// Builds the `dada` executable (optimized, if `release` is true) and
+/// returns its path.
+fn build_dada(release: bool) -> eyre::Result<PathBuf> {
+    let workspace_dir = workspace_dir()?;
+    let _directory = xshell::pushd(&workspace_dir)?;
+    let profile = if release { "release" } else { "debug" };
+    let release_flag = if release { Some("--release") } else { None };
+    xshell::cmd!("cargo build --quiet --package dada {release_flag...}").run()?;
+    Ok(target_dir()?
+        .join(profile)
+        .join(format!("dada{}", std::env::consts::EXE_SUFFIX)))
+}
+
+/// The directory cargo builds into.
+fn target_dir() -> eyre::Result<PathBuf> {
+    match std::env::var_os("CARGO_TARGET_DIR") {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => Ok(workspace_dir()?.join("target")),
     }
 }
@@ -0,0 +1,176 @@
+//! `cargo xtask release`: builds the files to distribute for a release into
+//! `dist`: a stripped, optimized `dada` executable for each target, and the
+//! playground (the wasm build of `dada-web`) as an archive, each named with
+//! the version of `dada`. `dist/manifest.json` lists them, with their
+//! checksums.
+
+use std::path::Path;
+
+use structopt::StructOpt;
+
+use crate::{deploy, target_dir, workspace_dir};
+
+/// The targets that releases are built for, unless others are given.
+const TARGETS: &[&str] = &[
+    "x86_64-unknown-linux-gnu",
+    "aarch64-unknown-linux-gnu",
+    "x86_64-apple-darwin",
+    "aarch64-apple-darwin",
+    "x86_64-pc-windows-msvc",
+];
+
+#[derive(StructOpt)]
+pub struct Release {
+    /// The targets to build `dada` for (by default, all the major ones);
+    /// each needs its standard library (`rustup target add`) and a linker
+    #[structopt(long = "target")]
+    targets: Vec<String>,
+
+    /// Leave out the playground
+    #[structopt(long)]
+    no_playground: bool,
+
+    /// The SHA-256 checksum (in hex) that the downloaded wasm-pack archive
+    /// must have (see `cargo xtask deploy`)
+    #[structopt(long)]
+    wasm_pack_sha256: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct Manifest {
+    version: String,
+    artifacts: Vec<Artifact>,
+}
+
+#[derive(serde::Serialize)]
+struct Artifact {
+    /// The file name, relative to `dist`.
+    file: String,
+
+    /// `cli` or `playground`.
+    kind: &'static str,
+
+    /// The target the executable is for (none for the playground).
+    target: Option<String>,
+
+    /// The SHA-256 checksum, in lowercase hex.
+    sha256: String,
+}
+
+impl Release {
+    pub fn main(&self) -> eyre::Result<()> {
+        let workspace_dir = workspace_dir()?;
+        let _directory = xshell::pushd(&workspace_dir)?;
+        let version = dada_version()?;
+        let dist_dir = workspace_dir.join("dist");
+        xshell::rm_rf(&dist_dir)?;
+        xshell::mkdir_p(&dist_dir)?;
+
+        let targets: Vec<&str> = if self.targets.is_empty() {
+            TARGETS.to_vec()
+        } else {
+            self.targets.iter().map(|t| &t[..]).collect()
+        };
+
+        let mut artifacts = vec![];
+        for target in targets {
+            let file = build_cli(target, &version, &dist_dir)?;
+            artifacts.push(artifact(&dist_dir, file, "cli", Some(target))?);
+        }
+        if !self.no_playground {
+            let file = self.build_playground(&version, &dist_dir)?;
+            artifacts.push(artifact(&dist_dir, file, "playground", None)?);
+        }
+
+        let manifest = Manifest { version, artifacts };
+        xshell::write_file(
+            dist_dir.join("manifest.json"),
+            serde_json::to_string_pretty(&manifest)?,
+        )?;
+        tracing::info!(
+            "{} artifacts written to `{}`",
+            manifest.artifacts.len(),
+            dist_dir.display()
+        );
+        Ok(())
+    }
+
+    /// Builds the playground (as `cargo xtask deploy` does, but optimized)
+    /// and packs it into an archive in `dist_dir`, returning its name.
+    fn build_playground(&self, version: &str, dist_dir: &Path) -> eyre::Result<String> {
+        let target_dir = target_dir()?;
+        let dada_downloads = target_dir.join("dada-downloads");
+        xshell::mkdir_p(&dada_downloads)?;
+        let wasm_pack_path =
+            deploy::download_wasm_pack(&dada_downloads, self.wasm_pack_sha256.as_deref())?;
+
+        let name = format!("dada-playground-{version}");
+        let out_dir = target_dir.join("dada-release").join(&name);
+        xshell::rm_rf(&out_dir)?;
+        {
+            let _directory = xshell::pushd("components/dada-web")?;
+            xshell::Cmd::new(&wasm_pack_path)
+                .arg("build")
+                .arg("--target")
+                .arg("web")
+                .arg("--release")
+                .arg("--out-dir")
+                .arg(&out_dir)
+                .arg("--")
+                .arg("--no-default-features")
+                .arg("--features")
+                .arg("extra-intrinsics")
+                .run()?;
+        }
+
+        let file = format!("{name}.tar.gz");
+        let archive = dist_dir.join(&file);
+        let parent = out_dir.parent().unwrap();
+        xshell::cmd!("tar czf {archive} -C {parent} {name}").run()?;
+        Ok(file)
+    }
+}
+
+/// Builds a stripped, optimized `dada` for `target` and copies it into
+/// `dist_dir`, returning its name there.
+fn build_cli(target: &str, version: &str, dist_dir: &Path) -> eyre::Result<String> {
+    xshell::cmd!("cargo build --release --package dada --target {target}")
+        .env("CARGO_PROFILE_RELEASE_STRIP", "symbols")
+        .run()?;
+    let exe_suffix = if target.contains("windows") {
+        ".exe"
+    } else {
+        ""
+    };
+    let built = target_dir()?
+        .join(target)
+        .join("release")
+        .join(format!("dada{exe_suffix}"));
+    let file = format!("dada-{version}-{target}{exe_suffix}");
+    xshell::cp(built, dist_dir.join(&file))?;
+    Ok(file)
+}
+
+fn artifact(
+    dist_dir: &Path,
+    file: String,
+    kind: &'static str,
+    target: Option<&str>,
+) -> eyre::Result<Artifact> {
+    Ok(Artifact {
+        sha256: deploy::sha256(&dist_dir.join(&file))?,
+        file,
+        kind,
+        target: target.map(str::to_string),
+    })
+}
+
+/// The version of the `dada` package, from `cargo pkgid`, which prints
+/// something like `path+file:///.../dada#0.1.0` (or `...#dada@0.1.0`).
+fn dada_version() -> eyre::Result<String> {
+    let pkgid = xshell::cmd!("cargo pkgid --package dada").read()?;
+    match pkgid.trim().rsplit(['#', '@']).next() {
+        Some(version) if !version.is_empty() => Ok(version.to_string()),
+        _ => eyre::bail!("no version in `{}`", pkgid),
+    }
+}
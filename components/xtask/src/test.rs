@@ -0,0 +1,121 @@
+//! `cargo xtask test`: runs the test corpus with `dada test`, split across
+//! several processes that run at once. Each test is checked against the
+//! `.ref` files next to it (the compiler output, stdout, and the syntax
+//! tree, validated tree, and BIR dumps), as `dada test` does.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Mutex;
+
+use structopt::StructOpt;
+
+use crate::build_dada;
+
+#[derive(StructOpt)]
+pub struct Test {
+    /// Directories and/or `.dada` files to test
+    #[structopt(parse(from_os_str), default_value = "dada_tests")]
+    paths: Vec<PathBuf>,
+
+    /// Instead of checking the `.ref` files, regenerate them
+    #[structopt(long)]
+    bless: bool,
+
+    /// How many `dada test` processes to run at once (by default, one per
+    /// CPU)
+    #[structopt(long)]
+    jobs: Option<usize>,
+}
+
+impl Test {
+    pub fn main(&self) -> eyre::Result<()> {
+        let dada = build_dada(false)?;
+
+        let mut tests = vec![];
+        for path in &self.paths {
+            collect_tests(path, &mut tests)?;
+        }
+        if tests.is_empty() {
+            eyre::bail!("no tests found");
+        }
+        tests.sort();
+
+        let jobs = match self.jobs {
+            Some(jobs) => jobs.max(1),
+            None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        };
+        let shards = shard(&tests, jobs);
+        tracing::info!(
+            "running {} tests in {} processes",
+            tests.len(),
+            shards.len()
+        );
+
+        // Print the output of each process as a whole, once it is done, so
+        // that the output of different processes isn't interleaved.
+        let failed = Mutex::new(0);
+        std::thread::scope(|scope| {
+            for shard in &shards {
+                let (dada, failed) = (&dada, &failed);
+                scope.spawn(move || {
+                    let output = self.run_shard(dada, shard);
+                    let passed = match &output {
+                        Ok(output) => {
+                            print!("{}", String::from_utf8_lossy(&output.stdout));
+                            eprint!("{}", String::from_utf8_lossy(&output.stderr));
+                            output.status.success()
+                        }
+                        Err(e) => {
+                            tracing::error!("{e:?}");
+                            false
+                        }
+                    };
+                    if !passed {
+                        *failed.lock().unwrap() += 1;
+                    }
+                });
+            }
+        });
+
+        let failed = failed.into_inner().unwrap();
+        if failed == 0 {
+            tracing::info!("all {} tests passed", tests.len());
+            Ok(())
+        } else {
+            eyre::bail!("tests failed in {} of {} processes", failed, shards.len())
+        }
+    }
+
+    /// Runs `dada test` on the tests in `shard`.
+    fn run_shard(&self, dada: &Path, shard: &[PathBuf]) -> eyre::Result<Output> {
+        let mut command = Command::new(dada);
+        command.arg("test");
+        if self.bless {
+            command.arg("--bless");
+        }
+        command.args(shard);
+        Ok(command.output()?)
+    }
+}
+
+/// Adds `path`, if it is a `.dada` file, or the `.dada` files in it, if it
+/// is a directory, to `tests`.
+fn collect_tests(path: &Path, tests: &mut Vec<PathBuf>) -> eyre::Result<()> {
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            collect_tests(&entry?.path(), tests)?;
+        }
+    } else if path.extension().is_some_and(|e| e == "dada") {
+        tests.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+/// Splits `tests` into at most `jobs` shards of about the same size.
+fn shard(tests: &[PathBuf], jobs: usize) -> Vec<Vec<PathBuf>> {
+    let mut shards = vec![vec![]; jobs.min(tests.len())];
+    for (test, index) in tests.iter().zip((0..shards.len()).cycle()) {
+        shards[index].push(test.clone());
+    }
+    shards
+}
@@ -0,0 +1,8 @@
+#![no_main]
+
+use dada_fuzz::FuzzToken;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|tokens: Vec<FuzzToken>| {
+    dada_fuzz::validate(&dada_fuzz::source_for_tokens(&tokens))
+});
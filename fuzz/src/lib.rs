@@ -0,0 +1,84 @@
+//! Shared code for the fuzz targets in `fuzz_targets`. Each target feeds
+//! its input to one stage of the compiler (and the stages before it); any
+//! panic is a bug, since bad input should only ever produce diagnostics.
+
+use arbitrary::Arbitrary;
+use dada_ir::{code::syntax::op::Op, filename::Filename, kw::Keyword};
+
+/// A database with `source` as the contents of a file, and that file.
+pub fn db_with_source(source: &str) -> (dada_db::Db, Filename) {
+    let mut db = dada_db::Db::default();
+    let filename = Filename::from(&db, "fuzz.dada");
+    db.update_file(filename, source.to_string());
+    (db, filename)
+}
+
+/// Lexes `source`.
+pub fn lex(source: &str) {
+    let (db, filename) = db_with_source(source);
+    dada_lex::lex_file(&db, filename);
+}
+
+/// Parses the items in `source`.
+pub fn parse(source: &str) {
+    let (db, filename) = db_with_source(source);
+    for item in db.items(filename) {
+        db.debug_syntax_tree(item);
+    }
+}
+
+/// Validates and brews the items in `source`, and checks it for errors.
+pub fn validate(source: &str) {
+    let (db, filename) = db_with_source(source);
+    for item in db.items(filename) {
+        db.debug_validated_tree(item);
+        db.debug_bir(item);
+    }
+    db.diagnostics(filename);
+}
+
+/// A token for building programs that mostly lex and parse, so that the
+/// fuzzer spends its time in the validator rather than on inputs that the
+/// parser rejects.
+#[derive(Arbitrary, Debug)]
+pub enum FuzzToken {
+    Keyword(u8),
+    Op(u8),
+    /// One of a few names, so that names are often used after they are
+    /// declared.
+    Name(u8),
+    Integer(u16),
+    String(u8),
+    OpenParen,
+    CloseParen,
+    OpenBrace,
+    CloseBrace,
+    Comma,
+    Newline,
+}
+
+/// The source text for `tokens`, separated by spaces.
+pub fn source_for_tokens(tokens: &[FuzzToken]) -> String {
+    const NAMES: &[&str] = &["a", "b", "p", "x", "main", "print", "Point"];
+    let keywords: Vec<Keyword> = Keyword::all().collect();
+    let ops: Vec<Op> = Op::all().collect();
+
+    let mut source = String::new();
+    for token in tokens {
+        match token {
+            FuzzToken::Keyword(i) => source.push_str(keywords[*i as usize % keywords.len()].str()),
+            FuzzToken::Op(i) => source.push_str(ops[*i as usize % ops.len()].str()),
+            FuzzToken::Name(i) => source.push_str(NAMES[*i as usize % NAMES.len()]),
+            FuzzToken::Integer(i) => source.push_str(&i.to_string()),
+            FuzzToken::String(i) => source.push_str(&format!("\"s{}\"", i)),
+            FuzzToken::OpenParen => source.push('('),
+            FuzzToken::CloseParen => source.push(')'),
+            FuzzToken::OpenBrace => source.push('{'),
+            FuzzToken::CloseBrace => source.push('}'),
+            FuzzToken::Comma => source.push(','),
+            FuzzToken::Newline => source.push('\n'),
+        }
+        source.push(' ');
+    }
+    source
+}
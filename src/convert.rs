@@ -0,0 +1,297 @@
+//! Converting between Rust values and Dada [`Value`]s.
+//!
+//! Dada has no list or map types, so sequences (like `Vec`) become tuples
+//! and maps become tuples of `(key, value)` pairs. Types that implement
+//! serde's traits can also be converted with [`to_value`](crate::to_value)
+//! and [`from_value`](crate::from_value), which turn structs into class
+//! instances.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    hash::Hash,
+};
+
+use crate::Value;
+
+/// Types that can be passed to Dada.
+pub trait ToDada {
+    fn to_dada(&self) -> Value;
+}
+
+/// Types that can be returned from Dada.
+pub trait FromDada: Sized {
+    fn from_dada(value: Value) -> Result<Self, ConversionError>;
+}
+
+/// A Dada value could not be converted to (or from) a Rust value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConversionError {
+    message: String,
+}
+
+impl ConversionError {
+    pub(crate) fn new(message: impl fmt::Display) -> Self {
+        Self {
+            message: message.to_string(),
+        }
+    }
+
+    /// The value was not of the kind that was `expected`.
+    pub(crate) fn expected(expected: &str, found: &Value) -> Self {
+        Self::new(format_args!("expected {}, found `{}`", expected, found))
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl<T: ToDada + ?Sized> ToDada for &T {
+    fn to_dada(&self) -> Value {
+        T::to_dada(self)
+    }
+}
+
+impl ToDada for Value {
+    fn to_dada(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl FromDada for Value {
+    fn from_dada(value: Value) -> Result<Self, ConversionError> {
+        Ok(value)
+    }
+}
+
+impl ToDada for () {
+    fn to_dada(&self) -> Value {
+        Value::Unit
+    }
+}
+
+impl FromDada for () {
+    fn from_dada(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Unit => Ok(()),
+            value => Err(ConversionError::expected("`()`", &value)),
+        }
+    }
+}
+
+impl ToDada for bool {
+    fn to_dada(&self) -> Value {
+        Value::Bool(*self)
+    }
+}
+
+impl FromDada for bool {
+    fn from_dada(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            value => Err(ConversionError::expected("a boolean", &value)),
+        }
+    }
+}
+
+macro_rules! integer_impls {
+    ($variant:ident: $($ty:ty),*) => {
+        $(
+            impl ToDada for $ty {
+                fn to_dada(&self) -> Value {
+                    Value::$variant((*self).try_into().unwrap())
+                }
+            }
+
+            impl FromDada for $ty {
+                fn from_dada(value: Value) -> Result<Self, ConversionError> {
+                    let converted = match value {
+                        Value::Int(i) | Value::UnsignedInt(i) => i.try_into().ok(),
+                        Value::SignedInt(i) => i.try_into().ok(),
+                        _ => return Err(ConversionError::expected("an integer", &value)),
+                    };
+                    converted.ok_or_else(|| {
+                        ConversionError::new(format_args!(
+                            "`{}` does not fit in `{}`",
+                            value,
+                            stringify!($ty)
+                        ))
+                    })
+                }
+            }
+        )*
+    };
+}
+
+integer_impls!(SignedInt: i8, i16, i32, i64, isize);
+integer_impls!(UnsignedInt: u8, u16, u32, u64, usize);
+
+impl ToDada for f64 {
+    fn to_dada(&self) -> Value {
+        Value::Float(*self)
+    }
+}
+
+impl FromDada for f64 {
+    fn from_dada(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Float(f) => Ok(f),
+            value => Err(ConversionError::expected("a float", &value)),
+        }
+    }
+}
+
+impl ToDada for f32 {
+    fn to_dada(&self) -> Value {
+        Value::Float(f64::from(*self))
+    }
+}
+
+impl FromDada for f32 {
+    fn from_dada(value: Value) -> Result<Self, ConversionError> {
+        f64::from_dada(value).map(|f| f as f32)
+    }
+}
+
+impl ToDada for str {
+    fn to_dada(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl ToDada for String {
+    fn to_dada(&self) -> Value {
+        Value::String(self.clone())
+    }
+}
+
+impl FromDada for String {
+    fn from_dada(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::String(s) => Ok(s),
+            value => Err(ConversionError::expected("a string", &value)),
+        }
+    }
+}
+
+/// `None` is passed as `()`, and `()` is returned as `None`.
+impl<T: ToDada> ToDada for Option<T> {
+    fn to_dada(&self) -> Value {
+        match self {
+            Some(value) => value.to_dada(),
+            None => Value::Unit,
+        }
+    }
+}
+
+impl<T: FromDada> FromDada for Option<T> {
+    fn from_dada(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Unit => Ok(None),
+            value => T::from_dada(value).map(Some),
+        }
+    }
+}
+
+impl<T: ToDada> ToDada for [T] {
+    fn to_dada(&self) -> Value {
+        Value::Tuple(self.iter().map(ToDada::to_dada).collect())
+    }
+}
+
+impl<T: ToDada> ToDada for Vec<T> {
+    fn to_dada(&self) -> Value {
+        self[..].to_dada()
+    }
+}
+
+impl<T: FromDada> FromDada for Vec<T> {
+    fn from_dada(value: Value) -> Result<Self, ConversionError> {
+        match value {
+            Value::Tuple(values) => values.into_iter().map(T::from_dada).collect(),
+            value => Err(ConversionError::expected("a tuple", &value)),
+        }
+    }
+}
+
+macro_rules! tuple_impls {
+    ($($len:literal => ($($name:ident),*),)*) => {
+        $(
+            impl<$($name: ToDada),*> ToDada for ($($name,)*) {
+                #[allow(non_snake_case)]
+                fn to_dada(&self) -> Value {
+                    let ($($name,)*) = self;
+                    Value::Tuple(vec![$($name.to_dada()),*])
+                }
+            }
+
+            impl<$($name: FromDada),*> FromDada for ($($name,)*) {
+                fn from_dada(value: Value) -> Result<Self, ConversionError> {
+                    match value {
+                        Value::Tuple(values) if values.len() == $len => {
+                            let mut values = values.into_iter();
+                            Ok(($($name::from_dada(values.next().unwrap())?,)*))
+                        }
+                        value => Err(ConversionError::expected(
+                            concat!("a tuple of ", $len, " elements"),
+                            &value,
+                        )),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+tuple_impls! {
+    1 => (A),
+    2 => (A, B),
+    3 => (A, B, C),
+    4 => (A, B, C, D),
+    5 => (A, B, C, D, E),
+    6 => (A, B, C, D, E, F),
+}
+
+fn map_to_dada<'a, K: ToDada + 'a, V: ToDada + 'a>(
+    entries: impl Iterator<Item = (&'a K, &'a V)>,
+) -> Value {
+    Value::Tuple(
+        entries
+            .map(|(key, value)| Value::Tuple(vec![key.to_dada(), value.to_dada()]))
+            .collect(),
+    )
+}
+
+impl<K: ToDada, V: ToDada, S> ToDada for HashMap<K, V, S> {
+    fn to_dada(&self) -> Value {
+        map_to_dada(self.iter())
+    }
+}
+
+impl<K, V, S> FromDada for HashMap<K, V, S>
+where
+    K: FromDada + Eq + Hash,
+    V: FromDada,
+    S: std::hash::BuildHasher + Default,
+{
+    fn from_dada(value: Value) -> Result<Self, ConversionError> {
+        Vec::<(K, V)>::from_dada(value).map(|entries| entries.into_iter().collect())
+    }
+}
+
+impl<K: ToDada, V: ToDada> ToDada for BTreeMap<K, V> {
+    fn to_dada(&self) -> Value {
+        map_to_dada(self.iter())
+    }
+}
+
+impl<K: FromDada + Ord, V: FromDada> FromDada for BTreeMap<K, V> {
+    fn from_dada(value: Value) -> Result<Self, ConversionError> {
+        Vec::<(K, V)>::from_dada(value).map(|entries| entries.into_iter().collect())
+    }
+}
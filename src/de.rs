@@ -0,0 +1,185 @@
+//! Converting a Dada [`Value`] to any `serde::Deserialize` type.
+
+use serde::de::{
+    self, value::StringDeserializer, DeserializeOwned, DeserializeSeed, Deserializer as _,
+    IntoDeserializer, Visitor,
+};
+
+use crate::{convert::ConversionError, Value};
+
+/// Converts `value` into a Rust value; the inverse of
+/// [`to_value`](crate::to_value). Instances can be read as structs (or as
+/// maps from field names to values); `()` can be read as `None`; and
+/// strings can be read as unit variants.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, ConversionError> {
+    T::deserialize(value)
+}
+
+impl de::Error for ConversionError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        ConversionError::new(msg)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ConversionError> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = ConversionError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self {
+            Value::Unit => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Int(i) | Value::UnsignedInt(i) => visitor.visit_u64(i),
+            Value::SignedInt(i) => visitor.visit_i64(i),
+            Value::Float(f) => visitor.visit_f64(f),
+            Value::String(s) => visitor.visit_string(s),
+            Value::Tuple(values) => {
+                visitor.visit_seq(de::value::SeqDeserializer::new(values.into_iter()))
+            }
+            Value::Instance { fields, .. } => {
+                visitor.visit_map(de::value::MapDeserializer::new(fields.into_iter()))
+            }
+            value @ Value::Other(_) => Err(ConversionError::expected(
+                "a value that can be converted",
+                &value,
+            )),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self {
+            Value::Unit => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ConversionError> {
+        match self {
+            Value::Tuple(entries) => {
+                let entries = entries
+                    .into_iter()
+                    .map(|entry| match entry {
+                        Value::Tuple(pair) if pair.len() == 2 => {
+                            let mut pair = pair.into_iter();
+                            Ok((pair.next().unwrap(), pair.next().unwrap()))
+                        }
+                        entry => Err(ConversionError::expected("a `(key, value)` pair", &entry)),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                visitor.visit_map(de::value::MapDeserializer::new(entries.into_iter()))
+            }
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        match self {
+            Value::String(variant) => visitor.visit_enum(Enum {
+                variant,
+                fields: None,
+            }),
+            Value::Instance { class, fields } => visitor.visit_enum(Enum {
+                variant: class,
+                fields: Some(fields),
+            }),
+            value => Err(ConversionError::expected("a string or an instance", &value)),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct struct
+        identifier ignored_any
+    }
+}
+
+/// An enum variant: a unit variant (which has no fields) or a struct variant.
+struct Enum {
+    variant: String,
+    fields: Option<Vec<(String, Value)>>,
+}
+
+impl<'de> de::EnumAccess<'de> for Enum {
+    type Error = ConversionError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), ConversionError> {
+        let variant: StringDeserializer<ConversionError> = self.variant.clone().into_deserializer();
+        Ok((seed.deserialize(variant)?, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Enum {
+    type Error = ConversionError;
+
+    fn unit_variant(self) -> Result<(), ConversionError> {
+        match self.fields {
+            None => Ok(()),
+            Some(_) => Err(ConversionError::new(format_args!(
+                "expected the unit variant `{}`, found an instance",
+                self.variant
+            ))),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        _seed: T,
+    ) -> Result<T::Value, ConversionError> {
+        Err(self.unnamed_fields())
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        Err(self.unnamed_fields())
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ConversionError> {
+        match self.fields {
+            Some(fields) => visitor.visit_map(de::value::MapDeserializer::new(fields.into_iter())),
+            None => Err(ConversionError::new(format_args!(
+                "expected an instance of `{}`, found a string",
+                self.variant
+            ))),
+        }
+    }
+}
+
+impl Enum {
+    fn unnamed_fields(&self) -> ConversionError {
+        ConversionError::new(format_args!(
+            "cannot convert to the variant `{}`: Dada has no equivalent of variants with unnamed fields",
+            self.variant
+        ))
+    }
+}
@@ -0,0 +1,299 @@
+//! Embedding Dada in Rust programs.
+//!
+//! A [`Runtime`] holds the source files you have loaded. You can call
+//! any function defined in them by name, passing arguments from Rust
+//! and getting the result back:
+//!
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), dada::Error> {
+//! let mut runtime = dada::Runtime::new();
+//! let diagnostics = runtime.load("add.dada", "fn add(a, b) -> { a + b }");
+//! assert!(diagnostics.is_empty());
+//!
+//! let sum = runtime
+//!     .call("add", vec![dada::Value::Int(1), dada::Value::Int(2)])
+//!     .await?;
+//! assert_eq!(sum, dada::Value::Int(3));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Anything the program prints is collected (see [`Runtime::take_output`]),
+//! unless it is passed to a callback as it is printed (see
+//! [`Runtime::on_output`]).
+//!
+//! Dada code can also call back into Rust: see [`Runtime::register_fn`]
+//! and [`Runtime::register_async_fn`].
+//!
+//! Rust values are converted to and from [`Value`]s with the [`ToDada`]
+//! and [`FromDada`] traits or, for types that implement serde's traits,
+//! with [`to_value`] and [`from_value`].
+
+use std::{future::Future, sync::Arc};
+
+use dada_execute::{host_function::HostFunctions, kernel::BufferKernel};
+use dada_ir::{filename::Filename, function::Function, word::Word};
+
+pub use convert::{ConversionError, FromDada, ToDada};
+pub use dada_execute::{config::ExecutionConfig, HostValue as Value};
+pub use dada_ir::diagnostic::Severity;
+pub use de::from_value;
+pub use ser::to_value;
+
+mod convert;
+mod de;
+mod ser;
+
+/// A set of loaded Dada source files whose functions can be called.
+#[derive(Default)]
+pub struct Runtime {
+    db: dada_db::Db,
+
+    /// Files loaded so far, in order.
+    filenames: Vec<Filename>,
+
+    /// Configuration (e.g., memory limits) used when calling functions.
+    config: ExecutionConfig,
+
+    /// Rust functions that Dada code can call.
+    host_functions: HostFunctions,
+
+    /// Names of the host functions added as intrinsics (see
+    /// [`Self::intrinsic`]).
+    intrinsics: Vec<String>,
+
+    /// Output printed by the program and not yet taken.
+    output: String,
+
+    /// If `Some`, receives each line the program prints instead of
+    /// [`Self::output`].
+    output_callback: Option<Arc<dyn Fn(&str) + Send + Sync>>,
+}
+
+impl Runtime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder method: the configuration to use when calling functions.
+    pub fn config(self, config: ExecutionConfig) -> Self {
+        Self { config, ..self }
+    }
+
+    /// Builder method: adds an intrinsic named `name`, in scope in every
+    /// file, that runs `function`. Unlike a function registered with
+    /// [`Self::register_fn`], an item of the same name in a file shadows
+    /// the intrinsic there.
+    pub fn intrinsic(
+        mut self,
+        name: &str,
+        function: impl Fn(Vec<Value>) -> eyre::Result<Value> + Send + Sync + 'static,
+    ) -> Self {
+        self.host_functions.register(name, function);
+        self.intrinsics.push(name.to_string());
+        self.db = std::mem::take(&mut self.db).with_intrinsics([name]);
+        self.update_host_functions();
+        self
+    }
+
+    /// Loads (or, if `name` was loaded before, replaces) a source file,
+    /// returning any diagnostics the compiler reports for it.
+    pub fn load(&mut self, name: &str, source: impl Into<String>) -> Vec<Diagnostic> {
+        let filename = Filename::from(&self.db, name);
+        self.db.update_file(filename, source.into());
+        if !self.filenames.contains(&filename) {
+            self.filenames.push(filename);
+            self.db
+                .set_host_functions(filename, self.host_function_names());
+        }
+
+        let diagnostics = self.db.diagnostics(filename);
+        diagnostics
+            .iter()
+            .map(|d| Diagnostic::from(&self.db, d))
+            .collect()
+    }
+
+    /// Registers a Rust function that Dada code can call as `name(...)`,
+    /// replacing any earlier function registered with that name. Host
+    /// functions take precedence over intrinsics of the same name.
+    ///
+    /// Register functions before loading the files that call them, so that
+    /// [`Self::load`] doesn't report them as unknown.
+    pub fn register_fn(
+        &mut self,
+        name: &str,
+        function: impl Fn(Vec<Value>) -> eyre::Result<Value> + Send + Sync + 'static,
+    ) {
+        self.host_functions.register(name, function);
+        self.update_host_functions();
+    }
+
+    /// Like [`Self::register_fn`], but calling the function in Dada yields
+    /// a thunk; the Rust future runs when the thunk is awaited.
+    pub fn register_async_fn<F>(
+        &mut self,
+        name: &str,
+        function: impl Fn(Vec<Value>) -> F + Send + Sync + 'static,
+    ) where
+        F: Future<Output = eyre::Result<Value>> + 'static,
+    {
+        self.host_functions.register_async(name, function);
+        self.update_host_functions();
+    }
+
+    /// Passes each line the program prints (with its newline) to
+    /// `callback` as soon as it is printed, rather than collecting it for
+    /// [`Self::take_output`]. Replaces any earlier callback.
+    pub fn on_output(&mut self, callback: impl Fn(&str) + Send + Sync + 'static) {
+        self.output_callback = Some(Arc::new(callback));
+    }
+
+    /// True if a function named `name` is defined in some loaded file.
+    pub fn has_function(&self, name: &str) -> bool {
+        self.function(name).is_some()
+    }
+
+    /// Calls the function named `name` with `arguments`, returning its
+    /// result. If several loaded files define `name`, the one loaded first
+    /// wins.
+    pub async fn call(&mut self, name: &str, arguments: Vec<Value>) -> Result<Value, Error> {
+        let function = self
+            .function(name)
+            .ok_or_else(|| Error::UnknownFunction(name.to_string()))?;
+
+        let config = self
+            .config
+            .clone()
+            .host_functions(self.host_functions.clone());
+        let mut kernel = BufferKernel::new().config(config);
+        if let Some(callback) = self.output_callback.clone() {
+            kernel = kernel.output_callback(move |event| callback(&format!("{}\n", event.text)));
+        }
+        let result = kernel.call(&self.db, function, arguments).await;
+        let output = kernel.take_buffer();
+        if self.output_callback.is_none() {
+            self.output.push_str(&output);
+        }
+
+        result.map_err(
+            |report| match report.downcast::<dada_execute::DiagnosticError>() {
+                Ok(error) => Error::Runtime(Diagnostic::from(&self.db, error.diagnostic())),
+                Err(report) => Error::Other(report),
+            },
+        )
+    }
+
+    /// Takes everything the program has printed since the last call.
+    pub fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
+    fn host_function_names(&self) -> Vec<Word> {
+        let mut names: Vec<&str> = self
+            .host_functions
+            .names()
+            .filter(|name| !self.intrinsics.iter().any(|intrinsic| intrinsic == name))
+            .collect();
+        names.sort_unstable();
+        names
+            .into_iter()
+            .map(|name| Word::from(&self.db, name))
+            .collect()
+    }
+
+    fn update_host_functions(&mut self) {
+        let names = self.host_function_names();
+        for &filename in &self.filenames {
+            self.db.set_host_functions(filename, names.clone());
+        }
+    }
+
+    fn function(&self, name: &str) -> Option<Function> {
+        self.filenames
+            .iter()
+            .find_map(|filename| self.db.function_named(*filename, name))
+    }
+}
+
+/// A message from the compiler or interpreter, such as a type error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+
+    /// Name of the file the diagnostic refers to.
+    pub filename: String,
+
+    /// 1-based line and column where the diagnostic starts.
+    pub line: u32,
+    pub column: u32,
+
+    /// The diagnostic formatted as `dada run` would print it
+    /// (without colors), including labels and source snippets.
+    pub rendered: String,
+}
+
+impl Diagnostic {
+    fn from(db: &dada_db::Db, diagnostic: &dada_ir::diagnostic::Diagnostic) -> Self {
+        let span = diagnostic.span;
+        let start = dada_ir::lines::line_column(db, span.filename, span.start);
+        let rendered = dada_error_format::format_diagnostics_with_options(
+            db,
+            std::slice::from_ref(diagnostic),
+            dada_error_format::FormatOptions::no_color(),
+        )
+        .unwrap_or_else(|_| diagnostic.message.clone());
+        Self {
+            severity: diagnostic.severity,
+            message: diagnostic.message.clone(),
+            filename: span.filename.as_str(db).to_string(),
+            line: start.line1(),
+            column: start.column1(),
+            rendered,
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.rendered)
+    }
+}
+
+/// Why [`Runtime::call`] failed.
+#[derive(Debug)]
+pub enum Error {
+    /// No loaded file defines a function with this name.
+    UnknownFunction(String),
+
+    /// The program reported an error while running.
+    Runtime(Diagnostic),
+
+    /// A value could not be converted to or from Rust.
+    Conversion(ConversionError),
+
+    /// Some other error, such as an argument that can't be passed to Dada.
+    Other(eyre::Report),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownFunction(name) => write!(f, "no function named `{}`", name),
+            Error::Runtime(diagnostic) => write!(f, "{}", diagnostic),
+            Error::Conversion(error) => write!(f, "{}", error),
+            Error::Other(report) => write!(f, "{}", report),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ConversionError> for Error {
+    fn from(error: ConversionError) -> Self {
+        Error::Conversion(error)
+    }
+}
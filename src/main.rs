@@ -1,6 +1,9 @@
 use structopt::StructOpt;
 
+mod allocations;
+
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
+    dada_lang::count_allocations_with(allocations::allocations);
     dada_lang::Options::from_args().main().await
 }
@@ -0,0 +1,345 @@
+//! Converting any `serde::Serialize` type to a Dada [`Value`].
+
+use serde::ser::{self, Serialize, Serializer as _};
+
+use crate::{convert::ConversionError, Value};
+
+/// Converts `value` into a Dada value. Structs become instances of the
+/// class with the same name (and, likewise, struct variants become
+/// instances of the class named after the variant); unit variants become
+/// strings; sequences and tuples become tuples; maps become tuples of
+/// `(key, value)` pairs; and `None` becomes `()`.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, ConversionError> {
+    value.serialize(Serializer)
+}
+
+impl ser::Error for ConversionError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        ConversionError::new(msg)
+    }
+}
+
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    type SerializeSeq = SerializeTuple;
+    type SerializeTuple = SerializeTuple;
+    type SerializeTupleStruct = SerializeTuple;
+    type SerializeTupleVariant = ser::Impossible<Value, ConversionError>;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeInstance;
+    type SerializeStructVariant = SerializeInstance;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, ConversionError> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, ConversionError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, ConversionError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, ConversionError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, ConversionError> {
+        Ok(Value::SignedInt(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, ConversionError> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, ConversionError> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, ConversionError> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, ConversionError> {
+        Ok(Value::UnsignedInt(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, ConversionError> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, ConversionError> {
+        Ok(Value::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, ConversionError> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, ConversionError> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, ConversionError> {
+        Ok(Value::Tuple(
+            v.iter().map(|b| Value::UnsignedInt((*b).into())).collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Value, ConversionError> {
+        Ok(Value::Unit)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, ConversionError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, ConversionError> {
+        Ok(Value::Unit)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Value, ConversionError> {
+        Ok(Value::Instance {
+            class: name.to_string(),
+            fields: vec![],
+        })
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, ConversionError> {
+        Ok(Value::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, ConversionError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<Value, ConversionError> {
+        Err(unnamed_variant_fields(name, variant))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeTuple, ConversionError> {
+        Ok(SerializeTuple {
+            values: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeTuple, ConversionError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeTuple, ConversionError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, ConversionError> {
+        Err(unnamed_variant_fields(name, variant))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap, ConversionError> {
+        Ok(SerializeMap {
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<SerializeInstance, ConversionError> {
+        Ok(SerializeInstance {
+            class: name,
+            fields: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeInstance, ConversionError> {
+        self.serialize_struct(variant, len)
+    }
+}
+
+fn unnamed_variant_fields(name: &str, variant: &str) -> ConversionError {
+    ConversionError::new(format_args!(
+        "cannot convert `{}::{}`: Dada has no equivalent of variants with unnamed fields",
+        name, variant
+    ))
+}
+
+struct SerializeTuple {
+    values: Vec<Value>,
+}
+
+impl SerializeTuple {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        self.values.push(to_value(value)?);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for SerializeTuple {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), ConversionError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(Value::Tuple(self.values))
+    }
+}
+
+impl ser::SerializeTuple for SerializeTuple {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), ConversionError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(Value::Tuple(self.values))
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeTuple {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(Value::Tuple(self.values))
+    }
+}
+
+struct SerializeMap {
+    entries: Vec<Value>,
+
+    /// The key whose value is about to be serialized.
+    key: Option<Value>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), ConversionError> {
+        self.key = Some(to_value(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConversionError> {
+        let key = self
+            .key
+            .take()
+            .expect("`serialize_value` called before `serialize_key`");
+        self.entries.push(Value::Tuple(vec![key, to_value(value)?]));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(Value::Tuple(self.entries))
+    }
+}
+
+struct SerializeInstance {
+    class: &'static str,
+    fields: Vec<(String, Value)>,
+}
+
+impl SerializeInstance {
+    fn field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ConversionError> {
+        self.fields.push((key.to_string(), to_value(value)?));
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        Value::Instance {
+            class: self.class.to_string(),
+            fields: self.fields,
+        }
+    }
+}
+
+impl ser::SerializeStruct for SerializeInstance {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ConversionError> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeInstance {
+    type Ok = Value;
+    type Error = ConversionError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ConversionError> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Value, ConversionError> {
+        Ok(self.finish())
+    }
+}
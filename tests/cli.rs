@@ -0,0 +1,171 @@
+//! Runs the `dada` binary the way a user would.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+const HELLO: &str = "async fn main() {\n    print(\"hi\").await\n}\n";
+
+/// Writes `source` to a fresh file named `name` in a temporary directory.
+fn source_file(name: &str, source: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dada-cli-{}-{}", std::process::id(), name));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, source).unwrap();
+    path
+}
+
+fn dada(args: &[&std::ffi::OsStr]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_dada"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn check_file() {
+    let path = source_file("check.dada", HELLO);
+    let output = dada(&["check".as_ref(), path.as_os_str()]);
+    assert!(output.status.success(), "{:?}", output);
+}
+
+#[test]
+fn run_file() {
+    let path = source_file("run.dada", HELLO);
+    let output = dada(&["run".as_ref(), path.as_os_str()]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hi\n");
+}
+
+#[test]
+fn repl_runs_each_statement_once() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dada"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"x = 1\nprint(\"hi\").await\nx + 1\n:quit\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("hi\n").count(), 1, "{}", stdout);
+    assert!(stdout.contains("2\n"), "{}", stdout);
+}
+
+const TEST_FNS: &str = "\
+class Point(any x, any y)
+
+async fn test_passes() {
+    print(\"hi\").await
+}
+
+async fn test_fails() {
+    any p = Point(x: 22, y: 44)
+    any q = p.lease
+    x = p.x
+    x = q.x
+}
+";
+
+#[test]
+fn test_fns() {
+    let path = source_file("test_fns.dada", TEST_FNS);
+    let output = dada(&["test-fns".as_ref(), path.as_os_str()]);
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("::test_passes ... ok"), "{}", stderr);
+    assert!(stderr.contains("::test_fails ... FAILED"), "{}", stderr);
+    assert!(
+        stderr.contains("lease to this object was cancelled"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("1 passed, 1 failed, 0 filtered out"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_fns_filter() {
+    let path = source_file("test_fns_filter.dada", TEST_FNS);
+    let output = dada(&[
+        "test-fns".as_ref(),
+        "--filter".as_ref(),
+        "passes".as_ref(),
+        path.as_os_str(),
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("test_fails"), "{}", stderr);
+    assert!(
+        stderr.contains("1 passed, 0 failed, 1 filtered out"),
+        "{}",
+        stderr
+    );
+}
+
+const FFI: &str = "\
+extern \"libm.so.6\" fn cos(x: f64) -> f64
+extern \"libc.so.6\" fn strnlen(s: bytes) -> u64
+
+async fn main() {
+    print(cos(0.0)).await
+    print(strnlen(\"hello\")).await
+    print(strnlen((104, 0, 105))).await
+}
+";
+
+/// `bytes` are passed with their length, so `strnlen` stops at the end of
+/// `"hello"` even though nothing NUL-terminates it.
+#[test]
+#[cfg(target_os = "linux")]
+fn run_extern_functions() {
+    let path = source_file("ffi.dada", FFI);
+    let output = dada(&[
+        "run".as_ref(),
+        "--allow".as_ref(),
+        "ffi".as_ref(),
+        path.as_os_str(),
+    ]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n5_u\n1_u\n");
+}
+
+const NO_MAIN: &str = "\
+# A program whose function isn't named `main`.
+
+async fn start() {
+    print(\"hi\").await
+}
+";
+
+/// The missing `main` (E0046) is reported at the name of the file's first
+/// item, and is the only error.
+#[test]
+fn run_file_without_main() {
+    let path = source_file("no_main.dada", NO_MAIN);
+    let output = dada(&[
+        "--color".as_ref(),
+        "never".as_ref(),
+        "run".as_ref(),
+        path.as_os_str(),
+    ]);
+    assert!(!output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.matches("[E0").count(), 1, "{}", stderr);
+    assert!(stderr.contains("[E0046]"), "{}", stderr);
+    assert!(
+        stderr.contains("there is no function named `main` to run"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("no_main.dada:3:10"), "{}", stderr);
+}
@@ -0,0 +1,185 @@
+//! Converts Rust values to Dada values and back, with `ToDada`/`FromDada`
+//! and with serde.
+
+use std::collections::{BTreeMap, HashMap};
+
+use dada::{from_value, to_value, ConversionError, FromDada, ToDada, Value};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Point {
+    x: i64,
+    y: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Path {
+    name: String,
+    points: Vec<Point>,
+    closed: Option<bool>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Empty,
+    Circle { radius: f64 },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Wrapped {
+    Number(i64),
+}
+
+/// Converts `value` with `to_value` and back with `from_value`.
+fn round_trip<T>(value: &T) -> T
+where
+    T: Serialize + serde::de::DeserializeOwned,
+{
+    from_value(to_value(value).unwrap()).unwrap()
+}
+
+#[test]
+fn struct_becomes_instance() {
+    let point = Point { x: -22, y: 44 };
+    assert_eq!(
+        to_value(&point).unwrap(),
+        Value::Instance {
+            class: "Point".to_string(),
+            fields: vec![
+                ("x".to_string(), Value::SignedInt(-22)),
+                ("y".to_string(), Value::UnsignedInt(44)),
+            ],
+        }
+    );
+    assert_eq!(round_trip(&point), point);
+}
+
+#[test]
+fn nested_struct_round_trips() {
+    let path = Path {
+        name: "triangle".to_string(),
+        points: vec![
+            Point { x: 0, y: 0 },
+            Point { x: 22, y: 0 },
+            Point { x: 0, y: 44 },
+        ],
+        closed: Some(true),
+    };
+    assert_eq!(round_trip(&path), path);
+
+    let open = Path {
+        closed: None,
+        ..path
+    };
+    assert_eq!(round_trip(&open), open);
+}
+
+#[test]
+fn fields_can_be_in_any_order() {
+    let value = Value::Instance {
+        class: "Point".to_string(),
+        fields: vec![
+            ("y".to_string(), Value::Int(44)),
+            ("x".to_string(), Value::Int(22)),
+        ],
+    };
+    assert_eq!(from_value::<Point>(value).unwrap(), Point { x: 22, y: 44 });
+}
+
+#[test]
+fn enum_variants() {
+    assert_eq!(
+        to_value(&Shape::Empty).unwrap(),
+        Value::String("Empty".to_string())
+    );
+    assert_eq!(
+        to_value(&Shape::Circle { radius: 1.5 }).unwrap(),
+        Value::Instance {
+            class: "Circle".to_string(),
+            fields: vec![("radius".to_string(), Value::Float(1.5))],
+        }
+    );
+    assert_eq!(round_trip(&Shape::Empty), Shape::Empty);
+    assert_eq!(
+        round_trip(&Shape::Circle { radius: 1.5 }),
+        Shape::Circle { radius: 1.5 }
+    );
+}
+
+#[test]
+fn map_becomes_tuple_of_pairs() {
+    let map: BTreeMap<String, u32> = [("a".to_string(), 1), ("b".to_string(), 2)]
+        .into_iter()
+        .collect();
+    let value = Value::Tuple(vec![
+        Value::Tuple(vec![Value::String("a".to_string()), Value::UnsignedInt(1)]),
+        Value::Tuple(vec![Value::String("b".to_string()), Value::UnsignedInt(2)]),
+    ]);
+    assert_eq!(to_value(&map).unwrap(), value);
+    assert_eq!(map.to_dada(), value);
+    assert_eq!(round_trip(&map), map);
+    assert_eq!(BTreeMap::<String, u32>::from_dada(value).unwrap(), map);
+}
+
+#[test]
+fn to_dada_round_trips() {
+    let numbers = vec![1_i32, -2, 3];
+    assert_eq!(
+        numbers.to_dada(),
+        Value::Tuple(vec![
+            Value::SignedInt(1),
+            Value::SignedInt(-2),
+            Value::SignedInt(3),
+        ])
+    );
+    assert_eq!(Vec::<i32>::from_dada(numbers.to_dada()).unwrap(), numbers);
+
+    let pair = (22_u8, "hi".to_string());
+    assert_eq!(<(u8, String)>::from_dada(pair.to_dada()).unwrap(), pair);
+
+    let map: HashMap<String, bool> = [("yes".to_string(), true)].into_iter().collect();
+    assert_eq!(
+        HashMap::<String, bool>::from_dada(map.to_dada()).unwrap(),
+        map
+    );
+
+    assert_eq!(None::<i64>.to_dada(), Value::Unit);
+    assert_eq!(Option::<i64>::from_dada(Value::Unit).unwrap(), None);
+    assert_eq!(Option::<i64>::from_dada(Value::Int(7)).unwrap(), Some(7));
+}
+
+/// The message of the error that `result` should be.
+fn error<T: std::fmt::Debug>(result: Result<T, ConversionError>) -> String {
+    result.unwrap_err().to_string()
+}
+
+#[test]
+fn conversion_errors() {
+    assert_eq!(
+        error(bool::from_dada(Value::Int(1))),
+        "expected a boolean, found `1`"
+    );
+    assert_eq!(
+        error(u8::from_dada(Value::Int(300))),
+        "`300` does not fit in `u8`"
+    );
+    assert_eq!(
+        error(<(i64, i64)>::from_dada(Value::Tuple(vec![Value::Int(1)]))),
+        "expected a tuple of 2 elements, found `(1)`"
+    );
+    assert_eq!(
+        error(BTreeMap::<String, u32>::from_dada(Value::Tuple(vec![
+            Value::Int(1)
+        ]))),
+        "expected a tuple of 2 elements, found `1`"
+    );
+    assert_eq!(
+        error(to_value(&Wrapped::Number(1))),
+        "cannot convert `Wrapped::Number`: Dada has no equivalent of variants with unnamed fields"
+    );
+    assert_eq!(
+        error(from_value::<Wrapped>(Value::String("Number".to_string()))),
+        "cannot convert to the variant `Number`: Dada has no equivalent of variants with unnamed fields"
+    );
+    assert!(from_value::<Point>(Value::Int(1)).is_err());
+}
@@ -0,0 +1,141 @@
+//! Counts how often each phase runs for a function: when an edit elsewhere
+//! in its file moves it, which should not make it run again, and when a
+//! program is run, which should only brew the functions it calls.
+
+use std::sync::{Arc, Mutex};
+
+use dada_ir::{filename::Filename, item::Item};
+use tracing_subscriber::fmt::format::FmtSpan;
+
+const BEFORE: &str = "\
+fn first() {
+    1
+}
+
+fn second() {
+    x = 22
+    x + 44
+}
+";
+
+/// `BEFORE` with a longer literal in `first`, which moves `second`.
+const AFTER: &str = "\
+fn first() {
+    1000
+}
+
+fn second() {
+    x = 22
+    x + 44
+}
+";
+
+const CALLS: &str = "\
+async fn main() {
+    print(helper()).await
+}
+
+fn helper() -> {
+    22
+}
+
+fn unreachable() -> {
+    44
+}
+";
+
+/// Checks and brews every function in `filename`.
+fn compile(db: &dada_db::Db, filename: Filename) {
+    db.diagnostics(filename);
+    for item in db.items(filename) {
+        if let Item::Function(_) = item {
+            db.debug_bir(item);
+        }
+    }
+}
+
+/// Runs `f` and returns the phase spans it created, one per line.
+fn phases(f: impl FnOnce()) -> String {
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let writer = output.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(dada_ir::phase::TARGET)
+        .with_span_events(FmtSpan::NEW)
+        .with_target(false)
+        .with_ansi(false)
+        .with_writer(move || Writer(writer.clone()))
+        .finish();
+    tracing::subscriber::with_default(subscriber, f);
+    let output = output.lock().unwrap();
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+struct Writer(Arc<Mutex<Vec<u8>>>);
+
+impl std::io::Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// How many times `phase` ran for `item`. Each line names the spans the
+/// new one was created within before the new one, so only the last counts.
+fn runs(phases: &str, phase: &str, item: &str) -> usize {
+    phases
+        .lines()
+        .filter_map(|line| {
+            let (spans, _) = line.rsplit_once("}: ")?;
+            let (name, fields) = spans.rsplit_once('{')?;
+            let name = name.rsplit([' ', ':']).next()?;
+            Some(name == phase && fields.contains(item))
+        })
+        .filter(|&counts| counts)
+        .count()
+}
+
+#[test]
+fn moving_a_function_does_not_recompile_it() {
+    let mut db = dada_db::Db::default();
+    let filename = Filename::from(&db, "incremental.dada");
+    db.update_file(filename, BEFORE.to_string());
+    let before = phases(|| compile(&db, filename));
+    for phase in ["parse", "validate", "brew"] {
+        assert_eq!(runs(&before, phase, "second"), 1, "{phase}: {before}");
+    }
+
+    db.update_file(filename, AFTER.to_string());
+    let after = phases(|| compile(&db, filename));
+    for phase in ["parse", "validate", "brew"] {
+        assert_eq!(runs(&after, phase, "first"), 1, "{phase}: {after}");
+        assert_eq!(runs(&after, phase, "second"), 0, "{phase}: {after}");
+    }
+}
+
+#[test]
+fn running_a_program_brews_only_what_it_calls() {
+    let mut db = dada_db::Db::default();
+    let filename = Filename::from(&db, "lazy.dada");
+    db.update_file(filename, CALLS.to_string());
+    let main = db.function_named(filename, "main").unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let mut kernel = dada_execute::kernel::BufferKernel::new();
+
+    // As `dada run` does: check the whole program, then run `main`.
+    let run = phases(|| {
+        assert!(db.diagnostics(filename).is_empty());
+        runtime.block_on(kernel.interpret_and_buffer(&db, main, vec![]));
+    });
+    assert_eq!(kernel.buffer(), "22\n");
+    for item in ["main", "helper", "unreachable"] {
+        assert_eq!(runs(&run, "validate", item), 1, "{item}: {run}");
+    }
+    assert_eq!(runs(&run, "brew", "main"), 1, "{run}");
+    assert_eq!(runs(&run, "brew", "helper"), 1, "{run}");
+    assert_eq!(runs(&run, "brew", "unreachable"), 0, "{run}");
+}
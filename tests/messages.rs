@@ -0,0 +1,61 @@
+//! Checks the message packs against the compiler: each message that a pack
+//! translates must be one that an `error!` is written with, under the same
+//! code, or the translation would never be shown.
+
+use std::path::Path;
+
+/// The code and message of each `error!` in the `.rs` files under `dir`,
+/// like `("UnresolvedName", "can't find anything named `{}`")`.
+fn error_messages(dir: &Path, messages: &mut Vec<(String, String)>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            error_messages(&path, messages);
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            let source = std::fs::read_to_string(&path).unwrap();
+            let mut rest = &source[..];
+            while let Some(start) = rest.find("error!(") {
+                rest = &rest[start + "error!(".len()..];
+                messages.extend(error_message(rest));
+            }
+        }
+    }
+}
+
+/// The code and message of `error!(Code, span, "message", args...)`, given
+/// what follows the `error!(`.
+fn error_message(invocation: &str) -> Option<(String, String)> {
+    let (name, rest) = invocation.split_once(',')?;
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(char::is_alphanumeric) {
+        return None;
+    }
+    let (_, rest) = rest.split_once('"')?;
+    let (message, _) = rest.split_once('"')?;
+    Some((name.to_string(), message.to_string()))
+}
+
+#[test]
+fn every_translation_is_of_an_error_message() {
+    let mut messages = vec![];
+    let components = Path::new(env!("CARGO_MANIFEST_DIR")).join("components");
+    error_messages(&components, &mut messages);
+    assert!(!messages.is_empty());
+
+    for locale in dada_ir::messages::locales() {
+        let Some(catalog) = dada_ir::messages::catalog(locale) else {
+            continue;
+        };
+        for (code, message) in catalog.messages() {
+            let name = format!("{:?}", code);
+            assert!(
+                messages.iter().any(|(n, m)| *n == name && m == message),
+                "the `{}` pack translates `{}` under {}, but no `error!({}, ...)` is written with it",
+                locale,
+                message,
+                code.as_str(),
+                name,
+            );
+        }
+    }
+}
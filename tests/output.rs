@@ -0,0 +1,40 @@
+//! Checks the output events that `print` emits, which is how the
+//! playground links each line of output to the call that printed it.
+
+use dada_execute::kernel::BufferKernel;
+use dada_ir::filename::Filename;
+
+const PROGRAM: &str = "\
+async fn main() {
+    print(\"hi\").await
+    x = 22
+    print(x + 1).await
+}
+";
+
+#[tokio::test]
+async fn output_events() {
+    let mut db = dada_db::Db::default();
+    let filename = Filename::from(&db, "output.dada");
+    db.update_file(filename, PROGRAM);
+    let main = db.function_named(filename, "main").unwrap();
+    let mut kernel = BufferKernel::new();
+    kernel.interpret_and_buffer(&db, main, vec![]).await;
+    assert_eq!(kernel.buffer(), "hi\n23\n");
+
+    let events: Vec<(String, &str)> = kernel
+        .take_output_events()
+        .into_iter()
+        .map(|event| {
+            assert_eq!(event.span.filename, filename);
+            (event.text, event.span.snippet(&db))
+        })
+        .collect();
+    assert_eq!(
+        events,
+        [
+            ("hi".to_string(), "print(\"hi\")"),
+            ("23".to_string(), "print(x + 1)"),
+        ]
+    );
+}
@@ -0,0 +1,174 @@
+//! Checks the peephole pass that removes the temporaries holding literals
+//! from brewed BIR: on BIR built by hand, whose statements afterward are
+//! known exactly, and on a brewed function with and without a breakpoint.
+
+use dada_brew::{peephole::remove_temporaries, prelude::*};
+use dada_ir::{code::bir, filename::Filename, span::LineColumn, storage::Atomic, word::Word};
+
+/// Tables for one basic block, built up a statement at a time.
+struct Block {
+    tables: bir::Tables,
+    statements: Vec<bir::Statement>,
+    temp: bir::LocalVariable,
+    literal: bir::Expr,
+}
+
+impl Block {
+    fn new() -> Self {
+        let mut tables = bir::Tables::default();
+        let temp = tables.add(bir::LocalVariableData {
+            name: None,
+            specifier: None,
+            atomic: Atomic::No,
+        });
+        let literal = tables.add(bir::ExprData::IntegerLiteral(22));
+        Block {
+            tables,
+            statements: vec![],
+            temp,
+            literal,
+        }
+    }
+
+    /// A variable named `name`, to assign to.
+    fn target(&mut self, db: &dada_db::Db, name: &str) -> bir::TargetPlace {
+        let variable = self.tables.add(bir::LocalVariableData {
+            name: Some(Word::from(db, name)),
+            specifier: None,
+            atomic: Atomic::No,
+        });
+        self.tables
+            .add(bir::TargetPlaceData::LocalVariable(variable))
+    }
+
+    fn push(&mut self, data: bir::StatementData) -> bir::StatementData {
+        self.statements.push(self.tables.add(data.clone()));
+        data
+    }
+
+    /// `temp = 22`
+    fn assign_literal(&mut self) -> bir::StatementData {
+        let target = self
+            .tables
+            .add(bir::TargetPlaceData::LocalVariable(self.temp));
+        self.push(bir::StatementData::AssignExpr(target, self.literal))
+    }
+
+    /// `target = temp.give`
+    fn give_temp_to(&mut self, target: bir::TargetPlace) -> bir::StatementData {
+        let temp = self.tables.add(bir::PlaceData::LocalVariable(self.temp));
+        let give = self.tables.add(bir::ExprData::Give(temp));
+        self.push(bir::StatementData::AssignExpr(target, give))
+    }
+
+    /// `clear(temp)`
+    fn clear_temp(&mut self) -> bir::StatementData {
+        self.push(bir::StatementData::Clear(self.temp))
+    }
+
+    /// Ends the block (with a terminator that reads nothing, so that the
+    /// statements are the only uses of `temp`), runs the pass, and returns
+    /// the statements left in the block.
+    fn remove_temporaries(mut self) -> Vec<bir::StatementData> {
+        let terminator = self.tables.add(bir::TerminatorData::Error);
+        let block = self.tables.add(bir::BasicBlockData {
+            statements: self.statements,
+            terminator,
+        });
+        remove_temporaries(&mut self.tables);
+        self.tables[block]
+            .statements
+            .iter()
+            .map(|&statement| self.tables[statement].clone())
+            .collect()
+    }
+}
+
+#[test]
+fn unused_literal() {
+    // temp = 22; clear(temp)
+    let mut block = Block::new();
+    block.assign_literal();
+    block.clear_temp();
+    assert_eq!(block.remove_temporaries(), []);
+}
+
+#[test]
+fn literal_given_once() {
+    // temp = 22; x = temp.give; clear(temp)  =>  x = 22
+    let db = dada_db::Db::default();
+    let mut block = Block::new();
+    let x = block.target(&db, "x");
+    block.assign_literal();
+    block.give_temp_to(x);
+    block.clear_temp();
+    let literal = block.literal;
+    assert_eq!(
+        block.remove_temporaries(),
+        [bir::StatementData::AssignExpr(x, literal)]
+    );
+}
+
+#[test]
+fn literal_given_twice() {
+    // temp = 22; x = temp.give; y = temp.give; clear(temp) is left alone,
+    // since `temp` is read twice.
+    let db = dada_db::Db::default();
+    let mut block = Block::new();
+    let (x, y) = (block.target(&db, "x"), block.target(&db, "y"));
+    let statements = vec![
+        block.assign_literal(),
+        block.give_temp_to(x),
+        block.give_temp_to(y),
+        block.clear_temp(),
+    ];
+    assert_eq!(block.remove_temporaries(), statements);
+}
+
+const ASSIGNMENTS: &str = "\
+fn assignments() {
+    x = 22
+    y = 44
+}
+";
+
+/// How many times, in the BIR of `function`, a temporary is assigned a
+/// literal (like the `()` that each assignment evaluates to) and then
+/// cleared by the next statement.
+fn unused_literals(db: &dada_db::Db, function: dada_ir::function::Function) -> usize {
+    let bir = function.brew(db);
+    let data = bir.data(db);
+    let tables = &data.tables;
+    data.all_basic_blocks()
+        .flat_map(|block| tables[block].statements.windows(2))
+        .filter(|pair| {
+            let (bir::StatementData::AssignExpr(target, expr), bir::StatementData::Clear(cleared)) =
+                (&tables[pair[0]], &tables[pair[1]])
+            else {
+                return false;
+            };
+            tables[*target] == bir::TargetPlaceData::LocalVariable(*cleared)
+                && tables[*cleared].name.is_none()
+                && matches!(
+                    tables[*expr],
+                    bir::ExprData::Unit | bir::ExprData::IntegerLiteral(_)
+                )
+        })
+        .count()
+}
+
+#[test]
+fn brewed_with_and_without_breakpoints() {
+    let mut db = dada_db::Db::default();
+    let filename = Filename::from(&db, "peephole.dada");
+    db.update_file(filename, ASSIGNMENTS.to_string());
+    let function = db.function_named(filename, "assignments").unwrap();
+
+    // The heap graph at a breakpoint shows the temporaries, so with one
+    // the function is left as brewed.
+    db.set_breakpoints(filename, vec![LineColumn::new0(1u32, 8u32)]);
+    assert!(unused_literals(&db, function) > 0);
+
+    db.set_breakpoints(filename, vec![]);
+    assert_eq!(unused_literals(&db, function), 0);
+}
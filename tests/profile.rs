@@ -0,0 +1,145 @@
+//! Profiles a small program through `dada run` and checks the parts of each
+//! report that don't depend on how long the steps took, and that with
+//! `--deterministic` the reports are the same from run to run.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const PROGRAM: &str = "\
+async fn main() {
+    print(add(22, 44)).await
+}
+
+fn add(a, b) -> {
+    a + b
+}
+";
+
+/// Writes [`PROGRAM`] to `name` in a fresh temporary directory.
+fn program(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dada-profile-{}-{}", std::process::id(), name));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, PROGRAM).unwrap();
+    path
+}
+
+fn dada_run(args: &[&std::ffi::OsStr]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_dada"))
+        .arg("run")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "66\n");
+    output
+}
+
+/// The last column of each row in the section of the profile report
+/// that starts with `heading`, sorted.
+fn report_section(report: &str, heading: &str) -> Vec<String> {
+    let mut rows: Vec<String> = report
+        .lines()
+        .skip_while(|line| *line != heading)
+        .skip(2)
+        .take_while(|line| !line.is_empty())
+        .map(|line| line.rsplit(' ').next().unwrap().to_string())
+        .collect();
+    rows.sort();
+    rows
+}
+
+#[test]
+fn profile_report() {
+    let path = program("profile.dada");
+    let output = dada_run(&["--profile".as_ref(), path.as_os_str()]);
+    let report = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        report_section(&report, "hot functions:"),
+        ["add", "main"],
+        "{}",
+        report
+    );
+    let lines = report_section(&report, "hot lines:");
+    for line in [2, 6] {
+        let location = format!("profile.dada:{}", line);
+        assert!(lines.iter().any(|l| l.ends_with(&location)), "{}", report);
+    }
+}
+
+#[test]
+fn flamegraph() {
+    let path = program("flamegraph.dada");
+    let folded = path.with_extension("folded");
+    dada_run(&[
+        "--flamegraph".as_ref(),
+        folded.as_os_str(),
+        path.as_os_str(),
+    ]);
+    let folded = std::fs::read_to_string(folded).unwrap();
+    let stacks: Vec<&str> = folded
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().0)
+        .collect();
+    assert_eq!(stacks, ["main", "main;add"], "{}", folded);
+}
+
+#[test]
+fn chrome_trace() {
+    let path = program("chrome_trace.dada");
+    let trace = path.with_extension("json");
+    dada_run(&[
+        "--chrome-trace".as_ref(),
+        trace.as_os_str(),
+        path.as_os_str(),
+    ]);
+    let trace = std::fs::read_to_string(trace).unwrap();
+    assert!(trace.starts_with("{\"traceEvents\":["), "{}", trace);
+
+    // Everything up to the timestamp, which is all that doesn't change
+    // from run to run.
+    let events: Vec<&str> = trace
+        .lines()
+        .filter(|line| line.starts_with("{\"name\""))
+        .map(|line| line.split_once(",\"ts\"").unwrap().0)
+        .collect();
+    assert_eq!(
+        events,
+        [
+            r#"{"name":"main","cat":"function","ph":"B""#,
+            r#"{"name":"add","cat":"function","ph":"B""#,
+            r#"{"name":"add","cat":"function","ph":"E""#,
+            r#"{"name":"print","cat":"await","ph":"B""#,
+            r#"{"name":"print","cat":"await","ph":"E""#,
+            r#"{"name":"main","cat":"function","ph":"E""#,
+        ],
+        "{}",
+        trace
+    );
+}
+
+#[test]
+fn deterministic() {
+    let path = program("deterministic.dada");
+    let trace = path.with_extension("json");
+    let run = || {
+        let output = dada_run(&[
+            "--deterministic".as_ref(),
+            "--profile".as_ref(),
+            "--chrome-trace".as_ref(),
+            trace.as_os_str(),
+            path.as_os_str(),
+        ]);
+        (output.stderr, std::fs::read(&trace).unwrap())
+    };
+    let (report, events) = run();
+    let (report_again, events_again) = run();
+    assert_eq!(
+        String::from_utf8_lossy(&report),
+        String::from_utf8_lossy(&report_again)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&events),
+        String::from_utf8_lossy(&events_again)
+    );
+}
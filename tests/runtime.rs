@@ -0,0 +1,158 @@
+//! Embeds Dada with `dada::Runtime`: loading files, calling their
+//! functions, and adding intrinsics from Rust.
+
+use dada::{Error, Runtime, Value};
+
+#[tokio::test]
+async fn call() {
+    let mut runtime = Runtime::new();
+    let diagnostics = runtime.load("add.dada", "fn add(a, b) -> { a + b }");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let sum = runtime
+        .call("add", vec![Value::Int(1), Value::Int(2)])
+        .await
+        .unwrap();
+    assert_eq!(sum, Value::Int(3));
+}
+
+#[tokio::test]
+async fn unknown_function() {
+    let mut runtime = Runtime::new();
+    runtime.load("add.dada", "fn add(a, b) -> { a + b }");
+    match runtime.call("subtract", vec![]).await {
+        Err(Error::UnknownFunction(name)) => assert_eq!(name, "subtract"),
+        result => panic!("expected an unknown function, found {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn runtime_error() {
+    let mut runtime = Runtime::new();
+    runtime.load("divide.dada", "fn divide(a, b) -> {\n    a / b\n}\n");
+    match runtime
+        .call("divide", vec![Value::Int(1), Value::Int(0)])
+        .await
+    {
+        Err(Error::Runtime(diagnostic)) => {
+            assert_eq!(diagnostic.message, "divide by zero");
+            assert_eq!(diagnostic.filename, "divide.dada");
+            assert_eq!((diagnostic.line, diagnostic.column), (2, 7));
+        }
+        result => panic!("expected a runtime error, found {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn output() {
+    let mut runtime = Runtime::new();
+    runtime.load(
+        "greet.dada",
+        "async fn greet() {\n    print(\"hi\").await\n    print(\"there\").await\n}\n",
+    );
+    let result = runtime.call("greet", vec![]).await.unwrap();
+    assert_eq!(result, Value::Unit);
+    assert_eq!(runtime.take_output(), "hi\nthere\n");
+    assert_eq!(runtime.take_output(), "");
+}
+
+#[tokio::test]
+async fn reload() {
+    let mut runtime = Runtime::new();
+    runtime.load("answer.dada", "fn answer() -> { 22 }");
+    let answer = runtime.call("answer", vec![]).await.unwrap();
+    assert_eq!(answer, Value::Int(22));
+
+    let diagnostics = runtime.load("answer.dada", "fn answer() -> { 44 }");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let answer = runtime.call("answer", vec![]).await.unwrap();
+    assert_eq!(answer, Value::Int(44));
+}
+
+#[tokio::test]
+async fn sync_host_function() {
+    let mut runtime = Runtime::new();
+    runtime.register_fn("triple", |arguments| match arguments[..] {
+        [Value::Int(i)] => Ok(Value::Int(i * 3)),
+        _ => eyre::bail!("expected an integer, found {:?}", arguments),
+    });
+    let diagnostics = runtime.load("triple.dada", "fn main() -> { triple(14) }");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let result = runtime.call("main", vec![]).await.unwrap();
+    assert_eq!(result, Value::Int(42));
+}
+
+#[tokio::test]
+async fn async_host_function() {
+    let mut runtime = Runtime::new();
+    runtime.register_async_fn("fetch", |arguments| async move {
+        match arguments[..] {
+            [Value::Int(i)] => Ok(Value::String(format!("item {}", i))),
+            _ => eyre::bail!("expected an integer, found {:?}", arguments),
+        }
+    });
+    let diagnostics = runtime.load("fetch.dada", "async fn main() -> { fetch(7).await }");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let result = runtime.call("main", vec![]).await.unwrap();
+    assert_eq!(result, Value::String("item 7".to_string()));
+}
+
+#[tokio::test]
+async fn host_function_error() {
+    let mut runtime = Runtime::new();
+    runtime.register_fn("fail", |_| eyre::bail!("out of luck"));
+    runtime.load("fail.dada", "fn main() -> {\n    fail()\n}\n");
+    match runtime.call("main", vec![]).await {
+        Err(Error::Runtime(diagnostic)) => {
+            assert_eq!(diagnostic.message, "out of luck");
+            assert_eq!((diagnostic.line, diagnostic.column), (2, 5));
+        }
+        result => panic!("expected a runtime error, found {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn unknown_host_function() {
+    let mut runtime = Runtime::new();
+    let diagnostics = runtime.load("unknown.dada", "fn main() -> { fetch(7) }");
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].message, "can't find anything named `fetch`");
+}
+
+fn double(arguments: Vec<Value>) -> eyre::Result<Value> {
+    match arguments[..] {
+        [Value::Int(i)] => Ok(Value::Int(i * 2)),
+        _ => eyre::bail!("expected an integer, found {:?}", arguments),
+    }
+}
+
+#[tokio::test]
+async fn call_added_intrinsic() {
+    let mut runtime = Runtime::new().intrinsic("double", double);
+    let diagnostics = runtime.load("call.dada", "fn main() -> { double(21) }");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let result = runtime.call("main", vec![]).await.unwrap();
+    assert_eq!(result, Value::Int(42));
+}
+
+#[tokio::test]
+async fn intrinsic_added_after_loading() {
+    let mut runtime = Runtime::new();
+    runtime.load("call.dada", "fn main() -> { double(21) }");
+    let mut runtime = runtime.intrinsic("double", double);
+    let diagnostics = runtime.load("other.dada", "fn other() -> { 0 }");
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let result = runtime.call("main", vec![]).await.unwrap();
+    assert_eq!(result, Value::Int(42));
+}
+
+#[tokio::test]
+async fn item_shadows_intrinsic() {
+    let mut runtime = Runtime::new().intrinsic("double", double);
+    let diagnostics = runtime.load(
+        "shadow.dada",
+        "fn double(x) -> { x + 1 }\nfn main() -> { double(21) }",
+    );
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let result = runtime.call("main", vec![]).await.unwrap();
+    assert_eq!(result, Value::Int(22));
+}
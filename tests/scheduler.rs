@@ -0,0 +1,92 @@
+//! Runs programs with schedulers of our own, as an embedder would, and
+//! checks which tasks the interpreter hands them.
+
+use dada_execute::{
+    kernel::BufferKernel,
+    scheduler::{Scheduler, Task},
+};
+use dada_ir::filename::Filename;
+
+const PRINT_TWICE: &str = "\
+async fn main() {
+    print(\"a\").await
+    print(\"b\").await
+}
+";
+
+const COUNT: &str = "\
+async fn main() {
+    i = 0
+    while i < 10000 {
+        i += 1
+    }
+    print(i).await
+}
+";
+
+/// Counts the tasks it runs and the times it is asked to yield.
+#[derive(Default)]
+struct CountingScheduler {
+    tasks: usize,
+    yields: usize,
+}
+
+#[async_trait::async_trait(?Send)]
+impl Scheduler for CountingScheduler {
+    async fn run<'a>(&mut self, task: Task<'a>) -> eyre::Result<()> {
+        self.tasks += 1;
+        task.await
+    }
+
+    async fn yield_now(&mut self) {
+        self.yields += 1;
+    }
+}
+
+/// Fails each task instead of running it.
+struct RefusingScheduler;
+
+#[async_trait::async_trait(?Send)]
+impl Scheduler for RefusingScheduler {
+    async fn run<'a>(&mut self, _task: Task<'a>) -> eyre::Result<()> {
+        eyre::bail!("task refused")
+    }
+}
+
+/// Runs the `main` function of `source` with `scheduler` and returns
+/// what it printed.
+async fn run(source: &str, scheduler: &mut dyn Scheduler) -> String {
+    let mut db = dada_db::Db::default();
+    let filename = Filename::from(&db, "scheduler.dada");
+    db.update_file(filename, source.to_string());
+    let main = db.function_named(filename, "main").unwrap();
+    let mut kernel = BufferKernel::new();
+    kernel
+        .interpret_and_buffer_with_scheduler(&db, main, scheduler, vec![])
+        .await;
+    kernel.take_buffer()
+}
+
+#[tokio::test]
+async fn each_await_is_a_task() {
+    let mut scheduler = CountingScheduler::default();
+    let output = run(PRINT_TWICE, &mut scheduler).await;
+    assert_eq!(output, "a\nb\n");
+    assert_eq!(scheduler.tasks, 2);
+}
+
+#[tokio::test]
+async fn long_runs_yield() {
+    let mut scheduler = CountingScheduler::default();
+    let output = run(COUNT, &mut scheduler).await;
+    assert_eq!(output, "10000\n");
+    assert_eq!(scheduler.tasks, 1);
+    assert!(scheduler.yields > 0);
+}
+
+#[tokio::test]
+async fn failed_task_stops_the_program() {
+    let output = run(PRINT_TWICE, &mut RefusingScheduler).await;
+    assert!(!output.starts_with("a\n"), "{}", output);
+    assert!(output.contains("task refused"), "{}", output);
+}
@@ -0,0 +1,89 @@
+//! Suspends a program, encodes the snapshot, and resumes it from the
+//! encoded bytes in a fresh database, the way a later run would.
+
+use dada_bytecode::DecodeError;
+use dada_execute::{
+    kernel::BufferKernel,
+    scheduler::InlineScheduler,
+    snapshot::{RunOutcome, Snapshot},
+};
+use dada_ir::filename::Filename;
+
+const SOURCE: &str = "\
+class Point(any x, any y)
+
+async fn main() {
+    p = Point(22, 44)
+    n = 0
+    while n < 3 {
+        print(p.x + n).await
+        n += 1
+    }
+    print(p).await
+}
+";
+
+fn load(source: &str) -> (dada_db::Db, Filename) {
+    let mut db = dada_db::Db::default();
+    let filename = Filename::from(&db, "snapshot.dada");
+    db.update_file(filename, source.to_string());
+    (db, filename)
+}
+
+/// Runs `main` until it has taken `steps` steps and returns the encoded
+/// snapshot, along with the output printed before it was suspended.
+async fn suspend_after(steps: usize) -> (Vec<u8>, String) {
+    let (db, filename) = load(SOURCE);
+    let main = db.function_named(filename, "main").unwrap();
+    let mut taken = 0;
+    let mut kernel = BufferKernel::new().suspend_when(move |_, _| {
+        taken += 1;
+        taken == steps
+    });
+    let outcome =
+        dada_execute::interpret_suspendable(main, &db, &mut kernel, &mut InlineScheduler, vec![])
+            .await
+            .unwrap();
+    match outcome {
+        RunOutcome::Suspended(snapshot) => (snapshot.encode(&db), kernel.take_buffer()),
+        RunOutcome::Completed => panic!("completed before {steps} steps"),
+    }
+}
+
+#[tokio::test]
+async fn resume_encoded_snapshot() {
+    let (db, filename) = load(SOURCE);
+    let main = db.function_named(filename, "main").unwrap();
+    let mut uninterrupted = BufferKernel::new();
+    uninterrupted.interpret(&db, main, vec![]).await.unwrap();
+
+    for steps in [1, 10, 20] {
+        let (bytes, before) = suspend_after(steps).await;
+
+        let (db, _) = load(SOURCE);
+        let snapshot = Snapshot::decode(&db, &bytes).unwrap();
+        assert_eq!(snapshot.encode(&db), bytes);
+
+        let mut kernel = BufferKernel::new();
+        let outcome = dada_execute::resume(snapshot, &db, &mut kernel, &mut InlineScheduler)
+            .await
+            .unwrap();
+        assert!(matches!(outcome, RunOutcome::Completed));
+        assert_eq!(
+            format!("{}{}", before, kernel.buffer()),
+            uninterrupted.buffer(),
+            "suspended after {steps} steps",
+        );
+    }
+}
+
+#[tokio::test]
+async fn reject_changed_source() {
+    let (bytes, _) = suspend_after(10).await;
+    let (db, _) = load(&SOURCE.replace("22", "23"));
+    match Snapshot::decode(&db, &bytes) {
+        Err(DecodeError::SourceChanged { filename }) => assert_eq!(filename, "snapshot.dada"),
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("decoded a snapshot of a changed file"),
+    }
+}
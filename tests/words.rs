@@ -0,0 +1,51 @@
+//! Interns words through a `WordCache`, which keeps words of up to
+//! `WordCache::INLINE_LEN` bytes inline and longer ones in a `String`, and
+//! checks that it gives the same `Word` that the database does.
+
+use dada_ir::word::{Word, WordCache};
+
+#[test]
+fn same_word_as_the_database() {
+    let db = dada_db::Db::default();
+    let mut cache = WordCache::default();
+    let inline_len = WordCache::INLINE_LEN;
+    for len in [1, inline_len - 1, inline_len, inline_len + 1, 40] {
+        let string = "abcdefghijklmnopqrstuvwxyz".repeat(2)[..len].to_string();
+        let word = cache.intern(&db, &string);
+        assert_eq!(word, Word::from(&db, &string[..]), "{:?}", string);
+        assert_eq!(word.as_str(&db), string);
+        // Found in the cache the second time.
+        assert_eq!(cache.intern(&db, &string), word, "{:?}", string);
+    }
+}
+
+#[test]
+fn words_interned_by_the_database_first() {
+    let db = dada_db::Db::default();
+    let short = "x".repeat(WordCache::INLINE_LEN);
+    let long = "x".repeat(WordCache::INLINE_LEN + 1);
+    let (short_word, long_word) = (Word::from(&db, &short[..]), Word::from(&db, &long[..]));
+    let mut cache = WordCache::default();
+    assert_eq!(cache.intern(&db, &short), short_word);
+    assert_eq!(cache.intern(&db, &long), long_word);
+    assert_ne!(short_word, long_word);
+}
+
+#[test]
+fn inline_words_differ_by_length() {
+    // The inline bytes of a short word are padded with zeros, so a word
+    // that ends with zero bytes only differs from the one without them in
+    // its length.
+    let db = dada_db::Db::default();
+    let mut cache = WordCache::default();
+    let words: Vec<Word> = ["a", "a\0", "a\0\0", ""]
+        .into_iter()
+        .map(|string| cache.intern(&db, string))
+        .collect();
+    for (i, word) in words.iter().enumerate() {
+        for other in &words[i + 1..] {
+            assert_ne!(word, other);
+        }
+    }
+    assert_eq!(words[1].as_str(&db), "a\0");
+}